@@ -0,0 +1,61 @@
+//! Benchmarks of the queries run against a sculpt on every stroke.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use glam::{vec3, Vec3};
+use sbrush::{Coverage, Ray, Sculpt};
+
+use std::hint::black_box;
+
+/// A sphere filling most of a sculpt, with its surface split down to single voxels.
+fn sphere(resolution: u32) -> Sculpt {
+	let mut sculpt = Sculpt::new(resolution);
+	let (center, radius) = (Vec3::splat(0.5), 0.4);
+	let voxel_size = sculpt.get_voxel_size();
+	sculpt.apply(&|size, node: Vec3| {
+		let reach = (size - voxel_size) / 2.0;
+		let near = ((node - center).abs() - reach).max(Vec3::ZERO).length();
+		let far = ((node - center).abs() + reach).length();
+		if far < radius {
+			Coverage::Solid
+		} else if near >= radius {
+			Coverage::Empty
+		} else {
+			Coverage::Mixed
+		}
+	});
+
+	sculpt
+}
+
+fn closest_surface_point(c: &mut Criterion) {
+	let sculpt = sphere(256);
+	let mut group = c.benchmark_group("closest_surface_point");
+
+	for (name, point) in [
+		("near", vec3(0.5, 0.5, 0.91)),
+		("far", vec3(0.5, 0.5, 0.99)),
+		("inside", vec3(0.5, 0.5, 0.8)),
+		("deep", vec3(0.5, 0.5, 0.5)),
+	] {
+		group.bench_function(name, |b| b.iter(|| sculpt.closest_surface_point(black_box(point))));
+	}
+
+	group.finish();
+}
+
+fn raycast_batch(c: &mut Criterion) {
+	let sculpt = sphere(256);
+	// a grid of rays across the front of the sculpt, most hitting the sphere
+	let rays: Vec<Ray> = (0..64 * 64)
+		.map(|index| Ray::new(vec3((index % 64) as f32 / 64.0, (index / 64) as f32 / 64.0, 0.0), Vec3::Z))
+		.collect();
+	let mut group = c.benchmark_group("raycast");
+
+	group.bench_function("single", |b| b.iter(|| rays.iter().map(|ray| sculpt.raycast(ray.origin, ray.direction)).collect::<Vec<_>>()));
+	group.bench_function("batch", |b| b.iter(|| sculpt.raycast_batch(black_box(&rays))));
+
+	group.finish();
+}
+
+criterion_group!(benches, closest_surface_point, raycast_batch);
+criterion_main!(benches);
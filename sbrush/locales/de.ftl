@@ -0,0 +1,361 @@
+# German messages.
+
+window-title = Swirlix { $version }
+
+# Brushes
+brush-round = Runder Pinsel
+brush-square = Eckiger Pinsel
+brush-spray = Sprühpinsel
+brush-voxel-pen = Voxelstift
+
+# Scene
+object-sculpt = Skulptur
+
+# Actions
+action-add = Voxel hinzufügen
+action-remove = Voxel entfernen
+action-round-brush = Runder Pinsel
+action-square-brush = Eckiger Pinsel
+action-spray-brush = Sprühpinsel
+action-voxel-pen = Voxelstift, für ganze Voxel im Raster
+action-brush-slot = Zum Pinsel in Platz { $slot } wechseln
+action-last-brush = Zum vorherigen Pinsel zurückwechseln
+action-toggle-smoothing = Glätten nach Strichen umschalten
+action-beauty-render = Schönes Rendering nach render.png
+action-toggle-heat = Wärmeansicht der Bearbeitungen umschalten
+action-toggle-mask = Maskenansicht umschalten
+action-toggle-paint = Zwischen Malen und Modellieren umschalten
+action-toggle-guides = Zwischen dem Zeichnen von Führungskurven und Modellieren umschalten
+action-toggle-help = Diese Hilfe umschalten
+action-toggle-materials = Materialkugeln umschalten, ein Klick wählt das Pinselmaterial
+action-toggle-profile = CPU-Zeit je Bild umschalten
+action-toggle-traversal = Debug: mit dem alten Octree-Abstieg vergleichen
+action-toggle-mesh = Zwischen Raymarching, einer Netzvorschau und beidem wechseln
+action-cycle-present-mode = Vertikale Synchronisation wechseln (fifo, mailbox, immediate)
+action-cycle-tonemap = Tonwertkurve wechseln
+action-increase-exposure = Belichtung erhöhen
+action-decrease-exposure = Belichtung verringern
+action-screenshot = Bildschirmfoto nach screenshot.png speichern
+action-command-palette = Befehlspalette öffnen
+action-save = Das aktive Objekt in seiner Datei speichern
+action-open = Ein gespeichertes Dokument öffnen
+action-undo = Die letzte Änderung rückgängig machen
+action-redo = Die letzte rückgängig gemachte Änderung wiederholen
+action-cancel = Lange Vorgänge abbrechen, etwa ein schönes Rendering
+
+# Mouse bindings
+binding-left-click = Linksklick
+binding-right-click = Rechtsklick
+binding-middle-click = Mittelklick
+binding-other-click = { $button }-Klick
+
+# Help overlay
+help-title = Hilfe
+help-controls = Steuerung
+help-current-brush = Aktueller Pinsel
+help-brush = Pinsel
+help-size = Größe
+help-size-value = { $percent } % eines Chunks
+help-relax = Glätten
+help-relax-off = Aus
+help-relax-iterations = { $iterations } Durchläufe
+help-strength = Stärke
+help-strength-value = { $percent } %
+help-hardness = Härte
+help-hardness-value = { $percent } %
+help-accumulation = Aufbau pro Strich
+help-accumulation-off = Unbegrenzt
+help-accumulation-limit = { $percent } % der Größe
+
+# Beauty render
+beauty-render-started = Schönes Rendering wird gestartet...
+beauty-render-saved = Schönes Rendering unter { $path } gespeichert
+beauty-render-failed = Das schöne Rendering konnte nicht gespeichert werden: { $error }
+beauty-render-progress = render.png wird gerendert
+beauty-render-saving = render.png wird gespeichert
+beauty-render-cancelled = Das schöne Rendering wurde abgebrochen
+beauty-render-environment-failed = Die Umgebung { $path } konnte nicht geladen werden, daher wird das Rendering vom Himmel beleuchtet: { $error }
+document-load-failed = Ein Chunk der geöffneten Datei konnte nicht gelesen werden und bleibt leer: { $error }
+document-saving = { $path } wird gespeichert
+document-saved = { $path } gespeichert
+document-save-failed = { $path } konnte nicht gespeichert werden: { $error }
+document-reloaded = "{ $name }" wurde von einem anderen Programm geändert und neu geladen
+document-changed = "{ $name }" wurde von einem anderen Programm geändert, reload verwirft die eigenen Änderungen und liest es erneut
+document-reload-failed = "{ $name }" konnte nicht neu geladen werden: { $error }
+document-autosave-failed = Die Sicherung von { $path } konnte nicht geschrieben werden: { $error }
+thumbnail-failed = Das Vorschaubild { $path } konnte nicht gespeichert werden: { $error }
+
+# Dateidialoge
+dialog-documents = Swirlix-Dokumente
+dialog-save = Das Objekt speichern
+dialog-open = Ein Dokument öffnen
+
+# Fortschritt
+progress-title = In Arbeit
+progress-cancel = Zum Abbrechen Escape drücken
+
+# Versionen
+versions-title = Versionen
+versions-help = Auf und Ab wählen eine Version, Enter stellt sie wieder her und Escape schließt
+versions-restored = Die um { $time } gespeicherte Version wurde wiederhergestellt
+versions-restore-failed = Die Version konnte nicht wiederhergestellt werden: { $error }
+
+# Screenshots
+screenshot-saving = screenshot.png wird gespeichert
+screenshot-saved = Bildschirmfoto unter { $path } gespeichert
+screenshot-failed = Das Bildschirmfoto konnte nicht gespeichert werden: { $error }
+screenshot-unavailable = das Bild konnte nicht zurückgelesen werden
+
+# Zeitraffer
+timelapse-saving = Das Zeitrafferbild { $path } wird gespeichert
+timelapse-failed = Das Zeitrafferbild { $path } konnte nicht gespeichert werden: { $error }
+
+# Dauertest
+soak-finished = Dauertest beendet, der Bericht liegt in { $path }
+soak-failed = Der Bericht des Dauertests konnte nicht geschrieben werden: { $error }
+
+# Review-Pakete
+review-saving = Das Review-Bild { $path } wird gespeichert
+review-failed = Das Review-Bild { $path } konnte nicht gespeichert werden: { $error }
+
+# Maskieren der Ansicht
+mask-visible-failed = Was die Ansicht nicht zeigt, konnte nicht maskiert werden, da das Bild nicht zurückgelesen werden konnte
+
+# Present modes
+present-mode-changed = Bilder werden mit { $name } angezeigt
+present-mode-unknown = Unbekannter Anzeigemodus { $name }, erwartet wurde fifo, mailbox oder immediate
+quality-unknown = Unbekannte Renderqualität { $name }, erwartet wurde low, medium oder high
+
+# Grafikadapter
+adapter-unknown = Es gibt keinen Adapter { $name }, wgpu wählt selbst. Die Adapter sind { $adapters }
+adapter-chosen = Gerendert wird mit { $name }
+
+# Knotenlimit
+node-limit-reached = Striche wurden nur bis Tiefe { $depth } verfeinert, um im Limit von { $limit } Knoten zu bleiben, es lässt sich mit "node limit" anheben
+
+# CPU-Profil
+profile-title = CPU-Zeit
+profile-events = Ereignisse
+profile-sculpt = Modellieren
+profile-serialization = Dateien
+profile-upload = Hochladen
+profile-total = Gesamt
+profile-times = { $last } ms, im Mittel { $average }, höchstens { $peak }
+profile-slow-frame = Langsames Bild, { $total } ms auf der CPU: { $phases }
+profile-threshold-invalid = SWIRLIX_PROFILE sollte eine Anzahl Millisekunden sein, nicht { $value }
+showcase-delay-invalid = SWIRLIX_SHOWCASE sollte eine Anzahl Sekunden sein, nicht { $value }
+
+# Session statistics
+stats-save-failed = Die Sitzungsstatistik konnte nicht gespeichert werden: { $error }
+
+# Fehler
+error-missing-material = Es gibt kein Material { $index }, die Palette hat nur { $count } Materialien
+error-missing-object = Es gibt kein Objekt { $index }
+error-unknown-object = Es gibt kein Objekt namens "{ $name }"
+error-no-file = Das Objekt wurde nicht aus einer Datei geöffnet oder in eine gespeichert
+error-empty-palette = Die Palette hat keine Farben
+error-no-adapter = Kein Grafikadapter kann in das Fenster zeichnen
+error-unsupported-surface = Der Grafikadapter kann nicht in das Fenster zeichnen
+error-surface = In das Fenster kann nicht gezeichnet werden: { $error }
+error-device = Die GPU konnte nicht eingerichtet werden: { $error }
+error-event-loop = Die Ereignisschleife ist fehlgeschlagen: { $error }
+error-window = Das Fenster konnte nicht erstellt werden: { $error }
+
+# Befehlspalette
+palette-title = Befehle
+command-empty = Befehl eingeben, oder "help" für eine Liste
+command-unknown = Es gibt keinen Befehl namens "{ $name }"
+command-usage = Verwendung: { $usage }
+command-brush-size-range = Die Pinselgröße muss größer als 0 und höchstens 1 sein
+command-brush-voxels-range = Die Anzahl Voxel muss mindestens 1 und höchstens die Auflösung eines Chunks sein
+command-brush-strength-range = Die Pinselstärke muss zwischen 0 und 1 liegen
+command-brush-hardness-range = Die Pinselhärte muss zwischen 0 und 1 liegen
+command-brush-accumulation-range = Die Aufbaugrenze muss größer als 0 und höchstens 1 sein
+command-brush-backfaces-range = Der Rückseitenwinkel muss zwischen 0 und 180 Grad liegen
+command-brush-material-mix-range = Die Mischung muss zwischen 0 und 1 liegen
+command-material-range = Die Palette hat nur { $count } Materialien
+command-gradient-empty = Es gibt nichts zu füllen
+command-wear-intensity-range = Die Intensität muss zwischen 0 und 1 liegen
+command-paint-color-range = Die Farbkanäle müssen zwischen 0 und 1 liegen
+command-undo-empty = Es gibt nichts zum Rückgängigmachen
+command-redo-empty = Es gibt nichts zum Wiederholen
+command-paint-undo-empty = Es gibt keine Bemalung zum Rückgängigmachen
+command-paint-redo-empty = Es gibt keine rückgängig gemachte Bemalung zum Wiederholen
+command-object-unknown = Es gibt kein Objekt namens "{ $name }"
+command-object-last = Das letzte Objekt kann nicht gelöscht werden
+command-object-resolution-range = Die Auflösung muss eine Zweierpotenz zwischen 8 und 1024 sein
+command-object-scale-range = Die Skalierung muss größer als 0 sein
+command-object-boolean-failed = Der Schneider muss ein anderes Objekt sein, und das bearbeitete Objekt darf nicht gesperrt sein
+command-object-duplicate-failed = Das bearbeitete Objekt konnte nicht kopiert werden
+command-instance-radius-range = Der Streuradius muss größer als 0 sein
+command-snap-grid-range = Die Rastergröße muss größer als 0 sein
+command-snap-voxels-range = Die Einrasttiefe darf höchstens { $max } sein, die Tiefe der feinsten Voxel
+command-node-limit-range = Das Knotenlimit muss größer als 0 sein
+command-snap-angle-range = Der Einrastwinkel muss größer als 0 und höchstens 180 Grad sein
+command-view-empty = In Platz { $slot } ist keine Ansicht gespeichert
+command-help = Alle Befehle auflisten
+command-save = Das bearbeitete Objekt in einer Datei speichern, komprimiert mit einer Stufe von 1 bis 22 (standardmäßig 3)
+command-saving = { $path } wird im Hintergrund gespeichert
+command-save-level-range = Die Kompressionsstufe muss zwischen 1 und 22 liegen
+command-save-version = Eine Version der Datei des bearbeiteten Objekts daneben speichern, mit einem Vorschaubild der Ansicht
+command-saving-version = Eine Version wird im Hintergrund in { $path } gespeichert
+command-save-version-failed = Die Version konnte nicht gespeichert werden: { $error }
+command-versions = Eine gespeicherte Version der Datei des bearbeiteten Objekts zum Wiederherstellen auswählen
+command-versions-none = Die Datei des bearbeiteten Objekts hat keine gespeicherten Versionen
+command-versions-failed = Die gespeicherten Versionen konnten nicht aufgelistet werden: { $error }
+command-open = Eine gespeicherte Datei als neues Objekt öffnen, deren Chunks erst bei Bedarf gelesen werden
+command-opened = "{ $name }" geöffnet
+command-open-failed = Die Datei konnte nicht geöffnet werden: { $error }
+command-timelapse-started = Alle { $strokes } Striche wird ein Bild in { $directory } aufgenommen
+command-timelapse-failed = Der Zeitraffer konnte nicht gestartet werden: { $error }
+command-timelapse-stopped = Zeitraffer nach { $frames } Bildern beendet
+command-timelapse-idle = Es läuft kein Zeitraffer
+command-soak-started = { $strokes } zufällige Striche werden angewendet, danach wird der Bericht in { $path } geschrieben
+command-reload = Das aktive Objekt erneut aus seiner Datei lesen und die Änderungen seit dem Öffnen oder Speichern verwerfen
+command-reloaded = "{ $name }" neu geladen
+command-reload-failed = Die Datei konnte nicht neu geladen werden: { $error }
+command-brush = Zum runden, eckigen oder Sprühpinsel oder zum Voxelstift wechseln
+command-brush-list = Die Pinsel mit ID und Namen auflisten
+command-brush-entry = { $id } ({ $name })
+command-brush-slot = Eine Zifferntaste an einen Pinsel binden, per ID oder Name
+command-brush-last = Zum vorherigen Pinsel zurückwechseln
+command-brush-size = Pinselgröße als Anteil eines Chunks setzen
+command-brush-voxels = Pinselgröße als Anzahl Voxel setzen
+command-brush-strength = Setzen, wie stark der Pinsel wirkt, etwa wie dicht der Sprühpinsel sprüht
+command-brush-hardness = Festlegen, wie weit zum Rand hin der Pinsel bis auf einzelne Voxel verfeinert, sodass weichere Pinsel ausgefranste Ränder hinterlassen
+command-brush-accumulation = Begrenzen, wie viel ein Strich als Anteil der Pinselgröße aufbaut oder abträgt, wie die Deckkraft von Farbe, oder ihn beliebig anhäufen lassen
+command-brush-through = Jede Oberfläche unter dem Mauszeiger bearbeiten, etwa beide Seiten einer dünnen Hülle, oder nur eine Stelle
+command-brush-backfaces = Striche von Oberflächen fernhalten, die um mehr als einen Winkel von der Kamera abgewandt sind, oder jede Oberfläche bearbeiten
+command-stroke-lock = Wählen, woran Striche bei gehaltener Alt-Taste gebunden sind, eine Weltachse, die Flächennormale, die Strichebene oder die nächste Führungskurve
+command-stroke-plane = Die Strichebene entlang der Oberfläche unter einem Bildschirmpunkt (0 bis 1) setzen, oder sie entfernen
+command-stroke-plane-placed = Die Strichebene geht durch { $point } in Richtung { $normal }
+command-brush-relax = Entspannungsdurchläufe nach jedem Strich setzen
+command-brush-material = Das Material des Pinsels setzen, oder zwei Materialien mit einem Anteil des zweiten rastern
+command-add = Strich an einem Bildschirmpunkt hinzufügen (0 bis 1)
+command-remove = Strich an einem Bildschirmpunkt entfernen (0 bis 1)
+command-view-store = Aktuelle Ansicht merken
+command-view-recall = Zu einer gemerkten Ansicht wechseln
+command-view-duration = Dauer des Wechsels zu einer gemerkten Ansicht setzen
+command-pin-add = Eine Notiz an die Oberfläche unter einem Punkt des Bildschirms (0 bis 1) heften, etwa als Review-Kommentar oder To-do
+command-pin-remove = Eine Notiz anhand ihrer Nummer in "pin list" entfernen
+command-pin-list = Die Notizen des bearbeiteten Objekts auflisten
+command-pin-go = Die Ansicht zu einer Notiz bewegen
+command-pin-added = Notiz { $number } hinzugefügt
+command-pin-missing = Es gibt keine Notiz { $number }
+command-pin-none = Das Objekt hat keine Notizen
+command-guide-add = Eine Führungskurve entlang der Oberfläche durch Bildschirmpunkte (0 bis 1) zeichnen
+command-guide-remove = Eine Führungskurve anhand ihrer Nummer entfernen
+command-guide-draw = Striche Führungskurven entlang der Oberfläche zeichnen lassen statt zu modellieren, oder wieder modellieren
+command-guide-array = Einen Abdruck des Pinsels, oder ein aufgezeichnetes Makro, gleichmäßig entlang einer Führungskurve als ein Strich wiederholen, mit align entlang der Kurve gedreht
+command-guide-added = Führungskurve { $number } hinzugefügt
+command-guide-missing = Es gibt keine Führungskurve { $number }
+command-guide-off-surface = Eine Führungskurve braucht mindestens zwei Punkte auf der Oberfläche
+command-stencil-circle = Einen Kreis auf dem Bildschirm (0 bis 1, Radius anhand seiner Höhe) um eine Tiefe in Voxeln in die Oberfläche darunter schneiden, oder um so viel anheben
+command-stencil-polygon = Ein Polygon durch Bildschirmpunkte (0 bis 1) um eine Tiefe in Voxeln in die Oberfläche darunter schneiden, oder um so viel anheben
+command-stencil-svg = Die Formen einer SVG-Datei, auf eine Höhe um einen Bildschirmpunkt eingepasst, um eine Tiefe in Voxeln in die Oberfläche darunter schneiden, oder um so viel anheben
+command-stencil-missed = Die Schablone liegt nicht über dem aktiven Objekt, oder es ist gesperrt
+command-stencil-svg-failed = Die SVG-Datei konnte nicht gelesen werden: { $error }
+command-stencil-svg-empty = Die SVG-Datei hat keine Formen, die als Schablone dienen können
+command-review-export = Die Notizen als Review-Paket exportieren: ein Bild mit Blick auf jede Notiz und eine review.json mit allen Notizen
+command-review-exported = { $count } Notizen werden nach { $directory } exportiert
+command-review-failed = Das Review-Paket konnte nicht exportiert werden: { $error }
+command-report = Einen JSON-Bericht über die Knoten jeder Tiefe, das Volumen jedes Materials, die Begrenzung und die Striche des bearbeiteten Objekts schreiben
+command-report-written = Der Bericht über { $nodes } Knoten wurde nach { $path } geschrieben
+command-report-failed = Der Bericht konnte nicht geschrieben werden: { $error }
+command-export-stl = Die Voxeloberfläche der sichtbaren, des bearbeiteten oder der genannten Objekte in eine binäre STL-Datei exportieren
+command-exported = { $triangles } Dreiecke nach { $path } geschrieben
+command-export-obj = Die Voxeloberfläche der sichtbaren, des bearbeiteten oder der genannten Objekte in eine Wavefront-OBJ-Datei exportieren
+command-export-gltf = Die Voxeloberfläche der sichtbaren, des bearbeiteten oder der genannten Objekte in eine binäre glTF-Datei exportieren
+command-export-vox = Die Voxel der sichtbaren, des bearbeiteten oder der genannten Objekte in eine MagicaVoxel-vox-Datei exportieren
+command-exported-quads = { $quads } Vierecke nach { $path } geschrieben
+command-exported-voxels = { $voxels } Voxel nach { $path } geschrieben
+command-export-failed = Der Export ist fehlgeschlagen: { $error }
+command-orbit = Ansicht um eine Bewegung über den Bildschirm kreisen
+command-pan = Ansicht um eine Bewegung über den Bildschirm verschieben
+command-zoom = Hineinzoomen (oder mit negativen Werten herauszoomen)
+command-turn = Ansicht um die senkrechte Achse drehen
+command-mask-cavity = Falten und Spalten (oder Kanten und Spitzen) maskieren und so vor dem Entfernen schützen
+command-mask-facing = Flächen maskieren, die um mehr als einen Winkel von der Ansicht abgewandt sind
+command-mask-visible = Alles maskieren, was die Ansicht nicht zeigt, sodass nur die sichtbare Oberfläche bearbeitbar bleibt
+command-mask-clear = Maske löschen
+command-paint = Über das Material der Voxel malen, statt sie zu modellieren, oder wieder modellieren
+command-paint-color = Malfarbe setzen, wobei Alpha angibt, wie stark sie das Material überdeckt
+command-paint-clear = Alle Bemalung entfernen und die Form behalten
+command-undo = Die letzte Änderung am aktiven Objekt rückgängig machen, Bemalung eingeschlossen
+command-redo = Die letzte rückgängig gemachte Änderung am aktiven Objekt wiederholen
+command-paint-undo = Den letzten Malstrich rückgängig machen, ohne Formänderungen anzutasten
+command-paint-redo = Den letzten rückgängig gemachten Malstrich wiederholen
+command-object-add = Ein leeres Objekt mit eigener Auflösung hinzufügen und bearbeiten
+command-object-added = "{ $name }" hinzugefügt
+command-object-duplicate = Das bearbeitete Objekt kopieren und die Kopie bearbeiten
+command-object-delete = Ein Objekt löschen, oder das bearbeitete
+command-object-select = Ein anderes Objekt bearbeiten
+command-object-list = Die Objekte der Szene auflisten
+command-object-active = { $name } (aktiv)
+command-object-show = Ein Objekt, oder das bearbeitete, einblenden
+command-object-hide = Ein Objekt, oder das bearbeitete, in der Ansicht und beim Rendern ausblenden
+command-object-lock = Ein Objekt, oder das bearbeitete, sperren, damit Striche es nicht verändern
+command-object-unlock = Ein Objekt, oder das bearbeitete, entsperren
+command-object-solo = Nur das bearbeitete Objekt zeigen, oder wieder jedes sichtbare
+command-object-move = Das bearbeitete Objekt an eine Position verschieben
+command-object-rotate = Das bearbeitete Objekt um Winkel um die x-, y- und z-Achse drehen
+command-object-scale = Das bearbeitete Objekt skalieren
+command-object-boolean = Ein anderes Objekt dort, wo es platziert ist, mit dem bearbeiteten vereinigen, es abziehen, oder nur behalten, was beide enthalten, und es dann ausblenden
+command-snap-grid = Platzierte Objekte und Instanzen an einem Raster einrasten, oder nicht mehr daran einrasten
+command-snap-angle = Die Drehung platzierter Objekte und Instanzen in Winkelschritten einrasten, oder nicht mehr einrasten
+command-snap-surface = Platzierte Objekte und Instanzen auf die Oberfläche der Objekte darunter fallen lassen, oder nicht mehr
+command-snap-voxels = Die Mitte des Pinsels am Voxelraster einer Octree-Tiefe einrasten, oder nicht mehr daran einrasten
+command-instance-add = Das bearbeitete Objekt ein weiteres Mal an einer Position zeigen, mit denselben Voxeln
+command-instance-scatter = Instanzen des bearbeiteten Objekts auf dem Boden darum verstreuen, wie Steine oder Nieten
+command-instance-count = Das Objekt hat { $count } Instanzen
+command-instance-clear = Die Instanzen des bearbeiteten Objekts entfernen
+command-gradient = Das aktive Objekt entlang einer Achse von einer Seite zur anderen mit einer Materialreihe füllen
+command-gradient-between = Das aktive Objekt zwischen der Skulptur unter zwei Bildschirmpunkten mit einer Materialreihe füllen
+command-wear = Die konvexen Kanten des aktiven Objekts mit einem Kantenmaterial abnutzen oder abtragen
+command-quantize = Die Skulptur in Blöcke einer Octree-Tiefe zusammenfassen, für einen Voxel-Art-Look
+command-node-limit = Begrenzen, wie viele Knoten jedes Objekt haben darf, wobei Striche darüber hinaus weniger fein verfeinert werden, oder das Limit aufheben
+command-validate = Den Octree der Skulptur auf Unstimmigkeiten prüfen
+command-validate-clean = Die Skulptur hat keine Unstimmigkeiten
+command-validate-issues = { $count } Unstimmigkeiten gefunden, beheben mit "repair"
+command-validate-loaded = Die Datei war beschädigt, beim Lesen wurden { $count } Unstimmigkeiten behoben
+command-repair = Unstimmigkeiten im Octree der Skulptur beheben
+command-repaired = { $count } Unstimmigkeiten behoben
+command-watertight = Prüfen, ob die Oberfläche wasserdicht ist, wie für den Export eines Meshes nötig
+command-watertight-ok = Die Oberfläche ist wasserdicht
+command-watertight-failed = Die Oberfläche ist an { $count } Stellen nicht wasserdicht, dort vor dem Export Voxel hinzufügen oder entfernen: { $places }
+command-watertight-edge = Voxel oder Lücken berühren sich nur an einer Kante bei { $position }
+command-watertight-vertex = Voxel oder Lücken berühren sich nur an einer Ecke bei { $position }
+command-colors = Materialien mit einer eingebauten Palette einfärben, siehe "colors list"
+command-colors-import = Materialien mit einer GIMP- (.gpl) oder Adobe-Swatch-Exchange-Palette (.ase) einfärben
+command-colors-list = Eingebaute Paletten auflisten
+command-colors-unknown = Es gibt keine Palette namens "{ $name }"
+command-colors-import-failed = Die Palette konnte nicht importiert werden: { $error }
+command-colors-imported = { $count } Farben importiert
+command-colors-safe = { $name } (für Farbenblinde geeignet)
+command-material-subsurface = Licht unter der Oberfläche eines Materials streuen lassen, von 0 für keines bis 1, damit es weniger nach Plastik aussieht
+command-material-subsurface-range = Die Streuung muss zwischen 0 und 1 liegen
+command-light-directional = Ein Licht hinzufügen, das entlang einer Richtung scheint, wie Sonnenlicht, wahlweise farbig
+command-light-point = Ein Licht hinzufügen, das sich von einem Punkt ausbreitet, wahlweise farbig
+command-light-list = Die Lichter der Ansicht auflisten
+command-light-clear = Alle Lichter entfernen, sodass die Skulptur wieder von der Kamera aus beleuchtet wird
+command-light-none = Es gibt keine Lichter, also wird die Skulptur von der Kamera aus beleuchtet
+command-light-direction = Ein gerichtetes Licht braucht eine Richtung
+command-light-range = Die Intensität darf nicht negativ sein, und die Farbkanäle müssen zwischen 0 und 1 liegen
+command-light-full = Es kann höchstens { $count } Lichter geben
+command-color = Das Farbmanagement des aktiven Objekts anzeigen
+command-color-space = Den Farbraum festlegen, in dem die Materialien des aktiven Objekts angegeben und schattiert werden
+command-color-tonemap = Festlegen, wie die Farben des aktiven Objekts auf den Bildschirm abgebildet werden
+command-color-exposure = Die Belichtung des aktiven Objekts in Blendenstufen festlegen, wobei jede Stufe die Helligkeit verdoppelt
+command-color-current = Arbeitsfarbraum { $space }, Tone Mapping { $tonemap }, Belichtung { $exposure } Blendenstufen
+command-stats = Sitzungsstatistiken ein- oder ausschalten
+command-macro-record = Befehle und Striche in ein Makro aufnehmen
+command-macro-stop = Makroaufnahme beenden
+command-macro-play = Makro abspielen, optional an einen Bildschirmpunkt verschoben und skaliert
+command-macro-list = Aufgenommene Makros auflisten
+command-timelapse = Alle paar Striche ein Bild in ein Verzeichnis aufnehmen, wahlweise aus einer gespeicherten Ansicht
+command-timelapse-stop = Die Zeitrafferaufnahme beenden
+command-soak = Zufällige Striche anwenden, dabei Speicher, Knoten, Puffer und Bildzeiten verfolgen und einen Bericht schreiben
+command-onion = Das bearbeitete Objekt im jetzigen Zustand als durchscheinenden Geist zeigen, um spätere Zustände damit zu vergleichen, oder den Geist ausblenden
+command-macro-recording = "{ $name }" wird aufgenommen, beenden mit "macro stop"
+command-macro-not-recording = Es wird kein Makro aufgenommen
+command-macro-missing = Es gibt kein Makro namens "{ $name }"
@@ -0,0 +1,361 @@
+# English messages, also used for anything missing from another locale.
+
+window-title = Swirlix { $version }
+
+# Brushes
+brush-round = Round Brush
+brush-square = Square Brush
+brush-spray = Spray Brush
+brush-voxel-pen = Voxel Pen
+
+# Scene
+object-sculpt = Sculpt
+
+# Actions
+action-add = Add voxels
+action-remove = Remove voxels
+action-round-brush = Round brush
+action-square-brush = Square brush
+action-spray-brush = Spray brush
+action-voxel-pen = Voxel pen, for whole voxels on the grid
+action-brush-slot = Switch to the brush in slot { $slot }
+action-last-brush = Switch back to the previous brush
+action-toggle-smoothing = Toggle relaxing after strokes
+action-beauty-render = Beauty render to render.png
+action-toggle-heat = Toggle the edit history heat view
+action-toggle-mask = Toggle the mask view
+action-toggle-paint = Toggle painting instead of sculpting
+action-toggle-guides = Toggle drawing guide curves instead of sculpting
+action-toggle-help = Toggle this help
+action-toggle-materials = Toggle the material spheres, clicked to pick the brush material
+action-toggle-profile = Toggle the CPU time spent on each frame
+action-toggle-traversal = Debug: compare with the old octree descent
+action-toggle-mesh = Switch between ray marching, a mesh preview and both
+action-cycle-present-mode = Cycle vertical sync (fifo, mailbox, immediate)
+action-cycle-tonemap = Cycle the tone mapping curve
+action-increase-exposure = Increase the exposure
+action-decrease-exposure = Decrease the exposure
+action-screenshot = Save a screenshot to screenshot.png
+action-command-palette = Open the command palette
+action-save = Save the active object to its file
+action-open = Open a saved document
+action-undo = Undo the latest edit
+action-redo = Redo the latest undone edit
+action-cancel = Cancel long operations, like a beauty render
+
+# Mouse bindings
+binding-left-click = Left Click
+binding-right-click = Right Click
+binding-middle-click = Middle Click
+binding-other-click = { $button } Click
+
+# Help overlay
+help-title = Help
+help-controls = Controls
+help-current-brush = Current Brush
+help-brush = Brush
+help-size = Size
+help-size-value = { $percent }% of a chunk
+help-relax = Relax
+help-relax-off = Off
+help-relax-iterations = { $iterations } iterations
+help-strength = Strength
+help-strength-value = { $percent }%
+help-hardness = Hardness
+help-hardness-value = { $percent }%
+help-accumulation = Build-up per stroke
+help-accumulation-off = Unlimited
+help-accumulation-limit = { $percent }% of the size
+
+# Beauty render
+beauty-render-started = Starting beauty render...
+beauty-render-saved = Saved beauty render to { $path }
+beauty-render-failed = Could not save the beauty render: { $error }
+beauty-render-progress = Rendering render.png
+beauty-render-saving = Saving render.png
+beauty-render-cancelled = Cancelled the beauty render
+beauty-render-environment-failed = Could not load the environment { $path }, so the render is lit by the sky: { $error }
+document-load-failed = Could not read a chunk of the opened file, leaving it empty: { $error }
+document-saving = Saving { $path }
+document-saved = Saved { $path }
+document-save-failed = Could not save { $path }: { $error }
+document-reloaded = "{ $name }" was changed by another program and reloaded
+document-changed = "{ $name }" was changed by another program, run reload to drop your edits and read it again
+document-reload-failed = Could not reload "{ $name }": { $error }
+document-autosave-failed = Could not write a backup of { $path }: { $error }
+thumbnail-failed = Could not save the thumbnail { $path }: { $error }
+
+# File dialogs
+dialog-documents = Swirlix documents
+dialog-save = Save the object
+dialog-open = Open a document
+
+# Progress
+progress-title = Working
+progress-cancel = Press Escape to cancel
+
+# Versions
+versions-title = Versions
+versions-help = Up and Down pick a version, Enter restores it and Escape closes
+versions-restored = Restored the version saved at { $time }
+versions-restore-failed = Could not restore the version: { $error }
+
+# Screenshots
+screenshot-saving = Saving screenshot.png
+screenshot-saved = Saved screenshot to { $path }
+screenshot-failed = Could not save the screenshot: { $error }
+screenshot-unavailable = the frame could not be read back
+
+# Timelapse
+timelapse-saving = Saving the timelapse frame { $path }
+timelapse-failed = Could not save the timelapse frame { $path }: { $error }
+
+# Soak test
+soak-finished = Finished the soak test, the report is in { $path }
+soak-failed = Could not write the soak test report: { $error }
+
+# Review packages
+review-saving = Saving the review image { $path }
+review-failed = Could not save the review image { $path }: { $error }
+
+# Masking the view
+mask-visible-failed = Could not mask what the view doesn't show, as the frame could not be read back
+
+# Present modes
+present-mode-changed = Presenting frames with { $name }
+present-mode-unknown = Unknown present mode { $name }, expected fifo, mailbox or immediate
+quality-unknown = Unknown render quality { $name }, expected low, medium or high
+
+# Adapters
+adapter-unknown = There is no adapter { $name }, leaving the pick to wgpu. The adapters are { $adapters }
+adapter-chosen = Rendering with { $name }
+
+# Node limit
+node-limit-reached = Strokes were refined only to depth { $depth } to keep within the limit of { $limit } nodes, raise it with "node limit"
+
+# CPU profile
+profile-title = CPU Time
+profile-events = Events
+profile-sculpt = Sculpting
+profile-serialization = Files
+profile-upload = Upload
+profile-total = Total
+profile-times = { $last } ms, average { $average }, peak { $peak }
+profile-slow-frame = Slow frame, { $total } ms on the CPU: { $phases }
+profile-threshold-invalid = SWIRLIX_PROFILE should be a number of milliseconds, not { $value }
+showcase-delay-invalid = SWIRLIX_SHOWCASE should be a number of seconds, not { $value }
+
+# Session statistics
+stats-save-failed = Could not save the session statistics: { $error }
+
+# Errors
+error-missing-material = There is no material { $index }, the palette only has { $count } materials
+error-missing-object = There is no object { $index }
+error-unknown-object = There is no object called "{ $name }"
+error-no-file = The object wasn't opened from or saved to a file
+error-empty-palette = The palette has no colors
+error-no-adapter = No GPU adapter can draw to the window
+error-unsupported-surface = The GPU adapter can't draw to the window
+error-surface = Could not draw to the window: { $error }
+error-device = Could not set up the GPU: { $error }
+error-event-loop = The event loop failed: { $error }
+error-window = Could not create the window: { $error }
+
+# Command palette
+palette-title = Commands
+command-empty = Type a command, or "help" to list them
+command-unknown = There is no command called "{ $name }"
+command-usage = Usage: { $usage }
+command-brush-size-range = The brush size has to be more than 0 and at most 1
+command-brush-voxels-range = The voxel count has to be at least 1 and at most the resolution of a chunk
+command-brush-strength-range = The brush strength has to be between 0 and 1
+command-brush-hardness-range = The brush hardness has to be between 0 and 1
+command-brush-accumulation-range = The accumulation limit has to be more than 0 and at most 1
+command-brush-backfaces-range = The backface angle has to be between 0 and 180 degrees
+command-brush-material-mix-range = The mix has to be between 0 and 1
+command-material-range = The palette only has { $count } materials
+command-gradient-empty = There is nothing to fill
+command-wear-intensity-range = The intensity has to be between 0 and 1
+command-paint-color-range = The paint color channels have to be between 0 and 1
+command-undo-empty = There is nothing to undo
+command-redo-empty = There is nothing to redo
+command-paint-undo-empty = There is no paint to undo
+command-paint-redo-empty = There is no undone paint to redo
+command-object-unknown = There is no object called "{ $name }"
+command-object-last = The last object can't be deleted
+command-object-resolution-range = The resolution has to be a power of two between 8 and 1024
+command-object-scale-range = The scale has to be more than 0
+command-object-boolean-failed = The cutter has to be another object, and the edited object can't be locked
+command-object-duplicate-failed = The edited object couldn't be copied
+command-instance-radius-range = The scatter radius has to be more than 0
+command-snap-grid-range = The grid size has to be more than 0
+command-snap-voxels-range = The snapping depth has to be at most { $max }, the depth of the finest voxels
+command-node-limit-range = The node limit has to be more than 0
+command-snap-angle-range = The snapping angle has to be more than 0 and at most 180 degrees
+command-view-empty = No view is stored in slot { $slot }
+command-help = List every command
+command-save = Save the edited object to a file, compressed at a level from 1 to 22 (3 by default)
+command-saving = Saving to { $path } in the background
+command-save-level-range = The compression level has to be between 1 and 22
+command-save-version = Save a version of the file of the edited object beside it, with a thumbnail of the view
+command-saving-version = Saving a version to { $path } in the background
+command-save-version-failed = Could not save a version: { $error }
+command-versions = Pick a saved version of the file of the edited object to restore
+command-versions-none = The file of the edited object has no saved versions
+command-versions-failed = Could not list the saved versions: { $error }
+command-open = Open a saved file as a new object, reading its chunks as they are needed
+command-opened = Opened "{ $name }"
+command-open-failed = Could not open the file: { $error }
+command-timelapse-started = Capturing a frame into { $directory } every { $strokes } strokes
+command-timelapse-failed = Could not start the timelapse: { $error }
+command-timelapse-stopped = Stopped the timelapse after { $frames } frames
+command-timelapse-idle = No timelapse is running
+command-soak-started = Applying { $strokes } random strokes, then writing the report to { $path }
+command-reload = Read the active object again from its file, dropping the edits since it was opened or saved
+command-reloaded = Reloaded "{ $name }"
+command-reload-failed = Could not reload the file: { $error }
+command-brush = Switch to the round, square or spray brush, or the voxel pen
+command-brush-list = List the brushes by id and name
+command-brush-entry = { $id } ({ $name })
+command-brush-slot = Bind a number key to a brush by its id or name
+command-brush-last = Switch back to the previous brush
+command-brush-size = Set the brush size as a fraction of a chunk
+command-brush-voxels = Set the brush size as a number of voxels across
+command-brush-strength = Set how strongly the brush applies, like how densely the spray brush sprays
+command-brush-hardness = Set how far toward its edge the brush refines down to single voxels, so softer brushes leave feathered edges
+command-brush-accumulation = Limit how much a stroke builds up or carves away as a fraction of the brush size, like paint opacity, or let it pile up
+command-brush-through = Stroke every surface under the cursor, like both sides of a thin shell, or only one place
+command-brush-backfaces = Keep strokes off the surface facing away from the camera by more than an angle, or let them affect any surface
+command-stroke-lock = Choose what strokes are kept to while alt is held, a world axis, the surface normal, the stroke plane or the nearest guide
+command-stroke-plane = Set the stroke plane along the surface under a point of the screen (0 to 1), or clear it
+command-stroke-plane-placed = The stroke plane goes through { $point } facing { $normal }
+command-brush-relax = Set the relax iterations after each stroke
+command-brush-material = Set the material the brush adds, or dither two materials with a fraction of the second
+command-add = Add a stroke at a point of the screen (0 to 1)
+command-remove = Remove a stroke at a point of the screen (0 to 1)
+command-view-store = Bookmark the current view
+command-view-recall = Move to a bookmarked view
+command-view-duration = Set how long moving to a bookmarked view takes
+command-pin-add = Pin a note to the surface under a point of the screen (0 to 1), like a review comment or a to-do
+command-pin-remove = Remove a pin by its number in "pin list"
+command-pin-list = List the pins of the edited object
+command-pin-go = Move the view to look at a pin
+command-pin-added = Added pin { $number }
+command-pin-missing = There is no pin { $number }
+command-pin-none = The object has no pins
+command-guide-add = Draw a guide curve along the surface through points of the screen (0 to 1)
+command-guide-remove = Remove a guide by its number
+command-guide-draw = Let strokes draw guides along the surface instead of sculpting, or sculpt again
+command-guide-array = Repeat a stamp of the brush, or a recorded macro turned along the curve if aligned, evenly along a guide as one stroke
+command-guide-added = Added guide { $number }
+command-guide-missing = There is no guide { $number }
+command-guide-off-surface = A guide needs at least two of its points on the surface
+command-stencil-circle = Cut a circle on the screen (0 to 1, radius by its height) into the surface under it by a depth in voxels, or raise it by as much
+command-stencil-polygon = Cut a polygon through points of the screen (0 to 1) into the surface under it by a depth in voxels, or raise it by as much
+command-stencil-svg = Cut the shapes of an SVG file, fitted to a height around a point of the screen, into the surface under them by a depth in voxels, or raise them by as much
+command-stencil-missed = The stencil doesn't cover the active object, or it is locked
+command-stencil-svg-failed = Could not read the SVG file: { $error }
+command-stencil-svg-empty = The SVG file has no shapes to use as a stencil
+command-review-export = Export the pins as a review package: an image looking at each pin, and a review.json listing the notes
+command-review-exported = Exporting { $count } pins into { $directory }
+command-review-failed = Could not export the review package: { $error }
+command-report = Write a JSON report of the nodes at each depth, the volume of each material, the bounds and the strokes of the edited object
+command-report-written = Wrote the report of { $nodes } nodes to { $path }
+command-report-failed = Could not write the report: { $error }
+command-export-stl = Export the voxel surface of the shown objects, the edited one or the named ones to a binary STL file
+command-exported = Wrote { $triangles } triangles to { $path }
+command-export-obj = Export the voxel surface of the shown objects, the edited one or the named ones to a Wavefront OBJ file
+command-export-gltf = Export the voxel surface of the shown objects, the edited one or the named ones to a binary glTF file
+command-export-vox = Export the voxels of the shown objects, the edited one or the named ones to a MagicaVoxel vox file
+command-exported-quads = Wrote { $quads } quads to { $path }
+command-exported-voxels = Wrote { $voxels } voxels to { $path }
+command-export-failed = Could not export: { $error }
+command-orbit = Orbit the view by a movement across the screen
+command-pan = Pan the view by a movement across the screen
+command-zoom = Zoom in (or out, with negative amounts)
+command-turn = Turn the view around the vertical axis
+command-mask-cavity = Mask creases and crevices (or edges and peaks), protecting them from removal
+command-mask-facing = Mask the surfaces turned away from the view by more than an angle
+command-mask-visible = Mask everything the view doesn't show, leaving only the visible surface editable
+command-mask-clear = Clear the mask
+command-paint = Paint over the material of the voxels instead of sculpting them, or sculpt again
+command-paint-color = Set the paint color, where the alpha is how much it covers the material
+command-paint-clear = Remove all paint, keeping the shape
+command-undo = Undo the latest edit of the active object, paint included
+command-redo = Redo the latest undone edit of the active object
+command-paint-undo = Undo the latest paint stroke, leaving shape edits alone
+command-paint-redo = Redo the latest undone paint stroke
+command-object-add = Add an empty object with its own resolution, and edit it
+command-object-added = Added "{ $name }"
+command-object-duplicate = Copy the edited object, and edit the copy
+command-object-delete = Delete an object, or the edited one
+command-object-select = Edit another object
+command-object-list = List the objects of the scene
+command-object-active = { $name } (active)
+command-object-show = Show an object, or the edited one
+command-object-hide = Hide an object, or the edited one, from the viewport and renders
+command-object-lock = Lock an object, or the edited one, so strokes leave it alone
+command-object-unlock = Unlock an object, or the edited one
+command-object-solo = Show only the edited object, or every visible one again
+command-object-move = Move the edited object to a position
+command-object-rotate = Rotate the edited object by angles around the x, y and z axes
+command-object-scale = Scale the edited object
+command-object-boolean = Unite another object with the edited one where it is placed, subtract it, or keep only what both hold, then hide it
+command-snap-grid = Snap placed objects and instances to a grid, or stop snapping them to it
+command-snap-angle = Snap the rotation of placed objects and instances to steps of an angle, or stop snapping it
+command-snap-surface = Drop placed objects and instances onto the surface of the objects below them, or stop dropping them
+command-snap-voxels = Snap the center of the brush to the voxel grid at an octree depth, or stop snapping it
+command-instance-add = Show the edited object once more at a position, sharing its voxels
+command-instance-scatter = Scatter instances of the edited object on the ground around it, like rocks or rivets
+command-instance-count = The object has { $count } instances
+command-instance-clear = Remove the instances of the edited object
+command-gradient = Fill the active object with a ramp of materials from one side to the other along an axis
+command-gradient-between = Fill the active object with a ramp of materials between the sculpt under two points of the screen
+command-wear = Wear down the convex edges of the active object with an edge material, or erode them
+command-quantize = Collapse the sculpt into blocks of an octree depth, for a voxel art look
+command-node-limit = Limit how many nodes each object may hold, refining strokes which would go over it less deeply, or lift the limit
+command-validate = Check the sculpt for inconsistencies in its octree
+command-validate-clean = The sculpt has no inconsistencies
+command-validate-issues = Found { $count } inconsistencies, fix them with "repair"
+command-validate-loaded = The file was damaged, { $count } inconsistencies were repaired as it was read
+command-repair = Fix inconsistencies in the octree of the sculpt
+command-repaired = Repaired { $count } inconsistencies
+command-watertight = Check that the surface is watertight, as needed to export a mesh
+command-watertight-ok = The surface is watertight
+command-watertight-failed = The surface isn't watertight in { $count } places, add or remove voxels there before exporting: { $places }
+command-watertight-edge = voxels or gaps meet only along an edge at { $position }
+command-watertight-vertex = voxels or gaps meet only at a corner at { $position }
+command-colors = Color the materials with a built-in palette, see "colors list"
+command-colors-import = Color the materials with a GIMP (.gpl) or Adobe swatch exchange (.ase) palette
+command-colors-list = List the built-in palettes
+command-colors-unknown = There is no palette called "{ $name }"
+command-colors-import-failed = Could not import the palette: { $error }
+command-colors-imported = Imported { $count } colors
+command-colors-safe = { $name } (color-blind safe)
+command-material-subsurface = Let light scatter under the surface of a material, from 0 for none to 1, so it looks less like plastic
+command-material-subsurface-range = The scattering has to be between 0 and 1
+command-light-directional = Add a light shining along a direction, like sunlight, optionally colored
+command-light-point = Add a light spreading out from a point, optionally colored
+command-light-list = List the lights of the viewport
+command-light-clear = Remove every light, lighting the sculpt from the camera again
+command-light-none = There are no lights, so the sculpt is lit from the camera
+command-light-direction = A directional light needs a direction
+command-light-range = The intensity can't be negative, and the color channels have to be between 0 and 1
+command-light-full = There can be at most { $count } lights
+command-color = Show the color management of the active object
+command-color-space = Set the color space the materials of the active object are given and shaded in
+command-color-tonemap = Set how the colors of the active object are mapped onto the display
+command-color-exposure = Set the exposure of the active object in stops, where each stop doubles the brightness
+command-color-current = Working space { $space }, tone mapping { $tonemap }, exposure { $exposure } stops
+command-stats = Opt in or out of session statistics
+command-macro-record = Record commands and strokes into a macro
+command-macro-stop = Stop recording the macro
+command-macro-play = Play a macro, optionally moved to a point of the screen and scaled
+command-macro-list = List the recorded macros
+command-timelapse = Capture a frame into a directory every few strokes, optionally from a bookmarked view
+command-timelapse-stop = Stop capturing the timelapse
+command-soak = Apply random strokes while tracking memory, nodes, buffers and frame times, then write a report
+command-onion = Show the edited object as it is now as a translucent ghost, for comparing later states with it, or hide the ghost
+command-macro-recording = Recording "{ $name }", stop with "macro stop"
+command-macro-not-recording = No macro is being recorded
+command-macro-missing = There is no macro called "{ $name }"
@@ -0,0 +1,144 @@
+struct Chunk {
+    object_to_world: mat4x4<f32>,
+    world_to_object: mat4x4<f32>,
+    root_center: vec3<f32>,
+    root_size: f32,
+    resolution: u32,
+    layer: u32,
+}
+
+// the arguments of the indirect draw, followed by the count of triangles extracted
+struct Draw {
+    vertex_count: u32,
+    instance_count: u32,
+    first_vertex: u32,
+    first_instance: u32,
+    triangles: atomic<u32>,
+}
+
+@group(0) @binding(0) var<storage, read> triangle_table: array<u32>;
+// the vertices, each a position, a packed normal and a voxel pointer
+@group(0) @binding(1) var<storage, read_write> vertices: array<u32>;
+@group(0) @binding(2) var<storage, read_write> draw: Draw;
+@group(1) @binding(0) var<uniform> chunk: Chunk;
+@group(1) @binding(1) var<storage, read> voxels: array<u32>;
+
+const max_cells = 128u;
+const case_size = 16u;
+const no_edge = 12u;
+const vertex_size = 5u;
+
+const no_leaf = 0xffffffffu;
+
+// the corners at the ends of each edge of a cube, as x | y << 1 | z << 2
+const edge_corners = array<vec2<u32>, 12>(
+    vec2<u32>(0u, 1u), vec2<u32>(2u, 3u), vec2<u32>(4u, 5u), vec2<u32>(6u, 7u),
+    vec2<u32>(0u, 2u), vec2<u32>(1u, 3u), vec2<u32>(4u, 6u), vec2<u32>(5u, 7u),
+    vec2<u32>(0u, 4u), vec2<u32>(1u, 5u), vec2<u32>(2u, 6u), vec2<u32>(3u, 7u),
+);
+
+// one cube between each pair of samples, which reach one past each side of the chunk
+@compute @workgroup_size(4, 4, 4)
+fn extract_main(@builtin(global_invocation_id) cube: vec3<u32>) {
+    let cells = min(chunk.resolution, max_cells);
+    if (any(cube > vec3<u32>(cells))) {
+        return;
+    }
+
+    var case_index = 0u;
+    var pointer = no_leaf;
+    for (var corner = 0u; corner < 8u; corner += 1u) {
+        let leaf = leaf_at(sample_point(cube, corner, cells));
+        if (leaf != no_leaf) {
+            case_index |= 1u << corner;
+            pointer = leaf;
+        }
+    }
+
+    var edges = edge_corners;
+    for (var index = 0u; index < case_size; index += 3u) {
+        let first = case_index * case_size + index;
+        if (triangle_table[first] == no_edge) {
+            break;
+        }
+
+        var points: array<vec3<f32>, 3>;
+        for (var vertex = 0u; vertex < 3u; vertex += 1u) {
+            // the voxels are either solid or empty, so the surface crosses each edge in its middle
+            let ends = edges[triangle_table[first + vertex]];
+            let middle = (sample_point(cube, ends.x, cells) + sample_point(cube, ends.y, cells)) / 2.0;
+            points[vertex] = chunk.root_center + (middle - 0.5) * chunk.root_size;
+        }
+        let normal = pack4x8snorm(vec4<f32>(normalize(cross(points[1] - points[0], points[2] - points[0])), 0.0));
+
+        // triangles past the end of the buffer are dropped
+        let triangle = atomicAdd(&draw.triangles, 1u);
+        if (triangle >= capacity()) {
+            return;
+        }
+        for (var vertex = 0u; vertex < 3u; vertex += 1u) {
+            let offset = (triangle * 3u + vertex) * vertex_size;
+            vertices[offset] = bitcast<u32>(points[vertex].x);
+            vertices[offset + 1u] = bitcast<u32>(points[vertex].y);
+            vertices[offset + 2u] = bitcast<u32>(points[vertex].z);
+            vertices[offset + 3u] = normal;
+            vertices[offset + 4u] = pointer;
+        }
+    }
+}
+
+// draw the triangles which fit
+@compute @workgroup_size(1)
+fn finish_main() {
+    draw.vertex_count = min(atomicLoad(&draw.triangles), capacity()) * 3u;
+    draw.instance_count = 1u;
+    draw.first_vertex = 0u;
+    draw.first_instance = 0u;
+}
+
+// how many triangles fit in the vertex buffer
+fn capacity() -> u32 {
+    return arrayLength(&vertices) / (vertex_size * 3u);
+}
+
+// the sample at a corner of a cube, from 0 to 1 across the chunk
+fn sample_point(cube: vec3<u32>, corner: u32, cells: u32) -> vec3<f32> {
+    let offset = vec3<u32>(corner & 1u, (corner >> 1u) & 1u, corner >> 2u);
+    let sample = vec3<f32>(cube + offset) - 1.0;
+
+    return (sample + 0.5) / f32(cells);
+}
+
+// the voxel buffer entry of the leaf holding a point (0 to 1 across the chunk), or no_leaf where it is empty
+fn leaf_at(point: vec3<f32>) -> u32 {
+    if (any(point < vec3<f32>(0.0)) || any(point >= vec3<f32>(1.0))) {
+        return no_leaf;
+    }
+
+    var pointer = 0u;
+    var center = vec3<f32>(0.5);
+    var size = 1.0;
+    for (var level = 0u; level < 32u; level += 1u) {
+        let node = voxels[pointer];
+        let children = (node >> 8u) & 255u;
+        let leaves = node & 255u;
+        let side = select(vec3<u32>(0u), vec3<u32>(1u), point >= center);
+        let bit = 1u << (side.x | (side.y << 1u) | (side.z << 2u));
+        if ((children & bit) == 0u) {
+            return no_leaf;
+        }
+
+        // the children are packed in order, leaves taking one entry and the others two
+        let before = bit - 1u;
+        let child = voxels[pointer + 1u] + countOneBits(children & before) + countOneBits(children & ~leaves & before);
+        if ((leaves & bit) != 0u) {
+            return child;
+        }
+
+        pointer = child;
+        size /= 2.0;
+        center += (vec3<f32>(side) - 0.5) * size;
+    }
+
+    return no_leaf;
+}
@@ -0,0 +1,107 @@
+struct View {
+    view_projection: mat4x4<f32>,
+    inverse_view_projection: mat4x4<f32>,
+    previous_view_projection: mat4x4<f32>,
+    previous_inverse_view_projection: mat4x4<f32>,
+    history_valid: u32,
+}
+
+struct Preview {
+    center: vec3<f32>,
+    size: f32,
+    shape: u32,
+    color: vec4<f32>,
+}
+
+struct VertexInput {
+    @builtin(vertex_index) index: u32,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+const shape_cube = 1u;
+const steps = 64;
+
+@group(0) @binding(0) var<uniform> view: View;
+@group(0) @binding(1) var<uniform> preview: Preview;
+// the ray distance to the sculpt drawn this frame, as f32 bits (0 for a miss)
+@group(0) @binding(2) var history: texture_2d<u32>;
+
+@vertex
+fn vertex_main(input: VertexInput) -> VertexOutput {
+    let x = f32(i32(input.index & 1u) * 2 - 1);
+    let y = f32(i32(input.index & 2u) - 1);
+    let u = x / 2.0 + 0.5;
+    let v = 1.0 - (y / 2.0 + 0.5);
+    return VertexOutput(vec4<f32>(x, y, 0.0, 1.0), vec2<f32>(u, v));
+}
+
+@fragment
+fn fragment_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let ndc = vec2<f32>(input.uv.x * 2.0 - 1.0, 1.0 - input.uv.y * 2.0);
+    let near = view.inverse_view_projection * vec4<f32>(ndc, 0.0, 1.0);
+    let far = view.inverse_view_projection * vec4<f32>(ndc, 1.0, 1.0);
+    let origin = near.xyz / near.w;
+    let direction = normalize(far.xyz / far.w - origin);
+
+    // only march where the ray passes through the bounding sphere of the stamp
+    let bounds = preview.size * sqrt(3.0);
+    let along = dot(preview.center - origin, direction);
+    let closest = length(origin + direction * along - preview.center);
+    if (closest > bounds) {
+        discard;
+    }
+    var t = max(along - bounds, 0.0);
+    let end = along + bounds;
+
+    var hit = false;
+    for (var step = 0; step < steps && t < end; step++) {
+        let distance = stamp_distance(origin + direction * t);
+        if (distance < preview.size * 0.001) {
+            hit = true;
+            break;
+        }
+        t += distance;
+    }
+    if (!hit) {
+        discard;
+    }
+
+    // rims stand out more than faces pointing at the camera, so the sculpt shows through
+    let normal = stamp_normal(origin + direction * t);
+    let facing = abs(dot(normal, direction));
+    var alpha = preview.color.a * (1.0 - 0.6 * facing);
+
+    // fade the parts behind the sculpt instead of hiding them, to help with depth
+    let size = textureDimensions(history);
+    let pixel = min(vec2<u32>(input.uv * vec2<f32>(size)), size - 1u);
+    let surface = textureLoad(history, pixel, 0).x;
+    if (surface != 0u && bitcast<f32>(surface) < t) {
+        alpha *= 0.3;
+    }
+
+    return vec4<f32>(preview.color.rgb, alpha);
+}
+
+// signed distance to the stamp volume
+fn stamp_distance(point: vec3<f32>) -> f32 {
+    let local = point - preview.center;
+    if (preview.shape == shape_cube) {
+        let q = abs(local) - vec3<f32>(preview.size);
+        return length(max(q, vec3<f32>(0.0))) + min(max(q.x, max(q.y, q.z)), 0.0);
+    }
+
+    return length(local) - preview.size;
+}
+
+fn stamp_normal(point: vec3<f32>) -> vec3<f32> {
+    let e = vec2<f32>(preview.size * 0.001, 0.0);
+    return normalize(vec3<f32>(
+        stamp_distance(point + e.xyy) - stamp_distance(point - e.xyy),
+        stamp_distance(point + e.yxy) - stamp_distance(point - e.yxy),
+        stamp_distance(point + e.yyx) - stamp_distance(point - e.yyx),
+    ));
+}
@@ -1,5 +1,26 @@
+// composed with the defines of renderer::variants, HEAT, MASK and DESCENT, before it is compiled
 struct Settings {
     resolution: u32,
+    display_mode: u32,
+    traversal: u32,
+    sample: u32,
+}
+
+struct View {
+    view_projection: mat4x4<f32>,
+    inverse_view_projection: mat4x4<f32>,
+    previous_view_projection: mat4x4<f32>,
+    previous_inverse_view_projection: mat4x4<f32>,
+    history_valid: u32,
+}
+
+struct Chunk {
+    object_to_world: mat4x4<f32>,
+    world_to_object: mat4x4<f32>,
+    root_center: vec3<f32>,
+    root_size: f32,
+    resolution: u32,
+    layer: u32,
 }
 
 struct VertexInput {
@@ -8,13 +29,84 @@ struct VertexInput {
 
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
-    @location(0) uv: vec2<f32>,
+}
+
+// a vertex of a mesh extracted from the chunk, in the space of its object
+struct MeshVertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec4<f32>,
+    // the voxel buffer entry of a leaf next to the vertex
+    @location(2) pointer: u32,
+}
+
+struct MeshVertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) @interpolate(flat) pointer: u32,
 }
 
 struct Material {
     color: vec4<f32>,
     roughness: f32,
     metallic: f32,
+    // how much light scatters under the surface, from 0 for none to 1
+    subsurface: f32,
+}
+
+struct Light {
+    // the direction a directional light shines along, or the position a point light spreads out from
+    vector: vec3<f32>,
+    kind: u32,
+    color: vec3<f32>,
+    intensity: f32,
+}
+
+// the lights of the sculpt, or none to light it from the camera
+struct Lights {
+    count: u32,
+    lights: array<Light, max_lights>,
+}
+
+// the layout of the material strip, in viewport heights from the top right corner
+struct Strip {
+    count: u32,
+    selected: u32,
+    aspect: f32,
+    radius: f32,
+    origin: vec2<f32>,
+    spacing: f32,
+}
+
+struct StripVertexOutput {
+    @builtin(position) position: vec4<f32>,
+    // the position in the quad around a sphere, with its edge 1 away from the center
+    @location(0) offset: vec2<f32>,
+    @location(1) @interpolate(flat) material: u32,
+}
+
+struct FragmentOutput {
+    @location(0) color: vec4<f32>,
+    @location(1) distance: u32,
+    @location(2) pick: vec4<u32>,
+    // the depth of the hit, so the nearest object wins where the chunks of several overlap
+    @builtin(frag_depth) depth: f32,
+}
+
+struct SurfaceHit {
+    hit: bool,
+    distance: f32,
+    pointer: u32,
+    material: u32,
+    normal: vec3<f32>,
+    depth: u32,
+}
+
+struct TraversalFrame {
+    entry: u32,
+    t0: vec3<f32>,
+    t1: vec3<f32>,
+    child: u32,
 }
 
 struct VoxelHit {
@@ -28,32 +120,504 @@ struct VoxelHit {
     color: u32,
 }
 
+// the corners of the chunk box for each triangle, as x | y << 1 | z << 2
+const box_corners = array<u32, 36>(
+    0u, 2u, 6u, 0u, 6u, 4u,
+    1u, 5u, 7u, 1u, 7u, 3u,
+    0u, 4u, 5u, 0u, 5u, 1u,
+    2u, 3u, 7u, 2u, 7u, 6u,
+    0u, 1u, 3u, 0u, 3u, 2u,
+    4u, 6u, 7u, 4u, 7u, 5u,
+);
+
 @vertex
 fn vertex_main(input: VertexInput) -> VertexOutput {
-    let x = f32(i32(input.index & 1u) * 2 - 1);
-    let y = f32(i32(input.index & 2u) - 1);
-    let u = x / 2.0 + 0.5;
-    let v = 1.0 - (y / 2.0 + 0.5);
-    return VertexOutput(vec4<f32>(x, y, 0.0, 1.0), vec2<f32>(u, v));
+    var corners = box_corners;
+    let corner = corners[input.index];
+    let offset = vec3<f32>(f32(corner & 1u), f32((corner >> 1u) & 1u), f32((corner >> 2u) & 1u)) - 0.5;
+    let position = chunk.object_to_world * vec4<f32>(chunk.root_center + offset * chunk.root_size, 1.0);
+    return VertexOutput(view.view_projection * position);
 }
 
 @group(0) @binding(0) var<uniform> settings: Settings;
-@group(0) @binding(1) var<storage, read> voxels: array<u32>;
+@group(0) @binding(1) var<uniform> view: View;
 @group(0) @binding(2) var<storage, read> materials: array<Material>;
+@group(0) @binding(3) var history: texture_2d<u32>;
+@group(0) @binding(4) var<uniform> strip: Strip;
+@group(0) @binding(5) var<uniform> lights: Lights;
+@group(1) @binding(0) var<uniform> chunk: Chunk;
+@group(1) @binding(1) var<storage, read> voxels: array<u32>;
+@group(1) @binding(2) var<storage, read> heat: array<f32>;
+// the paint layer, packed like pack4x8unorm, mixed over the material by its alpha
+@group(1) @binding(3) var<storage, read> paint: array<u32>;
+
+const display_heat = 1u;
+// the heat buffer holds the mask in this mode
+const display_mask = 2u;
+
+const traversal_descent = 1u;
+
+const no_child = 8u;
+
+// the most pixels of last frame the skipped start of a ray is checked across, before it is marched whole
+const history_steps = 64u;
+
+// the limits of the traversal, set from the render quality when the pipeline is created
+override max_depth: u32 = 16u;
+override max_iterations: u32 = 512u;
+override descent_steps: u32 = 16u;
+// how close, in voxels, the stepwise descent has to come to a voxel to hit it
+override hit_distance: f32 = 2.0;
+
+// the layer picked on the material strip, above every object
+const strip_layer = 0xffffffffu;
+// the size of the ring around the selected material, relative to its sphere
+const ring_size = 1.2;
+
+// the most lights, which the lights uniform has room for
+const max_lights = 8u;
+const light_directional = 0u;
+const light_point = 1u;
+
+// the share of the color lit without any light
+const ambient = 0.1;
+
+// how deep light scattering under the surface is followed into a sculpt, relative to its chunk,
+// and how many points along the way are looked up in the octree
+const scatter_depth = 0.1;
+const scatter_samples = 6u;
+
+// the tint and opacity of the onion skin ghost
+const ghost_color = vec3<f32>(0.55, 0.8, 1.0);
+const ghost_opacity = 0.35;
 
-const hit_distance = 2.0;
+@fragment
+fn fragment_main(input: VertexOutput) -> FragmentOutput {
+    return march_chunk(input.position.xy + pixel_jitter(), true);
+}
 
+// an earlier state of a sculpt, blended over it as a translucent ghost
 @fragment
-fn fragment_main(input: VertexOutput) -> @location(0) vec4<f32> {
-    let ray_origin = vec3<f32>(input.uv, 0.0); // multiply by world matrix
-    let ray_direction = vec3<f32>(0.0, 0.0, 1.0); // multiply by world matrix
-    
-    const max_steps = 16u;
-    const maximum_distance = 1.0;
+fn ghost_fragment_main(input: VertexOutput) -> FragmentOutput {
+    // last frame's surface is the sculpt's, not the ghost's, so rays start at the chunk
+    var output = march_chunk(input.position.xy + pixel_jitter(), false);
+    output.color = vec4<f32>(mix(output.color.rgb, ghost_color, 0.5), ghost_opacity);
 
-    var ray_distance = 0.0;
+    return output;
+}
 
-    for (var step = 0u; step < max_steps; step += 1u) {
+// shade the surface of the chunk through a pixel, optionally starting near last frame's surface
+fn march_chunk(pixel: vec2<f32>, use_history: bool) -> FragmentOutput {
+    let ray = screen_ray(view.inverse_view_projection, pixel / f32(settings.resolution));
+    let ray_origin = ray[0];
+    let ray_direction = ray[1];
+
+    // the octree is marched in the space of its object, where distances are scaled
+    let object_origin = (chunk.world_to_object * vec4<f32>(ray_origin, 1.0)).xyz;
+    let stretched = (chunk.world_to_object * vec4<f32>(ray_direction, 0.0)).xyz;
+    let scale = length(stretched);
+    let object_direction = stretched / scale;
+
+    // start near last frame's surface, unless that lands inside of a voxel or misses
+    var start = 0.0;
+    if (use_history) {
+        start = history_start(ray_origin, ray_direction, pixel) * scale;
+    }
+
+    var surface: SurfaceHit;
+#ifdef DESCENT
+    if (settings.traversal == traversal_descent) {
+        surface = march_descent(object_origin, object_direction);
+    } else {
+        surface = trace_from(object_origin, object_direction, start);
+    }
+#else
+    surface = trace_from(object_origin, object_direction, start);
+#endif
+
+    if (surface.hit) {
+        surface.distance /= scale;
+        // normals are mapped by the inverse transpose, which is the transposed inverse
+        surface.normal = normalize((transpose(chunk.world_to_object) * vec4<f32>(surface.normal, 0.0)).xyz);
+        let position = ray_origin + surface.distance * ray_direction;
+
+        let color = surface_color(surface.material, surface.pointer);
+        let shaded = simple_blinn_phong(position, color, surface.normal, ray_direction, surface.distance, materials[surface.material].subsurface);
+
+        let pick = vec4<u32>(chunk.layer, surface.material, surface.depth, 1u);
+
+        let projected = view.view_projection * vec4<f32>(position, 1.0);
+
+        return FragmentOutput(shaded, bitcast<u32>(surface.distance), pick, projected.z / projected.w);
+    }
+
+    // leave the pixel to chunks further back
+    discard;
+}
+
+// the exact traversal, starting at a distance if that doesn't miss the surface
+fn trace_from(origin: vec3<f32>, direction: vec3<f32>, start: f32) -> SurfaceHit {
+    var surface: SurfaceHit;
+    if (start > 0.0) {
+        surface = trace_exact(origin, direction, start);
+    }
+    if (!surface.hit || surface.distance <= start) {
+        surface = trace_exact(origin, direction, 0.0);
+    }
+
+    return surface;
+}
+
+// the color of a leaf's material, painted over, or its edit heat or mask for the display mode
+fn surface_color(material: u32, pointer: u32) -> vec4<f32> {
+    var color = materials[material].color;
+    let painted = unpack4x8unorm(paint[pointer]);
+    color = vec4<f32>(mix(color.rgb, painted.rgb, painted.a), color.a);
+#ifdef HEAT
+    if (settings.display_mode == display_heat) {
+        color = heat_color(heat[pointer]);
+    }
+#endif
+#ifdef MASK
+    if (settings.display_mode == display_mask) {
+        color = vec4<f32>(mix(color.rgb, vec3<f32>(0.1, 0.1, 0.15), heat[pointer] * 0.8), color.a);
+    }
+#endif
+
+    return color;
+}
+
+// the triangles extracted from the chunk, drawn instead of marching it
+@vertex
+fn mesh_vertex_main(input: MeshVertexInput) -> MeshVertexOutput {
+    let position = chunk.object_to_world * vec4<f32>(input.position, 1.0);
+    let normal = normalize((transpose(chunk.world_to_object) * vec4<f32>(input.normal.xyz, 0.0)).xyz);
+
+    return MeshVertexOutput(view.view_projection * position, position.xyz, normal, input.pointer);
+}
+
+@fragment
+fn mesh_fragment_main(input: MeshVertexOutput) -> FragmentOutput {
+    // the distance along the ray through the pixel, like a marched hit, so rays and the preview can start from it
+    let ray = screen_ray(view.inverse_view_projection, input.position.xy / f32(settings.resolution));
+    let distance = dot(input.world_position - ray[0], ray[1]);
+
+    // leaves hold their material in the voxel buffer
+    let material = voxels[input.pointer];
+    let shaded = simple_blinn_phong(input.world_position, surface_color(material, input.pointer), input.normal, ray[1], distance, materials[material].subsurface);
+
+    // the mesh is as fine as the leaves at most
+    let pick = vec4<u32>(chunk.layer, material, firstTrailingBit(chunk.resolution), 1u);
+
+    return FragmentOutput(shaded, bitcast<u32>(distance), pick, input.position.z);
+}
+
+// the spheres of the material strip, drawn over the sculpt as quads facing the camera
+@vertex
+fn strip_vertex_main(@builtin(vertex_index) index: u32, @builtin(instance_index) material: u32) -> StripVertexOutput {
+    // the quad leaves room for the ring around the selected material
+    let offset = (vec2<f32>(f32(index & 1u), f32(index >> 1u)) * 2.0 - 1.0) * ring_size;
+    let center = vec2<f32>(strip.aspect - strip.origin.x, strip.origin.y + f32(material) * strip.spacing);
+    let screen = (center + offset * strip.radius) / vec2<f32>(strip.aspect, 1.0);
+
+    return StripVertexOutput(vec4<f32>(screen.x * 2.0 - 1.0, 1.0 - screen.y * 2.0, 0.0, 1.0), offset, material);
+}
+
+@fragment
+fn strip_fragment_main(input: StripVertexOutput) -> FragmentOutput {
+    let pick = vec4<u32>(strip_layer, input.material, 0u, 1u);
+    let edge = length(input.offset);
+    if (edge > 1.0) {
+        if (input.material == strip.selected && edge <= ring_size) {
+            return FragmentOutput(vec4<f32>(1.0), 0u, pick, 0.0);
+        }
+        discard;
+    }
+
+    // the sphere faces the camera, so its normal is turned from the view axes into the world
+    let origin = view_center(0.0);
+    let right = normalize(view_point(vec3<f32>(1.0, 0.0, 0.0)) - origin);
+    let up = normalize(view_point(vec3<f32>(0.0, 1.0, 0.0)) - origin);
+    let forward = normalize(view_center(1.0) - origin);
+    let normal = normalize(right * input.offset.x - up * input.offset.y - forward * sqrt(1.0 - edge * edge));
+
+    // lit from the camera, like the sculpt without lights, but without distance, so rays never start from it
+    // the sphere has no octree to scatter through, so only its light wraps around
+    let shaded = blinn_phong(materials[input.material].color, normal, -forward, forward, 0.0, materials[input.material].subsurface, 0.0);
+
+    return FragmentOutput(shaded, 0u, pick, 0.0);
+}
+
+// the offset of rays from the center of their pixel, spread over the pixel by the samples accumulated while the view is still
+fn pixel_jitter() -> vec2<f32> {
+    if (settings.sample == 0u) {
+        return vec2<f32>(0.0);
+    }
+
+    return vec2<f32>(halton(settings.sample, 2u), halton(settings.sample, 3u)) - 0.5;
+}
+
+// a point of the Halton sequence in a base, from 0 to 1
+fn halton(index: u32, base: u32) -> f32 {
+    var fraction = 1.0;
+    var result = 0.0;
+    var remaining = index;
+    while (remaining > 0u) {
+        fraction /= f32(base);
+        result += fraction * f32(remaining % base);
+        remaining /= base;
+    }
+
+    return result;
+}
+
+// the ray through a point on the screen (0 to 1, y down) for a view
+fn screen_ray(inverse_view_projection: mat4x4<f32>, screen: vec2<f32>) -> array<vec3<f32>, 2> {
+    let ndc = vec2<f32>(screen.x * 2.0 - 1.0, 1.0 - screen.y * 2.0);
+    let near = inverse_view_projection * vec4<f32>(ndc, 0.0, 1.0);
+    let far = inverse_view_projection * vec4<f32>(ndc, 1.0, 1.0);
+    let origin = near.xyz / near.w;
+
+    return array<vec3<f32>, 2>(origin, normalize(far.xyz / far.w - origin));
+}
+
+// the surface hit through a pixel last frame, if any
+fn previous_surface(pixel: vec2<i32>) -> vec4<f32> {
+    let distance = textureLoad(history, pixel, 0).x;
+    if (distance == 0u) {
+        return vec4<f32>(0.0);
+    }
+
+    let ray = screen_ray(view.previous_inverse_view_projection, (vec2<f32>(pixel) + 0.5) / f32(settings.resolution));
+
+    return vec4<f32>(ray[0] + ray[1] * bitcast<f32>(distance), 1.0);
+}
+
+// where a point of the world was on screen (0 to 1, y down) for a view, and its w, which isn't positive behind the view
+fn project(view_projection: mat4x4<f32>, point: vec3<f32>) -> vec3<f32> {
+    let projected = view_projection * vec4<f32>(point, 1.0);
+
+    return vec3<f32>(vec2<f32>(projected.x / projected.w, -projected.y / projected.w) * 0.5 + 0.5, projected.w);
+}
+
+// whether a stretch of a ray was in front of every surface seen last frame, checked on each
+// pixel it crossed on screen then; a surface which has since slid in front of where the ray
+// would start was seen on one of them, so the stretch isn't known to be empty
+fn history_empty(origin: vec3<f32>, direction: vec3<f32>, distance: f32, margin: f32) -> bool {
+    let resolution = f32(settings.resolution);
+    let near = project(view.previous_view_projection, origin);
+    let far = project(view.previous_view_projection, origin + direction * distance);
+    if (near.z <= 0.0 || far.z <= 0.0) {
+        return false;
+    }
+    let steps = u32(ceil(length((far.xy - near.xy) * resolution))) + 1u;
+    if (steps > history_steps) {
+        return false;
+    }
+
+    for (var step = 0u; step <= steps; step++) {
+        let along = f32(step) / f32(steps);
+        let screen = mix(near.xy, far.xy, along);
+        if (any(screen < vec2<f32>(0.0)) || any(screen >= vec2<f32>(1.0))) {
+            return false;
+        }
+        let texel = vec2<i32>(screen * resolution);
+        let seen = textureLoad(history, texel, 0).x;
+        if (seen == 0u) {
+            continue;
+        }
+
+        // the point of the stretch drawn there, interpolated in perspective
+        let point = origin + direction * (distance * along / far.z / ((1.0 - along) / near.z + along / far.z));
+        let ray = screen_ray(view.previous_inverse_view_projection, (vec2<f32>(texel) + 0.5) / resolution);
+        if (bitcast<f32>(seen) < dot(point - ray[0], ray[1]) + margin) {
+            return false;
+        }
+    }
+
+    return true;
+}
+
+// the distance to start a ray at, from the surface hit last frame, or 0 to march all of it
+fn history_start(origin: vec3<f32>, direction: vec3<f32>, pixel: vec2<f32>) -> f32 {
+    if (view.history_valid == 0u) {
+        return 0.0;
+    }
+
+    // guess with the surface seen through the same pixel, then look that point up
+    // where it was on screen last frame
+    let guess = previous_surface(vec2<i32>(pixel));
+    if (guess.w == 0.0) {
+        return 0.0;
+    }
+    let projected = project(view.previous_view_projection, origin + direction * dot(guess.xyz - origin, direction));
+    if (projected.z <= 0.0 || any(projected.xy < vec2<f32>(0.0)) || any(projected.xy >= vec2<f32>(1.0))) {
+        return 0.0;
+    }
+    let previous = previous_surface(vec2<i32>(projected.xy * f32(settings.resolution)));
+    if (previous.w == 0.0) {
+        return 0.0;
+    }
+
+    // the reprojected surface has to be on this ray, within voxels as large as they are in the world
+    let distance = dot(previous.xyz - origin, direction);
+    let world_voxel_size = voxel_size() / length((chunk.world_to_object * vec4<f32>(direction, 0.0)).xyz);
+    if (length(origin + direction * distance - previous.xyz) > 2.0 * world_voxel_size) {
+        return 0.0;
+    }
+
+    // the surface is only where the ray hits first if nothing was seen in front of it last frame,
+    // like the edge of a closer object sliding over it as the view moves
+    let start = max(distance - 2.0 * world_voxel_size, 0.0);
+    if (!history_empty(origin, direction, start, world_voxel_size)) {
+        return 0.0;
+    }
+
+    return start;
+}
+
+// exact front to back traversal of the octree (Revelles et al.), using the
+// entry and exit distances of each node along the ray, so no voxel is skipped;
+// nodes ending before the start distance are skipped
+fn trace_exact(origin: vec3<f32>, direction: vec3<f32>, start: f32) -> SurfaceHit {
+    var result = SurfaceHit(false, 0.0, 0u, 0u, vec3<f32>(0.0), 0u);
+
+    // mirror the ray so every direction component is positive, remembering
+    // the flipped axes to map child indices back
+    var mirrored_origin = origin;
+    var mirrored_direction = direction;
+    var flip = 0u;
+    if (direction.x < 0.0) {
+        mirrored_origin.x = 2.0 * chunk.root_center.x - origin.x;
+        mirrored_direction.x = -direction.x;
+        flip |= 1u;
+    }
+    if (direction.y < 0.0) {
+        mirrored_origin.y = 2.0 * chunk.root_center.y - origin.y;
+        mirrored_direction.y = -direction.y;
+        flip |= 2u;
+    }
+    if (direction.z < 0.0) {
+        mirrored_origin.z = 2.0 * chunk.root_center.z - origin.z;
+        mirrored_direction.z = -direction.z;
+        flip |= 4u;
+    }
+    mirrored_direction = max(mirrored_direction, vec3<f32>(1e-7));
+
+    let t0 = (chunk.root_center - chunk.root_size / 2.0 - mirrored_origin) / mirrored_direction;
+    let t1 = (chunk.root_center + chunk.root_size / 2.0 - mirrored_origin) / mirrored_direction;
+    if (max_component(t0) >= min_component(t1) || min_component(t1) < start || ((voxels[0] >> 8u) & 255u) == 0u) {
+        return result;
+    }
+
+    // room for the deepest traversal the render quality allows, of which max_depth is used
+    var stack: array<TraversalFrame, 24>;
+    var level = 0;
+    stack[0] = TraversalFrame(0u, t0, t1, first_child(t0, (t0 + t1) / 2.0));
+
+    for (var iteration = 0u; iteration < max_iterations; iteration += 1u) {
+        let frame = stack[level];
+        if (frame.child == no_child) {
+            level -= 1;
+            if (level < 0) {
+                break;
+            }
+            continue;
+        }
+
+        let child = frame.child;
+        let middle = (frame.t0 + frame.t1) / 2.0;
+        let upper = vec3<bool>((child & 1u) != 0u, (child & 2u) != 0u, (child & 4u) != 0u);
+        let child_t0 = select(frame.t0, middle, upper);
+        let child_t1 = select(middle, frame.t1, upper);
+        stack[level].child = next_child(child, child_t1);
+
+        let index = child ^ flip;
+        let masks = voxels[frame.entry];
+        let children = (masks >> 8u) & 255u;
+        let leaves = masks & 255u;
+        if ((children & (1u << index)) == 0u || min_component(child_t1) < start) {
+            continue;
+        }
+
+        // leaves take one entry and interior nodes two, in child order
+        let before = (1u << index) - 1u;
+        let entry = voxels[frame.entry + 1u] + countOneBits(children & before) + countOneBits(children & ~leaves & before);
+
+        if ((leaves & (1u << index)) != 0u) {
+            let entry_distance = max_component(child_t0);
+            var normal = vec3<f32>(0.0);
+            if (entry_distance == child_t0.x) {
+                normal.x = -sign(direction.x);
+            } else if (entry_distance == child_t0.y) {
+                normal.y = -sign(direction.y);
+            } else {
+                normal.z = -sign(direction.z);
+            }
+            if (entry_distance < start) {
+                normal = -direction;
+            }
+
+            return SurfaceHit(true, max(entry_distance, start), entry, voxels[entry], normal, u32(level + 1));
+        }
+
+        if (level < i32(max_depth) - 1) {
+            level += 1;
+            stack[level] = TraversalFrame(entry, child_t0, child_t1, first_child(child_t0, (child_t0 + child_t1) / 2.0));
+        }
+    }
+
+    return result;
+}
+
+// the first child a ray passes through, from the plane it enters the node by
+fn first_child(t0: vec3<f32>, middle: vec3<f32>) -> u32 {
+    var child = 0u;
+    let entry = max_component(t0);
+    if (entry == t0.x) {
+        if (middle.y < entry) { child |= 2u; }
+        if (middle.z < entry) { child |= 4u; }
+    } else if (entry == t0.y) {
+        if (middle.x < entry) { child |= 1u; }
+        if (middle.z < entry) { child |= 4u; }
+    } else {
+        if (middle.x < entry) { child |= 1u; }
+        if (middle.y < entry) { child |= 2u; }
+    }
+    return child;
+}
+
+// the sibling a ray moves into after leaving a child, by the plane it exits through
+fn next_child(child: u32, t1: vec3<f32>) -> u32 {
+    let exit = min_component(t1);
+    var axis = 4u;
+    if (exit == t1.x) {
+        axis = 1u;
+    } else if (exit == t1.y) {
+        axis = 2u;
+    }
+    if ((child & axis) != 0u) {
+        return no_child;
+    }
+    return child | axis;
+}
+
+fn max_component(value: vec3<f32>) -> f32 {
+    return max(value.x, max(value.y, value.z));
+}
+
+fn min_component(value: vec3<f32>) -> f32 {
+    return min(value.x, min(value.y, value.z));
+}
+
+// the original stepwise descent, kept behind a debug toggle for comparison
+fn march_descent(ray_origin: vec3<f32>, ray_direction: vec3<f32>) -> SurfaceHit {
+    let entry_distance = box_entry(ray_origin, ray_direction);
+    let maximum_distance = entry_distance + chunk.root_size * sqrt(3.0);
+
+    var ray_distance = entry_distance;
+
+    for (var step = 0u; step < descent_steps; step += 1u) {
         var position = ray_origin + ray_distance * ray_direction;
 
         let closest = hit_root(position);
@@ -62,12 +626,14 @@ fn fragment_main(input: VertexOutput) -> @location(0) vec4<f32> {
             break;
         }
 
-        ray_distance += max(closest.distance, 1.0 / f32(settings.resolution));
+        ray_distance += max(closest.distance, voxel_size());
 
-        if (closest.distance <= hit_distance / f32(settings.resolution)) {
+        if (closest.distance <= hit_distance * voxel_size()) {
             position = ray_origin + ray_distance * ray_direction;
 
-            return simple_blinn_phong(position, materials[closest.color].color, voxel_normal(closest, position, ray_direction), ray_direction, ray_distance);
+            let depth = u32(round(log2(chunk.root_size / closest.size)));
+
+            return SurfaceHit(true, ray_distance, closest.pointer, closest.color, voxel_normal(closest, position, ray_direction), depth);
         }
 
         if (ray_distance > maximum_distance) {
@@ -75,7 +641,36 @@ fn fragment_main(input: VertexOutput) -> @location(0) vec4<f32> {
         }
     }
 
-    return vec4<f32>(0.03, 0.04, 0.06, 1.0);
+    return SurfaceHit(false, 0.0, 0u, 0u, vec3<f32>(0.0), 0u);
+}
+
+// cold blue for untouched voxels through to hot red for fresh, repeated edits
+fn heat_color(value: f32) -> vec4<f32> {
+    let t = 1.0 - exp(-value);
+    let cold = vec3<f32>(0.1, 0.2, 0.8);
+    let warm = vec3<f32>(0.9, 0.8, 0.2);
+    let hot = vec3<f32>(0.9, 0.1, 0.05);
+
+    if (t < 0.5) {
+        return vec4<f32>(mix(cold, warm, t * 2.0), 1.0);
+    }
+
+    return vec4<f32>(mix(warm, hot, t * 2.0 - 1.0), 1.0);
+}
+
+fn voxel_size() -> f32 {
+    return chunk.root_size / f32(chunk.resolution);
+}
+
+// where the ray enters the chunk, or 0 if it starts inside
+fn box_entry(origin: vec3<f32>, direction: vec3<f32>) -> f32 {
+    let safe_direction = select(direction, vec3<f32>(1e-8), abs(direction) < vec3<f32>(1e-8));
+    let inverse_direction = 1.0 / safe_direction;
+    let low = (chunk.root_center - chunk.root_size / 2.0 - origin) * inverse_direction;
+    let high = (chunk.root_center + chunk.root_size / 2.0 - origin) * inverse_direction;
+    let near = min(low, high);
+
+    return max(max(near.x, near.y), max(near.z, 0.0));
 }
 
 fn voxel_normal(hit: VoxelHit, position: vec3<f32>, view_direction: vec3<f32>) -> vec3<f32> {
@@ -91,28 +686,28 @@ fn voxel_normal(hit: VoxelHit, position: vec3<f32>, view_direction: vec3<f32>) -
     let rbt = hit_root(hit.center + vec3<f32>(delta, delta, delta));
 
     var normal = vec3<f32>(0.0, 0.0, 0.0);
-    if lfb.distance <= (hit_distance / f32(settings.resolution)) {
+    if lfb.distance <= (hit_distance * voxel_size()) {
         normal += vec3<f32>(1.0, 1.0, 1.0) * min(rbt.distance, delta);
     }
-    if rfb.distance <= (hit_distance / f32(settings.resolution)) {
+    if rfb.distance <= (hit_distance * voxel_size()) {
         normal += vec3<f32>(-1.0, 1.0, 1.0) * min(lbt.distance, delta);
     }
-    if lbb.distance <= (hit_distance / f32(settings.resolution)) {
+    if lbb.distance <= (hit_distance * voxel_size()) {
         normal += vec3<f32>(1.0, -1.0, 1.0) * min(rft.distance, delta);
     }
-    if rbb.distance <= (hit_distance / f32(settings.resolution)) {
+    if rbb.distance <= (hit_distance * voxel_size()) {
         normal += vec3<f32>(-1.0, -1.0, 1.0) * min(lft.distance, delta);
     }
-    if lft.distance <= (hit_distance / f32(settings.resolution)) {
+    if lft.distance <= (hit_distance * voxel_size()) {
         normal += vec3<f32>(1.0, 1.0, -1.0) * min(rbb.distance, delta);
     }
-    if rft.distance <= (hit_distance / f32(settings.resolution)) {
+    if rft.distance <= (hit_distance * voxel_size()) {
         normal += vec3<f32>(-1.0, 1.0, -1.0) * min(lbb.distance, delta);
     }
-    if lbt.distance <= (hit_distance / f32(settings.resolution)) {
+    if lbt.distance <= (hit_distance * voxel_size()) {
         normal += vec3<f32>(1.0, -1.0, -1.0) * min(rfb.distance, delta);
     }
-    if rbt.distance <= (hit_distance / f32(settings.resolution)) {
+    if rbt.distance <= (hit_distance * voxel_size()) {
         normal += vec3<f32>(-1.0, -1.0, -1.0) * min(lfb.distance, delta);
     }
 
@@ -124,7 +719,7 @@ fn voxel_normal(hit: VoxelHit, position: vec3<f32>, view_direction: vec3<f32>) -
 }
 
 fn hit_root(position: vec3<f32>) -> VoxelHit {
-    let root = VoxelHit(false, 0u, 100.0, vec3<f32>(0.5, 0.5, 0.5), 1.0, 0u, 0u, 0u);
+    let root = VoxelHit(false, 0u, 100.0, chunk.root_center, chunk.root_size, 0u, 0u, 0u);
 
     var hit = hit_voxel(root, position);
 
@@ -161,7 +756,7 @@ fn hit_voxel(parent: VoxelHit, position: vec3<f32>) -> VoxelHit {
             if (hit.distance < minimum_distance) {
                 result = hit;
                 minimum_distance = hit.distance;
-                if (hit.distance <= hit_distance / f32(settings.resolution)) {
+                if (hit.distance <= hit_distance * voxel_size()) {
                     break;
                 }
             }
@@ -248,16 +843,90 @@ fn voxel_distance(position: vec3<f32>, center: vec3<f32>, half_size: f32) -> f32
     return sqrt(pow(max(0.0, shifted.x - 1.0), 2.0) + pow(max(0.0, shifted.y - 1.0), 2.0) + pow(max(0.0, shifted.z - 1.0), 2.0)) * half_size;
 }
 
-fn simple_blinn_phong(position: vec3<f32>, color: vec4<f32>, normal: vec3<f32>, view_direction: vec3<f32>, depth: f32) -> vec4<f32> {
+fn simple_blinn_phong(position: vec3<f32>, color: vec4<f32>, normal: vec3<f32>, view_direction: vec3<f32>, depth: f32, subsurface: f32) -> vec4<f32> {
+    // without lights of its own, the sculpt is lit from the camera
+    if (lights.count == 0u) {
+        let light_direction = normalize(view_center(0.0) - position);
+
+        return blinn_phong(color, normal, light_direction, view_direction, depth, subsurface, scattered_transmittance(position, light_direction, subsurface));
+    }
+
+    var shaded = color.rgb * ambient;
+    for (var index = 0u; index < min(lights.count, max_lights); index += 1u) {
+        let light = lights.lights[index];
+        var light_direction = -normalize(light.vector);
+        var radiance = light.color * light.intensity;
+        if (light.kind == light_point) {
+            let offset = light.vector - position;
+            light_direction = normalize(offset);
+            radiance /= max(dot(offset, offset), 1e-4);
+        }
+
+        let transmittance = scattered_transmittance(position, light_direction, subsurface);
+        shaded += direct_light(color, normal, light_direction, view_direction, radiance, subsurface, transmittance);
+    }
+
+    return vec4<f32>(shaded, depth);
+}
+
+// the share of light getting through the sculpt toward a point on its surface, only looked up for materials scattering light
+fn scattered_transmittance(position: vec3<f32>, light_direction: vec3<f32>, subsurface: f32) -> f32 {
+    if (subsurface > 0.0) {
+        return light_transmittance(position, -light_direction);
+    }
+
+    return 0.0;
+}
+
+// the share of light getting through the sculpt from a point on its surface along the light, in the world;
+// the octree is looked up at a few points beneath the surface, and the solid ones block the light
+fn light_transmittance(position: vec3<f32>, direction: vec3<f32>) -> f32 {
+    let origin = (chunk.world_to_object * vec4<f32>(position, 1.0)).xyz;
+    let object_direction = normalize((chunk.world_to_object * vec4<f32>(direction, 0.0)).xyz);
+    let step = chunk.root_size * scatter_depth / f32(scatter_samples);
+
+    var solid = 0u;
+    for (var sample = 0u; sample < scatter_samples; sample += 1u) {
+        let point = origin + object_direction * step * (f32(sample) + 0.5);
+        if (hit_root(point).distance < voxel_size() * 0.5) {
+            solid += 1u;
+        }
+    }
+
+    return 1.0 - f32(solid) / f32(scatter_samples);
+}
+
+// the shading of a surface lit by a white light from a direction, shared by the sculpt and the material strip
+fn blinn_phong(color: vec4<f32>, normal: vec3<f32>, light_direction: vec3<f32>, view_direction: vec3<f32>, depth: f32, subsurface: f32, transmittance: f32) -> vec4<f32> {
+    let direct = direct_light(color, normal, light_direction, view_direction, vec3<f32>(1.0), subsurface, transmittance);
+
+    return vec4<f32>(color.rgb * ambient + direct, depth);
+}
+
+// the light a surface reflects from a single light; light wraps past the edge of what is lit by
+// the strength of the scattering under the surface, and shines through where the sculpt is thin along the light
+fn direct_light(color: vec4<f32>, normal: vec3<f32>, light_direction: vec3<f32>, view_direction: vec3<f32>, radiance: vec3<f32>, subsurface: f32, transmittance: f32) -> vec3<f32> {
     const specular_power = 2.0;
     const gloss = 6.0;
 
-    let light_direction = normalize(vec3<f32>(0.5, 0.5, 0.0) - position);
-    let light_color = vec3<f32>(1.0, 1.0, 1.0);
-    let n_dot_l = saturate(dot(normal, light_direction));
+    let n_dot_l = saturate((dot(normal, light_direction) + subsurface) / (1.0 + subsurface));
     let h = (light_direction + view_direction) / 2.0;
     let n_dot_h = saturate(dot(normal, h));
     let specular = pow(n_dot_h, specular_power) * gloss;
+    // light coming out of the material is tinted deeper by its color on the way
+    let scattered = color.rgb * color.rgb * transmittance * subsurface;
+
+    return (color.rgb * n_dot_l * 0.9 + scattered + specular) * radiance;
+}
+
+// the point at a depth in the middle of the view
+fn view_center(depth: f32) -> vec3<f32> {
+    return view_point(vec3<f32>(0.0, 0.0, depth));
+}
+
+// the point in the world at a point in normalized device coordinates
+fn view_point(ndc: vec3<f32>) -> vec3<f32> {
+    let point = view.inverse_view_projection * vec4<f32>(ndc, 1.0);
 
-    return vec4<f32>(color.rgb * light_color * n_dot_l * 0.9 + color.rgb * 0.1, depth) + specular;
+    return point.xyz / point.w;
 }
@@ -2,6 +2,14 @@ struct Settings {
     resolution: u32,
 }
 
+struct Display {
+    tonemap: u32,
+    exposure: f32,
+    encode_srgb: u32,
+    // from the working space of the document to linear sRGB
+    to_linear_srgb: mat3x3<f32>,
+}
+
 struct VertexInput {
     @builtin(vertex_index) index: u32,
 }
@@ -23,6 +31,48 @@ fn vertex_main(input: VertexInput) -> VertexOutput {
 @group(0) @binding(0) var<uniform> settings: Settings;
 @group(0) @binding(1) var render_sampler: sampler;
 @group(0) @binding(2) var render_texture: texture_2d<f32>;
+@group(0) @binding(3) var<uniform> display: Display;
+
+// the ACES filmic curve, fitted by Krzysztof Narkowicz
+fn aces(color: vec3<f32>) -> vec3<f32> {
+    let x = color * 0.6;
+    return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), vec3(0.0), vec3(1.0));
+}
+
+fn hable(x: vec3<f32>) -> vec3<f32> {
+    return ((x * (0.15 * x + 0.05) + 0.004) / (x * (0.15 * x + 0.5) + 0.06)) - 0.02 / 0.3;
+}
+
+// the Uncharted 2 filmic curve by John Hable, with its white point at 11.2
+fn filmic(color: vec3<f32>) -> vec3<f32> {
+    return clamp(hable(color * 2.0) / hable(vec3(11.2)), vec3(0.0), vec3(1.0));
+}
+
+fn tonemap(color: vec3<f32>) -> vec3<f32> {
+    switch display.tonemap {
+        case 1u: {
+            return aces(color);
+        }
+        case 2u: {
+            return filmic(color);
+        }
+        default: {
+            return clamp(color, vec3(0.0), vec3(1.0));
+        }
+    }
+}
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3(0.0031308));
+}
+
+// a frame, blended into the accumulation with the weight of the blend constant
+@fragment
+fn accumulate_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    return textureLoad(render_texture, vec2<i32>(input.position.xy), 0);
+}
 
 @fragment
 fn fragment_main(input: VertexOutput) -> @location(0) vec4<f32> {
@@ -39,5 +89,11 @@ fn fragment_main(input: VertexOutput) -> @location(0) vec4<f32> {
         }
     }
 
-    return vec4<f32>(total / count, 1.0);
+    let linear = max(display.to_linear_srgb * (total / count), vec3(0.0));
+    var color = tonemap(linear * display.exposure);
+    if (display.encode_srgb != 0u) {
+        color = linear_to_srgb(color);
+    }
+
+    return vec4<f32>(color, 1.0);
 }
@@ -0,0 +1,70 @@
+use glam::{Mat4, Vec3, vec3};
+
+/// An axis-aligned bounding box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+	pub min: Vec3,
+	pub max: Vec3,
+}
+
+impl Aabb {
+	/// Create a bounding box from two corners.
+	pub fn new(a: Vec3, b: Vec3) -> Self {
+		Self {
+			min: a.min(b),
+			max: a.max(b),
+		}
+	}
+
+	/// Create a cube from its center and edge length.
+	pub fn cube(center: Vec3, size: f32) -> Self {
+		Self {
+			min: center - size / 2.0,
+			max: center + size / 2.0,
+		}
+	}
+
+	/// Get the center of the box.
+	pub fn center(&self) -> Vec3 {
+		(self.min + self.max) / 2.0
+	}
+
+	/// Get the edge lengths of the box.
+	pub fn extent(&self) -> Vec3 {
+		self.max - self.min
+	}
+
+	/// Check whether a point is inside of the box.
+	pub fn contains(&self, point: Vec3) -> bool {
+		point.cmpge(self.min).all() && point.cmple(self.max).all()
+	}
+
+	/// Check whether two boxes overlap.
+	pub fn intersects(&self, other: &Aabb) -> bool {
+		self.min.cmple(other.max).all() && other.min.cmple(self.max).all()
+	}
+
+	/// Get the box around the corners of this box, once they are transformed by a matrix.
+	pub fn transform(&self, matrix: Mat4) -> Self {
+		let corners = (0..8).map(|corner| matrix.transform_point3(vec3(
+			if corner & 1 == 0 { self.min.x } else { self.max.x },
+			if corner & 2 == 0 { self.min.y } else { self.max.y },
+			if corner & 4 == 0 { self.min.z } else { self.max.z },
+		)));
+
+		corners.fold(Self { min: Vec3::INFINITY, max: Vec3::NEG_INFINITY }, |bounds, corner| Self {
+			min: bounds.min.min(corner),
+			max: bounds.max.max(corner),
+		})
+	}
+}
+
+impl Default for Aabb {
+	/// The default box is the unit cube.
+	fn default() -> Self {
+		Self {
+			min: Vec3::ZERO,
+			max: Vec3::ONE,
+		}
+	}
+}
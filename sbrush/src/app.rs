@@ -1,16 +1,47 @@
-use crate::editor::Editor;
-use crate::renderer::Renderer;
+use crate::document::DEFAULT_LEVEL;
+use crate::editor::{Editor, FileChange, SaveKind};
+use crate::editor::review::ReviewCapture;
+use crate::editor::soak::SoakFrame;
+use crate::editor::timelapse::TimelapseCapture;
+use crate::error::Error;
+use crate::i18n::{self, tr, tr_args};
+use crate::input::{Action, Binding, InputEvent, InputMap, InputQueue};
+use crate::material::MaterialMix;
+use crate::overlay::{CommandPalette, GuideLine, HelpPane, MaterialStrip, PinMarker, ProfilePane, ProgressPane, SnapGrid, VersionPicker};
+use crate::path_tracer::{Environment, PathTracer};
+use crate::renderer::{AdapterChoice, ChunkId, DisplayMode, DrawMode, PresentMode, RenderQuality, Renderer, Traversal, VisiblePoint};
+use crate::util::backups::{self, Version};
+use crate::util::jobs::{Job, JobPool, Priority};
+use crate::util::profile::{FrameProfiler, Phase};
+use crate::util::progress::Progress;
 
+use std::f32::consts::{LN_2, TAU};
+use std::io;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::{Duration, Instant};
 
-use winit::error::EventLoopError;
+use glam::{Vec2, Vec3, vec2};
 use winit::event_loop::{EventLoop, ControlFlow, ActiveEventLoop};
 use winit::application::ApplicationHandler;
 use winit::dpi::{PhysicalPosition, PhysicalSize};
-use winit::event::{ElementState, MouseButton, WindowEvent};
-use winit::keyboard::KeyCode;
+use winit::event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent};
+use winit::event::KeyEvent;
+use winit::keyboard::{Key, ModifiersState, NamedKey, PhysicalKey};
 use winit::window::{Window, WindowId};
 
+/// How many chunks of opened files are read between frames.
+const CHUNKS_PER_FRAME: usize = 4;
+/// How often the files of the objects are checked for changes by other programs.
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+/// How often objects edited since they were saved are backed up beside their files.
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(120);
+/// The width and height of the thumbnails saved beside versions, in pixels.
+const THUMBNAIL_SIZE: u32 = 96;
+/// How far the showcase turns the view each second, a full turn in 40 seconds.
+const SHOWCASE_TURN: f32 = TAU / 40.0;
+
 /// The main application class.
 ///
 /// A winit application. Manages the window and owns all other resources.
@@ -19,16 +50,961 @@ pub struct App {
     window: Option<Arc<Window>>,
     context: Option<Renderer>,
     cursor_position: PhysicalPosition<f64>,
+    cursor_inside: bool,
+    modifiers: ModifiersState,
+    orbiting: bool,
+    /// The button held down for a stroke, which continues as the cursor moves.
+    stroking: Option<(MouseButton, Action)>,
+    magnified: bool,
+    /// The line typed into the command palette, while it is open.
+    palette: Option<String>,
+    palette_message: Option<String>,
+    /// The saved versions to pick one to restore from, with the selected one, while the picker is open.
+    version_picker: Option<(Vec<Version>, usize)>,
     editor: Editor,
+    /// The beauty render running in the background, which hands back the tracer unless it was cancelled.
+    beauty_render: Option<Job<Option<PathTracer>>>,
+    /// The HDRI beauty renders are lit by, instead of the sky.
+    environment: Option<PathBuf>,
+    last_update: Option<Instant>,
+    input_map: InputMap,
+    /// The inputs which edit the document, waiting for the next simulation tick.
+    input_queue: InputQueue,
+    show_help: bool,
+    show_materials: bool,
+    show_profile: bool,
+    /// The CPU time spent on the last frames.
+    profiler: FrameProfiler,
+    /// Frames which take longer than this on the CPU are logged.
+    slow_frame: Option<Duration>,
+    display_mode: DisplayMode,
+    traversal: Traversal,
+    draw_mode: DrawMode,
+    present_mode: PresentMode,
+    /// The traversal limits of the rays.
+    quality: RenderQuality,
+    /// The GPU adapter to render with.
+    adapter: AdapterChoice,
+    stats_path: Option<String>,
+    jobs: JobPool,
+    /// The long operations shown with a progress bar.
+    tasks: Vec<Progress>,
+    /// The saves running in the background, by the path they save to.
+    saves: Vec<(PathBuf, Job<io::Result<()>>)>,
+    /// Whether the document changed since it was last uploaded.
+    upload_pending: bool,
+    /// When the files of the objects were last checked for changes by other programs.
+    last_watch: Option<Instant>,
+    /// When the edited objects were last backed up.
+    last_autosave: Option<Instant>,
+    /// The surface seen through the last frame, while it is read back to mask what it doesn't show.
+    visible_mask: Option<Receiver<Option<Vec<VisiblePoint>>>>,
+    /// The surface under a point of the last frame, while it is read back to place the brush on.
+    surface_pick: Option<(Vec2, Receiver<Option<VisiblePoint>>)>,
+    /// The timelapse frame to capture after the next frame is drawn.
+    timelapse_capture: Option<TimelapseCapture>,
+    /// Whether the window lost the keyboard focus to another one.
+    unfocused: bool,
+    /// Whether the window is fully covered by others, where the platform tells.
+    occluded: bool,
+    /// Whether the window is minimized, which some platforms only tell by a size of zero.
+    minimized: bool,
+    /// How long without input before the view turns for a showcase, if it ever does.
+    showcase_delay: Option<Duration>,
+    /// When the last input came, which the showcase waits on.
+    last_input: Option<Instant>,
+    /// Whether the view is turning for a showcase, until the next input.
+    showcasing: bool,
+    /// Why the window or the renderer couldn't be created, which ends the event loop.
+    failure: Option<Error>,
 }
 
 impl App {
     /// Run the main event loop.
-    pub fn run() -> Result<(), EventLoopError> {
-        let event_loop = EventLoop::new().unwrap();
+    pub fn run() -> Result<(), Error> {
+        i18n::set_locale(&i18n::system_locale());
+
+        let event_loop = EventLoop::new()?;
         event_loop.set_control_flow(ControlFlow::Poll);
         let mut app = App::default();
-        event_loop.run_app(&mut app)
+        // session statistics are opt-in, and only written locally
+        if let Ok(path) = std::env::var("SWIRLIX_STATS") {
+            app.editor.set_stats_enabled(true);
+            app.stats_path = Some(path);
+        }
+        if let Ok(value) = std::env::var("SWIRLIX_PROFILE") {
+            match value.parse::<f64>() {
+                Ok(milliseconds) if milliseconds >= 0.0 => app.slow_frame = Some(Duration::from_secs_f64(milliseconds / 1000.0)),
+                _ => eprintln!("{}", tr_args("profile-threshold-invalid", &[("value", &value)])),
+            }
+        }
+        if let Ok(value) = std::env::var("SWIRLIX_SHOWCASE") {
+            match value.parse::<f64>() {
+                Ok(seconds) if seconds > 0.0 => app.showcase_delay = Some(Duration::from_secs_f64(seconds)),
+                _ => eprintln!("{}", tr_args("showcase-delay-invalid", &[("value", &value)])),
+            }
+        }
+        if let Ok(name) = std::env::var("SWIRLIX_PRESENT_MODE") {
+            match PresentMode::from_name(&name) {
+                Some(present_mode) => app.present_mode = present_mode,
+                None => eprintln!("{}", tr_args("present-mode-unknown", &[("name", &name)])),
+            }
+        }
+        if let Ok(name) = std::env::var("SWIRLIX_QUALITY") {
+            match RenderQuality::from_name(&name) {
+                Some(quality) => app.quality = quality,
+                None => eprintln!("{}", tr_args("quality-unknown", &[("name", &name)])),
+            }
+        }
+        if let Some(path) = std::env::var_os("SWIRLIX_HDRI") {
+            app.environment = Some(PathBuf::from(path));
+        }
+        if let Ok(value) = std::env::var("SWIRLIX_ADAPTER") {
+            app.adapter = AdapterChoice::parse(&value);
+            let adapters = Renderer::enumerate_adapters();
+            if matches!(app.adapter, AdapterChoice::Index(_) | AdapterChoice::Name(_)) && app.adapter.select(&adapters).is_none() {
+                let names: Vec<String> = adapters.iter().enumerate()
+                    .map(|(index, adapter)| format!("{index}: {} ({:?})", adapter.name, adapter.backend))
+                    .collect();
+                eprintln!("{}", tr_args("adapter-unknown", &[("name", &value), ("adapters", &names.join(", "))]));
+            }
+        }
+        event_loop.run_app(&mut app)?;
+
+        match app.failure {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Create the window and the renderer drawing to it.
+    fn create_window(&mut self, event_loop: &ActiveEventLoop) -> Result<(), Error> {
+        let version = env!("CARGO_PKG_VERSION");
+        let win_attr = Window::default_attributes()
+            .with_title(tr_args("window-title", &[("version", version)]))
+            .with_inner_size(PhysicalSize {
+                width: 1024,
+                height: 1024,
+            });
+        // use Arc
+        let window = Arc::new(event_loop.create_window(win_attr)?);
+        self.window = Some(window.clone());
+        let mut context = Renderer::new(window.clone(), 1024, &self.adapter, self.jobs.clone())?;
+        if self.adapter != AdapterChoice::Default {
+            println!("{}", tr_args("adapter-chosen", &[("name", &context.get_adapter_info().name)]));
+        }
+        self.present_mode = context.set_present_mode(self.present_mode);
+        context.set_quality(self.quality);
+        self.context = Some(context);
+        self.update_view();
+        self.request_upload();
+
+        Ok(())
+    }
+
+    /// Mark the document as changed, for it to be uploaded before the next frame is drawn.
+    fn request_upload(&mut self) {
+        self.upload_pending = true;
+        if let Some(window) = self.window.as_ref() {
+            window.request_redraw();
+        }
+    }
+
+    /// Send the shown objects, as latched at the start of the frame, to the renderer.
+    ///
+    /// Hidden objects have their buffers dropped, until they are shown again.
+    fn upload_document(&mut self) {
+        if !self.upload_pending {
+            return;
+        }
+        // reloaded files can bring a palette of another size
+        let strip = self.material_strip();
+        let Some(context) = self.context.as_mut() else {
+            return;
+        };
+        self.upload_pending = false;
+        let start = Instant::now();
+
+        let frame = self.editor.latch_upload(self.display_mode == DisplayMode::Mask);
+        context.set_material_buffer(frame.get_materials().to_vec());
+        context.set_material_strip(strip);
+        for (object, transforms) in frame.get_transforms() {
+            context.set_object_transforms(*object as u32, transforms.clone());
+        }
+        let mut ids = Vec::with_capacity(frame.get_chunk_count());
+        for page in frame.pages() {
+            let id = ChunkId { object: page.object as u32, coordinate: page.coordinate };
+            context.set_chunk_buffer(id, page.bounds, page.resolution, page.voxels, page.heat, page.paint);
+            ids.push(id);
+        }
+        context.retain_chunks(&ids);
+
+        if self.editor.take_ghost_changed() {
+            context.clear_ghost();
+            if let Some(ghost) = self.editor.get_ghost() {
+                for page in ghost.pages() {
+                    context.set_ghost_chunk(page.coordinate, page.bounds, page.resolution, ghost.get_transform(), page.voxels);
+                }
+            }
+        }
+        self.profiler.record(Phase::Upload, start.elapsed());
+    }
+
+    /// Start the save a command asked for, if any, in the background.
+    ///
+    /// The save writes a snapshot, so sculpting can go on while it runs.
+    fn start_save(&mut self) {
+        let Some(request) = self.editor.take_save_request() else {
+            return;
+        };
+        if let (SaveKind::Version(version), Some(context)) = (&request.kind, self.context.as_ref()) {
+            let path = backups::thumbnail_path(&version.path);
+            let jobs = self.jobs.clone();
+            context.capture(move |capture| save_thumbnail(&jobs, capture, path));
+            // the thumbnail is read back as the device is polled by the next draw
+            if let Some(window) = self.window.as_ref() {
+                window.request_redraw();
+            }
+        }
+        let path = request.get_target().to_path_buf();
+        let message = tr_args("document-saving", &[("path", &path.display().to_string())]);
+        let job = self.jobs.spawn(Priority::High, message, move |progress| request.run(progress));
+        self.tasks.push(job.get_progress().clone());
+        self.saves.push((path, job));
+        self.update_progress();
+    }
+
+    /// Back up the objects edited since they were last saved or backed up, in the background.
+    fn autosave(&mut self) {
+        for request in self.editor.take_autosaves() {
+            self.jobs.spawn(Priority::Low, String::new(), move |progress| {
+                if let Err(error) = request.run(progress) {
+                    eprintln!("{}", tr_args("document-autosave-failed", &[("path", &request.path.display().to_string()), ("error", &error.to_string())]));
+                }
+            });
+        }
+    }
+
+    /// Show the picker of saved versions, if a command asked for it.
+    fn open_version_picker(&mut self) {
+        if !self.editor.take_version_picker_request() {
+            return;
+        }
+        let versions = self.editor.get_versions().unwrap_or_default();
+        if !versions.is_empty() {
+            self.version_picker = Some((versions, 0));
+        }
+        self.update_version_picker();
+    }
+
+    /// Refresh the picker of saved versions, if it is open.
+    fn update_version_picker(&mut self) {
+        let picker = self.version_picker.as_ref()
+            .map(|(versions, selected)| VersionPicker::new(versions, *selected));
+        if let Some(context) = self.context.as_mut() {
+            context.set_version_picker(picker);
+        }
+        if let Some(window) = self.window.as_ref() {
+            window.request_redraw();
+        }
+    }
+
+    /// Pick a version with the keyboard in the open picker, restoring it with Enter.
+    fn version_picker_key(&mut self, event: KeyEvent) {
+        let Some((versions, selected)) = self.version_picker.as_mut() else {
+            return;
+        };
+
+        match event.logical_key {
+            Key::Named(NamedKey::Escape) => self.version_picker = None,
+            Key::Named(NamedKey::ArrowUp) => *selected = selected.saturating_sub(1),
+            Key::Named(NamedKey::ArrowDown) => *selected = (*selected + 1).min(versions.len() - 1),
+            Key::Named(NamedKey::Enter) => {
+                let version = versions[*selected].clone();
+                self.version_picker = None;
+                match self.editor.restore_version(&version) {
+                    Ok(()) => println!("{}", tr_args("versions-restored", &[("time", &version.describe_time())])),
+                    Err(error) => eprintln!("{}", tr_args("versions-restore-failed", &[("error", &error.to_string())])),
+                }
+                self.request_upload();
+                self.update_materials();
+            }
+            _ => (),
+        }
+
+        self.update_version_picker();
+    }
+
+    /// Read back the surface seen through the last frame, if a command asked to mask what it doesn't show.
+    fn start_visible_mask(&mut self) {
+        if !self.editor.take_visible_mask_request() {
+            return;
+        }
+        let (Some(context), Some(window)) = (self.context.as_ref(), self.window.as_ref()) else {
+            return;
+        };
+        let (sender, receiver) = mpsc::channel();
+        context.read_visible(move |points| {
+            let _ = sender.send(points);
+        });
+        self.visible_mask = Some(receiver);
+        // the read finishes as the device is polled by the next draw
+        window.request_redraw();
+    }
+
+    /// Check whether the window can't be seen, so frames aren't drawn until it can.
+    fn is_hidden(&self) -> bool {
+        self.occluded || self.minimized
+    }
+
+    /// Check whether the window is in the background, so the event loop waits for events instead of spinning.
+    fn is_idle(&self) -> bool {
+        self.unfocused || self.is_hidden()
+    }
+
+    /// Draw the frames which were skipped while the window was hidden, once it is back.
+    fn resume_drawing(&mut self, was_hidden: bool) {
+        if was_hidden && !self.is_hidden() {
+            // animations pick up where they were, rather than jumping by the time spent hidden
+            self.last_update = None;
+            if let Some(window) = self.window.as_ref() {
+                window.request_redraw();
+            }
+        }
+    }
+
+    /// Start the showcase once there was no input for long enough, and turn the view while it runs.
+    ///
+    /// Returns whether the showcase is running.
+    fn update_showcase(&mut self, now: Instant, delta: Duration) -> bool {
+        let Some(delay) = self.showcase_delay else {
+            return false;
+        };
+        let last_input = *self.last_input.get_or_insert(now);
+        if !self.showcasing {
+            // a held stroke, an open palette or a soak test is still work going on
+            let working = self.stroking.is_some() || self.palette.is_some() || !self.input_queue.is_empty() || self.editor.is_soaking();
+            if now - last_input < delay || working {
+                return false;
+            }
+            self.showcasing = true;
+            if let Some(context) = self.context.as_mut() {
+                context.set_showcase(true);
+                context.set_brush_preview(None);
+            }
+        }
+        if !self.is_hidden() {
+            // a long wait for events doesn't turn the view by a jump
+            self.editor.turn(SHOWCASE_TURN * delta.as_secs_f32().min(0.1));
+            self.update_view();
+        }
+
+        true
+    }
+
+    /// Note an input, ending the showcase if it is running, so editing picks up from the turned view.
+    fn end_showcase(&mut self) {
+        self.last_input = Some(Instant::now());
+        if !std::mem::take(&mut self.showcasing) {
+            return;
+        }
+        if let (Some(context), Some(window)) = (self.context.as_mut(), self.window.as_ref()) {
+            context.set_showcase(false);
+            window.request_redraw();
+        }
+    }
+
+    /// Hold on to the timelapse frame the editor queued, if any, for it to be captured after the next draw.
+    ///
+    /// The frame is drawn first, so the capture shows the edits which queued it.
+    fn queue_timelapse_capture(&mut self) {
+        let Some(capture) = self.editor.take_timelapse_capture() else {
+            return;
+        };
+        self.timelapse_capture = Some(capture);
+        if let Some(window) = self.window.as_ref() {
+            window.request_redraw();
+        }
+    }
+
+    /// Sample the frame just drawn for the running soak test, if any, and ask for the next one.
+    fn record_soak_frame(&mut self, draw: Duration) {
+        if !self.editor.is_soaking() {
+            return;
+        }
+        let times = self.profiler.get_last().unwrap_or_default();
+        let frame = SoakFrame {
+            cpu: times.get_total(),
+            upload: times.get(Phase::Upload),
+            draw,
+            buffer_bytes: self.context.as_ref().map_or(0, |context| context.get_chunk_buffer_size()),
+        };
+        match self.editor.record_soak_frame(frame) {
+            Some(Ok(path)) => println!("{}", tr_args("soak-finished", &[("path", &path.display().to_string())])),
+            Some(Err(error)) => eprintln!("{}", tr_args("soak-failed", &[("error", &error.to_string())])),
+            None => {
+                if let Some(window) = self.window.as_ref() {
+                    window.request_redraw();
+                }
+            },
+        }
+    }
+
+    /// Capture the queued timelapse frame from the last drawn frame, or from its bookmarked view, and save it in the background.
+    fn capture_timelapse_frame(&mut self) {
+        let Some(context) = self.context.as_mut() else {
+            return;
+        };
+        let Some(TimelapseCapture { path, view }) = self.timelapse_capture.take() else {
+            return;
+        };
+        let jobs = self.jobs.clone();
+        let callback = move |capture| save_capture(&jobs, capture, path, "timelapse-saving", "timelapse-failed");
+        match view {
+            Some(view) => context.capture_from(view, callback),
+            None => context.capture(callback),
+        }
+        // the capture is read back as the device is polled by the next draw
+        if let Some(window) = self.window.as_ref() {
+            window.request_redraw();
+        }
+    }
+
+    /// Render the images of review packages the editor asked for, if any, and save them in the background.
+    fn capture_review_images(&mut self) {
+        let Some(context) = self.context.as_mut() else {
+            return;
+        };
+        let captures = self.editor.take_review_captures();
+        if captures.is_empty() {
+            return;
+        }
+        for ReviewCapture { path, view } in captures {
+            let jobs = self.jobs.clone();
+            context.capture_from(view, move |capture| save_capture(&jobs, capture, path, "review-saving", "review-failed"));
+        }
+        // the images are read back as the device is polled by the next draw
+        if let Some(window) = self.window.as_ref() {
+            window.request_redraw();
+        }
+    }
+
+    /// Mask what the last frame doesn't show, once the surface seen through it has been read back.
+    fn finish_visible_mask(&mut self) {
+        let Some(receiver) = self.visible_mask.as_ref() else {
+            return;
+        };
+        match receiver.try_recv() {
+            Ok(Some(points)) => {
+                let active = self.editor.get_scene().get_active_index() as u32;
+                let points: Vec<(Vec3, Vec3)> = points.iter()
+                    .filter(|point| point.layer == active)
+                    .map(|point| (point.position, point.direction))
+                    .collect();
+                self.editor.mask_visible(&points);
+                self.request_upload();
+            }
+            Ok(None) | Err(TryRecvError::Disconnected) => eprintln!("{}", tr("mask-visible-failed")),
+            Err(TryRecvError::Empty) => {
+                // hidden windows don't draw, so the read finishes once the window is back
+                if let Some(window) = self.window.as_ref().filter(|_| !self.is_hidden()) {
+                    window.request_redraw();
+                }
+                return;
+            }
+        }
+        self.visible_mask = None;
+    }
+
+    /// Refresh the help overlay, if it is shown.
+    fn update_help(&mut self) {
+        let help = self.show_help.then(|| HelpPane::new(&self.input_map, self.editor.get_brush()));
+        if let Some(context) = self.context.as_mut() {
+            context.set_help(help);
+        }
+        if let Some(window) = self.window.as_ref() {
+            window.request_redraw();
+        }
+    }
+
+    /// Refresh the CPU times, if they are shown.
+    ///
+    /// Doesn't ask for a frame, as it is called for every frame drawn.
+    fn update_profile(&mut self) {
+        let profile = self.show_profile.then(|| ProfilePane::new(&self.profiler));
+        if let Some(context) = self.context.as_mut() {
+            context.set_profile(profile);
+        }
+    }
+
+    /// Finish the CPU times of the frame just drawn, logging it if it was slow.
+    fn finish_profile_frame(&mut self) {
+        let times = self.profiler.finish_frame();
+        if self.slow_frame.is_some_and(|slow_frame| times.get_total() > slow_frame) {
+            eprintln!("{}", times.summary());
+        }
+        self.update_profile();
+    }
+
+    /// Get the material strip, if it is shown.
+    fn material_strip(&self) -> Option<MaterialStrip> {
+        let materials = self.editor.get_material_buffer().len();
+
+        self.show_materials.then(|| MaterialStrip::new(materials, self.editor.get_brush().get_material().first))
+    }
+
+    /// Refresh the material strip, if it is shown.
+    fn update_materials(&mut self) {
+        let strip = self.material_strip();
+        if let Some(context) = self.context.as_mut() {
+            context.set_material_strip(strip);
+        }
+        if let Some(window) = self.window.as_ref() {
+            window.request_redraw();
+        }
+    }
+
+    /// Find the material of the sphere of the material strip under the cursor.
+    fn material_under_cursor(&self) -> Option<u32> {
+        let size = self.window.as_ref()?.inner_size();
+        let point = self.screen_fraction(self.cursor_position.x, self.cursor_position.y);
+
+        self.material_strip()?.material_at(point, size.width as f32 / size.height.max(1) as f32)
+    }
+
+    /// Refresh the progress bars of long operations.
+    fn update_progress(&mut self) {
+        let progress = (!self.tasks.is_empty()).then(|| ProgressPane::new(&self.tasks));
+        if let Some(context) = self.context.as_mut() {
+            context.set_progress(progress);
+        }
+        if let Some(window) = self.window.as_ref() {
+            window.request_redraw();
+        }
+    }
+
+    /// Send the editor's view to the renderer.
+    fn update_view(&mut self) {
+        let (Some(context), Some(window)) = (self.context.as_mut(), self.window.as_ref()) else {
+            return;
+        };
+        let size = window.inner_size();
+        self.editor.set_aspect(size.width as f32 / size.height.max(1) as f32);
+
+        let view_projection = self.editor.get_view_projection();
+        context.set_view(view_projection);
+        // pins stay on the sculpt as the view moves
+        let pins = self.editor.get_pins().into_iter()
+            .enumerate()
+            .filter_map(|(index, pin)| PinMarker::project(view_projection, pin.position, format!("{}: {}", index + 1, pin.note)))
+            .collect();
+        context.set_pins(pins);
+        let guides = self.editor.get_guides().iter()
+            .map(|guide| GuideLine::project(view_projection, guide))
+            .collect();
+        context.set_guides(guides);
+        window.request_redraw();
+    }
+
+    /// Get a movement in pixels as a fraction of the window.
+    fn screen_fraction(&self, x: f64, y: f64) -> Vec2 {
+        let size = self.window.as_ref().map(|window| window.inner_size()).unwrap_or_default();
+
+        vec2(x as f32 / size.width.max(1) as f32, y as f32 / size.height.max(1) as f32)
+    }
+
+    /// Orbit, or pan while shift is held, by a movement across the screen.
+    ///
+    /// Mouse drags and trackpad scrolling share this, so they feel the same.
+    fn navigate(&mut self, delta: Vec2) {
+        if self.modifiers.shift_key() {
+            self.editor.pan(delta);
+        } else {
+            self.editor.orbit(delta);
+        }
+        self.update_view();
+    }
+
+    /// Move the brush preview to the cursor, and pick the surface under it if no pick is running.
+    fn update_preview(&mut self) {
+        let point = self.cursor_point();
+        self.show_brush(point);
+        let (Some(context), Some(window)) = (self.context.as_mut(), self.window.as_ref()) else {
+            return;
+        };
+
+        if self.surface_pick.is_none() {
+            let (sender, receiver) = mpsc::channel();
+            context.read_surface(point.x, point.y, move |surface| {
+                let _ = sender.send(surface);
+            });
+            self.surface_pick = Some((point, receiver));
+        }
+        // the pick finishes as the device is polled by the next draw
+        window.request_redraw();
+    }
+
+    /// Place the brush on the surface under the cursor, once it has been read back.
+    ///
+    /// If the cursor moved on while it was read, the surface under it is picked again.
+    fn finish_surface_pick(&mut self) {
+        let Some((point, receiver)) = self.surface_pick.as_ref() else {
+            return;
+        };
+        let surface = match receiver.try_recv() {
+            Ok(surface) => surface,
+            Err(TryRecvError::Disconnected) => None,
+            Err(TryRecvError::Empty) => return,
+        };
+        let point = *point;
+        let moved = point != self.cursor_point();
+        self.surface_pick = None;
+        self.editor.set_picked_surface(surface.map(|surface| surface.position));
+        if !self.cursor_inside {
+            return;
+        }
+
+        if moved {
+            self.update_preview();
+        } else {
+            self.show_brush(point);
+        }
+    }
+
+    /// Show the brush preview where the brush stamps at a point of the window, with the grid it snaps to.
+    fn show_brush(&mut self, point: Vec2) {
+        let Some(context) = self.context.as_mut() else {
+            return;
+        };
+        let grid = self.editor.get_snap_grid(point.x, point.y);
+
+        context.set_brush_preview(Some(self.editor.get_stamp(point.x, point.y)));
+        context.set_snap_grid(SnapGrid::project(self.editor.get_view_projection(), &grid));
+    }
+
+    /// Catch up with whatever a command changed.
+    fn after_command(&mut self) {
+        self.start_save();
+        self.start_visible_mask();
+        self.open_version_picker();
+        self.queue_timelapse_capture();
+        self.warn_node_limit();
+        // commands can change anything
+        self.request_upload();
+        self.update_view();
+        self.update_help();
+        self.update_materials();
+        if self.cursor_inside {
+            self.update_preview();
+        }
+    }
+
+    /// A native file dialog for documents, over the window.
+    fn document_dialog(&self, title: &str) -> rfd::FileDialog {
+        let dialog = rfd::FileDialog::new()
+            .set_title(title)
+            .add_filter(tr("dialog-documents"), &["swx"]);
+        match self.window.as_ref() {
+            Some(window) => dialog.set_parent(window.as_ref()),
+            None => dialog,
+        }
+    }
+
+    /// Ask where to save the active object with a native file dialog, and save it there.
+    fn save_dialog(&mut self) {
+        let name = self.editor.get_scene().get_active().name.clone();
+        let Some(mut path) = self.document_dialog(&tr("dialog-save")).set_file_name(format!("{name}.swx")).save_file() else {
+            return;
+        };
+        if path.extension().is_none() {
+            path.set_extension("swx");
+        }
+
+        self.editor.request_save(path, DEFAULT_LEVEL);
+        self.start_save();
+    }
+
+    /// Pick a document with a native file dialog, and open it as a new object.
+    fn open_dialog(&mut self) {
+        let Some(path) = self.document_dialog(&tr("dialog-open")).pick_file() else {
+            return;
+        };
+
+        match self.editor.open_document(&path) {
+            Ok(index) => println!("{}", tr_args("command-opened", &[("name", &self.editor.get_scene().get_objects()[index].name)])),
+            Err(error) => eprintln!("{}", tr_args("command-open-failed", &[("error", &error.to_string())])),
+        }
+        self.after_command();
+    }
+
+    /// Open the command palette with a line already typed in.
+    fn open_palette(&mut self, line: &str) {
+        self.palette = Some(line.to_owned());
+        self.palette_message = None;
+        self.update_palette();
+    }
+
+    /// Refresh the command palette, if it is open.
+    fn update_palette(&mut self) {
+        let palette = self.palette.as_ref()
+            .map(|input| CommandPalette::new(input, self.editor.get_commands(), self.palette_message.clone()));
+        if let Some(context) = self.context.as_mut() {
+            context.set_palette(palette);
+        }
+        if let Some(window) = self.window.as_ref() {
+            window.request_redraw();
+        }
+    }
+
+    /// Type into the open command palette.
+    fn palette_key(&mut self, event: KeyEvent) {
+        let Some(input) = self.palette.as_mut() else {
+            return;
+        };
+
+        match event.logical_key {
+            Key::Named(NamedKey::Escape) => self.palette = None,
+            Key::Named(NamedKey::Backspace) => {
+                input.pop();
+            }
+            Key::Named(NamedKey::Tab) => {
+                if let Some(command) = self.editor.get_commands().suggest(input).first() {
+                    if command.name.starts_with(input.trim()) {
+                        *input = format!("{} ", command.name);
+                    }
+                }
+            }
+            Key::Named(NamedKey::Enter) => {
+                let line = input.clone();
+                match self.editor.run_command(&line) {
+                    Ok(message) => {
+                        if !message.is_empty() {
+                            println!("{message}");
+                        }
+                        self.palette = None;
+                        self.palette_message = None;
+                    }
+                    Err(error) => self.palette_message = Some(error.to_string()),
+                }
+                self.after_command();
+            }
+            _ => {
+                if let Some(text) = event.text.as_ref() {
+                    input.extend(text.chars().filter(|character| !character.is_control()));
+                }
+            }
+        }
+
+        self.update_palette();
+    }
+
+    /// Get the cursor position as a fraction of the window.
+    fn cursor_point(&self) -> Vec2 {
+        self.screen_fraction(self.cursor_position.x, self.cursor_position.y)
+    }
+
+    /// Handle the inputs queued since the last simulation tick, in order.
+    fn simulate_tick(&mut self) {
+        let events: Vec<InputEvent> = self.input_queue.drain().collect();
+        for event in events {
+            match event {
+                InputEvent::Press(action, point) => self.perform(action, point),
+                InputEvent::BeginStroke(button, action, point) => {
+                    // passes of one stroke share its accumulation limit
+                    self.editor.begin_stroke();
+                    self.stroking = Some((button, action));
+                    self.perform(action, point);
+                }
+                InputEvent::Move(point) => {
+                    if let Some((_, action)) = self.stroking {
+                        self.perform(action, point);
+                    }
+                }
+                InputEvent::Release(button) => {
+                    if self.stroking.is_some_and(|(held, _)| held == button) {
+                        self.editor.end_stroke();
+                        self.stroking = None;
+                        self.queue_timelapse_capture();
+                    }
+                }
+            }
+        }
+        self.warn_node_limit();
+    }
+
+    /// Warn that strokes were refined less deeply to stay within the node limit, if they were.
+    fn warn_node_limit(&mut self) {
+        if let Some(depth) = self.editor.take_node_limit_warning() {
+            let limit = self.editor.get_node_limit().unwrap_or_default();
+            eprintln!("{}", tr_args("node-limit-reached", &[("limit", &limit.to_string()), ("depth", &depth.to_string())]));
+        }
+    }
+
+    /// Carry out a bound action, with the cursor at a point given as a fraction of the window.
+    fn perform(&mut self, action: Action, point: Vec2) {
+        let Vec2 { x, y } = point;
+
+        match action {
+            Action::Add | Action::Remove if self.editor.is_drawing_guides() => {
+                if action == Action::Add {
+                    self.editor.add(x, y);
+                } else {
+                    self.editor.remove(x, y);
+                }
+                // guides are drawn over the view, leaving the voxels alone
+                self.update_view();
+            }
+            Action::Add => {
+                self.editor.add(x, y);
+                self.request_upload();
+            }
+            Action::Remove => {
+                self.editor.remove(x, y);
+                self.request_upload();
+            }
+            Action::RoundBrush => { self.editor.set_brush_by_name("round"); }
+            Action::SquareBrush => { self.editor.set_brush_by_name("square"); }
+            Action::SprayBrush => { self.editor.set_brush_by_name("spray"); }
+            Action::VoxelPen => { self.editor.set_brush_by_name("pen"); }
+            Action::BrushSlot(slot) => { self.editor.select_brush_slot(slot); }
+            Action::LastBrush => self.editor.toggle_last_brush(),
+            Action::ToggleSmoothing => self.editor.toggle_smoothing(),
+            Action::TogglePaint => self.editor.set_painting(!self.editor.is_painting()),
+            Action::ToggleGuides => self.editor.set_drawing_guides(!self.editor.is_drawing_guides()),
+            Action::BeautyRender => {
+                if self.beauty_render.is_none() {
+                    println!("{}", tr("beauty-render-started"));
+                    let mut beauty_render = PathTracer::new(1024, 1024, 64, 4);
+                    beauty_render.set_color_management(self.editor.get_color_management());
+                    let environment = self.environment.clone();
+                    // the render traces a copy of the scene, which shares the chunks until they are edited
+                    let scene = self.editor.get_scene().clone();
+                    let job = self.jobs.spawn(Priority::Normal, tr("beauty-render-progress"), move |progress| {
+                        if let Some(path) = environment {
+                            match Environment::load(&path) {
+                                Ok(environment) => beauty_render.set_environment(environment),
+                                Err(error) => eprintln!("{}", tr_args("beauty-render-environment-failed", &[("path", &path.display().to_string()), ("error", &error.to_string())])),
+                            }
+                        }
+                        beauty_render.set_progress(progress.clone());
+                        beauty_render.render(&scene);
+
+                        beauty_render.is_complete().then_some(beauty_render)
+                    });
+                    self.tasks.push(job.get_progress().clone());
+                    self.beauty_render = Some(job);
+                }
+            }
+            Action::Screenshot => {
+                if let Some(context) = self.context.as_ref() {
+                    let jobs = self.jobs.clone();
+                    context.capture(move |capture| {
+                        // encoding the image is left to a job, as the callback runs while polling the device
+                        jobs.spawn(Priority::High, tr("screenshot-saving"), move |_| {
+                            let result = match capture {
+                                Some(capture) => capture.save("screenshot.png").map_err(|error| error.to_string()),
+                                None => Err(tr("screenshot-unavailable")),
+                            };
+                            match result {
+                                Ok(()) => println!("{}", tr_args("screenshot-saved", &[("path", "screenshot.png")])),
+                                Err(error) => eprintln!("{}", tr_args("screenshot-failed", &[("error", &error)])),
+                            }
+                        });
+                    });
+                }
+            }
+            Action::ToggleHelp => self.show_help = !self.show_help,
+            Action::ToggleMaterials => self.show_materials = !self.show_materials,
+            Action::ToggleProfile => {
+                self.show_profile = !self.show_profile;
+                self.update_profile();
+            }
+            Action::Cancel => {
+                for task in &self.tasks {
+                    task.cancel();
+                }
+            }
+            Action::Undo => {
+                if self.editor.undo() {
+                    self.request_upload();
+                }
+            }
+            Action::Redo => {
+                if self.editor.redo() {
+                    self.request_upload();
+                }
+            }
+            Action::CommandPalette => self.open_palette(""),
+            Action::Save => {
+                let path = self.editor.get_scene().get_active().file.as_ref().map(|file| file.watch.get_path().to_path_buf());
+                match path {
+                    Some(path) => {
+                        self.editor.request_save(path, DEFAULT_LEVEL);
+                        self.start_save();
+                    }
+                    // without a file yet, a native dialog asks for one
+                    None => self.save_dialog(),
+                }
+            }
+            Action::Open => self.open_dialog(),
+            Action::ToggleHeat | Action::ToggleMask => {
+                let toggled = if action == Action::ToggleHeat { DisplayMode::Heat } else { DisplayMode::Mask };
+                self.display_mode = if self.display_mode == toggled { DisplayMode::Shaded } else { toggled };
+                if let Some(context) = self.context.as_mut() {
+                    context.set_display_mode(self.display_mode);
+                }
+                // the chunks carry the channel the display mode shows
+                self.request_upload();
+            }
+            Action::ToggleTraversal => {
+                self.traversal = match self.traversal {
+                    Traversal::Exact => Traversal::Descent,
+                    Traversal::Descent => Traversal::Exact,
+                };
+                if let Some(context) = self.context.as_mut() {
+                    context.set_traversal(self.traversal);
+                }
+            }
+            Action::ToggleMesh => {
+                self.draw_mode = match self.draw_mode {
+                    DrawMode::RayMarched => DrawMode::Mesh,
+                    DrawMode::Mesh => DrawMode::Hybrid,
+                    DrawMode::Hybrid => DrawMode::RayMarched,
+                };
+                if let Some(context) = self.context.as_mut() {
+                    context.set_draw_mode(self.draw_mode);
+                }
+            }
+            Action::CyclePresentMode => {
+                if let Some(context) = self.context.as_mut() {
+                    let modes = [PresentMode::Fifo, PresentMode::Mailbox, PresentMode::Immediate];
+                    let current = modes.iter().position(|mode| *mode == self.present_mode).unwrap_or(0);
+                    // skip modes the surface can't present with, which fall back to fifo
+                    self.present_mode = (1..=modes.len())
+                        .map(|offset| modes[(current + offset) % modes.len()])
+                        .find(|mode| context.set_present_mode(*mode) == *mode)
+                        .unwrap_or_default();
+                    println!("{}", tr_args("present-mode-changed", &[("name", self.present_mode.name())]));
+                }
+            }
+            Action::CycleTonemap => {
+                let mut color = self.editor.get_color_management();
+                color.tonemap = color.tonemap.next();
+                self.editor.set_color_management(color);
+            }
+            Action::IncreaseExposure | Action::DecreaseExposure => {
+                let mut color = self.editor.get_color_management();
+                color.exposure += if action == Action::IncreaseExposure { 0.5 } else { -0.5 };
+                self.editor.set_color_management(color);
+            }
+        }
+
+        self.update_help();
+        self.update_materials();
+        if self.cursor_inside {
+            // the brush may have changed
+            self.update_preview();
+        }
     }
 }
 
@@ -36,98 +1012,386 @@ impl ApplicationHandler for App {
     /// Start or resume the application.
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         if self.window.is_none() {
-            let version = env!("CARGO_PKG_VERSION");
-            let win_attr = Window::default_attributes()
-                .with_title(format!("Swirlix {version}"))
-                .with_inner_size(PhysicalSize {
-                    width: 1024,
-                    height: 1024,
-                });
-            // use Arc
-            let window = Arc::new(
-                event_loop
-                    .create_window(win_attr)
-                    .expect("Could not create the window."),
-            );
-            self.window = Some(window.clone());
-            let context = Renderer::new(window.clone(), 1024);
-            self.context = Some(context);
-        }
-    }
-
-    /// Handle window events.
+            if let Err(error) = self.create_window(event_loop) {
+                // the error is returned by run, once the loop has ended
+                self.failure = Some(error);
+                event_loop.exit();
+            }
+        }
+    }
+
+    /// Handle window events, timing all but drawing.
     fn window_event(
         &mut self,
         event_loop: &ActiveEventLoop,
         _window_id: WindowId,
         event: WindowEvent,
     ) {
+        let drawing = matches!(event, WindowEvent::RedrawRequested);
+        let start = Instant::now();
+        let input = matches!(event,
+            WindowEvent::KeyboardInput { .. } | WindowEvent::CursorMoved { .. } | WindowEvent::MouseInput { .. }
+            | WindowEvent::MouseWheel { .. } | WindowEvent::PinchGesture { .. } | WindowEvent::RotationGesture { .. }
+            | WindowEvent::PanGesture { .. } | WindowEvent::DoubleTapGesture { .. } | WindowEvent::Touch(_));
+        if input {
+            self.end_showcase();
+        }
+        self.handle_window_event(event_loop, event);
+        if !drawing {
+            self.profiler.record(Phase::Events, start.elapsed());
+        }
+    }
+
+    /// Progress any background work between frames.
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        let now = Instant::now();
+        let delta = now - self.last_update.unwrap_or(now);
+        self.last_update = Some(now);
+        // inputs are handled at a fixed tick, so strokes are sampled alike however fast frames come
+        if self.input_queue.advance(delta) > 0 {
+            self.simulate_tick();
+            self.profiler.record(Phase::Sculpt, now.elapsed());
+        }
+        let animating = self.editor.animate(delta);
+        if animating {
+            self.update_view();
+        }
+        let showcasing = self.update_showcase(now, delta);
+
+        // opened files are read a few chunks at a time, so sculpting can start right away
+        let start = Instant::now();
+        let loaded = self.editor.load_saved_chunks(CHUNKS_PER_FRAME);
+        self.profiler.record(Phase::Serialization, start.elapsed());
+        let loading = !matches!(loaded, Ok(0));
+        match loaded {
+            Ok(0) => (),
+            Ok(_) => self.request_upload(),
+            Err(error) => {
+                eprintln!("{}", tr_args("document-load-failed", &[("error", &error.to_string())]));
+                self.request_upload();
+            },
+        }
+
+        if self.beauty_render.as_mut().is_some_and(|job| job.is_done()) {
+            match self.beauty_render.take().and_then(Job::wait).flatten() {
+                Some(beauty_render) => {
+                    let job = self.jobs.spawn(Priority::Normal, tr("beauty-render-saving"), move |_| match beauty_render.save("render.png") {
+                        Ok(()) => println!("{}", tr_args("beauty-render-saved", &[("path", "render.png")])),
+                        Err(error) => eprintln!("{}", tr_args("beauty-render-failed", &[("error", &error.to_string())])),
+                    });
+                    self.tasks.push(job.get_progress().clone());
+                },
+                // the render only traced a copy of the scene, so dropping it leaves nothing half done
+                None => println!("{}", tr("beauty-render-cancelled")),
+            }
+        }
+
+        self.finish_visible_mask();
+        self.finish_surface_pick();
+
+        let editor = &mut self.editor;
+        self.saves.retain_mut(|(path, job)| {
+            let name = path.display().to_string();
+            match job.try_take() {
+                Some(Ok(())) => {
+                    editor.acknowledge_save(path);
+                    println!("{}", tr_args("document-saved", &[("path", &name)]));
+                },
+                Some(Err(error)) => eprintln!("{}", tr_args("document-save-failed", &[("path", &name), ("error", &error.to_string())])),
+                None => return !job.is_done(),
+            }
+
+            false
+        });
+
+        // edited objects are backed up beside their files now and then
+        if self.last_autosave.is_none_or(|saved| now - saved >= AUTOSAVE_INTERVAL) {
+            self.last_autosave = Some(now);
+            self.autosave();
+        }
+
+        // files aren't checked while this editor is writing one, so its own saves aren't taken for changes
+        if self.saves.is_empty() && self.last_watch.is_none_or(|watched| now - watched >= WATCH_INTERVAL) {
+            self.last_watch = Some(now);
+            let start = Instant::now();
+            let changes = self.editor.check_files();
+            self.profiler.record(Phase::Serialization, start.elapsed());
+            let mut reloaded = false;
+            for change in changes {
+                match change {
+                    FileChange::Reloaded(name) => {
+                        println!("{}", tr_args("document-reloaded", &[("name", &name)]));
+                        reloaded = true;
+                    },
+                    FileChange::Conflict(name) => println!("{}", tr_args("document-changed", &[("name", &name)])),
+                    FileChange::Failed(name, error) => eprintln!("{}", tr_args("document-reload-failed", &[("name", &name), ("error", &error)])),
+                }
+            }
+            if reloaded {
+                self.request_upload();
+            }
+        }
+
+        // the hybrid draw mode keeps drawing a still view, until its frames are all accumulated
+        let refining = self.context.as_ref().is_some_and(|context| context.is_refining()) && !self.is_hidden();
+        if refining {
+            if let Some(window) = self.window.as_ref() {
+                window.request_redraw();
+            }
+        }
+
+        let running = self.tasks.len();
+        self.tasks.retain(|task| !task.is_finished());
+        if !self.tasks.is_empty() || self.tasks.len() != running {
+            self.update_progress();
+        }
+
+        // in the background, the loop only spins for work which is still going,
+        // and otherwise wakes up for events, or to check the files again
+        let busy = animating || refining || !self.input_queue.is_empty() || loading || self.beauty_render.is_some() || !self.tasks.is_empty() || !self.saves.is_empty() || self.editor.is_soaking() || showcasing;
+        if self.is_idle() && !busy {
+            event_loop.set_control_flow(ControlFlow::WaitUntil(now + WATCH_INTERVAL));
+        } else {
+            event_loop.set_control_flow(ControlFlow::Poll);
+        }
+    }
+}
+
+impl App {
+    /// Handle a window event.
+    fn handle_window_event(&mut self, event_loop: &ActiveEventLoop, event: WindowEvent) {
         match event {
             WindowEvent::CloseRequested => {
                 // drop the context to avoid segfault at close
                 self.context = None;
+                // the job pool finishes the save before the app is dropped
+                if let (Some(stats), Some(path)) = (self.editor.session_stats(), self.stats_path.clone()) {
+                    let stats = stats.clone();
+                    self.jobs.spawn(Priority::High, String::new(), move |_| {
+                        if let Err(error) = stats.save(path) {
+                            eprintln!("{}", tr_args("stats-save-failed", &[("error", &error.to_string())]));
+                        }
+                    });
+                }
                 event_loop.exit();
             }
             WindowEvent::Resized(new_size) => {
+                let was_hidden = self.is_hidden();
+                self.minimized = new_size.width == 0 || new_size.height == 0;
                 if let (Some(context), Some(window)) =
                     (self.context.as_mut(), self.window.as_ref())
                 {
                     context.resize((new_size.width, new_size.height));
-                    window.request_redraw();
                 }
+                self.update_view();
+                self.resume_drawing(was_hidden);
+            }
+            WindowEvent::Focused(focused) => self.unfocused = !focused,
+            WindowEvent::Occluded(occluded) => {
+                let was_hidden = self.is_hidden();
+                self.occluded = occluded;
+                self.resume_drawing(was_hidden);
             }
+            // edits stay pending while the window is hidden, and are drawn together once it is back
+            WindowEvent::RedrawRequested if self.is_hidden() => (),
             WindowEvent::RedrawRequested => {
+                // a soak test applies its strokes once per frame, right before they are uploaded
+                if self.editor.is_soaking() {
+                    self.editor.soak_strokes();
+                    self.upload_pending = true;
+                }
+                // the edits since the last frame are uploaded together, from one latch
+                self.upload_document();
+                let start = Instant::now();
                 if let (Some(context), Some(window)) = (self.context.as_mut(), self.window.as_ref()) {
-                    context.draw();
+                    // the active object's color management is followed as objects are switched and opened
+                    context.set_color_management(&self.editor.get_color_management());
+                    context.set_lights(self.editor.get_lights());
+                    if !context.draw() {
+                        // skipped frames are drawn again once the surface is ready
+                        window.request_redraw();
+                        return;
+                    }
                 }
+                let draw = start.elapsed();
+                self.finish_profile_frame();
+                self.record_soak_frame(draw);
+                self.capture_timelapse_frame();
+                self.capture_review_images();
             }
             WindowEvent::CursorMoved {
                 device_id: _,
                 position,
             } => {
+                if self.orbiting {
+                    let delta = self.screen_fraction(position.x - self.cursor_position.x, position.y - self.cursor_position.y);
+                    self.navigate(delta);
+                }
                 self.cursor_position = position;
+                self.cursor_inside = true;
+                self.input_queue.push(InputEvent::Move(self.cursor_point()));
+                self.update_preview();
+            }
+            WindowEvent::CursorLeft { device_id: _ } => {
+                self.cursor_inside = false;
+                if let (Some(context), Some(window)) = (self.context.as_mut(), self.window.as_ref()) {
+                    context.set_brush_preview(None);
+                    context.set_snap_grid(SnapGrid::default());
+                    window.request_redraw();
+                }
             }
+            // the open version picker takes the keys which move through it
             WindowEvent::KeyboardInput {
                 device_id: _,
                 event,
                 is_synthetic: _,
-            } => {
-                if event.physical_key == KeyCode::KeyR {
-                    self.editor.set_brush(0);
-                }
-                if event.physical_key == KeyCode::KeyS {
-                    self.editor.set_brush(1);
+            } if self.version_picker.is_some() && event.state == ElementState::Pressed => {
+                self.version_picker_key(event);
+            }
+            // the open palette takes all typing, including held keys
+            WindowEvent::KeyboardInput {
+                device_id: _,
+                event,
+                is_synthetic: _,
+            } if self.palette.is_some() && event.state == ElementState::Pressed => {
+                self.palette_key(event);
+            }
+            WindowEvent::KeyboardInput {
+                device_id: _,
+                event,
+                is_synthetic: _,
+            } if event.state == ElementState::Pressed && !event.repeat => {
+                if let PhysicalKey::Code(key) = event.physical_key {
+                    if let Some(action) = self.input_map.get_key_action(self.modifiers, key) {
+                        self.input_queue.push(InputEvent::Press(action, self.cursor_point()));
+                    }
                 }
             }
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers.state();
+                // holding alt keeps strokes to the stroke lock
+                self.editor.set_stroke_locked(self.modifiers.alt_key());
+            }
+            // the middle button is kept for navigating
             WindowEvent::MouseInput {
                 device_id: _,
                 state,
-                button,
+                button: MouseButton::Middle,
             } => {
-                // left click = add
-                if state == ElementState::Pressed && button == MouseButton::Left {
-                    let size = self.window.as_ref().unwrap().inner_size();
-                    // remap x/y values from pixel to 0-1 for now...
-                    self.editor.add((self.cursor_position.x / size.width as f64) as f32, (self.cursor_position.y / size.height as f64) as f32);
-                    self.context.as_mut().unwrap().set_material_buffer(self.editor.get_material_buffer());
-                    self.context.as_mut().unwrap().set_voxel_buffer(self.editor.get_voxel_buffer());
-                    if let Some(window) = self.window.as_ref() {
-                        window.request_redraw();
+                self.orbiting = state.is_pressed();
+            }
+            WindowEvent::MouseWheel {
+                device_id: _,
+                delta,
+                phase: _,
+            } => {
+                match delta {
+                    MouseScrollDelta::LineDelta(_, lines) => {
+                        self.editor.zoom(lines * 0.1);
+                        self.update_view();
                     }
-                }
-                // right click = remove
-                if state == ElementState::Pressed && button == MouseButton::Right {
-                    let size = self.window.as_ref().unwrap().inner_size();
-                    // remap x/y values from pixel to 0-1 for now...
-                    self.editor.remove((self.cursor_position.x / size.width as f64) as f32, (self.cursor_position.y / size.height as f64) as f32);
-                    self.context.as_mut().unwrap().set_material_buffer(self.editor.get_material_buffer());
-                    self.context.as_mut().unwrap().set_voxel_buffer(self.editor.get_voxel_buffer());
-                    if let Some(window) = self.window.as_ref() {
-                        window.request_redraw();
+                    // two finger scrolling on a trackpad
+                    MouseScrollDelta::PixelDelta(pixels) => {
+                        let delta = self.screen_fraction(pixels.x, pixels.y);
+                        if self.modifiers.shift_key() {
+                            self.editor.orbit(delta);
+                        } else {
+                            self.editor.pan(delta);
+                        }
+                        self.update_view();
                     }
                 }
             }
+            WindowEvent::PinchGesture {
+                device_id: _,
+                delta,
+                phase: _,
+            } => {
+                self.editor.zoom(delta as f32);
+                self.update_view();
+            }
+            WindowEvent::RotationGesture {
+                device_id: _,
+                delta,
+                phase: _,
+            } => {
+                self.editor.turn(delta.to_radians());
+                self.update_view();
+            }
+            WindowEvent::PanGesture {
+                device_id: _,
+                delta,
+                phase: _,
+            } => {
+                let delta = self.screen_fraction(delta.x as f64, delta.y as f64);
+                self.editor.pan(delta);
+                self.update_view();
+            }
+            // smart magnify toggles a closer look
+            WindowEvent::DoubleTapGesture { device_id: _ } => {
+                self.magnified = !self.magnified;
+                self.editor.zoom(if self.magnified { LN_2 } else { -LN_2 });
+                self.update_view();
+            }
+            // clicking a sphere of the material strip picks the brush material instead of stroking
+            WindowEvent::MouseInput {
+                device_id: _,
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+            } if self.material_under_cursor().is_some() => {
+                // the strip only shows materials of the palette
+                if let Some(Ok(())) = self.material_under_cursor().map(|material| self.editor.set_brush_material(MaterialMix::single(material))) {
+                    self.update_materials();
+                }
+            }
+            WindowEvent::MouseInput {
+                device_id: _,
+                state: ElementState::Pressed,
+                button,
+            } => {
+                if let Some(action) = self.input_map.get_action(Binding::Mouse(button)) {
+                    let event = if matches!(action, Action::Add | Action::Remove) {
+                        InputEvent::BeginStroke(button, action, self.cursor_point())
+                    } else {
+                        InputEvent::Press(action, self.cursor_point())
+                    };
+                    self.input_queue.push(event);
+                }
+            }
+            WindowEvent::MouseInput {
+                device_id: _,
+                state: ElementState::Released,
+                button,
+            } => self.input_queue.push(InputEvent::Release(button)),
             _ => (),
         }
     }
 }
+
+/// Save a small copy of an image read back from the surface in the background, to show beside a saved version.
+fn save_thumbnail(jobs: &JobPool, capture: Option<image::RgbaImage>, path: PathBuf) {
+    jobs.spawn(Priority::Low, String::new(), move |_| {
+        let result = match capture {
+            Some(capture) => image::imageops::thumbnail(&capture, THUMBNAIL_SIZE, THUMBNAIL_SIZE).save(&path).map_err(|error| error.to_string()),
+            None => Err(tr("screenshot-unavailable")),
+        };
+        if let Err(error) = result {
+            eprintln!("{}", tr_args("thumbnail-failed", &[("path", &path.display().to_string()), ("error", &error)]));
+        }
+    });
+}
+
+/// Save an image read back from the surface in the background, reporting with the messages of the given keys.
+fn save_capture(jobs: &JobPool, capture: Option<image::RgbaImage>, path: PathBuf, saving: &str, failed: &'static str) {
+    // encoding the image is left to a job, as the callback runs while polling the device
+    let message = tr_args(saving, &[("path", &path.display().to_string())]);
+    jobs.spawn(Priority::Low, message, move |_| {
+        let result = match capture {
+            Some(capture) => capture.save(&path).map_err(|error| error.to_string()),
+            None => Err(tr("screenshot-unavailable")),
+        };
+        if let Err(error) = result {
+            eprintln!("{}", tr_args(failed, &[("path", &path.display().to_string()), ("error", &error)]));
+        }
+    });
+}
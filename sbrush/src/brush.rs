@@ -1,12 +1,131 @@
-use crate::sculpt::Sculpt;
+//! Brushes for sculpting, each a tip applied with its dynamics.
+//!
+//! The tips shape what a stroke stamps, the dynamics how strongly and
+//! softly it applies, and the registry holds the brushes the editor picks
+//! from, starting out with the presets.
+
+pub mod dynamics;
+pub mod presets;
+pub mod registry;
+pub mod tips;
+
+pub use dynamics::Dynamics;
+pub use registry::BrushRegistry;
+pub use tips::{Draw, RoundBrushTip, SprayBrushTip, SquareBrushTip, VoxelPenTip};
+
+use crate::aabb::Aabb;
+use crate::document::Document;
+use crate::material::MaterialMix;
+use crate::rng::Rng;
+use crate::sculpt::{self, Coverage};
+
+use glam::Vec3;
+
+/// How much the edit heat of a voxel fades with each stroke.
+pub const HEAT_DECAY: f32 = 0.9;
+
+/// How far apart a stroke stamps the brush, relative to the radius of its tip.
+pub const STROKE_SPACING: f32 = 0.25;
+
+/// The most stamps a stroke fills in between two cursor samples, so a jump across the sculpt can't stall it.
+const MAX_STROKE_STAMPS: usize = 256;
+
+/// The shape of a brush tip, as an analytic distance field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TipShape {
+	Sphere,
+	Cube,
+}
+
+/// The volume a stroke would stamp.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stamp {
+	pub shape: TipShape,
+	pub center: Vec3,
+	/// The radius of a sphere or half the side of a cube.
+	pub size: f32,
+}
 
-use glam::{Vec3, vec3};
+/// The settings of a brush which are saved with a document, apart from its tip.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BrushSettings {
+	/// The size relative to a chunk.
+	pub size: f32,
+	pub dynamics: Dynamics,
+	pub material: MaterialMix,
+}
+
+/// The settings of every brush saved with a document, by id, and which one was in use.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SavedBrushes {
+	pub current: String,
+	pub brushes: Vec<(String, BrushSettings)>,
+}
+
+/// The path of a stroke while its button is held.
+///
+/// Cursor samples come as often as the mouse moves, which leaves gaps
+/// between the stamps of a fast stroke. The path fills them in, stamping
+/// at even steps along the way from where it last stamped.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Stroke {
+	/// Where the stroke last stamped, once it has.
+	last: Option<Vec3>,
+}
+
+impl Stroke {
+	/// Start a stroke which hasn't stamped yet.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Get where to stamp on the way to the next cursor sample, a spacing apart.
+	///
+	/// The first sample is stamped where it is. Later ones are stamped once
+	/// the cursor moved a spacing away, with the steps between filled in, and
+	/// the rest of the way is carried over to the next sample.
+	pub fn follow(&mut self, position: Vec3, spacing: f32) -> Vec<Vec3> {
+		let Some(last) = self.last else {
+			self.last = Some(position);
+			return vec![position];
+		};
+		let distance = last.distance(position);
+		if spacing <= 0.0 || distance <= 0.0 {
+			return Vec::new();
+		}
+		// a sample landing a hair short of a step still reaches it
+		let mut steps = (distance / spacing + 1e-4) as usize;
+		let mut spacing = spacing;
+		if steps > MAX_STROKE_STAMPS {
+			steps = MAX_STROKE_STAMPS;
+			spacing = distance / steps as f32;
+		}
+		let direction = (position - last) / distance;
+		let stamps: Vec<Vec3> = (1..=steps)
+			.map(|step| last + direction * (step as f32 * spacing))
+			.collect();
+		if let Some(stamp) = stamps.last() {
+			self.last = Some(*stamp);
+		}
+
+		stamps
+	}
+
+	/// Check whether the stroke has stamped yet.
+	pub fn is_started(&self) -> bool {
+		self.last.is_some()
+	}
+}
 
 /// A brush for sculpting.
 pub struct Brush {
 	pub name: String,
 	tip: Box<dyn Draw>,
 	size: f32,
+	dynamics: Dynamics,
+	/// The materials added voxels are filled with.
+	material: MaterialMix,
+	rng: Rng,
 }
 
 impl Brush {
@@ -16,258 +135,334 @@ impl Brush {
 			name,
 			tip,
 			size: 0.1,
+			dynamics: Dynamics::default(),
+			material: MaterialMix::default(),
+			rng: Rng::new(1),
 		}
 	}
 
-	/// Sculpt by adding geometry.
-	pub fn add(&self, sculpt: &mut Sculpt, x: f32, y: f32) {
-		self.tip.add(sculpt, x, y, self.size);
+	/// Get the size of the brush relative to a chunk.
+	pub fn get_size(&self) -> f32 {
+		self.size
 	}
 
-    /// Sculpt by removing geometry.
-	pub fn remove(&self, sculpt: &mut Sculpt, x: f32, y: f32) {
-		self.tip.remove(sculpt, x, y, self.size);
+	/// Set the size of the brush relative to a chunk.
+	pub fn set_size(&mut self, size: f32) {
+		self.size = size;
 	}
-}
 
-pub trait Draw {
-	/// Sculpt by adding geometry.
-	fn add(&self, sculpt: &mut Sculpt, x: f32, y: f32, size: f32);
+	/// Get the shape stamped by the tip.
+	pub fn get_shape(&self) -> TipShape {
+		self.tip.shape()
+	}
 
-	/// Sculpt by removing geometry.
-	fn remove(&self, sculpt: &mut Sculpt, x: f32, y: f32, size: f32);
-}
+	/// Get the smoothing iterations run after each stroke.
+	pub fn get_smoothing(&self) -> u32 {
+		self.dynamics.smoothing
+	}
 
-/// A brush tip for drawing spherical shapes.
-pub struct RoundBrushTip {}
+	/// Set the smoothing iterations run after each stroke (0 disables it).
+	pub fn set_smoothing(&mut self, iterations: u32) {
+		self.dynamics.smoothing = iterations;
+	}
 
-impl RoundBrushTip {
-	/// Create a new round brush tip/effector.
-	pub fn new() -> Self {
-		Self {}
+	/// Get how strongly the brush applies, from 0 to 1.
+	pub fn get_strength(&self) -> f32 {
+		self.dynamics.strength
 	}
 
-	/// Function for implicitly defining a spherical shape for the brush.
-	pub fn filler(brush_size: f32, brush_position: Vec3) -> Box<dyn Fn (f32, Vec3) -> bool> {
-		Box::new(move |size: f32, center: Vec3| {
-			let half_size = size / 2.0;
-			let low_point = vec3(center.x - half_size, center.y - half_size, center.z - half_size);
-			let high_point = vec3(center.x + half_size, center.y + half_size, center.z + half_size);
-			let mut dist_squared = brush_size.powi(2);
-			if brush_position.x < low_point.x {
-				dist_squared -= (brush_position.x - low_point.x).powi(2);
-			} else if brush_position.x > high_point.x {
-				dist_squared -= (brush_position.x - high_point.x).powi(2);
-			}
-			if brush_position.y < low_point.y {
-				dist_squared -= (brush_position.y - low_point.y).powi(2);
-			} else if brush_position.y > high_point.y {
-				dist_squared -= (brush_position.y - high_point.y).powi(2);
-			}
-			if brush_position.z < low_point.z {
-				dist_squared -= (brush_position.z - low_point.z).powi(2);
-			} else if brush_position.z > high_point.z {
-				dist_squared -= (brush_position.z - high_point.z).powi(2);
-			}
+	/// Set how strongly the brush applies, from 0 to 1.
+	pub fn set_strength(&mut self, strength: f32) {
+		self.dynamics.strength = strength.clamp(0.0, 1.0);
+	}
 
-			dist_squared >= 0.0
-		})
+	/// Get how far toward its edge the brush refines down to single voxels, from 0 to 1.
+	pub fn get_hardness(&self) -> f32 {
+		self.dynamics.hardness
 	}
 
-	/// Function for determining interior leaf nodes for a sphere.
-	pub fn container(brush_size: f32, brush_position: Vec3) -> Box<dyn Fn (f32, Vec3) -> bool> {
-		Box::new(move |size: f32, center: Vec3| {
-			let half_size = size / 2.0;
-			let low_point = vec3(center.x - half_size, center.y - half_size, center.z - half_size);
-			let high_point = vec3(center.x + half_size, center.y + half_size, center.z + half_size);
-			let mut dist_squared = brush_size.powi(2);
-			if brush_position.x > center.x {
-				dist_squared -= (brush_position.x - low_point.x).powi(2);
-			} else {
-				dist_squared -= (brush_position.x - high_point.x).powi(2);
-			}
-			if brush_position.y > center.y {
-				dist_squared -= (brush_position.y - low_point.y).powi(2);
-			} else {
-				dist_squared -= (brush_position.y - high_point.y).powi(2);
-			}
-			if brush_position.z > center.z {
-				dist_squared -= (brush_position.z - low_point.z).powi(2);
-			} else {
-				dist_squared -= (brush_position.z - high_point.z).powi(2);
-			}
+	/// Set how far toward its edge the brush refines down to single voxels, from 0 to 1.
+	///
+	/// Softer brushes leave coarser voxels toward their edge, which
+	/// feathers it and saves nodes.
+	pub fn set_hardness(&mut self, hardness: f32) {
+		self.dynamics.hardness = hardness.clamp(0.0, 1.0);
+	}
 
-			dist_squared > 0.0
-		})
+	/// Get the most a stroke builds up or carves away, relative to the size, if limited.
+	pub fn get_accumulation(&self) -> Option<f32> {
+		self.dynamics.accumulation
 	}
-}
 
-impl Draw for RoundBrushTip {
-	/// Sculpt by adding geometry.
-	fn add(&self, sculpt: &mut Sculpt, x: f32, y: f32, size: f32) {
-		let brush_position = vec3(x, y, 0.5);
-		let brush_size = size;
-		sculpt.subdivide(
-			RoundBrushTip::filler(brush_size, brush_position),
-			RoundBrushTip::container(brush_size, brush_position)
-		);
+	/// Limit how much a stroke builds up or carves away, relative to the size, like paint opacity.
+	///
+	/// Passes of an open stroke going over the same place don't pile up
+	/// beyond the limit. `None` lets them pile up.
+	pub fn set_accumulation(&mut self, limit: Option<f32>) {
+		self.dynamics.accumulation = limit;
 	}
 
-	/// Sculpt by removing geometry.
-	fn remove(&self, sculpt: &mut Sculpt, x: f32, y: f32, size: f32) {
-		let brush_position = vec3(x, y, 0.5);
-		let brush_size = size;
-		sculpt.unsubdivide(
-			RoundBrushTip::filler(brush_size, brush_position),
-			RoundBrushTip::container(brush_size, brush_position)
-		);
+	/// Get how the brush applies its tip.
+	pub fn get_dynamics(&self) -> Dynamics {
+		self.dynamics
 	}
-}
 
-/// A brush tip for drawing cubical shapes.
-pub struct SquareBrushTip {}
+	/// Get the materials added voxels are filled with.
+	pub fn get_material(&self) -> MaterialMix {
+		self.material
+	}
 
-impl SquareBrushTip {
-	/// Create a new square brush tip/effector.
-	pub fn new() -> Self {
-		Self {}
+	/// Set the materials added voxels are filled with, like a dithered mix of two palette entries.
+	pub fn set_material(&mut self, material: MaterialMix) {
+		self.material = material;
 	}
 
-	/// Function for implicitly defining a cubical shape for the brush.
-	pub fn filler(brush_size: f32, brush_position: Vec3) -> Box<dyn Fn (f32, Vec3) -> bool> {
-		Box::new(move |size: f32, center: Vec3| {
-			let half_size = size / 2.0;
-			let low_point = vec3(center.x - half_size, center.y - half_size, center.z - half_size);
-			let high_point = vec3(center.x + half_size, center.y + half_size, center.z + half_size);
-			
-			let x_in_range = (brush_position.x - brush_size < low_point.x && brush_position.x + brush_size > low_point.x)
-				|| (brush_position.x - brush_size < high_point.x && brush_position.x + brush_size > high_point.x)
-				|| (brush_position.x - brush_size > low_point.x && brush_position.x + brush_size < high_point.x);
-			let y_in_range = (brush_position.y - brush_size < low_point.y && brush_position.y + brush_size > low_point.y)
-				|| (brush_position.y - brush_size < high_point.y && brush_position.y + brush_size > high_point.y)
-				|| (brush_position.y - brush_size > low_point.y && brush_position.y + brush_size < high_point.y);
-			let z_in_range = (brush_position.z - brush_size < low_point.z && brush_position.z + brush_size > low_point.z)
-				|| (brush_position.z - brush_size < high_point.z && brush_position.z + brush_size > high_point.z)
-				|| (brush_position.z - brush_size > low_point.z && brush_position.z + brush_size < high_point.z);
-
-			x_in_range && y_in_range && z_in_range
-		})
+	/// Get the settings saved with a document.
+	pub fn get_settings(&self) -> BrushSettings {
+		BrushSettings {
+			size: self.size,
+			dynamics: self.dynamics,
+			material: self.material,
+		}
 	}
 
-	/// Function for determining interior leaf nodes for a cube.
-	pub fn container(brush_size: f32, brush_position: Vec3) -> Box<dyn Fn (f32, Vec3) -> bool> {
-		Box::new(move |size: f32, center: Vec3| {
-			let half_size = size / 2.0;
-			let low_point = vec3(center.x - half_size, center.y - half_size, center.z - half_size);
-			let high_point = vec3(center.x + half_size, center.y + half_size, center.z + half_size);
-			
-			let x_in_range = (brush_position.x - brush_size < low_point.x && brush_position.x + brush_size > low_point.x)
-				&& (brush_position.x - brush_size < high_point.x && brush_position.x + brush_size > high_point.x);
-			let y_in_range = (brush_position.y - brush_size < low_point.y && brush_position.y + brush_size > low_point.y)
-				&& (brush_position.y - brush_size < high_point.y && brush_position.y + brush_size > high_point.y);
-			let z_in_range = (brush_position.z - brush_size < low_point.z && brush_position.z + brush_size > low_point.z)
-				&& (brush_position.z - brush_size < high_point.z && brush_position.z + brush_size > high_point.z);
-
-			x_in_range && y_in_range && z_in_range
-		})
+	/// Take on settings saved with a document, keeping them in range.
+	pub fn set_settings(&mut self, settings: &BrushSettings) {
+		self.set_size(settings.size);
+		self.set_strength(settings.dynamics.strength);
+		self.set_hardness(settings.dynamics.hardness);
+		self.set_accumulation(settings.dynamics.accumulation);
+		self.set_smoothing(settings.dynamics.smoothing);
+		self.set_material(settings.material);
+	}
+
+	/// Restart the random numbers used by stochastic tips, so strokes can be repeated.
+	pub fn set_seed(&mut self, seed: u32) {
+		self.rng = Rng::new(seed);
+	}
+
+	/// Get how far apart a stroke stamps the brush, which is never less than a voxel.
+	pub fn get_spacing(&self, document: &Document) -> f32 {
+		(self.size * document.get_chunk_size() * STROKE_SPACING).max(document.get_voxel_size())
+	}
+
+	/// Get the volume a stroke at a position would stamp.
+	pub fn stamp(&self, document: &Document, position: Vec3) -> Stamp {
+		let (center, size) = self.tip.snap(position, self.size * document.get_chunk_size(), document.get_voxel_size());
+
+		Stamp {
+			shape: self.tip.shape(),
+			center,
+			size,
+		}
 	}
-}
 
-impl Draw for SquareBrushTip {
 	/// Sculpt by adding geometry.
-	fn add(&self, sculpt: &mut Sculpt, x: f32, y: f32, size: f32) {
-		let brush_position = vec3(x, y, 0.5);
-		let brush_size = size;
-		sculpt.subdivide(
-			SquareBrushTip::filler(brush_size, brush_position),
-			SquareBrushTip::container(brush_size, brush_position)
-		);
+	///
+	/// Chunks are created as needed to fit the stroke. A stroke which
+	/// would go over the node limit of the document is refined less deeply.
+	pub fn add(&mut self, document: &mut Document, position: Vec3) {
+		match self.dynamics.get_accumulation_depth(self.size * document.get_chunk_size()) {
+			Some(depth) => {
+				let reach = self.get_reach(document, position);
+				document.limit_accumulation(reach, depth, false, |document| self.add_pass(document, position));
+			},
+			None => self.add_pass(document, position),
+		}
 	}
 
 	/// Sculpt by removing geometry.
-	fn remove(&self, sculpt: &mut Sculpt, x: f32, y: f32, size: f32) {
-		let brush_position = vec3(x, y, 0.5);
-		let brush_size = size;
-		sculpt.unsubdivide(
-			SquareBrushTip::filler(brush_size, brush_position),
-			SquareBrushTip::container(brush_size, brush_position)
-		);
+	///
+	/// Chunks left empty by the stroke are removed.
+	pub fn remove(&mut self, document: &mut Document, position: Vec3) {
+		match self.dynamics.get_accumulation_depth(self.size * document.get_chunk_size()) {
+			Some(depth) => {
+				let reach = self.get_reach(document, position);
+				document.limit_accumulation(reach, depth, true, |document| self.remove_pass(document, position));
+			},
+			None => self.remove_pass(document, position),
+		}
 	}
-}
 
-#[cfg(test)]
-mod tests {
-	use super::*;
+	/// Add the geometry of one pass of a stroke.
+	fn add_pass(&mut self, document: &mut Document, position: Vec3) {
+		let reach = self.get_reach(document, position);
+		let (position, size) = self.tip.snap(position, self.size * document.get_chunk_size(), document.get_voxel_size());
+		let stamps = self.tip.scatter(&mut self.rng, position, size, self.dynamics.strength);
+		let stroke = document.begin_stroke();
+		let depth = document.fit_node_limit(reach);
+		for sculpt in document.get_chunks_in(reach, true) {
+			sculpt.set_depth_limit(depth);
+			for &(center, radius) in &stamps {
+				self.tip.add(sculpt, center, radius, self.dynamics.hardness, &self.material);
+			}
+			self.dynamics.relax(sculpt, position, size);
+			sculpt.set_depth_limit(None);
+			sculpt.mark_edited(reach, stroke, HEAT_DECAY);
+		}
+	}
 
-    #[test]
-    fn round_brush_filler_contains_small_center_point() {
-    	let filler = RoundBrushTip::filler(0.5, vec3(0.5, 0.5, 0.5));
-    	assert!(filler(0.25, vec3(0.5, 0.5, 0.5)))
-    }
+	/// Remove the geometry of one pass of a stroke.
+	fn remove_pass(&mut self, document: &mut Document, position: Vec3) {
+		let reach = self.get_reach(document, position);
+		let (position, size) = self.tip.snap(position, self.size * document.get_chunk_size(), document.get_voxel_size());
+		let stamps = self.tip.scatter(&mut self.rng, position, size, self.dynamics.strength);
+		let stroke = document.begin_stroke();
+		let depth = document.fit_node_limit(reach);
+		for sculpt in document.get_chunks_in(reach, false) {
+			sculpt.set_depth_limit(depth);
+			for &(center, radius) in &stamps {
+				self.tip.remove(sculpt, center, radius, self.dynamics.hardness);
+			}
+			self.dynamics.relax(sculpt, position, size);
+			sculpt.set_depth_limit(None);
+			sculpt.mark_edited(reach, stroke, HEAT_DECAY);
+		}
+		document.prune();
+	}
 
-    #[test]
-    fn round_brush_filler_contains_large_center_point() {
-    	let filler = RoundBrushTip::filler(0.5, vec3(0.5, 0.5, 0.5));
-    	assert!(filler(1.0, vec3(0.5, 0.5, 0.5)))
-    }
+	/// Paint the voxels under a stamp at a position without changing their shape.
+	///
+	/// Without a color, the paint is removed instead.
+	pub fn paint(&self, document: &mut Document, position: Vec3, color: Option<[f32; 4]>) {
+		let stamp = self.stamp(document, position);
+		let voxel_size = document.get_voxel_size();
+		let reach = self.get_reach(document, position);
+		let paint = color.map_or(0, sculpt::pack_paint);
 
-    #[test]
-    fn round_brush_filler_contains_small_offcenter_point() {
-    	let filler = RoundBrushTip::filler(0.5, vec3(0.5, 0.5, 0.5));
-    	assert!(filler(0.05, vec3(0.75, 0.75, 0.75)))
-    }
+		document.paint(reach, &|size, center| stamp.cover(size, center, voxel_size), paint);
+	}
 
-    #[test]
-    fn round_brush_filler_contains_large_offcenter_point() {
-    	let filler = RoundBrushTip::filler(0.5, vec3(0.5, 0.5, 0.5));
-    	assert!(filler(1.0, vec3(0.75, 0.75, 0.75)))
-    }
+	/// Get the space a stroke at a position can affect.
+	pub fn get_reach(&self, document: &Document, position: Vec3) -> Aabb {
+		let (position, size) = self.tip.snap(position, self.size * document.get_chunk_size(), document.get_voxel_size());
 
-    #[test]
-    fn round_brush_filler_contains_large_far_off_point() {
-    	let filler = RoundBrushTip::filler(0.5, vec3(0.5, 0.5, 0.5));
-    	assert!(filler(4.0, vec3(2.0, 2.0, 2.0)))
-    }
+		Aabb::cube(position, (size + 2.0 * document.get_voxel_size()) * 2.0)
+	}
+}
 
-    #[test]
-    fn round_brush_filler_does_not_contains_far_off_point() {
-    	let filler = RoundBrushTip::filler(0.5, vec3(0.5, 0.5, 0.5));
-    	assert!(!filler(0.25, vec3(2.0, 2.0, 2.0)))
-    }
+impl Stamp {
+	/// Get how far a point is from the center, relative to the size (1 on the edge).
+	pub fn get_distance(&self, point: Vec3) -> f32 {
+		let offset = (point - self.center).abs();
+		let distance = match self.shape {
+			TipShape::Sphere => offset.length(),
+			TipShape::Cube => offset.max_element(),
+		};
 
-    #[test]
-    fn round_brush_container_contains_small_center_point() {
-    	let container = RoundBrushTip::container(0.5, vec3(0.5, 0.5, 0.5));
-    	assert!(container(0.25, vec3(0.5, 0.5, 0.5)))
-    }
+		distance / self.size
+	}
 
-    #[test]
-    fn round_brush_container_does_not_contain_large_center_point() {
-    	let container = RoundBrushTip::container(0.5, vec3(0.5, 0.5, 0.5));
-    	assert!(!container(1.0, vec3(0.5, 0.5, 0.5)))
-    }
+	/// Classify how much of a node the stamp covers.
+	///
+	/// Nodes as small as a voxel are covered if their center is.
+	pub fn cover(&self, size: f32, center: Vec3, voxel_size: f32) -> Coverage {
+		let half = size / 2.0;
+		let nearest = self.center.clamp(center - half, center + half);
+		if self.get_distance(nearest) > 1.0 {
+			return Coverage::Unchanged;
+		}
+
+		let away = (center - self.center).signum();
+		if self.get_distance(center + away * half) <= 1.0 {
+			Coverage::Solid
+		} else if size <= voxel_size {
+			if self.get_distance(center) <= 1.0 { Coverage::Solid } else { Coverage::Unchanged }
+		} else {
+			Coverage::Mixed
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use glam::vec3;
 
     #[test]
-    fn round_brush_container_contains_small_offcenter_point() {
-    	let container = RoundBrushTip::container(0.5, vec3(0.5, 0.5, 0.5));
-    	assert!(container(0.05, vec3(0.75, 0.75, 0.75)))
+    fn strokes_fill_in_the_gaps_between_samples() {
+    	let mut stroke = Stroke::new();
+
+    	assert_eq!(stroke.follow(Vec3::ZERO, 0.1), vec![Vec3::ZERO]);
+    	let stamps = stroke.follow(vec3(0.35, 0.0, 0.0), 0.1);
+    	assert_eq!(stamps.len(), 3);
+    	assert!(stamps[2].abs_diff_eq(vec3(0.3, 0.0, 0.0), 1e-6));
+
+    	// the rest of the way is carried over, rather than stamped short
+    	assert!(stroke.follow(vec3(0.38, 0.0, 0.0), 0.1).is_empty());
+    	let stamps = stroke.follow(vec3(0.4, 0.0, 0.0), 0.1);
+    	assert_eq!(stamps.len(), 1);
+    	assert!(stamps[0].abs_diff_eq(vec3(0.4, 0.0, 0.0), 1e-6));
+
+    	// a jump across the sculpt is filled in with no more than the most stamps
+    	assert_eq!(stroke.follow(vec3(1000.0, 0.0, 0.0), 0.1).len(), MAX_STROKE_STAMPS);
     }
 
     #[test]
-    fn round_brush_container_does_not_contain_large_offcenter_point() {
-    	let container = RoundBrushTip::container(0.5, vec3(0.5, 0.5, 0.5));
-    	assert!(!container(1.0, vec3(0.75, 0.75, 0.75)))
+    fn stamp_follows_the_tip_and_chunk_size() {
+    	let document = Document::new(64, 2.0);
+    	let brush = Brush::new("Square Brush".to_owned(), Box::new(SquareBrushTip::new()));
+
+    	let stamp = brush.stamp(&document, vec3(0.5, 0.5, 0.5));
+
+    	assert_eq!(stamp.shape, TipShape::Cube);
+    	assert_eq!(stamp.center, vec3(0.5, 0.5, 0.5));
+    	assert_eq!(stamp.size, 0.2);
     }
 
     #[test]
-    fn round_brush_container_does_not_contain_large_far_off_point() {
-    	let container = RoundBrushTip::container(0.5, vec3(0.5, 0.5, 0.5));
-    	assert!(!container(4.0, vec3(2.0, 2.0, 2.0)))
+    fn strokes_over_the_node_limit_are_refined_less_deeply() {
+    	let stroke = |limit: Option<usize>| {
+    		let mut document = Document::new(256, 1.0);
+    		document.set_node_limit(limit);
+    		let mut brush = Brush::new("Round Brush".to_owned(), Box::new(RoundBrushTip::new()));
+    		brush.set_size(0.3);
+    		brush.add(&mut document, vec3(0.5, 0.5, 0.5));
+    		document
+    	};
+
+    	let mut unlimited = stroke(None);
+    	assert_eq!(unlimited.take_reduced_depth(), None);
+
+    	let mut limited = stroke(Some(unlimited.get_node_count() / 10));
+    	let depth = limited.take_reduced_depth().unwrap();
+    	assert!(depth < 8);
+    	assert_eq!(limited.take_reduced_depth(), None);
+    	assert!(limited.get_node_count() <= unlimited.get_node_count() / 10);
+    	assert!(limited.is_solid(vec3(0.5, 0.5, 0.5)));
+    	assert!(limited.get_home_chunk().get_depth_limit().is_none());
     }
 
     #[test]
-    fn round_brush_container_does_not_contains_far_off_point() {
-    	let container = RoundBrushTip::container(0.5, vec3(0.5, 0.5, 0.5));
-    	assert!(!container(0.25, vec3(2.0, 2.0, 2.0)))
+    fn voxel_pen_edits_whole_voxels_on_the_grid() {
+    	let mut document = Document::new(64, 1.0);
+    	let mut brush = Brush::new("Voxel Pen".to_owned(), Box::new(VoxelPenTip::new()));
+    	let voxel = document.get_voxel_size();
+    	// smaller than a voxel still edits one
+    	brush.set_size(0.001);
+    	let position = vec3(20.3, 30.6, 40.5) * voxel;
+
+    	let stamp = brush.stamp(&document, position);
+    	assert_eq!(stamp.center, vec3(20.5, 30.5, 40.5) * voxel);
+    	assert_eq!(stamp.size, voxel / 2.0);
+
+    	brush.add(&mut document, position);
+    	let chunk = document.get_home_chunk();
+    	assert_eq!(chunk.get_leaves().len(), 1);
+    	assert!(chunk.is_solid(stamp.center));
+    	assert!(!chunk.is_solid(stamp.center + vec3(voxel, 0.0, 0.0)));
+
+    	// a block three voxels across, centered on the voxel under the stroke
+    	brush.set_size(1.5 / 64.0);
+    	brush.add(&mut document, vec3(10.9, 10.1, 10.5) * voxel);
+    	let chunk = document.get_home_chunk();
+    	assert!(chunk.is_solid(vec3(9.5, 9.5, 9.5) * voxel) && chunk.is_solid(vec3(11.5, 11.5, 11.5) * voxel));
+    	assert!(!chunk.is_solid(vec3(12.5, 10.5, 10.5) * voxel) && !chunk.is_solid(vec3(10.5, 8.5, 10.5) * voxel));
+
+    	brush.set_size(0.001);
+    	brush.remove(&mut document, vec3(10.5, 10.5, 10.5) * voxel);
+    	let chunk = document.get_home_chunk();
+    	assert!(!chunk.is_solid(vec3(10.5, 10.5, 10.5) * voxel) && chunk.is_solid(vec3(9.5, 10.5, 10.5) * voxel));
     }
 }
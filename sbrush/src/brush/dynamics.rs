@@ -0,0 +1,46 @@
+//! How a brush applies its tip, apart from the shape of the tip.
+
+use crate::sculpt::Sculpt;
+
+use glam::Vec3;
+
+/// How strongly, how softly and how far a brush applies.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Dynamics {
+	/// How strongly the brush applies, from 0 to 1.
+	pub strength: f32,
+	/// How far toward its edge the brush refines down to single voxels, from 0 to 1.
+	pub hardness: f32,
+	/// The most a stroke builds up or carves away, relative to the size, if limited.
+	pub accumulation: Option<f32>,
+	/// The smoothing iterations run after each stroke (0 disables it).
+	pub smoothing: u32,
+}
+
+impl Default for Dynamics {
+	fn default() -> Self {
+		Self {
+			strength: 0.5,
+			hardness: 1.0,
+			accumulation: None,
+			smoothing: 0,
+		}
+	}
+}
+
+impl Dynamics {
+	/// Get the most a stroke of a size builds up or carves away, if limited.
+	pub fn get_accumulation_depth(&self, size: f32) -> Option<f32> {
+		self.accumulation.map(|limit| limit * size)
+	}
+
+	/// Relax the surface around a stroke to hide stair-stepping.
+	pub fn relax(&self, sculpt: &mut Sculpt, position: Vec3, size: f32) {
+		if self.smoothing == 0 {
+			return;
+		}
+
+		let reach = Vec3::splat(size + 2.0 * sculpt.get_voxel_size());
+		sculpt.relax(position - reach, position + reach, self.smoothing);
+	}
+}
@@ -0,0 +1,34 @@
+//! The brushes the editor starts out with.
+
+use super::{Brush, Draw, RoundBrushTip, SprayBrushTip, SquareBrushTip, VoxelPenTip};
+use crate::i18n::tr;
+
+/// A brush the editor starts out with.
+pub struct BrushPreset {
+	/// The id the brush is picked by, which isn't translated.
+	pub id: &'static str,
+	/// The key of the translated name.
+	name: &'static str,
+	tip: fn() -> Box<dyn Draw>,
+	/// The size relative to a chunk.
+	size: f32,
+}
+
+impl BrushPreset {
+	/// Create the brush, named in the current language.
+	pub fn create(&self) -> Brush {
+		let mut brush = Brush::new(tr(self.name), (self.tip)());
+		brush.set_size(self.size);
+
+		brush
+	}
+}
+
+/// The built-in brushes, in the order their keys pick them.
+pub const BUILT_IN: [BrushPreset; 4] = [
+	BrushPreset { id: "round", name: "brush-round", tip: || Box::new(RoundBrushTip::new()), size: 0.1 },
+	BrushPreset { id: "square", name: "brush-square", tip: || Box::new(SquareBrushTip::new()), size: 0.1 },
+	BrushPreset { id: "spray", name: "brush-spray", tip: || Box::new(SprayBrushTip::new()), size: 0.1 },
+	// the pen starts out on single voxels
+	BrushPreset { id: "pen", name: "brush-voxel-pen", tip: || Box::new(VoxelPenTip::new()), size: 0.0 },
+];
@@ -0,0 +1,171 @@
+//! The brushes to pick from, by id or name.
+
+use super::{Brush, SavedBrushes};
+use super::presets;
+
+use std::ops::{Index, IndexMut};
+
+/// The brushes to pick from, each under an id which isn't translated, like `round`.
+///
+/// Brushes keep the order they were registered in, so they can also be
+/// picked by index.
+pub struct BrushRegistry {
+	ids: Vec<String>,
+	brushes: Vec<Brush>,
+}
+
+impl Default for BrushRegistry {
+	/// The built-in brushes.
+	fn default() -> Self {
+		let mut registry = Self::new();
+		for preset in &presets::BUILT_IN {
+			registry.register(preset.id, preset.create());
+		}
+
+		registry
+	}
+}
+
+impl BrushRegistry {
+	/// Create a registry without any brushes.
+	pub fn new() -> Self {
+		Self {
+			ids: Vec::new(),
+			brushes: Vec::new(),
+		}
+	}
+
+	/// Add a brush under an id, replacing the brush already under it, and get its index.
+	pub fn register(&mut self, id: &str, brush: Brush) -> usize {
+		if let Some(index) = self.ids.iter().position(|known| known == id) {
+			self.brushes[index] = brush;
+			return index;
+		}
+		self.ids.push(id.to_owned());
+		self.brushes.push(brush);
+
+		self.brushes.len() - 1
+	}
+
+	/// Find the index of a brush by its id, or else by its name in any case.
+	pub fn find(&self, id: &str) -> Option<usize> {
+		self.ids.iter().position(|known| known == id).or_else(|| {
+			let name = id.to_lowercase();
+			self.brushes.iter().position(|brush| brush.name.to_lowercase() == name)
+		})
+	}
+
+	/// Get a brush by its id or name.
+	pub fn get(&self, id: &str) -> Option<&Brush> {
+		self.find(id).map(|index| &self.brushes[index])
+	}
+
+	/// Get the id of the brush at an index.
+	pub fn get_id(&self, index: usize) -> Option<&str> {
+		self.ids.get(index).map(String::as_str)
+	}
+
+	/// Get the count of brushes.
+	pub fn len(&self) -> usize {
+		self.brushes.len()
+	}
+
+	/// Check whether there are no brushes.
+	pub fn is_empty(&self) -> bool {
+		self.brushes.is_empty()
+	}
+
+	/// Get the ids and brushes, in the order they were registered.
+	pub fn iter(&self) -> impl Iterator<Item = (&str, &Brush)> {
+		self.ids.iter().map(String::as_str).zip(&self.brushes)
+	}
+
+	/// Get the settings of every brush to save with a document, along with the current one.
+	pub fn save(&self, current: usize) -> SavedBrushes {
+		SavedBrushes {
+			current: self.get_id(current).unwrap_or_default().to_owned(),
+			brushes: self.iter().map(|(id, brush)| (id.to_owned(), brush.get_settings())).collect(),
+		}
+	}
+
+	/// Take on the settings saved with a document, returning the index of the brush which was in use.
+	///
+	/// Brushes which aren't registered are left out.
+	pub fn restore(&mut self, saved: &SavedBrushes) -> Option<usize> {
+		for (id, settings) in &saved.brushes {
+			if let Some(index) = self.ids.iter().position(|known| known == id) {
+				self.brushes[index].set_settings(settings);
+			}
+		}
+
+		self.ids.iter().position(|known| *known == saved.current)
+	}
+
+	/// Get every brush to change, in the order they were registered.
+	pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Brush> {
+		self.brushes.iter_mut()
+	}
+}
+
+impl Index<usize> for BrushRegistry {
+	type Output = Brush;
+
+	fn index(&self, index: usize) -> &Brush {
+		&self.brushes[index]
+	}
+}
+
+impl IndexMut<usize> for BrushRegistry {
+	fn index_mut(&mut self, index: usize) -> &mut Brush {
+		&mut self.brushes[index]
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use crate::brush::{SquareBrushTip, TipShape};
+
+    #[test]
+    fn brushes_are_found_by_id_or_name() {
+    	let registry = BrushRegistry::default();
+
+    	assert_eq!(registry.len(), 4);
+    	assert_eq!(registry.find("square"), Some(1));
+    	assert_eq!(registry.get_id(3), Some("pen"));
+    	assert_eq!(registry.get("pen").map(Brush::get_size), Some(0.0));
+    	let name = registry[2].name.to_uppercase();
+    	assert_eq!(registry.find(&name), Some(2));
+    	assert_eq!(registry.find("chisel"), None);
+    }
+
+    #[test]
+    fn saved_settings_are_restored_by_id() {
+    	let mut registry = BrushRegistry::default();
+    	registry[2].set_strength(0.9);
+    	registry[3].set_size(0.25);
+    	let mut saved = registry.save(2);
+    	saved.brushes.push(("chisel".to_owned(), saved.brushes[0].1));
+
+    	let mut restored = BrushRegistry::default();
+    	assert_eq!(restored.restore(&saved), Some(2));
+    	assert_eq!(restored.get("spray").map(Brush::get_strength), Some(0.9));
+    	assert_eq!(restored.get("pen").map(Brush::get_size), Some(0.25));
+    	assert_eq!(restored.len(), 4);
+    	assert_eq!(restored.restore(&SavedBrushes::default()), None);
+    }
+
+    #[test]
+    fn registering_an_id_again_replaces_the_brush() {
+    	let mut registry = BrushRegistry::default();
+
+    	let index = registry.register("round", Brush::new("Boxy".to_owned(), Box::new(SquareBrushTip::new())));
+    	assert_eq!(index, 0);
+    	assert_eq!(registry.len(), 4);
+    	assert_eq!(registry.get("round").map(Brush::get_shape), Some(TipShape::Cube));
+
+    	assert_eq!(registry.register("chisel", Brush::new("Chisel".to_owned(), Box::new(SquareBrushTip::new()))), 4);
+    	assert_eq!(registry.iter().last().map(|(id, _)| id), Some("chisel"));
+    }
+}
@@ -0,0 +1,501 @@
+//! The tips brushes stamp with, each shaping the volume a stroke adds or removes.
+
+use super::{Stamp, TipShape};
+use crate::aabb::Aabb;
+use crate::material::MaterialMix;
+use crate::rng::Rng;
+use crate::sculpt::Sculpt;
+
+use std::rc::Rc;
+
+use glam::{Vec3, vec3};
+
+/// The filler and container functions of a brush tip.
+type ShapeFunctions = (Box<dyn Fn(f32, Vec3) -> bool>, Box<dyn Fn(f32, Vec3) -> bool>);
+
+/// Stop refining the nodes of a tip's shape early toward its edge.
+///
+/// Inside of the hard core, nodes are refined down to single voxels.
+/// Past it, the largest leaf grows with the distance beyond the core,
+/// and such a coarse node is kept whole if the shape covers its
+/// center, or left out if it doesn't.
+fn soften(stamp: Stamp, hardness: f32, voxel_size: f32, filler: Box<dyn Fn(f32, Vec3) -> bool>, container: Box<dyn Fn(f32, Vec3) -> bool>) -> ShapeFunctions {
+	if hardness >= 1.0 {
+		return (filler, container);
+	}
+
+	let coarsest = move |center: Vec3| ((stamp.get_distance(center) - hardness) * stamp.size / 2.0).max(voxel_size);
+	let filler: Rc<dyn Fn(f32, Vec3) -> bool> = Rc::from(filler);
+	let covers = Rc::clone(&filler);
+
+	(
+		Box::new(move |size, center| filler(size, center) && (size > coarsest(center) || filler(0.0, center))),
+		Box::new(move |size, center| container(size, center) || (size <= coarsest(center) && covers(0.0, center))),
+	)
+}
+
+pub trait Draw {
+	/// Get the shape stamped by the tip.
+	fn shape(&self) -> TipShape;
+
+	/// Sculpt by adding geometry of a material mix, refining less toward the edge the softer it is.
+	fn add(&self, sculpt: &mut Sculpt, position: Vec3, size: f32, hardness: f32, material: &MaterialMix);
+
+	/// Sculpt by removing geometry, refining less toward the edge the softer it is.
+	fn remove(&self, sculpt: &mut Sculpt, position: Vec3, size: f32, hardness: f32);
+
+	/// Get where a stroke at a position is centered, and its size, given the size of the finest voxels.
+	///
+	/// Most tips go where the stroke is, at the size of the brush.
+	fn snap(&self, position: Vec3, size: f32, voxel_size: f32) -> (Vec3, f32) {
+		(position, size)
+	}
+
+	/// Get the stamps (centers and sizes) a single stroke applies.
+	///
+	/// Most tips stamp once, where the stroke is.
+	fn scatter(&self, rng: &mut Rng, position: Vec3, size: f32, strength: f32) -> Vec<(Vec3, f32)> {
+		vec![(position, size)]
+	}
+}
+
+/// Get the square of a number.
+///
+/// Unlike `powi`, whose precision is unspecified, this rounds the same on every platform.
+fn square(value: f32) -> f32 {
+	value * value
+}
+
+/// A brush tip for drawing spherical shapes.
+pub struct RoundBrushTip {}
+
+impl RoundBrushTip {
+	/// Create a new round brush tip/effector.
+	pub fn new() -> Self {
+		Self {}
+	}
+
+	/// Function for implicitly defining a spherical shape for the brush.
+	pub fn filler(brush_size: f32, brush_position: Vec3) -> Box<dyn Fn (f32, Vec3) -> bool> {
+		Box::new(move |size: f32, center: Vec3| {
+			let half_size = size / 2.0;
+			let low_point = vec3(center.x - half_size, center.y - half_size, center.z - half_size);
+			let high_point = vec3(center.x + half_size, center.y + half_size, center.z + half_size);
+			let mut dist_squared = square(brush_size);
+			if brush_position.x < low_point.x {
+				dist_squared -= square(brush_position.x - low_point.x);
+			} else if brush_position.x > high_point.x {
+				dist_squared -= square(brush_position.x - high_point.x);
+			}
+			if brush_position.y < low_point.y {
+				dist_squared -= square(brush_position.y - low_point.y);
+			} else if brush_position.y > high_point.y {
+				dist_squared -= square(brush_position.y - high_point.y);
+			}
+			if brush_position.z < low_point.z {
+				dist_squared -= square(brush_position.z - low_point.z);
+			} else if brush_position.z > high_point.z {
+				dist_squared -= square(brush_position.z - high_point.z);
+			}
+
+			dist_squared >= 0.0
+		})
+	}
+
+	/// Function for determining interior leaf nodes for a sphere.
+	pub fn container(brush_size: f32, brush_position: Vec3) -> Box<dyn Fn (f32, Vec3) -> bool> {
+		Box::new(move |size: f32, center: Vec3| {
+			let half_size = size / 2.0;
+			let low_point = vec3(center.x - half_size, center.y - half_size, center.z - half_size);
+			let high_point = vec3(center.x + half_size, center.y + half_size, center.z + half_size);
+			let mut dist_squared = square(brush_size);
+			if brush_position.x > center.x {
+				dist_squared -= square(brush_position.x - low_point.x);
+			} else {
+				dist_squared -= square(brush_position.x - high_point.x);
+			}
+			if brush_position.y > center.y {
+				dist_squared -= square(brush_position.y - low_point.y);
+			} else {
+				dist_squared -= square(brush_position.y - high_point.y);
+			}
+			if brush_position.z > center.z {
+				dist_squared -= square(brush_position.z - low_point.z);
+			} else {
+				dist_squared -= square(brush_position.z - high_point.z);
+			}
+
+			dist_squared > 0.0
+		})
+	}
+}
+
+impl Draw for RoundBrushTip {
+	/// Get the shape stamped by the tip.
+	fn shape(&self) -> TipShape {
+		TipShape::Sphere
+	}
+
+	/// Sculpt by adding geometry.
+	fn add(&self, sculpt: &mut Sculpt, position: Vec3, size: f32, hardness: f32, material: &MaterialMix) {
+		let brush_position = position;
+		let brush_size = size;
+		let (filler, container) = soften(
+			Stamp { shape: TipShape::Sphere, center: brush_position, size: brush_size },
+			hardness,
+			sculpt.get_voxel_size(),
+			RoundBrushTip::filler(brush_size, brush_position),
+			RoundBrushTip::container(brush_size, brush_position),
+		);
+		sculpt.subdivide_with(material, filler, container);
+	}
+
+	/// Sculpt by removing geometry.
+	fn remove(&self, sculpt: &mut Sculpt, position: Vec3, size: f32, hardness: f32) {
+		let brush_position = position;
+		let brush_size = size;
+		let (filler, container) = soften(
+			Stamp { shape: TipShape::Sphere, center: brush_position, size: brush_size },
+			hardness,
+			sculpt.get_voxel_size(),
+			RoundBrushTip::filler(brush_size, brush_position),
+			RoundBrushTip::container(brush_size, brush_position),
+		);
+		sculpt.unsubdivide(filler, container);
+	}
+}
+
+/// A brush tip for drawing cubical shapes.
+pub struct SquareBrushTip {}
+
+impl SquareBrushTip {
+	/// Create a new square brush tip/effector.
+	pub fn new() -> Self {
+		Self {}
+	}
+
+	/// Function for implicitly defining a cubical shape for the brush.
+	pub fn filler(brush_size: f32, brush_position: Vec3) -> Box<dyn Fn (f32, Vec3) -> bool> {
+		Box::new(move |size: f32, center: Vec3| {
+			let half_size = size / 2.0;
+			let low_point = vec3(center.x - half_size, center.y - half_size, center.z - half_size);
+			let high_point = vec3(center.x + half_size, center.y + half_size, center.z + half_size);
+			
+			let x_in_range = (brush_position.x - brush_size < low_point.x && brush_position.x + brush_size > low_point.x)
+				|| (brush_position.x - brush_size < high_point.x && brush_position.x + brush_size > high_point.x)
+				|| (brush_position.x - brush_size > low_point.x && brush_position.x + brush_size < high_point.x);
+			let y_in_range = (brush_position.y - brush_size < low_point.y && brush_position.y + brush_size > low_point.y)
+				|| (brush_position.y - brush_size < high_point.y && brush_position.y + brush_size > high_point.y)
+				|| (brush_position.y - brush_size > low_point.y && brush_position.y + brush_size < high_point.y);
+			let z_in_range = (brush_position.z - brush_size < low_point.z && brush_position.z + brush_size > low_point.z)
+				|| (brush_position.z - brush_size < high_point.z && brush_position.z + brush_size > high_point.z)
+				|| (brush_position.z - brush_size > low_point.z && brush_position.z + brush_size < high_point.z);
+
+			x_in_range && y_in_range && z_in_range
+		})
+	}
+
+	/// Function for determining interior leaf nodes for a cube.
+	pub fn container(brush_size: f32, brush_position: Vec3) -> Box<dyn Fn (f32, Vec3) -> bool> {
+		Box::new(move |size: f32, center: Vec3| {
+			let half_size = size / 2.0;
+			let low_point = vec3(center.x - half_size, center.y - half_size, center.z - half_size);
+			let high_point = vec3(center.x + half_size, center.y + half_size, center.z + half_size);
+			
+			let x_in_range = (brush_position.x - brush_size < low_point.x && brush_position.x + brush_size > low_point.x)
+				&& (brush_position.x - brush_size < high_point.x && brush_position.x + brush_size > high_point.x);
+			let y_in_range = (brush_position.y - brush_size < low_point.y && brush_position.y + brush_size > low_point.y)
+				&& (brush_position.y - brush_size < high_point.y && brush_position.y + brush_size > high_point.y);
+			let z_in_range = (brush_position.z - brush_size < low_point.z && brush_position.z + brush_size > low_point.z)
+				&& (brush_position.z - brush_size < high_point.z && brush_position.z + brush_size > high_point.z);
+
+			x_in_range && y_in_range && z_in_range
+		})
+	}
+}
+
+impl Draw for SquareBrushTip {
+	/// Get the shape stamped by the tip.
+	fn shape(&self) -> TipShape {
+		TipShape::Cube
+	}
+
+	/// Sculpt by adding geometry.
+	fn add(&self, sculpt: &mut Sculpt, position: Vec3, size: f32, hardness: f32, material: &MaterialMix) {
+		let brush_position = position;
+		let brush_size = size;
+		let (filler, container) = soften(
+			Stamp { shape: TipShape::Cube, center: brush_position, size: brush_size },
+			hardness,
+			sculpt.get_voxel_size(),
+			SquareBrushTip::filler(brush_size, brush_position),
+			SquareBrushTip::container(brush_size, brush_position),
+		);
+		sculpt.subdivide_with(material, filler, container);
+	}
+
+	/// Sculpt by removing geometry.
+	fn remove(&self, sculpt: &mut Sculpt, position: Vec3, size: f32, hardness: f32) {
+		let brush_position = position;
+		let brush_size = size;
+		let (filler, container) = soften(
+			Stamp { shape: TipShape::Cube, center: brush_position, size: brush_size },
+			hardness,
+			sculpt.get_voxel_size(),
+			SquareBrushTip::filler(brush_size, brush_position),
+			SquareBrushTip::container(brush_size, brush_position),
+		);
+		sculpt.unsubdivide(filler, container);
+	}
+}
+
+/// A brush tip spraying many tiny random spheres inside of its radius,
+/// building up rough, organic surfaces.
+pub struct SprayBrushTip {}
+
+impl SprayBrushTip {
+	/// The size of each droplet relative to the brush.
+	const DROPLET_SIZE: f32 = 0.15;
+
+	/// The most droplets sprayed by one stroke, at full strength.
+	const MAX_DROPLETS: f32 = 48.0;
+
+	/// Create a new spray brush tip/effector.
+	pub fn new() -> Self {
+		Self {}
+	}
+}
+
+impl Draw for SprayBrushTip {
+	/// Get the shape stamped by the tip.
+	fn shape(&self) -> TipShape {
+		TipShape::Sphere
+	}
+
+	/// Sculpt by adding geometry.
+	fn add(&self, sculpt: &mut Sculpt, position: Vec3, size: f32, hardness: f32, material: &MaterialMix) {
+		RoundBrushTip::new().add(sculpt, position, size, hardness, material);
+	}
+
+	/// Sculpt by removing geometry.
+	fn remove(&self, sculpt: &mut Sculpt, position: Vec3, size: f32, hardness: f32) {
+		RoundBrushTip::new().remove(sculpt, position, size, hardness);
+	}
+
+	/// Get randomly placed droplets, more of them the stronger the brush.
+	fn scatter(&self, rng: &mut Rng, position: Vec3, size: f32, strength: f32) -> Vec<(Vec3, f32)> {
+		let count = (strength * Self::MAX_DROPLETS).ceil().max(1.0) as usize;
+
+		(0..count).map(|_| {
+			// droplets vary in size, and stay inside of the brush radius
+			let radius = size * Self::DROPLET_SIZE * (0.5 + rng.next_f32());
+			let center = position + rng.next_in_unit_sphere() * (size - radius);
+			(center, radius)
+		}).collect()
+	}
+}
+
+/// A brush tip editing whole blocks of the finest voxels, aligned to their grid,
+/// for precise voxel art.
+///
+/// The block is as many voxels across as fit the brush, but at least
+/// one, and is edited exactly, however hard or soft the brush is.
+pub struct VoxelPenTip {}
+
+impl VoxelPenTip {
+	/// Create a new voxel pen tip/effector.
+	pub fn new() -> Self {
+		Self {}
+	}
+
+	/// Function for implicitly defining a block of voxels.
+	///
+	/// Nodes only touching the block from outside are left out, however the edges round.
+	pub fn filler(block: Aabb, voxel_size: f32) -> Box<dyn Fn (f32, Vec3) -> bool> {
+		let tolerance = voxel_size / 4.0;
+
+		Box::new(move |size: f32, center: Vec3| {
+			let half_size = size / 2.0;
+
+			(center - half_size).cmplt(block.max - tolerance).all() && (center + half_size).cmpgt(block.min + tolerance).all()
+		})
+	}
+
+	/// Function for determining interior leaf nodes for a block of voxels.
+	pub fn container(block: Aabb, voxel_size: f32) -> Box<dyn Fn (f32, Vec3) -> bool> {
+		let tolerance = voxel_size / 4.0;
+
+		Box::new(move |size: f32, center: Vec3| {
+			let half_size = size / 2.0;
+
+			(center - half_size).cmpgt(block.min - tolerance).all() && (center + half_size).cmplt(block.max + tolerance).all()
+		})
+	}
+}
+
+impl Draw for VoxelPenTip {
+	/// Get the shape stamped by the tip.
+	fn shape(&self) -> TipShape {
+		TipShape::Cube
+	}
+
+	/// Sculpt by adding a block of voxels.
+	fn add(&self, sculpt: &mut Sculpt, position: Vec3, size: f32, hardness: f32, material: &MaterialMix) {
+		let block = Aabb::cube(position, size * 2.0);
+		let voxel_size = sculpt.get_voxel_size();
+		sculpt.subdivide_with(material, VoxelPenTip::filler(block, voxel_size), VoxelPenTip::container(block, voxel_size));
+	}
+
+	/// Sculpt by removing a block of voxels.
+	fn remove(&self, sculpt: &mut Sculpt, position: Vec3, size: f32, hardness: f32) {
+		let block = Aabb::cube(position, size * 2.0);
+		let voxel_size = sculpt.get_voxel_size();
+		sculpt.unsubdivide(VoxelPenTip::filler(block, voxel_size), VoxelPenTip::container(block, voxel_size));
+	}
+
+	/// Get the block of whole voxels nearest to a stroke.
+	///
+	/// Blocks an odd number of voxels across are centered on the voxel
+	/// under the stroke, and even ones on the nearest voxel corner.
+	fn snap(&self, position: Vec3, size: f32, voxel_size: f32) -> (Vec3, f32) {
+		let count = (size * 2.0 / voxel_size).round().max(1.0);
+		let min = (position / voxel_size - count / 2.0).round() * voxel_size;
+		let half_size = count * voxel_size / 2.0;
+
+		(min + half_size, half_size)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    #[test]
+    fn spray_scatters_droplets_inside_the_brush() {
+    	let tip = SprayBrushTip::new();
+    	let position = vec3(0.5, 0.5, 0.5);
+
+    	let light = tip.scatter(&mut Rng::new(7), position, 0.2, 0.25);
+    	let heavy = tip.scatter(&mut Rng::new(7), position, 0.2, 1.0);
+
+    	assert!(light.len() < heavy.len());
+    	assert_eq!(light, tip.scatter(&mut Rng::new(7), position, 0.2, 0.25));
+    	for (center, radius) in heavy {
+    		assert!(radius < 0.2 * SprayBrushTip::DROPLET_SIZE * 1.5);
+    		assert!(center.distance(position) + radius <= 0.2 + 1e-5);
+    	}
+    }
+
+    #[test]
+    fn mixed_materials_are_dithered_over_the_surface() {
+    	let mut sculpt = Sculpt::new(64);
+    	let center = vec3(0.5, 0.5, 0.5);
+    	let mix = MaterialMix { first: 1, second: 2, mix: 0.3 };
+    	SquareBrushTip::new().add(&mut sculpt, center, 0.25, 1.0, &mix);
+
+    	// one voxel deep over the top face of the cube
+    	let top: Vec<u32> = (0..32)
+    		.flat_map(|y| (0..32).map(move |x| vec3(x as f32 + 16.5, y as f32 + 16.5, 47.5) / 64.0))
+    		.filter_map(|point| sculpt.get_leaf_material(point))
+    		.collect();
+    	assert_eq!(top.len(), 32 * 32);
+    	let second = top.iter().filter(|material| **material == 2).count() as f32 / top.len() as f32;
+    	assert!((second - 0.3).abs() < 0.05);
+    	// the inside is kept in large leaves
+    	assert!(sculpt.get_leaves().len() < 32 * 32 * 32 / 2);
+    	assert_eq!(sculpt.get_leaf_material(vec3(2.0, 2.0, 2.0)), None);
+    }
+
+    #[test]
+    fn soft_brushes_refine_less_at_the_edge() {
+    	let center = vec3(0.5, 0.5, 0.5);
+    	let stroke = |hardness: f32| {
+    		let mut sculpt = Sculpt::new(64);
+    		RoundBrushTip::new().add(&mut sculpt, center, 0.3, hardness, &MaterialMix::default());
+    		sculpt
+    	};
+
+    	let hard = stroke(1.0);
+    	let soft = stroke(0.2);
+
+    	assert!(soft.get_leaves().len() < hard.get_leaves().len());
+    	assert!(soft.is_solid(center) && soft.is_solid(center + vec3(0.05, 0.0, 0.0)));
+    	// coarse leaves at the edge can only reach as far as their center is covered
+    	assert!(!soft.is_solid(center + vec3(0.32, 0.0, 0.0)));
+
+    	let stamp = Stamp { shape: TipShape::Cube, center, size: 0.25 };
+    	assert_eq!(stamp.get_distance(center + vec3(0.125, -0.05, 0.0)), 0.5);
+    }
+
+    #[test]
+    fn round_brush_filler_contains_small_center_point() {
+    	let filler = RoundBrushTip::filler(0.5, vec3(0.5, 0.5, 0.5));
+    	assert!(filler(0.25, vec3(0.5, 0.5, 0.5)))
+    }
+
+    #[test]
+    fn round_brush_filler_contains_large_center_point() {
+    	let filler = RoundBrushTip::filler(0.5, vec3(0.5, 0.5, 0.5));
+    	assert!(filler(1.0, vec3(0.5, 0.5, 0.5)))
+    }
+
+    #[test]
+    fn round_brush_filler_contains_small_offcenter_point() {
+    	let filler = RoundBrushTip::filler(0.5, vec3(0.5, 0.5, 0.5));
+    	assert!(filler(0.05, vec3(0.75, 0.75, 0.75)))
+    }
+
+    #[test]
+    fn round_brush_filler_contains_large_offcenter_point() {
+    	let filler = RoundBrushTip::filler(0.5, vec3(0.5, 0.5, 0.5));
+    	assert!(filler(1.0, vec3(0.75, 0.75, 0.75)))
+    }
+
+    #[test]
+    fn round_brush_filler_contains_large_far_off_point() {
+    	let filler = RoundBrushTip::filler(0.5, vec3(0.5, 0.5, 0.5));
+    	assert!(filler(4.0, vec3(2.0, 2.0, 2.0)))
+    }
+
+    #[test]
+    fn round_brush_filler_does_not_contains_far_off_point() {
+    	let filler = RoundBrushTip::filler(0.5, vec3(0.5, 0.5, 0.5));
+    	assert!(!filler(0.25, vec3(2.0, 2.0, 2.0)))
+    }
+
+    #[test]
+    fn round_brush_container_contains_small_center_point() {
+    	let container = RoundBrushTip::container(0.5, vec3(0.5, 0.5, 0.5));
+    	assert!(container(0.25, vec3(0.5, 0.5, 0.5)))
+    }
+
+    #[test]
+    fn round_brush_container_does_not_contain_large_center_point() {
+    	let container = RoundBrushTip::container(0.5, vec3(0.5, 0.5, 0.5));
+    	assert!(!container(1.0, vec3(0.5, 0.5, 0.5)))
+    }
+
+    #[test]
+    fn round_brush_container_contains_small_offcenter_point() {
+    	let container = RoundBrushTip::container(0.5, vec3(0.5, 0.5, 0.5));
+    	assert!(container(0.05, vec3(0.75, 0.75, 0.75)))
+    }
+
+    #[test]
+    fn round_brush_container_does_not_contain_large_offcenter_point() {
+    	let container = RoundBrushTip::container(0.5, vec3(0.5, 0.5, 0.5));
+    	assert!(!container(1.0, vec3(0.75, 0.75, 0.75)))
+    }
+
+    #[test]
+    fn round_brush_container_does_not_contain_large_far_off_point() {
+    	let container = RoundBrushTip::container(0.5, vec3(0.5, 0.5, 0.5));
+    	assert!(!container(4.0, vec3(2.0, 2.0, 2.0)))
+    }
+
+    #[test]
+    fn round_brush_container_does_not_contains_far_off_point() {
+    	let container = RoundBrushTip::container(0.5, vec3(0.5, 0.5, 0.5));
+    	assert!(!container(0.25, vec3(2.0, 2.0, 2.0)))
+    }
+}
@@ -0,0 +1,428 @@
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+use std::time::Duration;
+
+use crate::aabb::Aabb;
+
+use glam::{Mat4, Vec2, Vec3, Vec4, vec2, vec3, vec4};
+
+/// An orbiting camera looking at a target point.
+///
+/// Uses the right-handed, +Z up coordinate system
+/// of the document.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+	pub target: Vec3,
+	pub yaw: f32,
+	pub pitch: f32,
+	pub distance: f32,
+	pub fov: f32,
+}
+
+impl Default for Camera {
+	/// The default camera looks at the front of the sculpt.
+	fn default() -> Self {
+		Self {
+			target: vec3(0.5, 0.5, 0.5),
+			yaw: 0.0,
+			pitch: 0.0,
+			distance: 2.0,
+			fov: PI / 4.0,
+		}
+	}
+}
+
+impl Camera {
+	/// Get a camera looking along +Z at the front of a box, like the front view.
+	///
+	/// Looking straight down the pole keeps screen y along world y,
+	/// so switching from the front view doesn't flip the image.
+	pub fn front(bounds: Aabb) -> Self {
+		let fov = PI / 4.0;
+		let extent = bounds.extent().max_element();
+
+		Self {
+			target: bounds.center(),
+			yaw: 0.0,
+			pitch: -FRAC_PI_2,
+			distance: extent / 2.0 + extent / 2.0 / (fov / 2.0).tan(),
+			fov,
+		}
+	}
+
+	/// Get the position of the camera.
+	pub fn eye(&self) -> Vec3 {
+		let offset = vec3(
+			-self.yaw.sin() * self.pitch.cos(),
+			-self.yaw.cos() * self.pitch.cos(),
+			self.pitch.sin(),
+		);
+
+		self.target + offset * self.distance
+	}
+
+	/// Get the direction the camera is looking.
+	pub fn forward(&self) -> Vec3 {
+		(self.target - self.eye()).normalize()
+	}
+
+	/// Get the direction to the right of the view.
+	pub fn right(&self) -> Vec3 {
+		vec3(self.yaw.cos(), -self.yaw.sin(), 0.0)
+	}
+
+	/// Get the direction to the top of the view.
+	pub fn up(&self) -> Vec3 {
+		self.right().cross(self.forward())
+	}
+
+	/// Get the ray through a point on the screen.
+	///
+	/// The screen coordinates go from 0 to 1, with y pointing down.
+	pub fn ray(&self, x: f32, y: f32, aspect: f32) -> (Vec3, Vec3) {
+		let scale = (self.fov / 2.0).tan();
+		let direction = self.forward()
+			+ self.right() * (x * 2.0 - 1.0) * scale * aspect
+			+ self.up() * (1.0 - y * 2.0) * scale;
+
+		(self.eye(), direction.normalize())
+	}
+
+	/// Get the point on the screen a position is seen at, and how far it is from the eye, the inverse of `ray`.
+	///
+	/// Returns None for positions behind the camera.
+	pub fn project(&self, position: Vec3, aspect: f32) -> Option<(Vec2, f32)> {
+		let offset = position - self.eye();
+		let depth = offset.dot(self.forward());
+		if depth <= 0.0 {
+			return None;
+		}
+		let scale = (self.fov / 2.0).tan() * depth;
+		let x = (offset.dot(self.right()) / (scale * aspect) + 1.0) / 2.0;
+		let y = (1.0 - offset.dot(self.up()) / scale) / 2.0;
+
+		Some((vec2(x, y), offset.length()))
+	}
+
+	/// Get the perspective view projection matrix of the camera.
+	pub fn view_projection(&self, aspect: f32) -> Mat4 {
+		let projection = Mat4::perspective_rh(self.fov, aspect, self.distance * 0.01, self.distance * 100.0);
+
+		projection * Mat4::look_at_rh(self.eye(), self.target, self.up())
+	}
+
+	/// Interpolate between two cameras.
+	///
+	/// The yaw takes the shortest way around.
+	pub fn lerp(&self, other: &Camera, t: f32) -> Camera {
+		let mut yaw_delta = (other.yaw - self.yaw).rem_euclid(TAU);
+		if yaw_delta > PI {
+			yaw_delta -= TAU;
+		}
+
+		Camera {
+			target: self.target.lerp(other.target, t),
+			yaw: self.yaw + yaw_delta * t,
+			pitch: (self.pitch + (other.pitch - self.pitch) * t).clamp(-FRAC_PI_2, FRAC_PI_2),
+			distance: self.distance + (other.distance - self.distance) * t,
+			fov: self.fov + (other.fov - self.fov) * t,
+		}
+	}
+}
+
+/// Turns pointer and gesture movement into camera moves.
+///
+/// Movement is given in fractions of the screen, so mice and
+/// trackpads of any resolution navigate the same way.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraController {
+	/// The radians turned when moving across the whole screen.
+	pub orbit_speed: f32,
+	/// How quickly zooming changes the distance to the target.
+	pub zoom_speed: f32,
+}
+
+impl Default for CameraController {
+	fn default() -> Self {
+		Self {
+			orbit_speed: PI,
+			zoom_speed: 1.0,
+		}
+	}
+}
+
+impl CameraController {
+	/// Turn the camera around its target, as if dragging the sculpt.
+	pub fn orbit(&self, camera: &mut Camera, delta: Vec2) {
+		camera.yaw = (camera.yaw - delta.x * self.orbit_speed).rem_euclid(TAU);
+		camera.pitch = (camera.pitch + delta.y * self.orbit_speed).clamp(-FRAC_PI_2, FRAC_PI_2);
+	}
+
+	/// Turn the camera around the vertical axis by an angle, like a turntable.
+	pub fn turn(&self, camera: &mut Camera, angle: f32) {
+		camera.yaw = (camera.yaw + angle).rem_euclid(TAU);
+	}
+
+	/// Slide the target across the view, keeping the sculpt under the pointer.
+	pub fn pan(&self, camera: &mut Camera, delta: Vec2) {
+		let scale = 2.0 * camera.distance * (camera.fov / 2.0).tan();
+
+		camera.target += (camera.up() * delta.y - camera.right() * delta.x) * scale;
+	}
+
+	/// Move towards (positive amounts) or away from the target.
+	pub fn zoom(&self, camera: &mut Camera, amount: f32) {
+		camera.distance = (camera.distance * (-amount * self.zoom_speed).exp()).max(1e-3);
+	}
+}
+
+/// A smooth move from one camera to another.
+#[derive(Clone, Copy, Debug)]
+pub struct CameraTransition {
+	from: Camera,
+	to: Camera,
+	duration: Duration,
+	elapsed: Duration,
+}
+
+impl CameraTransition {
+	/// Create a transition taking the given amount of time.
+	pub fn new(from: Camera, to: Camera, duration: Duration) -> Self {
+		Self {
+			from,
+			to,
+			duration,
+			elapsed: Duration::ZERO,
+		}
+	}
+
+	/// Advance the transition, returning the current camera.
+	pub fn advance(&mut self, delta: Duration) -> Camera {
+		self.elapsed = (self.elapsed + delta).min(self.duration);
+
+		self.camera()
+	}
+
+	/// Get the camera at the current point of the transition.
+	pub fn camera(&self) -> Camera {
+		if self.duration.is_zero() {
+			return self.to;
+		}
+
+		let t = self.elapsed.as_secs_f32() / self.duration.as_secs_f32();
+
+		self.from.lerp(&self.to, ease(t))
+	}
+
+	/// Check whether the transition has reached its end.
+	pub fn is_finished(&self) -> bool {
+		self.elapsed >= self.duration
+	}
+}
+
+/// Interpolate cameras through a list of keys.
+///
+/// Each segment between keys gets the same number of frames,
+/// and the final key is always included.
+pub fn camera_path(keys: &[Camera], frames_per_segment: u32) -> Vec<Camera> {
+	let mut path = Vec::new();
+
+	for pair in keys.windows(2) {
+		for frame in 0..frames_per_segment {
+			let t = frame as f32 / frames_per_segment as f32;
+			path.push(pair[0].lerp(&pair[1], ease(t)));
+		}
+	}
+
+	if let Some(last) = keys.last() {
+		path.push(*last);
+	}
+
+	path
+}
+
+/// Get an orthographic view looking along +Z at the front of a box.
+///
+/// Screen x follows world x and screen y points down along world y,
+/// matching the legacy viewport. Depth starts at the front of the box
+/// and reaches far enough for a long row of chunks behind it.
+pub fn front_view(bounds: Aabb) -> Mat4 {
+	let extent = bounds.extent();
+	let near = bounds.min.z;
+	let far = near + extent.max_element() * 64.0;
+
+	Mat4::from_cols(
+		vec4(2.0 / extent.x, 0.0, 0.0, 0.0),
+		vec4(0.0, -2.0 / extent.y, 0.0, 0.0),
+		vec4(0.0, 0.0, 1.0 / (far - near), 0.0),
+		vec4(
+			-(bounds.min.x + bounds.max.x) / extent.x,
+			(bounds.min.y + bounds.max.y) / extent.y,
+			-near / (far - near),
+			1.0,
+		),
+	)
+}
+
+/// The clipping planes of a view.
+///
+/// Planes point inward and use the 0 to 1 depth range of wgpu.
+#[derive(Clone, Copy, Debug)]
+pub struct Frustum {
+	planes: [Vec4; 6],
+}
+
+impl Frustum {
+	/// Extract the planes from a view projection matrix.
+	pub fn from_matrix(view_projection: Mat4) -> Self {
+		let x = view_projection.row(0);
+		let y = view_projection.row(1);
+		let z = view_projection.row(2);
+		let w = view_projection.row(3);
+
+		Self {
+			planes: [w + x, w - x, w + y, w - y, z, w - z],
+		}
+	}
+
+	/// Check whether any part of a box may be visible.
+	///
+	/// Conservative: boxes near the corners of the frustum can pass
+	/// without actually being inside of it.
+	pub fn intersects(&self, bounds: &Aabb) -> bool {
+		self.planes.iter().all(|plane| {
+			let normal = plane.truncate();
+			// the corner furthest along the plane normal
+			let corner = Vec3::select(normal.cmpge(Vec3::ZERO), bounds.max, bounds.min);
+
+			normal.dot(corner) + plane.w >= 0.0
+		})
+	}
+}
+
+/// Ease in and out of a transition.
+fn ease(t: f32) -> f32 {
+	let t = t.clamp(0.0, 1.0);
+
+	t * t * (3.0 - 2.0 * t)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use glam::vec2;
+
+    #[test]
+    fn default_camera_looks_at_target() {
+    	let camera = Camera::default();
+
+    	assert!((camera.forward() - vec3(0.0, 1.0, 0.0)).length() < 1e-5);
+    	assert!((camera.up() - vec3(0.0, 0.0, 1.0)).length() < 1e-5);
+
+    	let (origin, direction) = camera.ray(0.5, 0.5, 1.0);
+    	assert!((origin + direction * camera.distance - camera.target).length() < 1e-5);
+    }
+
+    #[test]
+    fn projecting_inverts_rays() {
+    	let camera = Camera { yaw: 0.7, pitch: 0.3, ..Camera::default() };
+    	let (origin, direction) = camera.ray(0.2, 0.9, 1.5);
+    	let (point, distance) = camera.project(origin + direction * 3.0, 1.5).unwrap();
+
+    	assert!((point - vec2(0.2, 0.9)).length() < 1e-4);
+    	assert!((distance - 3.0).abs() < 1e-4);
+    	assert_eq!(camera.project(origin - camera.forward(), 1.5), None);
+    }
+
+    #[test]
+    fn front_camera_matches_front_view() {
+    	let bounds = Aabb::new(vec3(0.0, 0.0, 0.0), vec3(1.0, 1.0, 1.0));
+    	let camera = Camera::front(bounds);
+
+    	assert!((camera.forward() - vec3(0.0, 0.0, 1.0)).length() < 1e-5);
+    	assert!((camera.up() - vec3(0.0, -1.0, 0.0)).length() < 1e-5);
+
+    	let view_projection = camera.view_projection(1.0);
+    	let center = view_projection.project_point3(bounds.center());
+    	assert!(center.truncate().length() < 1e-5);
+    	let corner = view_projection.project_point3(vec3(1.0, 1.0, 0.0));
+    	assert!((corner.truncate() - vec2(1.0, -1.0)).length() < 1e-4);
+    }
+
+    #[test]
+    fn controller_orbits_pans_and_zooms() {
+    	let controller = CameraController::default();
+    	let mut camera = Camera::default();
+
+    	controller.orbit(&mut camera, vec2(0.0, 1.0));
+    	assert_eq!(camera.pitch, FRAC_PI_2);
+
+    	let mut camera = Camera::default();
+    	controller.pan(&mut camera, vec2(0.5, 0.0));
+    	assert!(camera.target.x < 0.5);
+    	assert!((camera.target.y - 0.5).abs() < 1e-5);
+
+    	controller.zoom(&mut camera, 1.0);
+    	assert!(camera.distance < 2.0);
+    	controller.zoom(&mut camera, -1.0);
+    	assert!((camera.distance - 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn lerp_takes_the_shortest_yaw() {
+    	let from = Camera { yaw: 0.1, ..Default::default() };
+    	let to = Camera { yaw: TAU - 0.1, ..Default::default() };
+
+    	assert!((from.lerp(&to, 0.5).yaw).abs() < 1e-5);
+    }
+
+    #[test]
+    fn transition_ends_at_destination() {
+    	let to = Camera { distance: 4.0, ..Default::default() };
+    	let mut transition = CameraTransition::new(Camera::default(), to, Duration::from_millis(100));
+
+    	let halfway = transition.advance(Duration::from_millis(50));
+    	assert!((halfway.distance - 3.0).abs() < 1e-5);
+    	assert!(!transition.is_finished());
+
+    	assert_eq!(transition.advance(Duration::from_millis(80)), to);
+    	assert!(transition.is_finished());
+    }
+
+    #[test]
+    fn camera_path_includes_every_key() {
+    	let keys = [
+    		Camera::default(),
+    		Camera { distance: 3.0, ..Default::default() },
+    		Camera { distance: 1.0, ..Default::default() },
+    	];
+
+    	let path = camera_path(&keys, 4);
+    	assert_eq!(path.len(), 9);
+    	assert_eq!(path[0], keys[0]);
+    	assert_eq!(path[4], keys[1]);
+    	assert_eq!(path[8], keys[2]);
+    }
+
+    #[test]
+    fn front_view_maps_box_to_screen() {
+    	let bounds = Aabb::new(vec3(-1.0, 0.0, 2.0), vec3(1.0, 2.0, 4.0));
+    	let view = front_view(bounds);
+
+    	let top_left = view.project_point3(vec3(-1.0, 0.0, 2.0));
+    	assert!((top_left - vec3(-1.0, 1.0, 0.0)).length() < 1e-5);
+
+    	let bottom_right = view.project_point3(vec3(1.0, 2.0, 2.0));
+    	assert!((bottom_right - vec3(1.0, -1.0, 0.0)).length() < 1e-5);
+    }
+
+    #[test]
+    fn frustum_culls_boxes_outside_of_view() {
+    	let frustum = Frustum::from_matrix(front_view(Aabb::default()));
+
+    	assert!(frustum.intersects(&Aabb::default()));
+    	assert!(frustum.intersects(&Aabb::new(vec3(0.5, 0.5, 3.0), vec3(1.5, 1.5, 4.0))));
+    	assert!(!frustum.intersects(&Aabb::new(vec3(1.5, 0.0, 0.0), vec3(2.5, 1.0, 1.0))));
+    	assert!(!frustum.intersects(&Aabb::new(vec3(0.0, 0.0, -2.0), vec3(1.0, 1.0, -1.0))));
+    }
+}
@@ -0,0 +1,179 @@
+//! Color management, mapping the colors a document is shaded in onto the display.
+//!
+//! Material colors are taken to be in the working space of the document,
+//! and shading happens there. The final pass converts the colors to
+//! linear sRGB, scales them by the exposure and maps them onto the
+//! display with the tone mapping curve. The settings are saved with the
+//! document, so it looks the same in every session and on every machine.
+
+use glam::{Mat3, Vec3};
+
+/// The color space material colors are given and shaded in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WorkingSpace {
+	/// The primaries of sRGB and Rec. 709, without the transfer curve.
+	#[default]
+	LinearSrgb,
+	/// The AP1 primaries of ACES, for colors beyond the sRGB gamut.
+	AcesCg,
+	/// The wide gamut primaries of Rec. 2020.
+	Rec2020,
+}
+
+impl WorkingSpace {
+	/// Every working space, in the order they are listed.
+	pub const ALL: [WorkingSpace; 3] = [WorkingSpace::LinearSrgb, WorkingSpace::AcesCg, WorkingSpace::Rec2020];
+
+	/// Get the name the working space is selected by.
+	pub fn name(self) -> &'static str {
+		match self {
+			WorkingSpace::LinearSrgb => "srgb",
+			WorkingSpace::AcesCg => "acescg",
+			WorkingSpace::Rec2020 => "rec2020",
+		}
+	}
+
+	/// Find a working space by its name.
+	pub fn from_name(name: &str) -> Option<Self> {
+		Self::ALL.into_iter().find(|space| space.name() == name)
+	}
+
+	/// Get the matrix converting colors in the working space to linear sRGB.
+	pub fn to_linear_srgb(self) -> Mat3 {
+		// the rows of each matrix, adapted to the white point of sRGB
+		let rows = match self {
+			WorkingSpace::LinearSrgb => return Mat3::IDENTITY,
+			WorkingSpace::AcesCg => [
+				[1.705_051, -0.621_792, -0.083_259],
+				[-0.130_256, 1.140_805, -0.010_548],
+				[-0.024_003, -0.128_969, 1.152_972],
+			],
+			WorkingSpace::Rec2020 => [
+				[1.660_491, -0.587_641, -0.072_850],
+				[-0.124_550, 1.132_9, -0.008_349],
+				[-0.018_151, -0.100_579, 1.118_73],
+			],
+		};
+
+		Mat3::from_cols_array_2d(&rows).transpose()
+	}
+}
+
+/// How the final pass maps the rendered colors onto the display.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Tonemap {
+	/// Clip colors which are too bright.
+	None,
+	/// The ACES filmic curve.
+	#[default]
+	Aces,
+	/// The Uncharted 2 filmic curve, which keeps more of the shadows.
+	Filmic,
+}
+
+impl Tonemap {
+	/// Every tone mapping curve, in the order they are cycled through.
+	pub const ALL: [Tonemap; 3] = [Tonemap::None, Tonemap::Aces, Tonemap::Filmic];
+
+	/// Get the name the curve is selected by.
+	pub fn name(self) -> &'static str {
+		match self {
+			Tonemap::None => "none",
+			Tonemap::Aces => "aces",
+			Tonemap::Filmic => "filmic",
+		}
+	}
+
+	/// Find a curve by its name.
+	pub fn from_name(name: &str) -> Option<Self> {
+		Self::ALL.into_iter().find(|tonemap| tonemap.name() == name)
+	}
+
+	/// Get the curve which follows this one.
+	pub fn next(self) -> Self {
+		Self::ALL[(self as usize + 1) % Self::ALL.len()]
+	}
+
+	/// Map a linear color onto the display range, like the final pass does.
+	pub fn apply(self, color: Vec3) -> Vec3 {
+		match self {
+			Tonemap::None => color,
+			Tonemap::Aces => {
+				// the fit by Krzysztof Narkowicz
+				let x = color * 0.6;
+				(x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)
+			},
+			Tonemap::Filmic => {
+				let hable = |x: Vec3| (x * (0.15 * x + 0.05) + 0.004) / (x * (0.15 * x + 0.5) + 0.06) - 0.02 / 0.3;
+				hable(color * 2.0) / hable(Vec3::splat(11.2))
+			},
+		}
+		.clamp(Vec3::ZERO, Vec3::ONE)
+	}
+}
+
+/// The color management settings of a document.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorManagement {
+	pub working_space: WorkingSpace,
+	/// The display transform.
+	pub tonemap: Tonemap,
+	/// The exposure in stops, where each stop doubles the brightness.
+	pub exposure: f32,
+}
+
+impl Default for ColorManagement {
+	fn default() -> Self {
+		Self {
+			working_space: WorkingSpace::default(),
+			tonemap: Tonemap::default(),
+			exposure: 0.0,
+		}
+	}
+}
+
+impl ColorManagement {
+	/// Map a color in the working space onto the display range, still linear, like the final pass does.
+	pub fn apply(&self, color: Vec3) -> Vec3 {
+		let color = self.working_space.to_linear_srgb() * color;
+
+		self.tonemap.apply(color.max(Vec3::ZERO) * self.exposure.exp2())
+	}
+}
+
+/// Encode a linear channel with the sRGB curve, like the final pass of the viewport.
+pub fn linear_to_srgb(channel: f32) -> f32 {
+	if channel <= 0.0031308 {
+		channel * 12.92
+	} else {
+		1.055 * channel.powf(1.0 / 2.4) - 0.055
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    #[test]
+    fn working_spaces_keep_white() {
+    	for space in WorkingSpace::ALL {
+    		assert_eq!(WorkingSpace::from_name(space.name()), Some(space));
+    		assert!((space.to_linear_srgb() * Vec3::ONE - Vec3::ONE).abs().max_element() < 1e-3, "{space:?}");
+    	}
+    	// the green of a wider gamut is greener than sRGB can show
+    	assert!((WorkingSpace::AcesCg.to_linear_srgb() * Vec3::Y).x < 0.0);
+    }
+
+    #[test]
+    fn exposure_doubles_by_the_stop() {
+    	let mut color = ColorManagement { tonemap: Tonemap::None, ..ColorManagement::default() };
+    	assert_eq!(color.apply(Vec3::splat(0.25)), Vec3::splat(0.25));
+    	color.exposure = 1.0;
+    	assert_eq!(color.apply(Vec3::splat(0.25)), Vec3::splat(0.5));
+    	color.exposure = 4.0;
+    	assert_eq!(color.apply(Vec3::splat(0.25)), Vec3::ONE);
+
+    	assert_eq!(Tonemap::Filmic.next(), Tonemap::None);
+    	assert!(Tonemap::Aces.apply(Vec3::splat(100.0)).cmple(Vec3::ONE).all());
+    }
+}
@@ -0,0 +1,1103 @@
+use crate::aabb::Aabb;
+use crate::brush::SavedBrushes;
+use crate::color::ColorManagement;
+use crate::material::{Gradient, Material};
+use crate::sculpt::{Channel, Coverage, Issue, Sculpt, SculptHit, SculptSpan, VoxelGrid, Wear};
+use crate::util::progress::Progress;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use glam::{IVec3, Mat4, Vec3};
+
+mod backfaces;
+mod boolean;
+mod file;
+mod history;
+mod mesh;
+mod paint;
+mod stroke;
+pub mod watertight;
+pub use boolean::BooleanOp;
+pub use file::{Snapshot, DEFAULT_LEVEL};
+use file::SavedChunk;
+use history::History;
+use paint::PaintHistory;
+use stroke::Stroke;
+use watertight::SurfaceIssue;
+
+/// The most nodes the chunks of a document hold together before strokes lose detail, by default.
+///
+/// Nodes take around a hundred bytes each, so this is about a gigabyte.
+pub const DEFAULT_NODE_LIMIT: usize = 1 << 23;
+
+/// A note pinned to a place on the sculpt, like a review comment or a to-do.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pin {
+	/// Where the pin is, in the space of the document.
+	pub position: Vec3,
+	pub note: String,
+}
+
+/// A guide curve drawn on the sculpt, like a seam or an edge loop to follow.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Guide {
+	/// The points of the curve on the surface, in the space of the document, joined by straight lines.
+	pub points: Vec<Vec3>,
+}
+
+/// A sculpting space split into chunks.
+///
+/// Each chunk is its own sparse voxel octree, stored by its
+/// integer chunk coordinate, so the space can grow in any
+/// direction as needed.
+///
+/// A document opened from a file leaves its chunks there until they are
+/// edited or loaded, and until then they are left out of everything else.
+#[derive(Clone)]
+pub struct Document {
+	/// The chunks, shared with snapshots until either changes them.
+	chunks: HashMap<IVec3, Arc<Sculpt>>,
+	/// The chunks still in the file the document was opened from.
+	saved: HashMap<IVec3, SavedChunk>,
+	chunk_size: f32,
+	resolution: u32,
+	stroke: u32,
+	/// Counts the edits, to tell whether the document changed since a point.
+	revision: u64,
+	/// The stroke the following passes belong to, if one is open.
+	open_stroke: Option<Stroke>,
+	/// The edits to the chunks, which can be undone.
+	history: History,
+	/// The paint edits, kept apart from shape edits.
+	paint_history: PaintHistory,
+	palette: Vec<[f32; 4]>,
+	/// How much light scatters under the surface of each material, by its index.
+	subsurface: Vec<f32>,
+	/// The most nodes the chunks may hold together before strokes are refined less deeply, if limited.
+	node_limit: Option<usize>,
+	/// The coarsest depth strokes were limited to since this was last taken.
+	reduced_depth: Option<u32>,
+	pins: Vec<Pin>,
+	guides: Vec<Guide>,
+	color: ColorManagement,
+	/// The brush settings the document was saved or opened with.
+	brushes: SavedBrushes,
+	/// The strokes made with each brush since the document was created or opened, by its name.
+	brush_strokes: BTreeMap<String, u32>,
+	/// The inconsistencies fixed in chunks as they were read from the file, which is damaged if there were any.
+	repaired: usize,
+}
+
+impl Document {
+	/// Create a document with a single, empty chunk at the origin.
+	pub fn new(resolution: u32, chunk_size: f32) -> Self {
+		let mut document = Self {
+			chunks: HashMap::new(),
+			saved: HashMap::new(),
+			chunk_size,
+			resolution,
+			stroke: 0,
+			revision: 0,
+			open_stroke: None,
+			history: History::default(),
+			paint_history: PaintHistory::default(),
+			palette: vec![Material::default().color],
+			subsurface: Vec::new(),
+			node_limit: Some(DEFAULT_NODE_LIMIT),
+			reduced_depth: None,
+			pins: Vec::new(),
+			guides: Vec::new(),
+			color: ColorManagement::default(),
+			brushes: SavedBrushes::default(),
+			brush_strokes: BTreeMap::new(),
+			repaired: 0,
+		};
+		document.create_chunk(IVec3::ZERO);
+
+		document
+	}
+
+	/// Open a saved document, leaving its chunks in the file until they are needed.
+	pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+		file::open(path.as_ref())
+	}
+
+	/// Save the document to a file, replacing it.
+	pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+		self.save_with_level(path, DEFAULT_LEVEL)
+	}
+
+	/// Save the document to a file, compressing its chunks at a zstd level.
+	///
+	/// Higher levels make smaller files, but take longer to save.
+	pub fn save_with_level(&self, path: impl AsRef<Path>, level: i32) -> io::Result<()> {
+		self.snapshot().save(path.as_ref(), level, &Progress::new(""))
+	}
+
+	/// Take a snapshot of the document, to save in the background while it is edited further.
+	pub fn snapshot(&self) -> Snapshot {
+		Snapshot::new(self)
+	}
+
+	/// Get how many chunks are still in the file the document was opened from.
+	pub fn get_saved_count(&self) -> usize {
+		self.saved.len()
+	}
+
+	/// Count the inconsistencies fixed in chunks as they were read from the file the document was opened from.
+	pub fn get_repaired_count(&self) -> usize {
+		self.repaired
+	}
+
+	/// Read a chunk from the file the document was opened from, unless it was read already.
+	///
+	/// A chunk which can't be read is dropped, so it isn't tried again.
+	fn load_chunk(&mut self, coordinate: IVec3) -> io::Result<()> {
+		let Some(saved) = self.saved.remove(&coordinate) else {
+			return Ok(());
+		};
+		let chunk = file::load(self, coordinate, &saved)?;
+		self.chunks.insert(coordinate, Arc::new(chunk));
+
+		Ok(())
+	}
+
+	/// Read the chunks overlapping a box from the file the document was opened from.
+	///
+	/// Every chunk is tried, returning the first error.
+	pub fn load_chunks_in(&mut self, bounds: Aabb) -> io::Result<()> {
+		let coordinates: Vec<IVec3> = self.saved.keys()
+			.copied()
+			.filter(|coordinate| self.get_chunk_bounds(*coordinate).intersects(&bounds))
+			.collect();
+
+		coordinates.into_iter()
+			.map(|coordinate| self.load_chunk(coordinate))
+			.fold(Ok(()), Result::and)
+	}
+
+	/// Read up to a number of chunks from the file the document was opened from, nearest to a point first.
+	///
+	/// Returns how many were read.
+	pub fn load_nearest(&mut self, point: Vec3, count: usize) -> io::Result<usize> {
+		let mut coordinates: Vec<IVec3> = self.saved.keys().copied().collect();
+		coordinates.sort_by(|a, b| {
+			let distance = |coordinate: &IVec3| self.get_chunk_bounds(*coordinate).center().distance_squared(point);
+			distance(a).total_cmp(&distance(b))
+		});
+		coordinates.truncate(count);
+
+		let loaded = coordinates.len();
+		coordinates.into_iter().try_for_each(|coordinate| self.load_chunk(coordinate))?;
+
+		Ok(loaded)
+	}
+
+	/// Get the density of each chunk in voxels per axis.
+	pub fn get_resolution(&self) -> u32 {
+		self.resolution
+	}
+
+	/// Get the edge length of each chunk.
+	pub fn get_chunk_size(&self) -> f32 {
+		self.chunk_size
+	}
+
+	/// Get the edge length of the finest voxels.
+	pub fn get_voxel_size(&self) -> f32 {
+		self.chunk_size / self.resolution as f32
+	}
+
+	/// Get the space covered by a chunk.
+	pub fn get_chunk_bounds(&self, coordinate: IVec3) -> Aabb {
+		let min = coordinate.as_vec3() * self.chunk_size;
+
+		Aabb::new(min, min + self.chunk_size)
+	}
+
+	/// Get the coordinate of the chunk containing a point.
+	pub fn chunk_at(&self, point: Vec3) -> IVec3 {
+		(point / self.chunk_size).floor().as_ivec3()
+	}
+
+	/// Get the coordinates of all chunks in a stable order.
+	pub fn get_chunk_coordinates(&self) -> Vec<IVec3> {
+		let mut coordinates: Vec<IVec3> = self.chunks.keys().copied().collect();
+		coordinates.sort_by_key(|coordinate| (coordinate.z, coordinate.y, coordinate.x));
+
+		coordinates
+	}
+
+	/// Start a new stroke, returning its number.
+	///
+	/// While a stroke is open, every pass belongs to it instead.
+	pub fn begin_stroke(&mut self) -> u32 {
+		if let Some(stroke) = self.open_stroke.as_ref() {
+			return stroke.number;
+		}
+		self.stroke += 1;
+
+		self.stroke
+	}
+
+	/// Open a stroke made of several passes, like dragging the brush, until it is closed.
+	pub fn open_stroke(&mut self) {
+		self.close_stroke();
+		let number = self.begin_stroke();
+		self.open_stroke = Some(Stroke::new(number));
+	}
+
+	/// Close the open stroke, so the next pass starts a new one.
+	pub fn close_stroke(&mut self) {
+		self.open_stroke = None;
+	}
+
+	/// Check whether a stroke is open.
+	pub fn is_stroke_open(&self) -> bool {
+		self.open_stroke.is_some()
+	}
+
+	/// Run a pass of a stroke, keeping what the stroke builds up within a depth of the surface it started on.
+	///
+	/// Additions stay within the depth outside of the voxels, and
+	/// removals within the depth inside of them, however many passes
+	/// of an open stroke go over the same place.
+	pub fn limit_accumulation(&mut self, reach: Aabb, depth: f32, remove: bool, pass: impl FnOnce(&mut Document)) {
+		stroke::limit(self, reach, depth, remove, pass);
+	}
+
+	/// Put another document, like a version of this one saved earlier, in place of this one as an edit to it.
+	///
+	/// The node limit is kept, while the history of the edits before is dropped.
+	pub fn restore(&mut self, document: Document) {
+		let revision = self.revision;
+		let node_limit = self.node_limit;
+		*self = document;
+		self.revision = revision + 1;
+		self.node_limit = node_limit;
+	}
+
+	/// Get the number of the latest stroke.
+	pub fn get_stroke(&self) -> u32 {
+		self.stroke
+	}
+
+	/// Count a stroke made with a brush.
+	pub fn count_brush_stroke(&mut self, brush: &str) {
+		*self.brush_strokes.entry(brush.to_string()).or_default() += 1;
+	}
+
+	/// Get the strokes made with each brush since the document was created or opened, by its name.
+	pub fn get_brush_strokes(&self) -> &BTreeMap<String, u32> {
+		&self.brush_strokes
+	}
+
+	/// Share the loaded chunks as they are now, in the order of their coordinates.
+	///
+	/// Chunks are copied on write, so later edits leave the shared ones untouched.
+	pub fn latch_chunks(&self) -> Vec<(IVec3, Arc<Sculpt>)> {
+		self.get_chunk_coordinates()
+			.into_iter()
+			.map(|coordinate| (coordinate, Arc::clone(&self.chunks[&coordinate])))
+			.collect()
+	}
+
+	/// Get the number of edits made so far, which changes with every edit.
+	pub fn get_revision(&self) -> u64 {
+		self.revision
+	}
+
+	/// Get a chunk by its coordinate.
+	pub fn get_chunk(&self, coordinate: IVec3) -> Option<&Sculpt> {
+		self.chunks.get(&coordinate).map(Arc::as_ref)
+	}
+
+	/// Get the chunk at the origin, which always exists.
+	pub fn get_home_chunk(&self) -> &Sculpt {
+		&self.chunks[&IVec3::ZERO]
+	}
+
+	/// Make sure a chunk exists, returning it.
+	///
+	/// A chunk still in the file is read from it, or made empty if it can't be.
+	fn create_chunk(&mut self, coordinate: IVec3) -> &mut Sculpt {
+		let _ = self.load_chunk(coordinate);
+		let bounds = self.get_chunk_bounds(coordinate);
+		let resolution = self.resolution;
+		let (palette, subsurface) = (&self.palette, &self.subsurface);
+
+		Arc::make_mut(self.chunks.entry(coordinate).or_insert_with(|| {
+			let mut chunk = Sculpt::new_with_bounds(resolution, bounds);
+			chunk.set_palette(palette);
+			chunk.set_subsurface(subsurface);
+			Arc::new(chunk)
+		}))
+	}
+
+	/// Get the chunks overlapping a box.
+	///
+	/// Chunks still in the file are read first, and missing chunks are
+	/// created if requested.
+	pub fn get_chunks_in(&mut self, bounds: Aabb, create: bool) -> Vec<&mut Sculpt> {
+		// a chunk which can't be read is left out, like any missing chunk
+		let _ = self.load_chunks_in(bounds);
+		let mut coordinates: Vec<IVec3> = self.chunks.iter()
+			.filter(|(_, chunk)| chunk.get_bounds().intersects(&bounds))
+			.map(|(coordinate, _)| *coordinate)
+			.collect();
+		let mut missing = Vec::new();
+		if create {
+			let low = self.chunk_at(bounds.min);
+			let high = self.chunk_at(bounds.max);
+			for z in low.z..=high.z {
+				for y in low.y..=high.y {
+					for x in low.x..=high.x {
+						missing.push(IVec3::new(x, y, z));
+					}
+				}
+			}
+			missing.retain(|coordinate| !self.chunks.contains_key(coordinate));
+			coordinates.extend(&missing);
+		}
+		history::record(self, &coordinates);
+		for coordinate in missing {
+			self.create_chunk(coordinate);
+		}
+
+		self.revision += 1;
+		self.chunks.values_mut()
+			.filter(|chunk| chunk.get_bounds().intersects(&bounds))
+			.map(Arc::make_mut)
+			.collect()
+	}
+
+	/// Get a chunk to edit it, copying it first if it is shared.
+	fn chunk_mut(&mut self, coordinate: IVec3) -> Option<&mut Sculpt> {
+		if self.chunks.contains_key(&coordinate) {
+			history::record(self, &[coordinate]);
+		}
+		self.revision += 1;
+
+		self.chunks.get_mut(&coordinate).map(Arc::make_mut)
+	}
+
+	/// Get every loaded chunk to edit them, copying those which are shared.
+	fn chunks_mut(&mut self) -> impl Iterator<Item = &mut Sculpt> {
+		let coordinates: Vec<IVec3> = self.chunks.keys().copied().collect();
+		history::record(self, &coordinates);
+		self.revision += 1;
+
+		self.chunks.values_mut().map(Arc::make_mut)
+	}
+
+	/// Remove chunks which no longer contain any voxels.
+	///
+	/// The chunk at the origin is always kept.
+	pub fn prune(&mut self) {
+		self.chunks.retain(|coordinate, chunk| *coordinate == IVec3::ZERO || !chunk.is_empty());
+	}
+
+	/// Limit how many nodes the chunks may hold together, or let them grow without limit.
+	///
+	/// Strokes which would go over the limit are refined less deeply.
+	pub fn set_node_limit(&mut self, limit: Option<usize>) {
+		self.node_limit = limit;
+	}
+
+	/// Get how many nodes the chunks may hold together, if it is limited.
+	pub fn get_node_limit(&self) -> Option<usize> {
+		self.node_limit
+	}
+
+	/// Get how many nodes the loaded chunks hold together.
+	pub fn get_node_count(&self) -> usize {
+		self.chunks.values().map(|chunk| chunk.get_node_count()).sum()
+	}
+
+	/// Count the nodes of the loaded chunks at each depth of their octrees, with the roots at 0.
+	pub fn get_depth_counts(&self) -> Vec<usize> {
+		let mut counts = Vec::new();
+		for coordinate in self.get_chunk_coordinates() {
+			for (depth, count) in self.chunks[&coordinate].get_depth_counts().into_iter().enumerate() {
+				if counts.len() <= depth {
+					counts.resize(depth + 1, 0);
+				}
+				counts[depth] += count;
+			}
+		}
+
+		counts
+	}
+
+	/// Get the volume the loaded chunks fill with each material, by its index in the palette.
+	pub fn get_material_volumes(&self) -> BTreeMap<u32, f32> {
+		let mut volumes = BTreeMap::new();
+		// the chunks are added up in a stable order, as rounding depends on it
+		for coordinate in self.get_chunk_coordinates() {
+			for (material, volume) in self.chunks[&coordinate].get_material_volumes() {
+				*volumes.entry(material).or_default() += volume;
+			}
+		}
+
+		volumes
+	}
+
+	/// Get the deepest level a stroke reaching a box can refine to without going over the node limit.
+	///
+	/// The nodes a stroke makes are estimated from the surface of the box,
+	/// which is where strokes split nodes. None means the stroke refines to
+	/// single voxels, and otherwise the depth is kept for `take_reduced_depth`.
+	pub fn fit_node_limit(&mut self, reach: Aabb) -> Option<u32> {
+		let limit = self.node_limit?;
+		let count = self.get_node_count();
+		let fits = |depth: u32| {
+			let cells = ((reach.max - reach.min) / (self.chunk_size / (1u64 << depth) as f32)).max(Vec3::ONE);
+			let estimate = 4.0 * (cells.x * cells.y + cells.y * cells.z + cells.z * cells.x);
+			count as f32 + estimate <= limit as f32
+		};
+		let finest = self.resolution.ilog2();
+		if fits(finest) {
+			return None;
+		}
+
+		// past the limit already, strokes still change the coarsest nodes
+		let depth = (1..finest).rev().find(|depth| fits(*depth)).unwrap_or(1);
+		self.reduced_depth = Some(self.reduced_depth.map_or(depth, |reduced| reduced.min(depth)));
+
+		Some(depth)
+	}
+
+	/// Take the coarsest depth strokes were limited to by the node limit since this was last asked, if any.
+	pub fn take_reduced_depth(&mut self) -> Option<u32> {
+		self.reduced_depth.take()
+	}
+
+	/// Pin a note to a position, returning the index of the pin.
+	pub fn add_pin(&mut self, position: Vec3, note: impl Into<String>) -> usize {
+		self.revision += 1;
+		self.pins.push(Pin { position, note: note.into() });
+
+		self.pins.len() - 1
+	}
+
+	/// Remove a pin by its index, returning it if it exists.
+	///
+	/// The pins after it move up by one.
+	pub fn remove_pin(&mut self, index: usize) -> Option<Pin> {
+		if index >= self.pins.len() {
+			return None;
+		}
+		self.revision += 1;
+
+		Some(self.pins.remove(index))
+	}
+
+	/// Get the pins, in the order they were added.
+	pub fn get_pins(&self) -> &[Pin] {
+		&self.pins
+	}
+
+	/// Add a guide curve through points, returning the index of the guide.
+	pub fn add_guide(&mut self, points: Vec<Vec3>) -> usize {
+		self.revision += 1;
+		self.guides.push(Guide { points });
+
+		self.guides.len() - 1
+	}
+
+	/// Add a point to the end of a guide, returning false if there is no such guide.
+	pub fn extend_guide(&mut self, index: usize, point: Vec3) -> bool {
+		let Some(guide) = self.guides.get_mut(index) else {
+			return false;
+		};
+		self.revision += 1;
+		guide.points.push(point);
+
+		true
+	}
+
+	/// Remove a guide by its index, returning it if it exists.
+	///
+	/// The guides after it move up by one.
+	pub fn remove_guide(&mut self, index: usize) -> Option<Guide> {
+		if index >= self.guides.len() {
+			return None;
+		}
+		self.revision += 1;
+
+		Some(self.guides.remove(index))
+	}
+
+	/// Get the guides, in the order they were added.
+	pub fn get_guides(&self) -> &[Guide] {
+		&self.guides
+	}
+
+	/// Get a material from the palette.
+	pub fn get_material(&self, index: u32) -> Option<&Material> {
+		self.get_home_chunk().get_material(index)
+	}
+
+	/// Replace the palette of every chunk with a material of each color.
+	pub fn set_palette(&mut self, colors: &[[f32; 4]]) {
+		if self.palette == colors {
+			return;
+		}
+		self.palette = colors.to_vec();
+		self.revision += 1;
+		// the history gets chunks the palette as they are put back, so changing it isn't an edit
+		for chunk in self.chunks.values_mut().map(Arc::make_mut) {
+			chunk.set_palette(colors);
+			chunk.set_subsurface(&self.subsurface);
+		}
+	}
+
+	/// Get the colors of the palette.
+	pub fn get_palette(&self) -> &[[f32; 4]] {
+		&self.palette
+	}
+
+	/// Set how much light scatters under the surface of a material, between 0 for none and 1.
+	///
+	/// The strength stays with the index when the palette is recolored.
+	pub fn set_subsurface(&mut self, index: u32, strength: f32) {
+		let index = index as usize;
+		if self.subsurface.get(index).copied().unwrap_or(0.0) == strength {
+			return;
+		}
+		if self.subsurface.len() <= index {
+			self.subsurface.resize(index + 1, 0.0);
+		}
+		self.subsurface[index] = strength;
+		self.revision += 1;
+		for chunk in self.chunks.values_mut().map(Arc::make_mut) {
+			chunk.set_subsurface(&self.subsurface);
+		}
+	}
+
+	/// Get how much light scatters under the surface of each material, by its index.
+	///
+	/// Materials beyond the strengths scatter none.
+	pub fn get_subsurface(&self) -> &[f32] {
+		&self.subsurface
+	}
+
+	/// Set how the colors of the document are shown.
+	pub fn set_color_management(&mut self, color: ColorManagement) {
+		if self.color != color {
+			self.revision += 1;
+			self.color = color;
+		}
+	}
+
+	/// Get how the colors of the document are shown.
+	pub fn get_color_management(&self) -> ColorManagement {
+		self.color
+	}
+
+	/// Set the brush settings to save with the document.
+	///
+	/// They aren't an edit of the document, so they don't count as a change.
+	pub fn set_saved_brushes(&mut self, brushes: SavedBrushes) {
+		self.brushes = brushes;
+	}
+
+	/// Get the brush settings the document was saved or opened with.
+	pub fn get_saved_brushes(&self) -> &SavedBrushes {
+		&self.brushes
+	}
+
+	/// Check whether a point is inside of a solid voxel.
+	pub fn is_solid(&self, point: Vec3) -> bool {
+		self.chunks.get(&self.chunk_at(point)).is_some_and(|chunk| chunk.is_solid(point))
+	}
+
+	/// Get the material of the solid voxel containing a point, if there is one.
+	pub fn get_material_at(&self, point: Vec3) -> Option<u32> {
+		self.chunks.get(&self.chunk_at(point)).and_then(|chunk| chunk.get_leaf_material(point))
+	}
+
+	/// Find the closest leaf voxel along a ray in any chunk.
+	///
+	/// Chunks are tried in a stable order, so ties on their borders always
+	/// go to the same one, rather than to whichever the hashing puts first.
+	pub fn raycast(&self, origin: Vec3, direction: Vec3) -> Option<SculptHit> {
+		self.get_chunk_coordinates()
+			.iter()
+			.filter_map(|coordinate| self.chunks[coordinate].raycast(origin, direction))
+			.min_by(|a, b| a.distance.total_cmp(&b.distance))
+	}
+
+	/// Find every surface along a ray, both where it enters and where it leaves the voxels, in order.
+	///
+	/// Surfaces between chunks are left out, as the voxels carry on across them.
+	pub fn raycast_all(&self, origin: Vec3, direction: Vec3) -> Vec<SculptHit> {
+		let spans = self.get_chunk_coordinates()
+			.iter()
+			.flat_map(|coordinate| self.chunks[coordinate].raycast_spans(origin, direction))
+			.collect();
+
+		SculptSpan::merge(spans, self.get_voxel_size() * 1e-3 / direction.length())
+			.into_iter()
+			.flat_map(|span| [span.entry, span.exit])
+			.collect()
+	}
+
+	/// Set the mask of every leaf from its center and size.
+	///
+	/// The masks are worked out before any are set, so they only
+	/// depend on the shape of the sculpt.
+	pub fn mask_by(&mut self, mask: impl Fn(&Document, Vec3, f32) -> f32) {
+		let masks: Vec<(IVec3, Vec<f32>)> = self.get_chunk_coordinates()
+			.into_iter()
+			.map(|coordinate| {
+				let leaves = self.chunks[&coordinate].get_leaves();
+				(coordinate, leaves.into_iter().map(|(center, size)| mask(self, center, size)).collect())
+			})
+			.collect();
+
+		for (coordinate, masks) in masks {
+			if let Some(chunk) = self.chunk_mut(coordinate) {
+				chunk.set_leaf_masks(&masks);
+			}
+		}
+	}
+
+	/// Mask the leaves in cavities, like creases and crevices.
+	///
+	/// The cavity is measured by how much of the space around each
+	/// surface leaf, a radius beyond its own bounds, is solid. Inverting
+	/// masks exposed areas like edges and peaks instead.
+	pub fn mask_by_cavity(&mut self, radius: f32, invert: bool) {
+		// the share of solid probes around a leaf just under a flat surface
+		const FLAT: f32 = 17.0 / 26.0;
+
+		self.mask_by(|document, center, size| {
+			let Some((solid, _)) = document.probe_surface(center, size / 2.0 + radius) else {
+				return 0.0;
+			};
+			let cavity = if invert { FLAT - solid } else { solid - FLAT };
+
+			(cavity / (1.0 - FLAT)).clamp(0.0, 1.0)
+		});
+	}
+
+	/// Mask the leaves turned away from a direction by more than an angle.
+	///
+	/// The mask fades in over a tenth of a radian, so its edge is soft.
+	pub fn mask_by_facing(&mut self, toward: Vec3, max_angle: f32) {
+		let toward = toward.normalize_or_zero();
+		let voxel_size = self.get_voxel_size();
+
+		self.mask_by(|document, center, size| {
+			let Some((_, normal)) = document.probe_surface(center, size / 2.0 + 2.0 * voxel_size) else {
+				return 0.0;
+			};
+			let angle = normal.dot(toward).clamp(-1.0, 1.0).acos();
+
+			((angle - max_angle) / 0.1).clamp(0.0, 1.0)
+		});
+	}
+
+	/// Mask every leaf except the ones holding a point, like the surface seen through each pixel of a view.
+	pub fn mask_except(&mut self, points: &[Vec3]) {
+		let voxel_size = self.get_voxel_size();
+		let levels = self.resolution.ilog2();
+		// the cell holding each point at every octree level, so a leaf of any size is looked up at once
+		let cells: HashSet<(u32, IVec3)> = points.iter()
+			.flat_map(|point| (0..=levels).map(move |level| (level, (*point / (voxel_size * (1 << level) as f32)).floor().as_ivec3())))
+			.collect();
+
+		self.mask_by(|_, center, size| {
+			let level = (size / voxel_size).log2().round() as u32;
+
+			if cells.contains(&(level, (center / size).floor().as_ivec3())) { 0.0 } else { 1.0 }
+		});
+	}
+
+	/// Get the box around every solid voxel, if there are any.
+	pub fn get_solid_bounds(&self) -> Option<Aabb> {
+		self.chunks.values()
+			.filter_map(|chunk| chunk.get_solid_bounds())
+			.reduce(|bounds, chunk| Aabb::new(bounds.min.min(chunk.min), bounds.max.max(chunk.max)))
+	}
+
+	/// Set the material of every voxel by a gradient, leaving the shape alone.
+	pub fn fill_gradient(&mut self, gradient: &Gradient) {
+		for chunk in self.chunks_mut() {
+			chunk.fill_materials(&|center, _| gradient.pick(center));
+		}
+	}
+
+	/// Wear down the convex edges of every chunk.
+	///
+	/// The surroundings of the solid voxels of every chunk are sampled
+	/// before any is worn, so the chunks don't see each other's wear.
+	pub fn wear(&mut self, wear: &Wear) {
+		let voxel_size = self.get_voxel_size();
+		let margin = wear.scale.max(1) as i32;
+		let surroundings: Vec<(IVec3, VoxelGrid)> = self.get_chunk_coordinates()
+			.into_iter()
+			.filter_map(|coordinate| {
+				let (low, high) = stroke::cells_around(self.chunks[&coordinate].get_solid_bounds()?, voxel_size, margin);
+				Some((coordinate, self.get_voxel_grid(low, high)))
+			})
+			.collect();
+
+		for (coordinate, surroundings) in surroundings {
+			if let Some(chunk) = self.chunk_mut(coordinate) {
+				chunk.wear(&surroundings, Vec3::ZERO, wear);
+			}
+		}
+		self.prune();
+	}
+
+	/// Collapse every chunk's subtrees below a depth, for a blocky look.
+	pub fn quantize(&mut self, depth: u32) {
+		for chunk in self.chunks_mut() {
+			chunk.quantize(depth);
+		}
+		self.prune();
+	}
+
+	/// Sample the finest voxel cells in a box, across chunks.
+	///
+	/// Cells are counted from the origin of the document. The low
+	/// cell is inclusive, and the high cell is exclusive.
+	pub fn get_voxel_grid(&self, low: IVec3, high: IVec3) -> VoxelGrid {
+		let mut grid = VoxelGrid::new(low, high);
+		let voxel_size = self.get_voxel_size();
+		let bounds = Aabb::new(low.as_vec3() * voxel_size, high.as_vec3() * voxel_size);
+		for chunk in self.chunks.values().filter(|chunk| chunk.get_bounds().intersects(&bounds)) {
+			chunk.sample_voxels(&mut grid, Vec3::ZERO);
+		}
+
+		grid
+	}
+
+	/// Run a stroke without changing the surface facing away from a direction by more than an angle.
+	///
+	/// Only the voxels within reach of the stroke are checked, so the far
+	/// side of thin features is kept while the near side is sculpted.
+	pub fn protect_backfaces(&mut self, reach: Aabb, toward: Vec3, max_angle: f32, stroke: impl FnOnce(&mut Document)) {
+		backfaces::protect(self, reach, toward, max_angle, stroke);
+	}
+
+	/// Combine the voxels of another document with this one, mapping points of this one into it by a matrix.
+	///
+	/// The matrix has to scale every axis alike. Added voxels take the
+	/// material of the other document.
+	pub fn apply_boolean(&mut self, cutter: &Document, to_cutter: Mat4, op: BooleanOp) {
+		boolean::apply(self, cutter, to_cutter, op);
+	}
+
+	/// Get the faces between solid voxels and empty space, as quads wound counter-clockwise seen from outside.
+	pub fn get_surface_quads(&self) -> Vec<[Vec3; 4]> {
+		mesh::surface(self)
+	}
+
+	/// Get the solid voxels, counted in voxels from the origin, with their materials.
+	pub fn get_solid_cells(&self) -> Vec<(IVec3, u32)> {
+		mesh::solid_cells(self)
+	}
+
+	/// Find where the surface isn't watertight, so an exported mesh of it would be broken.
+	pub fn check_watertight(&self) -> Vec<SurfaceIssue> {
+		watertight::check(self)
+	}
+
+	/// Check every chunk for inconsistencies, by chunk coordinate.
+	pub fn validate(&self) -> Vec<(IVec3, Issue)> {
+		self.chunks.iter()
+			.flat_map(|(coordinate, chunk)| chunk.validate().into_iter().map(|issue| (*coordinate, issue)))
+			.collect()
+	}
+
+	/// Fix the inconsistencies of the chunks `validate` flags, returning how many there were.
+	///
+	/// Chunks without any are left alone, so a clean document gains no edit to undo.
+	pub fn repair(&mut self) -> usize {
+		let mut damaged: Vec<IVec3> = self.validate().into_iter().map(|(coordinate, _)| coordinate).collect();
+		damaged.dedup();
+		if damaged.is_empty() {
+			return 0;
+		}
+		history::record(self, &damaged);
+		self.revision += 1;
+		let count = self.chunks.iter_mut()
+			.filter(|(coordinate, _)| damaged.contains(coordinate))
+			.map(|(_, chunk)| Arc::make_mut(chunk).repair().len())
+			.sum();
+		self.prune();
+
+		count
+	}
+
+	/// Unmask every voxel.
+	pub fn clear_mask(&mut self) {
+		for chunk in self.chunks_mut() {
+			chunk.clear_mask();
+		}
+	}
+
+	/// Paint the voxels selected by a classification in a box with a packed color, leaving their shape alone.
+	///
+	/// Paint edits have their own history, so they can be undone
+	/// without undoing the shape edits made since, and the other way around.
+	pub fn paint(&mut self, bounds: Aabb, classify: &dyn Fn(f32, Vec3) -> Coverage, paint: u32) {
+		let coordinates: Vec<IVec3> = self.get_chunk_coordinates()
+			.into_iter()
+			.filter(|coordinate| self.get_chunk_bounds(*coordinate).intersects(&bounds))
+			.collect();
+		paint::paint(self, &coordinates, classify, paint);
+	}
+
+	/// Remove the paint of every voxel, which can be undone like any paint edit.
+	pub fn clear_paint(&mut self) {
+		let coordinates = self.get_chunk_coordinates();
+		paint::paint(self, &coordinates, &|_, _| Coverage::Solid, 0);
+	}
+
+	/// Undo the latest edit of the chunks, paint included, returning false if there is none.
+	///
+	/// The chunks the edit changed are put back as they were before it.
+	pub fn undo(&mut self) -> bool {
+		history::undo(self)
+	}
+
+	/// Redo the latest undone edit of the chunks, returning false if there is none.
+	pub fn redo(&mut self) -> bool {
+		history::redo(self)
+	}
+
+	/// Undo the latest paint edit, returning false if there is none.
+	pub fn undo_paint(&mut self) -> bool {
+		paint::undo(self)
+	}
+
+	/// Redo the latest undone paint edit, returning false if there is none.
+	pub fn redo_paint(&mut self) -> bool {
+		paint::redo(self)
+	}
+
+	/// Get the packed paint at a point, which is 0 where nothing is painted.
+	pub fn get_paint(&self, point: Vec3) -> u32 {
+		self.chunks.get(&self.chunk_at(point)).map_or(0, |chunk| chunk.get_paint(point))
+	}
+
+	/// Probe the 26 neighbors of a point at a distance along each axis.
+	///
+	/// Returns the share of probes inside of solid voxels and the
+	/// direction away from them, or None when every probe is solid
+	/// (or none is), so the point is nowhere near the surface.
+	fn probe_surface(&self, center: Vec3, distance: f32) -> Option<(f32, Vec3)> {
+		let mut solid = 0;
+		let mut away = Vec3::ZERO;
+		for z in -1..=1 {
+			for y in -1..=1 {
+				for x in -1..=1 {
+					if (x, y, z) == (0, 0, 0) {
+						continue;
+					}
+					// probing the corners of a cube rather than a sphere keeps a flat
+					// surface at the same share of solid probes for any size of leaf
+					let offset = IVec3::new(x, y, z).as_vec3();
+					if self.is_solid(center + offset * distance) {
+						solid += 1;
+						away -= offset.normalize();
+					}
+				}
+			}
+		}
+
+		if solid == 0 || solid == 26 {
+			return None;
+		}
+
+		Some((solid as f32 / 26.0, away.normalize_or_zero()))
+	}
+
+	/// Get an attribute channel buffer of every chunk.
+	pub fn get_channel_buffers(&self, channel: Channel) -> Vec<(IVec3, Vec<f32>)> {
+		self.get_chunk_coordinates()
+			.into_iter()
+			.map(|coordinate| (coordinate, self.chunks[&coordinate].get_channel_buffer(channel)))
+			.collect()
+	}
+
+	/// Get the paint buffer of every chunk.
+	pub fn get_paint_buffers(&self) -> Vec<(IVec3, Vec<u32>)> {
+		self.get_chunk_coordinates()
+			.into_iter()
+			.map(|coordinate| (coordinate, self.chunks[&coordinate].get_paint_buffer()))
+			.collect()
+	}
+
+	/// Get the voxel buffer of every chunk.
+	pub fn get_voxel_buffers(&self) -> Vec<(IVec3, Vec<u32>)> {
+		self.get_chunk_coordinates()
+			.into_iter()
+			.map(|coordinate| (coordinate, self.chunks[&coordinate].get_voxel_buffer()))
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    use crate::brush::{RoundBrushTip, SquareBrushTip};
+    use crate::sculpt::WearEffect;
+    use glam::vec3;
+
+    #[test]
+    fn chunk_at_rounds_down() {
+    	let document = Document::new(16, 2.0);
+
+    	assert_eq!(document.chunk_at(vec3(0.5, 1.5, 3.0)), IVec3::new(0, 0, 1));
+    	assert_eq!(document.chunk_at(vec3(-0.5, -2.5, 0.0)), IVec3::new(-1, -2, 0));
+    	assert_eq!(document.get_chunk_bounds(IVec3::new(-1, 0, 0)), Aabb::new(vec3(-2.0, 0.0, 0.0), vec3(0.0, 2.0, 2.0)));
+    }
+
+    #[test]
+    fn strokes_across_boundaries_create_chunks() {
+    	let mut document = Document::new(16, 1.0);
+    	let position = vec3(1.0, 0.5, 0.5);
+    	let bounds = Aabb::cube(position, 0.5);
+
+    	for chunk in document.get_chunks_in(bounds, true) {
+    		chunk.subdivide(RoundBrushTip::filler(0.25, position), RoundBrushTip::container(0.25, position));
+    	}
+
+    	assert_eq!(document.get_chunk_coordinates(), vec![IVec3::ZERO, IVec3::new(1, 0, 0)]);
+    	assert!(document.is_solid(vec3(0.9, 0.5, 0.5)));
+    	assert!(document.is_solid(vec3(1.1, 0.5, 0.5)));
+
+    	let hit = document.raycast(vec3(1.5, 0.5, 0.5), vec3(-1.0, 0.0, 0.0)).unwrap();
+    	assert!(hit.position.x > 1.0);
+
+    	// the shared face of the chunks isn't a surface
+    	let hits = document.raycast_all(vec3(1.5, 0.5, 0.5), vec3(-1.0, 0.0, 0.0));
+    	assert_eq!(hits.len(), 2);
+    	assert!(hits[1].position.x < 1.0 && hits[1].normal == vec3(-1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn gradients_fill_materials_across_chunks() {
+    	let mut document = Document::new(16, 1.0);
+    	assert_eq!(document.get_solid_bounds(), None);
+    	let position = vec3(1.0, 0.5, 0.5);
+    	for chunk in document.get_chunks_in(Aabb::cube(position, 1.0), true) {
+    		chunk.subdivide(SquareBrushTip::filler(0.5, position), SquareBrushTip::container(0.5, position));
+    	}
+    	let bounds = document.get_solid_bounds().unwrap();
+    	assert_eq!(bounds, Aabb::cube(position, 1.0));
+    	let leaves: usize = document.chunks.values().map(|chunk| chunk.get_leaves().len()).sum();
+
+    	document.fill_gradient(&Gradient { start: bounds.min, end: bounds.max.with_y(0.0).with_z(0.0), ramp: vec![1, 2, 3, 4] });
+    	let material_at = |x: f32| {
+    		let point = vec3(x, 0.5, 0.5);
+    		document.get_chunk(document.chunk_at(point)).and_then(|chunk| chunk.get_leaf_material(point))
+    	};
+    	assert_eq!(material_at(0.6), Some(1));
+    	assert_eq!(material_at(0.9), Some(2));
+    	assert_eq!(material_at(1.1), Some(3));
+    	assert_eq!(material_at(1.4), Some(4));
+
+    	// leaves across the bands were split, and merge back once they are alike
+    	let count = |document: &Document| document.chunks.values().map(|chunk| chunk.get_leaves().len()).sum::<usize>();
+    	assert!(count(&document) > leaves);
+    	document.fill_gradient(&Gradient { start: bounds.min, end: bounds.max, ramp: vec![5] });
+    	assert_eq!(count(&document), leaves);
+    	assert!(document.is_solid(vec3(0.6, 0.5, 0.5)) && !document.is_solid(vec3(0.4, 0.5, 0.5)));
+    }
+
+    #[test]
+    fn wear_finds_convex_edges() {
+    	let block = || {
+    		let mut document = Document::new(32, 1.0);
+    		let center = vec3(0.5, 0.5, 0.5);
+    		for chunk in document.get_chunks_in(Aabb::cube(center, 0.5), true) {
+    			chunk.subdivide(SquareBrushTip::filler(0.25, center), SquareBrushTip::container(0.25, center));
+    		}
+    		document
+    	};
+    	let cell = |x: i32, y: i32, z: i32| (IVec3::new(x, y, z).as_vec3() + 0.5) / 32.0;
+    	// the block covers cells 8 to 23 along each axis
+    	let (corner, edge, face) = (cell(8, 8, 8), cell(16, 8, 8), cell(16, 16, 8));
+
+    	let mut worn = block();
+    	worn.wear(&Wear { intensity: 1.0, scale: 2, effect: WearEffect::Material(3) });
+    	let material_at = |point: Vec3| worn.get_home_chunk().get_leaf_material(point);
+    	assert_eq!(material_at(corner), Some(3));
+    	assert_eq!(material_at(edge), Some(3));
+    	assert_eq!(material_at(face), Some(0));
+    	assert_eq!(material_at(cell(16, 16, 16)), Some(0));
+
+    	let mut eroded = block();
+    	eroded.wear(&Wear { intensity: 1.0, scale: 2, effect: WearEffect::Erode });
+    	assert!(!eroded.is_solid(corner) && !eroded.is_solid(edge));
+    	assert!(eroded.is_solid(face));
+    	assert!(eroded.validate().is_empty());
+
+    	// without intensity nothing wears
+    	let mut untouched = block();
+    	untouched.wear(&Wear { intensity: 0.0, scale: 2, effect: WearEffect::Erode });
+    	assert!(untouched.is_solid(corner));
+    }
+
+    #[test]
+    fn masks_follow_cavities_and_facing() {
+    	let mut document = Document::new(64, 1.0);
+    	let (center, pit) = (vec3(0.5, 0.5, 0.31), vec3(0.5, 0.5, 0.6));
+    	// a block with a pit in the middle of its top
+    	for chunk in document.get_chunks_in(Aabb::cube(center, 0.6), true) {
+    		chunk.subdivide(SquareBrushTip::filler(0.3, center), SquareBrushTip::container(0.3, center));
+    		chunk.unsubdivide(SquareBrushTip::filler(0.065, pit), SquareBrushTip::container(0.065, pit));
+    	}
+    	let voxel = document.get_voxel_size();
+    	let cell = |x: f32, y: f32, z: f32| (vec3(x, y, z) + 0.5) * voxel;
+    	let mask_at = |document: &Document, point: Vec3| document.get_home_chunk().get_mask(point);
+    	// just under the floor of the pit next to its wall, at its rim, and on a side of the block
+    	let (floor, rim, side) = (cell(24.0, 32.0, 31.0), cell(23.0, 32.0, 39.0), cell(12.0, 32.0, 20.0));
+
+    	document.mask_by_cavity(voxel, false);
+    	assert!(mask_at(&document, floor) > 0.0);
+    	assert_eq!(mask_at(&document, rim), 0.0);
+    	assert_eq!(mask_at(&document, side), 0.0);
+
+    	document.mask_by_cavity(voxel, true);
+    	assert!(mask_at(&document, rim) > 0.0);
+    	assert_eq!(mask_at(&document, floor), 0.0);
+    	assert_eq!(mask_at(&document, side), 0.0);
+
+    	document.mask_by_facing(vec3(0.0, 0.0, 1.0), 0.5);
+    	assert_eq!(mask_at(&document, rim), 0.0);
+    	assert_eq!(mask_at(&document, side), 1.0);
+
+    	document.clear_mask();
+    	assert_eq!(mask_at(&document, side), 0.0);
+
+    	// seen from above, the top of the block is left unmasked, and its side isn't
+    	let top = cell(12.0, 32.0, 39.0);
+    	document.mask_except(&[top, floor]);
+    	assert_eq!(mask_at(&document, top), 0.0);
+    	assert_eq!(mask_at(&document, floor), 0.0);
+    	assert_eq!(mask_at(&document, side), 1.0);
+    	assert_eq!(mask_at(&document, rim), 1.0);
+    }
+
+    #[test]
+    fn prune_keeps_home_chunk() {
+    	let mut document = Document::new(16, 1.0);
+    	document.get_chunks_in(Aabb::cube(vec3(1.0, 1.0, 1.0), 0.5), true);
+    	assert_eq!(document.get_chunk_coordinates().len(), 8);
+
+    	document.prune();
+
+    	assert_eq!(document.get_chunk_coordinates(), vec![IVec3::ZERO]);
+    }
+}
@@ -0,0 +1,169 @@
+//! Keeps strokes off the far side of thin features.
+//!
+//! Before a stroke, a normal is estimated for each of the finest surface
+//! leaves it can reach, from which of their neighbors are solid. Every
+//! other cell belongs to the closest of those leaves. Whatever the stroke
+//! changes in the cells belonging to leaves facing away from the camera
+//! is put back afterwards, from a copy of the voxels.
+
+use super::Document;
+use super::stroke::{self, cells, cells_around, FACES};
+use crate::aabb::Aabb;
+use crate::sculpt::VoxelGrid;
+
+use std::collections::VecDeque;
+
+use glam::{IVec3, Vec3};
+
+/// Estimate the normal of a solid cell from its solid neighbors.
+///
+/// The normal is zero inside of the voxels, and for cells with empty space on opposite sides.
+fn normal(grid: &VoxelGrid, cell: IVec3) -> Vec3 {
+	let mut away = Vec3::ZERO;
+	for offset in cells(IVec3::NEG_ONE, IVec3::splat(2)).filter(|offset| *offset != IVec3::ZERO) {
+		if grid.get(cell + offset) {
+			away -= offset.as_vec3().normalize();
+		}
+	}
+
+	// balanced neighbors only leave rounding errors, which are far shorter than any one neighbor
+	if away.length() < 0.5 {
+		return Vec3::ZERO;
+	}
+
+	away.normalize()
+}
+
+/// Mark the cells belonging to surface leaves turned away from a direction by more than an angle.
+fn facing_away(grid: &VoxelGrid, toward: Vec3, max_angle: f32) -> VoxelGrid {
+	let (low, high) = (grid.get_low(), grid.get_high());
+	let mut away = VoxelGrid::new(low, high);
+
+	// a search outward from every surface leaf at once lets the closest one claim each cell
+	let mut claimed = VoxelGrid::new(low, high);
+	let mut queue = VecDeque::new();
+	// the cells on the border of the grid only see some of their neighbors
+	for cell in cells(low + 1, high - 1) {
+		if !grid.get(cell) || FACES.iter().all(|face| grid.get(cell + *face)) {
+			continue;
+		}
+		let normal = normal(grid, cell);
+		if normal == Vec3::ZERO {
+			continue;
+		}
+
+		claimed.set(cell, true);
+		away.set(cell, normal.dot(toward).clamp(-1.0, 1.0).acos() > max_angle);
+		queue.push_back(cell);
+	}
+
+	while let Some(cell) = queue.pop_front() {
+		for face in FACES {
+			let next = cell + face;
+			if grid.contains(next) && !claimed.get(next) {
+				claimed.set(next, true);
+				away.set(next, away.get(cell));
+				queue.push_back(next);
+			}
+		}
+	}
+
+	away
+}
+
+/// Run a stroke, then undo what it changed on the surface facing away from a direction.
+pub fn protect(document: &mut Document, reach: Aabb, toward: Vec3, max_angle: f32, stroke: impl FnOnce(&mut Document)) {
+	// a margin of cells, so the cells at the edge of the reach see their neighbors
+	let (low, high) = cells_around(reach, document.get_voxel_size(), 1);
+	let before = document.get_voxel_grid(low, high);
+	let away = facing_away(&before, toward.normalize_or_zero(), max_angle);
+
+	stroke::keep(document, &before, &away, stroke);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    use crate::brush::{Brush, RoundBrushTip};
+    use crate::sculpt::Coverage;
+
+    use glam::vec3;
+
+    /// A document with 32 voxels per chunk, holding a plate four voxels thick facing along z.
+    fn plate() -> Document {
+    	let mut document = Document::new(32, 1.0);
+    	let (low, high) = (IVec3::new(4, 4, 12), IVec3::new(28, 28, 16));
+    	for chunk in document.get_chunks_in(Aabb::new(Vec3::ZERO, Vec3::ONE), true) {
+    		chunk.apply(&|size, node| {
+    			let node_low = ((node - size / 2.0) * 32.0).round().as_ivec3();
+    			let node_high = ((node + size / 2.0) * 32.0).round().as_ivec3();
+    			if node_low.cmpge(low).all() && node_high.cmple(high).all() {
+    				Coverage::Solid
+    			} else if node_low.cmplt(high).all() && node_high.cmpgt(low).all() {
+    				Coverage::Mixed
+    			} else {
+    				Coverage::Unchanged
+    			}
+    		});
+    	}
+
+    	document
+    }
+
+    /// Stroke the front of the plate, with or without keeping the back of it.
+    fn stroke(remove: bool, protected: bool) -> Document {
+    	let mut document = plate();
+    	let mut brush = Brush::new("round".to_owned(), Box::new(RoundBrushTip::new()));
+    	brush.set_size(0.15);
+    	let position = vec3(0.5, 0.5, 12.0 / 32.0);
+    	let reach = brush.get_reach(&document, position);
+    	let mut stroke = |document: &mut Document| if remove {
+    		brush.remove(document, position);
+    	} else {
+    		brush.add(document, position);
+    	};
+
+    	if protected {
+    		protect(&mut document, reach, Vec3::NEG_Z, 60f32.to_radians(), stroke);
+    	} else {
+    		stroke(&mut document);
+    	}
+
+    	document
+    }
+
+    #[test]
+    fn normals_point_out_of_the_voxels() {
+    	let document = plate();
+    	let grid = document.get_voxel_grid(IVec3::splat(8), IVec3::splat(20));
+
+    	assert!(normal(&grid, IVec3::new(16, 16, 12)).abs_diff_eq(Vec3::NEG_Z, 1e-6));
+    	assert!(normal(&grid, IVec3::new(16, 16, 15)).abs_diff_eq(Vec3::Z, 1e-6));
+    	assert_eq!(normal(&grid, IVec3::new(16, 16, 13)), Vec3::ZERO);
+
+    	let away = facing_away(&grid, Vec3::NEG_Z, 1.0);
+    	assert!(!away.get(IVec3::new(16, 16, 10)));
+    	assert!(!away.get(IVec3::new(16, 16, 13)));
+    	assert!(away.get(IVec3::new(16, 16, 14)));
+    	assert!(away.get(IVec3::new(16, 16, 17)));
+    }
+
+    #[test]
+    fn strokes_keep_the_far_side() {
+    	let back = vec3(16.5, 16.5, 15.5) / 32.0;
+    	let behind = vec3(16.5, 16.5, 16.5) / 32.0;
+    	let front = vec3(16.5, 16.5, 12.5) / 32.0;
+    	let before = vec3(16.5, 16.5, 11.5) / 32.0;
+
+    	assert!(!stroke(true, false).is_solid(back));
+    	let carved = stroke(true, true);
+    	assert!(carved.is_solid(back));
+    	assert!(!carved.is_solid(front));
+
+    	assert!(stroke(false, false).is_solid(behind));
+    	let grown = stroke(false, true);
+    	assert!(!grown.is_solid(behind));
+    	assert!(grown.is_solid(before));
+    }
+}
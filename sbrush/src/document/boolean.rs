@@ -0,0 +1,159 @@
+//! Combines the voxels of another document, the cutter, with a document.
+//!
+//! The cutter is placed by a matrix from the space of the document into
+//! its own, and sampled where each node of the document lands in it. Nodes
+//! further from the surface of the cutter than their own size are settled
+//! whole, so only the ones its surface runs through are split, down to
+//! single voxels.
+
+use super::Document;
+use crate::aabb::Aabb;
+use crate::sculpt::Coverage;
+
+use glam::{Mat4, Vec3};
+
+/// How the cutter is combined with a document.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BooleanOp {
+	/// Add the voxels of the cutter.
+	Union,
+	/// Carve the voxels of the cutter away.
+	Subtract,
+	/// Keep only the voxels inside of the cutter.
+	Intersect,
+}
+
+impl BooleanOp {
+	/// Get an operation by its name in commands.
+	pub fn from_name(name: &str) -> Option<Self> {
+		match name {
+			"union" => Some(BooleanOp::Union),
+			"subtract" => Some(BooleanOp::Subtract),
+			"intersect" => Some(BooleanOp::Intersect),
+			_ => None,
+		}
+	}
+}
+
+/// Get the distance from a point to the surface of a document, which is negative inside of the voxels.
+///
+/// The borders of a chunk count as its surface from the inside, so the
+/// distance is at times too short, but never too long.
+fn surface_distance(document: &Document, point: Vec3) -> f32 {
+	let distance = document.chunks.values()
+		.filter_map(|chunk| chunk.closest_surface_point(point))
+		.map(|(_, distance)| distance.abs())
+		.fold(f32::INFINITY, f32::min);
+
+	if document.is_solid(point) { -distance } else { distance }
+}
+
+/// Combine a cutter with a document, mapping points of the document into the cutter by a matrix.
+///
+/// The matrix has to keep voxels cubes, scaling every axis alike.
+pub fn apply(document: &mut Document, cutter: &Document, to_cutter: Mat4, op: BooleanOp) {
+	let scale = to_cutter.x_axis.truncate().length();
+	let voxel_size = document.get_voxel_size();
+	let materials = document.palette.len() as u32;
+	let reach = cutter.get_solid_bounds().map(|bounds| bounds.transform(to_cutter.inverse()));
+
+	let classify = |size: f32, center: Vec3| {
+		let point = to_cutter.transform_point3(center);
+		let inside = if size > voxel_size * 1.5 {
+			let distance = surface_distance(cutter, point);
+			if distance.abs() <= size * 3f32.sqrt() / 2.0 * scale {
+				return Coverage::Mixed;
+			}
+			distance < 0.0
+		} else {
+			cutter.is_solid(point)
+		};
+
+		match (op, inside) {
+			(BooleanOp::Union, true) => Coverage::Solid,
+			(BooleanOp::Subtract, true) => Coverage::Empty,
+			(BooleanOp::Intersect, false) => Coverage::Empty,
+			_ => Coverage::Unchanged,
+		}
+	};
+	// the added voxels take the material of the cutter, if the document has it
+	let fill = |center: Vec3| {
+		let point = to_cutter.transform_point3(center);
+		cutter.get_chunk(cutter.chunk_at(point))
+			.and_then(|chunk| chunk.get_leaf_material(point))
+			.filter(|material| *material < materials)
+			.unwrap_or(0)
+	};
+
+	match (op, reach) {
+		(BooleanOp::Intersect, _) => {
+			// everything outside of the cutter goes, wherever it is
+			let _ = document.load_chunks_in(Aabb::new(Vec3::NEG_INFINITY, Vec3::INFINITY));
+			for chunk in document.chunks_mut() {
+				chunk.apply_with(&fill, &classify);
+			}
+		},
+		(_, Some(reach)) => {
+			for chunk in document.get_chunks_in(reach, op == BooleanOp::Union) {
+				chunk.apply_with(&fill, &classify);
+			}
+		},
+		// an empty cutter adds or carves nothing
+		(_, None) => (),
+	}
+	document.prune();
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    use crate::brush::{Brush, RoundBrushTip};
+
+    use glam::vec3;
+
+    /// A document with 32 voxels per chunk, holding a ball in the middle of the home chunk.
+    fn ball() -> Document {
+    	let mut document = Document::new(32, 1.0);
+    	let mut brush = Brush::new("round".to_owned(), Box::new(RoundBrushTip::new()));
+    	brush.set_size(0.25);
+    	brush.add(&mut document, vec3(0.5, 0.5, 0.5));
+
+    	document
+    }
+
+    #[test]
+    fn cutters_combine_through_their_placement() {
+    	let cutter = ball();
+    	// the cutter moved a quarter along x, into the side of the ball
+    	let to_cutter = Mat4::from_translation(vec3(-0.25, 0.0, 0.0));
+    	let (inside, beyond, shared) = (vec3(0.9, 0.5, 0.5), vec3(1.05, 0.5, 0.5), vec3(0.6, 0.5, 0.5));
+
+    	let mut union = ball();
+    	apply(&mut union, &cutter, to_cutter, BooleanOp::Union);
+    	assert!(union.is_solid(inside) && union.is_solid(vec3(0.3, 0.5, 0.5)));
+    	assert!(!union.is_solid(beyond));
+
+    	let mut subtract = ball();
+    	apply(&mut subtract, &cutter, to_cutter, BooleanOp::Subtract);
+    	assert!(!subtract.is_solid(shared) && subtract.is_solid(vec3(0.35, 0.5, 0.5)));
+
+    	let mut intersect = ball();
+    	apply(&mut intersect, &cutter, to_cutter, BooleanOp::Intersect);
+    	assert!(intersect.is_solid(shared) && !intersect.is_solid(vec3(0.35, 0.5, 0.5)));
+    	assert!(intersect.validate().is_empty());
+    }
+
+    #[test]
+    fn scaled_cutters_reach_other_chunks() {
+    	let mut document = ball();
+    	// the cutter is twice as large, centered on the far corner of the home chunk
+    	let to_cutter = Mat4::from_scale(Vec3::splat(0.5));
+    	let center = Vec3::ONE;
+    	apply(&mut document, &ball(), to_cutter, BooleanOp::Union);
+
+    	assert!(document.is_solid(center) && document.is_solid(center + vec3(0.45, 0.0, 0.0)));
+    	assert!(document.get_chunk(document.chunk_at(center + vec3(0.45, 0.0, 0.0))).is_some());
+    	assert!(!document.is_solid(center + vec3(0.7, 0.0, 0.0)));
+    }
+}
@@ -0,0 +1,943 @@
+//! Saving documents, and opening them without reading every chunk up front.
+//!
+//! A document file holds the settings, palette, pins, guides and color
+//! management of the document, then the nodes of each chunk as written by
+//! `Sculpt::write_nodes`, then a table of where each chunk is, and last
+//! where the table starts. The nodes of each chunk are compressed into a
+//! zstd frame of their own, with a checksum, so chunks are written one
+//! after another as they are compressed and a damaged chunk is caught as
+//! it is read.
+//!
+//! Opening a file reads it into memory, but only parses the settings and
+//! the table. Each chunk is decompressed the first time it is needed, so
+//! a file of many chunks opens about as quickly as reading it from disk.
+//! Files aren't mapped, since the editor watches them for other programs
+//! rewriting them in place, which would change the bytes under a map.
+
+use super::{Document, Guide, Pin};
+use crate::brush::{BrushSettings, Dynamics, SavedBrushes};
+use crate::color::{ColorManagement, Tonemap, WorkingSpace};
+use crate::material::MaterialMix;
+use crate::sculpt::Sculpt;
+use crate::util::backups::Backups;
+use crate::util::bytes::{invalid, take};
+use crate::util::migrations::{self, Migrations};
+use crate::util::progress::Progress;
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use glam::{IVec3, Vec3};
+
+/// The zstd level chunks are compressed at, unless another is asked for.
+pub const DEFAULT_LEVEL: i32 = 3;
+
+/// The length of an entry in the table of chunks: its coordinate, then where its nodes start and end.
+const ENTRY_LENGTH: usize = 28;
+
+/// The versions of the document format.
+///
+/// Version 1 compressed the chunks, and moved the table behind them.
+/// Version 2 added the pins behind the palette.
+/// Version 3 added the guide curves behind the pins.
+/// Version 4 added the color management behind the guides.
+/// Version 5 added the brush settings behind the color management.
+/// Version 6 added the subsurface scattering of the materials behind the brush settings.
+pub fn migrations() -> Migrations {
+	let mut migrations = Migrations::new(*b"SWRX");
+	migrations.register(compress_chunks);
+	migrations.register(add_pins);
+	migrations.register(add_guides);
+	migrations.register(add_color_management);
+	migrations.register(add_brushes);
+	migrations.register(add_subsurface);
+
+	migrations
+}
+
+/// The contents of an opened file, after the header of the format.
+enum Contents {
+	/// The file is in the current version, so it is kept as it was read.
+	Current(Vec<u8>),
+	/// The file was upgraded from an older version.
+	Upgraded(Vec<u8>),
+}
+
+impl Contents {
+	/// Get the bytes of the contents.
+	fn get(&self) -> &[u8] {
+		match self {
+			Self::Current(bytes) => &bytes[migrations::HEADER_LENGTH..],
+			Self::Upgraded(bytes) => bytes,
+		}
+	}
+}
+
+/// A chunk left in the file a document was opened from, until it is needed.
+#[derive(Clone)]
+pub struct SavedChunk {
+	contents: Arc<Contents>,
+	/// Where the compressed nodes of the chunk are in the contents.
+	range: Range<usize>,
+}
+
+impl SavedChunk {
+	/// Get the compressed nodes of the chunk, as they are in the file.
+	fn get_stored(&self) -> &[u8] {
+		&self.contents.get()[self.range.clone()]
+	}
+}
+
+/// Counts the saves, so saves running at once write beside the file under names of their own.
+static SAVES: AtomicU64 = AtomicU64::new(0);
+
+/// The settings of a document, in front of its chunks.
+#[derive(Clone)]
+struct Settings {
+	resolution: u32,
+	chunk_size: f32,
+	stroke: u32,
+	palette: Vec<[f32; 4]>,
+	pins: Vec<Pin>,
+	guides: Vec<Guide>,
+	color: ColorManagement,
+	brushes: SavedBrushes,
+	subsurface: Vec<f32>,
+}
+
+/// A chunk of a snapshot.
+#[derive(Clone)]
+enum SnapshotChunk {
+	Loaded(Arc<Sculpt>),
+	/// A chunk still in the file the document was opened from.
+	Saved(SavedChunk),
+}
+
+/// A document as it was at one moment, to save while the document is edited further.
+///
+/// Taking a snapshot only shares the chunks of the document, and
+/// whichever of the two changes a chunk afterwards copies it first.
+#[derive(Clone)]
+pub struct Snapshot {
+	settings: Settings,
+	chunks: Vec<(IVec3, SnapshotChunk)>,
+}
+
+impl Snapshot {
+	/// Take a snapshot of a document.
+	pub fn new(document: &Document) -> Self {
+		let loaded = document.chunks.iter().map(|(coordinate, chunk)| (*coordinate, SnapshotChunk::Loaded(chunk.clone())));
+		let saved = document.saved.iter().map(|(coordinate, saved)| (*coordinate, SnapshotChunk::Saved(saved.clone())));
+		let mut chunks: Vec<(IVec3, SnapshotChunk)> = loaded.chain(saved).collect();
+		chunks.sort_by_key(|(coordinate, _)| (coordinate.z, coordinate.y, coordinate.x));
+
+		Self {
+			settings: Settings {
+				resolution: document.resolution,
+				chunk_size: document.chunk_size,
+				stroke: document.stroke,
+				palette: document.palette.clone(),
+				pins: document.pins.clone(),
+				guides: document.guides.clone(),
+				color: document.color,
+				brushes: document.brushes.clone(),
+				subsurface: document.subsurface.clone(),
+			},
+			chunks,
+		}
+	}
+
+	/// Write the snapshot to a file, compressing its chunks at a zstd level.
+	///
+	/// Chunks which haven't been read from the file the document was
+	/// opened from are copied over as they are. The file is written beside
+	/// the path first and then moved over it, so a file still mapped by the
+	/// document is never changed under it, and a failed or cancelled save
+	/// leaves the file as it was.
+	pub fn save(&self, path: &Path, level: i32, progress: &Progress) -> io::Result<()> {
+		let mut name: OsString = path.file_name().unwrap_or_default().to_owned();
+		name.push(format!(".{}.saving", SAVES.fetch_add(1, Ordering::Relaxed)));
+		let temporary = path.with_file_name(name);
+
+		match self.write(&temporary, level, progress) {
+			Ok(()) => fs::rename(&temporary, path),
+			Err(error) => {
+				let _ = fs::remove_file(&temporary);
+				Err(error)
+			},
+		}
+	}
+
+	/// Write the snapshot as the newest backup of a file, rotating the older ones.
+	pub fn save_backup(&self, backups: &Backups, level: i32, progress: &Progress) -> io::Result<()> {
+		match backups.next_backup()? {
+			Some(path) => self.save(&path, level, progress),
+			None => Ok(()),
+		}
+	}
+
+	/// Write the snapshot to a new file, reporting the share of chunks written.
+	fn write(&self, path: &Path, level: i32, progress: &Progress) -> io::Result<()> {
+		let mut writer = BufWriter::new(File::create(path)?);
+		writer.write_all(&migrations().get_header())?;
+		let mut writer = Counted { writer, count: 0 };
+		write_settings(&mut writer, &self.settings)?;
+		write_pins(&mut writer, &self.settings.pins)?;
+		write_guides(&mut writer, &self.settings.guides)?;
+		write_color_management(&mut writer, &self.settings.color)?;
+		write_brushes(&mut writer, &self.settings.brushes)?;
+		write_subsurface(&mut writer, &self.settings.subsurface)?;
+
+		let mut table = Vec::new();
+		let mut nodes = Vec::new();
+		for (index, (coordinate, chunk)) in self.chunks.iter().enumerate() {
+			if progress.is_cancelled() {
+				return Err(io::Error::new(io::ErrorKind::Interrupted, "the save was cancelled"));
+			}
+			progress.set_fraction(index as f32 / self.chunks.len() as f32);
+
+			let start = writer.count;
+			match chunk {
+				SnapshotChunk::Loaded(chunk) => {
+					nodes.clear();
+					chunk.write_nodes(&mut nodes);
+					compress(&nodes, level, &mut writer)?;
+				},
+				SnapshotChunk::Saved(saved) => writer.write_all(saved.get_stored())?,
+			}
+			write_entry(&mut table, *coordinate, start..writer.count);
+		}
+
+		let table_start = writer.count as u64;
+		writer.write_all(&(self.chunks.len() as u32).to_le_bytes())?;
+		writer.write_all(&table)?;
+		writer.write_all(&table_start.to_le_bytes())?;
+		writer.flush()?;
+
+		writer.writer.get_ref().sync_all()
+	}
+}
+
+/// A writer counting the bytes written through it, to know where each chunk starts.
+struct Counted<W> {
+	writer: W,
+	count: usize,
+}
+
+impl<W: Write> Write for Counted<W> {
+	fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+		let written = self.writer.write(bytes)?;
+		self.count += written;
+
+		Ok(written)
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		self.writer.flush()
+	}
+}
+
+/// Read a chunk saved in the file of a document, fixing any inconsistencies in it.
+pub fn load(document: &mut Document, coordinate: IVec3, saved: &SavedChunk) -> io::Result<Sculpt> {
+	let nodes = zstd::stream::decode_all(saved.get_stored())
+		.map_err(|error| invalid(&format!("a chunk of the document is damaged ({error})")))?;
+	let mut chunk = Sculpt::read_nodes(document.resolution, document.get_chunk_bounds(coordinate), &nodes)?;
+	chunk.set_palette(&document.palette);
+	chunk.set_subsurface(&document.subsurface);
+	document.repaired += chunk.repair().len();
+
+	Ok(chunk)
+}
+
+/// Open a document file, leaving every chunk but the one at the origin in it.
+pub fn open(path: &Path) -> io::Result<Document> {
+	let file = fs::read(path)?;
+	let migrations = migrations();
+	let contents = if migrations.read_version(&file)? == migrations.get_version() {
+		Contents::Current(file)
+	} else {
+		Contents::Upgraded(migrations.read(&file)?)
+	};
+	let contents = Arc::new(contents);
+
+	let bytes = contents.get();
+	let mut rest = bytes;
+	let mut settings = read_settings(&mut rest)?;
+	settings.pins = read_pins(&mut rest)?;
+	settings.guides = read_guides(&mut rest)?;
+	settings.color = read_color_management(&mut rest)?;
+	settings.brushes = read_brushes(&mut rest)?;
+	settings.subsurface = read_subsurface(&mut rest)?;
+	let table_start = bytes.len()
+		.checked_sub(8)
+		.map(|end| (u64::from_le_bytes(bytes[end..].try_into().unwrap()) as usize, end))
+		.filter(|(start, end)| start <= end);
+	let Some((table_start, table_end)) = table_start else {
+		return Err(invalid("the document has no table of chunks"));
+	};
+	let mut table = &bytes[table_start..table_end];
+	let entries = read_table(&mut table, table_start)?;
+
+	let mut document = Document::new(settings.resolution, settings.chunk_size);
+	document.stroke = settings.stroke;
+	document.set_palette(&settings.palette);
+	for (index, strength) in settings.subsurface.iter().enumerate() {
+		document.set_subsurface(index as u32, *strength);
+	}
+	document.pins = settings.pins;
+	document.guides = settings.guides;
+	document.color = settings.color;
+	document.brushes = settings.brushes;
+	let mut saved: HashMap<IVec3, SavedChunk> = entries.into_iter()
+		.map(|(coordinate, range)| (coordinate, SavedChunk {
+			contents: contents.clone(),
+			range,
+		}))
+		.collect();
+	// the chunk at the origin always exists, so it is read right away
+	if let Some(home) = saved.remove(&IVec3::ZERO) {
+		let chunk = load(&mut document, IVec3::ZERO, &home)?;
+		document.chunks.insert(IVec3::ZERO, Arc::new(chunk));
+	}
+	document.saved = saved;
+
+	Ok(document)
+}
+
+/// Compress the nodes of a chunk into a zstd frame with a checksum.
+fn compress(nodes: &[u8], level: i32, writer: &mut impl Write) -> io::Result<()> {
+	let mut encoder = zstd::stream::Encoder::new(writer, level)?;
+	encoder.include_checksum(true)?;
+	encoder.write_all(nodes)?;
+	encoder.finish()?;
+
+	Ok(())
+}
+
+/// Upgrade version 0, where the table came first and the chunks weren't compressed.
+fn compress_chunks(contents: Vec<u8>) -> io::Result<Vec<u8>> {
+	let mut rest = contents.as_slice();
+	read_settings(&mut rest)?;
+	let settings_length = contents.len() - rest.len();
+	let entries = read_table(&mut rest, contents.len())?;
+
+	let mut upgraded = contents[..settings_length].to_vec();
+	let mut table = Vec::new();
+	for (coordinate, range) in &entries {
+		let start = upgraded.len();
+		compress(&contents[range.clone()], DEFAULT_LEVEL, &mut upgraded)?;
+		write_entry(&mut table, *coordinate, start..upgraded.len());
+	}
+	let table_start = upgraded.len() as u64;
+	upgraded.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+	upgraded.extend_from_slice(&table);
+	upgraded.extend_from_slice(&table_start.to_le_bytes());
+
+	Ok(upgraded)
+}
+
+/// Upgrade version 1, which had no pins, to have none.
+fn add_pins(contents: Vec<u8>) -> io::Result<Vec<u8>> {
+	let mut rest = contents.as_slice();
+	read_settings(&mut rest)?;
+	let mut pins = Vec::new();
+	write_pins(&mut pins, &[])?;
+
+	insert(&contents, contents.len() - rest.len(), &pins)
+}
+
+/// Upgrade version 2, which had no guides, to have none.
+fn add_guides(contents: Vec<u8>) -> io::Result<Vec<u8>> {
+	let mut rest = contents.as_slice();
+	read_settings(&mut rest)?;
+	read_pins(&mut rest)?;
+	let mut guides = Vec::new();
+	write_guides(&mut guides, &[])?;
+
+	insert(&contents, contents.len() - rest.len(), &guides)
+}
+
+/// Upgrade version 3, which had no color management, to the defaults.
+fn add_color_management(contents: Vec<u8>) -> io::Result<Vec<u8>> {
+	let mut rest = contents.as_slice();
+	read_settings(&mut rest)?;
+	read_pins(&mut rest)?;
+	read_guides(&mut rest)?;
+	let mut color = Vec::new();
+	write_color_management(&mut color, &ColorManagement::default())?;
+
+	insert(&contents, contents.len() - rest.len(), &color)
+}
+
+/// Upgrade version 4, which had no brush settings, to have none.
+fn add_brushes(contents: Vec<u8>) -> io::Result<Vec<u8>> {
+	let mut rest = contents.as_slice();
+	read_settings(&mut rest)?;
+	read_pins(&mut rest)?;
+	read_guides(&mut rest)?;
+	read_color_management(&mut rest)?;
+	let mut brushes = Vec::new();
+	write_brushes(&mut brushes, &SavedBrushes::default())?;
+
+	insert(&contents, contents.len() - rest.len(), &brushes)
+}
+
+/// Upgrade version 5, which had no subsurface scattering, to scatter under no material.
+fn add_subsurface(contents: Vec<u8>) -> io::Result<Vec<u8>> {
+	let mut rest = contents.as_slice();
+	read_settings(&mut rest)?;
+	read_pins(&mut rest)?;
+	read_guides(&mut rest)?;
+	read_color_management(&mut rest)?;
+	read_brushes(&mut rest)?;
+	let mut subsurface = Vec::new();
+	write_subsurface(&mut subsurface, &[])?;
+
+	insert(&contents, contents.len() - rest.len(), &subsurface)
+}
+
+/// Insert bytes in front of the chunks, moving the chunks and the table back by their length.
+fn insert(contents: &[u8], at: usize, inserted: &[u8]) -> io::Result<Vec<u8>> {
+	let table_start = contents.len()
+		.checked_sub(8)
+		.map(|end| u64::from_le_bytes(contents[end..].try_into().unwrap()) as usize)
+		.filter(|start| (at..contents.len() - 8).contains(start))
+		.ok_or_else(|| invalid("the document has no table of chunks"))?;
+	let entries = read_table(&mut &contents[table_start..], table_start)?;
+
+	let shift = inserted.len();
+	let mut upgraded = contents[..at].to_vec();
+	upgraded.extend_from_slice(inserted);
+	upgraded.extend_from_slice(&contents[at..table_start]);
+	let mut table = Vec::new();
+	for (coordinate, range) in entries.iter() {
+		write_entry(&mut table, *coordinate, range.start + shift..range.end + shift);
+	}
+	upgraded.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+	upgraded.extend_from_slice(&table);
+	upgraded.extend_from_slice(&((table_start + shift) as u64).to_le_bytes());
+
+	Ok(upgraded)
+}
+
+/// Write the settings of a document.
+fn write_settings(writer: &mut impl Write, settings: &Settings) -> io::Result<()> {
+	writer.write_all(&settings.resolution.to_le_bytes())?;
+	writer.write_all(&settings.chunk_size.to_le_bytes())?;
+	writer.write_all(&settings.stroke.to_le_bytes())?;
+	writer.write_all(&(settings.palette.len() as u32).to_le_bytes())?;
+	for component in settings.palette.iter().flatten() {
+		writer.write_all(&component.to_le_bytes())?;
+	}
+
+	Ok(())
+}
+
+/// Read the settings of a document from the front of some bytes.
+fn read_settings(rest: &mut &[u8]) -> io::Result<Settings> {
+	let resolution = u32::from_le_bytes(take(rest)?);
+	let chunk_size = f32::from_le_bytes(take(rest)?);
+	let stroke = u32::from_le_bytes(take(rest)?);
+	if resolution == 0 || !chunk_size.is_finite() || chunk_size <= 0.0 {
+		return Err(invalid("the document has no valid resolution or chunk size"));
+	}
+
+	let colors = u32::from_le_bytes(take(rest)?);
+	let mut palette = Vec::new();
+	for _ in 0..colors {
+		let color: [[u8; 4]; 4] = [take(rest)?, take(rest)?, take(rest)?, take(rest)?];
+		palette.push(color.map(f32::from_le_bytes));
+	}
+
+	Ok(Settings { resolution, chunk_size, stroke, palette, pins: Vec::new(), guides: Vec::new(), color: ColorManagement::default(), brushes: SavedBrushes::default(), subsurface: Vec::new() })
+}
+
+/// Write the pins of a document: the position and the length of the note in bytes, then the note.
+fn write_pins(writer: &mut impl Write, pins: &[Pin]) -> io::Result<()> {
+	writer.write_all(&(pins.len() as u32).to_le_bytes())?;
+	for pin in pins {
+		for component in pin.position.to_array() {
+			writer.write_all(&component.to_le_bytes())?;
+		}
+		writer.write_all(&(pin.note.len() as u32).to_le_bytes())?;
+		writer.write_all(pin.note.as_bytes())?;
+	}
+
+	Ok(())
+}
+
+/// Read the pins of a document from the front of some bytes.
+fn read_pins(rest: &mut &[u8]) -> io::Result<Vec<Pin>> {
+	let count = u32::from_le_bytes(take(rest)?);
+
+	(0..count)
+		.map(|_| {
+			let position = Vec3::from_array([take(rest)?, take(rest)?, take(rest)?].map(f32::from_le_bytes));
+			let length = u32::from_le_bytes(take(rest)?) as usize;
+			if rest.len() < length {
+				return Err(invalid("the document is cut off"));
+			}
+			let (note, after) = rest.split_at(length);
+			*rest = after;
+			let note = String::from_utf8(note.to_vec()).map_err(|_| invalid("a pin of the document has a damaged note"))?;
+
+			Ok(Pin { position, note })
+		})
+		.collect()
+}
+
+/// Write the guides of a document: the count of points of each, then the points.
+fn write_guides(writer: &mut impl Write, guides: &[Guide]) -> io::Result<()> {
+	writer.write_all(&(guides.len() as u32).to_le_bytes())?;
+	for guide in guides {
+		writer.write_all(&(guide.points.len() as u32).to_le_bytes())?;
+		for component in guide.points.iter().flat_map(|point| point.to_array()) {
+			writer.write_all(&component.to_le_bytes())?;
+		}
+	}
+
+	Ok(())
+}
+
+/// Read the guides of a document from the front of some bytes.
+fn read_guides(rest: &mut &[u8]) -> io::Result<Vec<Guide>> {
+	let count = u32::from_le_bytes(take(rest)?);
+
+	(0..count)
+		.map(|_| {
+			let length = u32::from_le_bytes(take(rest)?) as usize;
+			if rest.len() < length * 12 {
+				return Err(invalid("the document is cut off"));
+			}
+			let points = (0..length)
+				.map(|_| Ok(Vec3::from_array([take(rest)?, take(rest)?, take(rest)?].map(f32::from_le_bytes))))
+				.collect::<io::Result<_>>()?;
+
+			Ok(Guide { points })
+		})
+		.collect()
+}
+
+/// Write the color management of a document: the working space and the tone mapping curve by their index, then the exposure.
+fn write_color_management(writer: &mut impl Write, color: &ColorManagement) -> io::Result<()> {
+	writer.write_all(&(color.working_space as u32).to_le_bytes())?;
+	writer.write_all(&(color.tonemap as u32).to_le_bytes())?;
+	writer.write_all(&color.exposure.to_le_bytes())?;
+
+	Ok(())
+}
+
+/// Read the color management of a document from the front of some bytes.
+fn read_color_management(rest: &mut &[u8]) -> io::Result<ColorManagement> {
+	let working_space = WorkingSpace::ALL.get(u32::from_le_bytes(take(rest)?) as usize);
+	let tonemap = Tonemap::ALL.get(u32::from_le_bytes(take(rest)?) as usize);
+	let exposure = f32::from_le_bytes(take(rest)?);
+	let (Some(working_space), Some(tonemap)) = (working_space, tonemap) else {
+		return Err(invalid("the document has an unknown working space or tone mapping curve"));
+	};
+	if !exposure.is_finite() {
+		return Err(invalid("the document has no valid exposure"));
+	}
+
+	Ok(ColorManagement { working_space: *working_space, tonemap: *tonemap, exposure })
+}
+
+/// Write the brush settings of a document: the id of the current brush, then the id and the settings of each.
+///
+/// Ids are written as their length in bytes, then the bytes. An unlimited accumulation is written as 0.
+fn write_brushes(writer: &mut impl Write, saved: &SavedBrushes) -> io::Result<()> {
+	writer.write_all(&(saved.current.len() as u32).to_le_bytes())?;
+	writer.write_all(saved.current.as_bytes())?;
+	writer.write_all(&(saved.brushes.len() as u32).to_le_bytes())?;
+	for (id, settings) in &saved.brushes {
+		writer.write_all(&(id.len() as u32).to_le_bytes())?;
+		writer.write_all(id.as_bytes())?;
+		let dynamics = &settings.dynamics;
+		for value in [settings.size, dynamics.strength, dynamics.hardness, dynamics.accumulation.unwrap_or(0.0)] {
+			writer.write_all(&value.to_le_bytes())?;
+		}
+		writer.write_all(&dynamics.smoothing.to_le_bytes())?;
+		writer.write_all(&settings.material.first.to_le_bytes())?;
+		writer.write_all(&settings.material.second.to_le_bytes())?;
+		writer.write_all(&settings.material.mix.to_le_bytes())?;
+	}
+
+	Ok(())
+}
+
+/// Read the brush settings of a document from the front of some bytes.
+fn read_brushes(rest: &mut &[u8]) -> io::Result<SavedBrushes> {
+	let current = read_id(rest)?;
+	let count = u32::from_le_bytes(take(rest)?);
+	let brushes = (0..count)
+		.map(|_| {
+			let id = read_id(rest)?;
+			let [size, strength, hardness, accumulation] = [take(rest)?, take(rest)?, take(rest)?, take(rest)?].map(f32::from_le_bytes);
+			let smoothing = u32::from_le_bytes(take(rest)?);
+			let material = MaterialMix {
+				first: u32::from_le_bytes(take(rest)?),
+				second: u32::from_le_bytes(take(rest)?),
+				mix: f32::from_le_bytes(take(rest)?),
+			};
+			if ![size, strength, hardness, accumulation, material.mix].iter().all(|value| value.is_finite()) {
+				return Err(invalid("a brush of the document has no valid settings"));
+			}
+			let dynamics = Dynamics { strength, hardness, accumulation: (accumulation > 0.0).then_some(accumulation), smoothing };
+
+			Ok((id, BrushSettings { size, dynamics, material }))
+		})
+		.collect::<io::Result<_>>()?;
+
+	Ok(SavedBrushes { current, brushes })
+}
+
+/// Read the id of a brush from the front of some bytes.
+fn read_id(rest: &mut &[u8]) -> io::Result<String> {
+	let length = u32::from_le_bytes(take(rest)?) as usize;
+	if rest.len() < length {
+		return Err(invalid("the document is cut off"));
+	}
+	let (id, after) = rest.split_at(length);
+	*rest = after;
+
+	String::from_utf8(id.to_vec()).map_err(|_| invalid("a brush of the document has a damaged id"))
+}
+
+/// Write how much light scatters under the surface of each material: the count of materials, then the strength of each.
+fn write_subsurface(writer: &mut impl Write, strengths: &[f32]) -> io::Result<()> {
+	writer.write_all(&(strengths.len() as u32).to_le_bytes())?;
+	for strength in strengths {
+		writer.write_all(&strength.to_le_bytes())?;
+	}
+
+	Ok(())
+}
+
+/// Read how much light scatters under the surface of each material from the front of some bytes.
+fn read_subsurface(rest: &mut &[u8]) -> io::Result<Vec<f32>> {
+	let count = u32::from_le_bytes(take(rest)?) as usize;
+	if rest.len() < count * 4 {
+		return Err(invalid("the document is cut off"));
+	}
+
+	(0..count)
+		.map(|_| {
+			let strength = f32::from_le_bytes(take(rest)?);
+			if !(0.0..=1.0).contains(&strength) {
+				return Err(invalid("a material of the document has no valid subsurface scattering"));
+			}
+
+			Ok(strength)
+		})
+		.collect()
+}
+
+/// Append an entry to the table of chunks.
+fn write_entry(table: &mut Vec<u8>, coordinate: IVec3, range: Range<usize>) {
+	for component in coordinate.to_array() {
+		table.extend_from_slice(&component.to_le_bytes());
+	}
+	table.extend_from_slice(&(range.start as u64).to_le_bytes());
+	table.extend_from_slice(&(range.end as u64).to_le_bytes());
+}
+
+/// Read the table of chunks from the front of some bytes, checking that every chunk ends before a limit.
+fn read_table(rest: &mut &[u8], limit: usize) -> io::Result<Vec<(IVec3, Range<usize>)>> {
+	let count = u32::from_le_bytes(take(rest)?) as usize;
+	if rest.len() < count * ENTRY_LENGTH {
+		return Err(invalid("the table of chunks is cut off"));
+	}
+
+	(0..count)
+		.map(|_| {
+			let coordinate = IVec3::from_array([take(rest)?, take(rest)?, take(rest)?].map(i32::from_le_bytes));
+			let start = u64::from_le_bytes(take(rest)?) as usize;
+			let end = u64::from_le_bytes(take(rest)?) as usize;
+			if start > end || end > limit {
+				return Err(invalid("a chunk of the document is outside of the file"));
+			}
+
+			Ok((coordinate, start..end))
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    use crate::brush::RoundBrushTip;
+    use crate::aabb::Aabb;
+
+    use glam::{Vec3, vec3};
+
+    /// A path of its own for a test.
+    fn temporary(name: &str) -> std::path::PathBuf {
+    	std::env::temp_dir().join(format!("swirlix-{name}-{}.swx", std::process::id()))
+    }
+
+    /// A document with a ball in three chunks.
+    fn balls() -> Document {
+    	let mut document = Document::new(16, 1.0);
+    	document.set_palette(&[[1.0, 0.0, 0.0, 1.0], [0.0, 0.0, 1.0, 1.0]]);
+    	for position in [vec3(0.5, 0.5, 0.5), vec3(2.5, 0.5, 0.5), vec3(-1.5, 0.5, 0.5)] {
+    		for chunk in document.get_chunks_in(Aabb::cube(position, 0.5), true) {
+    			chunk.subdivide(RoundBrushTip::filler(0.25, position), RoundBrushTip::container(0.25, position));
+    		}
+    	}
+
+    	document
+    }
+
+    #[test]
+    fn damaged_chunks_are_repaired_as_they_are_read() {
+    	let path = temporary("damaged");
+    	let mut document = balls();
+    	let position = vec3(2.5, 0.5, 0.85);
+    	for chunk in document.get_chunks_in(Aabb::cube(position, 0.2), false) {
+    		chunk.subdivide_with(&MaterialMix::single(1), RoundBrushTip::filler(0.1, position), RoundBrushTip::container(0.1, position));
+    	}
+    	// the file loses the second material, so the chunk using it is damaged
+    	document.palette.truncate(1);
+    	document.save(&path).unwrap();
+
+    	let mut opened = Document::open(&path).unwrap();
+    	assert_eq!(opened.get_repaired_count(), 0);
+    	opened.load_nearest(Vec3::ZERO, 2).unwrap();
+    	assert!(opened.get_repaired_count() > 0);
+    	assert_eq!(opened.validate(), vec![]);
+
+    	// repairing a clean document leaves nothing to undo
+    	let revision = opened.get_revision();
+    	assert_eq!(opened.repair(), 0);
+    	assert_eq!(opened.get_revision(), revision);
+    	assert!(!opened.undo());
+    	std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn chunks_are_read_as_they_are_needed() {
+    	let path = temporary("lazy");
+    	let document = balls();
+    	document.save(&path).unwrap();
+
+    	let mut opened = Document::open(&path).unwrap();
+    	assert_eq!(opened.get_palette(), document.get_palette());
+    	assert_eq!(opened.get_chunk_coordinates(), vec![IVec3::ZERO]);
+    	assert_eq!(opened.get_saved_count(), 2);
+    	assert!(opened.is_solid(vec3(0.5, 0.5, 0.5)));
+
+    	// editing near a chunk reads it
+    	assert_eq!(opened.get_chunks_in(Aabb::cube(vec3(2.5, 0.5, 0.5), 0.1), false).len(), 1);
+    	assert!(opened.is_solid(vec3(2.5, 0.5, 0.5)));
+    	assert_eq!(opened.load_nearest(Vec3::ZERO, 4).unwrap(), 1);
+    	assert_eq!(opened.get_saved_count(), 0);
+    	assert_eq!(opened.get_voxel_buffers(), document.get_voxel_buffers());
+
+    	// saving over the file the document was opened from keeps the chunks it hasn't read
+    	let mut reopened = Document::open(&path).unwrap();
+    	reopened.save(&path).unwrap();
+    	reopened.load_nearest(Vec3::ZERO, 4).unwrap();
+    	assert_eq!(reopened.get_voxel_buffers(), document.get_voxel_buffers());
+
+    	fs::write(&path, b"SWRX\x01\0\0\0\x10\0").unwrap();
+    	assert_eq!(Document::open(&path).err().map(|error| error.kind()), Some(io::ErrorKind::InvalidData));
+    	fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn chunks_are_compressed_and_checked() {
+    	let path = temporary("compressed");
+    	let document = balls();
+    	let mut nodes = Vec::new();
+    	for coordinate in document.get_chunk_coordinates() {
+    		document.get_chunk(coordinate).unwrap().write_nodes(&mut nodes);
+    	}
+
+    	document.save_with_level(&path, 19).unwrap();
+    	let mut bytes = fs::read(&path).unwrap();
+    	assert!(bytes.len() < nodes.len() / 4);
+
+    	// flipping a bit in the last chunk fails its checksum once it is read
+    	let mut opened = Document::open(&path).unwrap();
+    	let (coordinate, saved) = opened.saved.iter().max_by_key(|(_, saved)| saved.range.start).unwrap();
+    	let (coordinate, middle) = (*coordinate, migrations::HEADER_LENGTH + (saved.range.start + saved.range.end) / 2);
+    	drop(opened);
+    	bytes[middle] ^= 0x10;
+    	fs::write(&path, &bytes).unwrap();
+
+    	opened = Document::open(&path).unwrap();
+    	assert_eq!(opened.load_chunks_in(opened.get_chunk_bounds(coordinate)).err().map(|error| error.kind()), Some(io::ErrorKind::InvalidData));
+    	fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn snapshots_keep_the_document_as_it_was() {
+    	let path = temporary("snapshot");
+    	let mut document = balls();
+    	let before = document.get_voxel_buffers();
+    	let snapshot = document.snapshot();
+
+    	// edits after the snapshot copy the chunks they change, leaving the others shared
+    	let position = vec3(2.5, 0.5, 0.5);
+    	for chunk in document.get_chunks_in(Aabb::cube(position, 0.5), false) {
+    		chunk.unsubdivide(RoundBrushTip::filler(0.3, position), RoundBrushTip::container(0.3, position));
+    	}
+    	let shared = |coordinate: IVec3| snapshot.chunks.iter().any(|(other, chunk)| {
+    		*other == coordinate && matches!(chunk, SnapshotChunk::Loaded(chunk) if Arc::ptr_eq(chunk, &document.chunks[&coordinate]))
+    	});
+    	assert!(shared(IVec3::ZERO) && !shared(IVec3::new(2, 0, 0)));
+
+    	snapshot.save(&path, DEFAULT_LEVEL, &Progress::new("")).unwrap();
+    	let mut opened = Document::open(&path).unwrap();
+    	opened.load_nearest(Vec3::ZERO, 4).unwrap();
+    	assert_eq!(opened.get_voxel_buffers(), before);
+
+    	// a cancelled save leaves the file as it was
+    	let progress = Progress::new("");
+    	progress.cancel();
+    	assert_eq!(document.snapshot().save(&path, DEFAULT_LEVEL, &progress).err().map(|error| error.kind()), Some(io::ErrorKind::Interrupted));
+    	assert_eq!(Document::open(&path).unwrap().get_saved_count(), 2);
+    	fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn pins_and_guides_are_saved_with_the_document() {
+    	let path = temporary("pins");
+    	let mut document = balls();
+    	document.add_pin(vec3(0.5, 0.75, 0.5), "round off the top");
+    	document.add_pin(vec3(2.5, 0.5, 0.25), "größer?");
+    	let guide = document.add_guide(vec![vec3(0.25, 0.5, 0.25), vec3(0.5, 0.5, 0.25)]);
+    	document.extend_guide(guide, vec3(0.75, 0.5, 0.25));
+
+    	document.save(&path).unwrap();
+    	let opened = Document::open(&path).unwrap();
+    	assert_eq!(opened.get_pins(), document.get_pins());
+    	assert_eq!(opened.get_guides(), document.get_guides());
+    	assert_eq!(opened.get_guides()[0].points.len(), 3);
+    	fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn color_management_is_saved_with_the_document() {
+    	let path = temporary("color");
+    	let mut document = balls();
+    	let color = ColorManagement { working_space: WorkingSpace::AcesCg, tonemap: Tonemap::Filmic, exposure: -1.5 };
+    	document.set_color_management(color);
+
+    	document.save(&path).unwrap();
+    	assert_eq!(Document::open(&path).unwrap().get_color_management(), color);
+    	fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn brush_settings_are_saved_with_the_document() {
+    	let path = temporary("brushes");
+    	let mut document = balls();
+    	let dynamics = Dynamics { strength: 0.8, hardness: 0.3, accumulation: Some(0.5), smoothing: 2 };
+    	let material = MaterialMix { first: 1, second: 0, mix: 0.25 };
+    	let brushes = SavedBrushes {
+    		current: "spray".to_owned(),
+    		brushes: vec![
+    			("round".to_owned(), BrushSettings { size: 0.2, dynamics: Dynamics::default(), material: MaterialMix::default() }),
+    			("spray".to_owned(), BrushSettings { size: 0.05, dynamics, material }),
+    		],
+    	};
+    	document.set_saved_brushes(brushes.clone());
+
+    	document.save(&path).unwrap();
+    	assert_eq!(Document::open(&path).unwrap().get_saved_brushes(), &brushes);
+    	fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn subsurface_scattering_is_saved_with_the_document() {
+    	let path = temporary("subsurface");
+    	let mut document = balls();
+    	document.set_subsurface(1, 0.6);
+
+    	document.save(&path).unwrap();
+    	let mut opened = Document::open(&path).unwrap();
+    	assert_eq!(opened.get_subsurface(), &[0.0, 0.6]);
+    	// chunks read later scatter like the one read right away
+    	opened.load_nearest(Vec3::ZERO, 4).unwrap();
+    	for coordinate in opened.get_chunk_coordinates() {
+    		assert_eq!(opened.get_chunk(coordinate).unwrap().get_material(1).map(|material| material.subsurface), Some(0.6));
+    	}
+    	fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn uncompressed_files_are_upgraded() {
+    	let path = temporary("upgraded");
+    	let document = balls();
+    	let coordinates = document.get_chunk_coordinates();
+
+    	// version 0 had the table in front of the chunks, which weren't compressed
+    	let mut contents = Vec::new();
+    	write_settings(&mut contents, &Settings { resolution: 16, chunk_size: 1.0, stroke: 0, palette: document.palette.clone(), pins: Vec::new(), guides: Vec::new(), color: ColorManagement::default(), brushes: SavedBrushes::default(), subsurface: Vec::new() }).unwrap();
+    	let mut nodes = Vec::new();
+    	let mut table = Vec::new();
+    	let nodes_start = contents.len() + 4 + coordinates.len() * ENTRY_LENGTH;
+    	for coordinate in &coordinates {
+    		let start = nodes.len();
+    		document.get_chunk(*coordinate).unwrap().write_nodes(&mut nodes);
+    		write_entry(&mut table, *coordinate, nodes_start + start..nodes_start + nodes.len());
+    	}
+    	contents.extend_from_slice(&(coordinates.len() as u32).to_le_bytes());
+    	contents.extend_from_slice(&table);
+    	contents.extend_from_slice(&nodes);
+    	fs::write(&path, [b"SWRX\0\0\0\0".as_slice(), &contents].concat()).unwrap();
+
+    	let mut opened = Document::open(&path).unwrap();
+    	opened.load_nearest(Vec3::ZERO, 4).unwrap();
+    	assert_eq!(opened.get_voxel_buffers(), document.get_voxel_buffers());
+    	assert_eq!(opened.get_color_management(), ColorManagement::default());
+    	assert_eq!(opened.get_saved_brushes(), &SavedBrushes::default());
+    	assert!(opened.get_subsurface().is_empty());
+    	fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn documents_of_every_version_open_and_save() {
+    	// each fixture was saved by the writer of its version: two balls, with whatever that version could hold
+    	for version in 0..=migrations().get_version() {
+    		let fixture = format!("{}/tests/fixtures/document-v{version}.swx", env!("CARGO_MANIFEST_DIR"));
+    		let mut opened = Document::open(&fixture).unwrap_or_else(|error| panic!("version {version}: {error}"));
+    		opened.load_nearest(Vec3::ZERO, 4).unwrap();
+    		assert_eq!(opened.get_palette(), &[[0.8, 0.8, 0.8, 1.0], [0.9, 0.3, 0.2, 1.0]], "version {version}");
+    		assert!(opened.is_solid(vec3(0.5, 0.5, 0.5)) && opened.is_solid(vec3(2.5, 0.5, 0.5)), "version {version}");
+    		assert!(!opened.is_solid(vec3(1.5, 0.5, 0.5)), "version {version}");
+
+    		let pins = if version >= 2 { vec![Pin { position: vec3(0.5, 0.5, 0.75), note: "nose".to_owned() }] } else { Vec::new() };
+    		assert_eq!(opened.get_pins(), pins, "version {version}");
+    		let guides = if version >= 3 { vec![Guide { points: vec![vec3(0.0, 0.0, 0.0), vec3(1.0, 0.5, 0.5), vec3(2.0, 1.0, 0.5)] }] } else { Vec::new() };
+    		assert_eq!(opened.get_guides(), guides, "version {version}");
+    		let color = if version >= 4 { ColorManagement { working_space: WorkingSpace::AcesCg, tonemap: Tonemap::Filmic, exposure: 0.5 } } else { ColorManagement::default() };
+    		assert_eq!(opened.get_color_management(), color, "version {version}");
+    		let brushes = if version >= 5 { SavedBrushes { current: "pen".to_owned(), brushes: Vec::new() } } else { SavedBrushes::default() };
+    		assert_eq!(opened.get_saved_brushes(), &brushes, "version {version}");
+    		let subsurface: &[f32] = if version >= 6 { &[0.0, 0.5] } else { &[] };
+    		assert_eq!(opened.get_subsurface(), subsurface, "version {version}");
+
+    		// saving writes the current version, which reads back the same
+    		let path = temporary(&format!("fixture-v{version}"));
+    		opened.save(&path).unwrap();
+    		let mut reopened = Document::open(&path).unwrap();
+    		reopened.load_nearest(Vec3::ZERO, 4).unwrap();
+    		assert_eq!(reopened.get_voxel_buffers(), opened.get_voxel_buffers(), "version {version}");
+    		assert_eq!(reopened.get_pins(), opened.get_pins());
+    		assert_eq!(reopened.get_guides(), opened.get_guides());
+    		assert_eq!(reopened.get_color_management(), opened.get_color_management());
+    		assert_eq!(reopened.get_saved_brushes(), opened.get_saved_brushes());
+    		assert_eq!(reopened.get_subsurface(), opened.get_subsurface());
+    		fs::remove_file(&path).unwrap();
+    	}
+    }
+}
@@ -0,0 +1,191 @@
+//! The history of the edits to the chunks, which can be undone and redone.
+//!
+//! Before an edit changes a chunk, the chunk is kept as it was. Chunks
+//! are copied on write, so keeping one only shares it, and the edit goes
+//! to a copy. Undoing an edit puts the chunks it kept back, keeping the
+//! ones they replace to redo it. The passes of an open stroke make a
+//! single edit.
+
+use super::{Document, DEFAULT_NODE_LIMIT};
+use crate::sculpt::Sculpt;
+
+use std::sync::Arc;
+
+use glam::IVec3;
+
+/// How many edits can be undone.
+const HISTORY_LENGTH: usize = 100;
+
+/// The most nodes the kept chunks may hold together before the oldest edits are forgotten.
+const HISTORY_NODES: usize = DEFAULT_NODE_LIMIT;
+
+/// The chunks an edit changed as they were before it, or `None` for those it created.
+#[derive(Clone)]
+struct ChunkEdit {
+	/// The stroke the edit was made in, if one was open.
+	stroke: Option<u32>,
+	/// The palette of the kept chunks.
+	palette: Vec<[f32; 4]>,
+	subsurface: Vec<f32>,
+	chunks: Vec<(IVec3, Option<Arc<Sculpt>>)>,
+}
+
+impl ChunkEdit {
+	/// Count the nodes of the kept chunks.
+	fn get_node_count(&self) -> usize {
+		self.chunks.iter()
+			.filter_map(|(_, chunk)| chunk.as_ref())
+			.map(|chunk| chunk.get_node_count())
+			.sum()
+	}
+}
+
+/// The edits which can be undone, and the undone ones which can be redone.
+#[derive(Clone, Default)]
+pub struct History {
+	undo: Vec<ChunkEdit>,
+	redo: Vec<ChunkEdit>,
+}
+
+impl History {
+	/// Forget the oldest edits beyond the length or the nodes the history may keep.
+	fn forget(&mut self) {
+		let mut nodes: usize = self.undo.iter().chain(&self.redo).map(ChunkEdit::get_node_count).sum();
+		while self.undo.len() > HISTORY_LENGTH || (nodes > HISTORY_NODES && self.undo.len() > 1) {
+			nodes -= self.undo.remove(0).get_node_count();
+		}
+	}
+}
+
+/// Keep the chunks at some coordinates as they are, before an edit changes them.
+///
+/// The passes of an open stroke add the chunks they change to the edit of the stroke.
+pub fn record(document: &mut Document, coordinates: &[IVec3]) {
+	if coordinates.is_empty() {
+		return;
+	}
+	let stroke = document.open_stroke.as_ref().map(|stroke| stroke.number);
+	let history = &mut document.history;
+	history.redo.clear();
+
+	let edit = match history.undo.last_mut().filter(|edit| stroke.is_some() && edit.stroke == stroke) {
+		Some(edit) => edit,
+		None => {
+			history.undo.push(ChunkEdit { stroke, palette: document.palette.clone(), subsurface: document.subsurface.clone(), chunks: Vec::new() });
+			history.undo.last_mut().unwrap()
+		},
+	};
+	for coordinate in coordinates {
+		if !edit.chunks.iter().any(|(kept, _)| kept == coordinate) {
+			edit.chunks.push((*coordinate, document.chunks.get(coordinate).cloned()));
+		}
+	}
+
+	history.forget();
+}
+
+/// Put back the chunks an edit kept, returning the edit which puts them back again.
+fn revert(document: &mut Document, edit: ChunkEdit) -> ChunkEdit {
+	document.close_stroke();
+	document.revision += 1;
+	let chunks = edit.chunks.into_iter()
+		.map(|(coordinate, chunk)| {
+			let replaced = match chunk {
+				Some(mut chunk) => {
+					// the palette may have changed since the chunk was kept
+					if edit.palette != document.palette || edit.subsurface != document.subsurface {
+						let chunk = Arc::make_mut(&mut chunk);
+						chunk.set_palette(&document.palette);
+						chunk.set_subsurface(&document.subsurface);
+					}
+					document.chunks.insert(coordinate, chunk)
+				},
+				None => document.chunks.remove(&coordinate),
+			};
+
+			(coordinate, replaced)
+		})
+		.collect();
+
+	ChunkEdit {
+		stroke: edit.stroke,
+		palette: document.palette.clone(),
+		subsurface: document.subsurface.clone(),
+		chunks,
+	}
+}
+
+/// Undo the latest edit, returning false if there is none.
+pub fn undo(document: &mut Document) -> bool {
+	let Some(edit) = document.history.undo.pop() else {
+		return false;
+	};
+	let redo = revert(document, edit);
+	document.history.redo.push(redo);
+
+	true
+}
+
+/// Redo the latest undone edit, returning false if there is none.
+pub fn redo(document: &mut Document) -> bool {
+	let Some(edit) = document.history.redo.pop() else {
+		return false;
+	};
+	let undo = revert(document, edit);
+	document.history.undo.push(undo);
+
+	true
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    use crate::brush::{Brush, RoundBrushTip};
+
+    use glam::vec3;
+
+    #[test]
+    fn undo_puts_back_the_chunks_of_a_stroke() {
+    	let mut document = Document::new(32, 1.0);
+    	let mut brush = Brush::new("Round Brush".to_owned(), Box::new(RoundBrushTip::new()));
+    	brush.set_size(0.2);
+    	brush.add(&mut document, vec3(0.5, 0.5, 0.5));
+    	let first = document.get_node_count();
+
+    	// passes of an open stroke are undone together, along with the chunks they created
+    	document.open_stroke();
+    	brush.add(&mut document, vec3(0.9, 0.5, 0.5));
+    	brush.add(&mut document, vec3(1.1, 0.5, 0.5));
+    	document.close_stroke();
+    	assert!(document.get_chunk_coordinates().len() > 1);
+
+    	assert!(undo(&mut document));
+    	assert_eq!(document.get_chunk_coordinates(), vec![IVec3::ZERO]);
+    	assert_eq!(document.get_node_count(), first);
+    	assert!(document.is_solid(vec3(0.5, 0.5, 0.5)) && !document.is_solid(vec3(0.9, 0.5, 0.5)));
+
+    	assert!(redo(&mut document));
+    	assert!(document.is_solid(vec3(1.1, 0.5, 0.5)));
+    	assert!(!redo(&mut document));
+
+    	assert!(undo(&mut document) && undo(&mut document));
+    	assert!(!document.is_solid(vec3(0.5, 0.5, 0.5)));
+    	assert!(!undo(&mut document));
+
+    	// a new edit can't be redone past
+    	brush.add(&mut document, vec3(0.5, 0.5, 0.5));
+    	assert!(!redo(&mut document));
+    }
+
+    #[test]
+    fn undone_chunks_keep_the_current_palette() {
+    	let mut document = Document::new(16, 1.0);
+    	let mut brush = Brush::new("Round Brush".to_owned(), Box::new(RoundBrushTip::new()));
+    	brush.add(&mut document, vec3(0.5, 0.5, 0.5));
+    	document.set_palette(&[[1.0, 0.0, 0.0, 1.0], [0.0, 1.0, 0.0, 1.0]]);
+
+    	assert!(undo(&mut document));
+    	assert_eq!(document.get_material(1).map(|material| material.color), Some([0.0, 1.0, 0.0, 1.0]));
+    }
+}
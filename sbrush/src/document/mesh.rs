@@ -0,0 +1,135 @@
+//! The surface of the voxels as a mesh, for exporting to other programs.
+//!
+//! Every face between a solid cell and an empty one becomes a quad, so
+//! the mesh is as blocky as the voxels, and closed wherever
+//! `watertight::check` finds no issues. Formats made of voxels get the
+//! solid cells themselves instead.
+
+use super::Document;
+
+use glam::{IVec3, Vec3};
+
+/// The number of cells along each axis read at once.
+const BLOCK: i32 = 32;
+
+/// The faces of a cell, as the outward normal and two axes along the face whose cross product is the normal.
+const FACES: [(IVec3, IVec3, IVec3); 6] = [
+	(IVec3::X, IVec3::Y, IVec3::Z),
+	(IVec3::NEG_X, IVec3::Z, IVec3::Y),
+	(IVec3::Y, IVec3::Z, IVec3::X),
+	(IVec3::NEG_Y, IVec3::X, IVec3::Z),
+	(IVec3::Z, IVec3::X, IVec3::Y),
+	(IVec3::NEG_Z, IVec3::Y, IVec3::X),
+];
+
+/// Get the faces between solid and empty cells, as quads wound counter-clockwise seen from outside.
+pub fn surface(document: &Document) -> Vec<[Vec3; 4]> {
+	let voxel_size = document.get_voxel_size();
+
+	let mut quads = Vec::new();
+	for (start, end) in blocks(document) {
+		// the cells around the block tell which of its faces the cells next to it cover
+		let grid = document.get_voxel_grid(start - 1, end + 1);
+		let solid = grid.count_solid();
+		if solid == 0 || solid == (end - start + 2).element_product() as usize {
+			continue;
+		}
+
+		for cell in cells_between(start, end).filter(|cell| grid.get(*cell)) {
+			for (normal, u, v) in FACES {
+				if grid.get(cell + normal) {
+					continue;
+				}
+				let corner = cell + normal.max(IVec3::ZERO);
+				quads.push([corner, corner + u, corner + u + v, corner + v].map(|corner| corner.as_vec3() * voxel_size));
+			}
+		}
+	}
+
+	quads
+}
+
+/// Get the solid cells, counted in voxels from the origin of the document, with their materials.
+pub fn solid_cells(document: &Document) -> Vec<(IVec3, u32)> {
+	let voxel_size = document.get_voxel_size();
+
+	let mut cells = Vec::new();
+	for (start, end) in blocks(document) {
+		let grid = document.get_voxel_grid(start, end);
+		for cell in cells_between(start, end).filter(|cell| grid.get(*cell)) {
+			let material = document.get_material_at((cell.as_vec3() + 0.5) * voxel_size).unwrap_or(0);
+			cells.push((cell, material));
+		}
+	}
+
+	cells
+}
+
+/// Split the cells around everything solid into blocks read at once, as the first cell and the one past the last.
+fn blocks(document: &Document) -> Vec<(IVec3, IVec3)> {
+	let Some(bounds) = document.get_solid_bounds() else {
+		return Vec::new();
+	};
+	let voxel_size = document.get_voxel_size();
+	let low = (bounds.min / voxel_size).floor().as_ivec3();
+	let high = (bounds.max / voxel_size).ceil().as_ivec3();
+
+	cells_between(IVec3::ZERO, (high - low + BLOCK - 1) / BLOCK)
+		.map(|block| {
+			let start = low + block * BLOCK;
+			(start, (start + BLOCK).min(high))
+		})
+		.collect()
+}
+
+/// Go through the cells from one up to another, x first.
+fn cells_between(start: IVec3, end: IVec3) -> impl Iterator<Item = IVec3> {
+	(start.z..end.z).flat_map(move |z| (start.y..end.y).flat_map(move |y| (start.x..end.x).map(move |x| IVec3::new(x, y, z))))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    use crate::aabb::Aabb;
+    use crate::brush::RoundBrushTip;
+    use crate::material::MaterialMix;
+
+    use glam::vec3;
+
+    #[test]
+    fn faces_between_voxels_are_left_out() {
+    	let mut document = Document::new(4, 1.0);
+    	assert!(surface(&document).is_empty());
+
+    	// two voxels side by side, the second in the chunk next to the first
+    	for center in [vec3(0.875, 0.125, 0.125), vec3(1.125, 0.125, 0.125)] {
+    		for chunk in document.get_chunks_in(Aabb::cube(center, 0.1), true) {
+    			chunk.subdivide(RoundBrushTip::filler(0.05, center), RoundBrushTip::container(0.05, center));
+    		}
+    	}
+    	let quads = surface(&document);
+
+    	assert_eq!(quads.len(), 10);
+    	for [a, b, c, _] in quads {
+    		// every face points away from the middle of the two voxels
+    		let normal = (b - a).cross(c - a);
+    		assert!(normal.dot((a + c) * 0.5 - vec3(1.0, 0.125, 0.125)) > 0.0);
+    	}
+    }
+
+    #[test]
+    fn solid_cells_keep_their_materials() {
+    	let mut document = Document::new(4, 1.0);
+    	document.set_palette(&[[1.0, 0.0, 0.0, 1.0], [0.0, 0.0, 1.0, 1.0]]);
+    	assert!(solid_cells(&document).is_empty());
+
+    	for (center, material) in [(vec3(0.875, 0.125, 0.125), 0), (vec3(1.125, 0.125, 0.125), 1)] {
+    		for chunk in document.get_chunks_in(Aabb::cube(center, 0.1), true) {
+    			chunk.subdivide_with(&MaterialMix::single(material), RoundBrushTip::filler(0.05, center), RoundBrushTip::container(0.05, center));
+    		}
+    	}
+
+    	assert_eq!(solid_cells(&document), vec![(IVec3::new(3, 0, 0), 0), (IVec3::new(4, 0, 0), 1)]);
+    }
+}
@@ -0,0 +1,168 @@
+//! The paint layer, kept apart from the shape of the voxels.
+//!
+//! Paint is a color over the material of each leaf. Every paint pass
+//! records what it painted over, so paint has a history of its own,
+//! which can be undone and redone without touching the shape edits
+//! made in between.
+
+use super::Document;
+use crate::sculpt::{Coverage, PaintPatch};
+
+use glam::{IVec3, Vec3};
+
+/// How many paint edits can be undone.
+const HISTORY_LENGTH: usize = 100;
+
+/// The paint a stroke painted over, by chunk, in the order it was painted.
+#[derive(Clone)]
+struct PaintEdit {
+	stroke: u32,
+	patches: Vec<(IVec3, Vec<PaintPatch>)>,
+}
+
+/// The paint edits which can be undone, and the undone ones which can be redone.
+#[derive(Clone, Default)]
+pub struct PaintHistory {
+	undo: Vec<PaintEdit>,
+	redo: Vec<PaintEdit>,
+}
+
+impl PaintHistory {
+	/// Record what a pass painted over, merging the passes of a stroke into one edit.
+	fn record(&mut self, stroke: u32, patches: Vec<(IVec3, Vec<PaintPatch>)>) {
+		if patches.is_empty() {
+			return;
+		}
+		self.redo.clear();
+
+		match self.undo.last_mut().filter(|edit| edit.stroke == stroke) {
+			Some(edit) => edit.patches.extend(patches),
+			None => {
+				self.undo.push(PaintEdit { stroke, patches });
+				if self.undo.len() > HISTORY_LENGTH {
+					self.undo.remove(0);
+				}
+			},
+		}
+	}
+}
+
+/// Paint the voxels of some chunks selected by a classification, as a pass of the current stroke.
+pub fn paint(document: &mut Document, coordinates: &[IVec3], classify: &dyn Fn(f32, Vec3) -> Coverage, paint: u32) {
+	let stroke = document.begin_stroke();
+	let patches = coordinates.iter()
+		.filter_map(|coordinate| {
+			let chunk = document.chunk_mut(*coordinate)?;
+			let patches = chunk.paint(classify, paint);
+
+			(!patches.is_empty()).then_some((*coordinate, patches))
+		})
+		.collect();
+
+	document.paint_history.record(stroke, patches);
+}
+
+/// Put back the paint an edit painted over, returning the edit which puts it back again.
+fn revert(document: &mut Document, edit: PaintEdit) -> PaintEdit {
+	let patches = edit.patches.iter()
+		.rev()
+		.filter_map(|(coordinate, patches)| {
+			// chunks which were emptied since have no paint left to put back
+			let chunk = document.chunk_mut(*coordinate)?;
+
+			Some((*coordinate, chunk.restore_paint(patches)))
+		})
+		.collect();
+
+	PaintEdit {
+		stroke: edit.stroke,
+		patches,
+	}
+}
+
+/// Undo the latest paint edit, returning false if there is none.
+pub fn undo(document: &mut Document) -> bool {
+	let Some(edit) = document.paint_history.undo.pop() else {
+		return false;
+	};
+	let redo = revert(document, edit);
+	document.paint_history.redo.push(redo);
+
+	true
+}
+
+/// Redo the latest undone paint edit, returning false if there is none.
+pub fn redo(document: &mut Document) -> bool {
+	let Some(edit) = document.paint_history.redo.pop() else {
+		return false;
+	};
+	let undo = revert(document, edit);
+	document.paint_history.undo.push(undo);
+
+	true
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    use crate::brush::{Brush, RoundBrushTip};
+    use crate::sculpt::unpack_paint;
+
+    use glam::vec3;
+
+    const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+    const BLUE: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+
+    #[test]
+    fn paint_is_undone_apart_from_the_shape() {
+    	let mut document = Document::new(32, 1.0);
+    	let mut brush = Brush::new("round".to_owned(), Box::new(RoundBrushTip::new()));
+    	brush.set_size(0.3);
+    	brush.add(&mut document, vec3(0.5, 0.5, 0.5));
+
+    	let center = vec3(0.5, 0.5, 0.5);
+    	let edge = vec3(0.5, 0.5, 0.25);
+    	brush.set_size(0.1);
+    	brush.paint(&mut document, center, Some(RED));
+    	brush.paint(&mut document, edge, Some(BLUE));
+    	assert_eq!(unpack_paint(document.get_paint(center)), RED);
+    	assert_eq!(unpack_paint(document.get_paint(edge)), BLUE);
+    	assert_eq!(document.get_paint(vec3(0.5, 0.5, 0.7)), 0);
+
+    	// carving into the painted sculpt isn't part of the paint history
+    	brush.remove(&mut document, vec3(0.5, 0.5, 0.8));
+    	assert!(document.undo_paint());
+    	assert_eq!(document.get_paint(edge), 0);
+    	assert_eq!(unpack_paint(document.get_paint(center)), RED);
+    	assert!(!document.is_solid(vec3(0.5, 0.5, 0.75)));
+
+    	assert!(document.redo_paint());
+    	assert_eq!(unpack_paint(document.get_paint(edge)), BLUE);
+
+    	document.clear_paint();
+    	assert_eq!(document.get_paint(center), 0);
+    	assert!(document.undo_paint());
+    	assert_eq!(unpack_paint(document.get_paint(center)), RED);
+    	assert!(document.validate().is_empty());
+    }
+
+    #[test]
+    fn passes_of_an_open_stroke_are_undone_together() {
+    	let mut document = Document::new(32, 1.0);
+    	let mut brush = Brush::new("round".to_owned(), Box::new(RoundBrushTip::new()));
+    	brush.set_size(0.3);
+    	brush.add(&mut document, vec3(0.5, 0.5, 0.5));
+    	brush.set_size(0.05);
+
+    	document.open_stroke();
+    	brush.paint(&mut document, vec3(0.4, 0.5, 0.5), Some(RED));
+    	brush.paint(&mut document, vec3(0.45, 0.5, 0.5), Some(BLUE));
+    	document.close_stroke();
+    	assert!(document.undo_paint());
+
+    	assert_eq!(document.get_paint(vec3(0.4, 0.5, 0.5)), 0);
+    	assert_eq!(document.get_paint(vec3(0.45, 0.5, 0.5)), 0);
+    	assert!(!document.undo_paint());
+    }
+}
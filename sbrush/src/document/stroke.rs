@@ -0,0 +1,185 @@
+//! Strokes made of several passes, like dragging the brush across the sculpt.
+//!
+//! An open stroke remembers which cells its passes changed, and what
+//! they were before, so later passes can tell the surface the stroke
+//! started on apart from what the stroke itself built up.
+
+use super::Document;
+use crate::aabb::Aabb;
+use crate::sculpt::{SculptCopy, VoxelGrid};
+
+use std::collections::{HashMap, VecDeque};
+
+use glam::{IVec3, Vec3};
+
+/// The neighbors sharing a face with a cell.
+pub const FACES: [IVec3; 6] = [IVec3::X, IVec3::NEG_X, IVec3::Y, IVec3::NEG_Y, IVec3::Z, IVec3::NEG_Z];
+
+/// A stroke which stays open across passes until it is closed.
+#[derive(Clone)]
+pub struct Stroke {
+	/// The number the passes of the stroke mark their edits with.
+	pub number: u32,
+	/// Whether each cell changed by the stroke was solid before it.
+	changed: HashMap<IVec3, bool>,
+}
+
+impl Stroke {
+	/// Open a stroke with a number.
+	pub fn new(number: u32) -> Self {
+		Self {
+			number,
+			changed: HashMap::new(),
+		}
+	}
+}
+
+/// Iterate over the cells of a box, where the low cell is inclusive and the high cell is exclusive.
+pub fn cells(low: IVec3, high: IVec3) -> impl Iterator<Item = IVec3> {
+	(low.z..high.z).flat_map(move |z| (low.y..high.y).flat_map(move |y| (low.x..high.x).map(move |x| IVec3::new(x, y, z))))
+}
+
+/// Get the cells covering a box, with a margin of cells around it.
+pub fn cells_around(reach: Aabb, voxel_size: f32, margin: i32) -> (IVec3, IVec3) {
+	let low = (reach.min / voxel_size).floor().as_ivec3() - margin;
+	let high = (reach.max / voxel_size).ceil().as_ivec3() + margin;
+
+	(low, high)
+}
+
+/// Run an edit, then put back the marked cells it changed.
+///
+/// The cells before the edit are sampled by the caller, over the box
+/// the edit can reach. The cells after the edit are returned.
+pub fn keep(document: &mut Document, before: &VoxelGrid, kept: &VoxelGrid, edit: impl FnOnce(&mut Document)) -> VoxelGrid {
+	let (low, high) = (before.get_low(), before.get_high());
+	let voxel_size = document.get_voxel_size();
+	let bounds = Aabb::new(low.as_vec3() * voxel_size, high.as_vec3() * voxel_size);
+
+	// chunks the edit could create are copied while they are still empty
+	document.get_chunks_in(bounds, true);
+	let copies: Vec<(IVec3, SculptCopy)> = document.chunks.iter()
+		.filter(|(_, chunk)| chunk.get_bounds().intersects(&bounds))
+		.map(|(coordinate, chunk)| (*coordinate, chunk.copy_within(bounds)))
+		.collect();
+
+	edit(document);
+
+	let mut after = document.get_voxel_grid(low, high);
+	let mut changed = VoxelGrid::new(low, high);
+	for cell in cells(low, high) {
+		changed.set(cell, kept.get(cell) && before.get(cell) != after.get(cell));
+	}
+	if changed.count_solid() > 0 {
+		for (coordinate, copy) in &copies {
+			// the edit may have removed the chunk as empty
+			document.create_chunk(*coordinate).restore(copy, &changed, Vec3::ZERO);
+		}
+		for cell in cells(low, high).filter(|cell| changed.get(*cell)) {
+			after.set(cell, before.get(cell));
+		}
+	}
+	document.prune();
+
+	after
+}
+
+/// Run a pass of the open stroke, keeping what the stroke builds up within a depth of its starting surface.
+///
+/// Additions stay within the depth outside of the voxels the stroke
+/// started on, and removals within the depth inside of them.
+pub fn limit(document: &mut Document, reach: Aabb, depth: f32, remove: bool, pass: impl FnOnce(&mut Document)) {
+	let voxel_size = document.get_voxel_size();
+	let steps = (depth / voxel_size).round().max(0.0) as u32;
+	// the surface the depth is measured from may be outside of the reach
+	let (low, high) = cells_around(reach, voxel_size, steps as i32 + 1);
+
+	let before = document.get_voxel_grid(low, high);
+	let mut started = before.clone();
+	if let Some(stroke) = document.open_stroke.as_ref() {
+		for (cell, solid) in &stroke.changed {
+			started.set(*cell, *solid);
+		}
+	}
+
+	// a search inward from the starting surface marks every cell within the depth
+	let mut near = VoxelGrid::new(low, high);
+	let mut queue = VecDeque::new();
+	for cell in cells(low, high).filter(|cell| started.get(*cell) != remove) {
+		near.set(cell, true);
+		queue.push_back((cell, 0));
+	}
+	while let Some((cell, step)) = queue.pop_front() {
+		if step == steps {
+			continue;
+		}
+		for face in FACES {
+			let next = cell + face;
+			if near.contains(next) && !near.get(next) {
+				near.set(next, true);
+				queue.push_back((next, step + 1));
+			}
+		}
+	}
+
+	let mut kept = VoxelGrid::new(low, high);
+	for cell in cells(low, high) {
+		kept.set(cell, !near.get(cell));
+	}
+	let after = keep(document, &before, &kept, pass);
+
+	if let Some(stroke) = document.open_stroke.as_mut() {
+		for cell in cells(low, high).filter(|cell| before.get(*cell) != after.get(*cell)) {
+			stroke.changed.entry(cell).or_insert(before.get(cell));
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    use crate::brush::{Brush, RoundBrushTip};
+
+    use glam::vec3;
+
+    /// Build up the top of a block with passes which each start on the surface the last one left.
+    fn pile_up(limit: Option<f32>) -> Document {
+    	let mut document = Document::new(32, 1.0);
+    	let mut brush = Brush::new("round".to_owned(), Box::new(RoundBrushTip::new()));
+    	brush.set_size(0.1);
+    	brush.add(&mut document, vec3(0.5, 0.5, 0.25));
+    	brush.set_accumulation(limit);
+
+    	document.open_stroke();
+    	for _ in 0..6 {
+    		let top = document.raycast_all(vec3(0.5, 0.5, 0.95), Vec3::NEG_Z)[0].position;
+    		brush.add(&mut document, top);
+    	}
+    	document.close_stroke();
+
+    	document
+    }
+
+    #[test]
+    fn open_strokes_share_a_number() {
+    	let mut document = Document::new(16, 1.0);
+    	document.open_stroke();
+    	let first = document.begin_stroke();
+    	assert_eq!(document.begin_stroke(), first);
+    	document.close_stroke();
+
+    	assert_eq!(document.begin_stroke(), first + 1);
+    }
+
+    #[test]
+    fn accumulation_is_limited_per_stroke() {
+    	// the block reaches 0.375, and each pass adds up to a brush radius on top
+    	let piled = pile_up(None);
+    	assert!(piled.is_solid(vec3(0.5, 0.5, 0.6)));
+
+    	let limited = pile_up(Some(0.5));
+    	assert!(limited.is_solid(vec3(0.5, 0.5, 0.38)));
+    	assert!(!limited.is_solid(vec3(0.5, 0.5, 0.45)));
+    }
+}
@@ -0,0 +1,196 @@
+//! Checks that the voxel surface is a closed manifold before it is exported.
+//!
+//! The surface of the voxels is made of the faces between solid and
+//! empty cells. It is only watertight if every edge is shared by two
+//! faces and the faces around every vertex form a single fan, which
+//! only depends on the eight cells around each vertex.
+
+use super::Document;
+
+use std::collections::HashSet;
+
+use glam::{IVec3, Vec3};
+
+/// The number of vertices along each axis checked at once.
+const BLOCK: i32 = 32;
+
+/// A place where the surface isn't a closed manifold.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SurfaceIssue {
+	/// Two voxels (or two gaps) meet only along an edge ending at the point.
+	Edge(Vec3),
+	/// Voxels (or gaps) meet only at the point.
+	Vertex(Vec3),
+}
+
+impl SurfaceIssue {
+	/// Get the vertex of the voxel grid the issue is at.
+	pub fn position(&self) -> Vec3 {
+		match *self {
+			SurfaceIssue::Edge(position) | SurfaceIssue::Vertex(position) => position,
+		}
+	}
+}
+
+/// How cells meet at a vertex where the surface isn't a manifold.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Contact {
+	Edge,
+	Vertex,
+}
+
+/// The kind of issue at a vertex, by which of the eight cells around it are solid.
+///
+/// Bit 0, 1 and 2 of a cell's index are set for the cells on the positive x, y and z side.
+fn classify(cells: u8) -> Option<Contact> {
+	// the four cells around each half edge leaving the vertex are a face of the 2x2x2 block
+	for (axis, u, v) in [(1, 2, 4), (2, 1, 4), (4, 1, 2)] {
+		for side in [0, axis] {
+			let solid = |index: u8| cells & (1 << (side | index)) != 0;
+			if solid(0) == solid(u | v) && solid(u) == solid(v) && solid(0) != solid(u) {
+				return Some(Contact::Edge);
+			}
+		}
+	}
+
+	if components(cells) > 1 || components(!cells) > 1 {
+		return Some(Contact::Vertex);
+	}
+
+	None
+}
+
+/// Count the groups of cells in a 2x2x2 block connected by their faces.
+fn components(cells: u8) -> u32 {
+	let mut left = cells;
+	let mut count = 0;
+	while left != 0 {
+		let mut group = left & left.wrapping_neg();
+		loop {
+			let grown = (0..8)
+				.filter(|index| group & (1 << index) != 0)
+				.fold(group, |grown, index: u8| grown | (1 << (index ^ 1)) | (1 << (index ^ 2)) | (1 << (index ^ 4)))
+				& cells;
+			if grown == group {
+				break;
+			}
+			group = grown;
+		}
+		left &= !group;
+		count += 1;
+	}
+
+	count
+}
+
+/// Find every vertex of the voxel grid where the surface isn't watertight.
+pub fn check(document: &Document) -> Vec<SurfaceIssue> {
+	let table: [Option<Contact>; 256] = std::array::from_fn(|cells| classify(cells as u8));
+	let resolution = document.get_resolution() as i32;
+	let voxel_size = document.get_voxel_size();
+
+	// vertices on the faces between chunks are seen from both sides
+	let mut seen = HashSet::new();
+	let mut issues = Vec::new();
+	for coordinate in document.get_chunk_coordinates() {
+		let low = coordinate * resolution;
+		let high = low + resolution + 1;
+		for z in (low.z..high.z).step_by(BLOCK as usize) {
+			for y in (low.y..high.y).step_by(BLOCK as usize) {
+				for x in (low.x..high.x).step_by(BLOCK as usize) {
+					let start = IVec3::new(x, y, z);
+					let end = (start + BLOCK).min(high);
+					let grid = document.get_voxel_grid(start - 1, end);
+					let solid = grid.count_solid();
+					if solid == 0 || solid == (end - start + 1).element_product() as usize {
+						continue;
+					}
+
+					for z in start.z..end.z {
+						for y in start.y..end.y {
+							for x in start.x..end.x {
+								let vertex = IVec3::new(x, y, z);
+								let cells = (0..8).fold(0u8, |cells, index| {
+									let offset = IVec3::new(index & 1, (index >> 1) & 1, (index >> 2) & 1);
+									cells | ((grid.get(vertex - 1 + offset) as u8) << index)
+								});
+								let Some(contact) = table[cells as usize] else {
+									continue;
+								};
+								if seen.insert(vertex) {
+									let position = vertex.as_vec3() * voxel_size;
+									issues.push(match contact {
+										Contact::Edge => SurfaceIssue::Edge(position),
+										Contact::Vertex => SurfaceIssue::Vertex(position),
+									});
+								}
+							}
+						}
+					}
+				}
+			}
+		}
+	}
+
+	issues
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    use crate::aabb::Aabb;
+    use crate::sculpt::Coverage;
+
+    use glam::vec3;
+
+    /// Fill some of the finest cells of a document with 16 voxels per chunk.
+    fn filled(cells: &[IVec3]) -> Document {
+    	let mut document = Document::new(16, 1.0);
+    	let voxel_size = document.get_voxel_size();
+    	for cell in cells {
+    		let center = (cell.as_vec3() + 0.5) * voxel_size;
+    		for chunk in document.get_chunks_in(Aabb::cube(center, voxel_size / 2.0), true) {
+    			chunk.apply(&|size, node| {
+    				let low = ((node - size / 2.0) / voxel_size).round().as_ivec3();
+    				let high = ((node + size / 2.0) / voxel_size).round().as_ivec3();
+    				match (cell.cmpge(low).all() && cell.cmplt(high).all(), high - low == IVec3::ONE) {
+    					(false, _) => Coverage::Unchanged,
+    					(true, true) => Coverage::Solid,
+    					(true, false) => Coverage::Mixed,
+    				}
+    			});
+    		}
+    	}
+
+    	document
+    }
+
+    #[test]
+    fn vertices_are_classified() {
+    	assert_eq!(classify(0b0000_0000), None);
+    	assert_eq!(classify(0b0000_0011), None);
+    	assert_eq!(classify(0b0000_1001), Some(Contact::Edge));
+    	assert_eq!(classify(!0b0000_1001), Some(Contact::Edge));
+    	assert_eq!(classify(0b1000_0001), Some(Contact::Vertex));
+    	assert_eq!(classify(!0b1000_0001), Some(Contact::Vertex));
+    	assert_eq!(components(0b0110_1001), 4);
+    }
+
+    #[test]
+    fn touching_voxels_are_reported() {
+    	let voxel = 1.0 / 16.0;
+
+    	assert_eq!(filled(&[IVec3::new(4, 4, 4), IVec3::new(5, 4, 4)]).check_watertight(), vec![]);
+    	assert_eq!(
+    		filled(&[IVec3::new(4, 4, 4), IVec3::new(5, 5, 5)]).check_watertight(),
+    		vec![SurfaceIssue::Vertex(vec3(5.0, 5.0, 5.0) * voxel)],
+    	);
+
+    	// across the boundary between two chunks
+    	let issues = filled(&[IVec3::new(15, 4, 4), IVec3::new(16, 5, 4)]).check_watertight();
+    	let positions: Vec<Vec3> = issues.iter().map(|issue| issue.position()).collect();
+    	assert!(issues.iter().all(|issue| matches!(issue, SurfaceIssue::Edge(_))));
+    	assert_eq!(positions, vec![vec3(16.0, 5.0, 4.0) * voxel, vec3(16.0, 5.0, 5.0) * voxel]);
+    }
+}
@@ -1,27 +1,228 @@
-use crate::brush::{SquareBrushTip, RoundBrushTip};
-use crate::brush::Brush;
-use crate::sculpt::Sculpt;
+use crate::aabb::Aabb;
+use crate::brush::{self, Brush, BrushRegistry, Stamp, Stroke};
+use crate::brush::presets;
+use crate::camera::{self, Camera, CameraController, CameraTransition};
+use crate::color::ColorManagement;
+use crate::document::{BooleanOp, Document, Snapshot, DEFAULT_LEVEL};
+use crate::document::watertight::SurfaceIssue;
+use crate::error::Error;
+use crate::i18n::{tr, tr_args};
+use crate::light::{Light, MAX_LIGHTS};
+use crate::material::{GpuMaterial, Gradient, MaterialMix};
+use crate::palette::{self, PalettePreset};
+use crate::rng::Rng;
+use crate::scene::{ObjectFile, Scene, SceneObject, Snapping, Transform};
+use crate::sculpt::{Channel, Wear};
+use crate::stats::SessionStats;
+use crate::util::backups::{Backups, Version, BACKUP_COUNT};
+use crate::util::progress::Progress;
+use crate::util::watch::FileWatch;
+
+use std::collections::{BTreeMap, HashMap};
+use std::f32::consts::TAU;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use glam::{IVec3, Mat4, Quat, Vec2, Vec3, vec3};
+
+pub mod command;
+pub mod constraint;
+pub mod export;
+pub mod guides;
+pub mod onion;
+pub mod pins;
+pub mod recording;
+pub mod report;
+pub mod review;
+pub mod soak;
+pub mod stencil;
+pub mod timelapse;
+pub mod upload;
+
+use command::{CommandError, CommandRegistry};
+use constraint::{StrokeAnchor, StrokeLock, StrokePlane};
+use onion::Ghost;
+use review::ReviewCapture;
+use soak::Soak;
+use timelapse::{Timelapse, TimelapseCapture};
+use recording::{Macro, MacroStep};
+
+/// How many cells of the voxel grid are shown to each side of a snapped brush.
+const SNAP_GRID_CELLS: i32 = 3;
 
 /// The owner of sculpt-related stuff.
 ///
-/// Holds the document information as well as
+/// Holds the scene of documents as well as
 /// session configuration.
 pub struct Editor {
-	sculpt: Sculpt,
+	scene: Scene,
+	/// How objects and instances snap as they are placed.
+	snapping: Snapping,
+	/// The depth of the voxel grid stamps snap to, if they do.
+	voxel_snap: Option<u32>,
+	/// The lights of the viewport, or none to light the sculpt from the camera.
+	lights: Vec<Light>,
 	current_brush: usize,
-	brushes: Vec<Brush>,
+	/// The brush picked before the current one, to switch back to.
+	previous_brush: usize,
+	/// The ids of the brushes picked by number.
+	brush_slots: BTreeMap<usize, String>,
+	brushes: BrushRegistry,
+	camera: Camera,
+	controller: CameraController,
+	/// Whether the user has navigated away from the front view.
+	free_view: bool,
+	aspect: f32,
+	/// The point of the surface last seen under the cursor, which the brush is placed level with.
+	picked_surface: Option<Vec3>,
+	views: HashMap<usize, Camera>,
+	view_transition: Option<CameraTransition>,
+	view_duration: Duration,
+	stats: Option<SessionStats>,
+	commands: CommandRegistry,
+	recording: Option<Macro>,
+	macros: BTreeMap<String, Macro>,
+	/// Whether a macro is playing, so its steps aren't recorded again.
+	playing: bool,
+	/// Whether strokes affect every surface under the cursor, rather than only one place.
+	sculpt_through: bool,
+	/// The angle from the camera beyond which strokes leave the surface alone, if any.
+	backface_limit: Option<f32>,
+	/// What strokes are locked to while the lock is held.
+	stroke_lock: StrokeLock,
+	/// Whether the lock is held, like while a modifier is held down.
+	stroke_locked: bool,
+	/// Where the locked stroke started, once it has.
+	stroke_anchor: Option<StrokeAnchor>,
+	stroke_plane: Option<StrokePlane>,
+	/// The path of the open stroke, which fills in the gaps between cursor samples.
+	stroke_path: Stroke,
+	/// Whether the open stroke was counted in the statistics yet.
+	stroke_counted: bool,
+	/// Whether strokes draw guides instead of sculpting.
+	drawing_guides: bool,
+	/// The guide the current stroke draws, once it has started one.
+	drawn_guide: Option<usize>,
+	/// Whether strokes paint the paint layer instead of sculpting.
+	painting: bool,
+	paint_color: [f32; 4],
+	/// The save asked for by a command, until it is run.
+	save_request: Option<SaveRequest>,
+	/// Whether a command asked to mask what the view doesn't show, until the view is read back.
+	visible_mask_request: bool,
+	/// Whether a command asked to pick a saved version to restore, until the picker is shown.
+	version_picker_request: bool,
+	/// The earlier state shown as an onion skin, if any.
+	ghost: Option<Ghost>,
+	/// Whether the ghost changed since it was last uploaded.
+	ghost_changed: bool,
+	timelapse: Option<Timelapse>,
+	/// The frame of the timelapse waiting to be captured.
+	timelapse_capture: Option<TimelapseCapture>,
+	/// The images of review packages waiting to be rendered.
+	review_captures: Vec<ReviewCapture>,
+	/// The soak test being run, if any.
+	soak: Option<Soak>,
+}
+
+/// What a save writes the snapshot of an object to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SaveKind {
+	/// The file of the object.
+	File,
+	/// The newest backup of the file of the object, as an autosave.
+	Backup,
+	/// A version of the file of the object, with a thumbnail of the view beside it.
+	Version(Version),
+}
+
+/// A snapshot of an object to save to a file, so it can be saved while it is edited further.
+pub struct SaveRequest {
+	pub snapshot: Snapshot,
+	/// The file of the object.
+	pub path: PathBuf,
+	/// The zstd level the chunks are compressed at.
+	pub level: i32,
+	pub kind: SaveKind,
+}
+
+impl SaveRequest {
+	/// Save the snapshot, reporting to a progress which can cancel it.
+	pub fn run(&self, progress: &Progress) -> io::Result<()> {
+		match &self.kind {
+			SaveKind::File => self.snapshot.save(&self.path, self.level, progress),
+			SaveKind::Backup => self.snapshot.save_backup(&Backups::new(&self.path, BACKUP_COUNT), self.level, progress),
+			SaveKind::Version(version) => self.snapshot.save(&version.path, self.level, progress),
+		}
+	}
+
+	/// Get the path the snapshot is saved to, where a backup is saved beside the file.
+	pub fn get_target(&self) -> &Path {
+		match &self.kind {
+			SaveKind::Version(version) => &version.path,
+			_ => &self.path,
+		}
+	}
+}
+
+/// What became of an object whose file was changed by another program.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FileChange {
+	/// The object had no edits of its own, so it was read again, by name.
+	Reloaded(String),
+	/// The object has edits of its own, so it is only read again when asked to, by name.
+	Conflict(String),
+	/// The file couldn't be read, like while it is still being written, by name and error.
+	Failed(String, String),
 }
 
 impl Default for Editor {
 	/// A default editor/document.
 	fn default() -> Self {
 		Editor {
-			sculpt: Sculpt::new(512),
+			scene: Scene::new(SceneObject::new(tr("object-sculpt"), Document::new(512, 1.0))),
+			snapping: Snapping::default(),
+			voxel_snap: None,
+			lights: Vec::new(),
 			current_brush: 0,
-			brushes: vec![
-				Brush::new("Round Brush".to_owned(), Box::new(RoundBrushTip::new())),
-				Brush::new("Square Brush".to_owned(), Box::new(SquareBrushTip::new())),
-			],
+			previous_brush: 0,
+			brush_slots: presets::BUILT_IN.iter().enumerate().map(|(index, preset)| (index + 1, preset.id.to_owned())).collect(),
+			brushes: BrushRegistry::default(),
+			camera: Camera::default(),
+			controller: CameraController::default(),
+			free_view: false,
+			aspect: 1.0,
+			picked_surface: None,
+			views: HashMap::new(),
+			view_transition: None,
+			view_duration: Duration::from_millis(500),
+			stats: None,
+			commands: CommandRegistry::default(),
+			recording: None,
+			macros: BTreeMap::new(),
+			sculpt_through: false,
+			backface_limit: None,
+			stroke_lock: StrokeLock::default(),
+			stroke_locked: false,
+			stroke_anchor: None,
+			stroke_plane: None,
+			stroke_path: Stroke::new(),
+			stroke_counted: false,
+			drawing_guides: false,
+			drawn_guide: None,
+			painting: false,
+			paint_color: [0.8, 0.1, 0.1, 1.0],
+			playing: false,
+			save_request: None,
+			visible_mask_request: false,
+			version_picker_request: false,
+			ghost: None,
+			ghost_changed: false,
+			timelapse: None,
+			timelapse_capture: None,
+			review_captures: Vec::new(),
+			soak: None,
 		}
 	}
 }
@@ -29,31 +230,1328 @@ impl Default for Editor {
 impl Editor {
 	/// Get the density of the sculpt in voxels per axis.
 	pub fn get_sculpt_resolution(&self) -> u32 {
-		self.sculpt.get_resolution()
+		self.get_document().get_resolution()
+	}
+
+	/// Get the document of the active object.
+	pub fn get_document(&self) -> &Document {
+		&self.scene.get_active().document
+	}
+
+	/// Get the document of the active object, to edit it.
+	fn document_mut(&mut self) -> &mut Document {
+		&mut self.scene.get_active_mut().document
+	}
+
+	/// Get the scene of every object.
+	pub fn get_scene(&self) -> &Scene {
+		&self.scene
+	}
+
+	/// Add an empty object with its own resolution, making it active.
+	///
+	/// The name is numbered if another object has it. Returns the index of the object.
+	pub fn add_object(&mut self, name: &str, resolution: u32) -> usize {
+		let mut document = Document::new(resolution, 1.0);
+		// every object shares the palette, as the viewport has a single material buffer
+		document.set_palette(self.get_document().get_palette());
+		document.set_color_management(self.get_color_management());
+		document.set_node_limit(self.get_document().get_node_limit());
+		let index = self.scene.add(SceneObject::new(name, document));
+		self.set_active_object(index);
+
+		index
+	}
+
+	/// Snapshot the active object, to be saved to a file in the background, compressed at a zstd level.
+	///
+	/// The save is picked up by `take_save_request`, replacing any which wasn't.
+	/// The settings of the brushes are saved along with it.
+	/// The file becomes the file of the object, to be watched once `acknowledge_save` is told it was saved.
+	pub fn request_save(&mut self, path: impl Into<PathBuf>, level: i32) {
+		let path = path.into();
+		let brushes = self.brushes.save(self.current_brush);
+		let object = self.scene.get_active_mut();
+		object.document.set_saved_brushes(brushes);
+		object.file = Some(ObjectFile {
+			watch: FileWatch::new(&path),
+			revision: object.document.get_revision(),
+			autosaved: object.document.get_revision(),
+		});
+		self.save_request = Some(SaveRequest {
+			snapshot: self.get_document().snapshot(),
+			path,
+			level,
+			kind: SaveKind::File,
+		});
+	}
+
+	/// Snapshot the active object, to be saved in the background as a version beside its file.
+	///
+	/// The save is picked up by `take_save_request` like any other, and the
+	/// object keeps its edits as unsaved changes to the file.
+	pub fn request_version(&mut self) -> Result<Version, Error> {
+		let Some(file) = self.scene.get_active().file.as_ref() else {
+			return Err(Error::NoFile);
+		};
+		let path = file.watch.get_path().to_path_buf();
+		let version = Backups::new(&path, BACKUP_COUNT).next_version()?;
+		self.save_request = Some(SaveRequest {
+			snapshot: self.get_document().snapshot(),
+			path,
+			level: DEFAULT_LEVEL,
+			kind: SaveKind::Version(version.clone()),
+		});
+
+		Ok(version)
+	}
+
+	/// Snapshot the objects edited since they were last saved or autosaved, to be saved in the background as backups of their files.
+	pub fn take_autosaves(&mut self) -> Vec<SaveRequest> {
+		let mut autosaves = Vec::new();
+		for index in 0..self.scene.get_objects().len() {
+			let Some(object) = self.scene.get_object_mut(index) else {
+				continue;
+			};
+			let revision = object.document.get_revision();
+			let Some(file) = object.file.as_mut().filter(|file| file.revision != revision && file.autosaved != revision) else {
+				continue;
+			};
+			file.autosaved = revision;
+			autosaves.push(SaveRequest {
+				snapshot: object.document.snapshot(),
+				path: file.watch.get_path().to_path_buf(),
+				level: DEFAULT_LEVEL,
+				kind: SaveKind::Backup,
+			});
+		}
+
+		autosaves
+	}
+
+	/// Get the saved versions of the file of the active object, newest first.
+	pub fn get_versions(&self) -> io::Result<Vec<Version>> {
+		match self.scene.get_active().file.as_ref() {
+			Some(file) => Backups::new(file.watch.get_path(), BACKUP_COUNT).get_versions(),
+			None => Ok(Vec::new()),
+		}
+	}
+
+	/// Put a saved version in place of the active object, keeping its file to be saved over.
+	///
+	/// The version becomes an unsaved change to the file, and the edits before it can't be undone.
+	pub fn restore_version(&mut self, version: &Version) -> Result<(), Error> {
+		let document = Document::open(&version.path)?;
+		self.document_mut().restore(document);
+		let palette = self.get_document().get_palette().to_vec();
+		self.set_palette(&palette);
+
+		Ok(())
+	}
+
+	/// Ask for the picker of saved versions to be shown.
+	pub fn request_version_picker(&mut self) {
+		self.version_picker_request = true;
+	}
+
+	/// Take the request to show the picker of saved versions, if there is one.
+	pub fn take_version_picker_request(&mut self) -> bool {
+		std::mem::take(&mut self.version_picker_request)
+	}
+
+	/// Take the latest save which was asked for, to run it.
+	pub fn take_save_request(&mut self) -> Option<SaveRequest> {
+		self.save_request.take()
+	}
+
+	/// Open a saved document as a new object named after the file, making it active.
+	///
+	/// Its palette becomes the palette of every object, and the brushes
+	/// take on the settings saved with it. The chunks are
+	/// read as they are edited, or a few at a time by `load_saved_chunks`.
+	/// Returns the index of the object.
+	pub fn open_document(&mut self, path: impl AsRef<Path>) -> Result<usize, Error> {
+		let path = path.as_ref();
+		let mut document = Document::open(path)?;
+		document.set_node_limit(self.get_document().get_node_limit());
+		let palette = document.get_palette().to_vec();
+		if let Some(current) = self.brushes.restore(document.get_saved_brushes()) {
+			self.current_brush = current;
+		}
+		let name = path.file_stem()
+			.map(|stem| stem.to_string_lossy().into_owned())
+			.unwrap_or_else(|| tr("object-sculpt"));
+
+		let index = self.scene.add(SceneObject::new(name, document));
+		self.set_palette(&palette);
+		self.set_active_object(index);
+		let object = self.scene.get_active_mut();
+		object.file = Some(ObjectFile {
+			watch: FileWatch::new(path),
+			revision: object.document.get_revision(),
+			autosaved: object.document.get_revision(),
+		});
+
+		Ok(index)
+	}
+
+	/// Take a file this editor saved as unchanged, so the save isn't mistaken for another program changing it.
+	pub fn acknowledge_save(&mut self, path: &Path) {
+		for index in 0..self.scene.get_objects().len() {
+			let file = self.scene.get_object_mut(index).and_then(|object| object.file.as_mut());
+			if let Some(file) = file.filter(|file| file.watch.get_path() == path) {
+				file.watch.acknowledge();
+			}
+		}
+	}
+
+	/// Check the files of the objects for changes by other programs.
+	///
+	/// Objects without edits since their file was opened or saved are
+	/// read again right away, and the rest are left for `reload_document`.
+	/// Those keep their edits, and the chunks they haven't read yet come
+	/// from the file as it was opened, which is held in memory.
+	pub fn check_files(&mut self) -> Vec<FileChange> {
+		let mut changes = Vec::new();
+		for index in 0..self.scene.get_objects().len() {
+			let Some(object) = self.scene.get_object_mut(index) else {
+				continue;
+			};
+			let name = object.name.clone();
+			let revision = object.document.get_revision();
+			let Some(file) = object.file.as_mut() else {
+				continue;
+			};
+			if !file.watch.check() {
+				continue;
+			}
+
+			if file.revision != revision {
+				changes.push(FileChange::Conflict(name));
+				continue;
+			}
+			changes.push(match self.reload_object(index) {
+				Ok(()) => FileChange::Reloaded(name),
+				Err(error) => FileChange::Failed(name, error.to_string()),
+			});
+		}
+
+		changes
+	}
+
+	/// Read the active object again from its file, dropping its edits since it was opened or saved.
+	pub fn reload_document(&mut self) -> Result<(), Error> {
+		self.reload_object(self.scene.get_active_index())
+	}
+
+	/// Read an object again from its file, keeping where it is placed.
+	fn reload_object(&mut self, index: usize) -> Result<(), Error> {
+		let Some(object) = self.scene.get_object_mut(index) else {
+			return Err(Error::MissingObject(index));
+		};
+		let Some(file) = object.file.as_mut() else {
+			return Err(Error::NoFile);
+		};
+		file.watch.acknowledge();
+		let limit = object.document.get_node_limit();
+		object.document = Document::open(file.watch.get_path())?;
+		object.document.set_node_limit(limit);
+		let palette = object.document.get_palette().to_vec();
+		self.set_palette(&palette);
+
+		if let Some(object) = self.scene.get_object_mut(index) {
+			let revision = object.document.get_revision();
+			if let Some(file) = object.file.as_mut() {
+				file.revision = revision;
+				file.autosaved = revision;
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Read up to a number of the chunks objects still have in their files, nearest to the camera first.
+	///
+	/// Returns how many were read.
+	pub fn load_saved_chunks(&mut self, count: usize) -> Result<usize, Error> {
+		let eye = self.camera.eye();
+		let mut loaded = 0;
+		for index in 0..self.scene.get_objects().len() {
+			let Some(object) = self.scene.get_object_mut(index) else {
+				continue;
+			};
+			let point = object.transform.to_local(eye);
+			loaded += object.document.load_nearest(point, count - loaded)?;
+			if loaded == count {
+				break;
+			}
+		}
+
+		Ok(loaded)
+	}
+
+	/// Copy an object, making the copy active. Returns the index of the copy.
+	pub fn duplicate_object(&mut self, index: usize) -> Option<usize> {
+		let copy = self.scene.duplicate(index)?;
+		self.set_active_object(copy);
+
+		Some(copy)
+	}
+
+	/// Delete an object, returning false if it is missing or the last one.
+	pub fn delete_object(&mut self, index: usize) -> bool {
+		self.document_mut().close_stroke();
+
+		self.scene.remove(index).is_some()
+	}
+
+	/// Make an object the one being edited, returning false if it is missing.
+	pub fn set_active_object(&mut self, index: usize) -> bool {
+		self.document_mut().close_stroke();
+
+		self.scene.set_active(index)
+	}
+
+	/// Place the active object in the world, snapped by the snapping options.
+	pub fn set_object_transform(&mut self, transform: Transform) {
+		let snapped = self.scene.snap(self.scene.get_active_index(), transform, &self.snapping);
+		self.scene.get_active_mut().transform = snapped;
+	}
+
+	/// Set how objects and instances snap as they are placed.
+	pub fn set_snapping(&mut self, snapping: Snapping) {
+		self.snapping = snapping;
+	}
+
+	/// Get how objects and instances snap as they are placed.
+	pub fn get_snapping(&self) -> Snapping {
+		self.snapping
+	}
+
+	/// Snap the centers of stamps to the voxel grid of the active object at a depth, or stop with None.
+	///
+	/// Depth 0 spaces the grid a chunk apart, and each depth below halves it, like the octree.
+	pub fn set_snap(&mut self, depth: Option<u32>) {
+		self.voxel_snap = depth;
+	}
+
+	/// Get the depth of the voxel grid stamps snap to, if they do.
+	pub fn get_snap(&self) -> Option<u32> {
+		self.voxel_snap
+	}
+
+	/// Add a light to the viewport, returning false if it already has `MAX_LIGHTS`.
+	///
+	/// Once there is a light, the sculpt is no longer lit from the camera.
+	pub fn add_light(&mut self, light: Light) -> bool {
+		if self.lights.len() >= MAX_LIGHTS {
+			return false;
+		}
+		self.lights.push(light);
+
+		true
+	}
+
+	/// Remove every light, lighting the sculpt from the camera again.
+	pub fn clear_lights(&mut self) {
+		self.lights.clear();
+	}
+
+	/// Get the lights of the viewport, in the order they were added.
+	pub fn get_lights(&self) -> &[Light] {
+		&self.lights
+	}
+
+	/// Get the spacing of the voxel grid stamps snap to, in the space of the active object, if they do.
+	fn get_snap_spacing(&self) -> Option<f32> {
+		self.voxel_snap.map(|depth| self.get_document().get_chunk_size() / (1u64 << depth.min(63)) as f32)
+	}
+
+	/// Snap a position in the world to the voxel grid of the active object, if stamps snap.
+	fn snap_to_voxels(&self, world_position: Vec3) -> Vec3 {
+		let Some(spacing) = self.get_snap_spacing() else {
+			return world_position;
+		};
+		let transform = self.get_object_transform();
+		let position = (transform.to_local(world_position) / spacing).round() * spacing;
+
+		transform.to_world(position)
+	}
+
+	/// Get the lines of the voxel grid around where the brush stamps at a point of the screen, if stamps snap.
+	///
+	/// The lines cross the snapped center, across the axis of the active
+	/// object the view looks along most. Each is a pair of ends in the world.
+	pub fn get_snap_grid(&self, x: f32, y: f32) -> Vec<[Vec3; 2]> {
+		let Some(spacing) = self.get_snap_spacing() else {
+			return Vec::new();
+		};
+		let transform = self.get_object_transform();
+		let center = transform.to_local(self.get_stamp(x, y).center);
+		let (_, direction) = self.screen_ray(x, y);
+		let normal = transform.to_local_vector(direction).abs().max_position();
+		let (across, along) = (Vec3::AXES[(normal + 1) % 3], Vec3::AXES[(normal + 2) % 3]);
+		let reach = SNAP_GRID_CELLS as f32 * spacing;
+
+		(-SNAP_GRID_CELLS..=SNAP_GRID_CELLS)
+			.flat_map(|step| {
+				let offset = step as f32 * spacing;
+				[
+					[center + across * offset - along * reach, center + across * offset + along * reach],
+					[center + along * offset - across * reach, center + along * offset + across * reach],
+				]
+			})
+			.map(|line| line.map(|end| transform.to_world(end)))
+			.collect()
+	}
+
+	/// Get the placement of the active object in the world.
+	pub fn get_object_transform(&self) -> Transform {
+		self.scene.get_active().transform
+	}
+
+	/// Combine another object, the cutter, with the active one where it is placed in the world.
+	///
+	/// The cutter is voxelized again through both placements, at the
+	/// resolution of the active object, then hidden so the result shows.
+	/// Returns false if the cutter is missing or the active object itself, or the active object is locked.
+	pub fn apply_boolean(&mut self, object: usize, op: BooleanOp) -> bool {
+		if object == self.scene.get_active_index() || self.scene.get_active().locked {
+			return false;
+		}
+		let Some(cutter) = self.scene.get_object_mut(object) else {
+			return false;
+		};
+		// a chunk of the cutter which can't be read is left out, like any missing chunk
+		let _ = cutter.document.load_chunks_in(Aabb::new(Vec3::NEG_INFINITY, Vec3::INFINITY));
+		cutter.visible = false;
+		let to_cutter = cutter.transform.to_matrix().inverse() * self.get_object_transform().to_matrix();
+		let cutter = self.scene.get_objects()[object].document.clone();
+
+		self.document_mut().close_stroke();
+		self.document_mut().apply_boolean(&cutter, to_cutter, op);
+
+		true
+	}
+
+	/// Show or hide an object, returning false if it is missing.
+	pub fn set_object_visible(&mut self, index: usize, visible: bool) -> bool {
+		let Some(object) = self.scene.get_object_mut(index) else {
+			return false;
+		};
+		object.visible = visible;
+
+		true
+	}
+
+	/// Lock an object against strokes, or unlock it, returning false if it is missing.
+	pub fn set_object_locked(&mut self, index: usize, locked: bool) -> bool {
+		let Some(object) = self.scene.get_object_mut(index) else {
+			return false;
+		};
+		object.locked = locked;
+
+		true
+	}
+
+	/// Show only the active object, or every visible one again.
+	pub fn set_solo(&mut self, solo: bool) {
+		self.scene.set_solo(solo);
+	}
+
+	/// Show the active object once more, at a placement in the world.
+	///
+	/// Instances share the voxels of the object, so they follow its edits without taking more memory.
+	pub fn add_instance(&mut self, transform: Transform) {
+		let snapped = self.scene.snap(self.scene.get_active_index(), transform, &self.snapping);
+		self.scene.get_active_mut().instances.push(snapped);
+	}
+
+	/// Scatter instances of the active object on the ground around it, within a radius.
+	///
+	/// Each instance is turned around the vertical axis and scaled a little,
+	/// so the copies don't look alike, then snapped like any placement.
+	/// The same seed scatters them the same way.
+	pub fn scatter_instances(&mut self, count: usize, radius: f32, seed: u32) {
+		let mut rng = Rng::new(seed);
+		let origin = self.get_object_transform();
+		for _ in 0..count {
+			// the square root spreads the instances evenly over the disc
+			let distance = radius * rng.next_f32().sqrt();
+			let angle = rng.next_f32() * TAU;
+			let turn = Quat::from_rotation_z(rng.next_f32() * TAU);
+			let scale = origin.scale * (0.75 + 0.5 * rng.next_f32());
+
+			self.add_instance(Transform {
+				translation: origin.translation + vec3(angle.cos(), angle.sin(), 0.0) * distance,
+				rotation: turn * origin.rotation,
+				scale,
+			});
+		}
+	}
+
+	/// Remove the instances of the active object, returning how many there were.
+	pub fn clear_instances(&mut self) -> usize {
+		std::mem::take(&mut self.scene.get_active_mut().instances).len()
+	}
+
+	/// Get the instances of the active object.
+	pub fn get_instances(&self) -> &[Transform] {
+		&self.scene.get_active().instances
+	}
+
+	/// Switch to a brush by its id, like `round`, or its name.
+	///
+	/// Returns whether there is such a brush.
+	pub fn set_brush_by_name(&mut self, name: &str) -> bool {
+		let Some(index) = self.brushes.find(name) else {
+			return false;
+		};
+		if index != self.current_brush {
+			self.previous_brush = self.current_brush;
+			self.current_brush = index;
+		}
+
+		true
+	}
+
+	/// Switch back to the brush picked before the current one.
+	pub fn toggle_last_brush(&mut self) {
+		std::mem::swap(&mut self.current_brush, &mut self.previous_brush);
+	}
+
+	/// Bind a numbered slot to a brush by its id or name, returning whether there is such a brush.
+	pub fn set_brush_slot(&mut self, slot: usize, name: &str) -> bool {
+		let Some(id) = self.brushes.find(name).and_then(|index| self.brushes.get_id(index)) else {
+			return false;
+		};
+		self.brush_slots.insert(slot, id.to_owned());
+
+		true
+	}
+
+	/// Switch to the brush bound to a numbered slot, returning false if nothing is bound to it.
+	pub fn select_brush_slot(&mut self, slot: usize) -> bool {
+		let Some(id) = self.brush_slots.get(&slot).cloned() else {
+			return false;
+		};
+
+		self.set_brush_by_name(&id)
 	}
 
-	/// Set the brush type.
-	pub fn set_brush(&mut self, brush: usize) {
-		self.current_brush = brush.clamp(0, self.brushes.len());
+	/// Get the ids of the brushes bound to numbered slots.
+	pub fn get_brush_slots(&self) -> &BTreeMap<usize, String> {
+		&self.brush_slots
+	}
+
+	/// Get the current brush.
+	pub fn get_brush(&self) -> &Brush {
+		&self.brushes[self.current_brush]
+	}
+
+	/// Get the id of the current brush.
+	pub fn get_brush_id(&self) -> &str {
+		self.brushes.get_id(self.current_brush).unwrap_or_default()
+	}
+
+	/// Get every brush, by id.
+	pub fn get_brushes(&self) -> &BrushRegistry {
+		&self.brushes
+	}
+
+	/// Set the size of the current brush relative to a chunk.
+	pub fn set_brush_size(&mut self, size: f32) {
+		self.brushes[self.current_brush].set_size(size);
+	}
+
+	/// Set the size of the current brush to span a number of the finest voxels, like the block of the voxel pen.
+	pub fn set_brush_voxels(&mut self, count: u32) {
+		let resolution = self.get_document().get_resolution();
+		self.set_brush_size(count as f32 / (2.0 * resolution as f32));
+	}
+
+	/// Set how strongly the current brush applies, from 0 to 1.
+	pub fn set_brush_strength(&mut self, strength: f32) {
+		self.brushes[self.current_brush].set_strength(strength);
+	}
+
+	/// Set how far toward its edge the current brush refines down to single voxels, from 0 to 1.
+	pub fn set_brush_hardness(&mut self, hardness: f32) {
+		self.brushes[self.current_brush].set_hardness(hardness);
+	}
+
+	/// Limit how much a stroke of the current brush builds up or carves away, relative to its size.
+	pub fn set_brush_accumulation(&mut self, limit: Option<f32>) {
+		self.brushes[self.current_brush].set_accumulation(limit);
+	}
+
+	/// Set the smoothing iterations of the current brush (0 disables it).
+	pub fn set_brush_smoothing(&mut self, iterations: u32) {
+		self.brushes[self.current_brush].set_smoothing(iterations);
+	}
+
+	/// Set the palette material the current brush adds, or two dithered together.
+	///
+	/// Fails, leaving the brush as it was, if a material isn't in the palette.
+	pub fn set_brush_material(&mut self, material: MaterialMix) -> Result<(), Error> {
+		let count = self.get_document().get_palette().len();
+		if let Some(index) = [material.first, material.second].into_iter().find(|index| *index as usize >= count) {
+			return Err(Error::MissingMaterial { index, count });
+		}
+		self.brushes[self.current_brush].set_material(material);
+
+		Ok(())
+	}
+
+	/// Run a typed command, like `brush size 0.05`.
+	///
+	/// Returns a message for the user, which may be empty.
+	pub fn run_command(&mut self, line: &str) -> Result<String, CommandError> {
+		let (command, args) = self.commands.parse(line)?;
+		let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+		let was_recording = self.recording.as_ref().map(|recording| recording.steps.len());
+		let message = command.run(self, &args)?;
+
+		// commands starting or stopping the recording aren't part of it, and
+		// strokes made by a command are already recorded as strokes
+		if let (Some(steps), Some(recording)) = (was_recording, self.recording.as_mut()) {
+			if recording.steps.len() == steps && !self.playing {
+				recording.steps.push(MacroStep::Command(line.trim().to_owned()));
+			}
+		}
+
+		Ok(message)
+	}
+
+	/// Start recording commands and strokes into a macro.
+	///
+	/// Anything recorded but not yet stopped is discarded.
+	pub fn start_recording(&mut self, name: &str) {
+		self.recording = Some(Macro::new(name));
+	}
+
+	/// Stop recording, keeping the macro under its name.
+	///
+	/// Returns false if nothing was being recorded.
+	pub fn stop_recording(&mut self) -> bool {
+		let Some(recording) = self.recording.take() else {
+			return false;
+		};
+		self.macros.insert(recording.name.clone(), recording);
+
+		true
+	}
+
+	/// Check whether a macro is being recorded.
+	pub fn is_recording(&self) -> bool {
+		self.recording.is_some()
+	}
+
+	/// Get a recorded macro.
+	pub fn get_macro(&self, name: &str) -> Option<&Macro> {
+		self.macros.get(name)
+	}
+
+	/// Get every recorded macro, by name.
+	pub fn get_macros(&self) -> impl Iterator<Item = &Macro> {
+		self.macros.values()
+	}
+
+	/// Re-run a recorded macro.
+	///
+	/// With a placement, the first stroke lands on the given position
+	/// and the strokes are scaled around it.
+	pub fn play_macro(&mut self, name: &str, placement: Option<(Vec3, f32)>) -> Result<(), CommandError> {
+		let Some(recorded) = self.macros.get(name) else {
+			return Err(CommandError::Failed(tr_args("command-macro-missing", &[("name", name)])));
+		};
+		let recorded = match placement {
+			Some((position, scale)) => recorded.placed(position, scale),
+			None => recorded.clone(),
+		};
+
+		// the ghost shows where the replay started from
+		if self.is_onion_skin() {
+			self.set_onion_skin(true);
+		}
+
+		self.play_steps(&recorded)
+	}
+
+	/// Run the steps of a macro, without recording them again.
+	fn play_steps(&mut self, recorded: &Macro) -> Result<(), CommandError> {
+		let playing = self.playing;
+		self.playing = true;
+		// joined samples are filled in between like they were as they were recorded
+		let mut path = Stroke::new();
+		let result = recorded.steps.iter().try_for_each(|step| match step {
+			MacroStep::Command(line) => self.run_command(line).map(|_| ()),
+			MacroStep::Stroke { remove, brush, position, size, joined } => {
+				let current_brush = self.current_brush;
+				// a brush which is gone leaves the stroke to the current one
+				self.current_brush = self.brushes.find(brush).unwrap_or(current_brush);
+				let current_size = self.brushes[self.current_brush].get_size();
+				self.brushes[self.current_brush].set_size(*size);
+				if !joined {
+					path = Stroke::new();
+					if !self.scene.get_active().locked {
+						self.record_stroke();
+					}
+				}
+				for stamp in path.follow(self.snap_to_voxels(*position), self.get_stroke_spacing()) {
+					self.stroke(*remove, stamp);
+				}
+				self.brushes[self.current_brush].set_size(current_size);
+				self.current_brush = current_brush;
+
+				Ok(())
+			},
+		});
+		self.playing = playing;
+
+		result
 	}
 
-	/// Get the buffer for the sculpted voxels.
-	pub fn get_voxel_buffer(&self) -> Vec<u32> {
-		self.sculpt.get_voxel_buffer()
+	/// Map a screen position onto the sculpt, like a stroke would.
+	pub fn screen_to_world(&self, x: f32, y: f32) -> Vec3 {
+		self.brush_position(x, y)
 	}
 
-	/// Get the buffer for the used materials.
-	pub fn get_material_buffer(&self) -> Vec<f32> {
-		self.sculpt.get_material_buffer()
+	/// Get the commands which can be run.
+	pub fn get_commands(&self) -> &CommandRegistry {
+		&self.commands
 	}
 
-	/// Draw additively on the sculpt.
+	/// Turn post-stroke smoothing on or off for the current brush.
+	pub fn toggle_smoothing(&mut self) {
+		let brush = &mut self.brushes[self.current_brush];
+		brush.set_smoothing(if brush.get_smoothing() == 0 { 1 } else { 0 });
+	}
+
+	/// Get the buffers for the sculpted voxels of every chunk of an object.
+	pub fn get_voxel_buffers(&self, object: usize) -> Vec<(IVec3, Vec<u32>)> {
+		self.scene.get_object(object).map_or_else(Vec::new, |object| object.document.get_voxel_buffers())
+	}
+
+	/// Get the buffers for the edit heat of every chunk of an object.
+	pub fn get_heat_buffers(&self, object: usize) -> Vec<(IVec3, Vec<f32>)> {
+		self.scene.get_object(object).map_or_else(Vec::new, |object| {
+			let channel = Channel::Heat {
+				stroke: object.document.get_stroke(),
+				decay: brush::HEAT_DECAY,
+			};
+
+			object.document.get_channel_buffers(channel)
+		})
+	}
+
+	/// Get the buffers for the paint layer of every chunk of an object.
+	pub fn get_paint_buffers(&self, object: usize) -> Vec<(IVec3, Vec<u32>)> {
+		self.scene.get_object(object).map_or_else(Vec::new, |object| object.document.get_paint_buffers())
+	}
+
+	/// Get the buffers for the mask of every chunk of an object.
+	pub fn get_mask_buffers(&self, object: usize) -> Vec<(IVec3, Vec<f32>)> {
+		self.scene.get_object(object).map_or_else(Vec::new, |object| object.document.get_channel_buffers(Channel::Mask))
+	}
+
+	/// Mask the voxels in cavities (or, inverted, on edges and peaks),
+	/// protecting them from removal strokes.
+	pub fn mask_cavities(&mut self, invert: bool) {
+		let radius = 2.0 * self.get_document().get_voxel_size();
+		self.document_mut().mask_by_cavity(radius, invert);
+	}
+
+	/// Mask the voxels turned away from the camera by more than an angle,
+	/// protecting them from removal strokes.
+	pub fn mask_facing(&mut self, max_angle: f32) {
+		let toward = self.get_object_transform().to_local_vector(-self.navigation_camera().forward());
+		self.document_mut().mask_by_facing(toward, max_angle);
+	}
+
+	/// Fill the active object with a ramp of materials from one side of it to the other along an axis.
+	///
+	/// Returns false if there is nothing to fill.
+	pub fn fill_gradient_along(&mut self, axis: Vec3, ramp: Vec<u32>) -> bool {
+		let Some(bounds) = self.get_document().get_solid_bounds() else {
+			return false;
+		};
+		// the corners furthest apart along the axis
+		let start = Vec3::select(axis.cmpge(Vec3::ZERO), bounds.min, bounds.max);
+		let end = Vec3::select(axis.cmpge(Vec3::ZERO), bounds.max, bounds.min);
+		let axis = axis.normalize_or_zero();
+
+		self.document_mut().fill_gradient(&Gradient {
+			start,
+			end: start + axis * (end - start).dot(axis),
+			ramp,
+		});
+
+		true
+	}
+
+	/// Fill the active object with a ramp of materials between the sculpt under two points of the screen.
+	pub fn fill_gradient_between(&mut self, start: Vec2, end: Vec2, ramp: Vec<u32>) {
+		let transform = self.get_object_transform();
+		let start = transform.to_local(self.brush_position(start.x, start.y));
+		let end = transform.to_local(self.brush_position(end.x, end.y));
+
+		self.document_mut().fill_gradient(&Gradient { start, end, ramp });
+	}
+
+	/// Wear down the convex edges of the active object, like the chipped edges of an old prop.
+	pub fn wear(&mut self, wear: Wear) {
+		self.document_mut().wear(&wear);
+	}
+
+	/// Collapse the sculpt into blocks of an octree depth, for a low resolution voxel art look.
+	pub fn quantize(&mut self, depth: u32) {
+		self.document_mut().quantize(depth);
+	}
+
+	/// Count the inconsistencies in the document.
+	pub fn validate(&self) -> usize {
+		self.get_document().validate().len()
+	}
+
+	/// Count the inconsistencies fixed in the document as its chunks were read from its file.
+	pub fn get_repaired_count(&self) -> usize {
+		self.get_document().get_repaired_count()
+	}
+
+	/// Fix the inconsistencies in the document, returning how many there were.
+	pub fn repair(&mut self) -> usize {
+		self.document_mut().repair()
+	}
+
+	/// Find where the surface isn't watertight, which has to be fixed before exporting a mesh.
+	pub fn check_watertight(&self) -> Vec<SurfaceIssue> {
+		self.get_document().check_watertight()
+	}
+
+	/// Ask for everything the view doesn't show to be masked, once the surface seen through it is read back.
+	pub fn request_visible_mask(&mut self) {
+		self.visible_mask_request = true;
+	}
+
+	/// Take the request to mask what the view doesn't show, if there is one.
+	pub fn take_visible_mask_request(&mut self) -> bool {
+		std::mem::take(&mut self.visible_mask_request)
+	}
+
+	/// Mask every voxel of the active object except the surface seen from the view, so only it can be carved.
+	///
+	/// Takes the point seen through each pixel showing the object, in the world, along with the direction of its ray.
+	pub fn mask_visible(&mut self, points: &[(Vec3, Vec3)]) {
+		let object = self.scene.get_active();
+		let nudge = object.document.get_voxel_size() / 4.0;
+		// instances show the same voxels, so each point is taken into the placement it is on
+		let inside: Vec<Vec3> = points.iter()
+			.filter_map(|(position, direction)| object.placements()
+				.map(|transform| transform.to_local(*position) + transform.to_local_vector(*direction).normalize_or_zero() * nudge)
+				.find(|point| object.document.is_solid(*point)))
+			.collect();
+
+		self.document_mut().mask_except(&inside);
+	}
+
+	/// Unmask every voxel.
+	pub fn clear_mask(&mut self) {
+		self.document_mut().clear_mask();
+	}
+
+	/// Let strokes paint over the material of the voxels instead of sculpting them.
+	///
+	/// Additive strokes paint with the paint color, while subtractive ones remove paint.
+	pub fn set_painting(&mut self, painting: bool) {
+		self.painting = painting;
+	}
+
+	/// Check whether strokes paint instead of sculpting.
+	pub fn is_painting(&self) -> bool {
+		self.painting
+	}
+
+	/// Set the color painted by strokes, where the alpha is how much it covers the material.
+	pub fn set_paint_color(&mut self, color: [f32; 4]) {
+		self.paint_color = color;
+	}
+
+	/// Get the color painted by strokes.
+	pub fn get_paint_color(&self) -> [f32; 4] {
+		self.paint_color
+	}
+
+	/// Remove the paint of every voxel, keeping the shape.
+	pub fn clear_paint(&mut self) {
+		self.document_mut().clear_paint();
+	}
+
+	/// Undo the latest edit of the active object, paint included. Returns false if there is none.
+	pub fn undo(&mut self) -> bool {
+		if !self.document_mut().undo() {
+			return false;
+		}
+		if let Some(stats) = self.stats.as_mut() {
+			stats.record_undo();
+		}
+
+		true
+	}
+
+	/// Redo the latest undone edit of the active object. Returns false if there is none.
+	pub fn redo(&mut self) -> bool {
+		self.document_mut().redo()
+	}
+
+	/// Undo the latest paint edit, leaving shape edits alone. Returns false if there is none.
+	pub fn undo_paint(&mut self) -> bool {
+		self.document_mut().undo_paint()
+	}
+
+	/// Redo the latest undone paint edit. Returns false if there is none.
+	pub fn redo_paint(&mut self) -> bool {
+		self.document_mut().redo_paint()
+	}
+
+	/// Use the colors of a built-in palette for the materials.
+	pub fn set_palette_preset(&mut self, preset: PalettePreset) {
+		self.set_palette(&preset.colors());
+	}
+
+	/// Use the colors of a .gpl or .ase palette file for the materials, returning how many there are.
+	pub fn import_palette(&mut self, path: impl AsRef<Path>) -> Result<usize, Error> {
+		let colors = palette::load(path)?;
+		if colors.is_empty() {
+			return Err(Error::EmptyPalette);
+		}
+		self.set_palette(&colors);
+
+		Ok(colors.len())
+	}
+
+	/// Use colors for the materials of every object.
+	fn set_palette(&mut self, colors: &[[f32; 4]]) {
+		for index in 0..self.scene.get_objects().len() {
+			if let Some(object) = self.scene.get_object_mut(index) {
+				object.document.set_palette(colors);
+			}
+		}
+	}
+
+	/// Set how much light scatters under the surface of a material of every object, between 0 for none and 1.
+	///
+	/// Fails if the material isn't in the palette.
+	pub fn set_subsurface(&mut self, index: u32, strength: f32) -> Result<(), Error> {
+		let count = self.get_document().get_palette().len();
+		if index as usize >= count {
+			return Err(Error::MissingMaterial { index, count });
+		}
+		for object in 0..self.scene.get_objects().len() {
+			if let Some(object) = self.scene.get_object_mut(object) {
+				object.document.set_subsurface(index, strength);
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Set how the colors of the active object are shown, which the viewport follows while it is active.
+	pub fn set_color_management(&mut self, color: ColorManagement) {
+		self.document_mut().set_color_management(color);
+	}
+
+	/// Get how the colors of the active object are shown.
+	pub fn get_color_management(&self) -> ColorManagement {
+		self.get_document().get_color_management()
+	}
+
+	/// Limit how many nodes each object may hold, refining strokes which would go over it less deeply, or let objects grow without limit.
+	pub fn set_node_limit(&mut self, limit: Option<usize>) {
+		for index in 0..self.scene.get_objects().len() {
+			if let Some(object) = self.scene.get_object_mut(index) {
+				object.document.set_node_limit(limit);
+			}
+		}
+	}
+
+	/// Get how many nodes each object may hold, if it is limited.
+	pub fn get_node_limit(&self) -> Option<usize> {
+		self.get_document().get_node_limit()
+	}
+
+	/// Take the coarsest depth strokes were refined to since this was last asked, if the node limit made them coarser.
+	pub fn take_node_limit_warning(&mut self) -> Option<u32> {
+		(0..self.scene.get_objects().len())
+			.filter_map(|index| self.scene.get_object_mut(index)?.document.take_reduced_depth())
+			.min()
+	}
+
+	/// Get the buffer for the used materials, which every object shares.
+	pub fn get_material_buffer(&self) -> Vec<GpuMaterial> {
+		self.get_document().get_home_chunk().get_material_buffer()
+	}
+
+	/// Start a stroke lasting until it is ended, like while a button is held down.
+	///
+	/// Passes of the stroke going over the same place share its accumulation limit.
+	pub fn begin_stroke(&mut self) {
+		self.document_mut().open_stroke();
+		self.stroke_anchor = None;
+		self.stroke_path = Stroke::new();
+		self.stroke_counted = false;
+		self.drawn_guide = None;
+	}
+
+	/// End the current stroke.
+	pub fn end_stroke(&mut self) {
+		self.document_mut().close_stroke();
+		self.stroke_anchor = None;
+		self.stroke_path = Stroke::new();
+		self.stroke_counted = false;
+		self.drawn_guide = None;
+		self.count_timelapse_stroke();
+	}
+
+	/// Draw additively on the sculpt at a point of the screen.
+	///
+	/// While the stroke lock is held, the stroke is kept to it, and while
+	/// guides are drawn, the stroke draws one instead.
 	pub fn add(&mut self, x: f32, y: f32) {
-		self.brushes[self.current_brush].add(&mut self.sculpt, x, y);
+		if self.drawing_guides {
+			self.draw_guide(x, y);
+			return;
+		}
+		self.stroke_sample(false, x, y);
 	}
 
-	/// Draw subtractively on the sculpt.
+	/// Draw subtractively on the sculpt at a point of the screen.
+	///
+	/// While the stroke lock is held, the stroke is kept to it, and while
+	/// guides are drawn, the stroke erases one instead.
 	pub fn remove(&mut self, x: f32, y: f32) {
-		self.brushes[self.current_brush].remove(&mut self.sculpt, x, y);
+		if self.drawing_guides {
+			self.erase_guide(x, y);
+			return;
+		}
+		self.stroke_sample(true, x, y);
+	}
+
+	/// Stroke the current brush through a cursor sample at a point of the screen.
+	///
+	/// The open stroke is counted in the statistics once, at its first
+	/// sample, and macros record the samples rather than every stamp.
+	fn stroke_sample(&mut self, remove: bool, x: f32, y: f32) {
+		if self.scene.get_active().locked {
+			return;
+		}
+		let samples = self.locked_positions(x, y);
+		let open = self.get_document().is_stroke_open();
+		let joined = open && samples.len() == 1 && self.stroke_path.is_started();
+		for position in self.path_positions(&samples) {
+			self.stroke(remove, position);
+		}
+
+		if !open || !self.stroke_counted {
+			self.record_stroke();
+			self.stroke_counted = open;
+		}
+		if let Some(recording) = self.recording.as_mut().filter(|_| !self.playing) {
+			let brush = self.brushes.get_id(self.current_brush).unwrap_or_default();
+			let size = self.brushes[self.current_brush].get_size();
+			for position in samples {
+				recording.steps.push(MacroStep::Stroke { remove, brush: brush.to_owned(), position, size, joined });
+			}
+		}
+	}
+
+	/// Get where the brush stamps on the way to the positions of a cursor sample.
+	///
+	/// While a stroke is open, the gaps since its last cursor sample are
+	/// filled in, a fraction of the brush size apart. Strokes reaching every
+	/// surface under the cursor stamp each of them as they are.
+	fn path_positions(&mut self, positions: &[Vec3]) -> Vec<Vec3> {
+		let [position] = positions[..] else {
+			return positions.to_vec();
+		};
+		if !self.get_document().is_stroke_open() {
+			return positions.to_vec();
+		}
+
+		self.stroke_path.follow(self.snap_to_voxels(position), self.get_stroke_spacing())
+	}
+
+	/// Get how far apart the current brush stamps along a stroke, in the world.
+	fn get_stroke_spacing(&self) -> f32 {
+		self.brushes[self.current_brush].get_spacing(self.get_document()) * self.get_object_transform().scale
+	}
+
+	/// Let strokes affect every surface along the view ray (e.g. both sides of a thin shell).
+	pub fn set_sculpt_through(&mut self, sculpt_through: bool) {
+		self.sculpt_through = sculpt_through;
+	}
+
+	/// Check whether strokes affect every surface along the view ray.
+	pub fn get_sculpt_through(&self) -> bool {
+		self.sculpt_through
+	}
+
+	/// Keep strokes off the surface facing away from the camera by more than an angle, in radians.
+	///
+	/// This protects the far side of thin features. `None` lets strokes affect any surface.
+	pub fn set_backface_masking(&mut self, max_angle: Option<f32>) {
+		self.backface_limit = max_angle;
+	}
+
+	/// Get the angle from the camera beyond which strokes leave the surface alone, if any.
+	pub fn get_backface_masking(&self) -> Option<f32> {
+		self.backface_limit
+	}
+
+	/// Get where a stroke at a point of the screen stamps the brush.
+	fn stroke_positions(&self, x: f32, y: f32) -> Vec<Vec3> {
+		if self.sculpt_through {
+			let (origin, direction) = self.screen_ray(x, y);
+			let transform = self.get_object_transform();
+			let surfaces: Vec<Vec3> = self.get_document().raycast_all(transform.to_local(origin), transform.to_local_vector(direction)).iter()
+				.map(|hit| transform.to_world(hit.position))
+				.collect();
+			if !surfaces.is_empty() {
+				return surfaces;
+			}
+		}
+
+		vec![self.brush_position(x, y)]
+	}
+
+	/// Get the view ray through a point of the screen.
+	fn screen_ray(&self, x: f32, y: f32) -> (Vec3, Vec3) {
+		if !self.free_view {
+			return (self.get_document().get_home_chunk().unit_to_world(vec3(x, y, 0.0)), vec3(0.0, 0.0, 1.0));
+		}
+
+		self.camera.ray(x, y, self.aspect)
+	}
+
+	/// Stroke the current brush at a position in the world.
+	///
+	/// The stroke lands on the active object, in its own space, snapped to its voxel grid if stamps snap, unless it is locked.
+	fn stroke(&mut self, remove: bool, world_position: Vec3) {
+		if self.scene.get_active().locked {
+			return;
+		}
+		let world_position = self.snap_to_voxels(world_position);
+		let toward = if self.free_view { self.camera.eye() - world_position } else { Vec3::NEG_Z };
+		let object = self.scene.get_active_mut();
+		let position = object.transform.to_local(world_position);
+		let toward = object.transform.to_local_vector(toward);
+		let document = &mut object.document;
+		let brush = &mut self.brushes[self.current_brush];
+		let reach = brush.get_reach(document, position);
+		if self.painting {
+			brush.paint(document, position, (!remove).then_some(self.paint_color));
+		} else {
+			let mut apply = |document: &mut Document| if remove {
+				brush.remove(document, position);
+			} else {
+				brush.add(document, position);
+			};
+			match self.backface_limit {
+				Some(max_angle) => document.protect_backfaces(reach, toward, max_angle, apply),
+				None => apply(document),
+			}
+		}
+	}
+
+	/// Get the volume the current brush would stamp at a screen position.
+	pub fn get_stamp(&self, x: f32, y: f32) -> Stamp {
+		let transform = self.get_object_transform();
+		let position = self.snap_to_voxels(match self.stroke_anchor.filter(|_| self.stroke_locked) {
+			Some(anchor) => self.lock_position(anchor, x, y),
+			None => self.brush_position(x, y),
+		});
+		let stamp = self.brushes[self.current_brush].stamp(self.get_document(), position);
+
+		Stamp {
+			size: stamp.size * transform.scale,
+			..stamp
+		}
+	}
+
+	/// Map a screen position onto the sculpt.
+	///
+	/// The brush is placed level with the surface last picked under the
+	/// cursor, if any. Without one, the front view maps the screen onto the
+	/// middle of the home chunk, while a free camera places the brush level
+	/// with its target.
+	fn brush_position(&self, x: f32, y: f32) -> Vec3 {
+		let (origin, direction) = self.screen_ray(x, y);
+		let forward = if self.free_view { self.camera.forward() } else { direction };
+		let level = match self.picked_surface {
+			Some(surface) => surface,
+			None if self.free_view => self.camera.target,
+			None => return self.get_document().get_home_chunk().unit_to_world(vec3(x, y, 0.5)),
+		};
+
+		origin + direction * (level - origin).dot(forward) / direction.dot(forward)
+	}
+
+	/// Set the point of the surface seen under the cursor, as picked from the last frame, or None if it missed.
+	///
+	/// Strokes and the brush preview are placed level with it, so they land on
+	/// the visible surface rather than in the middle of the sculpt.
+	pub fn set_picked_surface(&mut self, surface: Option<Vec3>) {
+		self.picked_surface = surface;
+	}
+
+	/// Get the point of the surface last picked under the cursor, if any.
+	pub fn get_picked_surface(&self) -> Option<Vec3> {
+		self.picked_surface
+	}
+
+	/// Get the current camera.
+	pub fn get_camera(&self) -> Camera {
+		self.camera
+	}
+
+	/// Move the camera, cancelling any view transition.
+	pub fn set_camera(&mut self, camera: Camera) {
+		self.camera = camera;
+		self.free_view = true;
+		self.view_transition = None;
+	}
+
+	/// Set the width over the height of the viewport.
+	pub fn set_aspect(&mut self, aspect: f32) {
+		self.aspect = aspect;
+	}
+
+	/// Get the view projection matrix to draw with.
+	pub fn get_view_projection(&self) -> Mat4 {
+		let bounds = self.get_document().get_home_chunk().get_bounds();
+		if !self.free_view {
+			return camera::front_view(bounds);
+		}
+
+		self.camera.view_projection(self.aspect)
+	}
+
+	/// Turn the camera around its target by a movement across the screen.
+	pub fn orbit(&mut self, delta: Vec2) {
+		let mut camera = self.navigation_camera();
+		self.controller.orbit(&mut camera, delta);
+		self.set_camera(camera);
+	}
+
+	/// Turn the camera around the vertical axis by an angle.
+	pub fn turn(&mut self, angle: f32) {
+		let mut camera = self.navigation_camera();
+		self.controller.turn(&mut camera, angle);
+		self.set_camera(camera);
+	}
+
+	/// Slide the camera by a movement across the screen.
+	pub fn pan(&mut self, delta: Vec2) {
+		let mut camera = self.navigation_camera();
+		self.controller.pan(&mut camera, delta);
+		self.set_camera(camera);
+	}
+
+	/// Move the camera towards (positive amounts) or away from its target.
+	pub fn zoom(&mut self, amount: f32) {
+		let mut camera = self.navigation_camera();
+		self.controller.zoom(&mut camera, amount);
+		self.set_camera(camera);
+	}
+
+	/// Get the camera to navigate from, starting from the front view if needed.
+	fn navigation_camera(&self) -> Camera {
+		if self.free_view {
+			return self.camera;
+		}
+
+		Camera::front(self.get_document().get_home_chunk().get_bounds())
+	}
+
+	/// Bookmark the current camera in a slot.
+	pub fn store_view(&mut self, slot: usize) {
+		self.views.insert(slot, self.navigation_camera());
+	}
+
+	/// Check whether a view is bookmarked in a slot.
+	pub fn has_view(&self, slot: usize) -> bool {
+		self.views.contains_key(&slot)
+	}
+
+	/// Smoothly move the camera to a bookmarked view.
+	///
+	/// Returns false if nothing is stored in the slot.
+	pub fn recall_view(&mut self, slot: usize) -> bool {
+		let Some(view) = self.views.get(&slot) else {
+			return false;
+		};
+
+		self.view_transition = Some(CameraTransition::new(self.navigation_camera(), *view, self.view_duration));
+		self.free_view = true;
+
+		true
+	}
+
+	/// Set how long moving to a bookmarked view takes.
+	pub fn set_view_duration(&mut self, duration: Duration) {
+		self.view_duration = duration;
+	}
+
+	/// Advance time-based editor state, returning whether the view changed.
+	pub fn animate(&mut self, delta: Duration) -> bool {
+		if let Some(stats) = self.stats.as_mut() {
+			stats.record_time(&self.brushes[self.current_brush].name, delta);
+		}
+
+		let Some(transition) = self.view_transition.as_mut() else {
+			return false;
+		};
+
+		self.camera = transition.advance(delta);
+		if transition.is_finished() {
+			self.view_transition = None;
+		}
+
+		true
+	}
+
+	/// Interpolate a camera path through bookmarked views.
+	///
+	/// Empty slots are skipped.
+	pub fn export_camera_path(&self, slots: &[usize], frames_per_segment: u32) -> Vec<Camera> {
+		let keys: Vec<Camera> = slots.iter()
+			.filter_map(|slot| self.views.get(slot).copied())
+			.collect();
+
+		camera::camera_path(&keys, frames_per_segment)
+	}
+
+	/// Opt in or out of collecting session statistics.
+	///
+	/// Opting out discards anything collected so far.
+	pub fn set_stats_enabled(&mut self, enabled: bool) {
+		if enabled != self.stats.is_some() {
+			self.stats = enabled.then(SessionStats::default);
+		}
+	}
+
+	/// Get the statistics of this session, if the user opted in.
+	pub fn session_stats(&self) -> Option<&SessionStats> {
+		self.stats.as_ref()
+	}
+
+	/// Count a stroke of the current brush.
+	fn record_stroke(&mut self) {
+		let brush = &self.brushes[self.current_brush].name;
+		self.scene.get_active_mut().document.count_brush_stroke(brush);
+		if let Some(stats) = self.stats.as_mut() {
+			stats.record_stroke(brush);
+		}
 	}
 }
@@ -0,0 +1,2250 @@
+//! Typed commands, so every editor feature can be reached without dedicated UI.
+//!
+//! A command is one or more words naming it, followed by its arguments
+//! (e.g. `brush size 0.05`). Arguments with spaces can be wrapped in
+//! double quotes.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::color::{Tonemap, WorkingSpace};
+use crate::document::{BooleanOp, DEFAULT_LEVEL};
+use crate::document::watertight::SurfaceIssue;
+use crate::i18n::{tr, tr_args};
+use crate::light::{Light, LightKind, MAX_LIGHTS};
+use crate::material::MaterialMix;
+use crate::palette::PalettePreset;
+use crate::scene::{ExportFilter, Transform};
+use crate::sculpt::{Wear, WearEffect};
+
+use super::Editor;
+use super::constraint::StrokeLock;
+use super::guides::ArrayStamp;
+use super::stencil::StencilShape;
+
+use glam::{vec2, vec3, EulerRot, Quat, Vec3};
+
+/// Runs a command with its arguments, returning a message for the user.
+pub type CommandFn = fn(&mut Editor, &[&str]) -> Result<String, CommandError>;
+
+/// Why a command could not be run.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CommandError {
+	/// Nothing was typed.
+	Empty,
+	/// No command has the given name.
+	Unknown(String),
+	/// The arguments didn't match the usage of the command.
+	Usage(&'static str),
+	/// The command ran, but couldn't do what was asked.
+	Failed(String),
+}
+
+impl fmt::Display for CommandError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let message = match self {
+			CommandError::Empty => tr("command-empty"),
+			CommandError::Unknown(name) => tr_args("command-unknown", &[("name", name)]),
+			CommandError::Usage(usage) => tr_args("command-usage", &[("usage", usage)]),
+			CommandError::Failed(reason) => reason.clone(),
+		};
+
+		f.write_str(&message)
+	}
+}
+
+impl std::error::Error for CommandError {}
+
+/// A named command.
+#[derive(Clone, Copy)]
+pub struct Command {
+	pub name: &'static str,
+	/// The name followed by the arguments, e.g. `brush size <fraction>`.
+	pub usage: &'static str,
+	description: &'static str,
+	run: CommandFn,
+	/// Whether the command is left out of help and suggestions, like tools for testing.
+	hidden: bool,
+}
+
+impl Command {
+	/// Get a short, translated description of what the command does.
+	pub fn describe(&self) -> String {
+		tr(self.description)
+	}
+
+	/// Run the command.
+	pub fn run(&self, editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+		(self.run)(editor, args)
+	}
+}
+
+/// The commands which can be typed, by name.
+pub struct CommandRegistry {
+	commands: Vec<Command>,
+}
+
+impl Default for CommandRegistry {
+	/// The built-in commands.
+	fn default() -> Self {
+		let mut registry = Self::new();
+		registry.register("help", "help", "command-help", help);
+		registry.register("save", "save <path> [level]", "command-save", save);
+		registry.register("save version", "save version", "command-save-version", save_version);
+		registry.register("versions", "versions", "command-versions", versions);
+		registry.register("open", "open <path>", "command-open", open);
+		registry.register("reload", "reload", "command-reload", reload);
+		registry.register("brush", "brush <round|square|spray|pen>", "command-brush", brush);
+		registry.register("brush list", "brush list", "command-brush-list", brush_list);
+		registry.register("brush slot", "brush slot <number> <brush>", "command-brush-slot", brush_slot);
+		registry.register("brush last", "brush last", "command-brush-last", brush_last);
+		registry.register("brush size", "brush size <fraction>", "command-brush-size", brush_size);
+		registry.register("brush voxels", "brush voxels <count>", "command-brush-voxels", brush_voxels);
+		registry.register("brush strength", "brush strength <fraction>", "command-brush-strength", brush_strength);
+		registry.register("brush hardness", "brush hardness <fraction>", "command-brush-hardness", brush_hardness);
+		registry.register("brush accumulation", "brush accumulation <fraction|off>", "command-brush-accumulation", brush_accumulation);
+		registry.register("brush through", "brush through <on|off>", "command-brush-through", brush_through);
+		registry.register("brush backfaces", "brush backfaces <degrees|off>", "command-brush-backfaces", brush_backfaces);
+		registry.register("stroke lock", "stroke lock <x|y|z|normal|plane|guide>", "command-stroke-lock", stroke_lock);
+		registry.register("stroke plane", "stroke plane <x> <y>|off", "command-stroke-plane", stroke_plane);
+		registry.register("brush relax", "brush relax <iterations>", "command-brush-relax", brush_relax);
+		registry.register("brush material", "brush material <index> [<second> <fraction>]", "command-brush-material", brush_material);
+		registry.register("add", "add <x> <y>", "command-add", add);
+		registry.register("remove", "remove <x> <y>", "command-remove", remove);
+		registry.register("view store", "view store <slot>", "command-view-store", view_store);
+		registry.register("view recall", "view recall <slot>", "command-view-recall", view_recall);
+		registry.register("view duration", "view duration <seconds>", "command-view-duration", view_duration);
+		registry.register("pin add", "pin add <x> <y> <note>...", "command-pin-add", pin_add);
+		registry.register("pin remove", "pin remove <number>", "command-pin-remove", pin_remove);
+		registry.register("pin list", "pin list", "command-pin-list", pin_list);
+		registry.register("pin go", "pin go <number>", "command-pin-go", pin_go);
+		registry.register("guide add", "guide add <x1> <y1> <x2> <y2> [<x> <y>...]", "command-guide-add", guide_add);
+		registry.register("guide remove", "guide remove <number>", "command-guide-remove", guide_remove);
+		registry.register("guide draw", "guide draw <on|off>", "command-guide-draw", guide_draw);
+		registry.register("guide array", "guide array <number> <count> [remove|macro <name> [align]]", "command-guide-array", guide_array);
+		registry.register("stencil circle", "stencil circle <add|remove> <depth> <x> <y> <radius>", "command-stencil-circle", stencil_circle);
+		registry.register("stencil polygon", "stencil polygon <add|remove> <depth> <x1> <y1> <x2> <y2> <x3> <y3> [<x> <y>...]", "command-stencil-polygon", stencil_polygon);
+		registry.register("stencil svg", "stencil svg <add|remove> <depth> <path> <x> <y> <height>", "command-stencil-svg", stencil_svg);
+		registry.register("review export", "review export <directory>", "command-review-export", review_export);
+		registry.register("report", "report <path>", "command-report", report);
+		registry.register("export stl", "export stl <path> [scene|active|<name>...]", "command-export-stl", export_stl);
+		registry.register("export obj", "export obj <path> [scene|active|<name>...]", "command-export-obj", export_obj);
+		registry.register("export gltf", "export gltf <path> [scene|active|<name>...]", "command-export-gltf", export_gltf);
+		registry.register("export vox", "export vox <path> [scene|active|<name>...]", "command-export-vox", export_vox);
+		registry.register("orbit", "orbit <x> <y>", "command-orbit", orbit);
+		registry.register("pan", "pan <x> <y>", "command-pan", pan);
+		registry.register("zoom", "zoom <amount>", "command-zoom", zoom);
+		registry.register("turn", "turn <degrees>", "command-turn", turn);
+		registry.register("mask cavity", "mask cavity [invert]", "command-mask-cavity", mask_cavity);
+		registry.register("mask facing", "mask facing <degrees>", "command-mask-facing", mask_facing);
+		registry.register("mask visible", "mask visible", "command-mask-visible", mask_visible);
+		registry.register("mask clear", "mask clear", "command-mask-clear", mask_clear);
+		registry.register("paint", "paint <on|off>", "command-paint", paint);
+		registry.register("paint color", "paint color <red> <green> <blue> [alpha]", "command-paint-color", paint_color);
+		registry.register("paint clear", "paint clear", "command-paint-clear", paint_clear);
+		registry.register("undo", "undo", "command-undo", undo);
+		registry.register("redo", "redo", "command-redo", redo);
+		registry.register("paint undo", "paint undo", "command-paint-undo", paint_undo);
+		registry.register("paint redo", "paint redo", "command-paint-redo", paint_redo);
+		registry.register("object add", "object add <name> [resolution]", "command-object-add", object_add);
+		registry.register("object duplicate", "object duplicate", "command-object-duplicate", object_duplicate);
+		registry.register("object delete", "object delete [name]", "command-object-delete", object_delete);
+		registry.register("object select", "object select <name>", "command-object-select", object_select);
+		registry.register("object list", "object list", "command-object-list", object_list);
+		registry.register("object show", "object show [name]", "command-object-show", object_show);
+		registry.register("object hide", "object hide [name]", "command-object-hide", object_hide);
+		registry.register("object lock", "object lock [name]", "command-object-lock", object_lock);
+		registry.register("object unlock", "object unlock [name]", "command-object-unlock", object_unlock);
+		registry.register("object solo", "object solo <on|off>", "command-object-solo", object_solo);
+		registry.register("object move", "object move <x> <y> <z>", "command-object-move", object_move);
+		registry.register("object rotate", "object rotate <x> <y> <z>", "command-object-rotate", object_rotate);
+		registry.register("object scale", "object scale <factor>", "command-object-scale", object_scale);
+		registry.register("object boolean", "object boolean <name> <union|subtract|intersect>", "command-object-boolean", object_boolean);
+		registry.register("snap grid", "snap grid <size|off>", "command-snap-grid", snap_grid);
+		registry.register("snap angle", "snap angle <degrees|off>", "command-snap-angle", snap_angle);
+		registry.register("snap surface", "snap surface <on|off>", "command-snap-surface", snap_surface);
+		registry.register("snap voxels", "snap voxels <depth|off>", "command-snap-voxels", snap_voxels);
+		registry.register("instance add", "instance add <x> <y> <z>", "command-instance-add", instance_add);
+		registry.register("instance scatter", "instance scatter <count> <radius> [seed]", "command-instance-scatter", instance_scatter);
+		registry.register("instance clear", "instance clear", "command-instance-clear", instance_clear);
+		registry.register("gradient", "gradient <x|y|z> <material>...", "command-gradient", gradient);
+		registry.register("gradient between", "gradient between <x1> <y1> <x2> <y2> <material>...", "command-gradient-between", gradient_between);
+		registry.register("wear", "wear <intensity> <scale> <material|erode>", "command-wear", wear);
+		registry.register("quantize", "quantize <depth>", "command-quantize", quantize);
+		registry.register("node limit", "node limit <count|off>", "command-node-limit", node_limit);
+		registry.register("validate", "validate", "command-validate", validate);
+		registry.register("repair", "repair", "command-repair", repair);
+		registry.register("watertight", "watertight", "command-watertight", watertight);
+		registry.register("colors", "colors <preset>", "command-colors", colors);
+		registry.register("colors import", "colors import <path>", "command-colors-import", colors_import);
+		registry.register("colors list", "colors list", "command-colors-list", colors_list);
+		registry.register("light directional", "light directional <x> <y> <z> <intensity> [<red> <green> <blue>]", "command-light-directional", light_directional);
+		registry.register("light point", "light point <x> <y> <z> <intensity> [<red> <green> <blue>]", "command-light-point", light_point);
+		registry.register("light list", "light list", "command-light-list", light_list);
+		registry.register("light clear", "light clear", "command-light-clear", light_clear);
+		registry.register("material subsurface", "material subsurface <index> <fraction>", "command-material-subsurface", material_subsurface);
+		registry.register("color", "color", "command-color", color);
+		registry.register("color space", "color space <srgb|acescg|rec2020>", "command-color-space", color_space);
+		registry.register("color tonemap", "color tonemap <none|aces|filmic>", "command-color-tonemap", color_tonemap);
+		registry.register("color exposure", "color exposure <stops>", "command-color-exposure", color_exposure);
+		registry.register("stats", "stats <on|off>", "command-stats", stats);
+		registry.register("macro record", "macro record <name>", "command-macro-record", macro_record);
+		registry.register("macro stop", "macro stop", "command-macro-stop", macro_stop);
+		registry.register("macro play", "macro play <name> [<x> <y> [<scale>]]", "command-macro-play", macro_play);
+		registry.register("macro list", "macro list", "command-macro-list", macro_list);
+		registry.register("onion", "onion <on|off>", "command-onion", onion);
+		registry.register("timelapse", "timelapse <directory> <strokes> [view]", "command-timelapse", timelapse);
+		registry.register("timelapse stop", "timelapse stop", "command-timelapse-stop", timelapse_stop);
+		registry.register_hidden("soak", "soak <strokes> <path> [seed]", "command-soak", soak);
+
+		registry
+	}
+}
+
+impl CommandRegistry {
+	/// Create a registry without any commands.
+	pub fn new() -> Self {
+		Self {
+			commands: Vec::new(),
+		}
+	}
+
+	/// Add a command, replacing any previous command with the same name.
+	pub fn register(&mut self, name: &'static str, usage: &'static str, description: &'static str, run: CommandFn) {
+		self.commands.retain(|command| command.name != name);
+		self.commands.push(Command {
+			name,
+			usage,
+			description,
+			run,
+			hidden: false,
+		});
+	}
+
+	/// Add a command which runs when typed, but isn't listed by help or suggested.
+	pub fn register_hidden(&mut self, name: &'static str, usage: &'static str, description: &'static str, run: CommandFn) {
+		self.register(name, usage, description, run);
+		if let Some(command) = self.commands.last_mut() {
+			command.hidden = true;
+		}
+	}
+
+	/// Get every command in order.
+	pub fn get_commands(&self) -> &[Command] {
+		&self.commands
+	}
+
+	/// Find the command a line names, along with its arguments.
+	///
+	/// The command with the most matching words wins, so
+	/// `brush size 0.05` runs `brush size` rather than `brush`.
+	pub fn parse(&self, line: &str) -> Result<(Command, Vec<String>), CommandError> {
+		let words = tokenize(line);
+		if words.is_empty() {
+			return Err(CommandError::Empty);
+		}
+
+		let command = self.commands.iter()
+			.filter(|command| {
+				let name: Vec<&str> = command.name.split(' ').collect();
+				words.len() >= name.len() && words.iter().zip(&name).all(|(word, part)| word == part)
+			})
+			.max_by_key(|command| command.name.split(' ').count())
+			.ok_or_else(|| CommandError::Unknown(words[0].clone()))?;
+		let args = words[command.name.split(' ').count()..].to_vec();
+
+		Ok((*command, args))
+	}
+
+	/// Get the commands a partly typed line could become.
+	///
+	/// Once the name is complete, this is the command which would run.
+	pub fn suggest(&self, line: &str) -> Vec<&Command> {
+		let typed = tokenize(line).join(" ");
+		let completions: Vec<&Command> = self.commands.iter()
+			.filter(|command| !command.hidden && command.name.starts_with(&typed))
+			.collect();
+		if !completions.is_empty() {
+			return completions;
+		}
+
+		match self.parse(line) {
+			Ok((command, _)) => self.commands.iter().filter(|known| known.name == command.name).collect(),
+			Err(_) => Vec::new(),
+		}
+	}
+}
+
+/// Split a line into words, keeping double quoted words together.
+fn tokenize(line: &str) -> Vec<String> {
+	let mut words = Vec::new();
+	let mut word = String::new();
+	let mut quoted = false;
+	let mut started = false;
+
+	for character in line.chars() {
+		match character {
+			'"' => {
+				quoted = !quoted;
+				started = true;
+			},
+			character if character.is_whitespace() && !quoted => {
+				if started {
+					words.push(std::mem::take(&mut word));
+					started = false;
+				}
+			},
+			character => {
+				word.push(character);
+				started = true;
+			},
+		}
+	}
+	if started {
+		words.push(word);
+	}
+
+	words
+}
+
+/// Parse an argument, failing with the usage of the command.
+fn arg<T: FromStr>(args: &[&str], index: usize, usage: &'static str) -> Result<T, CommandError> {
+	args.get(index)
+		.and_then(|arg| arg.parse().ok())
+		.ok_or(CommandError::Usage(usage))
+}
+
+/// Check that no arguments are left over.
+fn expect_count(args: &[&str], count: usize, usage: &'static str) -> Result<(), CommandError> {
+	if args.len() != count {
+		return Err(CommandError::Usage(usage));
+	}
+
+	Ok(())
+}
+
+/// Check that materials are in the palette.
+fn check_materials(editor: &Editor, materials: &[u32]) -> Result<(), CommandError> {
+	let count = editor.get_document().get_palette().len();
+	if materials.iter().any(|material| *material as usize >= count) {
+		return Err(CommandError::Failed(tr_args("command-material-range", &[("count", &count.to_string())])));
+	}
+
+	Ok(())
+}
+
+fn help(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 0, "help")?;
+
+	let usages: Vec<&str> = editor.get_commands().get_commands().iter()
+		.filter(|command| !command.hidden)
+		.map(|command| command.usage)
+		.collect();
+
+	Ok(usages.join(", "))
+}
+
+fn brush(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "brush <round|square|spray|pen>";
+	expect_count(args, 1, USAGE)?;
+
+	// brushes of your own are picked by their id or name as well
+	if !editor.set_brush_by_name(args[0]) {
+		return Err(CommandError::Usage(USAGE));
+	}
+
+	Ok(String::new())
+}
+
+fn brush_slot(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "brush slot <number> <brush>";
+	expect_count(args, 2, USAGE)?;
+
+	let slot: usize = arg(args, 0, USAGE)?;
+	if !editor.set_brush_slot(slot, args[1]) {
+		return Err(CommandError::Usage(USAGE));
+	}
+
+	Ok(String::new())
+}
+
+fn brush_last(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 0, "brush last")?;
+
+	editor.toggle_last_brush();
+
+	Ok(String::new())
+}
+
+fn brush_list(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 0, "brush list")?;
+
+	let brushes: Vec<String> = editor.get_brushes().iter()
+		.map(|(id, brush)| tr_args("command-brush-entry", &[("id", id), ("name", &brush.name)]))
+		.collect();
+
+	Ok(brushes.join(", "))
+}
+
+fn brush_through(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "brush through <on|off>";
+	expect_count(args, 1, USAGE)?;
+
+	match args[0] {
+		"on" => editor.set_sculpt_through(true),
+		"off" => editor.set_sculpt_through(false),
+		_ => return Err(CommandError::Usage(USAGE)),
+	}
+
+	Ok(String::new())
+}
+
+fn brush_backfaces(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "brush backfaces <degrees|off>";
+	expect_count(args, 1, USAGE)?;
+
+	if args[0] == "off" {
+		editor.set_backface_masking(None);
+		return Ok(String::new());
+	}
+	let degrees: f32 = arg(args, 0, USAGE)?;
+	if !(0.0..=180.0).contains(&degrees) {
+		return Err(CommandError::Failed(tr("command-brush-backfaces-range")));
+	}
+	editor.set_backface_masking(Some(degrees.to_radians()));
+
+	Ok(String::new())
+}
+
+fn stroke_lock(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "stroke lock <x|y|z|normal|plane|guide>";
+	expect_count(args, 1, USAGE)?;
+
+	let lock = StrokeLock::from_name(args[0]).ok_or(CommandError::Usage(USAGE))?;
+	editor.set_stroke_lock(lock);
+
+	Ok(String::new())
+}
+
+fn stroke_plane(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "stroke plane <x> <y>|off";
+	if args == ["off"] {
+		editor.set_stroke_plane(None);
+		return Ok(String::new());
+	}
+	expect_count(args, 2, USAGE)?;
+
+	let plane = editor.place_stroke_plane(arg(args, 0, USAGE)?, arg(args, 1, USAGE)?);
+	let point = format!("{:.3} {:.3} {:.3}", plane.point.x, plane.point.y, plane.point.z);
+	let normal = format!("{:.3} {:.3} {:.3}", plane.normal.x, plane.normal.y, plane.normal.z);
+
+	Ok(tr_args("command-stroke-plane-placed", &[("point", &point), ("normal", &normal)]))
+}
+
+fn brush_size(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "brush size <fraction>";
+	expect_count(args, 1, USAGE)?;
+
+	let size: f32 = arg(args, 0, USAGE)?;
+	if !(size > 0.0 && size <= 1.0) {
+		return Err(CommandError::Failed(tr("command-brush-size-range")));
+	}
+	editor.set_brush_size(size);
+
+	Ok(String::new())
+}
+
+fn brush_voxels(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "brush voxels <count>";
+	expect_count(args, 1, USAGE)?;
+
+	let count: u32 = arg(args, 0, USAGE)?;
+	if count == 0 || count > editor.get_document().get_resolution() {
+		return Err(CommandError::Failed(tr("command-brush-voxels-range")));
+	}
+	editor.set_brush_voxels(count);
+
+	Ok(String::new())
+}
+
+fn brush_strength(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "brush strength <fraction>";
+	expect_count(args, 1, USAGE)?;
+
+	let strength: f32 = arg(args, 0, USAGE)?;
+	if !(0.0..=1.0).contains(&strength) {
+		return Err(CommandError::Failed(tr("command-brush-strength-range")));
+	}
+	editor.set_brush_strength(strength);
+
+	Ok(String::new())
+}
+
+fn brush_hardness(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "brush hardness <fraction>";
+	expect_count(args, 1, USAGE)?;
+
+	let hardness: f32 = arg(args, 0, USAGE)?;
+	if !(0.0..=1.0).contains(&hardness) {
+		return Err(CommandError::Failed(tr("command-brush-hardness-range")));
+	}
+	editor.set_brush_hardness(hardness);
+
+	Ok(String::new())
+}
+
+fn brush_accumulation(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "brush accumulation <fraction|off>";
+	expect_count(args, 1, USAGE)?;
+
+	if args[0] == "off" {
+		editor.set_brush_accumulation(None);
+		return Ok(String::new());
+	}
+	let limit: f32 = arg(args, 0, USAGE)?;
+	if !(limit > 0.0 && limit <= 1.0) {
+		return Err(CommandError::Failed(tr("command-brush-accumulation-range")));
+	}
+	editor.set_brush_accumulation(Some(limit));
+
+	Ok(String::new())
+}
+
+fn brush_relax(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "brush relax <iterations>";
+	expect_count(args, 1, USAGE)?;
+
+	editor.set_brush_smoothing(arg(args, 0, USAGE)?);
+
+	Ok(String::new())
+}
+
+fn brush_material(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "brush material <index> [<second> <fraction>]";
+	if args.len() != 1 && args.len() != 3 {
+		return Err(CommandError::Usage(USAGE));
+	}
+
+	let first: u32 = arg(args, 0, USAGE)?;
+	let material = if args.len() == 3 {
+		let mix: f32 = arg(args, 2, USAGE)?;
+		if !(0.0..=1.0).contains(&mix) {
+			return Err(CommandError::Failed(tr("command-brush-material-mix-range")));
+		}
+		MaterialMix { first, second: arg(args, 1, USAGE)?, mix }
+	} else {
+		MaterialMix::single(first)
+	};
+
+	editor.set_brush_material(material)
+		.map_err(|error| CommandError::Failed(error.to_string()))?;
+
+	Ok(String::new())
+}
+
+fn add(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "add <x> <y>";
+	expect_count(args, 2, USAGE)?;
+
+	editor.add(arg(args, 0, USAGE)?, arg(args, 1, USAGE)?);
+
+	Ok(String::new())
+}
+
+fn remove(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "remove <x> <y>";
+	expect_count(args, 2, USAGE)?;
+
+	editor.remove(arg(args, 0, USAGE)?, arg(args, 1, USAGE)?);
+
+	Ok(String::new())
+}
+
+fn view_store(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "view store <slot>";
+	expect_count(args, 1, USAGE)?;
+
+	editor.store_view(arg(args, 0, USAGE)?);
+
+	Ok(String::new())
+}
+
+fn view_recall(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "view recall <slot>";
+	expect_count(args, 1, USAGE)?;
+
+	let slot: usize = arg(args, 0, USAGE)?;
+	if !editor.recall_view(slot) {
+		return Err(CommandError::Failed(tr_args("command-view-empty", &[("slot", &slot.to_string())])));
+	}
+
+	Ok(String::new())
+}
+
+fn pin_add(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "pin add <x> <y> <note>...";
+	if args.len() < 3 {
+		return Err(CommandError::Usage(USAGE));
+	}
+
+	let index = editor.add_pin(arg(args, 0, USAGE)?, arg(args, 1, USAGE)?, &args[2..].join(" "));
+
+	Ok(tr_args("command-pin-added", &[("number", &(index + 1).to_string())]))
+}
+
+/// Get the index of a pin from its number, as listed from 1.
+fn pin_index(editor: &Editor, args: &[&str], usage: &'static str) -> Result<usize, CommandError> {
+	expect_count(args, 1, usage)?;
+	let number: usize = arg(args, 0, usage)?;
+	if number == 0 || number > editor.get_pins().len() {
+		return Err(CommandError::Failed(tr_args("command-pin-missing", &[("number", &number.to_string())])));
+	}
+
+	Ok(number - 1)
+}
+
+fn pin_remove(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	let index = pin_index(editor, args, "pin remove <number>")?;
+
+	editor.remove_pin(index);
+
+	Ok(String::new())
+}
+
+fn pin_list(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 0, "pin list")?;
+
+	let pins = editor.get_pins();
+	if pins.is_empty() {
+		return Ok(tr("command-pin-none"));
+	}
+	let lines: Vec<String> = pins.iter()
+		.enumerate()
+		.map(|(index, pin)| format!("{}: {}", index + 1, pin.note))
+		.collect();
+
+	Ok(lines.join("\n"))
+}
+
+fn pin_go(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	let index = pin_index(editor, args, "pin go <number>")?;
+
+	editor.go_to_pin(index);
+
+	Ok(String::new())
+}
+
+fn guide_add(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "guide add <x1> <y1> <x2> <y2> [<x> <y>...]";
+	if args.len() < 4 || !args.len().is_multiple_of(2) {
+		return Err(CommandError::Usage(USAGE));
+	}
+
+	let points = (0..args.len() / 2)
+		.map(|index| Ok(vec2(arg(args, 2 * index, USAGE)?, arg(args, 2 * index + 1, USAGE)?)))
+		.collect::<Result<Vec<_>, CommandError>>()?;
+	let index = editor.add_guide(&points).ok_or_else(|| CommandError::Failed(tr("command-guide-off-surface")))?;
+
+	Ok(tr_args("command-guide-added", &[("number", &(index + 1).to_string())]))
+}
+
+fn guide_remove(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "guide remove <number>";
+	expect_count(args, 1, USAGE)?;
+
+	let number: usize = arg(args, 0, USAGE)?;
+	if number == 0 || editor.remove_guide(number - 1).is_none() {
+		return Err(CommandError::Failed(tr_args("command-guide-missing", &[("number", &number.to_string())])));
+	}
+
+	Ok(String::new())
+}
+
+fn guide_draw(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "guide draw <on|off>";
+	expect_count(args, 1, USAGE)?;
+
+	match args[0] {
+		"on" => editor.set_drawing_guides(true),
+		"off" => editor.set_drawing_guides(false),
+		_ => return Err(CommandError::Usage(USAGE)),
+	}
+
+	Ok(String::new())
+}
+
+fn guide_array(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "guide array <number> <count> [remove|macro <name> [align]]";
+	let number: usize = arg(args, 0, USAGE)?;
+	let count: usize = arg(args, 1, USAGE)?;
+
+	let stamp = match &args[2..] {
+		[] => ArrayStamp::Brush { remove: false },
+		["remove"] => ArrayStamp::Brush { remove: true },
+		["macro", name] => ArrayStamp::Macro { name: (*name).to_owned(), aligned: false },
+		["macro", name, "align"] => ArrayStamp::Macro { name: (*name).to_owned(), aligned: true },
+		_ => return Err(CommandError::Usage(USAGE)),
+	};
+	if number == 0 || count == 0 {
+		return Err(CommandError::Usage(USAGE));
+	}
+	editor.array_along_guide(number - 1, count, &stamp)?;
+
+	Ok(String::new())
+}
+
+/// Read whether a stencil removes voxels, and its depth in voxels.
+fn stencil_mode(args: &[&str], usage: &'static str) -> Result<(bool, u32), CommandError> {
+	let remove = match args.first() {
+		Some(&"add") => false,
+		Some(&"remove") => true,
+		_ => return Err(CommandError::Usage(usage)),
+	};
+	let depth: u32 = arg(args, 1, usage)?;
+	if depth == 0 {
+		return Err(CommandError::Usage(usage));
+	}
+
+	Ok((remove, depth))
+}
+
+/// Project a stencil, failing if it misses the sculpt.
+fn project_stencil(editor: &mut Editor, shape: &StencilShape, depth: u32, remove: bool) -> Result<String, CommandError> {
+	if !editor.project_stencil(shape, depth, remove) {
+		return Err(CommandError::Failed(tr("command-stencil-missed")));
+	}
+
+	Ok(String::new())
+}
+
+fn stencil_circle(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "stencil circle <add|remove> <depth> <x> <y> <radius>";
+	expect_count(args, 5, USAGE)?;
+
+	let (remove, depth) = stencil_mode(args, USAGE)?;
+	let center = vec2(arg(args, 2, USAGE)?, arg(args, 3, USAGE)?);
+	let radius: f32 = arg(args, 4, USAGE)?;
+	if radius <= 0.0 {
+		return Err(CommandError::Usage(USAGE));
+	}
+	let shape = StencilShape::circle(center, radius, editor.get_stencil_aspect());
+
+	project_stencil(editor, &shape, depth, remove)
+}
+
+fn stencil_polygon(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "stencil polygon <add|remove> <depth> <x1> <y1> <x2> <y2> <x3> <y3> [<x> <y>...]";
+	if args.len() < 8 || !args.len().is_multiple_of(2) {
+		return Err(CommandError::Usage(USAGE));
+	}
+
+	let (remove, depth) = stencil_mode(args, USAGE)?;
+	let points = (1..args.len() / 2)
+		.map(|index| Ok(vec2(arg(args, 2 * index, USAGE)?, arg(args, 2 * index + 1, USAGE)?)))
+		.collect::<Result<Vec<_>, CommandError>>()?;
+	let shape = StencilShape::polygon(&points, editor.get_stencil_aspect());
+
+	project_stencil(editor, &shape, depth, remove)
+}
+
+fn stencil_svg(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "stencil svg <add|remove> <depth> <path> <x> <y> <height>";
+	expect_count(args, 6, USAGE)?;
+
+	let (remove, depth) = stencil_mode(args, USAGE)?;
+	let center = vec2(arg(args, 3, USAGE)?, arg(args, 4, USAGE)?);
+	let height: f32 = arg(args, 5, USAGE)?;
+	if height <= 0.0 {
+		return Err(CommandError::Usage(USAGE));
+	}
+	let source = std::fs::read_to_string(args[2])
+		.map_err(|error| CommandError::Failed(tr_args("command-stencil-svg-failed", &[("error", &error.to_string())])))?;
+	let shape = StencilShape::from_svg(&source, center, height, editor.get_stencil_aspect())
+		.ok_or_else(|| CommandError::Failed(tr("command-stencil-svg-empty")))?;
+
+	project_stencil(editor, &shape, depth, remove)
+}
+
+fn review_export(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 1, "review export <directory>")?;
+
+	if editor.get_pins().is_empty() {
+		return Err(CommandError::Failed(tr("command-pin-none")));
+	}
+	let count = editor.export_review(args[0])
+		.map_err(|error| CommandError::Failed(tr_args("command-review-failed", &[("error", &error.to_string())])))?;
+
+	Ok(tr_args("command-review-exported", &[("count", &count.to_string()), ("directory", args[0])]))
+}
+
+fn report(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 1, "report <path>")?;
+
+	let report = editor.export_report(args[0])
+		.map_err(|error| CommandError::Failed(tr_args("command-report-failed", &[("error", &error.to_string())])))?;
+
+	Ok(tr_args("command-report-written", &[("nodes", &report.nodes.to_string()), ("path", args[0])]))
+}
+
+fn export_stl(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	let (path, filter) = export_args(args, "export stl <path> [scene|active|<name>...]")?;
+
+	let triangles = editor.export_stl(path, &filter)
+		.map_err(|error| CommandError::Failed(tr_args("command-export-failed", &[("error", &error.to_string())])))?;
+
+	Ok(tr_args("command-exported", &[("triangles", &triangles.to_string()), ("path", path)]))
+}
+
+fn export_obj(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	let (path, filter) = export_args(args, "export obj <path> [scene|active|<name>...]")?;
+
+	let quads = editor.export_obj(path, &filter)
+		.map_err(|error| CommandError::Failed(tr_args("command-export-failed", &[("error", &error.to_string())])))?;
+
+	Ok(tr_args("command-exported-quads", &[("quads", &quads.to_string()), ("path", path)]))
+}
+
+fn export_gltf(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	let (path, filter) = export_args(args, "export gltf <path> [scene|active|<name>...]")?;
+
+	let triangles = editor.export_gltf(path, &filter)
+		.map_err(|error| CommandError::Failed(tr_args("command-export-failed", &[("error", &error.to_string())])))?;
+
+	Ok(tr_args("command-exported", &[("triangles", &triangles.to_string()), ("path", path)]))
+}
+
+fn export_vox(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	let (path, filter) = export_args(args, "export vox <path> [scene|active|<name>...]")?;
+
+	let voxels = editor.export_vox(path, &filter)
+		.map_err(|error| CommandError::Failed(tr_args("command-export-failed", &[("error", &error.to_string())])))?;
+
+	Ok(tr_args("command-exported-voxels", &[("voxels", &voxels.to_string()), ("path", path)]))
+}
+
+/// Split the arguments of an export into the path and the filter picking the objects, the whole scene by default.
+fn export_args<'a>(args: &[&'a str], usage: &'static str) -> Result<(&'a str, ExportFilter), CommandError> {
+	let Some((path, names)) = args.split_first() else {
+		return Err(CommandError::Usage(usage));
+	};
+	let filter = match names {
+		[] | ["scene"] => ExportFilter::Scene,
+		["active"] => ExportFilter::Active,
+		names => ExportFilter::Named(names.iter().map(|name| name.to_string()).collect()),
+	};
+
+	Ok((path, filter))
+}
+
+fn view_duration(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "view duration <seconds>";
+	expect_count(args, 1, USAGE)?;
+
+	let seconds: f32 = arg(args, 0, USAGE)?;
+	let duration = std::time::Duration::try_from_secs_f32(seconds).map_err(|_| CommandError::Usage(USAGE))?;
+	editor.set_view_duration(duration);
+
+	Ok(String::new())
+}
+
+fn orbit(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "orbit <x> <y>";
+	expect_count(args, 2, USAGE)?;
+
+	editor.orbit(vec2(arg(args, 0, USAGE)?, arg(args, 1, USAGE)?));
+
+	Ok(String::new())
+}
+
+fn pan(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "pan <x> <y>";
+	expect_count(args, 2, USAGE)?;
+
+	editor.pan(vec2(arg(args, 0, USAGE)?, arg(args, 1, USAGE)?));
+
+	Ok(String::new())
+}
+
+fn zoom(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "zoom <amount>";
+	expect_count(args, 1, USAGE)?;
+
+	editor.zoom(arg(args, 0, USAGE)?);
+
+	Ok(String::new())
+}
+
+fn turn(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "turn <degrees>";
+	expect_count(args, 1, USAGE)?;
+
+	let degrees: f32 = arg(args, 0, USAGE)?;
+	editor.turn(degrees.to_radians());
+
+	Ok(String::new())
+}
+
+fn mask_cavity(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "mask cavity [invert]";
+
+	let invert = match args {
+		[] => false,
+		["invert"] => true,
+		_ => return Err(CommandError::Usage(USAGE)),
+	};
+	editor.mask_cavities(invert);
+
+	Ok(String::new())
+}
+
+fn mask_facing(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "mask facing <degrees>";
+	expect_count(args, 1, USAGE)?;
+
+	let degrees: f32 = arg(args, 0, USAGE)?;
+	editor.mask_facing(degrees.to_radians());
+
+	Ok(String::new())
+}
+
+fn mask_visible(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 0, "mask visible")?;
+
+	editor.request_visible_mask();
+
+	Ok(String::new())
+}
+
+fn mask_clear(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 0, "mask clear")?;
+
+	editor.clear_mask();
+
+	Ok(String::new())
+}
+
+fn paint(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "paint <on|off>";
+	expect_count(args, 1, USAGE)?;
+
+	match args[0] {
+		"on" => editor.set_painting(true),
+		"off" => editor.set_painting(false),
+		_ => return Err(CommandError::Usage(USAGE)),
+	}
+
+	Ok(String::new())
+}
+
+fn paint_color(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "paint color <red> <green> <blue> [alpha]";
+	if !(3..=4).contains(&args.len()) {
+		return Err(CommandError::Usage(USAGE));
+	}
+
+	let mut color = [1.0; 4];
+	for (index, channel) in color.iter_mut().enumerate().take(args.len()) {
+		*channel = arg(args, index, USAGE)?;
+	}
+	if color.iter().any(|channel| !(0.0..=1.0).contains(channel)) {
+		return Err(CommandError::Failed(tr("command-paint-color-range")));
+	}
+	editor.set_paint_color(color);
+
+	Ok(String::new())
+}
+
+fn paint_clear(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 0, "paint clear")?;
+
+	editor.clear_paint();
+
+	Ok(String::new())
+}
+
+fn undo(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 0, "undo")?;
+
+	if !editor.undo() {
+		return Err(CommandError::Failed(tr("command-undo-empty")));
+	}
+
+	Ok(String::new())
+}
+
+fn redo(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 0, "redo")?;
+
+	if !editor.redo() {
+		return Err(CommandError::Failed(tr("command-redo-empty")));
+	}
+
+	Ok(String::new())
+}
+
+fn paint_undo(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 0, "paint undo")?;
+
+	if !editor.undo_paint() {
+		return Err(CommandError::Failed(tr("command-paint-undo-empty")));
+	}
+
+	Ok(String::new())
+}
+
+fn paint_redo(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 0, "paint redo")?;
+
+	if !editor.redo_paint() {
+		return Err(CommandError::Failed(tr("command-paint-redo-empty")));
+	}
+
+	Ok(String::new())
+}
+
+/// Find an object by its name, failing if there is none.
+fn find_object(editor: &Editor, name: &str) -> Result<usize, CommandError> {
+	editor.get_scene().find(name)
+		.ok_or_else(|| CommandError::Failed(tr_args("command-object-unknown", &[("name", name)])))
+}
+
+/// Find the object named by an optional argument, or the active one without it.
+fn target_object(editor: &Editor, args: &[&str], usage: &'static str) -> Result<usize, CommandError> {
+	match args.len() {
+		0 => Ok(editor.get_scene().get_active_index()),
+		1 => find_object(editor, args[0]),
+		_ => Err(CommandError::Usage(usage)),
+	}
+}
+
+fn object_add(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "object add <name> [resolution]";
+	let resolution = match args.len() {
+		1 => editor.get_document().get_resolution(),
+		2 => arg(args, 1, USAGE)?,
+		_ => return Err(CommandError::Usage(USAGE)),
+	};
+	// the octree halves each node down to single voxels
+	if !resolution.is_power_of_two() || !(8..=1024).contains(&resolution) {
+		return Err(CommandError::Failed(tr("command-object-resolution-range")));
+	}
+
+	let index = editor.add_object(args[0], resolution);
+
+	Ok(tr_args("command-object-added", &[("name", &editor.get_scene().get_objects()[index].name)]))
+}
+
+fn object_duplicate(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 0, "object duplicate")?;
+
+	let active = editor.get_scene().get_active_index();
+	let index = editor.duplicate_object(active).ok_or_else(|| CommandError::Failed(tr("command-object-duplicate-failed")))?;
+
+	Ok(tr_args("command-object-added", &[("name", &editor.get_scene().get_objects()[index].name)]))
+}
+
+fn object_delete(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	let index = target_object(editor, args, "object delete [name]")?;
+
+	if !editor.delete_object(index) {
+		return Err(CommandError::Failed(tr("command-object-last")));
+	}
+
+	Ok(String::new())
+}
+
+fn object_select(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "object select <name>";
+	expect_count(args, 1, USAGE)?;
+
+	let index = find_object(editor, args[0])?;
+	editor.set_active_object(index);
+
+	Ok(String::new())
+}
+
+fn object_list(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 0, "object list")?;
+
+	let scene = editor.get_scene();
+	let names: Vec<String> = scene.get_objects().iter()
+		.enumerate()
+		.map(|(index, object)| if index == scene.get_active_index() {
+			tr_args("command-object-active", &[("name", &object.name)])
+		} else {
+			object.name.clone()
+		})
+		.collect();
+
+	Ok(names.join(", "))
+}
+
+fn object_show(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	let index = target_object(editor, args, "object show [name]")?;
+
+	editor.set_object_visible(index, true);
+
+	Ok(String::new())
+}
+
+fn object_hide(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	let index = target_object(editor, args, "object hide [name]")?;
+
+	editor.set_object_visible(index, false);
+
+	Ok(String::new())
+}
+
+fn object_lock(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	let index = target_object(editor, args, "object lock [name]")?;
+
+	editor.set_object_locked(index, true);
+
+	Ok(String::new())
+}
+
+fn object_unlock(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	let index = target_object(editor, args, "object unlock [name]")?;
+
+	editor.set_object_locked(index, false);
+
+	Ok(String::new())
+}
+
+fn object_solo(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "object solo <on|off>";
+	expect_count(args, 1, USAGE)?;
+
+	match args[0] {
+		"on" => editor.set_solo(true),
+		"off" => editor.set_solo(false),
+		_ => return Err(CommandError::Usage(USAGE)),
+	}
+
+	Ok(String::new())
+}
+
+fn object_move(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "object move <x> <y> <z>";
+	expect_count(args, 3, USAGE)?;
+
+	let mut transform = editor.get_object_transform();
+	transform.translation = vec3(arg(args, 0, USAGE)?, arg(args, 1, USAGE)?, arg(args, 2, USAGE)?);
+	editor.set_object_transform(transform);
+
+	Ok(String::new())
+}
+
+fn object_rotate(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "object rotate <x> <y> <z>";
+	expect_count(args, 3, USAGE)?;
+
+	let degrees: [f32; 3] = [arg(args, 0, USAGE)?, arg(args, 1, USAGE)?, arg(args, 2, USAGE)?];
+	let mut transform = editor.get_object_transform();
+	transform.rotation = Quat::from_euler(EulerRot::XYZ, degrees[0].to_radians(), degrees[1].to_radians(), degrees[2].to_radians());
+	editor.set_object_transform(transform);
+
+	Ok(String::new())
+}
+
+fn object_scale(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "object scale <factor>";
+	expect_count(args, 1, USAGE)?;
+
+	let scale: f32 = arg(args, 0, USAGE)?;
+	if scale <= 0.0 || !scale.is_finite() {
+		return Err(CommandError::Failed(tr("command-object-scale-range")));
+	}
+	let mut transform = editor.get_object_transform();
+	transform.scale = scale;
+	editor.set_object_transform(transform);
+
+	Ok(String::new())
+}
+
+fn object_boolean(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "object boolean <name> <union|subtract|intersect>";
+	expect_count(args, 2, USAGE)?;
+
+	let index = find_object(editor, args[0])?;
+	let op = BooleanOp::from_name(args[1]).ok_or(CommandError::Usage(USAGE))?;
+	if !editor.apply_boolean(index, op) {
+		return Err(CommandError::Failed(tr("command-object-boolean-failed")));
+	}
+
+	Ok(String::new())
+}
+
+fn snap_grid(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "snap grid <size|off>";
+	expect_count(args, 1, USAGE)?;
+
+	let mut snapping = editor.get_snapping();
+	snapping.grid = if args[0] == "off" {
+		None
+	} else {
+		let size: f32 = arg(args, 0, USAGE)?;
+		if size <= 0.0 || !size.is_finite() {
+			return Err(CommandError::Failed(tr("command-snap-grid-range")));
+		}
+		Some(size)
+	};
+	editor.set_snapping(snapping);
+
+	Ok(String::new())
+}
+
+fn snap_angle(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "snap angle <degrees|off>";
+	expect_count(args, 1, USAGE)?;
+
+	let mut snapping = editor.get_snapping();
+	snapping.angle = if args[0] == "off" {
+		None
+	} else {
+		let degrees: f32 = arg(args, 0, USAGE)?;
+		if !(degrees > 0.0 && degrees <= 180.0) {
+			return Err(CommandError::Failed(tr("command-snap-angle-range")));
+		}
+		Some(degrees.to_radians())
+	};
+	editor.set_snapping(snapping);
+
+	Ok(String::new())
+}
+
+fn snap_surface(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "snap surface <on|off>";
+	expect_count(args, 1, USAGE)?;
+
+	let mut snapping = editor.get_snapping();
+	snapping.surface = match args[0] {
+		"on" => true,
+		"off" => false,
+		_ => return Err(CommandError::Usage(USAGE)),
+	};
+	editor.set_snapping(snapping);
+
+	Ok(String::new())
+}
+
+fn snap_voxels(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "snap voxels <depth|off>";
+	expect_count(args, 1, USAGE)?;
+
+	let depth = if args[0] == "off" {
+		None
+	} else {
+		let depth: u32 = arg(args, 0, USAGE)?;
+		// below the finest voxels, stamps would snap between them
+		let max = editor.get_document().get_resolution().trailing_zeros();
+		if depth > max {
+			return Err(CommandError::Failed(tr_args("command-snap-voxels-range", &[("max", &max.to_string())])));
+		}
+		Some(depth)
+	};
+	editor.set_snap(depth);
+
+	Ok(String::new())
+}
+
+fn instance_add(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "instance add <x> <y> <z>";
+	expect_count(args, 3, USAGE)?;
+
+	let transform = Transform {
+		translation: vec3(arg(args, 0, USAGE)?, arg(args, 1, USAGE)?, arg(args, 2, USAGE)?),
+		..editor.get_object_transform()
+	};
+	editor.add_instance(transform);
+
+	Ok(String::new())
+}
+
+fn instance_scatter(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "instance scatter <count> <radius> [seed]";
+	if !(2..=3).contains(&args.len()) {
+		return Err(CommandError::Usage(USAGE));
+	}
+
+	let count: usize = arg(args, 0, USAGE)?;
+	let radius: f32 = arg(args, 1, USAGE)?;
+	let seed = if args.len() == 3 { arg(args, 2, USAGE)? } else { 1 };
+	if radius <= 0.0 || !radius.is_finite() {
+		return Err(CommandError::Failed(tr("command-instance-radius-range")));
+	}
+	editor.scatter_instances(count, radius, seed);
+
+	Ok(tr_args("command-instance-count", &[("count", &editor.get_instances().len().to_string())]))
+}
+
+fn instance_clear(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 0, "instance clear")?;
+
+	editor.clear_instances();
+
+	Ok(String::new())
+}
+
+fn gradient(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "gradient <x|y|z> <material>...";
+	if args.len() < 2 {
+		return Err(CommandError::Usage(USAGE));
+	}
+
+	let axis = match args[0] {
+		"x" => Vec3::X,
+		"y" => Vec3::Y,
+		"z" => Vec3::Z,
+		_ => return Err(CommandError::Usage(USAGE)),
+	};
+	let ramp = (1..args.len()).map(|index| arg(args, index, USAGE)).collect::<Result<Vec<u32>, _>>()?;
+	check_materials(editor, &ramp)?;
+	if !editor.fill_gradient_along(axis, ramp) {
+		return Err(CommandError::Failed(tr("command-gradient-empty")));
+	}
+
+	Ok(String::new())
+}
+
+fn gradient_between(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "gradient between <x1> <y1> <x2> <y2> <material>...";
+	if args.len() < 5 {
+		return Err(CommandError::Usage(USAGE));
+	}
+
+	let start = vec2(arg(args, 0, USAGE)?, arg(args, 1, USAGE)?);
+	let end = vec2(arg(args, 2, USAGE)?, arg(args, 3, USAGE)?);
+	let ramp = (4..args.len()).map(|index| arg(args, index, USAGE)).collect::<Result<Vec<u32>, _>>()?;
+	check_materials(editor, &ramp)?;
+	editor.fill_gradient_between(start, end, ramp);
+
+	Ok(String::new())
+}
+
+fn wear(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "wear <intensity> <scale> <material|erode>";
+	expect_count(args, 3, USAGE)?;
+
+	let intensity: f32 = arg(args, 0, USAGE)?;
+	if !(0.0..=1.0).contains(&intensity) {
+		return Err(CommandError::Failed(tr("command-wear-intensity-range")));
+	}
+	let effect = match args[2] {
+		"erode" => WearEffect::Erode,
+		_ => {
+			let material = arg(args, 2, USAGE)?;
+			check_materials(editor, &[material])?;
+			WearEffect::Material(material)
+		},
+	};
+	editor.wear(Wear { intensity, scale: arg(args, 1, USAGE)?, effect });
+
+	Ok(String::new())
+}
+
+fn quantize(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "quantize <depth>";
+	expect_count(args, 1, USAGE)?;
+
+	editor.quantize(arg(args, 0, USAGE)?);
+
+	Ok(String::new())
+}
+
+fn node_limit(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "node limit <count|off>";
+	expect_count(args, 1, USAGE)?;
+
+	if args[0] == "off" {
+		editor.set_node_limit(None);
+		return Ok(String::new());
+	}
+	let limit: usize = arg(args, 0, USAGE)?;
+	if limit == 0 {
+		return Err(CommandError::Failed(tr("command-node-limit-range")));
+	}
+	editor.set_node_limit(Some(limit));
+
+	Ok(String::new())
+}
+
+fn validate(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 0, "validate")?;
+
+	let message = match editor.validate() {
+		0 => tr("command-validate-clean"),
+		count => tr_args("command-validate-issues", &[("count", &count.to_string())]),
+	};
+
+	match editor.get_repaired_count() {
+		0 => Ok(message),
+		count => Ok(format!("{}\n{message}", tr_args("command-validate-loaded", &[("count", &count.to_string())]))),
+	}
+}
+
+fn repair(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 0, "repair")?;
+
+	let count = editor.repair();
+
+	Ok(tr_args("command-repaired", &[("count", &count.to_string())]))
+}
+
+fn watertight(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 0, "watertight")?;
+
+	let issues = editor.check_watertight();
+	if issues.is_empty() {
+		return Ok(tr("command-watertight-ok"));
+	}
+
+	// a few places are enough to start fixing, and the check can run again after
+	let places: Vec<String> = issues.iter()
+		.take(3)
+		.map(|issue| {
+			let position = issue.position();
+			let key = match issue {
+				SurfaceIssue::Edge(_) => "command-watertight-edge",
+				SurfaceIssue::Vertex(_) => "command-watertight-vertex",
+			};
+			tr_args(key, &[("position", &format!("{:.3}, {:.3}, {:.3}", position.x, position.y, position.z))])
+		})
+		.collect();
+
+	Err(CommandError::Failed(tr_args("command-watertight-failed", &[
+		("count", &issues.len().to_string()),
+		("places", &places.join("; ")),
+	])))
+}
+
+fn colors(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "colors <preset>";
+	expect_count(args, 1, USAGE)?;
+
+	let preset = PalettePreset::from_name(args[0])
+		.ok_or_else(|| CommandError::Failed(tr_args("command-colors-unknown", &[("name", args[0])])))?;
+	editor.set_palette_preset(preset);
+
+	Ok(String::new())
+}
+
+fn color(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 0, "color")?;
+
+	let color = editor.get_color_management();
+	Ok(tr_args("command-color-current", &[
+		("space", color.working_space.name()),
+		("tonemap", color.tonemap.name()),
+		("exposure", &color.exposure.to_string()),
+	]))
+}
+
+fn color_space(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "color space <srgb|acescg|rec2020>";
+	expect_count(args, 1, USAGE)?;
+
+	let mut color = editor.get_color_management();
+	color.working_space = WorkingSpace::from_name(args[0]).ok_or(CommandError::Usage(USAGE))?;
+	editor.set_color_management(color);
+
+	Ok(String::new())
+}
+
+fn color_tonemap(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "color tonemap <none|aces|filmic>";
+	expect_count(args, 1, USAGE)?;
+
+	let mut color = editor.get_color_management();
+	color.tonemap = Tonemap::from_name(args[0]).ok_or(CommandError::Usage(USAGE))?;
+	editor.set_color_management(color);
+
+	Ok(String::new())
+}
+
+fn color_exposure(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "color exposure <stops>";
+	expect_count(args, 1, USAGE)?;
+
+	let exposure: f32 = arg(args, 0, USAGE)?;
+	if !exposure.is_finite() {
+		return Err(CommandError::Usage(USAGE));
+	}
+	let mut color = editor.get_color_management();
+	color.exposure = exposure;
+	editor.set_color_management(color);
+
+	Ok(String::new())
+}
+
+fn save(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "save <path> [level]";
+	let level = match args.len() {
+		1 => DEFAULT_LEVEL,
+		2 => arg(args, 1, USAGE)?,
+		_ => return Err(CommandError::Usage(USAGE)),
+	};
+	if !(1..=22).contains(&level) {
+		return Err(CommandError::Failed(tr("command-save-level-range")));
+	}
+
+	editor.request_save(args[0], level);
+
+	Ok(tr_args("command-saving", &[("path", args[0])]))
+}
+
+fn save_version(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 0, "save version")?;
+
+	let version = editor.request_version()
+		.map_err(|error| CommandError::Failed(tr_args("command-save-version-failed", &[("error", &error.to_string())])))?;
+
+	Ok(tr_args("command-saving-version", &[("path", &version.path.display().to_string())]))
+}
+
+fn versions(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 0, "versions")?;
+
+	let versions = editor.get_versions()
+		.map_err(|error| CommandError::Failed(tr_args("command-versions-failed", &[("error", &error.to_string())])))?;
+	if versions.is_empty() {
+		return Err(CommandError::Failed(tr("command-versions-none")));
+	}
+	editor.request_version_picker();
+
+	Ok(String::new())
+}
+
+fn open(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 1, "open <path>")?;
+
+	let index = editor.open_document(args[0])
+		.map_err(|error| CommandError::Failed(tr_args("command-open-failed", &[("error", &error.to_string())])))?;
+
+	Ok(tr_args("command-opened", &[("name", &editor.get_scene().get_objects()[index].name)]))
+}
+
+fn reload(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 0, "reload")?;
+
+	editor.reload_document()
+		.map_err(|error| CommandError::Failed(tr_args("command-reload-failed", &[("error", &error.to_string())])))?;
+
+	Ok(tr_args("command-reloaded", &[("name", &editor.get_scene().get_active().name)]))
+}
+
+fn colors_import(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "colors import <path>";
+	expect_count(args, 1, USAGE)?;
+
+	let count = editor.import_palette(args[0])
+		.map_err(|error| CommandError::Failed(tr_args("command-colors-import-failed", &[("error", &error.to_string())])))?;
+
+	Ok(tr_args("command-colors-imported", &[("count", &count.to_string())]))
+}
+
+fn colors_list(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 0, "colors list")?;
+
+	let names: Vec<String> = PalettePreset::ALL.iter()
+		.map(|preset| if preset.is_color_blind_safe() {
+			tr_args("command-colors-safe", &[("name", preset.name())])
+		} else {
+			preset.name().to_owned()
+		})
+		.collect();
+
+	Ok(names.join(", "))
+}
+
+fn light_directional(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "light directional <x> <y> <z> <intensity> [<red> <green> <blue>]";
+	let (direction, color, intensity) = light_args(args, USAGE)?;
+	if direction == Vec3::ZERO {
+		return Err(CommandError::Failed(tr("command-light-direction")));
+	}
+
+	add_light(editor, Light { kind: LightKind::Directional(direction), color, intensity })
+}
+
+fn light_point(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "light point <x> <y> <z> <intensity> [<red> <green> <blue>]";
+	let (position, color, intensity) = light_args(args, USAGE)?;
+
+	add_light(editor, Light { kind: LightKind::Point(position), color, intensity })
+}
+
+/// Parse the vector, intensity and optional color of a light.
+fn light_args(args: &[&str], usage: &'static str) -> Result<(Vec3, [f32; 3], f32), CommandError> {
+	if args.len() != 4 && args.len() != 7 {
+		return Err(CommandError::Usage(usage));
+	}
+
+	let vector = vec3(arg(args, 0, usage)?, arg(args, 1, usage)?, arg(args, 2, usage)?);
+	let intensity: f32 = arg(args, 3, usage)?;
+	let mut color = [1.0; 3];
+	for (index, channel) in color.iter_mut().enumerate().take(args.len() - 4) {
+		*channel = arg(args, 4 + index, usage)?;
+	}
+	if !(vector.is_finite() && intensity.is_finite() && intensity >= 0.0) || color.iter().any(|channel| !(0.0..=1.0).contains(channel)) {
+		return Err(CommandError::Failed(tr("command-light-range")));
+	}
+
+	Ok((vector, color, intensity))
+}
+
+/// Add a light, failing if the viewport has as many as it can shade with.
+fn add_light(editor: &mut Editor, light: Light) -> Result<String, CommandError> {
+	if !editor.add_light(light) {
+		return Err(CommandError::Failed(tr_args("command-light-full", &[("count", &MAX_LIGHTS.to_string())])));
+	}
+
+	Ok(String::new())
+}
+
+fn light_list(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 0, "light list")?;
+
+	let lights = editor.get_lights();
+	if lights.is_empty() {
+		return Ok(tr("command-light-none"));
+	}
+	let lines: Vec<String> = lights.iter()
+		.enumerate()
+		.map(|(index, light)| {
+			let (kind, vector) = match light.kind {
+				LightKind::Directional(direction) => ("directional", direction),
+				LightKind::Point(position) => ("point", position),
+			};
+			let [red, green, blue] = light.color;
+			format!("{}: {kind} {} {} {} {} {red} {green} {blue}", index + 1, vector.x, vector.y, vector.z, light.intensity)
+		})
+		.collect();
+
+	Ok(lines.join("\n"))
+}
+
+fn light_clear(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 0, "light clear")?;
+
+	editor.clear_lights();
+
+	Ok(String::new())
+}
+
+fn material_subsurface(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "material subsurface <index> <fraction>";
+	expect_count(args, 2, USAGE)?;
+
+	let strength: f32 = arg(args, 1, USAGE)?;
+	if !(0.0..=1.0).contains(&strength) {
+		return Err(CommandError::Failed(tr("command-material-subsurface-range")));
+	}
+	editor.set_subsurface(arg(args, 0, USAGE)?, strength)
+		.map_err(|error| CommandError::Failed(error.to_string()))?;
+
+	Ok(String::new())
+}
+
+fn stats(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "stats <on|off>";
+	expect_count(args, 1, USAGE)?;
+
+	match args[0] {
+		"on" => editor.set_stats_enabled(true),
+		"off" => editor.set_stats_enabled(false),
+		_ => return Err(CommandError::Usage(USAGE)),
+	}
+
+	Ok(String::new())
+}
+
+fn macro_record(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "macro record <name>";
+	expect_count(args, 1, USAGE)?;
+
+	editor.start_recording(args[0]);
+
+	Ok(tr_args("command-macro-recording", &[("name", args[0])]))
+}
+
+fn macro_stop(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 0, "macro stop")?;
+
+	if !editor.stop_recording() {
+		return Err(CommandError::Failed(tr("command-macro-not-recording")));
+	}
+
+	Ok(String::new())
+}
+
+fn macro_play(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "macro play <name> [<x> <y> [<scale>]]";
+
+	let placement = match args.len() {
+		1 => None,
+		3 | 4 => {
+			let position = editor.screen_to_world(arg(args, 1, USAGE)?, arg(args, 2, USAGE)?);
+			let scale = if args.len() == 4 { arg(args, 3, USAGE)? } else { 1.0 };
+
+			Some((position, scale))
+		},
+		_ => return Err(CommandError::Usage(USAGE)),
+	};
+	editor.play_macro(args[0], placement)?;
+
+	Ok(String::new())
+}
+
+fn onion(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "onion <on|off>";
+	expect_count(args, 1, USAGE)?;
+
+	match args[0] {
+		"on" => editor.set_onion_skin(true),
+		"off" => editor.set_onion_skin(false),
+		_ => return Err(CommandError::Usage(USAGE)),
+	}
+
+	Ok(String::new())
+}
+
+fn timelapse(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "timelapse <directory> <strokes> [view]";
+	if !(2..=3).contains(&args.len()) {
+		return Err(CommandError::Usage(USAGE));
+	}
+
+	let every: u32 = arg(args, 1, USAGE)?;
+	let view: Option<usize> = if args.len() == 3 { Some(arg(args, 2, USAGE)?) } else { None };
+	if every == 0 {
+		return Err(CommandError::Usage(USAGE));
+	}
+	if let Some(slot) = view.filter(|slot| !editor.has_view(*slot)) {
+		return Err(CommandError::Failed(tr_args("command-view-empty", &[("slot", &slot.to_string())])));
+	}
+	editor.start_timelapse(args[0], every, view)
+		.map_err(|error| CommandError::Failed(tr_args("command-timelapse-failed", &[("error", &error.to_string())])))?;
+
+	Ok(tr_args("command-timelapse-started", &[("directory", args[0]), ("strokes", args[1])]))
+}
+
+fn soak(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	const USAGE: &str = "soak <strokes> <path> [seed]";
+	if !(2..=3).contains(&args.len()) {
+		return Err(CommandError::Usage(USAGE));
+	}
+
+	let strokes: u32 = arg(args, 0, USAGE)?;
+	let seed: u32 = if args.len() == 3 { arg(args, 2, USAGE)? } else { 1 };
+	if strokes == 0 {
+		return Err(CommandError::Usage(USAGE));
+	}
+	editor.start_soak(args[1], strokes, seed);
+
+	Ok(tr_args("command-soak-started", &[("strokes", args[0]), ("path", args[1])]))
+}
+
+fn timelapse_stop(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 0, "timelapse stop")?;
+
+	let Some(frames) = editor.stop_timelapse() else {
+		return Err(CommandError::Failed(tr("command-timelapse-idle")));
+	};
+
+	Ok(tr_args("command-timelapse-stopped", &[("frames", &frames.to_string())]))
+}
+
+fn macro_list(editor: &mut Editor, args: &[&str]) -> Result<String, CommandError> {
+	expect_count(args, 0, "macro list")?;
+
+	let names: Vec<&str> = editor.get_macros().map(|recorded| recorded.name.as_str()).collect();
+
+	Ok(names.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use crate::aabb::Aabb;
+	use crate::brush::{Brush, RoundBrushTip, TipShape};
+	use crate::document::Document;
+	use crate::scene::Snapping;
+	use crate::editor::FileChange;
+	use crate::editor::recording::MacroStep;
+	use crate::util::progress::Progress;
+
+    #[test]
+    fn parse_prefers_the_longest_name() {
+    	let registry = CommandRegistry::default();
+
+    	let (command, args) = registry.parse("brush size 0.05").unwrap();
+    	assert_eq!(command.name, "brush size");
+    	assert_eq!(args, vec!["0.05"]);
+
+    	let (command, args) = registry.parse("  brush   round ").unwrap();
+    	assert_eq!(command.name, "brush");
+    	assert_eq!(args, vec!["round"]);
+
+    	assert_eq!(registry.parse("frobnicate 3").err(), Some(CommandError::Unknown("frobnicate".to_owned())));
+    	assert_eq!(registry.parse("   ").err(), Some(CommandError::Empty));
+    }
+
+    #[test]
+    fn quotes_keep_words_together() {
+    	assert_eq!(tokenize(r#"export obj "~/my model.obj""#), vec!["export", "obj", "~/my model.obj"]);
+    	assert_eq!(tokenize(r#"name """#), vec!["name", ""]);
+    }
+
+    #[test]
+    fn commands_reach_the_editor() {
+    	let mut editor = Editor::default();
+
+    	editor.run_command("brush size 0.05").unwrap();
+    	assert_eq!(editor.get_brush().get_size(), 0.05);
+
+    	editor.run_command("brush square").unwrap();
+    	assert_eq!(editor.get_brush().get_shape(), TipShape::Cube);
+
+    	assert!(editor.run_command("brush list").unwrap().starts_with("round ("));
+    	assert!(matches!(editor.run_command("brush chisel"), Err(CommandError::Usage(_))));
+    	editor.run_command("brush pen").unwrap();
+    	editor.run_command("brush voxels 3").unwrap();
+    	let voxel_size = editor.get_document().get_voxel_size();
+    	assert_eq!(editor.get_stamp(0.5, 0.5).size, 1.5 * voxel_size);
+    	assert!(matches!(editor.run_command("brush voxels 0"), Err(CommandError::Failed(_))));
+    	editor.run_command("brush square").unwrap();
+
+    	// each brush keeps its own hardness
+    	editor.run_command("brush hardness 0.25").unwrap();
+    	assert_eq!(editor.get_brush().get_hardness(), 0.25);
+    	assert_eq!(editor.get_brushes().get("round").map(Brush::get_hardness), Some(1.0));
+    	assert!(matches!(editor.run_command("brush hardness 2"), Err(CommandError::Failed(_))));
+
+    	editor.run_command("brush accumulation 0.5").unwrap();
+    	assert_eq!(editor.get_brush().get_accumulation(), Some(0.5));
+    	assert!(matches!(editor.run_command("brush accumulation 0"), Err(CommandError::Failed(_))));
+    	editor.run_command("brush accumulation off").unwrap();
+    	assert_eq!(editor.get_brush().get_accumulation(), None);
+
+    	editor.run_command("brush through on").unwrap();
+    	assert!(editor.get_sculpt_through());
+
+    	editor.run_command("brush backfaces 90").unwrap();
+    	assert_eq!(editor.get_backface_masking(), Some(90f32.to_radians()));
+    	assert!(matches!(editor.run_command("brush backfaces 200"), Err(CommandError::Failed(_))));
+    	editor.run_command("brush backfaces off").unwrap();
+    	assert_eq!(editor.get_backface_masking(), None);
+
+    	editor.run_command("stroke lock normal").unwrap();
+    	assert_eq!(editor.get_stroke_lock(), StrokeLock::Normal);
+    	assert_eq!(editor.run_command("stroke lock w"), Err(CommandError::Usage("stroke lock <x|y|z|normal|plane|guide>")));
+    	editor.run_command("stroke plane 0.5 0.5").unwrap();
+    	assert!(editor.get_stroke_plane().is_some());
+    	editor.run_command("stroke plane off").unwrap();
+    	editor.run_command("guide draw on").unwrap();
+    	assert!(editor.is_drawing_guides());
+    	editor.run_command("guide draw off").unwrap();
+    	assert!(matches!(editor.run_command("guide add 0.1 0.1 0.2 0.1"), Err(CommandError::Failed(_))));
+    	assert_eq!(editor.run_command("guide add 0.1 0.1 0.2"), Err(CommandError::Usage("guide add <x1> <y1> <x2> <y2> [<x> <y>...]")));
+    	assert!(matches!(editor.run_command("guide remove 1"), Err(CommandError::Failed(_))));
+    	assert!(matches!(editor.run_command("guide array 1 5"), Err(CommandError::Failed(_))));
+    	assert_eq!(editor.run_command("guide array 1 5 macro"), Err(CommandError::Usage("guide array <number> <count> [remove|macro <name> [align]]")));
+    	assert_eq!(editor.get_stroke_plane(), None);
+
+    	editor.run_command("node limit 100000").unwrap();
+    	assert_eq!(editor.get_node_limit(), Some(100000));
+    	assert!(matches!(editor.run_command("node limit 0"), Err(CommandError::Failed(_))));
+    	editor.run_command("node limit off").unwrap();
+    	assert_eq!(editor.get_node_limit(), None);
+
+    	assert_eq!(editor.run_command("brush size big"), Err(CommandError::Usage("brush size <fraction>")));
+    	assert!(matches!(editor.run_command("view recall 7"), Err(CommandError::Failed(_))));
+
+    	editor.run_command("color space acescg").unwrap();
+    	editor.run_command("color tonemap filmic").unwrap();
+    	editor.run_command("color exposure -1.5").unwrap();
+    	assert_eq!(editor.run_command("color").unwrap(), "Working space acescg, tone mapping filmic, exposure -1.5 stops");
+    	assert_eq!(editor.run_command("color space xyz"), Err(CommandError::Usage("color space <srgb|acescg|rec2020>")));
+
+    	assert_eq!(editor.run_command("pin list").unwrap(), "The object has no pins");
+    	assert_eq!(editor.run_command("pin add 0.5 0.5 check the   overhang").unwrap(), "Added pin 1");
+    	assert_eq!(editor.run_command("pin list").unwrap(), "1: check the overhang");
+    	editor.run_command("pin go 1").unwrap();
+    	assert!(matches!(editor.run_command("pin go 2"), Err(CommandError::Failed(_))));
+    	assert_eq!(editor.run_command("pin add 0.5 0.5"), Err(CommandError::Usage("pin add <x> <y> <note>...")));
+    	editor.run_command("pin remove 1").unwrap();
+    	assert!(editor.get_pins().is_empty());
+
+    	editor.run_command("paint on").unwrap();
+    	assert!(editor.is_painting());
+    	editor.run_command("paint color 0 0.5 1").unwrap();
+    	assert_eq!(editor.get_paint_color(), [0.0, 0.5, 1.0, 1.0]);
+    	assert!(matches!(editor.run_command("paint color 2 0 0"), Err(CommandError::Failed(_))));
+    	assert!(matches!(editor.run_command("paint undo"), Err(CommandError::Failed(_))));
+
+    	editor.run_command("colors okabe-ito").unwrap();
+    	assert_eq!(editor.get_material_buffer().len(), 8);
+    	assert!(matches!(editor.run_command("colors rainbow"), Err(CommandError::Failed(_))));
+    	assert!(matches!(editor.run_command("colors import palette.txt"), Err(CommandError::Failed(_))));
+
+    	// the materials are checked against the palette
+    	editor.run_command("brush material 1 2 0.3").unwrap();
+    	assert_eq!(editor.get_brush().get_material(), MaterialMix { first: 1, second: 2, mix: 0.3 });
+    	editor.run_command("brush material 2").unwrap();
+    	assert!(!editor.get_brush().get_material().is_mixed());
+    	assert!(matches!(editor.run_command("brush material 1 2 1.5"), Err(CommandError::Failed(_))));
+    	assert!(matches!(editor.run_command("brush material 1 200 0.5"), Err(CommandError::Failed(_))));
+    	assert!(matches!(editor.run_command("brush material 1 2"), Err(CommandError::Usage(_))));
+    	assert!(matches!(editor.run_command("gradient z 1 2 3"), Err(CommandError::Failed(_))));
+    	editor.run_command("paint off").unwrap();
+    	editor.run_command("add 0.5 0.5").unwrap();
+    	editor.run_command("gradient z 1 2 3").unwrap();
+    	editor.run_command("gradient between 0.2 0.5 0.8 0.5 0 4").unwrap();
+    	assert!(matches!(editor.run_command("gradient w 1 2"), Err(CommandError::Usage(_))));
+    	assert!(matches!(editor.run_command("gradient x 1 200"), Err(CommandError::Failed(_))));
+    	editor.run_command("wear 0.5 2 3").unwrap();
+    	editor.run_command("wear 0.5 2 erode").unwrap();
+    	assert!(matches!(editor.run_command("wear 2 2 erode"), Err(CommandError::Failed(_))));
+    	assert!(matches!(editor.run_command("wear 0.5 2 chip"), Err(CommandError::Usage(_))));
+
+    	let path = std::env::temp_dir().join(format!("swirlix-commands-{}.swx", std::process::id()));
+    	editor.run_command(&format!("save \"{}\"", path.display())).unwrap();
+    	editor.take_save_request().unwrap().run(&Progress::new("")).unwrap();
+    	editor.run_command(&format!("open \"{}\"", path.display())).unwrap();
+    	editor.load_saved_chunks(usize::MAX).unwrap();
+    	assert_eq!(editor.get_scene().get_active_index(), 1);
+    	assert_eq!(editor.get_voxel_buffers(1), editor.get_voxel_buffers(0));
+    	editor.run_command("object delete").unwrap();
+    	std::fs::remove_file(&path).unwrap();
+    	assert!(matches!(editor.run_command("open nothing.swx"), Err(CommandError::Failed(_))));
+    	assert!(matches!(editor.run_command("save model.swx 30"), Err(CommandError::Failed(_))));
+
+    	editor.run_command("object add detail 64").unwrap();
+    	assert_eq!(editor.get_document().get_resolution(), 64);
+    	editor.run_command("object move 1 0 0").unwrap();
+    	editor.run_command("object scale 0.5").unwrap();
+    	assert_eq!(editor.get_object_transform().translation, vec3(1.0, 0.0, 0.0));
+    	assert!(matches!(editor.run_command("object add odd 100"), Err(CommandError::Failed(_))));
+    	editor.run_command("object duplicate").unwrap();
+    	assert_eq!(editor.run_command("object list").unwrap(), format!("{}, detail, {}", tr("object-sculpt"), tr_args("command-object-active", &[("name", "detail 2")])));
+    	assert_eq!(editor.get_object_transform().scale, 0.5);
+    	editor.run_command("object hide detail").unwrap();
+    	assert!(!editor.get_scene().is_shown(1));
+    	editor.run_command("object solo on").unwrap();
+    	assert!(editor.get_scene().is_shown(2) && !editor.get_scene().is_shown(0));
+    	editor.run_command("object solo off").unwrap();
+    	editor.run_command("object show detail").unwrap();
+    	assert!(editor.get_scene().is_shown(1));
+    	editor.run_command("object lock").unwrap();
+    	assert!(editor.get_scene().get_active().locked);
+    	editor.run_command("add 0.25 0.25").unwrap();
+    	assert!(!editor.get_document().is_solid(editor.screen_to_world(0.25, 0.25)));
+    	assert!(matches!(editor.run_command("object unlock nothing"), Err(CommandError::Failed(_))));
+    	editor.run_command("object unlock").unwrap();
+    	editor.run_command("object boolean detail subtract").unwrap();
+    	assert!(!editor.get_scene().is_shown(1));
+    	assert!(matches!(editor.run_command("object boolean \"detail 2\" union"), Err(CommandError::Failed(_))));
+    	assert_eq!(editor.run_command("object boolean detail xor"), Err(CommandError::Usage("object boolean <name> <union|subtract|intersect>")));
+    	editor.run_command("object select detail").unwrap();
+    	editor.run_command("object delete").unwrap();
+    	editor.run_command("object delete \"detail 2\"").unwrap();
+    	assert!(matches!(editor.run_command("object delete"), Err(CommandError::Failed(_))));
+    	assert!(matches!(editor.run_command("object select detail"), Err(CommandError::Failed(_))));
+
+    	editor.run_command("snap grid 0.5").unwrap();
+    	editor.run_command("snap angle 90").unwrap();
+    	editor.run_command("object move 1.2 0.1 0").unwrap();
+    	editor.run_command("object rotate 0 0 80").unwrap();
+    	assert_eq!(editor.get_object_transform().translation, vec3(1.0, 0.0, 0.0));
+    	assert!(editor.get_object_transform().rotation.abs_diff_eq(Quat::from_rotation_z(90f32.to_radians()), 1e-5));
+    	assert!(matches!(editor.run_command("snap angle 0"), Err(CommandError::Failed(_))));
+    	editor.run_command("snap grid off").unwrap();
+    	editor.run_command("snap angle off").unwrap();
+    	assert_eq!(editor.get_snapping(), Snapping::default());
+
+    	editor.run_command("instance add 2 0 0").unwrap();
+    	editor.run_command("instance scatter 5 3 7").unwrap();
+    	assert_eq!(editor.get_instances().len(), 6);
+    	assert_eq!(editor.get_instances()[0].translation, vec3(2.0, 0.0, 0.0));
+    	let center = editor.get_object_transform().translation;
+    	assert!(editor.get_instances()[1..].iter().all(|instance| (instance.translation - center).length() <= 3.0));
+    	assert!(matches!(editor.run_command("instance scatter 5 0"), Err(CommandError::Failed(_))));
+    	editor.run_command("instance clear").unwrap();
+    	assert!(editor.get_instances().is_empty());
+    }
+
+    #[test]
+    fn stamps_snap_to_the_voxel_grid() {
+    	let mut editor = Editor::default();
+    	let spacing = editor.get_document().get_chunk_size() / 4.0;
+    	let free = editor.get_stamp(0.3, 0.6).center;
+
+    	editor.run_command("snap voxels 2").unwrap();
+    	let snapped = editor.get_stamp(0.3, 0.6).center;
+    	assert_eq!(snapped, (free / spacing).round() * spacing);
+    	assert_eq!(editor.get_snap_grid(0.3, 0.6).len(), 14);
+    	editor.run_command("add 0.3 0.6").unwrap();
+    	assert!(editor.get_document().is_solid(snapped));
+
+    	let max = editor.get_document().get_resolution().trailing_zeros();
+    	assert!(matches!(editor.run_command(&format!("snap voxels {}", max + 1)), Err(CommandError::Failed(_))));
+    	editor.run_command("snap voxels off").unwrap();
+    	assert_eq!(editor.get_snap(), None);
+    	assert_eq!(editor.get_stamp(0.3, 0.6).center, free);
+    	assert!(editor.get_snap_grid(0.3, 0.6).is_empty());
+    }
+
+    #[test]
+    fn materials_scatter_light_under_their_surface() {
+    	let mut editor = Editor::default();
+    	editor.run_command("colors okabe-ito").unwrap();
+    	editor.run_command("material subsurface 1 0.7").unwrap();
+    	assert_eq!(editor.get_material_buffer()[1].subsurface, 0.7);
+    	assert_eq!(editor.get_material_buffer()[0].subsurface, 0.0);
+
+    	// recoloring the palette and undoing edits keep the scattering
+    	editor.add(0.5, 0.5);
+    	editor.run_command("colors default").unwrap();
+    	editor.run_command("colors okabe-ito").unwrap();
+    	editor.undo();
+    	assert_eq!(editor.get_material_buffer()[1].subsurface, 0.7);
+
+    	assert!(matches!(editor.run_command("material subsurface 1 1.5"), Err(CommandError::Failed(_))));
+    	assert!(matches!(editor.run_command("material subsurface 200 0.5"), Err(CommandError::Failed(_))));
+    	assert!(matches!(editor.run_command("material subsurface 1"), Err(CommandError::Usage(_))));
+    }
+
+    #[test]
+    fn lights_are_added_until_the_viewport_is_full() {
+    	let mut editor = Editor::default();
+    	assert_eq!(editor.run_command("light list").unwrap(), tr("command-light-none"));
+
+    	editor.run_command("light directional 0 0 -2 1.5").unwrap();
+    	editor.run_command("light point 1 2 3 4 1 0.5 0").unwrap();
+    	assert_eq!(editor.get_lights(), &[
+    		Light::new(LightKind::Directional(vec3(0.0, 0.0, -2.0)), 1.5),
+    		Light { kind: LightKind::Point(vec3(1.0, 2.0, 3.0)), color: [1.0, 0.5, 0.0], intensity: 4.0 },
+    	]);
+    	assert_eq!(editor.run_command("light list").unwrap(), "1: directional 0 0 -2 1.5 1 1 1\n2: point 1 2 3 4 1 0.5 0");
+
+    	assert!(matches!(editor.run_command("light directional 0 0 0 1"), Err(CommandError::Failed(_))));
+    	assert!(matches!(editor.run_command("light point 0 0 0 -1"), Err(CommandError::Failed(_))));
+    	assert!(matches!(editor.run_command("light point 0 0 0 1 2 0 0"), Err(CommandError::Failed(_))));
+    	assert!(matches!(editor.run_command("light point 0 0 0 1 1"), Err(CommandError::Usage(_))));
+    	for _ in 2..MAX_LIGHTS {
+    		editor.run_command("light point 0 0 0 1").unwrap();
+    	}
+    	assert!(matches!(editor.run_command("light point 0 0 0 1"), Err(CommandError::Failed(_))));
+    	assert_eq!(editor.get_lights().len(), MAX_LIGHTS);
+
+    	editor.run_command("light clear").unwrap();
+    	assert!(editor.get_lights().is_empty());
+    }
+
+    #[test]
+    fn interpolated_drags_count_as_one_stroke() {
+    	let mut editor = Editor::default();
+    	editor.set_stats_enabled(true);
+    	editor.run_command("macro record drag").unwrap();
+    	editor.run_command("brush size 0.05").unwrap();
+    	editor.begin_stroke();
+    	editor.add(0.3, 0.5);
+    	editor.add(0.7, 0.5);
+    	editor.end_stroke();
+    	editor.run_command("macro stop").unwrap();
+
+    	let between = editor.get_stamp(0.5, 0.5).center;
+    	assert!(editor.get_document().is_solid(between));
+    	assert_eq!(editor.get_document().get_stroke(), 1);
+    	let stats = editor.session_stats().unwrap();
+    	assert_eq!(stats.strokes, 1);
+    	assert_eq!(stats.tools.values().map(|tool| tool.strokes).sum::<u32>(), 1);
+
+    	// the macro keeps the two samples, and fills in between them again as it is played
+    	let recorded = editor.get_macro("drag").unwrap();
+    	let joined: Vec<bool> = recorded.steps.iter()
+    		.filter_map(|step| match step {
+    			MacroStep::Stroke { joined, .. } => Some(*joined),
+    			MacroStep::Command(_) => None,
+    		})
+    		.collect();
+    	assert_eq!(joined, vec![false, true]);
+    	editor.undo();
+    	assert!(!editor.get_document().is_solid(between));
+    	editor.run_command("macro play drag").unwrap();
+    	assert!(editor.get_document().is_solid(between));
+    	assert_eq!(editor.session_stats().unwrap().strokes, 2);
+    }
+
+    #[test]
+    fn macros_record_commands_and_strokes() {
+    	let mut editor = Editor::default();
+
+    	editor.run_command("macro record dot").unwrap();
+    	editor.run_command("brush square").unwrap();
+    	editor.run_command("add 0.25 0.25").unwrap();
+    	editor.run_command("macro stop").unwrap();
+
+    	let recorded = editor.get_macro("dot").unwrap();
+    	assert_eq!(recorded.steps.len(), 2);
+    	assert_eq!(recorded.steps[0], MacroStep::Command("brush square".to_owned()));
+    	assert!(matches!(recorded.steps[1], MacroStep::Stroke { remove: false, ref brush, .. } if brush == "square"));
+
+    	let target = editor.screen_to_world(0.75, 0.75);
+    	assert!(!editor.get_document().is_solid(target));
+    	editor.run_command("macro play dot 0.75 0.75 0.5").unwrap();
+    	assert!(editor.get_document().is_solid(target));
+    	// scaling the stroke leaves the brush as it was
+    	assert_eq!(editor.get_brush().get_size(), 0.1);
+
+    	assert!(matches!(editor.run_command("macro play nothing"), Err(CommandError::Failed(_))));
+    	assert!(matches!(editor.run_command("macro stop"), Err(CommandError::Failed(_))));
+    }
+
+    #[test]
+    fn files_changed_elsewhere_are_reloaded() {
+    	let path = std::env::temp_dir().join(format!("swirlix-reload-{}.swx", std::process::id()));
+    	let mut editor = Editor::default();
+    	assert!(matches!(editor.run_command("reload"), Err(CommandError::Failed(_))));
+
+    	editor.run_command(&format!("save \"{}\"", path.display())).unwrap();
+    	editor.take_save_request().unwrap().run(&Progress::new("")).unwrap();
+    	editor.acknowledge_save(&path);
+    	assert_eq!(editor.check_files(), vec![]);
+
+    	// another program writes to the file while the object has no edits of its own
+    	let mut other = Editor::default();
+    	other.add(0.5, 0.5);
+    	other.request_save(&path, 1);
+    	other.take_save_request().unwrap().run(&Progress::new("")).unwrap();
+    	let name = editor.get_scene().get_active().name.clone();
+    	assert_eq!(editor.check_files(), vec![FileChange::Reloaded(name.clone())]);
+    	editor.load_saved_chunks(usize::MAX).unwrap();
+    	assert_eq!(editor.get_voxel_buffers(0), other.get_voxel_buffers(0));
+
+    	// edits of its own are kept until a reload is asked for
+    	editor.add(0.25, 0.25);
+    	let edited = editor.get_voxel_buffers(0);
+    	other.remove(0.5, 0.5);
+    	other.request_save(&path, 1);
+    	other.take_save_request().unwrap().run(&Progress::new("")).unwrap();
+    	assert_eq!(editor.check_files(), vec![FileChange::Conflict(name)]);
+    	assert_eq!(editor.get_voxel_buffers(0), edited);
+    	editor.run_command("reload").unwrap();
+    	editor.load_saved_chunks(usize::MAX).unwrap();
+    	assert_eq!(editor.get_voxel_buffers(0), other.get_voxel_buffers(0));
+
+    	std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn versions_and_backups_are_saved_beside_the_file() {
+    	let folder = std::env::temp_dir().join(format!("swirlix-versions-{}", std::process::id()));
+    	let _ = std::fs::remove_dir_all(&folder);
+    	std::fs::create_dir_all(&folder).unwrap();
+    	let path = folder.join("model.swx");
+    	let mut editor = Editor::default();
+    	assert!(matches!(editor.run_command("save version"), Err(CommandError::Failed(_))));
+    	editor.run_command(&format!("save \"{}\"", path.display())).unwrap();
+    	editor.take_save_request().unwrap().run(&Progress::new("")).unwrap();
+    	assert!(editor.take_autosaves().is_empty());
+    	assert!(matches!(editor.run_command("versions"), Err(CommandError::Failed(_))));
+
+    	editor.add(0.5, 0.5);
+    	let added = editor.get_voxel_buffers(0);
+    	editor.run_command("save version").unwrap();
+    	editor.take_save_request().unwrap().run(&Progress::new("")).unwrap();
+
+    	// edits since the file was saved are backed up once
+    	let autosaves = editor.take_autosaves();
+    	assert_eq!(autosaves.len(), 1);
+    	autosaves[0].run(&Progress::new("")).unwrap();
+    	assert!(editor.take_autosaves().is_empty());
+    	assert!(folder.join("model.swx.1.bak").exists());
+
+    	editor.run_command("versions").unwrap();
+    	assert!(editor.take_version_picker_request());
+    	let versions = editor.get_versions().unwrap();
+    	assert_eq!(versions.len(), 1);
+    	editor.undo();
+    	assert_ne!(editor.get_voxel_buffers(0), added);
+    	editor.restore_version(&versions[0]).unwrap();
+    	editor.load_saved_chunks(usize::MAX).unwrap();
+    	assert_eq!(editor.get_voxel_buffers(0), added);
+    	// the restored version is an edit the file doesn't have yet
+    	assert_eq!(editor.take_autosaves().len(), 1);
+
+    	std::fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn conflicting_files_keep_the_chunks_they_were_opened_with() {
+    	let path = std::env::temp_dir().join(format!("swirlix-conflict-{}.swx", std::process::id()));
+    	let mut document = Document::new(16, 1.0);
+    	for position in [vec3(0.5, 0.5, 0.5), vec3(2.5, 0.5, 0.5)] {
+    		for chunk in document.get_chunks_in(Aabb::cube(position, 0.5), true) {
+    			chunk.subdivide(RoundBrushTip::filler(0.25, position), RoundBrushTip::container(0.25, position));
+    		}
+    	}
+    	document.save(&path).unwrap();
+
+    	let mut editor = Editor::default();
+    	let index = editor.open_document(&path).unwrap();
+    	let mut expected = Editor::default();
+    	let expected_index = expected.open_document(&path).unwrap();
+    	expected.load_saved_chunks(usize::MAX).unwrap();
+    	editor.add(0.5, 0.5);
+    	expected.add(0.5, 0.5);
+    	assert!(editor.get_document().get_saved_count() > 0);
+
+    	// another program rewrites the file in place while the object has edits of its own
+    	let other = path.with_extension("other.swx");
+    	Document::new(16, 1.0).save(&other).unwrap();
+    	std::fs::write(&path, std::fs::read(&other).unwrap()).unwrap();
+    	let name = editor.get_scene().get_active().name.clone();
+    	assert_eq!(editor.check_files(), vec![FileChange::Conflict(name)]);
+    	editor.load_saved_chunks(usize::MAX).unwrap();
+    	assert_eq!(editor.get_voxel_buffers(index), expected.get_voxel_buffers(expected_index));
+
+    	std::fs::remove_file(&path).unwrap();
+    	std::fs::remove_file(&other).unwrap();
+    }
+
+    #[test]
+    fn brush_slots_switch_by_number() {
+    	let mut editor = Editor::default();
+
+    	assert!(editor.select_brush_slot(4));
+    	assert_eq!(editor.get_brush_id(), "pen");
+    	editor.run_command("brush slot 7 spray").unwrap();
+    	assert!(editor.select_brush_slot(7));
+    	assert_eq!(editor.get_brush_id(), "spray");
+    	assert!(!editor.select_brush_slot(8));
+    	assert!(matches!(editor.run_command("brush slot 8 chisel"), Err(CommandError::Usage(_))));
+
+    	// the last brush toggles back and forth
+    	editor.run_command("brush last").unwrap();
+    	assert_eq!(editor.get_brush_id(), "pen");
+    	editor.toggle_last_brush();
+    	assert_eq!(editor.get_brush_id(), "spray");
+    	// picking the current brush again keeps the one to go back to
+    	assert!(editor.set_brush_by_name("spray"));
+    	editor.toggle_last_brush();
+    	assert_eq!(editor.get_brush_id(), "pen");
+    }
+
+    #[test]
+    fn suggestions_follow_the_typed_name() {
+    	let registry = CommandRegistry::default();
+
+    	let names: Vec<&str> = registry.suggest("brush s").iter().map(|command| command.name).collect();
+    	assert_eq!(names, vec!["brush slot", "brush size", "brush strength"]);
+    	let names: Vec<&str> = registry.suggest("view recall 2").iter().map(|command| command.name).collect();
+    	assert_eq!(names, vec!["view recall"]);
+    	// hidden commands run, but aren't offered
+    	assert!(registry.suggest("soa").is_empty());
+    	assert_eq!(registry.parse("soak 100 report.json").unwrap().0.name, "soak");
+    }
+}
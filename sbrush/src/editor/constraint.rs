@@ -0,0 +1,240 @@
+//! Strokes locked to a line or a plane while a modifier is held.
+//!
+//! Where a locked stroke starts is its anchor. From there on, each pass
+//! of the stroke lands on the point of the line or plane nearest to the
+//! view ray under the cursor, instead of wherever the cursor maps onto
+//! the sculpt, which keeps the edges of hard surfaces straight and flat.
+
+use super::Editor;
+
+use glam::Vec3;
+
+/// What strokes are locked to while the lock is held.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StrokeLock {
+	/// The world X axis through the anchor.
+	#[default]
+	X,
+	/// The world Y axis through the anchor.
+	Y,
+	/// The world Z axis through the anchor.
+	Z,
+	/// The surface normal at the anchor, to pull out or push in straight.
+	Normal,
+	/// The stroke plane, or the plane along the surface at the anchor without one.
+	Plane,
+	/// The nearest of the guide curves of the active object.
+	Guide,
+}
+
+impl StrokeLock {
+	/// Get a lock by its name in commands.
+	pub fn from_name(name: &str) -> Option<Self> {
+		match name {
+			"x" => Some(StrokeLock::X),
+			"y" => Some(StrokeLock::Y),
+			"z" => Some(StrokeLock::Z),
+			"normal" => Some(StrokeLock::Normal),
+			"plane" => Some(StrokeLock::Plane),
+			"guide" => Some(StrokeLock::Guide),
+			_ => None,
+		}
+	}
+}
+
+/// A plane in the world, which locked strokes can be kept on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StrokePlane {
+	pub point: Vec3,
+	/// The unit normal of the plane.
+	pub normal: Vec3,
+}
+
+/// Where a locked stroke started, in the world.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StrokeAnchor {
+	pub position: Vec3,
+	/// The unit normal of the surface under the anchor, or toward the view off the surface.
+	pub normal: Vec3,
+}
+
+impl Editor {
+	/// Set what strokes are locked to while the lock is held.
+	pub fn set_stroke_lock(&mut self, lock: StrokeLock) {
+		self.stroke_lock = lock;
+		self.stroke_anchor = None;
+	}
+
+	/// Get what strokes are locked to while the lock is held.
+	pub fn get_stroke_lock(&self) -> StrokeLock {
+		self.stroke_lock
+	}
+
+	/// Hold or let go of the lock, like while a modifier is held down.
+	///
+	/// The next pass after taking hold of it anchors the lock.
+	pub fn set_stroke_locked(&mut self, locked: bool) {
+		self.stroke_locked = locked;
+		if !locked {
+			self.stroke_anchor = None;
+		}
+	}
+
+	/// Check whether strokes are locked.
+	pub fn is_stroke_locked(&self) -> bool {
+		self.stroke_locked
+	}
+
+	/// Get where the locked stroke started, if it has.
+	pub fn get_stroke_anchor(&self) -> Option<StrokeAnchor> {
+		self.stroke_anchor
+	}
+
+	/// Set the plane strokes locked to a plane are kept on, or clear it with None.
+	pub fn set_stroke_plane(&mut self, plane: Option<StrokePlane>) {
+		self.stroke_plane = plane.map(|plane| StrokePlane { normal: plane.normal.normalize(), ..plane });
+	}
+
+	/// Get the plane strokes locked to a plane are kept on, if set.
+	pub fn get_stroke_plane(&self) -> Option<StrokePlane> {
+		self.stroke_plane
+	}
+
+	/// Set the stroke plane along the surface of the active object under a point of the screen, returning it.
+	///
+	/// Where there is no surface under the point, the plane faces the view where a stroke would land.
+	pub fn place_stroke_plane(&mut self, x: f32, y: f32) -> StrokePlane {
+		let (point, normal) = self.surface_under(x, y);
+		let plane = StrokePlane { point, normal };
+		self.stroke_plane = Some(plane);
+
+		plane
+	}
+
+	/// Get where passes of a stroke at a point of the screen land, kept to the lock while it is held.
+	pub(super) fn locked_positions(&mut self, x: f32, y: f32) -> Vec<Vec3> {
+		if !self.stroke_locked {
+			return self.stroke_positions(x, y);
+		}
+		let anchor = match self.stroke_anchor {
+			Some(anchor) => anchor,
+			None => {
+				let (_, normal) = self.surface_under(x, y);
+				let anchor = StrokeAnchor { position: self.stroke_positions(x, y)[0], normal };
+				self.stroke_anchor = Some(anchor);
+				anchor
+			},
+		};
+
+		vec![self.lock_position(anchor, x, y)]
+	}
+
+	/// Get the point of the line, plane or guide of the lock nearest to the view ray through a point of the screen.
+	pub(super) fn lock_position(&self, anchor: StrokeAnchor, x: f32, y: f32) -> Vec3 {
+		let (origin, direction) = self.screen_ray(x, y);
+		let axis = match self.stroke_lock {
+			StrokeLock::X => Vec3::X,
+			StrokeLock::Y => Vec3::Y,
+			StrokeLock::Z => Vec3::Z,
+			StrokeLock::Normal => anchor.normal,
+			StrokeLock::Plane => {
+				let plane = self.stroke_plane.unwrap_or(StrokePlane { point: anchor.position, normal: anchor.normal });
+				return nearest_on_plane(plane, origin, direction).unwrap_or_else(|| {
+					// seen edge on, the plane is met where the cursor maps onto the sculpt
+					let position = self.brush_position(x, y);
+					position - plane.normal * (position - plane.point).dot(plane.normal)
+				});
+			},
+			// without guides, the stroke goes where it would unlocked
+			StrokeLock::Guide => return self.nearest_on_guides(origin, direction).unwrap_or_else(|| self.brush_position(x, y)),
+		};
+
+		nearest_on_line(anchor.position, axis, origin, direction)
+	}
+
+	/// Get the surface of the active object under a point of the screen and its normal, in the world.
+	///
+	/// Where there is none, this is where a stroke would land, facing the view.
+	fn surface_under(&self, x: f32, y: f32) -> (Vec3, Vec3) {
+		let (origin, direction) = self.screen_ray(x, y);
+		let transform = self.get_object_transform();
+
+		match self.get_document().raycast(transform.to_local(origin), transform.to_local_vector(direction)) {
+			Some(hit) => (transform.to_world(hit.position), transform.to_world_vector(hit.normal).normalize()),
+			None => (self.brush_position(x, y), -direction.normalize()),
+		}
+	}
+}
+
+/// Get the point of a line nearest to a ray.
+///
+/// A ray running along the line meets it where the line starts.
+pub(super) fn nearest_on_line(point: Vec3, axis: Vec3, origin: Vec3, direction: Vec3) -> Vec3 {
+	let offset = point - origin;
+	let along = axis.dot(direction);
+	let length = direction.length_squared();
+	let denominator = axis.length_squared() * length - along * along;
+	if denominator.abs() <= f32::EPSILON * length {
+		return point;
+	}
+
+	point + axis * (along * direction.dot(offset) - length * axis.dot(offset)) / denominator
+}
+
+/// Get where a ray meets a plane, unless it runs along it.
+fn nearest_on_plane(plane: StrokePlane, origin: Vec3, direction: Vec3) -> Option<Vec3> {
+	let facing = direction.dot(plane.normal);
+	if facing.abs() <= 1e-4 * direction.length() {
+		return None;
+	}
+
+	Some(origin + direction * (plane.point - origin).dot(plane.normal) / facing)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    #[test]
+    fn locked_strokes_stay_on_their_line_or_plane() {
+    	let mut editor = Editor::default();
+    	editor.set_stroke_locked(true);
+    	let start = editor.locked_positions(0.25, 0.5)[0];
+    	// the front view looks down z, so an x lock keeps y and z
+    	let moved = editor.locked_positions(0.75, 0.9)[0];
+    	assert!((moved - start).y.abs() < 1e-5 && (moved - start).z.abs() < 1e-5);
+    	assert!(moved.x > start.x);
+
+    	// letting go of the lock starts a new anchor
+    	editor.set_stroke_locked(false);
+    	assert_eq!(editor.get_stroke_anchor(), None);
+    	editor.set_stroke_lock(StrokeLock::Plane);
+    	editor.set_stroke_plane(Some(StrokePlane { point: Vec3::splat(0.5), normal: Vec3::Y * 2.0 }));
+    	editor.set_stroke_locked(true);
+    	let on_plane = editor.locked_positions(0.3, 0.5)[0];
+    	// the view runs along the plane, so the cursor is dropped onto it
+    	assert!((on_plane.y - 0.5).abs() < 1e-5);
+
+    	let line = nearest_on_line(Vec3::ZERO, Vec3::X, Vec3::new(2.0, 1.0, -5.0), Vec3::Z);
+    	assert!(line.distance(Vec3::new(2.0, 0.0, 0.0)) < 1e-5);
+    	assert_eq!(nearest_on_line(Vec3::ONE, Vec3::Z, Vec3::ZERO, Vec3::Z), Vec3::ONE);
+    }
+
+    #[test]
+    fn normal_locks_pull_straight_out_of_the_surface() {
+    	let mut editor = Editor::default();
+    	editor.add(0.5, 0.5);
+    	editor.set_stroke_lock(StrokeLock::Normal);
+    	editor.set_stroke_locked(true);
+    	editor.locked_positions(0.5, 0.5);
+
+    	// the front of the stroke faces the view
+    	let anchor = editor.get_stroke_anchor().unwrap();
+    	assert!(anchor.normal.distance(Vec3::NEG_Z) < 1e-5);
+    	let plane = editor.place_stroke_plane(0.5, 0.5);
+    	assert!(plane.point.z < 0.5 && plane.normal.distance(Vec3::NEG_Z) < 1e-5);
+    	// looking straight along the normal, the stroke stays on the anchor
+    	let position = editor.locked_positions(0.7, 0.2)[0];
+    	assert!((position - anchor.position).truncate().length() < 1e-5);
+    }
+}
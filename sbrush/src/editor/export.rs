@@ -0,0 +1,492 @@
+//! Exports of scene objects for other programs, as STL, OBJ, binary glTF and vox files.
+//!
+//! An export filter picks the objects to write, the whole shown scene by
+//! default, and every format writes the same objects. Each object is
+//! written at every place it is shown, in world space, with its chunks
+//! still in its file read first. STL, OBJ and glTF get the voxel surface
+//! as a mesh, while MagicaVoxel's vox format gets the voxels themselves.
+
+use super::Editor;
+use crate::color::linear_to_srgb;
+use crate::error::Error;
+use crate::scene::ExportFilter;
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use glam::{IVec3, Vec3};
+use serde_json::{Value, json};
+
+/// The most voxels a vox model holds along each axis, so bigger exports are split into several models.
+const VOX_MODEL_SIZE: i32 = 256;
+
+/// The most colors a vox palette holds, after the empty index 0.
+const VOX_COLORS: usize = 255;
+
+/// The surface of an exported object, in world space at every place it is shown.
+struct Surface {
+	name: String,
+	quads: Vec<[Vec3; 4]>,
+}
+
+impl Editor {
+	/// Write the voxel surface of the objects an export filter picks to a binary STL file, returning how many triangles it has.
+	pub fn export_stl(&mut self, path: impl AsRef<Path>, filter: &ExportFilter) -> Result<usize, Error> {
+		let triangles: Vec<[Vec3; 3]> = self.export_surfaces(filter)?
+			.iter()
+			.flat_map(|surface| surface.quads.iter())
+			.flat_map(|[a, b, c, d]| [[*a, *b, *c], [*a, *c, *d]])
+			.collect();
+
+		// an 80 byte header, which readers ignore, and the count of triangles
+		let mut bytes = vec![0; 80];
+		bytes.extend_from_slice(&(triangles.len() as u32).to_le_bytes());
+		for [a, b, c] in &triangles {
+			let normal = (*b - *a).cross(*c - *a).normalize_or_zero();
+			for component in [normal, *a, *b, *c].iter().flat_map(|vector| vector.to_array()) {
+				bytes.extend_from_slice(&component.to_le_bytes());
+			}
+			bytes.extend_from_slice(&0u16.to_le_bytes());
+		}
+		fs::write(path, bytes)?;
+
+		Ok(triangles.len())
+	}
+
+	/// Write the voxel surface of the objects an export filter picks to a Wavefront OBJ file, as an object of quads for each, returning how many quads it has.
+	pub fn export_obj(&mut self, path: impl AsRef<Path>, filter: &ExportFilter) -> Result<usize, Error> {
+		let surfaces = self.export_surfaces(filter)?;
+
+		let mut text = String::new();
+		let mut vertices = HashMap::new();
+		let mut normals = HashMap::new();
+		let mut count = 0;
+		for surface in &surfaces {
+			text.push_str(&format!("o {}\n", surface.name));
+			for quad in &surface.quads {
+				let [a, b, c, _] = *quad;
+				let normal = index_of(&mut normals, &mut text, "vn", (b - a).cross(c - a).normalize_or_zero());
+				let corners = quad.map(|corner| index_of(&mut vertices, &mut text, "v", corner));
+				text.push_str(&format!("f {}//{normal} {}//{normal} {}//{normal} {}//{normal}\n", corners[0], corners[1], corners[2], corners[3]));
+			}
+			count += surface.quads.len();
+		}
+		fs::write(path, text)?;
+
+		Ok(count)
+	}
+
+	/// Write the voxel surface of the objects an export filter picks to a binary glTF file, as a mesh and a node for each, returning how many triangles it has.
+	pub fn export_gltf(&mut self, path: impl AsRef<Path>, filter: &ExportFilter) -> Result<usize, Error> {
+		let surfaces = self.export_surfaces(filter)?;
+
+		let mut buffer = Vec::new();
+		let mut views = Vec::new();
+		let mut accessors = Vec::new();
+		let mut meshes = Vec::new();
+		let mut count = 0;
+		for surface in surfaces.iter().filter(|surface| !surface.quads.is_empty()) {
+			// every quad has corners of its own, so its normal stays flat
+			let positions: Vec<[f32; 3]> = surface.quads.iter().flatten().map(|corner| corner.to_array()).collect();
+			let normals: Vec<[f32; 3]> = surface.quads.iter()
+				.flat_map(|[a, b, c, _]| [(*b - *a).cross(*c - *a).normalize_or_zero().to_array(); 4])
+				.collect();
+			let indices: Vec<u32> = (0..surface.quads.len() as u32)
+				.flat_map(|quad| [0, 1, 2, 0, 2, 3].map(|corner| quad * 4 + corner))
+				.collect();
+			let min = positions.iter().fold(Vec3::INFINITY, |min, position| min.min(Vec3::from(*position)));
+			let max = positions.iter().fold(Vec3::NEG_INFINITY, |max, position| max.max(Vec3::from(*position)));
+
+			let position = add_accessor(&mut buffer, &mut views, &mut accessors, bytemuck::cast_slice(&positions), json!({
+				"componentType": GLTF_FLOAT, "count": positions.len(), "type": "VEC3", "min": min.to_array(), "max": max.to_array(),
+			}), GLTF_VERTICES);
+			let normal = add_accessor(&mut buffer, &mut views, &mut accessors, bytemuck::cast_slice(&normals), json!({
+				"componentType": GLTF_FLOAT, "count": normals.len(), "type": "VEC3",
+			}), GLTF_VERTICES);
+			let index = add_accessor(&mut buffer, &mut views, &mut accessors, bytemuck::cast_slice(&indices), json!({
+				"componentType": GLTF_UNSIGNED_INT, "count": indices.len(), "type": "SCALAR",
+			}), GLTF_INDICES);
+			meshes.push(json!({
+				"name": surface.name,
+				"primitives": [{ "attributes": { "POSITION": position, "NORMAL": normal }, "indices": index }],
+			}));
+			count += indices.len() / 3;
+		}
+
+		let nodes: Vec<Value> = meshes.iter().enumerate().map(|(index, mesh)| json!({ "name": mesh["name"], "mesh": index })).collect();
+		let mut gltf = json!({
+			"asset": { "version": "2.0", "generator": "Swirlix" },
+			"scene": 0,
+			"scenes": [{ "nodes": (0..nodes.len()).collect::<Vec<_>>() }],
+			"nodes": nodes,
+			"meshes": meshes,
+			"accessors": accessors,
+			"bufferViews": views,
+			"buffers": [{ "byteLength": buffer.len() }],
+		});
+		// glTF doesn't allow empty lists or buffers, which an export of nothing would have
+		if buffer.is_empty() {
+			gltf["scenes"] = json!([{}]);
+			if let Some(gltf) = gltf.as_object_mut() {
+				gltf.retain(|key, _| !matches!(key.as_str(), "nodes" | "meshes" | "accessors" | "bufferViews" | "buffers"));
+			}
+		}
+
+		// the JSON is padded with spaces and the buffer with zeros, to keep the chunks aligned
+		let mut json = serde_json::to_vec(&gltf).map_err(std::io::Error::from)?;
+		json.resize(json.len().next_multiple_of(4), b' ');
+		buffer.resize(buffer.len().next_multiple_of(4), 0);
+		let mut bytes = Vec::new();
+		bytes.extend_from_slice(b"glTF");
+		bytes.extend_from_slice(&2u32.to_le_bytes());
+		let length = 12 + 8 + json.len() + if buffer.is_empty() { 0 } else { 8 + buffer.len() };
+		bytes.extend_from_slice(&(length as u32).to_le_bytes());
+		bytes.extend_from_slice(&(json.len() as u32).to_le_bytes());
+		bytes.extend_from_slice(b"JSON");
+		bytes.extend_from_slice(&json);
+		if !buffer.is_empty() {
+			bytes.extend_from_slice(&(buffer.len() as u32).to_le_bytes());
+			bytes.extend_from_slice(b"BIN\0");
+			bytes.extend_from_slice(&buffer);
+		}
+		fs::write(path, bytes)?;
+
+		Ok(count)
+	}
+
+	/// Write the voxels of the objects an export filter picks to a MagicaVoxel vox file, returning how many voxels it has.
+	///
+	/// The voxels are laid on a grid as fine as the finest voxels exported,
+	/// by their centers, so turned objects can leave gaps. Vox files hold
+	/// 255 colors, so colors beyond those take the closest one.
+	pub fn export_vox(&mut self, path: impl AsRef<Path>, filter: &ExportFilter) -> Result<usize, Error> {
+		let objects = self.load_exported(filter)?;
+		let objects: Vec<_> = objects.into_iter().filter_map(|index| self.scene.get_object(index)).collect();
+		let spacing = objects.iter()
+			.flat_map(|object| object.placements().map(|placement| object.document.get_voxel_size() * placement.scale))
+			.fold(f32::INFINITY, f32::min);
+
+		let mut colors = Vec::new();
+		let mut voxels = HashMap::new();
+		for object in &objects {
+			let document = &object.document;
+			let to_srgb = document.get_color_management().working_space.to_linear_srgb();
+			let palette: Vec<u8> = document.get_palette().iter()
+				.map(|color| {
+					let color = (to_srgb * Vec3::new(color[0], color[1], color[2])).clamp(Vec3::ZERO, Vec3::ONE);
+					color_index(&mut colors, color.to_array().map(|channel| (linear_to_srgb(channel) * 255.0).round() as u8))
+				})
+				.collect();
+			let cells = document.get_solid_cells();
+			for placement in object.placements() {
+				// coarser voxels cover a block of the finer ones
+				let size = (document.get_voxel_size() * placement.scale / spacing).round().max(1.0) as i32;
+				for (cell, material) in &cells {
+					let center = placement.to_world((cell.as_vec3() + 0.5) * document.get_voxel_size());
+					// vox files point z up
+					let low = (Vec3::new(center.x, -center.z, center.y) / spacing - size as f32 * 0.5).round().as_ivec3();
+					let color = palette.get(*material as usize).copied().unwrap_or(1);
+					for z in 0..size {
+						for y in 0..size {
+							for x in 0..size {
+								voxels.insert(low + IVec3::new(x, y, z), color);
+							}
+						}
+					}
+				}
+			}
+		}
+		fs::write(path, write_vox(&voxels, &colors))?;
+
+		Ok(voxels.len())
+	}
+
+	/// Pick the objects an export filter selects, reading the chunks still in their files.
+	fn load_exported(&mut self, filter: &ExportFilter) -> Result<Vec<usize>, Error> {
+		let objects = self.scene.select(filter).map_err(Error::UnknownObject)?;
+		for index in &objects {
+			if let Some(object) = self.scene.get_object_mut(*index) {
+				let saved = object.document.get_saved_count();
+				object.document.load_nearest(Vec3::ZERO, saved)?;
+			}
+		}
+
+		Ok(objects)
+	}
+
+	/// Get the surfaces of the objects an export filter picks, at every place each is shown.
+	fn export_surfaces(&mut self, filter: &ExportFilter) -> Result<Vec<Surface>, Error> {
+		let objects = self.load_exported(filter)?;
+
+		Ok(objects.into_iter()
+			.filter_map(|index| self.scene.get_object(index))
+			.map(|object| {
+				let quads = object.document.get_surface_quads();
+				Surface {
+					name: object.name.clone(),
+					quads: object.placements()
+						.flat_map(|placement| quads.iter().map(move |quad| quad.map(|corner| placement.to_world(corner))))
+						.collect(),
+				}
+			})
+			.collect())
+	}
+}
+
+/// The glTF component type of 32 bit floats.
+const GLTF_FLOAT: u32 = 5126;
+/// The glTF component type of 32 bit unsigned integers.
+const GLTF_UNSIGNED_INT: u32 = 5125;
+/// The glTF buffer target of vertex attributes.
+const GLTF_VERTICES: u32 = 34962;
+/// The glTF buffer target of indices.
+const GLTF_INDICES: u32 = 34963;
+
+/// Get the OBJ index of a vertex or normal, writing it the first time it is seen.
+fn index_of(indices: &mut HashMap<[u32; 3], usize>, text: &mut String, keyword: &str, vector: Vec3) -> usize {
+	let count = indices.len();
+	*indices.entry(vector.to_array().map(f32::to_bits)).or_insert_with(|| {
+		text.push_str(&format!("{keyword} {} {} {}\n", vector.x, vector.y, vector.z));
+		// OBJ counts from 1
+		count + 1
+	})
+}
+
+/// Add data to the glTF buffer, with a view of it and an accessor reading it, returning the index of the accessor.
+fn add_accessor(buffer: &mut Vec<u8>, views: &mut Vec<Value>, accessors: &mut Vec<Value>, data: &[u8], mut accessor: Value, target: u32) -> usize {
+	views.push(json!({ "buffer": 0, "byteOffset": buffer.len(), "byteLength": data.len(), "target": target }));
+	buffer.extend_from_slice(data);
+	accessor["bufferView"] = json!(views.len() - 1);
+	accessors.push(accessor);
+
+	accessors.len() - 1
+}
+
+/// Get the vox palette index of a color, adding it while the palette has room, or taking the closest color once it is full.
+fn color_index(colors: &mut Vec<[u8; 3]>, color: [u8; 3]) -> u8 {
+	let index = match colors.iter().position(|known| *known == color) {
+		Some(index) => index,
+		None if colors.len() < VOX_COLORS => {
+			colors.push(color);
+			colors.len() - 1
+		}
+		None => {
+			let distance = |known: &[u8; 3]| known.iter().zip(color).map(|(a, b)| (*a as i32 - b as i32).pow(2)).sum::<i32>();
+			(0..colors.len()).min_by_key(|index| distance(&colors[*index])).unwrap_or(0)
+		}
+	};
+
+	// index 0 is empty space
+	index as u8 + 1
+}
+
+/// Encode voxels, keyed by their position with z up, and their palette as a vox file.
+///
+/// Vox models are at most 256 voxels across, so the voxels are split into
+/// models, which a scene graph of a group with a transform for each model
+/// puts back into place.
+fn write_vox(voxels: &HashMap<IVec3, u8>, colors: &[[u8; 3]]) -> Vec<u8> {
+	let start = voxels.keys().copied().reduce(IVec3::min).unwrap_or(IVec3::ZERO);
+	let mut models: BTreeMap<[i32; 3], Vec<(IVec3, u8)>> = BTreeMap::new();
+	for (position, color) in voxels {
+		models.entry(((*position - start) / VOX_MODEL_SIZE).to_array()).or_default().push((*position, *color));
+	}
+	// readers expect a model, even an empty one
+	if models.is_empty() {
+		models.insert([0; 3], Vec::new());
+	}
+
+	let mut children = Vec::new();
+	let mut places = Vec::new();
+	for voxels in models.values_mut() {
+		voxels.sort_by_key(|(position, _)| position.to_array());
+		let low = voxels.iter().map(|(position, _)| *position).reduce(IVec3::min).unwrap_or(IVec3::ZERO);
+		let high = voxels.iter().map(|(position, _)| *position + 1).reduce(IVec3::max).unwrap_or(IVec3::ONE);
+		let size = high - low;
+
+		write_vox_chunk(&mut children, b"SIZE", &ints(&size.to_array()), &[]);
+		let mut content = ints(&[voxels.len() as i32]);
+		for (position, color) in voxels.iter() {
+			let [x, y, z] = (*position - low).to_array().map(|coordinate| coordinate as u8);
+			content.extend_from_slice(&[x, y, z, *color]);
+		}
+		write_vox_chunk(&mut children, b"XYZI", &content, &[]);
+		// a model is placed by its middle, rounded down
+		places.push(low + size / 2);
+	}
+
+	// a root transform holding a group, holding a transform and a shape for each model
+	let mut content = ints(&[0]);
+	write_vox_dict(&mut content, &[]);
+	content.extend_from_slice(&ints(&[1, -1, -1, 1]));
+	write_vox_dict(&mut content, &[]);
+	write_vox_chunk(&mut children, b"nTRN", &content, &[]);
+	let mut content = ints(&[1]);
+	write_vox_dict(&mut content, &[]);
+	content.extend_from_slice(&ints(&[places.len() as i32]));
+	content.extend_from_slice(&ints(&(0..places.len() as i32).map(|model| 2 + model * 2).collect::<Vec<_>>()));
+	write_vox_chunk(&mut children, b"nGRP", &content, &[]);
+	for (model, place) in places.iter().enumerate() {
+		let node = 2 + model as i32 * 2;
+		let mut content = ints(&[node]);
+		write_vox_dict(&mut content, &[]);
+		content.extend_from_slice(&ints(&[node + 1, -1, 0, 1]));
+		write_vox_dict(&mut content, &[("_t", &format!("{} {} {}", place.x, place.y, place.z))]);
+		write_vox_chunk(&mut children, b"nTRN", &content, &[]);
+		let mut content = ints(&[node + 1]);
+		write_vox_dict(&mut content, &[]);
+		content.extend_from_slice(&ints(&[1, model as i32]));
+		write_vox_dict(&mut content, &[]);
+		write_vox_chunk(&mut children, b"nSHP", &content, &[]);
+	}
+
+	// the palette starts at index 1, and the entries past the colors are unused
+	let mut palette = vec![0; 256 * 4];
+	for (entry, [r, g, b]) in palette.chunks_exact_mut(4).zip(colors) {
+		entry.copy_from_slice(&[*r, *g, *b, 255]);
+	}
+	write_vox_chunk(&mut children, b"RGBA", &palette, &[]);
+
+	let mut bytes = Vec::new();
+	bytes.extend_from_slice(b"VOX ");
+	bytes.extend_from_slice(&150i32.to_le_bytes());
+	write_vox_chunk(&mut bytes, b"MAIN", &[], &children);
+
+	bytes
+}
+
+/// Write a vox chunk, with the sizes of its content and its children in front.
+fn write_vox_chunk(bytes: &mut Vec<u8>, id: &[u8; 4], content: &[u8], children: &[u8]) {
+	bytes.extend_from_slice(id);
+	bytes.extend_from_slice(&ints(&[content.len() as i32, children.len() as i32]));
+	bytes.extend_from_slice(content);
+	bytes.extend_from_slice(children);
+}
+
+/// Write a vox dictionary of strings.
+fn write_vox_dict(bytes: &mut Vec<u8>, entries: &[(&str, &str)]) {
+	bytes.extend_from_slice(&ints(&[entries.len() as i32]));
+	for text in entries.iter().flat_map(|(key, value)| [key, value]) {
+		bytes.extend_from_slice(&ints(&[text.len() as i32]));
+		bytes.extend_from_slice(text.as_bytes());
+	}
+}
+
+/// Encode integers as little endian bytes.
+fn ints(values: &[i32]) -> Vec<u8> {
+	values.iter().flat_map(|value| value.to_le_bytes()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    #[test]
+    fn exports_write_the_objects_the_filter_picks() {
+    	let path = std::env::temp_dir().join(format!("swirlix-export-{}.stl", std::process::id()));
+    	let mut editor = Editor::default();
+    	editor.add_object("Rock", 64);
+    	assert_eq!(editor.export_stl(&path, &ExportFilter::Scene).unwrap(), 0);
+
+    	editor.add(0.5, 0.5);
+    	let active = editor.export_stl(&path, &ExportFilter::Active).unwrap();
+    	assert!(active > 0);
+    	let bytes = fs::read(&path).unwrap();
+    	assert_eq!(bytes.len(), 84 + active * 50);
+    	assert_eq!(u32::from_le_bytes(bytes[80..84].try_into().unwrap()) as usize, active);
+
+    	// the duplicate is hidden, so only naming it exports it along with the scene
+    	editor.run_command("object duplicate").unwrap();
+    	editor.run_command("object hide").unwrap();
+    	let name = editor.get_scene().get_active().name.clone();
+    	assert_eq!(editor.export_stl(&path, &ExportFilter::Scene).unwrap(), active);
+    	assert_eq!(editor.export_stl(&path, &ExportFilter::Named(vec!["Rock".to_owned(), name])).unwrap(), active * 2);
+    	assert!(matches!(editor.export_stl(&path, &ExportFilter::Named(vec!["Cube".to_owned()])), Err(Error::UnknownObject(_))));
+
+    	fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn every_format_exports_the_same_objects() {
+    	let path = std::env::temp_dir().join(format!("swirlix-export-formats-{}", std::process::id()));
+    	let mut editor = Editor::default();
+    	editor.add_object("Rock", 64);
+    	editor.add(0.5, 0.5);
+    	let triangles = editor.export_stl(&path, &ExportFilter::Active).unwrap();
+    	editor.run_command("object duplicate").unwrap();
+    	let name = editor.get_scene().get_active().name.clone();
+    	let filter = ExportFilter::Named(vec!["Rock".to_owned(), name.clone()]);
+
+    	// an object of quads for each, sharing the corners they meet at
+    	assert_eq!(editor.export_obj(&path, &filter).unwrap(), triangles);
+    	let text = fs::read_to_string(&path).unwrap();
+    	assert_eq!(text.lines().filter(|line| line.starts_with("o ")).collect::<Vec<_>>(), ["o Rock".to_owned(), format!("o {name}")]);
+    	assert_eq!(text.lines().filter(|line| line.starts_with("f ")).count(), triangles);
+    	assert!(text.lines().filter(|line| line.starts_with("v ")).count() < triangles * 2);
+
+    	// a mesh for each, with the chunks of a binary glTF file in order
+    	assert_eq!(editor.export_gltf(&path, &filter).unwrap(), triangles * 2);
+    	let bytes = fs::read(&path).unwrap();
+    	assert_eq!(&bytes[0..4], b"glTF");
+    	assert_eq!(u32::from_le_bytes(bytes[8..12].try_into().unwrap()) as usize, bytes.len());
+    	let length = u32::from_le_bytes(bytes[12..16].try_into().unwrap()) as usize;
+    	let gltf: Value = serde_json::from_slice(&bytes[20..20 + length]).unwrap();
+    	assert_eq!(gltf["meshes"].as_array().map(Vec::len), Some(2));
+    	assert_eq!(&bytes[20 + length + 4..20 + length + 8], b"BIN\0");
+    	assert_eq!(editor.export_gltf(&path, &ExportFilter::Named(Vec::new())).unwrap(), 0);
+    	let bytes = fs::read(&path).unwrap();
+    	assert!(serde_json::from_slice::<Value>(&bytes[20..]).unwrap().get("buffers").is_none());
+
+    	// the duplicate is in the same place, so its voxels fall onto the same ones
+    	let voxels = editor.export_vox(&path, &ExportFilter::Active).unwrap();
+    	assert!(voxels > 0);
+    	assert_eq!(editor.export_vox(&path, &filter).unwrap(), voxels);
+    	let bytes = fs::read(&path).unwrap();
+    	assert_eq!(&bytes[0..4], b"VOX ");
+    	assert_eq!(&bytes[8..12], b"MAIN");
+    	assert_eq!(20 + u32::from_le_bytes(bytes[16..20].try_into().unwrap()) as usize, bytes.len());
+    	let mut written = 0;
+    	let mut chunk = 20;
+    	while chunk < bytes.len() {
+    		let int = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap()) as usize;
+    		if &bytes[chunk..chunk + 4] == b"XYZI" {
+    			written += int(chunk + 12);
+    		}
+    		chunk += 12 + int(chunk + 4) + int(chunk + 8);
+    	}
+    	assert_eq!(written, voxels);
+
+    	fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn vox_models_are_split_and_placed() {
+    	let voxels = HashMap::from([(IVec3::new(0, 0, 0), 1), (IVec3::new(300, 0, 0), 2), (IVec3::new(-1, 2, 3), 1)]);
+    	let bytes = write_vox(&voxels, &[[255, 0, 0], [0, 0, 255]]);
+    	let count = |id: &[u8]| bytes.windows(4).filter(|window| *window == id).count();
+
+    	// the voxel past 256 across gets a model of its own
+    	assert_eq!(count(b"SIZE"), 2);
+    	assert_eq!(count(b"nSHP"), 2);
+    	assert_eq!(count(b"nGRP"), 1);
+    	assert_eq!(count(b"nTRN"), 3);
+    	// each is placed by its middle, rounded down
+    	for place in [b"0 1 2".as_slice(), b"300 0 0"] {
+    		assert!(bytes.windows(place.len()).any(|window| window == place));
+    	}
+    	assert!(bytes.ends_with(&[0; 4 * 254]));
+    }
+
+    #[test]
+    fn full_vox_palettes_take_the_closest_color() {
+    	let mut colors = Vec::new();
+    	assert_eq!(color_index(&mut colors, [10, 0, 0]), 1);
+    	assert_eq!(color_index(&mut colors, [10, 0, 0]), 1);
+    	for value in 1..VOX_COLORS as u8 {
+    		color_index(&mut colors, [0, value, 0]);
+    	}
+    	assert_eq!(colors.len(), VOX_COLORS);
+    	assert_eq!(color_index(&mut colors, [12, 0, 0]), 1);
+    }
+}
@@ -0,0 +1,303 @@
+//! Guide curves drawn on the surface of the sculpt.
+//!
+//! Guides belong to the document of an object like pins, so they are
+//! saved with it and move along with the object. While guides are drawn,
+//! each stroke lays a new guide along the surface under the cursor instead
+//! of sculpting, and removal strokes erase the guide under the brush. They
+//! are shown as lines over the viewport, and strokes locked to the guides
+//! follow the nearest of them, like for seams and edge loops.
+//!
+//! A stamp of the brush, or a recorded macro, can also be repeated at even
+//! intervals along a guide, like for rows of rivets or stitches.
+
+use super::Editor;
+use super::command::CommandError;
+use super::constraint::nearest_on_line;
+use crate::document::Guide;
+use crate::i18n::tr_args;
+
+use glam::{Quat, Vec2, Vec3};
+
+/// How many points are laid along each line of a guide drawn through points of the screen.
+const STEPS: u32 = 16;
+
+/// What is repeated along a guide.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ArrayStamp {
+	/// A stamp of the current brush, adding or removing.
+	Brush { remove: bool },
+	/// A recorded macro, placed by its first stroke, and turned so its x axis follows the guide if aligned.
+	Macro { name: String, aligned: bool },
+}
+
+impl Editor {
+	/// Make strokes draw guides instead of sculpting, or sculpt again.
+	pub fn set_drawing_guides(&mut self, drawing: bool) {
+		self.drawing_guides = drawing;
+		self.drawn_guide = None;
+	}
+
+	/// Check whether strokes draw guides.
+	pub fn is_drawing_guides(&self) -> bool {
+		self.drawing_guides
+	}
+
+	/// Draw a guide along the surface of the active object through points of the screen, returning its index.
+	///
+	/// Points between them are snapped onto the surface too, so the guide
+	/// follows it. Returns None if fewer than two points are on the surface.
+	pub fn add_guide(&mut self, points: &[Vec2]) -> Option<usize> {
+		let mut samples: Vec<Vec2> = points.windows(2)
+			.flat_map(|line| (0..STEPS).map(move |step| line[0].lerp(line[1], step as f32 / STEPS as f32)))
+			.collect();
+		samples.extend(points.last());
+		let surface: Vec<Vec3> = samples.into_iter()
+			.filter_map(|point| self.surface_point(point.x, point.y))
+			.collect();
+		if surface.len() < 2 {
+			return None;
+		}
+
+		Some(self.document_mut().add_guide(surface))
+	}
+
+	/// Remove a guide of the active object, returning it if it exists.
+	pub fn remove_guide(&mut self, index: usize) -> Option<Guide> {
+		self.document_mut().remove_guide(index)
+	}
+
+	/// Get the points of each guide of the active object, placed in the world.
+	pub fn get_guides(&self) -> Vec<Vec<Vec3>> {
+		let transform = self.get_object_transform();
+
+		self.get_document().get_guides().iter()
+			.map(|guide| guide.points.iter().map(|point| transform.to_world(*point)).collect())
+			.collect()
+	}
+
+	/// Repeat a stamp a number of times along a guide of the active object, evenly spaced from one end to the other.
+	///
+	/// The copies make up a single stroke, so they go together like one.
+	pub fn array_along_guide(&mut self, index: usize, count: usize, stamp: &ArrayStamp) -> Result<(), CommandError> {
+		let Some(guide) = self.get_guides().into_iter().nth(index) else {
+			return Err(CommandError::Failed(tr_args("command-guide-missing", &[("number", &(index + 1).to_string())])));
+		};
+		let recorded = match stamp {
+			ArrayStamp::Macro { name, .. } => Some(self.macros.get(name)
+				.cloned()
+				.ok_or_else(|| CommandError::Failed(tr_args("command-macro-missing", &[("name", name)])))?),
+			ArrayStamp::Brush { .. } => None,
+		};
+
+		self.begin_stroke();
+		let mut result = Ok(());
+		for (position, tangent) in even_stops(&guide, count) {
+			match (stamp, recorded.as_ref()) {
+				(ArrayStamp::Macro { aligned, .. }, Some(recorded)) => {
+					let rotation = if *aligned { Quat::from_rotation_arc(Vec3::X, tangent) } else { Quat::IDENTITY };
+					result = self.play_steps(&recorded.transformed(position, rotation, 1.0));
+				},
+				(ArrayStamp::Brush { remove }, _) => self.stroke(*remove, position),
+				_ => (),
+			}
+			if result.is_err() {
+				break;
+			}
+		}
+		if let ArrayStamp::Brush { .. } = stamp {
+			self.record_stroke();
+		}
+		self.end_stroke();
+
+		result
+	}
+
+	/// Carry the guide of the current stroke on to the surface under a point of the screen, starting it if needed.
+	///
+	/// Points closer than a voxel to the last one are left out.
+	pub(super) fn draw_guide(&mut self, x: f32, y: f32) {
+		let Some(point) = self.surface_point(x, y) else {
+			return;
+		};
+		let voxel_size = self.get_document().get_voxel_size();
+		let Some(index) = self.drawn_guide else {
+			self.drawn_guide = Some(self.document_mut().add_guide(vec![point]));
+			return;
+		};
+		let last = self.get_document().get_guides()[index].points.last().copied();
+		if last.is_none_or(|last| last.distance(point) >= voxel_size) {
+			self.document_mut().extend_guide(index, point);
+		}
+	}
+
+	/// Erase the guide of the active object nearest to the surface under a point of the screen, if the brush reaches it.
+	pub(super) fn erase_guide(&mut self, x: f32, y: f32) {
+		let transform = self.get_object_transform();
+		let position = self.surface_point(x, y).unwrap_or_else(|| transform.to_local(self.brush_position(x, y)));
+		let reach = self.get_brush().get_reach(self.get_document(), position);
+
+		let nearest = self.get_document().get_guides().iter()
+			.enumerate()
+			.filter_map(|(index, guide)| {
+				let point = guide.points.windows(2)
+					.map(|line| nearest_on_segment(line[0], line[1], position))
+					.chain(guide.points.first().copied())
+					.min_by(|a, b| a.distance_squared(position).total_cmp(&b.distance_squared(position)))?;
+				reach.contains(point).then_some((index, point.distance_squared(position)))
+			})
+			.min_by(|a, b| a.1.total_cmp(&b.1));
+		if let Some((index, _)) = nearest {
+			self.document_mut().remove_guide(index);
+		}
+	}
+
+	/// Get the point of the guides of the active object nearest to a view ray in the world, if there are any.
+	pub(super) fn nearest_on_guides(&self, origin: Vec3, direction: Vec3) -> Option<Vec3> {
+		let direction = direction.normalize();
+		let off_ray = |point: Vec3| (point - origin).cross(direction).length_squared();
+
+		self.get_guides().iter()
+			.flat_map(|guide| {
+				let lines = guide.windows(2).map(|line| {
+					let along = nearest_on_line(line[0], line[1] - line[0], origin, direction);
+					nearest_on_segment(line[0], line[1], along)
+				});
+				lines.chain(guide.first().copied()).collect::<Vec<Vec3>>()
+			})
+			.min_by(|a, b| off_ray(*a).total_cmp(&off_ray(*b)))
+	}
+
+	/// Get the surface of the active object under a point of the screen, in its own space, if there is any.
+	fn surface_point(&self, x: f32, y: f32) -> Option<Vec3> {
+		let (origin, direction) = self.screen_ray(x, y);
+		let transform = self.get_object_transform();
+
+		self.get_document().raycast(transform.to_local(origin), transform.to_local_vector(direction))
+			.map(|hit| hit.position)
+	}
+}
+
+/// Get a number of points evenly spaced along a curve from one end to the other, with the direction of the curve at each.
+///
+/// A single point goes at the start, and a curve without length runs along x.
+fn even_stops(points: &[Vec3], count: usize) -> Vec<(Vec3, Vec3)> {
+	let lengths: Vec<f32> = points.windows(2).map(|line| line[0].distance(line[1])).collect();
+	let total: f32 = lengths.iter().sum();
+	let Some(start) = points.first().copied() else {
+		return Vec::new();
+	};
+	if total == 0.0 {
+		return vec![(start, Vec3::X); count];
+	}
+
+	(0..count)
+		.map(|stop| {
+			let mut distance = if count > 1 { total * stop as f32 / (count - 1) as f32 } else { 0.0 };
+			for (line, length) in points.windows(2).zip(&lengths) {
+				if distance <= *length && *length > 0.0 {
+					return (line[0].lerp(line[1], distance / length), (line[1] - line[0]) / length);
+				}
+				distance -= length;
+			}
+			// rounding can leave the last stop just past the end
+			let (line, length) = points.windows(2).zip(&lengths).rev().find(|(_, length)| **length > 0.0).unwrap();
+			(line[1], (line[1] - line[0]) / length)
+		})
+		.collect()
+}
+
+/// Get the point of a line between two points nearest to a point.
+fn nearest_on_segment(start: Vec3, end: Vec3, point: Vec3) -> Vec3 {
+	let line = end - start;
+	let length = line.length_squared();
+	if length == 0.0 {
+		return start;
+	}
+
+	start + line * ((point - start).dot(line) / length).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    use crate::editor::constraint::StrokeLock;
+
+    use glam::vec2;
+
+    #[test]
+    fn guides_are_drawn_on_the_surface_and_erased() {
+    	let mut editor = Editor::default();
+    	editor.set_brush_size(0.5);
+    	editor.add(0.5, 0.5);
+    	editor.set_brush_size(0.05);
+    	// off the surface, the guide has too few points
+    	assert_eq!(editor.add_guide(&[vec2(0.05, 0.05), vec2(0.1, 0.05)]), None);
+    	let index = editor.add_guide(&[vec2(0.4, 0.5), vec2(0.6, 0.5)]).unwrap();
+    	let guide = &editor.get_guides()[index];
+    	assert_eq!(guide.len(), STEPS as usize + 1);
+    	assert!(guide.iter().all(|point| editor.get_document().is_solid(*point + Vec3::Z * 0.01)));
+
+    	// a stroke draws a guide of its own, leaving the sculpt alone
+    	editor.set_drawing_guides(true);
+    	let before = editor.get_voxel_buffers(0);
+    	editor.begin_stroke();
+    	for step in 0..10 {
+    		editor.add(0.5, 0.4 + step as f32 * 0.02);
+    	}
+    	editor.end_stroke();
+    	assert_eq!(editor.get_voxel_buffers(0), before);
+    	assert_eq!(editor.get_guides().len(), 2);
+    	assert!(editor.get_guides()[1].len() > 5);
+
+    	// strokes locked to the guides follow the nearest one
+    	editor.set_drawing_guides(false);
+    	editor.set_stroke_lock(StrokeLock::Guide);
+    	editor.set_stroke_locked(true);
+    	let locked = editor.locked_positions(0.62, 0.52)[0];
+    	assert!(locked.distance(editor.get_guides()[0][STEPS as usize]) < 1e-4);
+
+    	editor.set_drawing_guides(true);
+    	editor.remove(0.5, 0.3);
+    	assert_eq!(editor.get_guides().len(), 2);
+    	editor.remove(0.5, 0.42);
+    	assert_eq!(editor.get_guides().len(), 1);
+    	assert_eq!(editor.remove_guide(0).map(|guide| guide.points.len()), Some(STEPS as usize + 1));
+    	assert_eq!(editor.remove_guide(0), None);
+    }
+
+    #[test]
+    fn stops_are_even_along_the_curve() {
+    	let curve = [Vec3::ZERO, Vec3::X, Vec3::X, Vec3::new(1.0, 2.0, 0.0)];
+    	let stops = even_stops(&curve, 4);
+    	assert_eq!(stops.len(), 4);
+    	assert_eq!(stops[0], (Vec3::ZERO, Vec3::X));
+    	assert!(stops[1].0.abs_diff_eq(Vec3::X, 1e-5));
+    	assert!(stops[2].0.abs_diff_eq(Vec3::new(1.0, 1.0, 0.0), 1e-5) && stops[2].1 == Vec3::Y);
+    	assert!(stops[3].0.abs_diff_eq(Vec3::new(1.0, 2.0, 0.0), 1e-5));
+    	assert_eq!(even_stops(&[Vec3::ONE], 2), vec![(Vec3::ONE, Vec3::X); 2]);
+    	assert!(even_stops(&curve, 0).is_empty());
+    }
+
+    #[test]
+    fn stamps_repeat_along_guides() {
+    	let mut editor = Editor::default();
+    	editor.set_brush_size(0.5);
+    	editor.add(0.5, 0.5);
+    	let index = editor.add_guide(&[vec2(0.35, 0.5), vec2(0.65, 0.5)]).unwrap();
+    	editor.set_brush_size(0.02);
+    	let guide = editor.get_guides()[index].clone();
+    	// just under the surface, away from the camera
+    	let inward = editor.screen_ray(0.5, 0.5).1.normalize() * 0.005;
+
+    	// carving notches along the guide
+    	editor.array_along_guide(index, 3, &ArrayStamp::Brush { remove: true }).unwrap();
+    	let middle = guide[guide.len() / 2];
+    	assert!(!editor.get_document().is_solid(middle + inward));
+    	assert!(!editor.get_document().is_solid(guide[0] + inward));
+    	assert!(editor.get_document().is_solid(guide[0].lerp(middle, 0.5) + inward));
+
+    	assert!(matches!(editor.array_along_guide(index, 3, &ArrayStamp::Macro { name: "studs".to_owned(), aligned: true }), Err(CommandError::Failed(_))));
+    	assert!(matches!(editor.array_along_guide(4, 3, &ArrayStamp::Brush { remove: false }), Err(CommandError::Failed(_))));
+    }
+}
@@ -0,0 +1,118 @@
+//! Onion skin ghosting of an earlier state of the sculpt.
+//!
+//! The ghost keeps the chunks of the active object as they were when it
+//! was taken. Chunks are copied on write, so it shares them with the
+//! object until the object is edited further, and only costs memory for
+//! the chunks which changed since. It is drawn as a translucent overlay
+//! where it is in front of the sculpt, showing what was carved away, so
+//! replaying a macro shows how the sculpt evolved from where it started.
+
+use super::Editor;
+use crate::aabb::Aabb;
+use crate::sculpt::Sculpt;
+
+use std::sync::Arc;
+
+use glam::{IVec3, Mat4};
+
+/// An earlier state of an object, shown as a translucent ghost.
+pub struct Ghost {
+	/// The placement of the object in the world when the ghost was taken.
+	transform: Mat4,
+	resolution: u32,
+	chunks: Vec<(IVec3, Aabb, Arc<Sculpt>)>,
+}
+
+/// The voxel buffer of a chunk of the ghost, ready to be written to the GPU.
+pub struct GhostPage {
+	pub coordinate: IVec3,
+	pub bounds: Aabb,
+	pub resolution: u32,
+	pub voxels: Vec<u32>,
+}
+
+impl Ghost {
+	/// Get the placement of the ghost in the world.
+	pub fn get_transform(&self) -> Mat4 {
+		self.transform
+	}
+
+	/// Get how many chunks the ghost has.
+	pub fn get_chunk_count(&self) -> usize {
+		self.chunks.len()
+	}
+
+	/// Build the voxel buffers of every chunk of the ghost, one at a time.
+	pub fn pages(&self) -> impl Iterator<Item = GhostPage> + '_ {
+		self.chunks.iter().map(|(coordinate, bounds, sculpt)| GhostPage {
+			coordinate: *coordinate,
+			bounds: *bounds,
+			resolution: self.resolution,
+			voxels: sculpt.get_voxel_buffer(),
+		})
+	}
+}
+
+impl Editor {
+	/// Show the active object as it is now as a ghost, or hide the ghost.
+	///
+	/// While the ghost is shown, playing a macro takes it again first,
+	/// so it shows the state the macro started from.
+	pub fn set_onion_skin(&mut self, on: bool) {
+		self.ghost = on.then(|| {
+			let object = self.scene.get_active();
+			let document = &object.document;
+
+			Ghost {
+				transform: object.transform.to_matrix(),
+				resolution: document.get_resolution(),
+				chunks: document.latch_chunks().into_iter()
+					.map(|(coordinate, sculpt)| (coordinate, document.get_chunk_bounds(coordinate), sculpt))
+					.collect(),
+			}
+		});
+		self.ghost_changed = true;
+	}
+
+	/// Check whether a ghost is shown.
+	pub fn is_onion_skin(&self) -> bool {
+		self.ghost.is_some()
+	}
+
+	/// Get the ghost, if it is shown.
+	pub fn get_ghost(&self) -> Option<&Ghost> {
+		self.ghost.as_ref()
+	}
+
+	/// Check whether the ghost was taken or hidden since this was last asked, for it to be uploaded.
+	pub fn take_ghost_changed(&mut self) -> bool {
+		std::mem::take(&mut self.ghost_changed)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    #[test]
+    fn ghosts_keep_the_state_they_were_taken_in() {
+    	let mut editor = Editor::default();
+    	editor.add(0.5, 0.5);
+    	let before = editor.get_voxel_buffers(0);
+
+    	editor.set_onion_skin(true);
+    	assert!(editor.take_ghost_changed());
+    	assert!(!editor.take_ghost_changed());
+    	editor.remove(0.5, 0.5);
+    	assert_ne!(editor.get_voxel_buffers(0), before);
+
+    	let ghost = editor.get_ghost().unwrap();
+    	assert_eq!(ghost.get_chunk_count(), before.len());
+    	let pages: Vec<_> = ghost.pages().map(|page| (page.coordinate, page.voxels)).collect();
+    	assert_eq!(pages, before);
+
+    	editor.set_onion_skin(false);
+    	assert!(editor.get_ghost().is_none());
+    	assert!(editor.take_ghost_changed());
+    }
+}
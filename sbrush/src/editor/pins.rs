@@ -0,0 +1,101 @@
+//! Notes pinned to places on the sculpt.
+//!
+//! Pins belong to the document of an object, so they are saved with it
+//! and move along with the object. They are placed on the surface under
+//! a point of the screen, shown as markers over the viewport, and the
+//! camera can be moved to look at any of them, which makes them handy
+//! for review comments and to-do lists on a sculpt.
+
+use super::Editor;
+use crate::camera::{Camera, CameraTransition};
+use crate::document::Pin;
+
+use glam::Vec3;
+
+/// A pin of the active object, placed in the world.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlacedPin {
+	pub position: Vec3,
+	pub note: String,
+}
+
+impl Editor {
+	/// Pin a note to the surface of the active object under a point of the screen, returning the index of the pin.
+	///
+	/// Where there is no surface under the point, the pin goes where a stroke there would.
+	pub fn add_pin(&mut self, x: f32, y: f32, note: &str) -> usize {
+		let (origin, direction) = self.screen_ray(x, y);
+		let transform = self.get_object_transform();
+		let position = self.get_document().raycast(transform.to_local(origin), transform.to_local_vector(direction))
+			.map(|hit| hit.position)
+			.unwrap_or_else(|| transform.to_local(self.brush_position(x, y)));
+
+		self.document_mut().add_pin(position, note)
+	}
+
+	/// Remove a pin of the active object, returning it if it exists.
+	pub fn remove_pin(&mut self, index: usize) -> Option<Pin> {
+		self.document_mut().remove_pin(index)
+	}
+
+	/// Get the pins of the active object, placed in the world.
+	pub fn get_pins(&self) -> Vec<PlacedPin> {
+		let transform = self.get_object_transform();
+
+		self.get_document().get_pins().iter()
+			.map(|pin| PlacedPin { position: transform.to_world(pin.position), note: pin.note.clone() })
+			.collect()
+	}
+
+	/// Smoothly move the camera to look at a pin of the active object from where it looks now.
+	///
+	/// Returns false if there is no such pin.
+	pub fn go_to_pin(&mut self, index: usize) -> bool {
+		let Some(to) = self.get_pin_camera(index) else {
+			return false;
+		};
+
+		self.view_transition = Some(CameraTransition::new(self.navigation_camera(), to, self.view_duration));
+		self.free_view = true;
+
+		true
+	}
+
+	/// Get a camera looking at a pin of the active object from where the camera looks now, if the pin exists.
+	pub fn get_pin_camera(&self, index: usize) -> Option<Camera> {
+		let pin = self.get_pins().into_iter().nth(index)?;
+
+		Some(Camera {
+			target: pin.position,
+			..self.navigation_camera()
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    #[test]
+    fn pins_sit_on_the_surface_and_the_camera_goes_to_them() {
+    	let mut editor = Editor::default();
+    	editor.add(0.5, 0.5);
+    	let index = editor.add_pin(0.5, 0.5, "soften this edge");
+    	editor.add_pin(0.1, 0.1, "fill in");
+
+    	let pins = editor.get_pins();
+    	assert_eq!(pins.len(), 2);
+    	assert_eq!(pins[index].note, "soften this edge");
+    	// the front view looks down z, so the pin is on the front of the stroke rather than in its middle
+    	assert!(pins[index].position.z < 0.5 && editor.get_document().is_solid(pins[index].position + Vec3::Z * 0.01));
+
+    	assert!(editor.go_to_pin(index));
+    	editor.animate(std::time::Duration::from_secs(10));
+    	assert!(editor.get_camera().target.distance(pins[index].position) < 1e-5);
+    	assert!(!editor.go_to_pin(2));
+
+    	assert_eq!(editor.remove_pin(0).map(|pin| pin.note), Some("soften this edge".to_owned()));
+    	assert_eq!(editor.get_pins()[0].note, "fill in");
+    	assert_eq!(editor.remove_pin(1), None);
+    }
+}
@@ -0,0 +1,105 @@
+use glam::{Quat, Vec3};
+
+/// A single recorded step of a macro.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MacroStep {
+	/// A typed command, re-run as written.
+	Command(String),
+	/// A brush stroke, kept in world space so it can be moved and scaled.
+	Stroke {
+		remove: bool,
+		/// The id of the brush.
+		brush: String,
+		position: Vec3,
+		size: f32,
+		/// Whether the cursor sample continues the stroke of the step before, so the gap between them is filled in.
+		joined: bool,
+	},
+}
+
+/// A named sequence of commands and strokes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Macro {
+	pub name: String,
+	pub steps: Vec<MacroStep>,
+}
+
+impl Macro {
+	/// Create an empty macro.
+	pub fn new(name: &str) -> Self {
+		Self {
+			name: name.to_owned(),
+			steps: Vec::new(),
+		}
+	}
+
+	/// Get the position of the first stroke, which placement is relative to.
+	pub fn origin(&self) -> Option<Vec3> {
+		self.steps.iter().find_map(|step| match step {
+			MacroStep::Stroke { position, .. } => Some(*position),
+			MacroStep::Command(_) => None,
+		})
+	}
+
+	/// Get a copy with its first stroke moved to a position, and every
+	/// stroke scaled around it.
+	pub fn placed(&self, position: Vec3, scale: f32) -> Macro {
+		self.transformed(position, Quat::IDENTITY, scale)
+	}
+
+	/// Get a copy with its first stroke moved to a position, and every
+	/// stroke turned and scaled around it.
+	pub fn transformed(&self, position: Vec3, rotation: Quat, scale: f32) -> Macro {
+		let Some(origin) = self.origin() else {
+			return self.clone();
+		};
+
+		let steps = self.steps.iter().map(|step| match step {
+			MacroStep::Stroke { remove, brush, position: stroke, size, joined } => MacroStep::Stroke {
+				remove: *remove,
+				brush: brush.clone(),
+				position: position + rotation * (*stroke - origin) * scale,
+				size: size * scale,
+				joined: *joined,
+			},
+			step => step.clone(),
+		}).collect();
+
+		Macro {
+			name: self.name.clone(),
+			steps,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use glam::vec3;
+
+    #[test]
+    fn placing_moves_and_scales_strokes_around_the_first() {
+    	let mut recorded = Macro::new("studs");
+    	recorded.steps.push(MacroStep::Command("brush square".to_owned()));
+    	recorded.steps.push(MacroStep::Stroke { remove: false, brush: "square".to_owned(), position: vec3(0.2, 0.2, 0.5), size: 0.1, joined: false });
+    	recorded.steps.push(MacroStep::Stroke { remove: true, brush: "round".to_owned(), position: vec3(0.4, 0.2, 0.5), size: 0.05, joined: true });
+
+    	let placed = recorded.placed(vec3(0.5, 0.5, 0.5), 2.0);
+
+    	assert_eq!(placed.steps[0], recorded.steps[0]);
+    	assert_eq!(placed.steps[1], MacroStep::Stroke { remove: false, brush: "square".to_owned(), position: vec3(0.5, 0.5, 0.5), size: 0.2, joined: false });
+    	let MacroStep::Stroke { position, size, .. } = placed.steps[2].clone() else {
+    		panic!("strokes should stay strokes");
+    	};
+    	assert!((position - vec3(0.9, 0.5, 0.5)).length() < 1e-5);
+    	assert_eq!(size, 0.1);
+
+    	// turning a quarter around z sends the second stroke along y instead
+    	let turned = recorded.transformed(vec3(0.5, 0.5, 0.5), Quat::from_rotation_z(std::f32::consts::FRAC_PI_2), 1.0);
+    	let MacroStep::Stroke { position, .. } = turned.steps[2].clone() else {
+    		panic!("strokes should stay strokes");
+    	};
+    	assert!((position - vec3(0.5, 0.7, 0.5)).length() < 1e-5);
+    }
+}
@@ -0,0 +1,122 @@
+//! Reports of how complex an object is, for pipelines keeping assets within budgets.
+//!
+//! A report is a JSON file with the nodes of the active object at each
+//! depth of its octrees, the volume each material fills, the box around
+//! what is solid and how many strokes went into it. Sizes are in the space
+//! of the object, before it is placed in the world.
+
+use super::Editor;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// How complex an object is.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DocumentReport {
+	/// The name of the object.
+	pub object: String,
+	/// The density of each chunk in voxels per axis.
+	pub resolution: u32,
+	pub chunks: usize,
+	/// The nodes, counted like the node limit counts them.
+	pub nodes: usize,
+	/// The octree nodes at each depth, with the roots of the chunks at 0.
+	pub nodes_per_depth: Vec<usize>,
+	/// The volume each material fills, by its index in the palette.
+	pub materials: BTreeMap<u32, f32>,
+	/// The box around what is solid, if anything is.
+	pub bounds: Option<ReportBounds>,
+	/// The volume of what is solid.
+	pub volume: f32,
+	/// The strokes made on the object since it was created or opened.
+	pub strokes: u32,
+	/// The strokes of `strokes` made with each brush, by its name.
+	pub brush_strokes: BTreeMap<String, u32>,
+}
+
+/// The corners of a box in a report.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReportBounds {
+	pub min: [f32; 3],
+	pub max: [f32; 3],
+}
+
+impl DocumentReport {
+	/// Read a report written by `Editor::export_report`.
+	pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+		Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+	}
+}
+
+impl Editor {
+	/// Get a report of how complex the active object is.
+	///
+	/// Chunks still in the file the object was opened from are read first, so they are counted too.
+	pub fn get_report(&mut self) -> io::Result<DocumentReport> {
+		let document = self.document_mut();
+		let saved = document.get_saved_count();
+		document.load_nearest(document.get_home_chunk().get_bounds().center(), saved)?;
+
+		let document = self.get_document();
+		let materials = document.get_material_volumes();
+		let brush_strokes = document.get_brush_strokes().clone();
+
+		Ok(DocumentReport {
+			object: self.scene.get_active().name.clone(),
+			resolution: document.get_resolution(),
+			chunks: document.get_chunk_coordinates().len(),
+			nodes: document.get_node_count(),
+			nodes_per_depth: document.get_depth_counts(),
+			bounds: document.get_solid_bounds().map(|bounds| ReportBounds { min: bounds.min.to_array(), max: bounds.max.to_array() }),
+			volume: materials.values().sum(),
+			materials,
+			strokes: brush_strokes.values().sum(),
+			brush_strokes,
+		})
+	}
+
+	/// Write a report of how complex the active object is to a JSON file.
+	pub fn export_report(&mut self, path: impl AsRef<Path>) -> io::Result<DocumentReport> {
+		let report = self.get_report()?;
+		fs::write(path, serde_json::to_string_pretty(&report)?)?;
+
+		Ok(report)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    #[test]
+    fn reports_add_up_the_nodes_and_materials() {
+    	let path = std::env::temp_dir().join(format!("swirlix-report-{}.json", std::process::id()));
+    	let mut editor = Editor::default();
+    	let empty = editor.get_report().unwrap();
+    	assert_eq!(empty.bounds, None);
+    	assert_eq!(empty.volume, 0.0);
+
+    	editor.set_palette(&[[0.8, 0.8, 0.8, 1.0], [0.8, 0.1, 0.1, 1.0]]);
+    	editor.run_command("brush size 0.2").unwrap();
+    	editor.add(0.5, 0.5);
+    	editor.run_command("brush material 1").unwrap();
+    	editor.add(0.6, 0.5);
+    	let report = editor.export_report(&path).unwrap();
+
+    	assert_eq!(DocumentReport::load(&path).unwrap(), report);
+    	assert_eq!(report.object, "Sculpt");
+    	assert_eq!(report.nodes_per_depth[0], report.chunks);
+    	assert!(report.nodes_per_depth.iter().sum::<usize>() <= report.nodes);
+    	assert_eq!(report.materials.keys().copied().collect::<Vec<_>>(), vec![0, 1]);
+    	assert!(report.volume > 0.0 && report.volume == report.materials.values().sum::<f32>());
+    	let bounds = report.bounds.unwrap();
+    	assert!(bounds.min[0] < bounds.max[0]);
+    	assert_eq!(report.strokes, 2);
+    	assert_eq!(report.brush_strokes.values().sum::<u32>(), report.strokes);
+    	fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,127 @@
+//! Review packages of the pinned notes, for reviewers without the tool.
+//!
+//! A package is a directory with an image of the sculpt looking at each
+//! pin of the active object, and a `review.json` manifest listing the
+//! notes with their positions and images. The manifest is written right
+//! away, while the images are rendered by the viewport afterwards.
+
+use super::Editor;
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use glam::Mat4;
+use serde::{Deserialize, Serialize};
+
+/// The name of the manifest in a review package.
+pub const MANIFEST: &str = "review.json";
+
+/// The notes of a review package.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReviewManifest {
+	/// The name of the object the pins belong to.
+	pub object: String,
+	pub pins: Vec<ReviewNote>,
+}
+
+/// A pinned note in a review package.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReviewNote {
+	/// The number of the pin, as listed from 1.
+	pub number: usize,
+	pub note: String,
+	/// Where the pin is in the world.
+	pub position: [f32; 3],
+	/// The image looking at the pin, relative to the manifest.
+	pub image: String,
+}
+
+/// An image of a review package waiting to be rendered.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReviewCapture {
+	pub path: PathBuf,
+	/// The view projection looking at the pin.
+	pub view: Mat4,
+}
+
+impl ReviewManifest {
+	/// Read the manifest of a review package.
+	pub fn load(directory: impl AsRef<Path>) -> io::Result<Self> {
+		Ok(serde_json::from_str(&fs::read_to_string(directory.as_ref().join(MANIFEST))?)?)
+	}
+}
+
+impl Editor {
+	/// Export the pins of the active object as a review package into a directory, returning how many there are.
+	///
+	/// The images are left for `take_review_captures`, to be rendered from the views in the package.
+	pub fn export_review(&mut self, directory: impl Into<PathBuf>) -> io::Result<usize> {
+		let pins = self.get_pins();
+		if pins.is_empty() {
+			return Err(io::Error::new(io::ErrorKind::NotFound, "the object has no pins"));
+		}
+		let directory = directory.into();
+		fs::create_dir_all(&directory)?;
+
+		let mut notes = Vec::with_capacity(pins.len());
+		let mut captures = Vec::with_capacity(pins.len());
+		for (index, pin) in pins.into_iter().enumerate() {
+			let image = format!("pin-{:03}.png", index + 1);
+			let camera = self.get_pin_camera(index).unwrap();
+			captures.push(ReviewCapture { path: directory.join(&image), view: camera.view_projection(self.aspect) });
+			notes.push(ReviewNote {
+				number: index + 1,
+				note: pin.note,
+				position: pin.position.to_array(),
+				image,
+			});
+		}
+		let manifest = ReviewManifest {
+			object: self.scene.get_active().name.clone(),
+			pins: notes,
+		};
+		fs::write(directory.join(MANIFEST), serde_json::to_string_pretty(&manifest)?)?;
+
+		let count = captures.len();
+		self.review_captures.extend(captures);
+
+		Ok(count)
+	}
+
+	/// Take the images of review packages waiting to be rendered.
+	pub fn take_review_captures(&mut self) -> Vec<ReviewCapture> {
+		std::mem::take(&mut self.review_captures)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    #[test]
+    fn packages_list_every_pin_with_its_image() {
+    	let directory = std::env::temp_dir().join(format!("swirlix-review-{}", std::process::id()));
+    	let mut editor = Editor::default();
+    	assert_eq!(editor.export_review(&directory).map_err(|error| error.kind()), Err(io::ErrorKind::NotFound));
+
+    	editor.add(0.5, 0.5);
+    	editor.add_pin(0.5, 0.5, "too sharp");
+    	editor.add_pin(0.2, 0.8, "add a handle");
+    	assert_eq!(editor.export_review(&directory).unwrap(), 2);
+
+    	let manifest = ReviewManifest::load(&directory).unwrap();
+    	assert_eq!(manifest.object, "Sculpt");
+    	assert_eq!(manifest.pins.len(), 2);
+    	assert_eq!(manifest.pins[1].number, 2);
+    	assert_eq!(manifest.pins[1].note, "add a handle");
+    	assert_eq!(manifest.pins[0].position, editor.get_pins()[0].position.to_array());
+
+    	let captures = editor.take_review_captures();
+    	assert_eq!(captures.iter().map(|capture| capture.path.clone()).collect::<Vec<_>>(), vec![directory.join("pin-001.png"), directory.join("pin-002.png")]);
+    	// each image looks at its own pin
+    	assert_ne!(captures[0].view, captures[1].view);
+    	assert!(editor.take_review_captures().is_empty());
+    	fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,215 @@
+//! Soak tests, sculpting randomly for a long time to catch leaks and slowdowns.
+//!
+//! A soak test applies a few randomized strokes every frame, with every
+//! brush, size and direction, until it has applied as many as asked for.
+//! After each frame it samples the nodes of the document, the nodes kept
+//! for reuse, the chunk buffers on the GPU, the memory of the process and
+//! the time spent, and at the end it writes them to a JSON report. Steady
+//! growth in any of them under sustained editing points at the allocator,
+//! the compaction of removed nodes or the upload path.
+
+use super::Editor;
+use crate::rng::Rng;
+use crate::sculpt::pool;
+use crate::util::profile::milliseconds;
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use glam::Vec3;
+use serde::Serialize;
+
+/// How many strokes are applied each frame.
+pub const STROKES_PER_FRAME: u32 = 8;
+
+/// A running soak test.
+pub struct Soak {
+	path: PathBuf,
+	rng: Rng,
+	report: SoakReport,
+	/// How many strokes are left to apply.
+	remaining: u32,
+	started: Instant,
+	/// How long the strokes of the frame being sampled took.
+	stroke_time: Duration,
+}
+
+/// How a frame of a soak test went in the app, which the editor can't see.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SoakFrame {
+	/// The time the main thread spent on the frame, outside of drawing.
+	pub cpu: Duration,
+	/// The part of it spent sending the document to the renderer.
+	pub upload: Duration,
+	/// The time spent drawing the frame.
+	pub draw: Duration,
+	/// The size of the chunk buffers on the GPU, in bytes.
+	pub buffer_bytes: u64,
+}
+
+/// The state after one frame of a soak test.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct SoakSample {
+	/// How many strokes were applied so far.
+	pub strokes: u32,
+	pub nodes: usize,
+	pub chunks: usize,
+	/// The nodes kept by the main thread for reuse.
+	pub free_nodes: usize,
+	pub buffer_bytes: u64,
+	/// The memory of the process, where the platform tells.
+	pub resident_bytes: Option<u64>,
+	pub stroke_ms: f64,
+	pub cpu_ms: f64,
+	pub upload_ms: f64,
+	pub draw_ms: f64,
+}
+
+/// The report of a finished soak test.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct SoakReport {
+	pub strokes: u32,
+	pub seed: u32,
+	pub seconds: f64,
+	pub peak_nodes: usize,
+	pub peak_resident_bytes: Option<u64>,
+	pub peak_frame_ms: f64,
+	pub samples: Vec<SoakSample>,
+}
+
+impl Editor {
+	/// Start a soak test of a count of random strokes, writing its report to a path once it is done.
+	///
+	/// The same seed applies the same strokes. Any running soak test is dropped without a report.
+	pub fn start_soak(&mut self, path: impl Into<PathBuf>, strokes: u32, seed: u32) {
+		self.soak = Some(Soak {
+			path: path.into(),
+			rng: Rng::new(seed),
+			report: SoakReport { strokes, seed, ..SoakReport::default() },
+			remaining: strokes,
+			started: Instant::now(),
+			stroke_time: Duration::ZERO,
+		});
+	}
+
+	/// Check whether a soak test is running.
+	pub fn is_soaking(&self) -> bool {
+		self.soak.is_some()
+	}
+
+	/// Apply the strokes of the next frame of the soak test, if one is running.
+	///
+	/// Returns false if there is none, or it applied every stroke.
+	pub fn soak_strokes(&mut self) -> bool {
+		let Some(mut soak) = self.soak.take() else {
+			return false;
+		};
+		let start = Instant::now();
+		let home = self.get_document().get_home_chunk().get_bounds();
+		// the strokes leave the brushes as they were
+		let sizes: Vec<f32> = self.brushes.iter().map(|(_, brush)| brush.get_size()).collect();
+		let current_brush = self.current_brush;
+		for _ in 0..STROKES_PER_FRAME.min(soak.remaining) {
+			self.current_brush = soak.rng.next_u32() as usize % self.brushes.len();
+			self.set_brush_size(0.02 + soak.rng.next_f32() * 0.2);
+			// mostly adding, so the sculpt keeps growing while removals cut into it
+			let remove = soak.rng.next_f32() < 0.4;
+			let unit = Vec3::splat(0.5) + soak.rng.next_in_unit_sphere() * 0.4;
+			self.begin_stroke();
+			self.stroke(remove, home.min + unit * (home.max - home.min));
+			self.record_stroke();
+			self.end_stroke();
+		}
+		for (brush, size) in self.brushes.iter_mut().zip(sizes) {
+			brush.set_size(size);
+		}
+		self.current_brush = current_brush;
+		soak.remaining = soak.remaining.saturating_sub(STROKES_PER_FRAME);
+		soak.stroke_time = start.elapsed();
+		let applying = soak.remaining > 0;
+		self.soak = Some(soak);
+
+		applying
+	}
+
+	/// Sample the state after a frame of the soak test, writing the report once every stroke was applied.
+	///
+	/// Returns where the report was written, or why it couldn't be, once the soak test is finished.
+	pub fn record_soak_frame(&mut self, frame: SoakFrame) -> Option<io::Result<PathBuf>> {
+		let document = self.get_document();
+		let (nodes, chunks) = (document.get_node_count(), document.get_chunk_coordinates().len());
+		let soak = self.soak.as_mut()?;
+		let sample = SoakSample {
+			strokes: soak.report.strokes - soak.remaining,
+			nodes,
+			chunks,
+			free_nodes: pool::get_free_count(),
+			buffer_bytes: frame.buffer_bytes,
+			resident_bytes: resident_bytes(),
+			stroke_ms: milliseconds(soak.stroke_time),
+			cpu_ms: milliseconds(frame.cpu),
+			upload_ms: milliseconds(frame.upload),
+			draw_ms: milliseconds(frame.draw),
+		};
+		let report = &mut soak.report;
+		report.peak_nodes = report.peak_nodes.max(sample.nodes);
+		report.peak_resident_bytes = report.peak_resident_bytes.max(sample.resident_bytes);
+		report.peak_frame_ms = report.peak_frame_ms.max(sample.stroke_ms + sample.cpu_ms + sample.draw_ms);
+		report.samples.push(sample);
+		soak.stroke_time = Duration::ZERO;
+		if soak.remaining > 0 {
+			return None;
+		}
+
+		let mut soak = self.soak.take()?;
+		soak.report.seconds = soak.started.elapsed().as_secs_f64();
+		let written = serde_json::to_string_pretty(&soak.report)
+			.map_err(io::Error::from)
+			.and_then(|json| fs::write(&soak.path, json));
+
+		Some(written.map(|()| soak.path))
+	}
+}
+
+/// Get the memory of the process in bytes, where the platform tells.
+fn resident_bytes() -> Option<u64> {
+	let status = fs::read_to_string("/proc/self/status").ok()?;
+	let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+	let kilobytes: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+
+	Some(kilobytes * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    #[test]
+    fn soak_tests_sample_every_frame_and_write_a_report() {
+    	let path = std::env::temp_dir().join(format!("swirlix-soak-{}.json", std::process::id()));
+    	let mut editor = Editor::default();
+    	editor.start_soak(&path, 20, 7);
+
+    	let mut frames = 0;
+    	let written = loop {
+    		editor.soak_strokes();
+    		frames += 1;
+    		if let Some(written) = editor.record_soak_frame(SoakFrame { buffer_bytes: 64, ..SoakFrame::default() }) {
+    			break written;
+    		}
+    	};
+    	assert_eq!(written.unwrap(), path);
+    	assert_eq!(frames, 3);
+    	assert!(!editor.is_soaking());
+    	assert!(editor.get_document().get_node_count() > 1);
+
+    	let report: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    	assert_eq!(report["strokes"], 20);
+    	assert_eq!(report["samples"].as_array().unwrap().len(), 3);
+    	assert_eq!(report["samples"][2]["strokes"], 20);
+    	assert_eq!(report["samples"][0]["buffer_bytes"], 64);
+    	fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,612 @@
+//! Stencils, flat shapes projected from the camera onto the sculpt.
+//!
+//! A stencil is drawn on the screen, as a circle, a polygon or the shapes
+//! of an SVG file, and carried along the view rays through it. Where the
+//! rays meet the surface, the shape is cut into it to a depth, or raised
+//! from it toward the camera by as much, so logos, windows and lettering
+//! follow the surface they land on.
+//!
+//! Only the outlines of SVG files are read, from paths, polygons,
+//! polylines, rectangles, circles and ellipses. Curves are split into
+//! lines, while arcs and transforms are left out.
+
+use super::Editor;
+use crate::aabb::Aabb;
+use crate::camera::Camera;
+use crate::scene::Transform;
+use crate::sculpt::Coverage;
+
+use glam::{Vec2, Vec3, vec2};
+
+/// How many view rays are cast across each side of a stencil to find the surface under it.
+const DEPTH_SAMPLES: usize = 128;
+
+/// How many lines a curve of an SVG file or a circle is split into.
+const CURVE_STEPS: usize = 8;
+const CIRCLE_STEPS: usize = 96;
+
+/// A flat shape on the screen, as closed outlines, where overlapping outlines cut holes into each other.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StencilShape {
+	/// The outlines, with x scaled by the aspect ratio of the screen, so
+	/// distances are measured alike along both of its axes.
+	outlines: Vec<Vec<Vec2>>,
+	aspect: f32,
+}
+
+impl StencilShape {
+	/// Get a circle around a point of the screen, with a radius as a fraction of its height.
+	pub fn circle(center: Vec2, radius: f32, aspect: f32) -> Self {
+		let center = vec2(center.x * aspect, center.y);
+		let outline = (0..CIRCLE_STEPS)
+			.map(|step| center + Vec2::from_angle(step as f32 / CIRCLE_STEPS as f32 * std::f32::consts::TAU) * radius)
+			.collect();
+
+		Self { outlines: vec![outline], aspect }
+	}
+
+	/// Get a polygon through points of the screen.
+	pub fn polygon(points: &[Vec2], aspect: f32) -> Self {
+		Self {
+			outlines: vec![points.iter().map(|point| vec2(point.x * aspect, point.y)).collect()],
+			aspect,
+		}
+	}
+
+	/// Read the outlines of an SVG file, fitted to a height (as a fraction of the screen's) around a point of the screen.
+	///
+	/// Returns None if the file has no outlines with an area.
+	pub fn from_svg(source: &str, center: Vec2, height: f32, aspect: f32) -> Option<Self> {
+		let outlines = svg_outlines(source);
+		let points = || outlines.iter().flatten();
+		let low = points().fold(Vec2::INFINITY, |low, point| low.min(*point));
+		let high = points().fold(Vec2::NEG_INFINITY, |high, point| high.max(*point));
+		let extent = high - low;
+		if outlines.is_empty() || extent.min_element() <= 0.0 {
+			return None;
+		}
+		let scale = height / extent.y;
+		let center = vec2(center.x * aspect, center.y);
+		let middle = (low + high) / 2.0;
+
+		Some(Self {
+			outlines: outlines.iter()
+				.map(|outline| outline.iter().map(|point| center + (*point - middle) * scale).collect())
+				.collect(),
+			aspect,
+		})
+	}
+
+	/// Check whether a point of the screen is inside of the shape.
+	pub fn contains(&self, point: Vec2) -> bool {
+		let point = self.shape_point(point);
+		let mut inside = false;
+		for (start, end) in self.edges() {
+			if (start.y > point.y) != (end.y > point.y) && point.x < start.x + (point.y - start.y) / (end.y - start.y) * (end.x - start.x) {
+				inside = !inside;
+			}
+		}
+
+		inside
+	}
+
+	/// Get the distance from a point of the screen to the nearest outline, as a fraction of the screen's height.
+	pub fn edge_distance(&self, point: Vec2) -> f32 {
+		let point = self.shape_point(point);
+
+		self.edges()
+			.map(|(start, end)| {
+				let line = end - start;
+				let along = ((point - start).dot(line) / line.length_squared().max(f32::EPSILON)).clamp(0.0, 1.0);
+				point.distance(start + line * along)
+			})
+			.fold(f32::INFINITY, f32::min)
+	}
+
+	/// Get the corners of the box around the shape on the screen.
+	pub fn bounds(&self) -> (Vec2, Vec2) {
+		let points = || self.outlines.iter().flatten();
+		let low = points().fold(Vec2::INFINITY, |low, point| low.min(*point));
+		let high = points().fold(Vec2::NEG_INFINITY, |high, point| high.max(*point));
+
+		(self.screen_point(low), self.screen_point(high))
+	}
+
+	/// Get every edge of the outlines, each closed back to its start.
+	fn edges(&self) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+		self.outlines.iter().flat_map(|outline| {
+			(0..outline.len()).map(move |index| (outline[index], outline[(index + 1) % outline.len()]))
+		})
+	}
+
+	fn shape_point(&self, point: Vec2) -> Vec2 {
+		vec2(point.x * self.aspect, point.y)
+	}
+
+	fn screen_point(&self, point: Vec2) -> Vec2 {
+		vec2(point.x / self.aspect, point.y)
+	}
+}
+
+/// How far along the view rays across a stencil the surface is, sampled on a grid over its box on the screen.
+struct DepthMap {
+	low: Vec2,
+	high: Vec2,
+	/// The distance along each ray to the surface, row by row, if it meets it.
+	distances: Vec<Option<f32>>,
+}
+
+impl DepthMap {
+	/// Get the distance to the surface along the ray through the sample nearest to a point of the screen.
+	fn get(&self, point: Vec2) -> Option<f32> {
+		let cell = ((point - self.low) / (self.high - self.low).max(Vec2::splat(f32::EPSILON)) * DEPTH_SAMPLES as f32)
+			.floor()
+			.clamp(Vec2::ZERO, Vec2::splat(DEPTH_SAMPLES as f32 - 1.0));
+
+		self.distances[cell.y as usize * DEPTH_SAMPLES + cell.x as usize]
+	}
+}
+
+/// What the screen shows, to find where positions in the world are seen on it.
+struct StencilView {
+	camera: Option<Camera>,
+	aspect: f32,
+	/// The box the front view shows, when the camera isn't free.
+	home: Aabb,
+}
+
+impl StencilView {
+	/// Find the point of the screen a position in the world is seen at, and how far along its view ray it is.
+	fn screen_point(&self, position: Vec3) -> Option<(Vec2, f32)> {
+		let Some(camera) = &self.camera else {
+			let unit = (position - self.home.min) / (self.home.max - self.home.min);
+			return Some((unit.truncate(), position.z - self.home.min.z));
+		};
+
+		camera.project(position, self.aspect)
+	}
+
+	/// Get how far across the screen (as a fraction of its height) a ball reaches, seen at a distance.
+	///
+	/// Returns None if the camera is inside of it.
+	fn footprint(&self, distance: f32, radius: f32) -> Option<f32> {
+		let Some(camera) = &self.camera else {
+			return Some(radius / (self.home.max.y - self.home.min.y));
+		};
+		if distance <= radius {
+			return None;
+		}
+
+		// off to the side of the view, points are closer along the view direction than their distance
+		Some(1.5 * radius / (2.0 * (camera.fov / 2.0).tan() * (distance - radius)))
+	}
+}
+
+impl Editor {
+	/// Get the aspect ratio stencils are drawn with, which is 1 in the front view, where the screen shows a square of the sculpt.
+	pub fn get_stencil_aspect(&self) -> f32 {
+		if self.free_view { self.aspect } else { 1.0 }
+	}
+
+	/// Project a stencil from the camera onto the active object, cutting it in to a depth or raising it by as much.
+	///
+	/// The depth is counted in voxels, along the view rays from the surface
+	/// under each point of the stencil. Added voxels take the material of
+	/// the current brush. Returns false if the stencil misses the sculpt, or
+	/// the active object is locked.
+	pub fn project_stencil(&mut self, shape: &StencilShape, depth: u32, remove: bool) -> bool {
+		if self.scene.get_active().locked {
+			return false;
+		}
+		let Some(map) = self.depth_map(shape) else {
+			return false;
+		};
+		let transform = self.get_object_transform();
+		let voxel_size = self.get_document().get_voxel_size();
+		let depth = depth as f32 * voxel_size * transform.scale;
+		let reach = self.stencil_reach(&map, depth);
+		let view = StencilView {
+			camera: self.free_view.then_some(self.camera),
+			aspect: self.aspect,
+			home: self.get_document().get_home_chunk().get_bounds(),
+		};
+
+		// the stencil reaches as far as the surface under it, and its depth
+		let hits = || map.distances.iter().flatten().copied();
+		let nearest = hits().fold(f32::INFINITY, f32::min) - if remove { 0.0 } else { depth };
+		let furthest = hits().fold(f32::NEG_INFINITY, f32::max) + if remove { depth } else { 0.0 };
+
+		let classify = |size: f32, center: Vec3| {
+			let radius = size * 3f32.sqrt() / 2.0 * transform.scale;
+			let Some((point, distance)) = view.screen_point(transform.to_world(center)) else {
+				return Coverage::Unchanged;
+			};
+			if size > voxel_size * 1.5 {
+				let outside = !shape.contains(point) && view.footprint(distance, radius).is_some_and(|footprint| shape.edge_distance(point) > footprint);
+				if outside || distance + radius < nearest || distance - radius > furthest {
+					return Coverage::Unchanged;
+				}
+				return Coverage::Mixed;
+			}
+
+			let Some(surface) = map.get(point).filter(|_| shape.contains(point)) else {
+				return Coverage::Unchanged;
+			};
+			match remove {
+				true if distance >= surface && distance <= surface + depth => Coverage::Empty,
+				false if distance >= surface - depth && distance <= surface => Coverage::Solid,
+				_ => Coverage::Unchanged,
+			}
+		};
+		let material = self.get_brush().get_material();
+		let fill = |center: Vec3| material.pick((center / voxel_size).floor().as_ivec3());
+
+		self.begin_stroke();
+		let document = self.document_mut();
+		for chunk in document.get_chunks_in(transform_bounds(reach, transform), !remove) {
+			chunk.apply_with(&fill, &classify);
+		}
+		document.prune();
+		self.end_stroke();
+		self.record_stroke();
+
+		true
+	}
+
+	/// Cast view rays across the box of a stencil, returning None if none of them meet the active object.
+	fn depth_map(&self, shape: &StencilShape) -> Option<DepthMap> {
+		let (low, high) = shape.bounds();
+		let transform = self.get_object_transform();
+		let document = self.get_document();
+		let distances: Vec<Option<f32>> = (0..DEPTH_SAMPLES * DEPTH_SAMPLES)
+			.map(|index| {
+				let (origin, direction) = self.screen_ray_at(low, high, index);
+				document.raycast(transform.to_local(origin), transform.to_local_vector(direction))
+					.map(|hit| (transform.to_world(hit.position) - origin).dot(direction))
+			})
+			.collect();
+
+		distances.iter().any(Option::is_some).then_some(DepthMap { low, high, distances })
+	}
+
+	/// Get the box in the world the stencil can change, around where its rays meet the surface.
+	fn stencil_reach(&self, map: &DepthMap, depth: f32) -> Aabb {
+		let (mut low, mut high) = (Vec3::INFINITY, Vec3::NEG_INFINITY);
+		for (index, distance) in map.distances.iter().enumerate() {
+			let Some(distance) = distance else {
+				continue;
+			};
+			let (origin, direction) = self.screen_ray_at(map.low, map.high, index);
+			for along in [distance - depth, distance + depth] {
+				low = low.min(origin + direction * along);
+				high = high.max(origin + direction * along);
+			}
+		}
+
+		// rays between the samples land up to a cell further out
+		let spacing = self.get_document().get_voxel_size() * self.get_object_transform().scale;
+		let margin = (high - low).max_element() / DEPTH_SAMPLES as f32 + spacing;
+		Aabb::new(low - margin, high + margin)
+	}
+
+	/// Get the view ray through a sample of the grid over a box on the screen.
+	fn screen_ray_at(&self, low: Vec2, high: Vec2, index: usize) -> (Vec3, Vec3) {
+		let cell = vec2((index % DEPTH_SAMPLES) as f32, (index / DEPTH_SAMPLES) as f32) + 0.5;
+		let point = low + (high - low) * cell / DEPTH_SAMPLES as f32;
+
+		self.screen_ray(point.x, point.y)
+	}
+}
+
+/// Map a box in the world into the space of an object, as the box around its corners there.
+fn transform_bounds(bounds: Aabb, transform: Transform) -> Aabb {
+	let corners: Vec<Vec3> = (0..8)
+		.map(|corner| {
+			let pick = |bit: usize, low: f32, high: f32| if corner & bit == 0 { low } else { high };
+			transform.to_local(Vec3::new(
+				pick(1, bounds.min.x, bounds.max.x),
+				pick(2, bounds.min.y, bounds.max.y),
+				pick(4, bounds.min.z, bounds.max.z),
+			))
+		})
+		.collect();
+	let low = corners.iter().fold(Vec3::INFINITY, |low, corner| low.min(*corner));
+	let high = corners.iter().fold(Vec3::NEG_INFINITY, |high, corner| high.max(*corner));
+
+	Aabb::new(low, high)
+}
+
+/// Read the outlines of the shapes of an SVG file, in its own units.
+fn svg_outlines(source: &str) -> Vec<Vec<Vec2>> {
+	let mut outlines = Vec::new();
+	for element in source.split('<').skip(1) {
+		let tag = element.split('>').next().unwrap_or_default();
+		let name = tag.split_whitespace().next().unwrap_or_default();
+		let number = |name: &str| attribute(tag, name).and_then(|value| numbers(value).first().copied());
+		match name {
+			"path" => outlines.extend(attribute(tag, "d").map(path_outlines).unwrap_or_default()),
+			"polygon" | "polyline" => {
+				let points = attribute(tag, "points").map(numbers).unwrap_or_default();
+				outlines.push(points.chunks_exact(2).map(|pair| vec2(pair[0], pair[1])).collect());
+			},
+			"rect" => {
+				let (x, y) = (number("x").unwrap_or(0.0), number("y").unwrap_or(0.0));
+				let (width, height) = (number("width").unwrap_or(0.0), number("height").unwrap_or(0.0));
+				outlines.push(vec![vec2(x, y), vec2(x + width, y), vec2(x + width, y + height), vec2(x, y + height)]);
+			},
+			"circle" | "ellipse" => {
+				let center = vec2(number("cx").unwrap_or(0.0), number("cy").unwrap_or(0.0));
+				let radius = match number("r") {
+					Some(radius) => Vec2::splat(radius),
+					None => vec2(number("rx").unwrap_or(0.0), number("ry").unwrap_or(0.0)),
+				};
+				outlines.push((0..CIRCLE_STEPS)
+					.map(|step| center + Vec2::from_angle(step as f32 / CIRCLE_STEPS as f32 * std::f32::consts::TAU) * radius)
+					.collect());
+			},
+			_ => (),
+		}
+	}
+	outlines.retain(|outline| outline.len() >= 3);
+
+	outlines
+}
+
+/// Get the value of an attribute of a tag, by its whole name.
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+	let mut rest = tag;
+	while let Some(found) = rest.find(name) {
+		let before = rest[..found].chars().next_back();
+		let after = rest[found + name.len()..].trim_start();
+		rest = &rest[found + name.len()..];
+		if !before.is_some_and(char::is_whitespace) {
+			continue;
+		}
+		let Some(value) = after.strip_prefix('=').map(str::trim_start) else {
+			continue;
+		};
+		let quote = value.chars().next()?;
+		if quote != '"' && quote != '\'' {
+			continue;
+		}
+		return value[1..].split(quote).next();
+	}
+
+	None
+}
+
+/// Read the numbers of a list, which can be split by spaces, commas or signs, like `10,5-3.5.5`.
+fn numbers(list: &str) -> Vec<f32> {
+	path_tokens(list).into_iter()
+		.filter_map(|token| match token {
+			PathToken::Number(number) => Some(number),
+			PathToken::Command(_) => None,
+		})
+		.collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PathToken {
+	Command(char),
+	Number(f32),
+}
+
+/// Split the data of a path into commands and numbers.
+fn path_tokens(data: &str) -> Vec<PathToken> {
+	let mut tokens = Vec::new();
+	let characters: Vec<char> = data.chars().collect();
+	let mut index = 0;
+	while index < characters.len() {
+		let character = characters[index];
+		if character.is_ascii_alphabetic() {
+			tokens.push(PathToken::Command(character));
+			index += 1;
+			continue;
+		}
+		if !(character.is_ascii_digit() || matches!(character, '-' | '+' | '.')) {
+			index += 1;
+			continue;
+		}
+		// a number runs until a second sign (outside of an exponent) or a second point
+		let start = index;
+		let mut point = false;
+		let mut exponent = false;
+		index += 1;
+		if character == '.' {
+			point = true;
+		}
+		while index < characters.len() {
+			match characters[index] {
+				digit if digit.is_ascii_digit() => (),
+				'.' if !point && !exponent => point = true,
+				'e' | 'E' if !exponent => {
+					exponent = true;
+					if matches!(characters.get(index + 1), Some('-' | '+')) {
+						index += 1;
+					}
+				},
+				_ => break,
+			}
+			index += 1;
+		}
+		let text: String = characters[start..index].iter().collect();
+		if let Ok(number) = text.parse() {
+			tokens.push(PathToken::Number(number));
+		}
+	}
+
+	tokens
+}
+
+/// Follow the data of a path, returning the outline of each of its parts.
+fn path_outlines(data: &str) -> Vec<Vec<Vec2>> {
+	let mut outlines = Vec::new();
+	let mut outline: Vec<Vec2> = Vec::new();
+	let mut position = Vec2::ZERO;
+	let mut start = Vec2::ZERO;
+	// the last control point of a curve, reflected by smooth curves following it
+	let mut control: Option<Vec2> = None;
+
+	let tokens = path_tokens(data);
+	let mut index = 0;
+	let mut command = 'M';
+	while index < tokens.len() {
+		if let PathToken::Command(next) = tokens[index] {
+			command = next;
+			index += 1;
+		}
+		let count = match command.to_ascii_uppercase() {
+			'M' | 'L' | 'T' => 2,
+			'H' | 'V' => 1,
+			'C' => 6,
+			'S' | 'Q' => 4,
+			'A' => 7,
+			_ => 0,
+		};
+		let arguments: Vec<f32> = tokens[index..].iter()
+			.take(count)
+			.map_while(|token| match token {
+				PathToken::Number(number) => Some(*number),
+				PathToken::Command(_) => None,
+			})
+			.collect();
+		if count == 0 {
+			// closing the outline starts the next one where it began
+			if command.eq_ignore_ascii_case(&'Z') {
+				outlines.push(std::mem::take(&mut outline));
+				position = start;
+			}
+			control = None;
+			continue;
+		}
+		if arguments.len() < count {
+			break;
+		}
+		index += count;
+
+		let relative = command.is_ascii_lowercase();
+		let point = |x: f32, y: f32| if relative { position + vec2(x, y) } else { vec2(x, y) };
+		let mut next_control = None;
+		match command.to_ascii_uppercase() {
+			'M' => {
+				if !outline.is_empty() {
+					outlines.push(std::mem::take(&mut outline));
+				}
+				position = point(arguments[0], arguments[1]);
+				start = position;
+				outline.push(position);
+				// further pairs draw lines
+				command = if relative { 'l' } else { 'L' };
+			},
+			'L' => {
+				position = point(arguments[0], arguments[1]);
+				outline.push(position);
+			},
+			'H' => {
+				position.x = if relative { position.x + arguments[0] } else { arguments[0] };
+				outline.push(position);
+			},
+			'V' => {
+				position.y = if relative { position.y + arguments[0] } else { arguments[0] };
+				outline.push(position);
+			},
+			'C' | 'S' | 'Q' | 'T' => {
+				let upper = command.to_ascii_uppercase();
+				let reflected = control.map_or(position, |control| 2.0 * position - control);
+				let (first, second, end) = match upper {
+					'C' => (point(arguments[0], arguments[1]), point(arguments[2], arguments[3]), point(arguments[4], arguments[5])),
+					'S' => (reflected, point(arguments[0], arguments[1]), point(arguments[2], arguments[3])),
+					'Q' => {
+						let middle = point(arguments[0], arguments[1]);
+						(middle, middle, point(arguments[2], arguments[3]))
+					},
+					_ => (reflected, reflected, point(arguments[0], arguments[1])),
+				};
+				let quadratic = matches!(upper, 'Q' | 'T');
+				for step in 1..=CURVE_STEPS {
+					let t = step as f32 / CURVE_STEPS as f32;
+					outline.push(if quadratic {
+						position.lerp(first, t).lerp(first.lerp(end, t), t)
+					} else {
+						let (a, b, c) = (position.lerp(first, t), first.lerp(second, t), second.lerp(end, t));
+						a.lerp(b, t).lerp(b.lerp(c, t), t)
+					});
+				}
+				position = end;
+				next_control = Some(second);
+			},
+			// arcs are drawn as a line to their end
+			_ => {
+				position = point(arguments[5], arguments[6]);
+				outline.push(position);
+			},
+		}
+		control = next_control;
+	}
+	outlines.push(outline);
+	outlines.retain(|outline| outline.len() >= 3);
+
+	outlines
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    #[test]
+    fn svg_shapes_are_read_as_outlines() {
+    	let source = r#"<svg viewBox="0 0 10 10">
+    		<path d="M1,1 h8 v8 h-8 z m2 2 L7 3 7 7 3 7 Z"/>
+    		<rect x="0" y="0" width="2" height="1"/>
+    		<polygon points="0,0 1-1 2,0"/>
+    	</svg>"#;
+    	let outlines = svg_outlines(source);
+
+    	assert_eq!(outlines.len(), 4);
+    	assert_eq!(outlines[0], [vec2(1.0, 1.0), vec2(9.0, 1.0), vec2(9.0, 9.0), vec2(1.0, 9.0)]);
+    	// the relative move starts where the last part closed
+    	assert_eq!(outlines[1][0], vec2(3.0, 3.0));
+    	assert_eq!(outlines[3][1], vec2(1.0, -1.0));
+    }
+
+    #[test]
+    fn holes_cut_out_of_shapes() {
+    	let source = r#"<path d="M0 0 H4 V4 H0 Z M1 1 H3 V3 H1 Z"/>"#;
+    	let shape = StencilShape::from_svg(source, vec2(0.5, 0.5), 0.4, 1.0).unwrap();
+
+    	assert!(shape.contains(vec2(0.35, 0.5)));
+    	assert!(!shape.contains(vec2(0.5, 0.5)));
+    	assert!(!shape.contains(vec2(0.8, 0.5)));
+    	assert!((shape.edge_distance(vec2(0.5, 0.5)) - 0.1).abs() < 1e-5);
+    	assert_eq!(StencilShape::from_svg("<svg></svg>", vec2(0.5, 0.5), 0.4, 1.0), None);
+    }
+
+    #[test]
+    fn stencils_cut_and_raise_the_surface_under_them() {
+    	let mut editor = Editor::default();
+    	editor.set_brush_size(0.3);
+    	editor.add(0.5, 0.5);
+    	let (origin, direction) = editor.screen_ray(0.5, 0.5);
+    	let surface = editor.get_document().raycast(origin, direction).unwrap().position;
+    	let voxel = editor.get_document().get_voxel_size();
+
+    	let circle = StencilShape::circle(vec2(0.5, 0.5), 0.05, editor.get_stencil_aspect());
+    	assert!(editor.project_stencil(&circle, 4, true));
+    	assert!(!editor.get_document().is_solid(surface + direction * 2.5 * voxel));
+    	assert!(editor.get_document().is_solid(surface + direction * 5.5 * voxel));
+    	// outside of the circle, the surface is left alone
+    	let (origin, direction) = editor.screen_ray(0.5, 0.6);
+    	let beside = editor.get_document().raycast(origin, direction).unwrap().position;
+    	assert!(editor.get_document().is_solid(beside + direction * 0.5 * voxel));
+
+    	let square = StencilShape::polygon(&[vec2(0.55, 0.55), vec2(0.65, 0.55), vec2(0.65, 0.65), vec2(0.55, 0.65)], 1.0);
+    	let (origin, direction) = editor.screen_ray(0.6, 0.6);
+    	let before = editor.get_document().raycast(origin, direction).unwrap().position;
+    	assert!(editor.project_stencil(&square, 2, false));
+    	assert!(editor.get_document().is_solid(before - direction * 1.5 * voxel));
+    	assert!(!editor.get_document().is_solid(before - direction * 3.5 * voxel));
+
+    	let missed = StencilShape::circle(vec2(0.05, 0.05), 0.02, 1.0);
+    	assert!(!editor.project_stencil(&missed, 4, true));
+    }
+}
@@ -0,0 +1,128 @@
+//! Timelapse capture of the sculpt as it is worked on.
+//!
+//! While a timelapse runs, every few finished strokes ask for a frame
+//! to be captured into an image sequence, numbered in order. Frames are
+//! captured from the live view, or from a bookmarked view so the
+//! sequence holds still however the camera is moved while sculpting.
+
+use super::Editor;
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use glam::Mat4;
+
+/// A running timelapse.
+pub struct Timelapse {
+	directory: PathBuf,
+	/// How many strokes are finished between frames.
+	every: u32,
+	/// The bookmarked view frames are captured from, or None for the live view.
+	view: Option<usize>,
+	strokes: u32,
+	frames: u32,
+}
+
+/// A frame of a timelapse waiting to be captured.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimelapseCapture {
+	pub path: PathBuf,
+	/// The view projection to capture from, or None for the live view.
+	pub view: Option<Mat4>,
+}
+
+impl Editor {
+	/// Start capturing a frame into a directory every few finished strokes, starting with one right away.
+	///
+	/// Frames are captured from a bookmarked view if one is given, which
+	/// has to be stored. Any running timelapse is stopped.
+	pub fn start_timelapse(&mut self, directory: impl Into<PathBuf>, every: u32, view: Option<usize>) -> io::Result<()> {
+		if view.is_some_and(|slot| !self.has_view(slot)) {
+			return Err(io::Error::new(io::ErrorKind::NotFound, "the view isn't stored"));
+		}
+		let directory = directory.into();
+		fs::create_dir_all(&directory)?;
+
+		self.timelapse = Some(Timelapse {
+			directory,
+			every: every.max(1),
+			view,
+			strokes: 0,
+			frames: 0,
+		});
+		self.queue_timelapse_frame();
+
+		Ok(())
+	}
+
+	/// Stop the timelapse, returning how many frames it captured, if one was running.
+	pub fn stop_timelapse(&mut self) -> Option<u32> {
+		self.timelapse_capture = None;
+
+		self.timelapse.take().map(|timelapse| timelapse.frames)
+	}
+
+	/// Take the frame of the timelapse waiting to be captured, if any.
+	pub fn take_timelapse_capture(&mut self) -> Option<TimelapseCapture> {
+		self.timelapse_capture.take()
+	}
+
+	/// Count a finished stroke, queueing a frame once enough strokes were finished.
+	pub(super) fn count_timelapse_stroke(&mut self) {
+		let Some(timelapse) = self.timelapse.as_mut() else {
+			return;
+		};
+		timelapse.strokes += 1;
+		if timelapse.strokes % timelapse.every == 0 {
+			self.queue_timelapse_frame();
+		}
+	}
+
+	/// Queue the next frame of the timelapse for capture.
+	fn queue_timelapse_frame(&mut self) {
+		let Some(timelapse) = self.timelapse.as_mut() else {
+			return;
+		};
+		let path = timelapse.directory.join(format!("frame-{:05}.png", timelapse.frames));
+		timelapse.frames += 1;
+		let view = timelapse.view
+			.and_then(|slot| self.views.get(&slot))
+			.map(|camera| camera.view_projection(self.aspect));
+
+		self.timelapse_capture = Some(TimelapseCapture { path, view });
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    #[test]
+    fn frames_are_queued_every_few_strokes() {
+    	let directory = std::env::temp_dir().join(format!("swirlix-timelapse-{}", std::process::id()));
+    	let mut editor = Editor::default();
+    	assert!(editor.start_timelapse(&directory, 2, Some(1)).is_err());
+
+    	editor.start_timelapse(&directory, 2, None).unwrap();
+    	assert!(directory.is_dir());
+    	// the state the timelapse starts from is its first frame
+    	assert_eq!(editor.take_timelapse_capture(), Some(TimelapseCapture { path: directory.join("frame-00000.png"), view: None }));
+
+    	editor.begin_stroke();
+    	editor.end_stroke();
+    	assert_eq!(editor.take_timelapse_capture(), None);
+    	editor.begin_stroke();
+    	editor.end_stroke();
+    	assert_eq!(editor.take_timelapse_capture().unwrap().path, directory.join("frame-00001.png"));
+
+    	// bookmarked views hold the frames still
+    	editor.store_view(1);
+    	editor.start_timelapse(&directory, 1, Some(1)).unwrap();
+    	assert!(editor.take_timelapse_capture().unwrap().view.is_some());
+
+    	assert_eq!(editor.stop_timelapse(), Some(1));
+    	assert_eq!(editor.stop_timelapse(), None);
+    	fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,147 @@
+//! Frame-latched uploads of the sculpted chunks to the renderer.
+//!
+//! Edits never reach the GPU directly. They only mark the document as
+//! changed, and once a frame the app latches the chunks of every shown
+//! object, sharing them rather than copying them. Chunks are copied on
+//! write, so an edit made while a latched frame is still being read
+//! goes to a fresh copy, and every buffer written comes from a whole
+//! tree as it was at the latch. Edits within one frame share an upload.
+
+use super::Editor;
+use crate::aabb::Aabb;
+use crate::brush;
+use crate::material::GpuMaterial;
+use crate::scene::Transform;
+use crate::sculpt::{Channel, Sculpt};
+
+use std::sync::Arc;
+
+use glam::{IVec3, Mat4};
+
+/// A chunk as it was when it was latched.
+struct LatchedChunk {
+	object: usize,
+	coordinate: IVec3,
+	bounds: Aabb,
+	resolution: u32,
+	/// What the third buffer of the chunk shows.
+	channel: Channel,
+	sculpt: Arc<Sculpt>,
+}
+
+/// The buffers of a latched chunk, ready to be written to the GPU.
+pub struct ChunkPage {
+	pub object: usize,
+	pub coordinate: IVec3,
+	pub bounds: Aabb,
+	pub resolution: u32,
+	pub voxels: Vec<u32>,
+	/// The edit heat, or the mask, with one value per voxel buffer entry.
+	pub heat: Vec<f32>,
+	pub paint: Vec<u32>,
+}
+
+/// The shown objects as they were at the start of a frame.
+pub struct UploadFrame {
+	materials: Vec<GpuMaterial>,
+	/// The placements of each shown object, by its index.
+	transforms: Vec<(usize, Vec<Mat4>)>,
+	chunks: Vec<LatchedChunk>,
+}
+
+impl UploadFrame {
+	/// Get the material buffer.
+	pub fn get_materials(&self) -> &[GpuMaterial] {
+		&self.materials
+	}
+
+	/// Get the placements of each shown object, by its index.
+	pub fn get_transforms(&self) -> &[(usize, Vec<Mat4>)] {
+		&self.transforms
+	}
+
+	/// Get how many chunks were latched.
+	pub fn get_chunk_count(&self) -> usize {
+		self.chunks.len()
+	}
+
+	/// Build the buffers of every latched chunk, one at a time.
+	pub fn pages(&self) -> impl Iterator<Item = ChunkPage> + '_ {
+		self.chunks.iter().map(|chunk| ChunkPage {
+			object: chunk.object,
+			coordinate: chunk.coordinate,
+			bounds: chunk.bounds,
+			resolution: chunk.resolution,
+			voxels: chunk.sculpt.get_voxel_buffer(),
+			heat: chunk.sculpt.get_channel_buffer(chunk.channel),
+			paint: chunk.sculpt.get_paint_buffer(),
+		})
+	}
+}
+
+impl Editor {
+	/// Latch the shown objects for an upload, showing their mask instead of the edit heat if asked.
+	pub fn latch_upload(&self, mask: bool) -> UploadFrame {
+		let scene = self.get_scene();
+		let shown: Vec<_> = scene.get_objects().iter().enumerate().filter(|(index, _)| scene.is_shown(*index)).collect();
+
+		let transforms = shown.iter()
+			.map(|(index, object)| (*index, object.placements().map(Transform::to_matrix).collect()))
+			.collect();
+		let chunks = shown.iter()
+			.flat_map(|(index, object)| {
+				let document = &object.document;
+				let channel = if mask {
+					Channel::Mask
+				} else {
+					Channel::Heat {
+						stroke: document.get_stroke(),
+						decay: brush::HEAT_DECAY,
+					}
+				};
+
+				document.latch_chunks().into_iter().map(move |(coordinate, sculpt)| LatchedChunk {
+					object: *index,
+					coordinate,
+					bounds: document.get_chunk_bounds(coordinate),
+					resolution: document.get_resolution(),
+					channel,
+					sculpt,
+				})
+			})
+			.collect();
+
+		UploadFrame {
+			materials: self.get_material_buffer(),
+			transforms,
+			chunks,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    #[test]
+    fn latched_frames_ignore_later_edits() {
+    	let mut editor = Editor::default();
+    	editor.add(0.5, 0.5);
+    	let before = editor.get_voxel_buffers(0);
+
+    	let latched = editor.latch_upload(false);
+    	for _ in 0..4 {
+    		editor.remove(0.5, 0.5);
+    	}
+    	let after = editor.get_voxel_buffers(0);
+    	assert_ne!(after, before);
+
+    	// the latched frame still reads the chunks as they were before the edits
+    	let pages: Vec<_> = latched.pages().map(|page| (page.coordinate, page.voxels)).collect();
+    	assert_eq!(pages, before);
+    	assert_eq!(latched.get_transforms().len(), 1);
+
+    	let current: Vec<_> = editor.latch_upload(false).pages().map(|page| (page.coordinate, page.voxels)).collect();
+    	assert_eq!(current, after);
+    }
+}
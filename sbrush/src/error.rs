@@ -0,0 +1,100 @@
+//! The errors of the library, for callers to handle rather than crash on.
+
+use crate::i18n::{tr, tr_args};
+
+use std::fmt;
+use std::io;
+
+use winit::error::{EventLoopError, OsError};
+
+/// The result of the library's fallible calls.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// What went wrong in the library.
+#[derive(Debug)]
+pub enum Error {
+	/// Reading or writing a file failed.
+	Io(io::Error),
+	/// A material isn't in the palette, by index and the count of materials.
+	MissingMaterial { index: u32, count: usize },
+	/// There is no scene object with the index.
+	MissingObject(usize),
+	/// There is no scene object with the name.
+	UnknownObject(String),
+	/// The object wasn't opened from or saved to a file, so it can't be read again.
+	NoFile,
+	/// A palette file has no colors.
+	EmptyPalette,
+	/// No GPU adapter can draw to the window.
+	NoAdapter,
+	/// The window can't be drawn to by the chosen adapter.
+	UnsupportedSurface,
+	Surface(wgpu::CreateSurfaceError),
+	Device(wgpu::RequestDeviceError),
+	EventLoop(EventLoopError),
+	Window(OsError),
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let message = match self {
+			Error::Io(error) => error.to_string(),
+			Error::MissingMaterial { index, count } => tr_args("error-missing-material", &[("index", &index.to_string()), ("count", &count.to_string())]),
+			Error::MissingObject(index) => tr_args("error-missing-object", &[("index", &index.to_string())]),
+			Error::UnknownObject(name) => tr_args("error-unknown-object", &[("name", name)]),
+			Error::NoFile => tr("error-no-file"),
+			Error::EmptyPalette => tr("error-empty-palette"),
+			Error::NoAdapter => tr("error-no-adapter"),
+			Error::UnsupportedSurface => tr("error-unsupported-surface"),
+			Error::Surface(error) => tr_args("error-surface", &[("error", &error.to_string())]),
+			Error::Device(error) => tr_args("error-device", &[("error", &error.to_string())]),
+			Error::EventLoop(error) => tr_args("error-event-loop", &[("error", &error.to_string())]),
+			Error::Window(error) => tr_args("error-window", &[("error", &error.to_string())]),
+		};
+
+		f.write_str(&message)
+	}
+}
+
+impl std::error::Error for Error {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Error::Io(error) => Some(error),
+			Error::Surface(error) => Some(error),
+			Error::Device(error) => Some(error),
+			Error::EventLoop(error) => Some(error),
+			Error::Window(error) => Some(error),
+			_ => None,
+		}
+	}
+}
+
+impl From<io::Error> for Error {
+	fn from(error: io::Error) -> Self {
+		Error::Io(error)
+	}
+}
+
+impl From<wgpu::CreateSurfaceError> for Error {
+	fn from(error: wgpu::CreateSurfaceError) -> Self {
+		Error::Surface(error)
+	}
+}
+
+impl From<wgpu::RequestDeviceError> for Error {
+	fn from(error: wgpu::RequestDeviceError) -> Self {
+		Error::Device(error)
+	}
+}
+
+impl From<EventLoopError> for Error {
+	fn from(error: EventLoopError) -> Self {
+		Error::EventLoop(error)
+	}
+}
+
+impl From<OsError> for Error {
+	fn from(error: OsError) -> Self {
+		Error::Window(error)
+	}
+}
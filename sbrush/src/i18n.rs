@@ -0,0 +1,164 @@
+//! Translated user-facing strings.
+//!
+//! Messages live in Fluent-style catalogs (`key = value` lines,
+//! `{ $name }` placeholders, `#` comments). The active catalog can
+//! be switched at runtime, and anything it is missing falls back
+//! to English, then to the message key itself.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::{LazyLock, RwLock};
+
+/// The catalogs shipped with the application.
+const BUILT_IN: [(&str, &str); 2] = [
+	("en", include_str!("../locales/en.ftl")),
+	("de", include_str!("../locales/de.ftl")),
+];
+
+static ENGLISH: LazyLock<Catalog> = LazyLock::new(|| built_in("en").unwrap());
+
+static CURRENT: LazyLock<RwLock<Catalog>> = LazyLock::new(|| RwLock::new(ENGLISH.clone()));
+
+/// The messages of a single locale.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Catalog {
+	locale: String,
+	messages: HashMap<String, String>,
+}
+
+impl Catalog {
+	/// Parse a catalog from its source.
+	pub fn parse(locale: &str, source: &str) -> io::Result<Self> {
+		let mut messages = HashMap::new();
+
+		for (number, line) in source.lines().enumerate() {
+			let line = line.trim();
+			if line.is_empty() || line.starts_with('#') {
+				continue;
+			}
+
+			let Some((key, value)) = line.split_once('=') else {
+				return Err(io::Error::new(
+					io::ErrorKind::InvalidData,
+					format!("line {} of the {locale} catalog is not a message", number + 1),
+				));
+			};
+			messages.insert(key.trim().to_owned(), value.trim().to_owned());
+		}
+
+		Ok(Self {
+			locale: locale.to_owned(),
+			messages,
+		})
+	}
+
+	/// Load a catalog from a file, named by its locale.
+	pub fn load(locale: &str, path: impl AsRef<Path>) -> io::Result<Self> {
+		Self::parse(locale, &fs::read_to_string(path)?)
+	}
+
+	/// Get the locale of the catalog.
+	pub fn get_locale(&self) -> &str {
+		&self.locale
+	}
+
+	/// Get a message, filling in its placeholders.
+	pub fn format(&self, key: &str, args: &[(&str, &str)]) -> Option<String> {
+		let mut message = self.messages.get(key)?.clone();
+		for (name, value) in args {
+			message = message.replace(&format!("{{ ${name} }}"), value);
+		}
+
+		Some(message)
+	}
+}
+
+/// Get one of the catalogs shipped with the application.
+pub fn built_in(locale: &str) -> Option<Catalog> {
+	BUILT_IN.iter()
+		.find(|(name, _)| *name == locale)
+		.map(|(name, source)| Catalog::parse(name, source).expect("Built-in catalogs should parse."))
+}
+
+/// Get the locales shipped with the application.
+pub fn built_in_locales() -> Vec<&'static str> {
+	BUILT_IN.iter().map(|(name, _)| *name).collect()
+}
+
+/// Guess the locale of the user from the environment.
+///
+/// `SWIRLIX_LANG` takes priority over the usual `LANG`
+/// (e.g. `de_DE.UTF-8` becomes `de`).
+pub fn system_locale() -> String {
+	let value = std::env::var("SWIRLIX_LANG")
+		.or_else(|_| std::env::var("LANG"))
+		.unwrap_or_default();
+
+	value.split(['_', '.', '-']).next().unwrap_or_default().to_lowercase()
+}
+
+/// Switch to a catalog for all following messages.
+pub fn set_catalog(catalog: Catalog) {
+	*CURRENT.write().unwrap() = catalog;
+}
+
+/// Switch to a built-in locale, returning false if there is none.
+pub fn set_locale(locale: &str) -> bool {
+	let Some(catalog) = built_in(locale) else {
+		return false;
+	};
+	set_catalog(catalog);
+
+	true
+}
+
+/// Get the locale of the active catalog.
+pub fn get_locale() -> String {
+	CURRENT.read().unwrap().get_locale().to_owned()
+}
+
+/// Get a translated message.
+pub fn tr(key: &str) -> String {
+	tr_args(key, &[])
+}
+
+/// Get a translated message, filling in its placeholders.
+pub fn tr_args(key: &str, args: &[(&str, &str)]) -> String {
+	CURRENT.read().unwrap().format(key, args)
+		.or_else(|| ENGLISH.format(key, args))
+		.unwrap_or_else(|| key.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    #[test]
+    fn built_in_catalogs_have_every_message() {
+    	let english = built_in("en").unwrap();
+
+    	for locale in built_in_locales() {
+    		let catalog = built_in(locale).unwrap();
+    		for key in english.messages.keys() {
+    			assert!(catalog.messages.contains_key(key), "{locale} is missing {key}");
+    		}
+    	}
+    }
+
+    #[test]
+    fn format_fills_placeholders() {
+    	let catalog = Catalog::parse("en", "# comment\n\nsaved = Saved { $path } in { $time }s\n").unwrap();
+
+    	assert_eq!(catalog.format("saved", &[("path", "a.png"), ("time", "2")]), Some("Saved a.png in 2s".to_owned()));
+    	assert_eq!(catalog.format("missing", &[]), None);
+    	assert!(Catalog::parse("en", "no equals sign").is_err());
+    }
+
+    #[test]
+    fn missing_messages_fall_back() {
+    	assert_eq!(tr("brush-round"), "Round Brush");
+    	assert_eq!(tr("no-such-message"), "no-such-message");
+    }
+}
@@ -0,0 +1,358 @@
+use crate::i18n::{tr, tr_args};
+
+use std::collections::VecDeque;
+use std::collections::vec_deque::Drain;
+use std::time::Duration;
+
+use glam::Vec2;
+use winit::event::MouseButton;
+use winit::keyboard::{KeyCode, ModifiersState};
+
+/// Something the user can trigger from an input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+	Add,
+	Remove,
+	RoundBrush,
+	SquareBrush,
+	SprayBrush,
+	VoxelPen,
+	/// Switching to the brush bound to a numbered slot.
+	BrushSlot(usize),
+	LastBrush,
+	ToggleSmoothing,
+	BeautyRender,
+	ToggleHelp,
+	ToggleMaterials,
+	ToggleProfile,
+	ToggleHeat,
+	ToggleMask,
+	TogglePaint,
+	ToggleGuides,
+	ToggleTraversal,
+	ToggleMesh,
+	CyclePresentMode,
+	CycleTonemap,
+	IncreaseExposure,
+	DecreaseExposure,
+	Screenshot,
+	CommandPalette,
+	/// Saving the active object to its file, or asking where to.
+	Save,
+	Open,
+	Undo,
+	Redo,
+	Cancel,
+}
+
+impl Action {
+	/// Get the message key of the action's description.
+	fn message(&self) -> &'static str {
+		match self {
+			Action::Add => "action-add",
+			Action::Remove => "action-remove",
+			Action::RoundBrush => "action-round-brush",
+			Action::SquareBrush => "action-square-brush",
+			Action::SprayBrush => "action-spray-brush",
+			Action::VoxelPen => "action-voxel-pen",
+			Action::BrushSlot(_) => "action-brush-slot",
+			Action::LastBrush => "action-last-brush",
+			Action::ToggleSmoothing => "action-toggle-smoothing",
+			Action::BeautyRender => "action-beauty-render",
+			Action::ToggleHelp => "action-toggle-help",
+			Action::ToggleMaterials => "action-toggle-materials",
+			Action::ToggleProfile => "action-toggle-profile",
+			Action::ToggleHeat => "action-toggle-heat",
+			Action::ToggleMask => "action-toggle-mask",
+			Action::TogglePaint => "action-toggle-paint",
+			Action::ToggleGuides => "action-toggle-guides",
+			Action::ToggleTraversal => "action-toggle-traversal",
+			Action::ToggleMesh => "action-toggle-mesh",
+			Action::CyclePresentMode => "action-cycle-present-mode",
+			Action::CycleTonemap => "action-cycle-tonemap",
+			Action::IncreaseExposure => "action-increase-exposure",
+			Action::DecreaseExposure => "action-decrease-exposure",
+			Action::Screenshot => "action-screenshot",
+			Action::CommandPalette => "action-command-palette",
+			Action::Save => "action-save",
+			Action::Open => "action-open",
+			Action::Undo => "action-undo",
+			Action::Redo => "action-redo",
+			Action::Cancel => "action-cancel",
+		}
+	}
+
+	/// Get a short, translated description of what the action does.
+	pub fn describe(&self) -> String {
+		match self {
+			Action::BrushSlot(slot) => tr_args(self.message(), &[("slot", &slot.to_string())]),
+			_ => tr(self.message()),
+		}
+	}
+}
+
+/// A key or button which can be bound to an action.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Binding {
+	Key(KeyCode),
+	/// A key pressed while holding modifiers, like Ctrl+P.
+	Chord(ModifiersState, KeyCode),
+	Mouse(MouseButton),
+}
+
+impl Binding {
+	/// Get a readable name for the binding.
+	///
+	/// Mouse buttons are translated, while keys keep their printed name.
+	pub fn label(&self) -> String {
+		match self {
+			Binding::Key(key) => key_label(*key),
+			Binding::Chord(modifiers, key) => {
+				let mut label = String::new();
+				for (modifier, name) in [
+					(ModifiersState::CONTROL, "Ctrl+"),
+					(ModifiersState::ALT, "Alt+"),
+					(ModifiersState::SUPER, "Super+"),
+					(ModifiersState::SHIFT, "Shift+"),
+				] {
+					if modifiers.contains(modifier) {
+						label.push_str(name);
+					}
+				}
+				label.push_str(&key_label(*key));
+
+				label
+			},
+			Binding::Mouse(MouseButton::Left) => tr("binding-left-click"),
+			Binding::Mouse(MouseButton::Right) => tr("binding-right-click"),
+			Binding::Mouse(MouseButton::Middle) => tr("binding-middle-click"),
+			Binding::Mouse(button) => tr_args("binding-other-click", &[("button", &format!("{button:?}"))]),
+		}
+	}
+}
+
+/// Get the printed name of a key.
+fn key_label(key: KeyCode) -> String {
+	let name = format!("{key:?}");
+	let name = name.strip_prefix("Key").or(name.strip_prefix("Digit")).unwrap_or(&name);
+
+	name.to_owned()
+}
+
+/// The bindings from inputs to actions.
+///
+/// Bindings are kept in the order they were added,
+/// which is also the order they are listed in for help.
+pub struct InputMap {
+	bindings: Vec<(Binding, Action)>,
+}
+
+impl Default for InputMap {
+	/// The default controls.
+	fn default() -> Self {
+		let mut input_map = Self::new();
+		input_map.bind(Binding::Mouse(MouseButton::Left), Action::Add);
+		input_map.bind(Binding::Mouse(MouseButton::Right), Action::Remove);
+		input_map.bind(Binding::Key(KeyCode::KeyR), Action::RoundBrush);
+		input_map.bind(Binding::Key(KeyCode::KeyS), Action::SquareBrush);
+		input_map.bind(Binding::Key(KeyCode::KeyA), Action::SprayBrush);
+		input_map.bind(Binding::Key(KeyCode::KeyG), Action::VoxelPen);
+		let digits = [
+			KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3, KeyCode::Digit4, KeyCode::Digit5,
+			KeyCode::Digit6, KeyCode::Digit7, KeyCode::Digit8, KeyCode::Digit9,
+		];
+		for (slot, key) in digits.into_iter().enumerate() {
+			input_map.bind(Binding::Key(key), Action::BrushSlot(slot + 1));
+		}
+		input_map.bind(Binding::Key(KeyCode::KeyX), Action::LastBrush);
+		input_map.bind(Binding::Key(KeyCode::KeyL), Action::ToggleSmoothing);
+		input_map.bind(Binding::Key(KeyCode::KeyP), Action::BeautyRender);
+		input_map.bind(Binding::Key(KeyCode::KeyE), Action::ToggleHeat);
+		input_map.bind(Binding::Key(KeyCode::KeyM), Action::ToggleMask);
+		input_map.bind(Binding::Key(KeyCode::KeyC), Action::TogglePaint);
+		input_map.bind(Binding::Key(KeyCode::KeyD), Action::ToggleGuides);
+		input_map.bind(Binding::Key(KeyCode::KeyH), Action::ToggleHelp);
+		input_map.bind(Binding::Key(KeyCode::KeyB), Action::ToggleMaterials);
+		input_map.bind(Binding::Key(KeyCode::F2), Action::ToggleProfile);
+		input_map.bind(Binding::Key(KeyCode::F3), Action::ToggleTraversal);
+		input_map.bind(Binding::Key(KeyCode::F4), Action::ToggleMesh);
+		input_map.bind(Binding::Key(KeyCode::KeyV), Action::CyclePresentMode);
+		input_map.bind(Binding::Key(KeyCode::KeyT), Action::CycleTonemap);
+		input_map.bind(Binding::Key(KeyCode::BracketRight), Action::IncreaseExposure);
+		input_map.bind(Binding::Key(KeyCode::BracketLeft), Action::DecreaseExposure);
+		input_map.bind(Binding::Key(KeyCode::F12), Action::Screenshot);
+		input_map.bind(Binding::Chord(ModifiersState::CONTROL, KeyCode::KeyP), Action::CommandPalette);
+		input_map.bind(Binding::Chord(ModifiersState::CONTROL, KeyCode::KeyS), Action::Save);
+		input_map.bind(Binding::Chord(ModifiersState::CONTROL, KeyCode::KeyO), Action::Open);
+		input_map.bind(Binding::Chord(ModifiersState::CONTROL, KeyCode::KeyZ), Action::Undo);
+		input_map.bind(Binding::Chord(ModifiersState::CONTROL | ModifiersState::SHIFT, KeyCode::KeyZ), Action::Redo);
+		input_map.bind(Binding::Key(KeyCode::Escape), Action::Cancel);
+
+		input_map
+	}
+}
+
+impl InputMap {
+	/// Create an input map without any bindings.
+	pub fn new() -> Self {
+		Self {
+			bindings: Vec::new(),
+		}
+	}
+
+	/// Bind an input to an action, replacing any previous binding of the input.
+	pub fn bind(&mut self, binding: Binding, action: Action) {
+		self.bindings.retain(|(bound, _)| *bound != binding);
+		self.bindings.push((binding, action));
+	}
+
+	/// Get the action bound to an input.
+	pub fn get_action(&self, binding: Binding) -> Option<Action> {
+		self.bindings.iter()
+			.find(|(bound, _)| *bound == binding)
+			.map(|(_, action)| *action)
+	}
+
+	/// Get the action bound to a key, given the held modifiers.
+	///
+	/// Chords take priority. Without Ctrl, Alt or Super held,
+	/// the plain key binding is used as a fallback.
+	pub fn get_key_action(&self, modifiers: ModifiersState, key: KeyCode) -> Option<Action> {
+		let chord = (!modifiers.is_empty())
+			.then(|| self.get_action(Binding::Chord(modifiers, key)))
+			.flatten();
+		let command_held = modifiers.intersects(ModifiersState::CONTROL | ModifiersState::ALT | ModifiersState::SUPER);
+
+		chord.or_else(|| if command_held { None } else { self.get_action(Binding::Key(key)) })
+	}
+
+	/// Get every binding in order.
+	pub fn get_bindings(&self) -> &[(Binding, Action)] {
+		&self.bindings
+	}
+}
+
+/// How long a simulation tick is, so strokes are sampled at the same rate however fast events or frames come.
+pub const TICK: Duration = Duration::from_millis(8);
+/// The most ticks caught up on at once, so a stalled frame doesn't replay a burst of ticks.
+const MAX_CATCH_UP: u32 = 8;
+
+/// An input waiting for the next simulation tick, with points as fractions of the window.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InputEvent {
+	/// A bound key or button was pressed with the cursor at a point.
+	Press(Action, Vec2),
+	/// A button bound to a stroke was pressed with the cursor at a point.
+	BeginStroke(MouseButton, Action, Vec2),
+	/// The cursor moved to a point.
+	Move(Vec2),
+	/// A button was released.
+	Release(MouseButton),
+}
+
+/// The inputs which edit the document, queued from window events until the next simulation tick.
+///
+/// Events arrive as often as the platform sends them, while ticks come at
+/// a fixed rate. Moves between other events are merged into the last one,
+/// so a stroke gets at most one pass per tick, whatever the mouse polls at.
+#[derive(Default)]
+pub struct InputQueue {
+	events: VecDeque<InputEvent>,
+	/// The time since the last tick.
+	elapsed: Duration,
+}
+
+impl InputQueue {
+	/// Queue an input for the next tick.
+	pub fn push(&mut self, event: InputEvent) {
+		if let (InputEvent::Move(_), Some(InputEvent::Move(_))) = (event, self.events.back()) {
+			self.events.pop_back();
+		}
+		self.events.push_back(event);
+	}
+
+	/// Check whether no inputs are waiting.
+	pub fn is_empty(&self) -> bool {
+		self.events.is_empty()
+	}
+
+	/// Let time pass, returning how many ticks are due.
+	///
+	/// Ticks beyond a few are dropped rather than caught up on.
+	pub fn advance(&mut self, delta: Duration) -> u32 {
+		self.elapsed += delta;
+		let ticks = (self.elapsed.as_nanos() / TICK.as_nanos()) as u32;
+		self.elapsed -= TICK * ticks;
+
+		ticks.min(MAX_CATCH_UP)
+	}
+
+	/// Take the inputs queued for this tick, in the order they came.
+	pub fn drain(&mut self) -> Drain<'_, InputEvent> {
+		self.events.drain(..)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    #[test]
+    fn binding_replaces_previous_action() {
+    	let mut input_map = InputMap::default();
+    	let count = input_map.get_bindings().len();
+
+    	input_map.bind(Binding::Key(KeyCode::KeyR), Action::SquareBrush);
+
+    	assert_eq!(input_map.get_action(Binding::Key(KeyCode::KeyR)), Some(Action::SquareBrush));
+    	assert_eq!(input_map.get_bindings().len(), count);
+    	assert_eq!(input_map.get_action(Binding::Key(KeyCode::KeyQ)), None);
+    }
+
+    #[test]
+    fn labels_are_readable() {
+    	assert_eq!(Binding::Key(KeyCode::KeyH).label(), "H");
+    	assert_eq!(Binding::Key(KeyCode::Digit3).label(), "3");
+    	assert_eq!(Binding::Key(KeyCode::Escape).label(), "Escape");
+    	assert_eq!(Binding::Mouse(MouseButton::Left).label(), "Left Click");
+    	assert_eq!(Binding::Chord(ModifiersState::CONTROL | ModifiersState::SHIFT, KeyCode::KeyZ).label(), "Ctrl+Shift+Z");
+    }
+
+    #[test]
+    fn chords_take_priority_over_keys() {
+    	let input_map = InputMap::default();
+
+    	assert_eq!(input_map.get_key_action(ModifiersState::CONTROL, KeyCode::KeyP), Some(Action::CommandPalette));
+    	assert_eq!(input_map.get_key_action(ModifiersState::empty(), KeyCode::KeyP), Some(Action::BeautyRender));
+    	assert_eq!(input_map.get_key_action(ModifiersState::CONTROL, KeyCode::KeyR), None);
+    	assert_eq!(input_map.get_key_action(ModifiersState::CONTROL | ModifiersState::SHIFT, KeyCode::KeyZ), Some(Action::Redo));
+    	assert_eq!(input_map.get_key_action(ModifiersState::SHIFT, KeyCode::KeyR), Some(Action::RoundBrush));
+    	assert_eq!(input_map.get_key_action(ModifiersState::empty(), KeyCode::Digit3), Some(Action::BrushSlot(3)));
+    }
+
+    #[test]
+    fn inputs_wait_for_fixed_ticks() {
+    	let mut queue = InputQueue::default();
+    	queue.push(InputEvent::BeginStroke(MouseButton::Left, Action::Add, Vec2::ZERO));
+    	queue.push(InputEvent::Move(Vec2::splat(0.1)));
+    	queue.push(InputEvent::Move(Vec2::splat(0.2)));
+    	queue.push(InputEvent::Release(MouseButton::Left));
+    	queue.push(InputEvent::Move(Vec2::splat(0.3)));
+
+    	// ticks come at the same times however the time is split up
+    	assert_eq!(queue.advance(TICK / 2), 0);
+    	assert_eq!(queue.advance(TICK / 2), 1);
+    	assert_eq!(queue.advance(TICK * 5 / 2), 2);
+    	assert_eq!(queue.advance(TICK / 2), 1);
+    	assert_eq!(queue.advance(Duration::from_secs(1)), MAX_CATCH_UP);
+
+    	// moves between other inputs only keep the last one
+    	assert_eq!(queue.drain().collect::<Vec<_>>(), [
+    		InputEvent::BeginStroke(MouseButton::Left, Action::Add, Vec2::ZERO),
+    		InputEvent::Move(Vec2::splat(0.2)),
+    		InputEvent::Release(MouseButton::Left),
+    		InputEvent::Move(Vec2::splat(0.3)),
+    	]);
+    	assert!(queue.is_empty());
+    }
+}
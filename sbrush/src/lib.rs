@@ -5,11 +5,32 @@
 //! The library crate for a sculpting tool built on sparse 
 //! voxel octrees and the ray marching.
 
+mod aabb;
 mod app;
+mod camera;
+mod color;
+mod document;
 mod editor;
+mod error;
+mod i18n;
+mod input;
+mod noise;
+mod overlay;
+mod palette;
 mod renderer;
+mod rng;
+mod scene;
 mod sculpt;
+mod stats;
+mod util;
 mod brush;
 mod material;
+mod light;
+mod path_tracer;
+#[cfg(feature = "py")]
+mod py;
 
 pub use app::App;
+pub use editor::Editor;
+pub use error::{Error, Result};
+pub use sculpt::{Coverage, Ray, Sculpt, SculptHit};
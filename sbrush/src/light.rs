@@ -0,0 +1,72 @@
+//! The lights the viewport shades the sculpt with.
+//!
+//! Without lights of its own, the sculpt is lit from the camera, so
+//! whatever faces the view is lit. A rig of a few directional and point
+//! lights stays put in the world instead, for presenting a sculpt from
+//! every side.
+
+use bytemuck::{Pod, Zeroable};
+use glam::Vec3;
+
+/// The most lights the viewport shades with at once.
+pub const MAX_LIGHTS: usize = 8;
+
+/// Where the light of a light comes from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LightKind {
+	/// Parallel light shining along a direction, like sunlight.
+	Directional(Vec3),
+	/// Light spreading out from a point in the world, fading with the square of the distance.
+	Point(Vec3),
+}
+
+/// A light of the viewport.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Light {
+	pub kind: LightKind,
+	/// The linear color, in the working space.
+	pub color: [f32; 3],
+	pub intensity: f32,
+}
+
+impl Light {
+	/// Create a white light.
+	pub fn new(kind: LightKind, intensity: f32) -> Self {
+		Self {
+			kind,
+			color: [1.0; 3],
+			intensity,
+		}
+	}
+
+	/// Convert the light to the buffer data structure.
+	pub fn to_buffer(self) -> GpuLight {
+		let (vector, kind) = match self.kind {
+			LightKind::Directional(direction) => (direction.normalize_or_zero(), GpuLight::DIRECTIONAL),
+			LightKind::Point(position) => (position, GpuLight::POINT),
+		};
+
+		GpuLight {
+			vector: vector.to_array(),
+			kind,
+			color: self.color,
+			intensity: self.intensity,
+		}
+	}
+}
+
+/// A light as laid out in the light uniform.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct GpuLight {
+	/// The direction a directional light shines along, or the position a point light spreads out from.
+	pub vector: [f32; 3],
+	pub kind: u32,
+	pub color: [f32; 3],
+	pub intensity: f32,
+}
+
+impl GpuLight {
+	pub const DIRECTIONAL: u32 = 0;
+	pub const POINT: u32 = 1;
+}
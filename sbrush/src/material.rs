@@ -1,5 +1,8 @@
 use std::hash::{Hash, Hasher};
 
+use bytemuck::{Pod, Zeroable};
+use glam::{DVec3, IVec3, Vec3};
+
 /// A material to encode surface attributes.
 #[derive(Clone, Copy)]
 pub struct Material {
@@ -7,19 +10,103 @@ pub struct Material {
 	pub color: [f32; 4],
 	pub roughness: f32,
 	pub metallic: f32,
+	/// How much light scatters under the surface, between 0 for none and 1.
+	pub subsurface: f32,
+}
+
+/// The materials a brush fills new voxels with, dithering between two of them.
+///
+/// Each leaf stores a single material, so a mix like 70% of one and
+/// 30% of another is made by picking one of them per leaf.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MaterialMix {
+	pub first: u32,
+	pub second: u32,
+	/// How much of the second material is mixed in, between 0 and 1.
+	pub mix: f32,
+}
+
+impl MaterialMix {
+	/// Fill with a single material.
+	pub fn single(material: u32) -> Self {
+		Self {
+			first: material,
+			second: material,
+			mix: 0.0,
+		}
+	}
+
+	/// Check whether both materials show up in the fill.
+	pub fn is_mixed(&self) -> bool {
+		self.first != self.second && self.mix > 0.0 && self.mix < 1.0
+	}
+
+	/// Pick the material of a voxel cell.
+	pub fn pick(&self, cell: IVec3) -> u32 {
+		if dither_threshold(cell) < self.mix { self.second } else { self.first }
+	}
+}
+
+/// A ramp of palette materials laid out between two points.
+///
+/// Points are projected onto the line from the start to the end, and
+/// the ramp is split into even bands along it. Points beyond either end
+/// take the material at that end.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradient {
+	pub start: Vec3,
+	pub end: Vec3,
+	pub ramp: Vec<u32>,
+}
+
+impl Gradient {
+	/// Pick the material at a point.
+	pub fn pick(&self, point: Vec3) -> u32 {
+		let line = self.end - self.start;
+		let along = if line.length_squared() > 0.0 { (point - self.start).dot(line) / line.length_squared() } else { 0.0 };
+		let band = (along.clamp(0.0, 1.0) * self.ramp.len() as f32) as usize;
+
+		self.ramp.get(band.min(self.ramp.len().saturating_sub(1))).copied().unwrap_or(0)
+	}
+}
+
+/// Get a threshold between 0 and 1 for a voxel cell, to dither with.
+///
+/// The thresholds follow a low discrepancy sequence, which spreads them
+/// like blue noise: neighboring cells get thresholds far apart, so mixes
+/// come out evenly grained, without clumps of either material.
+pub fn dither_threshold(cell: IVec3) -> f32 {
+	// the generalized golden ratio for three dimensions, whose inverse powers step through the sequence
+	const PHI: f64 = 1.220_744_084_605_759_5;
+	let steps = DVec3::new(1.0 / PHI, 1.0 / (PHI * PHI), 1.0 / (PHI * PHI * PHI));
+
+	(cell.as_dvec3().dot(steps) + 0.5).rem_euclid(1.0) as f32
+}
+
+/// A material as laid out in the material storage buffer.
+///
+/// The padding rounds the size up to the 16 byte alignment
+/// of the color, which is the array stride in the shaders.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct GpuMaterial {
+	pub color: [f32; 4],
+	pub roughness: f32,
+	pub metallic: f32,
+	pub subsurface: f32,
+	pub _padding: [f32; 1],
 }
 
 impl Material {
 	/// Convert the material to the buffer data structure.
-	pub fn to_buffer(&self) -> [f32; 6] {
-		[
-			self.color[0],
-			self.color[1],
-			self.color[2],
-			self.color[3],
-			self.roughness,
-			self.metallic,
-		]
+	pub fn to_buffer(self) -> GpuMaterial {
+		GpuMaterial {
+			color: self.color,
+			roughness: self.roughness,
+			metallic: self.metallic,
+			subsurface: self.subsurface,
+			_padding: [0.0; 1],
+		}
 	}
 }
 
@@ -31,6 +118,7 @@ impl Default for Material {
 			color: [0.5, 0.5, 0.5, 1.0],
 			roughness: 0.5,
 			metallic: 0.0,
+			subsurface: 0.0,
 		}
 	}
 }
@@ -50,3 +138,37 @@ impl Hash for Material {
 		self.index.hash(state);
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    #[test]
+    fn gradients_split_the_ramp_into_bands() {
+    	let gradient = Gradient { start: Vec3::ZERO, end: Vec3::X, ramp: vec![4, 5, 6] };
+
+    	assert_eq!(gradient.pick(Vec3::new(0.1, 3.0, 0.0)), 4);
+    	assert_eq!(gradient.pick(Vec3::new(0.5, 0.0, -1.0)), 5);
+    	assert_eq!(gradient.pick(Vec3::new(0.9, 0.0, 0.0)), 6);
+    	assert_eq!(gradient.pick(Vec3::new(-2.0, 0.0, 0.0)), 4);
+    	assert_eq!(gradient.pick(Vec3::new(2.0, 0.0, 0.0)), 6);
+    }
+
+    #[test]
+    fn mixes_dither_evenly() {
+    	let mix = MaterialMix { first: 1, second: 2, mix: 0.3 };
+    	let cells: Vec<IVec3> = (0..16).flat_map(|z| (0..16).flat_map(move |y| (0..16).map(move |x| IVec3::new(x, y, z)))).collect();
+
+    	let second = cells.iter().filter(|cell| mix.pick(**cell) == 2).count() as f32 / cells.len() as f32;
+    	assert!((second - 0.3).abs() < 0.01);
+    	// small blocks get close to the mix too, as the pattern doesn't clump
+    	for corner in [IVec3::ZERO, IVec3::new(8, 4, 12), IVec3::new(-20, 7, 3)] {
+    		let block = (0..4).flat_map(|z| (0..4).flat_map(move |y| (0..4).map(move |x| corner + IVec3::new(x, y, z))));
+    		let count = block.filter(|cell| mix.pick(*cell) == 2).count();
+    		assert!((12..=27).contains(&count), "{count} of 64 cells at {corner}");
+    	}
+
+    	assert!(!MaterialMix::single(3).is_mixed());
+    	assert!(cells.iter().all(|cell| MaterialMix::single(3).pick(*cell) == 3));
+    }
+}
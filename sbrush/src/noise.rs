@@ -0,0 +1,73 @@
+//! Smooth, deterministic noise for procedural shapes.
+//!
+//! Value noise: each corner of a unit lattice gets a random value hashed
+//! from its cell and a seed, and the points in between blend the eight
+//! corners around them with a smoothstep, so the same seed always gives
+//! the same noise.
+
+use glam::{IVec3, Vec3};
+
+/// Get smooth noise at a point, between -1 and 1, changing about once per unit.
+pub fn value_noise(point: Vec3, seed: u32) -> f32 {
+	let low = point.floor();
+	let t = point - low;
+	let t = t * t * (3.0 - 2.0 * t);
+	let low = low.as_ivec3();
+	let corner = |x: i32, y: i32, z: i32| hash(low + IVec3::new(x, y, z), seed);
+
+	let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+	let y0 = lerp(lerp(corner(0, 0, 0), corner(1, 0, 0), t.x), lerp(corner(0, 1, 0), corner(1, 1, 0), t.x), t.y);
+	let y1 = lerp(lerp(corner(0, 0, 1), corner(1, 0, 1), t.x), lerp(corner(0, 1, 1), corner(1, 1, 1), t.x), t.y);
+
+	lerp(y0, y1, t.z)
+}
+
+/// Get three unrelated channels of noise at a point, each between -1 and 1.
+pub fn vector_noise(point: Vec3, seed: u32) -> Vec3 {
+	Vec3::new(
+		value_noise(point, mix(seed)),
+		value_noise(point, mix(seed ^ 0x9e37_79b9)),
+		value_noise(point, mix(seed ^ 0x7f4a_7c15)),
+	)
+}
+
+/// Get the random value of a lattice corner, between -1 and 1.
+fn hash(cell: IVec3, seed: u32) -> f32 {
+	let hashed = cell.to_array().iter().fold(mix(seed), |hashed, component| mix(hashed ^ *component as u32));
+
+	hashed as f32 / u32::MAX as f32 * 2.0 - 1.0
+}
+
+/// Scramble the bits of an integer, so nearby inputs give unrelated outputs.
+fn mix(mut value: u32) -> u32 {
+	value ^= value >> 16;
+	value = value.wrapping_mul(0x7feb_352d);
+	value ^= value >> 15;
+	value = value.wrapping_mul(0x846c_a68b);
+	value ^= value >> 16;
+
+	value
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    use glam::vec3;
+
+    #[test]
+    fn noise_is_smooth_and_seeded() {
+    	let point = vec3(1.3, -2.7, 0.45);
+    	let value = value_noise(point, 7);
+    	assert_eq!(value, value_noise(point, 7));
+    	assert_ne!(value, value_noise(point, 8));
+    	assert!((value - value_noise(point + 0.001, 7)).abs() < 0.01);
+
+    	let values: Vec<f32> = (0..64).map(|index| value_noise(vec3(index as f32 * 0.37, 0.5, 0.25), 3)).collect();
+    	assert!(values.iter().all(|value| (-1.0..=1.0).contains(value)));
+    	assert!(values.iter().any(|value| *value > 0.2) && values.iter().any(|value| *value < -0.2));
+
+    	let vector = vector_noise(point, 7);
+    	assert!(vector.x != vector.y && vector.y != vector.z);
+    }
+}
@@ -0,0 +1,675 @@
+use std::path::{Path, PathBuf};
+
+use glam::{Mat4, Vec2, Vec3, vec2};
+
+use crate::brush::Brush;
+use crate::editor::command::CommandRegistry;
+use crate::i18n::{tr, tr_args};
+use crate::input::InputMap;
+use crate::util::backups::Version;
+use crate::util::profile::{self, FrameProfiler, Phase};
+use crate::util::progress::Progress;
+
+/// A help pane listing the controls and current brush.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HelpPane {
+	pub bindings: Vec<(String, String)>,
+	pub brush: Vec<(String, String)>,
+}
+
+impl HelpPane {
+	/// Build the help from the current bindings and brush.
+	pub fn new(input_map: &InputMap, brush: &Brush) -> Self {
+		let bindings = input_map.get_bindings().iter()
+			.map(|(binding, action)| (binding.label(), action.describe()))
+			.collect();
+
+		let smoothing = match brush.get_smoothing() {
+			0 => tr("help-relax-off"),
+			iterations => tr_args("help-relax-iterations", &[("iterations", &iterations.to_string())]),
+		};
+		let accumulation = match brush.get_accumulation() {
+			None => tr("help-accumulation-off"),
+			Some(limit) => tr_args("help-accumulation-limit", &[("percent", &format!("{:.0}", limit * 100.0))]),
+		};
+		let percent = format!("{:.0}", brush.get_size() * 100.0);
+		let strength = format!("{:.0}", brush.get_strength() * 100.0);
+		let hardness = format!("{:.0}", brush.get_hardness() * 100.0);
+		let brush = vec![
+			(tr("help-brush"), brush.name.clone()),
+			(tr("help-size"), tr_args("help-size-value", &[("percent", &percent)])),
+			(tr("help-relax"), smoothing),
+			(tr("help-strength"), tr_args("help-strength-value", &[("percent", &strength)])),
+			(tr("help-hardness"), tr_args("help-hardness-value", &[("percent", &hardness)])),
+			(tr("help-accumulation"), accumulation),
+		];
+
+		Self {
+			bindings,
+			brush,
+		}
+	}
+}
+
+/// The command palette, showing the typed line and matching commands.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CommandPalette {
+	pub input: String,
+	/// The usage and description of each matching command.
+	pub suggestions: Vec<(String, String)>,
+	/// The outcome of the last command, if it needs showing.
+	pub message: Option<String>,
+}
+
+impl CommandPalette {
+	/// Build the palette for a partly typed line.
+	pub fn new(input: &str, commands: &CommandRegistry, message: Option<String>) -> Self {
+		let suggestions = commands.suggest(input).iter()
+			.map(|command| (command.usage.to_owned(), command.describe()))
+			.collect();
+
+		Self {
+			input: input.to_owned(),
+			suggestions,
+			message,
+		}
+	}
+}
+
+/// The saved versions of a file, newest first, to pick one to restore.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VersionPicker {
+	/// When each version was saved, and its thumbnail if it has one.
+	pub versions: Vec<(String, Option<PathBuf>)>,
+	pub selected: usize,
+}
+
+impl VersionPicker {
+	/// Build the picker from the saved versions, with one of them selected.
+	pub fn new(versions: &[Version], selected: usize) -> Self {
+		let versions = versions.iter()
+			.map(|version| (version.describe_time(), version.thumbnail.clone()))
+			.collect();
+
+		Self {
+			versions,
+			selected,
+		}
+	}
+}
+
+/// The long operations in progress, shown as progress bars.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProgressPane {
+	/// The message and finished fraction of each operation.
+	pub bars: Vec<(String, f32)>,
+}
+
+impl ProgressPane {
+	/// Build the pane from the progress of each operation.
+	pub fn new(tasks: &[Progress]) -> Self {
+		let bars = tasks.iter()
+			.map(|progress| (progress.get_message(), progress.get_fraction()))
+			.collect();
+
+		Self {
+			bars,
+		}
+	}
+}
+
+/// The CPU time spent preparing the last frames, by what it was spent on.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProfilePane {
+	/// The name and times of each phase, then of all of them together.
+	pub rows: Vec<(String, String)>,
+}
+
+impl ProfilePane {
+	/// Build the pane from the times of the last frames.
+	pub fn new(profiler: &FrameProfiler) -> Self {
+		let last = profiler.get_last().unwrap_or_default();
+		let average = profiler.get_average();
+		let peak = profiler.get_peak();
+		let times = |last, average, peak| tr_args("profile-times", &[
+			("last", &format!("{:.1}", profile::milliseconds(last))),
+			("average", &format!("{:.1}", profile::milliseconds(average))),
+			("peak", &format!("{:.1}", profile::milliseconds(peak))),
+		]);
+
+		let mut rows: Vec<(String, String)> = Phase::ALL.iter()
+			.map(|phase| (phase.describe(), times(last.get(*phase), average.get(*phase), peak.get(*phase))))
+			.collect();
+		rows.push((tr("profile-total"), times(last.get_total(), average.get_total(), peak.get_total())));
+
+		Self {
+			rows,
+		}
+	}
+}
+
+/// A strip of spheres down the right edge of the viewport, one shaded in each palette material.
+///
+/// Positions are in viewport heights from the top right corner, so the
+/// spheres stay round and the same size however wide the window is.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MaterialStrip {
+	/// The number of spheres, one for each of the first materials.
+	pub count: u32,
+	/// The material the current brush adds, which is ringed.
+	pub selected: Option<u32>,
+}
+
+impl MaterialStrip {
+	/// The most spheres shown, which keeps the strip inside of the viewport.
+	pub const MAX_SPHERES: u32 = 12;
+	/// The radius of a sphere.
+	pub const RADIUS: f32 = 0.03;
+	/// The distance between the centers of neighboring spheres.
+	pub const SPACING: f32 = 0.075;
+	/// The center of the first sphere, from the top right corner.
+	pub const ORIGIN: Vec2 = Vec2::new(0.05, 0.05);
+
+	/// Build the strip for the materials of a palette.
+	pub fn new(materials: usize, selected: u32) -> Self {
+		let count = (materials as u32).min(Self::MAX_SPHERES);
+
+		Self {
+			count,
+			selected: (selected < count).then_some(selected),
+		}
+	}
+
+	/// Get the center of a sphere in a viewport with an aspect ratio, in viewport heights from the left.
+	pub fn get_center(&self, index: u32, aspect: f32) -> Vec2 {
+		Vec2::new(aspect - Self::ORIGIN.x, Self::ORIGIN.y + Self::SPACING * index as f32)
+	}
+
+	/// Find the material of the sphere under a point of a viewport with an aspect ratio.
+	///
+	/// The point goes from 0 to 1 across the viewport, with y pointing down.
+	pub fn material_at(&self, point: Vec2, aspect: f32) -> Option<u32> {
+		let point = point * Vec2::new(aspect, 1.0);
+
+		(0..self.count).find(|index| self.get_center(*index, aspect).distance(point) <= Self::RADIUS)
+	}
+}
+
+/// A marker of a pinned note, drawn where the pin is on the screen.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PinMarker {
+	/// Where the pin is, from 0 to 1 across the viewport, with y pointing down.
+	pub point: Vec2,
+	pub label: String,
+}
+
+impl PinMarker {
+	/// Place the marker of a pin at a position in the world, unless it is behind the camera.
+	pub fn project(view_projection: Mat4, position: Vec3, label: String) -> Option<Self> {
+		Some(Self {
+			point: project(view_projection, position)?,
+			label,
+		})
+	}
+}
+
+/// The line of a guide curve, drawn along it on the screen.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GuideLine {
+	/// Where each point of the guide is, from 0 to 1 across the viewport, with y pointing down, unless it is behind the camera.
+	pub points: Vec<Option<Vec2>>,
+}
+
+impl GuideLine {
+	/// Place the line of a guide through points in the world.
+	pub fn project(view_projection: Mat4, points: &[Vec3]) -> Self {
+		Self {
+			points: points.iter().map(|point| project(view_projection, *point)).collect(),
+		}
+	}
+}
+
+/// The lines of the voxel grid the brush snaps to, drawn around it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SnapGrid {
+	/// Where the ends of each line are, from 0 to 1 across the viewport, with y pointing down, unless they are behind the camera.
+	pub lines: Vec<[Option<Vec2>; 2]>,
+}
+
+impl SnapGrid {
+	/// Place the lines of the grid between pairs of ends in the world.
+	pub fn project(view_projection: Mat4, lines: &[[Vec3; 2]]) -> Self {
+		Self {
+			lines: lines.iter().map(|line| line.map(|end| project(view_projection, end))).collect(),
+		}
+	}
+}
+
+/// Get where a position in the world is on the screen, from 0 to 1 with y pointing down, unless it is behind the camera.
+fn project(view_projection: Mat4, position: Vec3) -> Option<Vec2> {
+	let clip = view_projection * position.extend(1.0);
+	if clip.w <= 0.0 {
+		return None;
+	}
+	let device = clip.truncate() / clip.w;
+
+	Some(vec2(device.x * 0.5 + 0.5, 0.5 - device.y * 0.5))
+}
+
+/// The UI drawn on top of the viewport.
+///
+/// Uses egui for layout and text, drawn straight
+/// onto the surface after the sculpt.
+pub struct Overlay {
+	context: egui::Context,
+	renderer: egui_wgpu::Renderer,
+	help: Option<HelpPane>,
+	palette: Option<CommandPalette>,
+	version_picker: Option<VersionPicker>,
+	/// The thumbnails of the versions in the picker, read once it shows them.
+	thumbnails: Vec<Option<egui::TextureHandle>>,
+	progress: Option<ProgressPane>,
+	profile: Option<ProfilePane>,
+	pins: Vec<PinMarker>,
+	guides: Vec<GuideLine>,
+	grid: SnapGrid,
+}
+
+impl Overlay {
+	/// Create the overlay for a surface format.
+	pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+		Self {
+			context: egui::Context::default(),
+			renderer: egui_wgpu::Renderer::new(device, format, None, 1, false),
+			help: None,
+			palette: None,
+			version_picker: None,
+			thumbnails: Vec::new(),
+			progress: None,
+			profile: None,
+			pins: Vec::new(),
+			guides: Vec::new(),
+			grid: SnapGrid::default(),
+		}
+	}
+
+	/// Show or hide (with None) the help pane.
+	pub fn set_help(&mut self, help: Option<HelpPane>) {
+		self.help = help;
+	}
+
+	/// Show or hide (with None) the command palette.
+	pub fn set_palette(&mut self, palette: Option<CommandPalette>) {
+		self.palette = palette;
+	}
+
+	/// Show or hide (with None) the picker of saved versions.
+	pub fn set_version_picker(&mut self, picker: Option<VersionPicker>) {
+		// moving the selection keeps the thumbnails already read
+		let versions = picker.as_ref().map(|picker| &picker.versions);
+		if versions != self.version_picker.as_ref().map(|picker| &picker.versions) {
+			self.thumbnails = versions.into_iter()
+				.flatten()
+				.map(|(_, thumbnail)| thumbnail.as_deref().and_then(|path| load_thumbnail(&self.context, path)))
+				.collect();
+		}
+		self.version_picker = picker;
+	}
+
+	/// Show or hide (with None) the progress bars.
+	pub fn set_progress(&mut self, progress: Option<ProgressPane>) {
+		self.progress = progress;
+	}
+
+	/// Show or hide (with None) the CPU times.
+	pub fn set_profile(&mut self, profile: Option<ProfilePane>) {
+		self.profile = profile;
+	}
+
+	/// Set the markers of the pinned notes.
+	pub fn set_pins(&mut self, pins: Vec<PinMarker>) {
+		self.pins = pins;
+	}
+
+	/// Set the lines of the guide curves.
+	pub fn set_guides(&mut self, guides: Vec<GuideLine>) {
+		self.guides = guides;
+	}
+
+	/// Set the lines of the voxel grid the brush snaps to, where none hides it.
+	pub fn set_snap_grid(&mut self, grid: SnapGrid) {
+		self.grid = grid;
+	}
+
+	/// Check whether anything needs to be drawn.
+	pub fn is_visible(&self) -> bool {
+		self.help.is_some() || self.palette.is_some() || self.version_picker.is_some() || self.progress.is_some() || self.profile.is_some() || !self.pins.is_empty() || !self.guides.is_empty() || !self.grid.lines.is_empty()
+	}
+
+	/// Lay out and draw the overlay onto a target.
+	pub fn draw(
+		&mut self,
+		device: &wgpu::Device,
+		queue: &wgpu::Queue,
+		encoder: &mut wgpu::CommandEncoder,
+		target: &wgpu::TextureView,
+		size: (u32, u32),
+		pixels_per_point: f32,
+	) {
+		let screen = egui_wgpu::ScreenDescriptor {
+			size_in_pixels: [size.0, size.1],
+			pixels_per_point,
+		};
+		let input = egui::RawInput {
+			screen_rect: Some(egui::Rect::from_min_size(
+				egui::Pos2::ZERO,
+				egui::vec2(size.0 as f32, size.1 as f32) / pixels_per_point,
+			)),
+			..Default::default()
+		};
+
+		let help = self.help.as_ref();
+		let palette = self.palette.as_ref();
+		let version_picker = self.version_picker.as_ref();
+		let thumbnails = &self.thumbnails;
+		let progress = self.progress.as_ref();
+		let profile = self.profile.as_ref();
+		let pins = &self.pins;
+		let guides = &self.guides;
+		let grid = &self.grid;
+		let output = self.context.run(input, |context| {
+			snap_grid_lines(context, grid);
+			guide_lines(context, guides);
+			pin_markers(context, pins);
+			if let Some(help) = help {
+				help_window(context, help);
+			}
+			if let Some(palette) = palette {
+				palette_window(context, palette);
+			}
+			if let Some(picker) = version_picker {
+				version_picker_window(context, picker, thumbnails);
+			}
+			if let Some(progress) = progress {
+				progress_window(context, progress);
+			}
+			if let Some(profile) = profile {
+				profile_window(context, profile);
+			}
+		});
+		let paint_jobs = self.context.tessellate(output.shapes, output.pixels_per_point);
+
+		for (id, delta) in &output.textures_delta.set {
+			self.renderer.update_texture(device, queue, *id, delta);
+		}
+		self.renderer.update_buffers(device, queue, encoder, &paint_jobs, &screen);
+		{
+			let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+				label: Some("Overlay Render Pass"),
+				color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+					view: target,
+					resolve_target: None,
+					ops: wgpu::Operations {
+						load: wgpu::LoadOp::Load,
+						store: wgpu::StoreOp::Store,
+					},
+				})],
+				depth_stencil_attachment: None,
+				timestamp_writes: None,
+				occlusion_query_set: None,
+			}).forget_lifetime();
+			self.renderer.render(&mut rpass, &paint_jobs, &screen);
+		}
+		for id in &output.textures_delta.free {
+			self.renderer.free_texture(id);
+		}
+	}
+}
+
+/// Draw the lines of the snapping grid, faintly and under everything else.
+///
+/// Lines reaching behind the camera are left out.
+fn snap_grid_lines(context: &egui::Context, grid: &SnapGrid) {
+	let screen = context.screen_rect();
+	let painter = context.layer_painter(egui::LayerId::background());
+	let stroke = egui::Stroke::new(1.0, context.style().visuals.weak_text_color());
+	let place = |point: Vec2| screen.min + egui::vec2(point.x * screen.width(), point.y * screen.height());
+
+	for line in &grid.lines {
+		if let [Some(start), Some(end)] = line {
+			painter.line_segment([place(*start), place(*end)], stroke);
+		}
+	}
+}
+
+/// Draw the lines of guide curves, under the pins and any window.
+///
+/// Lines reaching behind the camera are left out.
+fn guide_lines(context: &egui::Context, guides: &[GuideLine]) {
+	let screen = context.screen_rect();
+	let painter = context.layer_painter(egui::LayerId::background());
+	let stroke = egui::Stroke::new(2.0, context.style().visuals.warn_fg_color);
+	let place = |point: Vec2| screen.min + egui::vec2(point.x * screen.width(), point.y * screen.height());
+
+	for guide in guides {
+		for line in guide.points.windows(2) {
+			if let [Some(start), Some(end)] = line {
+				painter.line_segment([place(*start), place(*end)], stroke);
+			}
+		}
+	}
+}
+
+/// Draw the markers of pinned notes, under any window.
+fn pin_markers(context: &egui::Context, pins: &[PinMarker]) {
+	let screen = context.screen_rect();
+	let painter = context.layer_painter(egui::LayerId::background());
+	let color = context.style().visuals.selection.bg_fill;
+	let text = context.style().visuals.strong_text_color();
+
+	for pin in pins {
+		let center = screen.min + egui::vec2(pin.point.x * screen.width(), pin.point.y * screen.height());
+		painter.circle(center, 5.0, color, egui::Stroke::new(1.5, text));
+		painter.text(center + egui::vec2(9.0, 0.0), egui::Align2::LEFT_CENTER, &pin.label, egui::FontId::proportional(14.0), text);
+	}
+}
+
+/// Lay out the help pane.
+fn help_window(context: &egui::Context, help: &HelpPane) {
+	egui::Window::new(tr("help-title"))
+		.anchor(egui::Align2::LEFT_TOP, egui::vec2(12.0, 12.0))
+		.collapsible(false)
+		.resizable(false)
+		.show(context, |ui| {
+			ui.heading(tr("help-controls"));
+			pairs_grid(ui, "help_bindings", &help.bindings);
+			ui.separator();
+			ui.heading(tr("help-current-brush"));
+			pairs_grid(ui, "help_brush", &help.brush);
+		});
+}
+
+/// Lay out the command palette.
+fn palette_window(context: &egui::Context, palette: &CommandPalette) {
+	egui::Window::new(tr("palette-title"))
+		.anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 12.0))
+		.collapsible(false)
+		.resizable(false)
+		.min_width(360.0)
+		.show(context, |ui| {
+			ui.monospace(format!("> {}\u{258f}", palette.input));
+			if let Some(message) = palette.message.as_ref() {
+				ui.colored_label(ui.visuals().warn_fg_color, message);
+			}
+			if !palette.suggestions.is_empty() {
+				ui.separator();
+				pairs_grid(ui, "palette_suggestions", &palette.suggestions);
+			}
+		});
+}
+
+/// Lay out the picker of saved versions, with the thumbnails of those which have one.
+fn version_picker_window(context: &egui::Context, picker: &VersionPicker, thumbnails: &[Option<egui::TextureHandle>]) {
+	egui::Window::new(tr("versions-title"))
+		.anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 12.0))
+		.collapsible(false)
+		.resizable(false)
+		.min_width(360.0)
+		.show(context, |ui| {
+			egui::ScrollArea::vertical().max_height(480.0).show(ui, |ui| {
+				egui::Grid::new("version_picker").num_columns(2).striped(true).show(ui, |ui| {
+					for (index, (time, _)) in picker.versions.iter().enumerate() {
+						match thumbnails.get(index).and_then(Option::as_ref) {
+							Some(thumbnail) => ui.add(egui::Image::new(thumbnail).fit_to_exact_size(egui::vec2(64.0, 64.0))),
+							None => ui.label(""),
+						};
+						let label = ui.selectable_label(index == picker.selected, time);
+						if index == picker.selected {
+							label.scroll_to_me(None);
+						}
+						ui.end_row();
+					}
+				});
+			});
+			ui.separator();
+			ui.small(tr("versions-help"));
+		});
+}
+
+/// Read a PNG thumbnail into a texture, if it can be read.
+fn load_thumbnail(context: &egui::Context, path: &Path) -> Option<egui::TextureHandle> {
+	let image = image::open(path).ok()?.to_rgba8();
+	let size = [image.width() as usize, image.height() as usize];
+	let image = egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw());
+
+	Some(context.load_texture(path.display().to_string(), image, egui::TextureOptions::LINEAR))
+}
+
+/// Lay out the progress bars.
+fn progress_window(context: &egui::Context, progress: &ProgressPane) {
+	egui::Window::new(tr("progress-title"))
+		.anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -12.0))
+		.collapsible(false)
+		.resizable(false)
+		.min_width(360.0)
+		.show(context, |ui| {
+			for (message, fraction) in &progress.bars {
+				ui.label(message);
+				ui.add(egui::ProgressBar::new(*fraction).show_percentage());
+			}
+			ui.small(tr("progress-cancel"));
+		});
+}
+
+/// Lay out the CPU times.
+fn profile_window(context: &egui::Context, profile: &ProfilePane) {
+	egui::Window::new(tr("profile-title"))
+		.anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-12.0, -12.0))
+		.collapsible(false)
+		.resizable(false)
+		.show(context, |ui| {
+			pairs_grid(ui, "profile_phases", &profile.rows);
+		});
+}
+
+/// Lay out labelled values in two columns.
+fn pairs_grid(ui: &mut egui::Ui, id: &str, pairs: &[(String, String)]) {
+	egui::Grid::new(id).num_columns(2).striped(true).show(ui, |ui| {
+		for (label, value) in pairs {
+			ui.strong(label);
+			ui.label(value);
+			ui.end_row();
+		}
+	});
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use glam::vec2;
+
+	use crate::brush::RoundBrushTip;
+
+    #[test]
+    fn help_lists_every_binding() {
+    	let input_map = InputMap::default();
+    	let brush = Brush::new("Round Brush".to_owned(), Box::new(RoundBrushTip::new()));
+
+    	let help = HelpPane::new(&input_map, &brush);
+
+    	assert_eq!(help.bindings.len(), input_map.get_bindings().len());
+    	assert!(help.bindings.contains(&("H".to_owned(), "Toggle this help".to_owned())));
+    	assert_eq!(help.brush[0], ("Brush".to_owned(), "Round Brush".to_owned()));
+    	assert_eq!(help.brush[2].1, "Off");
+    }
+
+    #[test]
+    fn palette_suggests_matching_commands() {
+    	let palette = CommandPalette::new("brush si", &CommandRegistry::default(), None);
+
+    	assert_eq!(palette.suggestions.len(), 1);
+    	assert_eq!(palette.suggestions[0].0, "brush size <fraction>");
+    }
+
+    #[test]
+    fn progress_shows_every_task() {
+    	let rendering = Progress::new("Rendering");
+    	rendering.set_fraction(0.25);
+
+    	let progress = ProgressPane::new(&[rendering, Progress::new("Saving")]);
+
+    	assert_eq!(progress.bars, vec![("Rendering".to_owned(), 0.25), ("Saving".to_owned(), 0.0)]);
+    }
+
+    #[test]
+    fn profile_lists_every_phase_and_the_total() {
+    	let mut profiler = FrameProfiler::default();
+    	profiler.record(Phase::Sculpt, std::time::Duration::from_micros(2500));
+    	profiler.finish_frame();
+
+    	let profile = ProfilePane::new(&profiler);
+
+    	assert_eq!(profile.rows.len(), Phase::ALL.len() + 1);
+    	assert_eq!(profile.rows[1], ("Sculpting".to_owned(), "2.5 ms, average 2.5, peak 2.5".to_owned()));
+    	assert_eq!(profile.rows[4].0, "Total");
+    }
+
+    #[test]
+    fn pin_markers_follow_the_view() {
+    	let view_projection = Mat4::orthographic_rh(0.0, 2.0, 0.0, 1.0, -1.0, 1.0);
+
+    	let marker = PinMarker::project(view_projection, Vec3::new(0.5, 0.75, 0.0), "1: here".to_owned()).unwrap();
+    	assert_eq!(marker.point, vec2(0.25, 0.25));
+    	assert_eq!(marker.label, "1: here");
+
+    	let perspective = Mat4::perspective_rh(1.0, 1.0, 0.1, 10.0);
+    	assert!(PinMarker::project(perspective, Vec3::new(0.0, 0.0, -1.0), String::new()).is_some());
+    	assert!(PinMarker::project(perspective, Vec3::new(0.0, 0.0, 1.0), String::new()).is_none());
+    }
+
+    #[test]
+    fn snap_grid_lines_leave_out_ends_behind_the_camera() {
+    	let perspective = Mat4::perspective_rh(1.0, 1.0, 0.1, 10.0);
+
+    	let grid = SnapGrid::project(perspective, &[[Vec3::new(0.0, 0.0, -1.0), Vec3::new(0.0, 0.0, 1.0)]]);
+    	assert_eq!(grid.lines.len(), 1);
+    	assert_eq!(grid.lines[0][0], Some(vec2(0.5, 0.5)));
+    	assert_eq!(grid.lines[0][1], None);
+    }
+
+    #[test]
+    fn strip_spheres_are_hit_in_wide_viewports() {
+    	let strip = MaterialStrip::new(20, 3);
+    	assert_eq!(strip.count, MaterialStrip::MAX_SPHERES);
+    	assert_eq!(strip.selected, Some(3));
+    	assert_eq!(MaterialStrip::new(4, 7).selected, None);
+
+    	// the third sphere, in a viewport twice as wide as it is high
+    	let center = strip.get_center(2, 2.0);
+    	assert_eq!(strip.material_at(vec2(center.x / 2.0, center.y), 2.0), Some(2));
+    	assert_eq!(strip.material_at(vec2(center.x / 2.0 + MaterialStrip::RADIUS, center.y), 2.0), None);
+    	assert_eq!(strip.material_at(vec2(0.5, 0.5), 2.0), None);
+    	assert_eq!(MaterialStrip::new(2, 0).material_at(vec2(center.x / 2.0, center.y), 2.0), None);
+    }
+}
@@ -0,0 +1,228 @@
+//! Material palettes, from built-in presets or imported files.
+//!
+//! Colors are kept linear, like the material colors they
+//! become, while presets and files are written in sRGB.
+
+use crate::material::Material;
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// A built-in palette.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PalettePreset {
+	/// The grey of the default material.
+	Default,
+	/// The palette by Masataka Okabe and Kei Ito, with grey in place of black.
+	OkabeIto,
+	/// Paul Tol's bright qualitative palette.
+	TolBright,
+	/// Paul Tol's muted qualitative palette.
+	TolMuted,
+}
+
+impl PalettePreset {
+	/// Every preset, in the order they are listed.
+	pub const ALL: [PalettePreset; 4] = [
+		PalettePreset::Default,
+		PalettePreset::OkabeIto,
+		PalettePreset::TolBright,
+		PalettePreset::TolMuted,
+	];
+
+	/// Get the name the preset is selected by.
+	pub fn name(&self) -> &'static str {
+		match self {
+			PalettePreset::Default => "default",
+			PalettePreset::OkabeIto => "okabe-ito",
+			PalettePreset::TolBright => "tol-bright",
+			PalettePreset::TolMuted => "tol-muted",
+		}
+	}
+
+	/// Get a preset by its name.
+	pub fn from_name(name: &str) -> Option<PalettePreset> {
+		Self::ALL.into_iter().find(|preset| preset.name() == name)
+	}
+
+	/// Check whether the colors stay distinct with common kinds of color blindness.
+	pub fn is_color_blind_safe(&self) -> bool {
+		*self != PalettePreset::Default
+	}
+
+	/// Get the linear colors of the preset.
+	pub fn colors(&self) -> Vec<[f32; 4]> {
+		let hex: &[u32] = match self {
+			PalettePreset::Default => return vec![Material::default().color],
+			PalettePreset::OkabeIto => &[0x999999, 0xe69f00, 0x56b4e9, 0x009e73, 0xf0e442, 0x0072b2, 0xd55e00, 0xcc79a7],
+			PalettePreset::TolBright => &[0xbbbbbb, 0x4477aa, 0xee6677, 0x228833, 0xccbb44, 0x66ccee, 0xaa3377],
+			PalettePreset::TolMuted => &[0xdddddd, 0xcc6677, 0x332288, 0xddcc77, 0x117733, 0x88ccee, 0x882255, 0x44aa99, 0x999933, 0xaa4499],
+		};
+
+		hex.iter()
+			.map(|hex| srgb_color([(hex >> 16) as u8, (hex >> 8) as u8, *hex as u8].map(|channel| channel as f32 / 255.0)))
+			.collect()
+	}
+}
+
+/// Convert an sRGB color to an opaque linear one.
+fn srgb_color(rgb: [f32; 3]) -> [f32; 4] {
+	let [r, g, b] = rgb.map(|channel| {
+		if channel <= 0.04045 {
+			channel / 12.92
+		} else {
+			((channel + 0.055) / 1.055).powf(2.4)
+		}
+	});
+
+	[r, g, b, 1.0]
+}
+
+/// Build an error for a palette file which can't be read.
+fn invalid(message: String) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Parse the colors of a GIMP palette (.gpl).
+pub fn parse_gpl(source: &str) -> io::Result<Vec<[f32; 4]>> {
+	let mut lines = source.lines().enumerate();
+	if lines.next().is_none_or(|(_, line)| line.trim() != "GIMP Palette") {
+		return Err(invalid("a GIMP palette has to start with \"GIMP Palette\"".to_owned()));
+	}
+
+	let mut colors = Vec::new();
+	for (number, line) in lines {
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('#') || line.starts_with("Name:") || line.starts_with("Columns:") {
+			continue;
+		}
+
+		// the channels may be followed by the name of the color
+		let channels: Vec<Option<u8>> = line.split_whitespace().take(3).map(|channel| channel.parse().ok()).collect();
+		let [Some(r), Some(g), Some(b)] = channels[..] else {
+			return Err(invalid(format!("line {} of the GIMP palette is not a color", number + 1)));
+		};
+		colors.push(srgb_color([r, g, b].map(|channel| channel as f32 / 255.0)));
+	}
+
+	Ok(colors)
+}
+
+/// Parse the colors of an Adobe Swatch Exchange file (.ase).
+///
+/// RGB, grey and CMYK swatches are read, while Lab swatches are skipped.
+pub fn parse_ase(bytes: &[u8]) -> io::Result<Vec<[f32; 4]>> {
+	let truncated = || invalid("the swatch exchange file ends early".to_owned());
+	let take = |offset: usize, length: usize| bytes.get(offset..offset + length).ok_or_else(truncated);
+	let u16_at = |offset: usize| take(offset, 2).map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]));
+	let u32_at = |offset: usize| take(offset, 4).map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+	let f32_at = |offset: usize| u32_at(offset).map(f32::from_bits);
+
+	if take(0, 4)? != b"ASEF" {
+		return Err(invalid("a swatch exchange file has to start with \"ASEF\"".to_owned()));
+	}
+
+	let count = u32_at(8)?;
+	let mut offset = 12;
+	let mut colors = Vec::new();
+	for _ in 0..count {
+		let kind = u16_at(offset)?;
+		let length = u32_at(offset + 2)? as usize;
+		let block = offset + 6;
+		offset = block + length;
+
+		// groups only wrap colors, so only their colors are read
+		if kind != 0x0001 {
+			continue;
+		}
+
+		// the name is a null terminated UTF-16 string, counted in code units
+		let model = block + 2 + u16_at(block)? as usize * 2;
+		let values = model + 4;
+		let color = match take(model, 4)? {
+			b"RGB " => srgb_color([f32_at(values)?, f32_at(values + 4)?, f32_at(values + 8)?]),
+			b"Gray" => srgb_color([f32_at(values)?; 3]),
+			b"CMYK" => {
+				let [c, m, y, k] = [f32_at(values)?, f32_at(values + 4)?, f32_at(values + 8)?, f32_at(values + 12)?];
+				srgb_color([c, m, y].map(|channel| (1.0 - channel) * (1.0 - k)))
+			},
+			_ => continue,
+		};
+		colors.push(color);
+	}
+
+	Ok(colors)
+}
+
+/// Load the colors of a palette file, picking the format by its extension.
+pub fn load(path: impl AsRef<Path>) -> io::Result<Vec<[f32; 4]>> {
+	let path = path.as_ref();
+	let extension = path.extension().and_then(|extension| extension.to_str()).map(str::to_lowercase);
+
+	match extension.as_deref() {
+		Some("gpl") => parse_gpl(&fs::read_to_string(path)?),
+		Some("ase") => parse_ase(&fs::read(path)?),
+		_ => Err(io::Error::new(io::ErrorKind::Unsupported, "palettes have to be .gpl or .ase files")),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    #[test]
+    fn presets_are_named_and_linear() {
+    	for preset in PalettePreset::ALL {
+    		assert_eq!(PalettePreset::from_name(preset.name()), Some(preset));
+    		assert!(!preset.colors().is_empty());
+    	}
+    	assert!(PalettePreset::OkabeIto.is_color_blind_safe());
+    	assert_eq!(PalettePreset::OkabeIto.colors().len(), 8);
+
+    	assert_eq!(PalettePreset::Default.colors(), vec![Material::default().color]);
+    	let [r, g, b, a] = PalettePreset::TolBright.colors()[0];
+    	assert!((r - 0.497).abs() < 0.001 && r == g && g == b);
+    	assert_eq!(a, 1.0);
+    }
+
+    #[test]
+    fn gimp_palettes_are_parsed() {
+    	let source = "GIMP Palette\nName: Test\nColumns: 2\n#\n255   0   0\tRed\n  0 255 255 Cyan\n";
+
+    	let colors = parse_gpl(source).unwrap();
+
+    	assert_eq!(colors, vec![[1.0, 0.0, 0.0, 1.0], [0.0, 1.0, 1.0, 1.0]]);
+    	assert!(parse_gpl("Not a palette\n").is_err());
+    	assert!(parse_gpl("GIMP Palette\n12 red\n").is_err());
+    }
+
+    #[test]
+    fn swatch_exchange_files_are_parsed() {
+    	let color = |model: &[u8; 4], values: &[f32]| {
+    		let mut block = vec![0, 2, 0, b'A', 0, 0];
+    		block.extend(model);
+    		for value in values {
+    			block.extend(value.to_be_bytes());
+    		}
+    		block.extend([0, 2]);
+
+    		let mut bytes = vec![0x00, 0x01];
+    		bytes.extend((block.len() as u32).to_be_bytes());
+    		bytes.extend(block);
+    		bytes
+    	};
+    	let mut bytes = b"ASEF\x00\x01\x00\x00\x00\x00\x00\x05".to_vec();
+    	bytes.extend([0xc0, 0x01, 0, 0, 0, 2, 0, 0]);
+    	bytes.extend(color(b"RGB ", &[1.0, 0.0, 1.0]));
+    	bytes.extend(color(b"LAB ", &[0.5, 0.0, 0.0]));
+    	bytes.extend(color(b"Gray", &[0.0]));
+    	bytes.extend([0xc0, 0x02, 0, 0, 0, 0]);
+
+    	let colors = parse_ase(&bytes).unwrap();
+
+    	assert_eq!(colors, vec![[1.0, 0.0, 1.0, 1.0], [0.0, 0.0, 0.0, 1.0]]);
+    	assert!(parse_ase(&bytes[..bytes.len() - 20]).is_err());
+    	assert!(parse_ase(b"GIMP").is_err());
+    }
+}
@@ -0,0 +1,297 @@
+use crate::camera::Camera;
+use crate::color::{ColorManagement, linear_to_srgb};
+use crate::scene::Scene;
+use crate::rng::Rng;
+use crate::sculpt;
+use crate::util::progress::Progress;
+
+use std::f32::consts::PI;
+use std::path::Path;
+use std::thread;
+
+use glam::{Vec3, vec3};
+use image::ImageResult;
+
+/// The lighting surrounding the sculpt.
+pub enum Environment {
+	/// A simple procedural sky gradient.
+	Sky,
+	/// An equirectangular HDR image.
+	Map {
+		width: usize,
+		height: usize,
+		pixels: Vec<Vec3>,
+	},
+}
+
+impl Environment {
+	/// Load an equirectangular HDRI (such as a Radiance `.hdr` file).
+	pub fn load(path: impl AsRef<Path>) -> ImageResult<Self> {
+		let image = image::open(path)?.into_rgb32f();
+		let (width, height) = image.dimensions();
+
+		Ok(Environment::Map {
+			width: width as usize,
+			height: height as usize,
+			pixels: image.pixels().map(|pixel| Vec3::from_array(pixel.0)).collect(),
+		})
+	}
+
+	/// Get the incoming light from a direction.
+	///
+	/// The environment uses the +Z up convention of the document.
+	pub fn sample(&self, direction: Vec3) -> Vec3 {
+		match self {
+			Environment::Sky => {
+				let horizon = vec3(0.8, 0.85, 0.9);
+				let zenith = vec3(0.3, 0.45, 0.8);
+				let ground = vec3(0.2, 0.18, 0.16);
+				if direction.z >= 0.0 {
+					horizon.lerp(zenith, direction.z)
+				} else {
+					horizon.lerp(ground, (-direction.z * 4.0).min(1.0))
+				}
+			},
+			Environment::Map { width, height, pixels } => {
+				let u = direction.y.atan2(direction.x) / (2.0 * PI) + 0.5;
+				let v = direction.z.clamp(-1.0, 1.0).acos() / PI;
+				let x = ((u * *width as f32) as usize).min(width - 1);
+				let y = ((v * *height as f32) as usize).min(height - 1);
+
+				pixels[y * width + x]
+			},
+		}
+	}
+}
+
+/// A progressive, offline path tracer for final renders.
+///
+/// Each call to `render_sample` traces one more sample per pixel
+/// on the CPU and accumulates it into the image.
+pub struct PathTracer {
+	width: u32,
+	height: u32,
+	samples: u32,
+	target_samples: u32,
+	bounces: u32,
+	environment: Environment,
+	camera: Option<Camera>,
+	/// How the image is mapped onto the display, like the viewport does, if at all.
+	color: Option<ColorManagement>,
+	accumulation: Vec<Vec3>,
+	progress: Progress,
+}
+
+impl PathTracer {
+	/// Create a path tracer for an image of the given size.
+	pub fn new(width: u32, height: u32, target_samples: u32, bounces: u32) -> Self {
+		Self {
+			width,
+			height,
+			samples: 0,
+			target_samples,
+			bounces,
+			environment: Environment::Sky,
+			camera: None,
+			color: None,
+			accumulation: vec![Vec3::ZERO; (width * height) as usize],
+			progress: Progress::default(),
+		}
+	}
+
+	/// Replace the lighting environment.
+	pub fn set_environment(&mut self, environment: Environment) {
+		self.environment = environment;
+	}
+
+	/// Render through a perspective camera instead of the front view.
+	pub fn set_camera(&mut self, camera: Camera) {
+		self.camera = Some(camera);
+	}
+
+	/// Map the image onto the display with the color management of a document, so it looks as it does in the viewport.
+	pub fn set_color_management(&mut self, color: ColorManagement) {
+		self.color = Some(color);
+	}
+
+	/// Get the number of samples accumulated so far.
+	pub fn get_samples(&self) -> u32 {
+		self.samples
+	}
+
+	/// Get the progress of the render, by the samples accumulated so far.
+	pub fn get_progress(&self) -> &Progress {
+		&self.progress
+	}
+
+	/// Check whether all requested samples have been accumulated.
+	pub fn is_complete(&self) -> bool {
+		self.samples >= self.target_samples
+	}
+
+	/// Report to another progress, like the one of the job running the render.
+	pub fn set_progress(&mut self, progress: Progress) {
+		self.progress = progress;
+	}
+
+	/// Trace one sample for every pixel, spread across threads.
+	///
+	/// The threads stop between rows once the progress is cancelled, and
+	/// the unfinished sample isn't counted.
+	pub fn render_sample(&mut self, scene: &Scene) {
+		let width = self.width as usize;
+		let threads = thread::available_parallelism().map_or(1, |count| count.get());
+		let rows_per_thread = (self.height as usize).div_ceil(threads).max(1);
+		let sample = self.samples;
+		let mut accumulation = std::mem::take(&mut self.accumulation);
+		let tracer = &*self;
+
+		thread::scope(|scope| {
+			for (chunk_index, chunk) in accumulation.chunks_mut(rows_per_thread * width).enumerate() {
+				scope.spawn(move || {
+					for (row_index, row) in chunk.chunks_mut(width).enumerate() {
+						if tracer.progress.is_cancelled() {
+							return;
+						}
+						for (offset, pixel) in row.iter_mut().enumerate() {
+							let index = (chunk_index * rows_per_thread + row_index) * width + offset;
+							*pixel += tracer.trace_pixel(scene, index, sample);
+						}
+					}
+				});
+			}
+		});
+
+		self.accumulation = accumulation;
+		if self.progress.is_cancelled() {
+			return;
+		}
+		self.samples += 1;
+		self.progress.set_fraction(self.samples as f32 / self.target_samples.max(1) as f32);
+	}
+
+	/// Trace samples until all requested ones have accumulated, or the progress is cancelled.
+	pub fn render(&mut self, scene: &Scene) {
+		while !self.is_complete() && !self.progress.is_cancelled() {
+			self.render_sample(scene);
+		}
+	}
+
+	/// Trace a single path through a pixel.
+	fn trace_pixel(&self, scene: &Scene, index: usize, sample: u32) -> Vec3 {
+		let mut rng = Rng::new((index as u32).wrapping_mul(0x9e37_79b9) ^ sample.wrapping_mul(0x85eb_ca6b));
+
+		let x = (index % self.width as usize) as f32;
+		let y = (index / self.width as usize) as f32;
+		let u = (x + rng.next_f32()) / self.width as f32;
+		let v = (y + rng.next_f32()) / self.height as f32;
+		let (mut origin, mut direction) = match self.camera {
+			Some(camera) => camera.ray(u, v, self.width as f32 / self.height as f32),
+			None => {
+				let active = scene.get_active();
+				let home = active.document.get_home_chunk().unit_to_world(vec3(u, v, 0.0));
+				(active.transform.to_world(home), active.transform.rotation * Vec3::Z)
+			},
+		};
+
+		let mut throughput = Vec3::ONE;
+
+		for _ in 0..=self.bounces {
+			let Some((index, hit)) = scene.raycast(origin, direction) else {
+				return throughput * self.environment.sample(direction);
+			};
+
+			// the hit is offset by a fraction of a voxel of the object it is on
+			let object = &scene.get_objects()[index];
+			let epsilon = 0.01 * object.document.get_voxel_size() * object.transform.scale;
+			let material = object.document.get_material(hit.material).copied().unwrap_or_default();
+			let color = sculpt::mix_paint(material.color, hit.paint);
+			let albedo = vec3(color[0], color[1], color[2]);
+
+			origin = hit.position + hit.normal * epsilon;
+			direction = if rng.next_f32() < material.metallic {
+				let reflected = direction - 2.0 * direction.dot(hit.normal) * hit.normal;
+				(reflected + rng.next_unit_vector() * material.roughness).normalize_or(hit.normal)
+			} else {
+				(hit.normal + rng.next_unit_vector()).normalize_or(hit.normal)
+			};
+
+			if direction.dot(hit.normal) <= 0.0 {
+				break;
+			}
+
+			throughput *= albedo;
+		}
+
+		Vec3::ZERO
+	}
+
+	/// Convert the accumulated samples to 8-bit sRGB pixels.
+	pub fn to_rgba8(&self) -> Vec<u8> {
+		let scale = 1.0 / self.samples.max(1) as f32;
+
+		self.accumulation.iter()
+			.flat_map(|pixel| {
+				let color = match &self.color {
+					Some(color) => color.apply(*pixel * scale).to_array().map(linear_to_srgb).into(),
+					None => (*pixel * scale).clamp(Vec3::ZERO, Vec3::ONE).powf(1.0 / 2.2),
+				} * 255.0;
+				[color.x as u8, color.y as u8, color.z as u8, 255]
+			})
+			.collect()
+	}
+
+	/// Save the current image as a PNG.
+	pub fn save(&self, path: impl AsRef<Path>) -> ImageResult<()> {
+		image::save_buffer(path, &self.to_rgba8(), self.width, self.height, image::ColorType::Rgba8)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    use crate::aabb::Aabb;
+    use crate::brush::RoundBrushTip;
+    use crate::document::Document;
+    use crate::scene::SceneObject;
+
+    #[test]
+    fn path_tracer_completes_after_target_samples() {
+    	let mut document = Document::new(16, 1.0);
+    	for sculpt in document.get_chunks_in(Aabb::default(), false) {
+    		sculpt.subdivide(RoundBrushTip::filler(0.25, vec3(0.5, 0.5, 0.5)), RoundBrushTip::container(0.25, vec3(0.5, 0.5, 0.5)));
+    	}
+
+    	let scene = Scene::new(SceneObject::new("sculpt", document));
+
+    	let mut tracer = PathTracer::new(8, 8, 2, 2);
+    	tracer.render_sample(&scene);
+    	assert!(!tracer.is_complete());
+    	tracer.render_sample(&scene);
+    	assert!(tracer.is_complete());
+    	assert_eq!(tracer.to_rgba8().len(), 8 * 8 * 4);
+    }
+
+    #[test]
+    fn cancelled_renders_stop_without_counting_the_sample() {
+    	let scene = Scene::new(SceneObject::new("sculpt", Document::new(16, 1.0)));
+    	let mut tracer = PathTracer::new(4, 4, 8, 2);
+    	tracer.render_sample(&scene);
+    	tracer.get_progress().cancel();
+
+    	tracer.render(&scene);
+    	tracer.render_sample(&scene);
+    	assert_eq!(tracer.get_samples(), 1);
+    	assert!(!tracer.is_complete());
+    }
+
+    #[test]
+    fn empty_sculpt_renders_environment() {
+    	let mut tracer = PathTracer::new(4, 4, 1, 2);
+    	tracer.render_sample(&Scene::new(SceneObject::new("sculpt", Document::new(16, 1.0))));
+
+    	let expected = Environment::Sky.sample(vec3(0.0, 0.0, 1.0));
+    	assert!(tracer.accumulation.iter().all(|pixel| *pixel == expected));
+    }
+}
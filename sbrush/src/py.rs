@@ -0,0 +1,155 @@
+//! Python bindings, for scripting sculpts in notebooks.
+//!
+//! Built with the `py` feature as the `sbrush` module. Sculpts are
+//! shaped with brush stamps and noise displacement, and exported as
+//! the GPU voxel buffer, for Swirlix or game engines to load.
+
+use crate::aabb::Aabb;
+use crate::brush::{RoundBrushTip, SquareBrushTip};
+use crate::material::MaterialMix;
+use crate::noise;
+use crate::sculpt::Sculpt;
+
+use std::path::PathBuf;
+
+use glam::Vec3;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+/// A sparse voxel octree filling a cube.
+#[pyclass(name = "Sculpt")]
+struct PySculpt {
+	sculpt: Sculpt,
+}
+
+impl PySculpt {
+	/// Check that a material is in the palette, so stamps don't leave dangling materials.
+	fn check_material(&self, material: u32) -> PyResult<MaterialMix> {
+		if self.sculpt.get_material(material).is_none() {
+			return Err(PyValueError::new_err(format!("material {material} isn't in the palette")));
+		}
+
+		Ok(MaterialMix::single(material))
+	}
+}
+
+#[pymethods]
+impl PySculpt {
+	/// Create an empty sculpt of a resolution, filling a cube around a center.
+	#[new]
+	#[pyo3(signature = (resolution = 256, center = (0.5, 0.5, 0.5), size = 1.0))]
+	fn new(resolution: u32, center: (f32, f32, f32), size: f32) -> PyResult<Self> {
+		if resolution == 0 || size.is_nan() || size <= 0.0 {
+			return Err(PyValueError::new_err("the resolution and size have to be more than 0"));
+		}
+
+		Ok(Self {
+			sculpt: Sculpt::new_with_bounds(resolution, Aabb::cube(Vec3::from(center), size)),
+		})
+	}
+
+	/// The number of voxels along each edge.
+	#[getter]
+	fn resolution(&self) -> u32 {
+		self.sculpt.get_resolution()
+	}
+
+	/// The edge length of the finest voxels.
+	#[getter]
+	fn voxel_size(&self) -> f32 {
+		self.sculpt.get_voxel_size()
+	}
+
+	/// Replace the palette with a material of each RGBA color.
+	fn set_palette(&mut self, colors: Vec<[f32; 4]>) -> PyResult<()> {
+		if colors.is_empty() {
+			return Err(PyValueError::new_err("the palette needs at least one color"));
+		}
+		self.sculpt.set_palette(&colors);
+
+		Ok(())
+	}
+
+	/// Fill a sphere with a material from the palette.
+	#[pyo3(signature = (center, radius, material = 0))]
+	fn add_sphere(&mut self, center: (f32, f32, f32), radius: f32, material: u32) -> PyResult<()> {
+		let mix = self.check_material(material)?;
+		let center = Vec3::from(center);
+		self.sculpt.subdivide_with(&mix, RoundBrushTip::filler(radius, center), RoundBrushTip::container(radius, center));
+
+		Ok(())
+	}
+
+	/// Carve a sphere away.
+	fn remove_sphere(&mut self, center: (f32, f32, f32), radius: f32) {
+		let center = Vec3::from(center);
+		self.sculpt.unsubdivide(RoundBrushTip::filler(radius, center), RoundBrushTip::container(radius, center));
+	}
+
+	/// Fill a cube, given half of its edge length, with a material from the palette.
+	#[pyo3(signature = (center, half_size, material = 0))]
+	fn add_cube(&mut self, center: (f32, f32, f32), half_size: f32, material: u32) -> PyResult<()> {
+		let mix = self.check_material(material)?;
+		let center = Vec3::from(center);
+		self.sculpt.subdivide_with(&mix, SquareBrushTip::filler(half_size, center), SquareBrushTip::container(half_size, center));
+
+		Ok(())
+	}
+
+	/// Carve a cube away, given half of its edge length.
+	fn remove_cube(&mut self, center: (f32, f32, f32), half_size: f32) {
+		let center = Vec3::from(center);
+		self.sculpt.unsubdivide(SquareBrushTip::filler(half_size, center), SquareBrushTip::container(half_size, center));
+	}
+
+	/// Warp the voxels by smooth noise, moving the surface by up to an amplitude.
+	#[pyo3(signature = (amplitude, frequency = 4.0, seed = 1))]
+	fn displace(&mut self, amplitude: f32, frequency: f32, seed: u32) {
+		self.sculpt.displace(amplitude, frequency, seed);
+	}
+
+	/// Check whether a point is inside of the voxels.
+	fn is_solid(&self, point: (f32, f32, f32)) -> bool {
+		self.sculpt.is_solid(Vec3::from(point))
+	}
+
+	/// Get the voxel buffer, as uploaded to the GPU.
+	fn voxel_buffer(&self) -> Vec<u32> {
+		self.sculpt.get_voxel_buffer()
+	}
+
+	/// Write the voxel buffer with its resolution, placement and palette to a file.
+	fn export_gpu_blob(&self, path: PathBuf) -> PyResult<()> {
+		Ok(self.sculpt.export_gpu_blob(path)?)
+	}
+
+	/// Read a sculpt from a file written by `export_gpu_blob`.
+	#[staticmethod]
+	fn import_gpu_blob(path: PathBuf) -> PyResult<Self> {
+		Ok(Self {
+			sculpt: Sculpt::import_gpu_blob(path)?,
+		})
+	}
+
+	fn __repr__(&self) -> String {
+		let bounds = self.sculpt.get_bounds();
+
+		format!("Sculpt(resolution={}, center={:?}, size={})", self.sculpt.get_resolution(), bounds.center().to_array(), self.sculpt.get_size())
+	}
+}
+
+/// Get smooth noise at a point, between -1 and 1, changing about once per unit.
+#[pyfunction]
+#[pyo3(signature = (point, seed = 1))]
+fn value_noise(point: (f32, f32, f32), seed: u32) -> f32 {
+	noise::value_noise(Vec3::from(point), seed)
+}
+
+/// Procedural sculpting with the voxel octrees of Swirlix.
+#[pymodule]
+fn sbrush(module: &Bound<'_, PyModule>) -> PyResult<()> {
+	module.add_class::<PySculpt>()?;
+	module.add_function(wrap_pyfunction!(value_noise, module)?)?;
+
+	Ok(())
+}
@@ -1,47 +1,549 @@
+use std::collections::HashMap;
 use std::num::NonZero;
 use std::borrow::Cow;
+use std::mem::size_of;
 use std::sync::Arc;
 
-use bytemuck::cast_slice;
+use bytemuck::{Pod, Zeroable, bytes_of, cast_slice};
+use glam::{IVec3, Mat4, UVec2, Vec3, uvec2, vec2};
 use winit::window::Window;
 
-use crate::material::Material;
+use crate::aabb::Aabb;
+use crate::brush::{Stamp, TipShape};
+use crate::camera::Frustum;
+use crate::color::{ColorManagement, Tonemap};
+use crate::error::Error;
+use crate::light::{GpuLight, Light, MAX_LIGHTS};
+use crate::material::{GpuMaterial, Material};
+use crate::util::jobs::JobPool;
+use crate::overlay::{CommandPalette, GuideLine, HelpPane, MaterialStrip, Overlay, PinMarker, ProfilePane, ProgressPane, SnapGrid, VersionPicker};
+
+pub mod graph;
+pub mod mesh;
+pub mod readback;
+pub mod uniform;
+pub mod variants;
+
+use graph::RenderGraph;
+use mesh::{ChunkMesh, MeshExtraction};
+use readback::ReadbackPool;
+use uniform::UniformBlock;
+use variants::{PipelineVariants, ShaderFeatures};
+
+/// The settings uniform shared by the shaders.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct Settings {
+    resolution: u32,
+    display_mode: u32,
+    traversal: u32,
+    /// Which sample of the accumulation the frame is, from 0, which isn't jittered.
+    sample: u32,
+}
+
+/// How the surface of the sculpt is colored.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DisplayMode {
+    /// Lit material colors.
+    #[default]
+    Shaded,
+    /// How recently and often each voxel was edited.
+    Heat,
+    /// Lit material colors, darkened where voxels are masked.
+    ///
+    /// The chunks' heat buffers have to hold the mask instead.
+    Mask,
+}
+
+/// How the surface of the sculpt is drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DrawMode {
+    /// Rays marched through the octree of each chunk.
+    #[default]
+    RayMarched,
+    /// Triangles extracted from each chunk on the GPU and rasterized, for a quicker preview.
+    Mesh,
+    /// Meshes while the view moves, and rays once it is still, accumulating
+    /// jittered frames into a smoother image.
+    Hybrid,
+}
+
+/// How rays find voxels in the octree.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Traversal {
+    /// Exact front to back traversal using the entry and exit of each node.
+    #[default]
+    Exact,
+    /// The original stepwise descent, for comparison.
+    Descent,
+}
+
+/// How finished frames are shown, trading latency against tearing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PresentMode {
+    /// Wait for the vertical blank, so frames never tear.
+    #[default]
+    Fifo,
+    /// Replace waiting frames with newer ones, lowering latency without tearing.
+    Mailbox,
+    /// Show frames right away, with the lowest latency but tearing.
+    Immediate,
+}
+
+impl PresentMode {
+    /// Get a present mode by its name, ignoring case.
+    pub fn from_name(name: &str) -> Option<PresentMode> {
+        match name.to_lowercase().as_str() {
+            "fifo" | "vsync" => Some(PresentMode::Fifo),
+            "mailbox" => Some(PresentMode::Mailbox),
+            "immediate" => Some(PresentMode::Immediate),
+            _ => None,
+        }
+    }
+
+    /// Get the lowercase name of the present mode.
+    pub fn name(&self) -> &'static str {
+        match self {
+            PresentMode::Fifo => "fifo",
+            PresentMode::Mailbox => "mailbox",
+            PresentMode::Immediate => "immediate",
+        }
+    }
+
+    /// Get the matching wgpu present mode.
+    fn to_wgpu(self) -> wgpu::PresentMode {
+        match self {
+            PresentMode::Fifo => wgpu::PresentMode::Fifo,
+            PresentMode::Mailbox => wgpu::PresentMode::Mailbox,
+            PresentMode::Immediate => wgpu::PresentMode::Immediate,
+        }
+    }
+}
+
+/// The limits of the ray traversal, compiled into the ray marching shaders as override constants.
+///
+/// Lower limits trade detail far down the octree, and rays grazing many
+/// nodes, for speed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RenderQuality {
+    /// How many levels of the octree rays descend, which is the size of their stack.
+    pub max_depth: u32,
+    /// How many nodes a ray visits in a chunk before giving up.
+    pub max_iterations: u32,
+    /// How many steps rays take in the stepwise descent.
+    pub descent_steps: u32,
+    /// How close, in voxels, a step of the stepwise descent has to come to hit a voxel.
+    pub hit_distance: f32,
+}
+
+impl Default for RenderQuality {
+    fn default() -> Self {
+        RenderQuality::MEDIUM
+    }
+}
+
+impl RenderQuality {
+    pub const LOW: RenderQuality = RenderQuality { max_depth: 10, max_iterations: 256, descent_steps: 8, hit_distance: 3.0 };
+    pub const MEDIUM: RenderQuality = RenderQuality { max_depth: 16, max_iterations: 512, descent_steps: 16, hit_distance: 2.0 };
+    pub const HIGH: RenderQuality = RenderQuality { max_depth: MAX_DEPTH, max_iterations: 1024, descent_steps: 32, hit_distance: 1.5 };
+
+    /// Get a preset by its name, ignoring case.
+    pub fn from_name(name: &str) -> Option<RenderQuality> {
+        match name.to_lowercase().as_str() {
+            "low" => Some(RenderQuality::LOW),
+            "medium" => Some(RenderQuality::MEDIUM),
+            "high" => Some(RenderQuality::HIGH),
+            _ => None,
+        }
+    }
+
+    /// Get the values of the override constants in the ray marching shader.
+    fn constants(&self) -> HashMap<String, f64> {
+        HashMap::from([
+            ("max_depth".to_owned(), self.max_depth.min(MAX_DEPTH) as f64),
+            ("max_iterations".to_owned(), self.max_iterations as f64),
+            ("descent_steps".to_owned(), self.descent_steps as f64),
+            ("hit_distance".to_owned(), self.hit_distance as f64),
+        ])
+    }
+}
+
+/// The most levels rays can descend, as the stack in the shader is allocated up front.
+const MAX_DEPTH: u32 = 24;
+
+/// Which GPU adapter to render with, for systems with several, like laptops with hybrid graphics.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum AdapterChoice {
+    /// Let wgpu pick.
+    #[default]
+    Default,
+    /// Prefer the adapter which saves power, usually an integrated GPU.
+    LowPower,
+    /// Prefer the fastest adapter, usually a discrete GPU.
+    HighPerformance,
+    /// The adapter at an index of `Renderer::enumerate_adapters`.
+    Index(usize),
+    /// The first adapter with a name containing this, ignoring case.
+    Name(String),
+}
+
+impl AdapterChoice {
+    /// Parse a power preference, an adapter index or part of an adapter name.
+    pub fn parse(value: &str) -> AdapterChoice {
+        match value.to_lowercase().as_str() {
+            "" | "default" => AdapterChoice::Default,
+            "low-power" | "integrated" => AdapterChoice::LowPower,
+            "high-performance" | "discrete" => AdapterChoice::HighPerformance,
+            lower => match lower.parse() {
+                Ok(index) => AdapterChoice::Index(index),
+                Err(_) => AdapterChoice::Name(lower.to_string()),
+            },
+        }
+    }
+
+    /// Find the index of the chosen adapter among adapters, if a specific one is chosen.
+    pub fn select(&self, adapters: &[wgpu::AdapterInfo]) -> Option<usize> {
+        match self {
+            AdapterChoice::Index(index) => (*index < adapters.len()).then_some(*index),
+            AdapterChoice::Name(name) => adapters.iter().position(|adapter| adapter.name.to_lowercase().contains(name)),
+            _ => None,
+        }
+    }
+
+    /// Get the power preference to request the adapter with, when none is chosen by index or name.
+    fn power_preference(&self) -> wgpu::PowerPreference {
+        match self {
+            AdapterChoice::LowPower => wgpu::PowerPreference::LowPower,
+            AdapterChoice::HighPerformance => wgpu::PowerPreference::HighPerformance,
+            _ => wgpu::PowerPreference::default(),
+        }
+    }
+}
+
+/// The display uniform used by the final pass.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct Display {
+    tonemap: u32,
+    exposure: f32,
+    encode_srgb: u32,
+    _padding: u32,
+    /// The columns of the matrix from the working space to linear sRGB.
+    to_linear_srgb: [[f32; 4]; 3],
+}
+
+/// The format the ray marched colors are rendered to, with room above 1 for tone mapping.
+const COLOR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// How many frames are averaged while the view is still in the hybrid draw mode.
+const MAX_SAMPLES: u32 = 16;
+
+/// How many frames the showcase averages as the view turns, short enough not to smear.
+const SHOWCASE_SAMPLES: u32 = 4;
+
+/// The view uniform used to build rays.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct View {
+    view_projection: [f32; 16],
+    inverse_view_projection: [f32; 16],
+    previous_view_projection: [f32; 16],
+    previous_inverse_view_projection: [f32; 16],
+    history_valid: u32,
+    _padding: [u32; 3],
+}
+
+/// The uniform describing a single chunk.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct ChunkSettings {
+    object_to_world: [f32; 16],
+    world_to_object: [f32; 16],
+    root_center: [f32; 3],
+    root_size: f32,
+    resolution: u32,
+    layer: u32,
+    _padding: [u32; 2],
+}
+
+/// The uniform describing the brush preview.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct Preview {
+    center: [f32; 3],
+    size: f32,
+    shape: u32,
+    _padding: [u32; 3],
+    color: [f32; 4],
+}
+
+impl Preview {
+    /// Describe a stamp for the shader.
+    fn from_stamp(stamp: &Stamp) -> Self {
+        Self {
+            center: stamp.center.to_array(),
+            size: stamp.size,
+            shape: match stamp.shape {
+                TipShape::Sphere => 0,
+                TipShape::Cube => 1,
+            },
+            _padding: [0; 3],
+            color: [0.55, 0.8, 1.0, 0.5],
+        }
+    }
+}
+
+/// The uniform laying out the material strip.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct Strip {
+    count: u32,
+    selected: u32,
+    aspect: f32,
+    radius: f32,
+    origin: [f32; 2],
+    spacing: f32,
+    _padding: u32,
+}
+
+impl Strip {
+    /// Lay out a material strip for the shader, in a viewport with an aspect ratio.
+    fn from_strip(strip: &MaterialStrip, aspect: f32) -> Self {
+        Self {
+            count: strip.count,
+            selected: strip.selected.unwrap_or(u32::MAX),
+            aspect,
+            radius: MaterialStrip::RADIUS,
+            origin: MaterialStrip::ORIGIN.to_array(),
+            spacing: MaterialStrip::SPACING,
+            _padding: 0,
+        }
+    }
+}
+
+/// The uniform holding the lights the sculpt is shaded with.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct Lights {
+    /// How many of the lights are used, or 0 to light the sculpt from the camera.
+    count: u32,
+    _padding: [u32; 3],
+    lights: [GpuLight; MAX_LIGHTS],
+}
+
+impl Lights {
+    /// Lay out lights for the shader, leaving out those beyond the most it shades with.
+    fn from_lights(lights: &[Light]) -> Self {
+        let mut uniform = Self::zeroed();
+        for (slot, light) in uniform.lights.iter_mut().zip(lights) {
+            *slot = light.to_buffer();
+        }
+        uniform.count = lights.len().min(MAX_LIGHTS) as u32;
+
+        uniform
+    }
+}
+
+/// The layer picked on the spheres of the material strip.
+pub const MATERIAL_STRIP_LAYER: u32 = u32::MAX;
+
+/// What was drawn under a point of the screen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PickResult {
+    pub hit: bool,
+    /// The scene object hit, or `MATERIAL_STRIP_LAYER` for the material strip.
+    pub layer: u32,
+    pub material: u32,
+    /// The depth of the leaf in the octree, with the root at 0.
+    pub depth: u32,
+}
+
+impl PickResult {
+    /// Read a result from the texels of the pick target.
+    fn from_texel(texel: [u32; 4]) -> Self {
+        Self {
+            hit: texel[3] != 0,
+            layer: texel[0],
+            material: texel[1],
+            depth: texel[2],
+        }
+    }
+}
+
+/// A point of the surface seen through a pixel of a frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VisiblePoint {
+    /// The scene object the point is on.
+    pub layer: u32,
+    pub position: Vec3,
+    /// The direction of the ray through the pixel, away from the camera.
+    pub direction: Vec3,
+}
+
+impl VisiblePoint {
+    /// Find the points seen through every pixel of a square frame, from the texels of its pick and history targets.
+    ///
+    /// Pixels missing the sculpt, or showing the material strip, have no point.
+    fn from_texels(pick: &[u8], distances: &[u8], resolution: u32, view: Mat4) -> Vec<Self> {
+        let inverse = view.inverse();
+        let pick: &[[u32; 4]] = bytemuck::cast_slice(pick);
+        let distances: &[u32] = bytemuck::cast_slice(distances);
+
+        pick.iter()
+            .zip(distances)
+            .enumerate()
+            .filter_map(|(index, (texel, distance))| {
+                let pixel = uvec2(index as u32 % resolution, index as u32 / resolution);
+                Self::from_texel(*texel, *distance, pixel, resolution, inverse)
+            })
+            .collect()
+    }
+
+    /// Find the point seen through a pixel of a square frame, if any, given the inverse of its view.
+    fn from_texel(texel: [u32; 4], distance: u32, pixel: UVec2, resolution: u32, inverse: Mat4) -> Option<Self> {
+        if texel[3] == 0 || texel[0] == MATERIAL_STRIP_LAYER {
+            return None;
+        }
+        // the same ray the pixel was marched along, through its center
+        let pixel = pixel.as_vec2() + 0.5;
+        let ndc = vec2(pixel.x / resolution as f32 * 2.0 - 1.0, 1.0 - pixel.y / resolution as f32 * 2.0);
+        let origin = inverse.project_point3(ndc.extend(0.0));
+        let direction = (inverse.project_point3(ndc.extend(1.0)) - origin).normalize();
+
+        Some(Self {
+            layer: texel[0],
+            position: origin + direction * f32::from_bits(distance),
+            direction,
+        })
+    }
+}
+
+/// Which chunk of which scene object a chunk's buffers belong to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ChunkId {
+    pub object: u32,
+    pub coordinate: IVec3,
+}
+
+/// A chunk drawn at one of the placements of its object.
+///
+/// Instances share the voxel buffers of their chunk, with settings of their own.
+struct ChunkPlacement {
+    /// The box around the chunk in the world, once placed.
+    world_bounds: Aabb,
+    settings: UniformBlock<ChunkSettings>,
+    bind_group: wgpu::BindGroup,
+}
+
+/// The GPU resources of a single chunk.
+struct ChunkBuffers {
+    /// The space covered by the chunk, in its object's space.
+    bounds: Aabb,
+    resolution: u32,
+    voxel_buffer: wgpu::Buffer,
+    heat_buffer: wgpu::Buffer,
+    paint_buffer: wgpu::Buffer,
+    /// One for the object itself, followed by one for each of its instances.
+    placements: Vec<ChunkPlacement>,
+    /// The triangles extracted from the voxels, while meshes are drawn.
+    mesh: Option<ChunkMesh>,
+}
 
 /// Handle rendering with wgpu.
 pub struct Renderer {
     adapter: wgpu::Adapter,
     window: Arc<Window>,
     surface_config: wgpu::SurfaceConfiguration,
+    /// Whether the surface has to be configured again before the next frame, like after resizes.
+    surface_outdated: bool,
     surface: wgpu::Surface<'static>,
     device: wgpu::Device,
     queue: wgpu::Queue,
     resolution: u32,
-    settings_buffer: wgpu::Buffer,
-    voxel_buffer: wgpu::Buffer,
+    settings: UniformBlock<Settings>,
+    display: UniformBlock<Display>,
+    view: Mat4,
+    view_uniform: UniformBlock<View>,
     material_buffer: wgpu::Buffer,
-    ray_marching_pipeline: wgpu::RenderPipeline,
-    ray_marching_bind_group: wgpu::BindGroup,
+    chunks: HashMap<ChunkId, ChunkBuffers>,
+    /// The placements of each scene object in the world, the object's own first.
+    object_transforms: HashMap<u32, Vec<Mat4>>,
+    ray_marching_variants: PipelineVariants,
+    draw_mode: DrawMode,
+    mesh_extraction: MeshExtraction,
+    /// The chunks of an earlier state of an object, blended over the sculpt as an onion skin.
+    ghost_chunks: HashMap<ChunkId, ChunkBuffers>,
+    ghost_variants: PipelineVariants,
+    /// The traversal limits the ray marching pipelines were created with.
+    quality: RenderQuality,
+    /// The pool shader variants compile on.
+    jobs: JobPool,
+    ray_marching_bind_groups: [wgpu::BindGroup; 2],
     ray_marching_texture: wgpu::Texture,
     ray_marching_texture_view: wgpu::TextureView,
+    depth_texture_view: wgpu::TextureView,
+    history_textures: [wgpu::Texture; 2],
+    history_texture_views: [wgpu::TextureView; 2],
+    pick_texture: wgpu::Texture,
+    pick_texture_view: wgpu::TextureView,
+    history_index: usize,
+    history_valid: bool,
+    previous_view: Mat4,
+    /// The average of the frames drawn since the view or the sculpt last changed.
+    accumulation_texture: wgpu::Texture,
+    accumulate_pipeline: wgpu::RenderPipeline,
+    accumulate_bind_group: wgpu::BindGroup,
+    /// How many frames the accumulation holds, counting the last one.
+    samples: u32,
+    /// Whether the last frame was drawn while the view moved.
+    moving: bool,
+    /// Whether the view slowly turns for a showcase, drawn with rays and averaged as it moves.
+    showcase: bool,
+    /// How many frames were drawn for the showcase, picking the jitter of each.
+    showcase_frames: u32,
     render_pipeline: wgpu::RenderPipeline,
     render_bind_group: wgpu::BindGroup,
+    preview: Option<Stamp>,
+    preview_uniform: UniformBlock<Preview>,
+    preview_pipeline: wgpu::RenderPipeline,
+    preview_bind_groups: [wgpu::BindGroup; 2],
+    material_strip: Option<MaterialStrip>,
+    strip_uniform: UniformBlock<Strip>,
+    strip_pipeline: wgpu::RenderPipeline,
+    lights: UniformBlock<Lights>,
+    overlay: Overlay,
+    readback: ReadbackPool,
 }
 
 impl Renderer {
     /// Create a new context asynchronously (which will be resolved synchronously with pollster).
     /// Requesting an adapter and device should not take very long, so this is OK.
-    pub async fn new_async(window: Arc<Window>, resolution: u32) -> Renderer {
+    ///
+    /// Adapters chosen by index or name which can't render to the window
+    /// are passed over for the one wgpu picks.
+    ///
+    /// Shader variants compile on the job pool. Fails if no adapter can
+    /// draw to the window, or the GPU can't be set up for it.
+    pub async fn new_async(window: Arc<Window>, resolution: u32, choice: &AdapterChoice, jobs: JobPool) -> Result<Renderer, Error> {
         let instance = wgpu::Instance::default();
-        let surface = instance.create_surface(Arc::clone(&window)).unwrap();
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                force_fallback_adapter: false,
-                // request an adapter which can render to our surface
-                compatible_surface: Some(&surface),
-            })
-            .await
-            .expect("Failed to find an appropriate adapter.");
+        let surface = instance.create_surface(Arc::clone(&window))?;
+        let mut adapters = instance.enumerate_adapters(wgpu::Backends::all());
+        let infos: Vec<wgpu::AdapterInfo> = adapters.iter().map(|adapter| adapter.get_info()).collect();
+        let chosen = choice.select(&infos)
+            .map(|index| adapters.swap_remove(index))
+            .filter(|adapter| adapter.is_surface_supported(&surface));
+        let adapter = match chosen {
+            Some(adapter) => adapter,
+            None => instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: choice.power_preference(),
+                    force_fallback_adapter: false,
+                    // request an adapter which can render to our surface
+                    compatible_surface: Some(&surface),
+                })
+                .await
+                .ok_or(Error::NoAdapter)?,
+        };
         // create the logical device and command queue
         let (device, queue) = adapter
             .request_device(
@@ -54,23 +556,24 @@ impl Renderer {
                 },
                 None,
             )
-            .await
-            .expect("Failed to create the device.");
+            .await?;
 
         let size = window.inner_size();
         // stop wgpu from panicing if these are less than 1
         let width = size.width.max(1);
         let height = size.height.max(1);
 
-        let surface_config = surface.get_default_config(&adapter, width, height).unwrap();
+        let mut surface_config = surface.get_default_config(&adapter, width, height).ok_or(Error::UnsupportedSurface)?;
+        surface_config.format = Renderer::surface_format(&surface.get_capabilities(&adapter).formats);
+        surface_config.view_formats = Vec::new();
 
         surface.configure(&device, &surface_config);
 
         let ray_marching_texture = device.create_texture(&wgpu::TextureDescriptor {
             label: Some("Ray Marching Texture"),
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8Unorm,
-            view_formats: &[wgpu::TextureFormat::Rgba8Unorm],
+            format: COLOR_FORMAT,
+            view_formats: &[COLOR_FORMAT],
             mip_level_count: 1,
             sample_count: 1,
             size: wgpu::Extent3d {
@@ -78,7 +581,7 @@ impl Renderer {
                 height: resolution,
                 depth_or_array_layers: 1,
             },
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::RENDER_ATTACHMENT,
         });
 
         let ray_marching_texture_view = ray_marching_texture.create_view(&wgpu::TextureViewDescriptor {
@@ -93,23 +596,97 @@ impl Renderer {
             usage: None,
         });
 
-        let settings_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Settings Buffer"),
-            size: 1 * 4,
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false
+        let accumulation_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Accumulation Texture"),
+            dimension: wgpu::TextureDimension::D2,
+            format: COLOR_FORMAT,
+            view_formats: &[],
+            mip_level_count: 1,
+            sample_count: 1,
+            size: wgpu::Extent3d {
+                width: resolution,
+                height: resolution,
+                depth_or_array_layers: 1,
+            },
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::RENDER_ATTACHMENT,
         });
 
-        queue.write_buffer(&settings_buffer, 0, cast_slice(&[resolution]));
+        let accumulation_texture_view = accumulation_texture.create_view(&wgpu::TextureViewDescriptor::default());
 
-        let voxel_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Voxel Buffer"),
-            size: 134217728,
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false
+        let depth_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Depth Texture"),
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Depth32Float,
+            view_formats: &[],
+            mip_level_count: 1,
+            sample_count: 1,
+            size: wgpu::Extent3d {
+                width: resolution,
+                height: resolution,
+                depth_or_array_layers: 1,
+            },
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
         });
 
-        queue.write_buffer(&voxel_buffer, 0, cast_slice(&[0, 0]));
+        let depth_texture_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // the ray distances to the surface hit in a frame (as float bits, for portability),
+        // read back by the next frame to start rays near the surface
+        let history_textures = [0, 1].map(|_| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("History Texture"),
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::R32Uint,
+                view_formats: &[],
+                mip_level_count: 1,
+                sample_count: 1,
+                size: wgpu::Extent3d {
+                    width: resolution,
+                    height: resolution,
+                    depth_or_array_layers: 1,
+                },
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            })
+        });
+        let history_texture_views = history_textures.each_ref().map(|texture| texture.create_view(&wgpu::TextureViewDescriptor::default()));
+
+        // the layer, material and octree depth of the surface under each pixel
+        let pick_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Pick Texture"),
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba32Uint,
+            view_formats: &[],
+            mip_level_count: 1,
+            sample_count: 1,
+            size: wgpu::Extent3d {
+                width: resolution,
+                height: resolution,
+                depth_or_array_layers: 1,
+            },
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        });
+
+        let pick_texture_view = pick_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let settings = UniformBlock::new(&device, "Settings Buffer", Settings {
+            resolution,
+            display_mode: DisplayMode::default() as u32,
+            traversal: Traversal::default() as u32,
+            sample: 0,
+        });
+
+        let display = UniformBlock::new(&device, "Display Buffer", Display {
+            tonemap: Tonemap::default() as u32,
+            exposure: 1.0,
+            // linear float surfaces are encoded by the compositor
+            encode_srgb: !(surface_config.format.is_srgb() || surface_config.format == wgpu::TextureFormat::Rgba16Float) as u32,
+            _padding: 0,
+            to_linear_srgb: [[1.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0]],
+        });
+
+        let view_uniform = UniformBlock::new(&device, "View Buffer", View::zeroed());
+
+        let view = Mat4::IDENTITY;
 
         let material_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Material Buffer"),
@@ -118,33 +695,42 @@ impl Renderer {
             mapped_at_creation: false
         });
 
-        queue.write_buffer(&material_buffer, 0, cast_slice(&Material::default().to_buffer()));
+        queue.write_buffer(&material_buffer, 0, bytes_of(&Material::default().to_buffer()));
 
         queue.submit([]);
 
-        let ray_marching_pipeline = Renderer::create_ray_marching_pipeline(&device);
+        let quality = RenderQuality::default();
+
+        let (ray_marching_variants, ghost_variants) = Renderer::create_ray_marching_variants(&device, &jobs, quality);
+        let ray_marching_pipeline = ray_marching_variants.complete();
+
+        let mesh_extraction = MeshExtraction::new(&device, &queue, [
+            &ray_marching_pipeline.get_bind_group_layout(0),
+            &ray_marching_pipeline.get_bind_group_layout(1),
+        ], COLOR_FORMAT);
+
+        let strip_uniform = UniformBlock::new(&device, "Strip Buffer", Strip::zeroed());
+
+        let lights = UniformBlock::new(&device, "Lights Buffer", Lights::zeroed());
+
+        let strip_pipeline = Renderer::create_strip_pipeline(&device, &ray_marching_pipeline.get_bind_group_layout(0));
 
         let render_pipeline = Renderer::create_render_pipeline(&device, surface_config.format);
 
-        let ray_marching_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        let accumulate_pipeline = Renderer::create_accumulate_pipeline(&device, &render_pipeline.get_bind_group_layout(0));
+
+        // each frame writes one history texture and reads the other
+        let ray_marching_bind_groups = [1, 0].map(|read| device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("Ray Marching Bind Group"),
             layout: &ray_marching_pipeline.get_bind_group_layout(0),
             entries: &[
                 wgpu::BindGroupEntry { 
                     binding: 0, 
-                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                        buffer: &settings_buffer,
-                        offset: 0,
-                        size: None,
-                    })
+                    resource: settings.binding(),
                 },
                 wgpu::BindGroupEntry { 
                     binding: 1, 
-                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                        buffer: &voxel_buffer,
-                        offset: 0,
-                        size: None,
-                    })
+                    resource: view_uniform.binding(),
                 },
                 wgpu::BindGroupEntry { 
                     binding: 2, 
@@ -154,8 +740,20 @@ impl Renderer {
                         size: None,
                     })
                 },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(&history_texture_views[read]),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: strip_uniform.binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: lights.binding(),
+                },
             ],
-        });
+        }));
 
         let render_sampler = device.create_sampler(&wgpu::SamplerDescriptor{
               mag_filter: wgpu::FilterMode::Linear,
@@ -163,82 +761,166 @@ impl Renderer {
               ..Default::default()
         });
 
-        let render_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Render Bind Group"),
-            layout: &render_pipeline.get_bind_group_layout(0),
+        // the final pass shows the accumulation, which the accumulate pass blends the ray marched colors into
+        let [accumulate_bind_group, render_bind_group] = [&ray_marching_texture_view, &accumulation_texture_view].map(|texture_view| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Render Bind Group"),
+                layout: &render_pipeline.get_bind_group_layout(0),
+                entries: &[
+                    wgpu::BindGroupEntry { 
+                        binding: 0, 
+                        resource: settings.binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&render_sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::TextureView(texture_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: display.binding(),
+                    },
+                ],
+            })
+        });
+
+        let preview_uniform = UniformBlock::new(&device, "Preview Buffer", Preview::zeroed());
+
+        let preview_pipeline = Renderer::create_preview_pipeline(&device, surface_config.format);
+
+        // the preview is drawn after ray marching, so it reads the history written that frame
+        let preview_bind_groups = [0, 1].map(|read| device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Preview Bind Group"),
+            layout: &preview_pipeline.get_bind_group_layout(0),
             entries: &[
-                wgpu::BindGroupEntry { 
-                    binding: 0, 
-                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                        buffer: &settings_buffer,
-                        offset: 0,
-                        size: None,
-                    })
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: view_uniform.binding(),
                 },
                 wgpu::BindGroupEntry {
                     binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&render_sampler),
+                    resource: preview_uniform.binding(),
                 },
                 wgpu::BindGroupEntry {
                     binding: 2,
-                    resource: wgpu::BindingResource::TextureView(&ray_marching_texture_view),
+                    resource: wgpu::BindingResource::TextureView(&history_texture_views[read]),
                 },
             ],
-        });
+        }));
+
+        let overlay = Overlay::new(&device, surface_config.format);
 
-        Renderer {
+        Ok(Renderer {
             resolution,
+            settings,
+            display,
             surface,
             surface_config,
+            surface_outdated: false,
             adapter,
             window,
             device,
             queue,
-            settings_buffer,
-            voxel_buffer,
+            view,
+            view_uniform,
             material_buffer,
-            ray_marching_pipeline,
-            ray_marching_bind_group,
+            chunks: HashMap::new(),
+            object_transforms: HashMap::new(),
+            ray_marching_variants,
+            draw_mode: DrawMode::default(),
+            mesh_extraction,
+            ghost_chunks: HashMap::new(),
+            ghost_variants,
+            quality,
+            jobs,
+            ray_marching_bind_groups,
             ray_marching_texture,
             ray_marching_texture_view,
+            depth_texture_view,
+            history_textures,
+            history_texture_views,
+            pick_texture,
+            pick_texture_view,
+            history_index: 0,
+            history_valid: false,
+            previous_view: view,
+            accumulation_texture,
+            accumulate_pipeline,
+            accumulate_bind_group,
+            samples: 0,
+            moving: false,
+            showcase: false,
+            showcase_frames: 0,
             render_pipeline,
             render_bind_group,
-        }
+            preview: None,
+            preview_uniform,
+            preview_pipeline,
+            preview_bind_groups,
+            material_strip: None,
+            strip_uniform,
+            strip_pipeline,
+            lights,
+            overlay,
+            readback: ReadbackPool::new(),
+        })
+    }
+
+    /// Create the variants of the ray marching and ghost pipelines, with the traversal limits of a quality.
+    fn create_ray_marching_variants(device: &wgpu::Device, jobs: &JobPool, quality: RenderQuality) -> (PipelineVariants, PipelineVariants) {
+        let ray_marching_variants = PipelineVariants::new(device, jobs.clone(), move |device, features| {
+            Renderer::create_ray_marching_pipeline(device, &quality, features)
+        });
+        let layouts = [0, 1].map(|index| ray_marching_variants.complete().get_bind_group_layout(index));
+        let ghost_variants = PipelineVariants::new(device, jobs.clone(), move |device, features| {
+            Renderer::create_ghost_pipeline(device, &[&layouts[0], &layouts[1]], &quality, features)
+        });
+
+        (ray_marching_variants, ghost_variants)
+    }
+
+    /// Get the source of the ray marching shader with some features.
+    fn ray_marching_source(features: ShaderFeatures) -> wgpu::ShaderSource<'static> {
+        wgpu::ShaderSource::Wgsl(Cow::Owned(variants::compose(include_str!("../shaders/ray_marching.wgsl"), features)))
     }
 
-    /// Create the pipeline for ray marching voxels.
+    /// Create the pipeline for ray marching voxels, with the traversal limits of a quality and some features.
     pub fn create_ray_marching_pipeline(
         device: &wgpu::Device,
+        quality: &RenderQuality,
+        features: ShaderFeatures,
     ) -> wgpu::RenderPipeline {
-        // load the shaders from disk
+        let constants = quality.constants();
+
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Ray Marching Shader Module"),
-            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("../shaders/ray_marching.wgsl"))),
+            source: Renderer::ray_marching_source(features),
         });
 
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("Ray Marching Bind Group Layout"),
             entries: &[
                 wgpu::BindGroupLayoutEntry {
-                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
                     binding: 0,
                     count: None,
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Uniform,
                         has_dynamic_offset: false,
-                        min_binding_size: NonZero::new(1 * 4),
+                        min_binding_size: NonZero::new(size_of::<Settings>() as u64),
                     }
                 },
                 wgpu::BindGroupLayoutEntry {
-                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
                     binding: 1,
                     count: None,
                     ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage {
-                            read_only: true,
-                        },
+                        ty: wgpu::BufferBindingType::Uniform,
                         has_dynamic_offset: false,
-                        min_binding_size: NonZero::new(134217728),
+                        min_binding_size: NonZero::new(size_of::<View>() as u64),
                     }
                 },
                 wgpu::BindGroupLayoutEntry {
@@ -253,37 +935,260 @@ impl Renderer {
                         min_binding_size: NonZero::new(134217728),
                     }
                 },
+                wgpu::BindGroupLayoutEntry {
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    binding: 3,
+                    count: None,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        sample_type: wgpu::TextureSampleType::Uint,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                },
+                wgpu::BindGroupLayoutEntry {
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    binding: 4,
+                    count: None,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: NonZero::new(size_of::<Strip>() as u64),
+                    }
+                },
+                wgpu::BindGroupLayoutEntry {
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    binding: 5,
+                    count: None,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: NonZero::new(size_of::<Lights>() as u64),
+                    }
+                },
             ],
         });
 
-        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("Ray Marching Pipeline Layout"),
-            bind_group_layouts: &[
-                &bind_group_layout,
-            ],
-            ..Default::default()
-        });
-
-        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Ray Marching Render Pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: Some("vertex_main"),
-                buffers: &[],
-                compilation_options: Default::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: Some("fragment_main"),
-                compilation_options: Default::default(),
-                targets: &[Some(wgpu::TextureFormat::Rgba8Unorm.into())],
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleStrip,
+        let chunk_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Chunk Bind Group Layout"),
+            entries: &[
+                // the settings and voxels are also read when extracting meshes
+                wgpu::BindGroupLayoutEntry {
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT | wgpu::ShaderStages::COMPUTE,
+                    binding: 0,
+                    count: None,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: NonZero::new(size_of::<ChunkSettings>() as u64),
+                    }
+                },
+                wgpu::BindGroupLayoutEntry {
+                    visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
+                    binding: 1,
+                    count: None,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage {
+                            read_only: true,
+                        },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    }
+                },
+                wgpu::BindGroupLayoutEntry {
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    binding: 2,
+                    count: None,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage {
+                            read_only: true,
+                        },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    }
+                },
+                wgpu::BindGroupLayoutEntry {
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    binding: 3,
+                    count: None,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage {
+                            read_only: true,
+                        },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    }
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Ray Marching Pipeline Layout"),
+            bind_group_layouts: &[
+                &bind_group_layout,
+                &chunk_bind_group_layout,
+            ],
+            ..Default::default()
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Ray Marching Render Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vertex_main"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fragment_main"),
+                compilation_options: wgpu::PipelineCompilationOptions {
+                    constants: &constants,
+                    ..Default::default()
+                },
+                targets: &[
+                    Some(COLOR_FORMAT.into()),
+                    Some(wgpu::TextureFormat::R32Uint.into()),
+                    Some(wgpu::TextureFormat::Rgba32Uint.into()),
+                ],
+            }),
+            // both sides of the chunk boxes are drawn, so a camera inside of a chunk still marches it
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: None,
                 ..Default::default()
             },
-            depth_stencil: None,
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    /// Create the pipeline for the onion skin ghost, drawn in the ray marching pass after the sculpt.
+    ///
+    /// Ghost chunks are marched like the sculpt's, but only blend their color
+    /// where they are in front of it, leaving the history and pick targets alone.
+    pub fn create_ghost_pipeline(device: &wgpu::Device, bind_group_layouts: &[&wgpu::BindGroupLayout], quality: &RenderQuality, features: ShaderFeatures) -> wgpu::RenderPipeline {
+        let constants = quality.constants();
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Ghost Shader Module"),
+            source: Renderer::ray_marching_source(features),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Ghost Pipeline Layout"),
+            bind_group_layouts,
+            ..Default::default()
+        });
+
+        let untouched = |format: wgpu::TextureFormat| Some(wgpu::ColorTargetState {
+            format,
+            blend: None,
+            write_mask: wgpu::ColorWrites::empty(),
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Ghost Render Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vertex_main"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("ghost_fragment_main"),
+                compilation_options: wgpu::PipelineCompilationOptions {
+                    constants: &constants,
+                    ..Default::default()
+                },
+                targets: &[
+                    Some(wgpu::ColorTargetState {
+                        format: COLOR_FORMAT,
+                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    }),
+                    untouched(wgpu::TextureFormat::R32Uint),
+                    untouched(wgpu::TextureFormat::Rgba32Uint),
+                ],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: None,
+                ..Default::default()
+            },
+            // the depth keeps the ghost behind the sculpt hidden, and only the nearest ghost chunk blended
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    /// Create the pipeline for the material strip, drawn in the ray marching pass.
+    ///
+    /// It shares the shader and first bind group of ray marching, so the
+    /// spheres are shaded like the sculpt and picked like it.
+    pub fn create_strip_pipeline(device: &wgpu::Device, bind_group_layout: &wgpu::BindGroupLayout) -> wgpu::RenderPipeline {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Strip Shader Module"),
+            source: Renderer::ray_marching_source(ShaderFeatures::ALL),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Strip Pipeline Layout"),
+            bind_group_layouts: &[
+                bind_group_layout,
+            ],
+            ..Default::default()
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Strip Render Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("strip_vertex_main"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("strip_fragment_main"),
+                compilation_options: Default::default(),
+                targets: &[
+                    Some(COLOR_FORMAT.into()),
+                    Some(wgpu::TextureFormat::R32Uint.into()),
+                    Some(wgpu::TextureFormat::Rgba32Uint.into()),
+                ],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+            // the spheres are in front of the whole sculpt
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
             multisample: wgpu::MultisampleState::default(),
             multiview: None,
             cache: None,
@@ -308,7 +1213,7 @@ impl Renderer {
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Uniform,
                         has_dynamic_offset: false,
-                        min_binding_size: NonZero::new(1 * 4),
+                        min_binding_size: NonZero::new(size_of::<Settings>() as u64),
                     }
                 },
                 wgpu::BindGroupLayoutEntry {
@@ -327,6 +1232,16 @@ impl Renderer {
                     },
                     count: None,
                 },
+                wgpu::BindGroupLayoutEntry {
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    binding: 3,
+                    count: None,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: NonZero::new(size_of::<Display>() as u64),
+                    }
+                },
             ],
         });
 
@@ -364,68 +1279,920 @@ impl Renderer {
         })
     }
 
+    /// Create the pipeline blending each frame into the accumulation, sharing the bind group layout of the final pass.
+    ///
+    /// The blend constant is set to the weight of the new frame.
+    pub fn create_accumulate_pipeline(device: &wgpu::Device, bind_group_layout: &wgpu::BindGroupLayout) -> wgpu::RenderPipeline {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Accumulate Shader Module"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("../shaders/render.wgsl"))),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Accumulate Pipeline Layout"),
+            bind_group_layouts: &[
+                bind_group_layout,
+            ],
+            ..Default::default()
+        });
+
+        let weighted = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::Constant,
+            dst_factor: wgpu::BlendFactor::OneMinusConstant,
+            operation: wgpu::BlendOperation::Add,
+        };
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Accumulate Render Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vertex_main"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("accumulate_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: COLOR_FORMAT,
+                    blend: Some(wgpu::BlendState {
+                        color: weighted,
+                        alpha: weighted,
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    /// Create the pipeline for the translucent brush preview.
+    pub fn create_preview_pipeline(device: &wgpu::Device, swap_chain_format: wgpu::TextureFormat) -> wgpu::RenderPipeline {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Preview Shader Module"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("../shaders/preview.wgsl"))),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Preview Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    binding: 0,
+                    count: None,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: NonZero::new(size_of::<View>() as u64),
+                    }
+                },
+                wgpu::BindGroupLayoutEntry {
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    binding: 1,
+                    count: None,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: NonZero::new(size_of::<Preview>() as u64),
+                    }
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        sample_type: wgpu::TextureSampleType::Uint,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Preview Pipeline Layout"),
+            bind_group_layouts: &[
+                &bind_group_layout,
+            ],
+            ..Default::default()
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Preview Render Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vertex_main"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fragment_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: swap_chain_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        })
+    }
+
     /// Create a context, using pollster to keep it synchronous.
-    pub fn new(window: Arc<Window>, resolution: u32) -> Renderer {
-        pollster::block_on(Renderer::new_async(window, resolution))
+    pub fn new(window: Arc<Window>, resolution: u32, choice: &AdapterChoice, jobs: JobPool) -> Result<Renderer, Error> {
+        pollster::block_on(Renderer::new_async(window, resolution, choice, jobs))
+    }
+
+    /// Get the adapters the renderer can choose from, in the order `AdapterChoice::Index` counts them.
+    pub fn enumerate_adapters() -> Vec<wgpu::AdapterInfo> {
+        wgpu::Instance::default()
+            .enumerate_adapters(wgpu::Backends::all())
+            .iter()
+            .map(|adapter| adapter.get_info())
+            .collect()
+    }
+
+    /// Get the adapter the renderer runs on.
+    pub fn get_adapter_info(&self) -> wgpu::AdapterInfo {
+        self.adapter.get_info()
+    }
+
+    /// Get the size of the buffers holding the chunks on the GPU, onion skin and meshes included, in bytes.
+    pub fn get_chunk_buffer_size(&self) -> u64 {
+        self.chunks.values()
+            .chain(self.ghost_chunks.values())
+            .map(|chunk| {
+                let mesh = chunk.mesh.as_ref().map_or(0, ChunkMesh::get_buffer_size);
+                chunk.voxel_buffer.size() + chunk.heat_buffer.size() + chunk.paint_buffer.size() + mesh
+            })
+            .sum()
+    }
+
+    /// Pick the surface format with the most precision, preferring
+    /// floating point and 10 bit formats over 8 bit sRGB ones.
+    fn surface_format(formats: &[wgpu::TextureFormat]) -> wgpu::TextureFormat {
+        let preferred = [
+            wgpu::TextureFormat::Rgba16Float,
+            wgpu::TextureFormat::Rgb10a2Unorm,
+        ];
+
+        preferred.into_iter()
+            .find(|format| formats.contains(format))
+            .or_else(|| formats.iter().copied().find(|format| format.is_srgb()))
+            .unwrap_or(formats[0])
     }
 
     /// Update context to match a new size of the window.
+    ///
+    /// The surface is configured for the size when the next frame is drawn,
+    /// so a storm of resizes while dragging the window configures it once.
     pub fn resize(&mut self, new_size: (u32, u32)) {
         let (width, height) = new_size;
         self.surface_config.width = width.max(1);
         self.surface_config.height = height.max(1);
-        self.surface.configure(&self.device, &self.surface_config);
+        self.surface_outdated = true;
+    }
+
+    /// Get the next texture of the surface to draw to, or None if the frame has to be skipped.
+    ///
+    /// Surfaces which went out of date or were lost are configured again,
+    /// for the next frame to draw to.
+    fn acquire_surface_texture(&mut self) -> Option<wgpu::SurfaceTexture> {
+        if std::mem::take(&mut self.surface_outdated) {
+            self.surface.configure(&self.device, &self.surface_config);
+        }
+        match self.surface.get_current_texture() {
+            // suboptimal textures can still be drawn to
+            Ok(surface_texture) => {
+                self.surface_outdated = surface_texture.suboptimal;
+                Some(surface_texture)
+            }
+            Err(wgpu::SurfaceError::Outdated | wgpu::SurfaceError::Lost) => {
+                self.surface.configure(&self.device, &self.surface_config);
+                None
+            }
+            Err(wgpu::SurfaceError::Timeout | wgpu::SurfaceError::Other) => None,
+            Err(wgpu::SurfaceError::OutOfMemory) => panic!("Ran out of memory for the next swap chain texture."),
+        }
+    }
+
+    /// Switch how frames are presented, reconfiguring the surface.
+    ///
+    /// Modes the surface does not support fall back to Fifo, which every
+    /// surface supports. Returns the mode which is now used.
+    pub fn set_present_mode(&mut self, present_mode: PresentMode) -> PresentMode {
+        let supported = self.surface.get_capabilities(&self.adapter).present_modes;
+        let present_mode = if supported.contains(&present_mode.to_wgpu()) {
+            present_mode
+        } else {
+            PresentMode::Fifo
+        };
+
+        if self.surface_config.present_mode != present_mode.to_wgpu() {
+            self.surface_config.present_mode = present_mode.to_wgpu();
+            self.surface.configure(&self.device, &self.surface_config);
+        }
+
+        present_mode
+    }
+
+    /// Show or hide (with None) the help overlay.
+    pub fn set_help(&mut self, help: Option<HelpPane>) {
+        self.overlay.set_help(help);
+    }
+
+    /// Show or hide (with None) the command palette.
+    pub fn set_palette(&mut self, palette: Option<CommandPalette>) {
+        self.overlay.set_palette(palette);
+    }
+
+    /// Show or hide (with None) the picker of saved versions.
+    pub fn set_version_picker(&mut self, picker: Option<VersionPicker>) {
+        self.overlay.set_version_picker(picker);
+    }
+
+    /// Show or hide (with None) the progress bars.
+    pub fn set_progress(&mut self, progress: Option<ProgressPane>) {
+        self.overlay.set_progress(progress);
+    }
+
+    /// Show or hide (with None) the CPU times.
+    pub fn set_profile(&mut self, profile: Option<ProfilePane>) {
+        self.overlay.set_profile(profile);
+    }
+
+    /// Set the markers of the pinned notes, where none hides them.
+    pub fn set_pins(&mut self, pins: Vec<PinMarker>) {
+        self.overlay.set_pins(pins);
+    }
+
+    /// Set the lines of the guide curves, where none hides them.
+    pub fn set_guides(&mut self, guides: Vec<GuideLine>) {
+        self.overlay.set_guides(guides);
+    }
+
+    /// Set the lines of the voxel grid the brush snaps to, where none hides it.
+    pub fn set_snap_grid(&mut self, grid: SnapGrid) {
+        self.overlay.set_snap_grid(grid);
+    }
+
+    /// Show or hide (with None) the material strip.
+    pub fn set_material_strip(&mut self, strip: Option<MaterialStrip>) {
+        self.material_strip = strip;
+    }
+
+    /// Get the contents of the view uniform for the next frame.
+    fn next_view(&self) -> View {
+        View {
+            view_projection: self.view.to_cols_array(),
+            inverse_view_projection: self.view.inverse().to_cols_array(),
+            previous_view_projection: self.previous_view.to_cols_array(),
+            previous_inverse_view_projection: self.previous_view.inverse().to_cols_array(),
+            history_valid: self.history_valid as u32,
+            _padding: [0; 3],
+        }
     }
 
-    /// Queue a change to the voxel buffer.
-    pub fn set_voxel_buffer(&mut self, voxels: Vec<u32>) {
-        self.queue.write_buffer(&self.voxel_buffer, 0, cast_slice(&voxels));
+    /// Set the view projection matrix used for rays and culling.
+    pub fn set_view(&mut self, view: Mat4) {
+        self.view = view;
+    }
+
+    /// Stop the next frame from starting rays at last frame's surface, or blending into the frames before it.
+    fn invalidate_history(&mut self) {
+        self.history_valid = false;
+    }
+
+    /// Set how the surface is colored.
+    pub fn set_display_mode(&mut self, display_mode: DisplayMode) {
+        self.settings.update(|settings| settings.display_mode = display_mode as u32);
+        // frames accumulated in the old colors would linger
+        self.invalidate_history();
+    }
+
+    /// Set how the surface of the sculpt is drawn.
+    ///
+    /// Meshes are extracted for every chunk with the next frame, and
+    /// dropped again when switching back to ray marching alone.
+    pub fn set_draw_mode(&mut self, draw_mode: DrawMode) {
+        if draw_mode == DrawMode::RayMarched {
+            for chunk in self.chunks.values_mut() {
+                chunk.mesh = None;
+            }
+        }
+        self.draw_mode = draw_mode;
+        self.invalidate_history();
+    }
+
+    /// Draw a showcase, or go back to the draw mode.
+    ///
+    /// While showcasing, the sculpt is ray marched whatever the draw mode,
+    /// and frames are blended into a short running average even as the
+    /// view moves, which smooths the edges of a slowly turning view.
+    pub fn set_showcase(&mut self, showcase: bool) {
+        if showcase != self.showcase {
+            self.showcase = showcase;
+            self.showcase_frames = 0;
+            self.invalidate_history();
+        }
+    }
+
+    /// Check whether the frames are still being refined, so more should be drawn even if nothing changes.
+    ///
+    /// This is while shader variants compile, and in the hybrid draw mode,
+    /// until the accumulation is full after the view stops moving.
+    pub fn is_refining(&self) -> bool {
+        let compiling = self.ray_marching_variants.is_compiling() || self.ghost_variants.is_compiling();
+
+        compiling || self.draw_mode == DrawMode::Hybrid && (self.moving || self.samples < MAX_SAMPLES)
+    }
+
+    /// Create the meshes of chunks which have none yet, while meshes are drawn.
+    fn create_meshes(&mut self) {
+        if self.draw_mode == DrawMode::RayMarched {
+            return;
+        }
+        for chunk in self.chunks.values_mut().filter(|chunk| chunk.mesh.is_none()) {
+            let entries = chunk.voxel_buffer.size() / size_of::<u32>() as u64;
+            chunk.mesh = Some(self.mesh_extraction.create_mesh(&self.device, entries));
+        }
+    }
+
+    /// Set how the final pass maps colors from the working space onto the display.
+    pub fn set_color_management(&mut self, color: &ColorManagement) {
+        let to_linear_srgb = color.working_space.to_linear_srgb();
+        self.display.update(|display| {
+            display.tonemap = color.tonemap as u32;
+            display.exposure = color.exposure.exp2();
+            display.to_linear_srgb = [to_linear_srgb.x_axis, to_linear_srgb.y_axis, to_linear_srgb.z_axis].map(|column| column.extend(0.0).to_array());
+        });
+    }
+
+    /// Set how the final pass maps colors onto the display, keeping the rest of the color management.
+    pub fn set_tonemap(&mut self, tonemap: Tonemap) {
+        self.display.update(|display| display.tonemap = tonemap as u32);
+    }
+
+    /// Set how much the colors are brightened before tone mapping, keeping the rest of the color management.
+    pub fn set_exposure(&mut self, exposure: f32) {
+        self.display.update(|display| display.exposure = exposure.max(0.0));
+    }
+
+    /// Set the lights the sculpt is shaded with, up to `MAX_LIGHTS`, or none to light it from the camera.
+    pub fn set_lights(&mut self, lights: &[Light]) {
+        let lights = Lights::from_lights(lights);
+        if bytes_of(&lights) == bytes_of(self.lights.get()) {
+            return;
+        }
+        self.lights.set(lights);
+        // frames accumulated under the old lights would linger
+        self.invalidate_history();
+    }
+
+    /// Set the traversal limits of the rays, creating the ray marching pipelines again with them.
+    ///
+    /// The new pipelines share the bind group layouts of the old ones, so the bind groups are kept.
+    pub fn set_quality(&mut self, quality: RenderQuality) {
+        if quality == self.quality {
+            return;
+        }
+        (self.ray_marching_variants, self.ghost_variants) = Renderer::create_ray_marching_variants(&self.device, &self.jobs, quality);
+        self.quality = quality;
+        self.invalidate_history();
+    }
+
+    /// Get the shader features the display mode and traversal need, for the variants to draw with.
+    fn shader_features(&self) -> ShaderFeatures {
+        let settings = self.settings.get();
+        let display = match settings.display_mode {
+            mode if mode == DisplayMode::Heat as u32 => ShaderFeatures::HEAT,
+            mode if mode == DisplayMode::Mask as u32 => ShaderFeatures::MASK,
+            _ => ShaderFeatures::NONE,
+        };
+        let traversal = if settings.traversal == Traversal::Descent as u32 { ShaderFeatures::DESCENT } else { ShaderFeatures::NONE };
+
+        display | traversal
+    }
+
+    /// Set how rays find voxels in the octree.
+    pub fn set_traversal(&mut self, traversal: Traversal) {
+        self.settings.update(|settings| settings.traversal = traversal as u32);
+    }
+
+    /// Queue a change to the voxel, edit heat and paint buffers of a chunk.
+    ///
+    /// The chunk is created if it is new, and its buffers grow
+    /// when the voxels no longer fit. The heat and paint buffers
+    /// have one value per voxel buffer entry.
+    pub fn set_chunk_buffer(&mut self, id: ChunkId, bounds: Aabb, resolution: u32, voxels: Vec<u32>, heat: Vec<f32>, paint: Vec<u32>) {
+        let size = (voxels.len() * size_of::<u32>()) as u64;
+
+        let fits = self.chunks.get(&id).is_some_and(|chunk| chunk.voxel_buffer.size() >= size);
+        if !fits {
+            let chunk = self.create_chunk_buffers(bounds, size);
+            self.chunks.insert(id, chunk);
+        }
+
+        let layout = self.ray_marching_variants.complete().get_bind_group_layout(1);
+        let transforms = self.object_transforms.get(&id.object).cloned().unwrap_or_else(|| vec![Mat4::IDENTITY]);
+        let chunk = self.chunks.get_mut(&id).unwrap();
+        chunk.bounds = bounds;
+        chunk.resolution = resolution;
+        if let Some(mesh) = chunk.mesh.as_mut() {
+            mesh.outdated = true;
+        }
+        Self::place_chunk(&self.device, &layout, chunk, id.object, &transforms);
+        self.queue.write_buffer(&chunk.voxel_buffer, 0, cast_slice(&voxels));
+        self.queue.write_buffer(&chunk.heat_buffer, 0, cast_slice(&heat));
+        self.queue.write_buffer(&chunk.paint_buffer, 0, cast_slice(&paint));
+        self.invalidate_history();
+    }
+
+    /// Drop the buffers of chunks which are not in the list.
+    pub fn retain_chunks(&mut self, ids: &[ChunkId]) {
+        let count = self.chunks.len();
+        self.chunks.retain(|id, _| ids.contains(id));
+        self.object_transforms.retain(|object, _| ids.iter().any(|id| id.object == *object));
+        if self.chunks.len() != count {
+            self.invalidate_history();
+        }
+    }
+
+    /// Place the chunks of a scene object in the world, once for each transform.
+    ///
+    /// The first transform places the object itself, and the rest its instances.
+    pub fn set_object_transforms(&mut self, object: u32, transforms: Vec<Mat4>) {
+        if self.object_transforms.get(&object) == Some(&transforms) {
+            return;
+        }
+        let layout = self.ray_marching_variants.complete().get_bind_group_layout(1);
+        for (id, chunk) in self.chunks.iter_mut().filter(|(id, _)| id.object == object) {
+            Self::place_chunk(&self.device, &layout, chunk, id.object, &transforms);
+        }
+        self.object_transforms.insert(object, transforms);
+        self.invalidate_history();
+    }
+
+    /// Update the placements of a chunk for its object and its object's transforms.
+    ///
+    /// Placements are added or dropped to match the transforms, sharing the voxel buffers of the chunk.
+    fn place_chunk(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, chunk: &mut ChunkBuffers, object: u32, transforms: &[Mat4]) {
+        chunk.placements.truncate(transforms.len());
+        while chunk.placements.len() < transforms.len() {
+            let placement = Self::create_chunk_placement(device, layout, chunk);
+            chunk.placements.push(placement);
+        }
+
+        let bounds = chunk.bounds;
+        for (placement, transform) in chunk.placements.iter_mut().zip(transforms) {
+            placement.world_bounds = bounds.transform(*transform);
+            placement.settings.set(ChunkSettings {
+                object_to_world: transform.to_cols_array(),
+                world_to_object: transform.inverse().to_cols_array(),
+                root_center: bounds.center().to_array(),
+                root_size: bounds.extent().max_element(),
+                resolution: chunk.resolution,
+                // the pick target reports the object under the cursor as its layer
+                layer: object,
+                _padding: [0; 2],
+            });
+        }
+    }
+
+    /// Create the settings and bind group drawing a chunk at one more placement.
+    fn create_chunk_placement(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, chunk: &ChunkBuffers) -> ChunkPlacement {
+        let settings = UniformBlock::new(device, "Chunk Settings Buffer", ChunkSettings::zeroed());
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Chunk Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry { 
+                    binding: 0, 
+                    resource: settings.binding(),
+                },
+                wgpu::BindGroupEntry { 
+                    binding: 1, 
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &chunk.voxel_buffer,
+                        offset: 0,
+                        size: None,
+                    })
+                },
+                wgpu::BindGroupEntry { 
+                    binding: 2, 
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &chunk.heat_buffer,
+                        offset: 0,
+                        size: None,
+                    })
+                },
+                wgpu::BindGroupEntry { 
+                    binding: 3, 
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &chunk.paint_buffer,
+                        offset: 0,
+                        size: None,
+                    })
+                },
+            ],
+        });
+
+        ChunkPlacement {
+            world_bounds: chunk.bounds,
+            settings,
+            bind_group,
+        }
+    }
+
+    /// Create the buffers of a chunk, which has no placements yet.
+    fn create_chunk_buffers(&self, bounds: Aabb, size: u64) -> ChunkBuffers {
+        // leave some room to grow so small strokes don't reallocate
+        let voxel_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Chunk Voxel Buffer"),
+            size: (size * 2).next_multiple_of(4096),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+
+        let heat_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Chunk Heat Buffer"),
+            size: voxel_buffer.size(),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+
+        let paint_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Chunk Paint Buffer"),
+            size: voxel_buffer.size(),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+
+        ChunkBuffers {
+            bounds,
+            resolution: 0,
+            voxel_buffer,
+            heat_buffer,
+            paint_buffer,
+            placements: Vec::new(),
+            mesh: None,
+        }
+    }
+
+    /// Show a chunk of an earlier state of an object as a translucent ghost, placed in the world by a transform.
+    pub fn set_ghost_chunk(&mut self, coordinate: IVec3, bounds: Aabb, resolution: u32, transform: Mat4, voxels: Vec<u32>) {
+        let id = ChunkId { object: 0, coordinate };
+        let mut chunk = self.create_chunk_buffers(bounds, (voxels.len() * size_of::<u32>()) as u64);
+        chunk.resolution = resolution;
+        // the ghost has no edit heat or paint, which the new buffers leave at zero
+        Self::place_chunk(&self.device, &self.ray_marching_variants.complete().get_bind_group_layout(1), &mut chunk, id.object, &[transform]);
+        self.queue.write_buffer(&chunk.voxel_buffer, 0, cast_slice(&voxels));
+        self.ghost_chunks.insert(id, chunk);
+        self.invalidate_history();
+    }
+
+    /// Hide the onion skin ghost.
+    pub fn clear_ghost(&mut self) {
+        self.ghost_chunks.clear();
+        self.invalidate_history();
+    }
+
+    /// Get the placed chunks inside of the view, sorted front to back.
+    fn visible_chunks(chunks: &HashMap<ChunkId, ChunkBuffers>, view: Mat4) -> Vec<&ChunkPlacement> {
+        let frustum = Frustum::from_matrix(view);
+
+        let mut visible: Vec<(f32, &ChunkPlacement)> = chunks.values()
+            .flat_map(|chunk| &chunk.placements)
+            .filter(|placement| frustum.intersects(&placement.world_bounds))
+            .map(|placement| (view.project_point3(placement.world_bounds.center()).z, placement))
+            .collect();
+        visible.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        visible.into_iter().map(|(_, chunk)| chunk).collect()
+    }
+
+    /// Show (or hide, with None) a translucent preview of where a stroke would stamp.
+    pub fn set_brush_preview(&mut self, stamp: Option<Stamp>) {
+        if let Some(stamp) = stamp.as_ref() {
+            self.preview_uniform.set(Preview::from_stamp(stamp));
+        }
+        self.preview = stamp;
     }
 
     /// Queue a change to the material buffer.
-    pub fn set_material_buffer(&mut self, materials: Vec<f32>) {
+    pub fn set_material_buffer(&mut self, materials: Vec<GpuMaterial>) {
         self.queue.write_buffer(&self.material_buffer, 0, cast_slice(&materials));
+        self.invalidate_history();
+    }
+
+    /// Find what was drawn under a point of the last frame.
+    ///
+    /// The point goes from 0 to 1 across the viewport, with y pointing down.
+    pub async fn pick(&self, x: f32, y: f32) -> PickResult {
+        let texel = self.texel_at(x, y);
+
+        let readback = self.readback.read_texture_async(
+            &self.device,
+            &self.queue,
+            &self.pick_texture,
+            wgpu::Origin3d { x: texel.x, y: texel.y, z: 0 },
+            wgpu::Extent3d { width: 1, height: 1, depth_or_array_layers: 1 },
+        );
+
+        match readback.await {
+            Ok(bytes) => PickResult::from_texel(*bytemuck::from_bytes(&bytes)),
+            Err(_) => PickResult::default(),
+        }
+    }
+
+    /// Read back the surface seen through every pixel of the last frame, calling back with its points.
+    ///
+    /// The callback runs once the device is polled, which happens every draw,
+    /// and gets None if the frame couldn't be read.
+    pub fn read_visible(&self, callback: impl FnOnce(Option<Vec<VisiblePoint>>) + Send + 'static) {
+        let resolution = self.resolution;
+        let view = self.previous_view;
+
+        self.read_pick_texels(UVec2::ZERO, resolution, move |texels| {
+            callback(texels.map(|[pick, distances]| VisiblePoint::from_texels(&pick, &distances, resolution, view)))
+        });
+    }
+
+    /// Read back the surface seen under a point of the last frame, calling back with it.
+    ///
+    /// The point goes from 0 to 1 across the viewport, with y pointing down.
+    /// The callback runs once the device is polled, which happens every draw,
+    /// and gets None if the point misses the sculpt or couldn't be read.
+    pub fn read_surface(&self, x: f32, y: f32, callback: impl FnOnce(Option<VisiblePoint>) + Send + 'static) {
+        let resolution = self.resolution;
+        let texel = self.texel_at(x, y);
+        let inverse = self.previous_view.inverse();
+
+        self.read_pick_texels(texel, 1, move |texels| {
+            callback(texels.and_then(|[pick, distance]| {
+                VisiblePoint::from_texel(*bytemuck::from_bytes(&pick), *bytemuck::from_bytes(&distance), texel, resolution, inverse)
+            }))
+        });
+    }
+
+    /// Get the texel of the last frame under a point, which goes from 0 to 1 across the viewport.
+    fn texel_at(&self, x: f32, y: f32) -> UVec2 {
+        (vec2(x, y) * self.resolution as f32)
+            .as_uvec2()
+            .min(UVec2::splat(self.resolution - 1))
+    }
+
+    /// Read back the pick and distance texels of a square of the last frame, calling back with their bytes.
+    fn read_pick_texels(&self, corner: UVec2, size: u32, callback: impl FnOnce(Option<[Vec<u8>; 2]>) + Send + 'static) {
+        // the last frame has already swapped its history for the next one
+        let history = &self.history_textures[1 - self.history_index];
+        let origin = wgpu::Origin3d { x: corner.x, y: corner.y, z: 0 };
+        let extent = wgpu::Extent3d { width: size, height: size, depth_or_array_layers: 1 };
+
+        self.readback.read_textures(
+            &self.device,
+            &self.queue,
+            &[(&self.pick_texture, origin, extent), (history, origin, extent)],
+            move |results| {
+                let texels: Result<Vec<Vec<u8>>, _> = results.into_iter().collect();
+                callback(texels.ok().and_then(|texels| texels.try_into().ok()))
+            },
+        );
+    }
+
+    /// Capture the last frame of the viewport, calling back with its pixels.
+    ///
+    /// The colors are clipped rather than tone mapped. The callback runs
+    /// once the device is polled, which happens every draw.
+    pub fn capture(&self, callback: impl FnOnce(Option<image::RgbaImage>) + Send + 'static) {
+        let resolution = self.resolution;
+
+        self.readback.read_texture(
+            &self.device,
+            &self.queue,
+            &self.accumulation_texture,
+            wgpu::Origin3d::ZERO,
+            wgpu::Extent3d { width: resolution, height: resolution, depth_or_array_layers: 1 },
+            move |result| callback(result.ok().and_then(|bytes| {
+                let pixels = bytes.chunks_exact(2)
+                    .map(|half| half_to_float(u16::from_le_bytes([half[0], half[1]])))
+                    .collect();
+                let image = image::Rgba32FImage::from_raw(resolution, resolution, pixels)?;
+
+                Some(image::DynamicImage::ImageRgba32F(image).to_rgba8())
+            })),
+        );
+    }
+
+    /// Upload the uniforms which changed since the last frame.
+    fn flush_uniforms(&mut self) {
+        let view = self.next_view();
+        self.view_uniform.set(view);
+
+        self.settings.flush(&self.queue);
+        self.display.flush(&self.queue);
+        self.view_uniform.flush(&self.queue);
+        self.preview_uniform.flush(&self.queue);
+        self.lights.flush(&self.queue);
+        if let Some(strip) = self.material_strip.as_ref() {
+            // the window may have been resized since the strip was set
+            let aspect = self.surface_config.width as f32 / self.surface_config.height as f32;
+            self.strip_uniform.set(Strip::from_strip(strip, aspect));
+            self.strip_uniform.flush(&self.queue);
+        }
+        for placement in self.chunks.values_mut().chain(self.ghost_chunks.values_mut()).flat_map(|chunk| &mut chunk.placements) {
+            placement.settings.flush(&self.queue);
+        }
     }
 
     /// Draw the contents to the wgpu surface.
-    pub fn draw(&mut self) {
-        let surface_texture = self
-            .surface
-            .get_current_texture()
-            .expect("Failed to acquire the next swap chain texture.");
+    ///
+    /// Returns whether the frame was drawn, as frames are skipped while
+    /// the surface can't be drawn to, like while it is configured again.
+    pub fn draw(&mut self) -> bool {
+        let Some(surface_texture) = self.acquire_surface_texture() else {
+            return false;
+        };
         let texture_view = surface_texture
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
+        self.render(Some(&texture_view), self.view != self.previous_view);
+        surface_texture.present();
+        // finish any reads which are ready
+        self.device.poll(wgpu::Maintain::Poll);
+
+        self.history_index = 1 - self.history_index;
+        self.history_valid = true;
+        self.previous_view = self.view;
+
+        true
+    }
+
+    /// Capture the viewport as seen from another view projection, calling back with its pixels.
+    ///
+    /// Nothing is composited onto the surface, and the next frame starts its rays
+    /// afresh, as the history then holds the other view. The callback runs
+    /// once the device is polled, which happens every draw.
+    pub fn capture_from(&mut self, view: Mat4, callback: impl FnOnce(Option<image::RgbaImage>) + Send + 'static) {
+        let live_view = std::mem::replace(&mut self.view, view);
+        self.invalidate_history();
+        self.render(None, false);
+        self.capture(callback);
+
+        self.view = live_view;
+    }
+
+    /// Render a frame, and composite it onto a surface texture if one is given.
+    ///
+    /// Each pass declares the textures it reads and writes, so the graph
+    /// runs them in order and new passes only need adding here.
+    ///
+    /// Frames of a still view are accumulated in the hybrid draw mode, until
+    /// the view or the sculpt changes, which starts over.
+    fn render(&mut self, surface: Option<&wgpu::TextureView>, moving: bool) {
+        let meshes = !self.showcase && (self.draw_mode == DrawMode::Mesh || (self.draw_mode == DrawMode::Hybrid && moving));
+        let (accumulating, limit) = if self.showcase {
+            (self.history_valid, SHOWCASE_SAMPLES)
+        } else {
+            (self.draw_mode == DrawMode::Hybrid && !moving && !self.moving && self.history_valid, MAX_SAMPLES)
+        };
+        self.samples = if accumulating { (self.samples + 1).min(limit) } else { 1 };
+        self.moving = moving && self.draw_mode == DrawMode::Hybrid && !self.showcase;
+        // the average of the showcase is kept full, so each frame takes the next jitter instead
+        let sample = if self.showcase { self.showcase_frames % MAX_SAMPLES } else { self.samples - 1 };
+        self.showcase_frames = self.showcase_frames.wrapping_add(u32::from(self.showcase));
+        self.settings.update(|settings| settings.sample = sample);
+        self.flush_uniforms();
+        let features = self.shader_features();
+        let ray_marching_pipeline = self.ray_marching_variants.get(features).clone();
+        let ghost_pipeline = self.ghost_variants.get(features).clone();
+        self.create_meshes();
+        // extract meshes from the voxels which changed since they were last extracted
+        let outdated: Vec<ChunkId> = self.chunks.iter_mut()
+            .filter_map(|(id, chunk)| chunk.mesh.as_mut().filter(|mesh| mesh.outdated).map(|mesh| {
+                mesh.outdated = false;
+                *id
+            }))
+            .collect();
         let mut encoder = self
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
-        {
+        let history_index = self.history_index;
+        let size = (self.surface_config.width, self.surface_config.height);
+        let pixels_per_point = self.window.scale_factor() as f32;
+
+        let mut graph = RenderGraph::new();
+        if !outdated.is_empty() {
+            graph.add_pass("extract meshes", &[], &["meshes"], |encoder| {
+                for id in &outdated {
+                    let chunk = &self.chunks[id];
+                    if let (Some(mesh), Some(placement)) = (chunk.mesh.as_ref(), chunk.placements.first()) {
+                        self.mesh_extraction.extract(&self.queue, encoder, mesh, &placement.bind_group, chunk.resolution);
+                    }
+                }
+            });
+        }
+        graph.add_pass("ray march", &["meshes"], &["color", "history", "pick", "depth"], |encoder| {
             let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Ray Marching Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &self.ray_marching_texture_view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::WHITE),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
+                color_attachments: &[
+                    Some(wgpu::RenderPassColorAttachment {
+                        view: &self.ray_marching_texture_view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color { r: 0.03, g: 0.04, b: 0.06, a: 1.0 }),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    }),
+                    // misses leave the history cleared
+                    Some(wgpu::RenderPassColorAttachment {
+                        view: &self.history_texture_views[history_index],
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    }),
+                    Some(wgpu::RenderPassColorAttachment {
+                        view: &self.pick_texture_view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    }),
+                ],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.depth_texture_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Discard,
+                    }),
+                    stencil_ops: None,
+                }),
                 timestamp_writes: None,
                 occlusion_query_set: None,
             });
-            rpass.set_pipeline(&self.ray_marching_pipeline);
-            rpass.set_bind_group(0, Some(&self.ray_marching_bind_group), &[]);
-            rpass.draw(0..4, 0..1);
-        }
-        {
+            rpass.set_bind_group(0, Some(&self.ray_marching_bind_groups[history_index]), &[]);
+            if meshes {
+                self.mesh_extraction.begin_draw(&mut rpass);
+                let frustum = Frustum::from_matrix(self.view);
+                for chunk in self.chunks.values() {
+                    let Some(mesh) = chunk.mesh.as_ref() else {
+                        continue;
+                    };
+                    for placement in chunk.placements.iter().filter(|placement| frustum.intersects(&placement.world_bounds)) {
+                        MeshExtraction::draw(&mut rpass, mesh, &placement.bind_group);
+                    }
+                }
+            } else {
+                rpass.set_pipeline(&ray_marching_pipeline);
+                // front to back, so pixels already hit by a closer chunk usually fail the depth
+                // test and never march the chunks behind them, while the depth of each hit keeps
+                // the nearest one where the chunks of several objects overlap
+                for placement in Self::visible_chunks(&self.chunks, self.view) {
+                    rpass.set_bind_group(1, Some(&placement.bind_group), &[]);
+                    rpass.draw(0..36, 0..1);
+                }
+            }
+            rpass.set_pipeline(&ghost_pipeline);
+            for placement in Self::visible_chunks(&self.ghost_chunks, self.view) {
+                rpass.set_bind_group(1, Some(&placement.bind_group), &[]);
+                rpass.draw(0..36, 0..1);
+            }
+            if let Some(strip) = self.material_strip.as_ref() {
+                rpass.set_pipeline(&self.strip_pipeline);
+                rpass.draw(0..4, 0..strip.count);
+            }
+        });
+        let weight = 1.0 / self.samples as f64;
+        graph.add_pass("accumulate", &["color"], &["accumulation"], |encoder| {
             let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Render Pass"),
+                label: Some("Accumulate Render Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &texture_view,
+                    view: &self.accumulation_texture.create_view(&wgpu::TextureViewDescriptor::default()),
                     resolve_target: None,
                     ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::WHITE),
+                        load: wgpu::LoadOp::Load,
                         store: wgpu::StoreOp::Store,
                     },
                 })],
@@ -433,11 +2200,285 @@ impl Renderer {
                 timestamp_writes: None,
                 occlusion_query_set: None,
             });
-            rpass.set_pipeline(&self.render_pipeline);
-            rpass.set_bind_group(0, Some(&self.render_bind_group), &[]);
+            rpass.set_pipeline(&self.accumulate_pipeline);
+            rpass.set_bind_group(0, Some(&self.accumulate_bind_group), &[]);
+            rpass.set_blend_constant(wgpu::Color { r: weight, g: weight, b: weight, a: weight });
             rpass.draw(0..4, 0..1);
+        });
+        if let Some(texture_view) = surface {
+            graph.add_pass("composite", &["accumulation"], &["surface"], |encoder| {
+                let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Render Render Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: texture_view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::WHITE),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                    timestamp_writes: None,
+                    occlusion_query_set: None,
+                });
+                rpass.set_pipeline(&self.render_pipeline);
+                rpass.set_bind_group(0, Some(&self.render_bind_group), &[]);
+                rpass.draw(0..4, 0..1);
+            });
+            if self.preview.is_some() {
+                graph.add_pass("preview", &["history"], &["surface"], |encoder| {
+                    let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                        label: Some("Brush Preview Render Pass"),
+                        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                            view: texture_view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Load,
+                                store: wgpu::StoreOp::Store,
+                            },
+                        })],
+                        depth_stencil_attachment: None,
+                        timestamp_writes: None,
+                        occlusion_query_set: None,
+                    });
+                    rpass.set_pipeline(&self.preview_pipeline);
+                    rpass.set_bind_group(0, Some(&self.preview_bind_groups[history_index]), &[]);
+                    rpass.draw(0..4, 0..1);
+                });
+            }
+            if self.overlay.is_visible() {
+                graph.add_pass("overlay", &[], &["surface"], |encoder| {
+                    self.overlay.draw(&self.device, &self.queue, encoder, texture_view, size, pixels_per_point);
+                });
+            }
         }
+        graph.execute(&mut encoder).expect("The render passes should not depend on each other in a loop.");
+
         self.queue.submit(Some(encoder.finish()));
-        surface_texture.present();
+    }
+}
+
+/// Convert the bits of a 16 bit float to a 32 bit one.
+fn half_to_float(bits: u16) -> f32 {
+    let sign = (bits as u32 & 0x8000) << 16;
+    let exponent = (bits >> 10) as u32 & 0x1f;
+    let mantissa = bits as u32 & 0x3ff;
+
+    let magnitude = match exponent {
+        // zero and subnormals
+        0 => mantissa as f32 * 2f32.powi(-24),
+        0x1f if mantissa == 0 => f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => f32::from_bits(((exponent + 112) << 23) | (mantissa << 13)),
+    };
+
+    f32::from_bits(magnitude.to_bits() | sign)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::light::LightKind;
+
+    use std::mem::offset_of;
+
+    /// Get the member offsets and size of a struct declared in a shader.
+    fn shader_layout(source: &str, name: &str) -> (Vec<(String, u32)>, u32) {
+        let module = naga::front::wgsl::parse_str(source).unwrap();
+        let (_, ty) = module.types.iter()
+            .find(|(_, ty)| ty.name.as_deref() == Some(name))
+            .unwrap_or_else(|| panic!("{name} should be declared in the shader"));
+        let naga::TypeInner::Struct { members, span } = &ty.inner else {
+            panic!("{name} should be a struct");
+        };
+
+        (members.iter().map(|member| (member.name.clone().unwrap(), member.offset)).collect(), *span)
+    }
+
+    /// Check that every member of a shader struct lines up with the Rust field of the same name,
+    /// and that the Rust struct fills out the (16 byte aligned) size of the shader struct.
+    macro_rules! assert_layout {
+        ($type:ty, $source:expr, $name:expr, [$($field:ident),* $(,)?]) => {{
+            let (members, span) = shader_layout($source, $name);
+            let fields = [$((stringify!($field), offset_of!($type, $field) as u32)),*];
+
+            assert_eq!(members.len(), fields.len(), "{} has a different number of members", $name);
+            for (member, offset) in &members {
+                assert!(fields.contains(&(member.as_str(), *offset)), "{}.{member} should be at {offset}", $name);
+            }
+            assert_eq!(size_of::<$type>() as u32, span.next_multiple_of(16), "{} has a different size", $name);
+        }};
+    }
+
+    #[test]
+    fn uniform_layouts_match_the_shaders() {
+        let ray_marching = &variants::compose(include_str!("../shaders/ray_marching.wgsl"), ShaderFeatures::ALL);
+        let render = include_str!("../shaders/render.wgsl");
+        let preview = include_str!("../shaders/preview.wgsl");
+        let marching_cubes = include_str!("../shaders/marching_cubes.wgsl");
+
+        assert_layout!(Settings, ray_marching, "Settings", [resolution, display_mode, traversal, sample]);
+        assert_layout!(Settings, render, "Settings", [resolution]);
+        assert_layout!(Display, render, "Display", [tonemap, exposure, encode_srgb, to_linear_srgb]);
+        assert_layout!(View, ray_marching, "View", [
+            view_projection,
+            inverse_view_projection,
+            previous_view_projection,
+            previous_inverse_view_projection,
+            history_valid,
+        ]);
+        assert_layout!(View, preview, "View", [
+            view_projection,
+            inverse_view_projection,
+            previous_view_projection,
+            previous_inverse_view_projection,
+            history_valid,
+        ]);
+        assert_layout!(ChunkSettings, ray_marching, "Chunk", [object_to_world, world_to_object, root_center, root_size, resolution, layer]);
+        assert_layout!(ChunkSettings, marching_cubes, "Chunk", [object_to_world, world_to_object, root_center, root_size, resolution, layer]);
+        assert_layout!(Preview, preview, "Preview", [center, size, shape, color]);
+        assert_layout!(GpuMaterial, ray_marching, "Material", [color, roughness, metallic, subsurface]);
+        assert_layout!(Strip, ray_marching, "Strip", [count, selected, aspect, radius, origin, spacing]);
+        assert_layout!(GpuLight, ray_marching, "Light", [vector, kind, color, intensity]);
+        assert_layout!(Lights, ray_marching, "Lights", [count, lights]);
+    }
+
+    #[test]
+    fn lights_beyond_the_most_are_left_out() {
+        let light = Light::new(LightKind::Point(Vec3::ONE), 2.0);
+        let uniform = Lights::from_lights(&[light; MAX_LIGHTS + 2]);
+        assert_eq!(uniform.count, MAX_LIGHTS as u32);
+        assert_eq!(uniform.lights[MAX_LIGHTS - 1], light.to_buffer());
+
+        let sun = Lights::from_lights(&[Light::new(LightKind::Directional(Vec3::new(0.0, 0.0, -4.0)), 1.0)]);
+        assert_eq!(sun.count, 1);
+        assert_eq!(sun.lights[0].vector, [0.0, 0.0, -1.0]);
+        assert_eq!(sun.lights[0].kind, GpuLight::DIRECTIONAL);
+        assert_eq!(Lights::from_lights(&[]).count, 0);
+    }
+
+    #[test]
+    fn quality_constants_are_overrides_in_the_shader() {
+        let module = naga::front::wgsl::parse_str(&variants::compose(include_str!("../shaders/ray_marching.wgsl"), ShaderFeatures::ALL)).unwrap();
+        let overrides: Vec<&str> = module.overrides.iter()
+            .filter_map(|(_, constant)| constant.name.as_deref())
+            .collect();
+
+        for name in RenderQuality::default().constants().keys() {
+            assert!(overrides.contains(&name.as_str()), "{name} should be an override in the shader");
+        }
+        assert!(include_str!("../shaders/ray_marching.wgsl").contains(&format!("array<TraversalFrame, {MAX_DEPTH}>")));
+        assert!(include_str!("../shaders/ray_marching.wgsl").contains(&format!("const max_lights = {MAX_LIGHTS}u;")));
+    }
+
+    #[test]
+    fn every_shader_variant_validates() {
+        for bits in 0..=ShaderFeatures::ALL.bits() {
+            let features = ShaderFeatures::from_bits(bits);
+            let source = variants::compose(include_str!("../shaders/ray_marching.wgsl"), features);
+            let module = naga::front::wgsl::parse_str(&source).unwrap_or_else(|error| panic!("{features:?}: {}", error.emit_to_string(&source)));
+            naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::empty())
+                .validate(&module)
+                .unwrap_or_else(|error| panic!("{features:?}: {error:?}"));
+        }
+    }
+
+    #[test]
+    fn mesh_extraction_matches_the_shader() {
+        let module = naga::front::wgsl::parse_str(include_str!("../shaders/marching_cubes.wgsl")).unwrap();
+        let constant = |name: &str| {
+            let (_, constant) = module.constants.iter()
+                .find(|(_, constant)| constant.name.as_deref() == Some(name))
+                .unwrap_or_else(|| panic!("{name} should be declared in the shader"));
+            match module.global_expressions[constant.init] {
+                naga::Expression::Literal(naga::Literal::U32(value)) => value,
+                _ => panic!("{name} should be a u32"),
+            }
+        };
+
+        assert_eq!(constant("max_cells"), mesh::MAX_CELLS);
+        assert_eq!(constant("case_size") as usize, mesh::CASE_SIZE);
+        assert_eq!(constant("no_edge"), mesh::NO_EDGE);
+        assert_eq!(constant("vertex_size") as usize * 4, size_of::<mesh::MeshVertex>());
+    }
+
+    #[test]
+    fn visible_points_follow_the_rays_of_their_pixels() {
+        let view = Mat4::orthographic_rh(-1.0, 1.0, -1.0, 1.0, 0.0, 10.0);
+        let hit = [2u32, 0, 5, 1];
+        let strip = [MATERIAL_STRIP_LAYER, 0, 0, 1];
+        let pick: Vec<u32> = [[0; 4], hit, strip, hit].concat();
+        let distances = [0.0f32, 3.0, 0.0, 4.0].map(f32::to_bits);
+
+        let points = VisiblePoint::from_texels(cast_slice(&pick), cast_slice(&distances), 2, view);
+
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].layer, 2);
+        assert!(points[0].position.abs_diff_eq(Vec3::new(0.5, 0.5, -3.0), 1e-5));
+        assert!(points[1].position.abs_diff_eq(Vec3::new(0.5, -0.5, -4.0), 1e-5));
+        assert!(points[1].direction.abs_diff_eq(Vec3::NEG_Z, 1e-5));
+
+        // a single pixel, as read under the cursor, lands on the same point
+        let point = VisiblePoint::from_texel(hit, distances[3], uvec2(1, 1), 2, view.inverse());
+        assert_eq!(point, Some(points[1]));
+        assert_eq!(VisiblePoint::from_texel(strip, 0, uvec2(0, 1), 2, view.inverse()), None);
+    }
+
+    #[test]
+    fn half_floats_are_widened() {
+        assert_eq!(half_to_float(0x0000), 0.0);
+        assert_eq!(half_to_float(0x3c00), 1.0);
+        assert_eq!(half_to_float(0xc000), -2.0);
+        assert_eq!(half_to_float(0x3555), 0.33325195);
+        assert_eq!(half_to_float(0x0001), 2f32.powi(-24));
+        assert_eq!(half_to_float(0x7c00), f32::INFINITY);
+        assert!(half_to_float(0x7e00).is_nan());
+    }
+
+    #[test]
+    fn present_modes_are_named() {
+        for mode in [PresentMode::Fifo, PresentMode::Mailbox, PresentMode::Immediate] {
+            assert_eq!(PresentMode::from_name(mode.name()), Some(mode));
+        }
+        assert_eq!(PresentMode::from_name("VSync"), Some(PresentMode::Fifo));
+        assert_eq!(PresentMode::from_name("tearing"), None);
+    }
+
+    #[test]
+    fn adapters_are_chosen_by_index_or_name() {
+        let adapter = |name: &str, device_type| wgpu::AdapterInfo {
+            name: name.to_string(),
+            vendor: 0,
+            device: 0,
+            device_type,
+            driver: String::new(),
+            driver_info: String::new(),
+            backend: wgpu::Backend::Vulkan,
+        };
+        let adapters = [
+            adapter("Intel(R) UHD Graphics 620", wgpu::DeviceType::IntegratedGpu),
+            adapter("NVIDIA GeForce MX150", wgpu::DeviceType::DiscreteGpu),
+        ];
+
+        assert_eq!(AdapterChoice::parse("1").select(&adapters), Some(1));
+        assert_eq!(AdapterChoice::parse("2").select(&adapters), None);
+        assert_eq!(AdapterChoice::parse("GeForce").select(&adapters), Some(1));
+        assert_eq!(AdapterChoice::parse("intel").select(&adapters), Some(0));
+        assert_eq!(AdapterChoice::parse("radeon").select(&adapters), None);
+        // power preferences leave the pick to wgpu
+        assert_eq!(AdapterChoice::parse("Low-Power"), AdapterChoice::LowPower);
+        assert_eq!(AdapterChoice::parse("high-performance").select(&adapters), None);
+        assert_eq!(AdapterChoice::parse(""), AdapterChoice::Default);
+    }
+
+    #[test]
+    fn surfaces_prefer_more_precision() {
+        use wgpu::TextureFormat::*;
+
+        assert_eq!(Renderer::surface_format(&[Bgra8Unorm, Bgra8UnormSrgb, Rgb10a2Unorm]), Rgb10a2Unorm);
+        assert_eq!(Renderer::surface_format(&[Rgb10a2Unorm, Rgba16Float]), Rgba16Float);
+        assert_eq!(Renderer::surface_format(&[Bgra8Unorm, Bgra8UnormSrgb]), Bgra8UnormSrgb);
+        assert_eq!(Renderer::surface_format(&[Bgra8Unorm]), Bgra8Unorm);
     }
 }
@@ -0,0 +1,157 @@
+use std::fmt;
+
+/// The name of a texture passes read from or write to.
+pub type Slot = &'static str;
+
+/// Records the commands of a pass.
+type Record<'a> = Box<dyn FnOnce(&mut wgpu::CommandEncoder) + 'a>;
+
+/// A pass along with the textures it uses.
+struct GraphPass<'a> {
+    name: &'static str,
+    reads: Vec<Slot>,
+    writes: Vec<Slot>,
+    record: Record<'a>,
+}
+
+/// Why the passes of a graph could not be ordered.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GraphError {
+    /// The passes depend on each other in a loop.
+    Cycle(Vec<&'static str>),
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphError::Cycle(passes) => write!(f, "render passes depend on each other: {}", passes.join(", ")),
+        }
+    }
+}
+
+impl std::error::Error for GraphError {}
+
+/// A list of passes, run in the order of their dependencies.
+///
+/// A pass runs after every pass writing a texture it reads. Passes
+/// writing the same texture (e.g. drawing on top of each other) run
+/// in the order they were added, which also decides reads of textures
+/// the pass writes itself.
+pub struct RenderGraph<'a> {
+    passes: Vec<GraphPass<'a>>,
+}
+
+impl<'a> RenderGraph<'a> {
+    /// Create an empty graph.
+    pub fn new() -> Self {
+        Self {
+            passes: Vec::new(),
+        }
+    }
+
+    /// Add a pass reading and writing some textures.
+    pub fn add_pass(
+        &mut self,
+        name: &'static str,
+        reads: &[Slot],
+        writes: &[Slot],
+        record: impl FnOnce(&mut wgpu::CommandEncoder) + 'a,
+    ) {
+        self.passes.push(GraphPass {
+            name,
+            reads: reads.to_vec(),
+            writes: writes.to_vec(),
+            record: Box::new(record),
+        });
+    }
+
+    /// Check whether a pass depends on another one.
+    fn depends_on(&self, pass: usize, other: usize) -> bool {
+        let (pass, before) = (&self.passes[pass], pass > other);
+        let other = &self.passes[other];
+
+        pass.reads.iter().any(|slot| other.writes.contains(slot) && (before || !pass.writes.contains(slot)))
+            || (before && pass.writes.iter().any(|slot| other.writes.contains(slot)))
+    }
+
+    /// Get the names of the passes in the order they will run.
+    pub fn order(&self) -> Result<Vec<&'static str>, GraphError> {
+        Ok(self.sorted()?.into_iter().map(|index| self.passes[index].name).collect())
+    }
+
+    /// Sort the passes by their dependencies, keeping the order they were added where possible.
+    fn sorted(&self) -> Result<Vec<usize>, GraphError> {
+        let count = self.passes.len();
+        let mut done = vec![false; count];
+        let mut order = Vec::with_capacity(count);
+
+        while order.len() < count {
+            let next = (0..count).find(|&pass| {
+                !done[pass] && (0..count).all(|other| other == pass || done[other] || !self.depends_on(pass, other))
+            });
+            let Some(next) = next else {
+                let stuck = (0..count).filter(|&pass| !done[pass]).map(|pass| self.passes[pass].name).collect();
+                return Err(GraphError::Cycle(stuck));
+            };
+            done[next] = true;
+            order.push(next);
+        }
+
+        Ok(order)
+    }
+
+    /// Record every pass in order.
+    pub fn execute(self, encoder: &mut wgpu::CommandEncoder) -> Result<(), GraphError> {
+        let order = self.sorted()?;
+        let mut passes: Vec<Option<GraphPass>> = self.passes.into_iter().map(Some).collect();
+
+        for index in order {
+            let pass = passes[index].take().unwrap();
+            (pass.record)(encoder);
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for RenderGraph<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passes_run_after_what_they_read() {
+        let mut graph = RenderGraph::new();
+        graph.add_pass("composite", &["color", "occlusion"], &["surface"], |_| {});
+        graph.add_pass("ambient occlusion", &["depth"], &["occlusion"], |_| {});
+        graph.add_pass("ray march", &[], &["color", "depth"], |_| {});
+        graph.add_pass("overlay", &[], &["surface"], |_| {});
+
+        assert_eq!(graph.order(), Ok(vec!["ray march", "ambient occlusion", "composite", "overlay"]));
+    }
+
+    #[test]
+    fn writers_keep_their_order() {
+        let mut graph = RenderGraph::new();
+        graph.add_pass("ray march", &[], &["color", "history"], |_| {});
+        graph.add_pass("composite", &["color"], &["surface"], |_| {});
+        graph.add_pass("preview", &["history"], &["surface"], |_| {});
+        graph.add_pass("overlay", &[], &["surface"], |_| {});
+
+        assert_eq!(graph.order(), Ok(vec!["ray march", "composite", "preview", "overlay"]));
+    }
+
+    #[test]
+    fn cycles_are_reported() {
+        let mut graph = RenderGraph::new();
+        graph.add_pass("a", &["b"], &["a"], |_| {});
+        graph.add_pass("b", &["a"], &["b"], |_| {});
+
+        assert_eq!(graph.order(), Err(GraphError::Cycle(vec!["a", "b"])));
+    }
+}
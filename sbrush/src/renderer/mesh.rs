@@ -0,0 +1,406 @@
+use std::borrow::Cow;
+use std::mem::size_of;
+use std::num::NonZero;
+
+use bytemuck::{Pod, Zeroable, cast_slice};
+
+use super::variants::{ShaderFeatures, compose};
+
+/// The most cells along each side of a chunk meshes are extracted with, coarser than the voxels of finer chunks.
+pub const MAX_CELLS: u32 = 128;
+
+/// How many edges of the table each case of a cube takes, enough for five triangles and an end.
+pub const CASE_SIZE: usize = 16;
+
+/// The edge ending the triangles of a case in the table.
+pub const NO_EDGE: u32 = 12;
+
+/// The most triangles kept for a chunk, which caps the size of its vertex buffer.
+const MAX_TRIANGLES: u64 = 1 << 19;
+
+/// The size of the indirect draw arguments, followed by the count of triangles extracted.
+const DRAW_SIZE: u64 = 5 * size_of::<u32>() as u64;
+
+/// The corners at the ends of each edge of a cube, with corners numbered x | y << 1 | z << 2.
+const EDGES: [[usize; 2]; 12] = [
+    [0, 1], [2, 3], [4, 5], [6, 7],
+    [0, 2], [1, 3], [4, 6], [5, 7],
+    [0, 4], [1, 5], [2, 6], [3, 7],
+];
+
+/// The corners around each face of a cube, counterclockwise seen from outside.
+const FACES: [[usize; 4]; 6] = [
+    [0, 4, 6, 2],
+    [1, 3, 7, 5],
+    [0, 1, 5, 4],
+    [2, 6, 7, 3],
+    [0, 2, 3, 1],
+    [4, 5, 7, 6],
+];
+
+/// A vertex of an extracted mesh, in the space of its object.
+///
+/// The pointer is the voxel buffer entry of a leaf next to the vertex,
+/// which the material, edit heat and paint are looked up with.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct MeshVertex {
+    pub position: [f32; 3],
+    pub normal: [i8; 4],
+    pub pointer: u32,
+}
+
+/// Build the marching cubes table, giving the edges of up to five triangles for each case of solid corners.
+///
+/// Each face of the cube is walked around, connecting where it enters the
+/// solid corners to where it leaves them again, which keeps diagonal solid
+/// corners of a face apart. Since both cubes sharing a face decide alike,
+/// the surface has no cracks. The segments close into loops around the
+/// cube, which are split into fans of triangles facing away from the solid.
+pub fn triangle_table() -> Vec<u32> {
+    let edge = |a: usize, b: usize| EDGES.iter().position(|ends| *ends == [a.min(b), a.max(b)]).unwrap() as u32;
+    let mut table = Vec::with_capacity(256 * CASE_SIZE);
+
+    for case in 0..256usize {
+        let solid = |corner: usize| case & (1 << corner) != 0;
+
+        // where the surface runs across each face, from where it enters the solid to where it leaves
+        let mut next = [NO_EDGE; 12];
+        for face in FACES {
+            let crossings: Vec<(bool, u32)> = (0..4)
+                .map(|index| (face[index], face[(index + 1) % 4]))
+                .filter(|(a, b)| solid(*a) != solid(*b))
+                .map(|(a, b)| (solid(b), edge(a, b)))
+                .collect();
+            for (index, (entering, from)) in crossings.iter().enumerate() {
+                if *entering {
+                    next[*from as usize] = crossings[(index + 1) % crossings.len()].1;
+                }
+            }
+        }
+
+        let mut triangles = Vec::new();
+        let mut visited = [false; 12];
+        for start in 0..12 {
+            if next[start] == NO_EDGE || visited[start] {
+                continue;
+            }
+            let mut around = vec![start as u32];
+            visited[start] = true;
+            let mut current = next[start] as usize;
+            while current != start {
+                around.push(current as u32);
+                visited[current] = true;
+                current = next[current] as usize;
+            }
+            for index in 1..around.len() - 1 {
+                triangles.extend([around[0], around[index], around[index + 1]]);
+            }
+        }
+
+        assert!(triangles.len() < CASE_SIZE, "case {case} should fit in the table");
+        triangles.resize(CASE_SIZE, NO_EDGE);
+        table.extend(triangles);
+    }
+
+    table
+}
+
+/// The triangles extracted from a chunk, drawn instead of ray marching it.
+pub struct ChunkMesh {
+    vertex_buffer: wgpu::Buffer,
+    /// The arguments of the indirect draw, followed by the count of triangles extracted.
+    draw_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    /// Whether the voxels changed since the mesh was extracted.
+    pub outdated: bool,
+}
+
+impl ChunkMesh {
+    /// Get the size of the buffers of the mesh, in bytes.
+    pub fn get_buffer_size(&self) -> u64 {
+        self.vertex_buffer.size() + self.draw_buffer.size()
+    }
+}
+
+/// Extracts meshes from the voxel buffers of chunks with marching cubes in a compute shader, and draws them.
+///
+/// The octree is sampled at the centers of a grid of cells, so chunks
+/// finer than `MAX_CELLS` are meshed more coarsely than they are marched.
+/// The samples reach one cell past each side of the chunk, where it counts
+/// as empty, which closes the mesh at the borders of the chunk.
+pub struct MeshExtraction {
+    bind_group_layout: wgpu::BindGroupLayout,
+    table_buffer: wgpu::Buffer,
+    extract_pipeline: wgpu::ComputePipeline,
+    finish_pipeline: wgpu::ComputePipeline,
+    draw_pipeline: wgpu::RenderPipeline,
+}
+
+impl MeshExtraction {
+    /// Create the pipelines, sharing the bind group layouts of ray marching, and upload the table.
+    ///
+    /// The first layout holds the view and materials, and the second the chunk.
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, ray_marching_layouts: [&wgpu::BindGroupLayout; 2], color_format: wgpu::TextureFormat) -> Self {
+        let table = triangle_table();
+        let table_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Marching Cubes Table Buffer"),
+            size: (table.len() * size_of::<u32>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        queue.write_buffer(&table_buffer, 0, cast_slice(&table));
+
+        let storage = |binding: u32, read_only: bool, min_binding_size: u64| wgpu::BindGroupLayoutEntry {
+            visibility: wgpu::ShaderStages::COMPUTE,
+            binding,
+            count: None,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage {
+                    read_only,
+                },
+                has_dynamic_offset: false,
+                min_binding_size: NonZero::new(min_binding_size),
+            },
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Mesh Extraction Bind Group Layout"),
+            entries: &[
+                storage(0, true, 0),
+                storage(1, false, 0),
+                storage(2, false, DRAW_SIZE),
+            ],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Marching Cubes Shader Module"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("../../shaders/marching_cubes.wgsl"))),
+        });
+
+        let compute_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Mesh Extraction Pipeline Layout"),
+            bind_group_layouts: &[
+                &bind_group_layout,
+                ray_marching_layouts[1],
+            ],
+            ..Default::default()
+        });
+        let compute_pipeline = |label: &str, entry_point: &str| device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some(label),
+            layout: Some(&compute_layout),
+            module: &shader,
+            entry_point: Some(entry_point),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        let extract_pipeline = compute_pipeline("Mesh Extraction Pipeline", "extract_main");
+        let finish_pipeline = compute_pipeline("Mesh Extraction Finish Pipeline", "finish_main");
+
+        let draw_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Mesh Shader Module"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Owned(compose(include_str!("../../shaders/ray_marching.wgsl"), ShaderFeatures::ALL))),
+        });
+
+        let draw_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Mesh Pipeline Layout"),
+            bind_group_layouts: &ray_marching_layouts,
+            ..Default::default()
+        });
+
+        // meshes draw to the same targets as ray marching, so everything reading them works alike
+        let draw_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Mesh Render Pipeline"),
+            layout: Some(&draw_layout),
+            vertex: wgpu::VertexState {
+                module: &draw_shader,
+                entry_point: Some("mesh_vertex_main"),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: size_of::<MeshVertex>() as u64,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Snorm8x4, 2 => Uint32],
+                }],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &draw_shader,
+                entry_point: Some("mesh_fragment_main"),
+                compilation_options: Default::default(),
+                targets: &[
+                    Some(color_format.into()),
+                    Some(wgpu::TextureFormat::R32Uint.into()),
+                    Some(wgpu::TextureFormat::Rgba32Uint.into()),
+                ],
+            }),
+            // the walls closing the meshes at the borders of chunks face into the sculpt, so nothing is culled
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            bind_group_layout,
+            table_buffer,
+            extract_pipeline,
+            finish_pipeline,
+            draw_pipeline,
+        }
+    }
+
+    /// Create the buffers of a chunk's mesh, with room for more triangles the more voxel buffer entries it has.
+    pub fn create_mesh(&self, device: &wgpu::Device, entries: u64) -> ChunkMesh {
+        let triangles = entries.clamp(4096, MAX_TRIANGLES);
+        let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Chunk Mesh Vertex Buffer"),
+            size: triangles * 3 * size_of::<MeshVertex>() as u64,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        let draw_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Chunk Mesh Draw Buffer"),
+            size: DRAW_SIZE,
+            usage: wgpu::BufferUsages::INDIRECT | wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Mesh Extraction Bind Group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.table_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: vertex_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: draw_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        ChunkMesh {
+            vertex_buffer,
+            draw_buffer,
+            bind_group,
+            outdated: true,
+        }
+    }
+
+    /// Queue the extraction of a mesh from the voxels of a chunk, bound by one of its placements.
+    ///
+    /// The triangle count is cleared by the queue, so the extraction has to
+    /// be submitted after it.
+    pub fn extract(&self, queue: &wgpu::Queue, encoder: &mut wgpu::CommandEncoder, mesh: &ChunkMesh, chunk: &wgpu::BindGroup, resolution: u32) {
+        queue.write_buffer(&mesh.draw_buffer, 0, &[0; DRAW_SIZE as usize]);
+
+        // one cube between each pair of samples, which reach one past each side
+        let workgroups = (resolution.min(MAX_CELLS) + 1).div_ceil(4);
+        let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Mesh Extraction Compute Pass"),
+            timestamp_writes: None,
+        });
+        cpass.set_bind_group(0, Some(&mesh.bind_group), &[]);
+        cpass.set_bind_group(1, Some(chunk), &[]);
+        cpass.set_pipeline(&self.extract_pipeline);
+        cpass.dispatch_workgroups(workgroups, workgroups, workgroups);
+        cpass.set_pipeline(&self.finish_pipeline);
+        cpass.dispatch_workgroups(1, 1, 1);
+    }
+
+    /// Start drawing meshes in a pass, which is left with the pipeline set.
+    pub fn begin_draw(&self, rpass: &mut wgpu::RenderPass<'_>) {
+        rpass.set_pipeline(&self.draw_pipeline);
+    }
+
+    /// Draw a mesh at a placement of its chunk, after `begin_draw`.
+    pub fn draw(rpass: &mut wgpu::RenderPass<'_>, mesh: &ChunkMesh, placement: &wgpu::BindGroup) {
+        rpass.set_bind_group(1, Some(placement), &[]);
+        rpass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+        rpass.draw_indirect(&mesh.draw_buffer, 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use glam::{IVec3, Vec3};
+
+    /// Get the middle of an edge of the unit cube.
+    fn edge_middle(edge: u32) -> Vec3 {
+        let corner = |corner: usize| IVec3::new(corner as i32 & 1, (corner as i32 >> 1) & 1, corner as i32 >> 2).as_vec3();
+        let [a, b] = EDGES[edge as usize];
+
+        (corner(a) + corner(b)) / 2.0
+    }
+
+    #[test]
+    fn triangles_face_away_from_the_solid_corners() {
+        let table = triangle_table();
+        assert_eq!(table.len(), 256 * CASE_SIZE);
+        assert!(table[..CASE_SIZE].iter().chain(&table[255 * CASE_SIZE..]).all(|edge| *edge == NO_EDGE));
+
+        for case in 1..255 {
+            let triangles: Vec<&[u32]> = table[case * CASE_SIZE..(case + 1) * CASE_SIZE]
+                .chunks(3)
+                .take_while(|triangle| triangle[0] != NO_EDGE)
+                .collect();
+            assert!(!triangles.is_empty(), "case {case} should have triangles");
+
+            // every crossed edge is used, and the sides of triangles not shared with another run along the faces
+            // of the cube, once from and once to each crossed edge, so the surface closes with the neighbours
+            let mut sides = Vec::new();
+            for triangle in &triangles {
+                for index in 0..3 {
+                    sides.push((triangle[index], triangle[(index + 1) % 3]));
+                }
+            }
+            let outer: Vec<(u32, u32)> = sides.iter().copied().filter(|(from, to)| !sides.contains(&(*to, *from))).collect();
+            let on_face = |edge: u32, face: &[usize; 4]| EDGES[edge as usize].iter().all(|corner| face.contains(corner));
+            assert!(outer.iter().all(|(from, to)| FACES.iter().any(|face| on_face(*from, face) && on_face(*to, face))), "case {case}");
+
+            let crossed: Vec<u32> = (0..12)
+                .filter(|edge| {
+                    let [a, b] = EDGES[*edge as usize];
+                    ((case >> a) & 1) != ((case >> b) & 1)
+                })
+                .collect();
+            let mut starts: Vec<u32> = outer.iter().map(|side| side.0).collect();
+            let mut ends: Vec<u32> = outer.iter().map(|side| side.1).collect();
+            starts.sort();
+            ends.sort();
+            assert_eq!(starts, crossed, "case {case}");
+            assert_eq!(ends, crossed, "case {case}");
+        }
+
+        // a lone solid corner is cut off by one triangle facing away from it
+        let triangle: Vec<Vec3> = table[CASE_SIZE..CASE_SIZE + 3].iter().map(|edge| edge_middle(*edge)).collect();
+        assert_eq!(table[CASE_SIZE + 3], NO_EDGE);
+        let normal = (triangle[1] - triangle[0]).cross(triangle[2] - triangle[0]);
+        assert!(normal.dot(Vec3::ONE) > 0.0);
+
+        // half of the cube solid is split by a flat square
+        let bottom = 0b1111;
+        let square: Vec<Vec3> = table[bottom * CASE_SIZE..bottom * CASE_SIZE + 6].iter().map(|edge| edge_middle(*edge)).collect();
+        assert!(square.iter().all(|point| point.z == 0.5));
+        for triangle in square.chunks(3) {
+            assert!((triangle[1] - triangle[0]).cross(triangle[2] - triangle[0]).z > 0.0);
+        }
+    }
+}
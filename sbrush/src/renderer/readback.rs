@@ -0,0 +1,246 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// The bytes read back from the GPU, or why they couldn't be.
+pub type ReadbackResult = Result<Vec<u8>, wgpu::BufferAsyncError>;
+
+/// Reusable staging buffers for reading data back from the GPU.
+///
+/// Each read copies into a staging buffer, maps it, and hands the bytes
+/// to a callback once the device has been polled. The staging buffer
+/// then goes back into the pool for the next read.
+#[derive(Clone, Default)]
+pub struct ReadbackPool {
+    free: Arc<Mutex<Vec<wgpu::Buffer>>>,
+}
+
+impl ReadbackPool {
+    /// Create an empty pool.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the number of idle staging buffers.
+    pub fn idle_count(&self) -> usize {
+        self.free.lock().unwrap().len()
+    }
+
+    /// Take the smallest idle staging buffer which fits, or create one.
+    fn staging(&self, device: &wgpu::Device, size: u64) -> wgpu::Buffer {
+        let mut free = self.free.lock().unwrap();
+        let best = free.iter()
+            .enumerate()
+            .filter(|(_, buffer)| buffer.size() >= size)
+            .min_by_key(|(_, buffer)| buffer.size())
+            .map(|(index, _)| index);
+
+        if let Some(index) = best {
+            return free.swap_remove(index);
+        }
+
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Readback Staging Buffer"),
+            size: size.next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false
+        })
+    }
+
+    /// Read part of a buffer, calling back with the bytes.
+    pub fn read_buffer(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        source: &wgpu::Buffer,
+        offset: u64,
+        size: u64,
+        callback: impl FnOnce(ReadbackResult) + Send + 'static,
+    ) {
+        let staging = self.staging(device, size);
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.copy_buffer_to_buffer(source, offset, &staging, 0, size);
+        queue.submit(Some(encoder.finish()));
+
+        self.map(staging, size, None, callback);
+    }
+
+    /// Read a region of a texture, calling back with the tightly packed texels.
+    pub fn read_texture(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture: &wgpu::Texture,
+        origin: wgpu::Origin3d,
+        extent: wgpu::Extent3d,
+        callback: impl FnOnce(ReadbackResult) + Send + 'static,
+    ) {
+        let texel_size = texture.format().block_copy_size(None).expect("Texture formats should be readable.");
+        let row_size = extent.width * texel_size;
+        // rows of a copy have to be aligned, so the padding is stripped after mapping
+        let padded_row_size = row_size.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let size = padded_row_size as u64 * extent.height as u64 * extent.depth_or_array_layers as u64;
+        let staging = self.staging(device, size);
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &staging,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_size),
+                    rows_per_image: Some(extent.height),
+                },
+            },
+            extent,
+        );
+        queue.submit(Some(encoder.finish()));
+
+        self.map(staging, size, Some((row_size as usize, padded_row_size as usize)), callback);
+    }
+
+    /// Read regions of several textures, calling back with the texels of each once all are read.
+    pub fn read_textures(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        regions: &[(&wgpu::Texture, wgpu::Origin3d, wgpu::Extent3d)],
+        callback: impl FnOnce(Vec<ReadbackResult>) + Send + 'static,
+    ) {
+        let join = Arc::new(Mutex::new(ReadbackJoin {
+            results: regions.iter().map(|_| None).collect(),
+            callback: Some(Box::new(callback)),
+        }));
+
+        for (index, (texture, origin, extent)) in regions.iter().enumerate() {
+            let join = Arc::clone(&join);
+            self.read_texture(device, queue, texture, *origin, *extent, move |result| {
+                let mut join = join.lock().unwrap();
+                join.results[index] = Some(result);
+                if join.results.iter().all(Option::is_some) {
+                    let results = join.results.iter_mut().map(|result| result.take().unwrap()).collect();
+                    if let Some(callback) = join.callback.take() {
+                        callback(results);
+                    }
+                }
+            });
+        }
+    }
+
+    /// Read part of a buffer as a future.
+    pub fn read_buffer_async(&self, device: &wgpu::Device, queue: &wgpu::Queue, source: &wgpu::Buffer, offset: u64, size: u64) -> Readback {
+        let readback = Readback::new(device);
+        self.read_buffer(device, queue, source, offset, size, readback.callback());
+
+        readback
+    }
+
+    /// Read a region of a texture as a future.
+    pub fn read_texture_async(&self, device: &wgpu::Device, queue: &wgpu::Queue, texture: &wgpu::Texture, origin: wgpu::Origin3d, extent: wgpu::Extent3d) -> Readback {
+        let readback = Readback::new(device);
+        self.read_texture(device, queue, texture, origin, extent, readback.callback());
+
+        readback
+    }
+
+    /// Map a staging buffer, returning it to the pool once the bytes are copied out.
+    fn map(&self, staging: wgpu::Buffer, size: u64, rows: Option<(usize, usize)>, callback: impl FnOnce(ReadbackResult) + Send + 'static) {
+        let free = Arc::clone(&self.free);
+        let mapped = staging.clone();
+
+        staging.slice(..size).map_async(wgpu::MapMode::Read, move |result| {
+            let bytes = result.map(|()| {
+                let view = mapped.slice(..size).get_mapped_range();
+                let bytes = match rows {
+                    Some((row_size, padded_row_size)) => view.chunks(padded_row_size)
+                        .flat_map(|row| &row[..row_size])
+                        .copied()
+                        .collect(),
+                    None => view.to_vec(),
+                };
+                drop(view);
+                mapped.unmap();
+
+                bytes
+            });
+            free.lock().unwrap().push(mapped);
+
+            callback(bytes);
+        });
+    }
+}
+
+/// The reads of several regions, until the last one finishes.
+struct ReadbackJoin {
+    results: Vec<Option<ReadbackResult>>,
+    callback: Option<Box<dyn FnOnce(Vec<ReadbackResult>) + Send>>,
+}
+
+/// The shared state of a pending read.
+#[derive(Default)]
+struct ReadbackState {
+    result: Option<ReadbackResult>,
+    waker: Option<Waker>,
+}
+
+/// A read from the GPU which finishes as the device is polled.
+pub struct Readback {
+    device: wgpu::Device,
+    state: Arc<Mutex<ReadbackState>>,
+}
+
+impl Readback {
+    /// Create an unfinished read.
+    fn new(device: &wgpu::Device) -> Self {
+        Self {
+            device: device.clone(),
+            state: Arc::default(),
+        }
+    }
+
+    /// Get a callback which finishes the read.
+    fn callback(&self) -> impl FnOnce(ReadbackResult) + Send + 'static {
+        let state = Arc::clone(&self.state);
+
+        move |result| {
+            let mut state = state.lock().unwrap();
+            state.result = Some(result);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    /// Block until the read finishes.
+    pub fn wait(self) -> ReadbackResult {
+        self.device.poll(wgpu::Maintain::Wait);
+
+        pollster::block_on(self)
+    }
+}
+
+impl Future for Readback {
+    type Output = ReadbackResult;
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Self::Output> {
+        // give the device a chance to run the map callback
+        self.device.poll(wgpu::Maintain::Poll);
+
+        let mut state = self.state.lock().unwrap();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(context.waker().clone());
+                Poll::Pending
+            },
+        }
+    }
+}
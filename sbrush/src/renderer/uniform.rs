@@ -0,0 +1,121 @@
+use std::mem::size_of;
+
+use bytemuck::{Pod, bytes_of};
+
+/// A CPU-side value which remembers whether it changed since it was last taken.
+struct Staged<T: Pod> {
+    value: T,
+    dirty: bool,
+}
+
+impl<T: Pod> Staged<T> {
+    /// Stage a value, which counts as changed until it is first taken.
+    fn new(value: T) -> Self {
+        Self {
+            value,
+            dirty: true,
+        }
+    }
+
+    /// Replace the value, only marking it changed if its bytes differ.
+    fn set(&mut self, value: T) {
+        if bytes_of(&value) != bytes_of(&self.value) {
+            self.value = value;
+            self.dirty = true;
+        }
+    }
+
+    /// Get the value if it changed, marking it as unchanged.
+    fn take_changed(&mut self) -> Option<&T> {
+        if !self.dirty {
+            return None;
+        }
+        self.dirty = false;
+
+        Some(&self.value)
+    }
+}
+
+/// A uniform buffer along with the value it should hold.
+///
+/// Changes are staged on the CPU and only uploaded by `flush`,
+/// and only when the value actually changed.
+pub struct UniformBlock<T: Pod> {
+    buffer: wgpu::Buffer,
+    staged: Staged<T>,
+}
+
+impl<T: Pod> UniformBlock<T> {
+    /// Create the buffer, uploading the initial value with the first flush.
+    pub fn new(device: &wgpu::Device, label: &str, value: T) -> Self {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: size_of::<T>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+
+        Self {
+            buffer,
+            staged: Staged::new(value),
+        }
+    }
+
+    /// Get the staged value.
+    pub fn get(&self) -> &T {
+        &self.staged.value
+    }
+
+    /// Stage a new value.
+    pub fn set(&mut self, value: T) {
+        self.staged.set(value);
+    }
+
+    /// Stage a change to part of the value.
+    pub fn update(&mut self, change: impl FnOnce(&mut T)) {
+        let mut value = self.staged.value;
+        change(&mut value);
+        self.staged.set(value);
+    }
+
+    /// Check whether the staged value still needs uploading.
+    pub fn is_dirty(&self) -> bool {
+        self.staged.dirty
+    }
+
+    /// Queue an upload of the staged value if it changed, returning whether it did.
+    pub fn flush(&mut self, queue: &wgpu::Queue) -> bool {
+        let Some(value) = self.staged.take_changed() else {
+            return false;
+        };
+        queue.write_buffer(&self.buffer, 0, bytes_of(value));
+
+        true
+    }
+
+    /// Get the whole buffer as a binding.
+    pub fn binding(&self) -> wgpu::BindingResource<'_> {
+        self.buffer.as_entire_binding()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_changed_values_are_taken() {
+        let mut staged = Staged::new([1u32, 2]);
+
+        assert_eq!(staged.take_changed(), Some(&[1, 2]));
+        assert_eq!(staged.take_changed(), None);
+
+        staged.set([1, 2]);
+        assert_eq!(staged.take_changed(), None);
+
+        staged.set([1, 3]);
+        staged.set([1, 4]);
+        assert_eq!(staged.take_changed(), Some(&[1, 4]));
+        assert_eq!(staged.take_changed(), None);
+    }
+}
@@ -0,0 +1,187 @@
+//! Composes shaders from feature defines, and caches a pipeline per variant.
+//!
+//! Code only some variants need sits between `#ifdef NAME`, an optional
+//! `#else` and `#endif` lines, which `compose` resolves before wgpu sees
+//! the source. The uniforms still pick the mode at runtime, so the variant
+//! with every feature draws every mode. It is compiled up front and drawn
+//! while leaner variants compile on the job pool, so switching never stalls
+//! a frame.
+
+use std::collections::HashMap;
+use std::ops::BitOr;
+use std::sync::Arc;
+
+use crate::util::jobs::{Job, JobPool, Priority};
+
+/// The features compiled into a shader variant.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub struct ShaderFeatures(u32);
+
+impl ShaderFeatures {
+    pub const NONE: ShaderFeatures = ShaderFeatures(0);
+    /// Coloring voxels by their edit heat.
+    pub const HEAT: ShaderFeatures = ShaderFeatures(1);
+    /// Darkening masked voxels.
+    pub const MASK: ShaderFeatures = ShaderFeatures(2);
+    /// The stepwise descent traversal, kept for comparison.
+    pub const DESCENT: ShaderFeatures = ShaderFeatures(4);
+    pub const ALL: ShaderFeatures = ShaderFeatures(7);
+
+    /// The name each feature is defined by in the shaders.
+    const DEFINES: [(ShaderFeatures, &'static str); 3] = [
+        (ShaderFeatures::HEAT, "HEAT"),
+        (ShaderFeatures::MASK, "MASK"),
+        (ShaderFeatures::DESCENT, "DESCENT"),
+    ];
+
+    /// Get the features from their bits, dropping unknown ones.
+    pub fn from_bits(bits: u32) -> ShaderFeatures {
+        ShaderFeatures(bits & ShaderFeatures::ALL.0)
+    }
+
+    /// Get the bits of the features.
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Check whether all features of another set are in this one.
+    pub fn contains(self, other: ShaderFeatures) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Check whether a define is set, which unknown names aren't.
+    fn is_defined(self, name: &str) -> bool {
+        ShaderFeatures::DEFINES.iter().any(|(feature, define)| *define == name && self.contains(*feature))
+    }
+}
+
+impl BitOr for ShaderFeatures {
+    type Output = ShaderFeatures;
+
+    fn bitor(self, other: ShaderFeatures) -> ShaderFeatures {
+        ShaderFeatures(self.0 | other.0)
+    }
+}
+
+/// Resolve the defines of a shader for a set of features.
+///
+/// Directives and left out lines become blank, so errors still point at the right line.
+pub fn compose(source: &str, features: ShaderFeatures) -> String {
+    // whether lines are kept, for each open #ifdef
+    let mut kept: Vec<bool> = Vec::new();
+    let mut composed = String::with_capacity(source.len());
+    for line in source.lines() {
+        let keeping = kept.last().copied().unwrap_or(true);
+        let directive = line.trim();
+        if let Some(name) = directive.strip_prefix("#ifdef ") {
+            kept.push(keeping && features.is_defined(name.trim()));
+        } else if directive == "#else" {
+            let outer = kept.len() < 2 || kept[kept.len() - 2];
+            if let Some(branch) = kept.last_mut() {
+                *branch = outer && !*branch;
+            }
+        } else if directive == "#endif" {
+            kept.pop();
+        } else if keeping {
+            composed.push_str(line);
+        }
+        composed.push('\n');
+    }
+
+    composed
+}
+
+/// Creates the pipeline of a variant.
+type Build = dyn Fn(&wgpu::Device, ShaderFeatures) -> wgpu::RenderPipeline + Send + Sync;
+
+/// The pipelines of the variants of a shader, each compiled once.
+pub struct PipelineVariants {
+    device: wgpu::Device,
+    build: Arc<Build>,
+    jobs: JobPool,
+    /// The variant with every feature, drawn while others compile.
+    complete: wgpu::RenderPipeline,
+    compiled: HashMap<ShaderFeatures, wgpu::RenderPipeline>,
+    compiling: HashMap<ShaderFeatures, Job<wgpu::RenderPipeline>>,
+}
+
+impl PipelineVariants {
+    /// Compile the variant with every feature right away, leaving the others for when they are asked for.
+    pub fn new(device: &wgpu::Device, jobs: JobPool, build: impl Fn(&wgpu::Device, ShaderFeatures) -> wgpu::RenderPipeline + Send + Sync + 'static) -> Self {
+        let complete = build(device, ShaderFeatures::ALL);
+
+        Self {
+            device: device.clone(),
+            build: Arc::new(build),
+            jobs,
+            complete,
+            compiled: HashMap::new(),
+            compiling: HashMap::new(),
+        }
+    }
+
+    /// Get the pipeline of a variant, starting to compile it if it is new.
+    ///
+    /// Until it is compiled, the variant with every feature is returned instead.
+    pub fn get(&mut self, features: ShaderFeatures) -> &wgpu::RenderPipeline {
+        self.collect();
+        if features != ShaderFeatures::ALL && !self.compiled.contains_key(&features) && !self.compiling.contains_key(&features) {
+            let (device, build) = (self.device.clone(), Arc::clone(&self.build));
+            let job = self.jobs.spawn(Priority::Normal, String::new(), move |_| build(&device, features));
+            self.compiling.insert(features, job);
+        }
+
+        self.compiled.get(&features).unwrap_or(&self.complete)
+    }
+
+    /// Get the variant with every feature, whose bind group layouts all variants share.
+    pub fn complete(&self) -> &wgpu::RenderPipeline {
+        &self.complete
+    }
+
+    /// Check whether any variants are still compiling.
+    pub fn is_compiling(&self) -> bool {
+        !self.compiling.is_empty()
+    }
+
+    /// Pick up the variants which finished compiling.
+    fn collect(&mut self) {
+        let complete = &self.complete;
+        let compiled = &mut self.compiled;
+        self.compiling.retain(|features, job| {
+            if !job.is_done() {
+                return true;
+            }
+            // a variant which failed to compile keeps drawing with every feature, instead of compiling again
+            compiled.insert(*features, job.try_take().unwrap_or_else(|| complete.clone()));
+
+            false
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "a\n#ifdef HEAT\nb\n#ifdef MASK\nc\n#else\nd\n#endif\n#else\ne\n#endif\nf";
+
+    fn kept(features: ShaderFeatures) -> Vec<String> {
+        compose(SOURCE, features).split_whitespace().map(str::to_owned).collect()
+    }
+
+    #[test]
+    fn defines_pick_the_branches() {
+        assert_eq!(kept(ShaderFeatures::NONE), ["a", "e", "f"]);
+        assert_eq!(kept(ShaderFeatures::HEAT), ["a", "b", "d", "f"]);
+        assert_eq!(kept(ShaderFeatures::HEAT | ShaderFeatures::MASK), ["a", "b", "c", "f"]);
+        // the inner branches stay out along with the outer one
+        assert_eq!(kept(ShaderFeatures::MASK), ["a", "e", "f"]);
+    }
+
+    #[test]
+    fn composing_keeps_the_line_numbers() {
+        assert_eq!(compose(SOURCE, ShaderFeatures::ALL).lines().count(), SOURCE.lines().count());
+        assert_eq!(compose(SOURCE, ShaderFeatures::NONE).lines().nth(9), Some("e"));
+    }
+}
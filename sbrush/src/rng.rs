@@ -0,0 +1,79 @@
+//! A small, deterministic random number generator.
+//!
+//! Shared by sampling in the path tracer and stochastic brushes,
+//! where the same seed has to give the same results.
+
+use glam::{Vec3, vec3};
+
+/// A small, deterministic random number generator (xorshift32).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rng {
+	state: u32,
+}
+
+impl Rng {
+	/// Create a generator from a seed.
+	pub fn new(seed: u32) -> Self {
+		Self {
+			state: seed.max(1),
+		}
+	}
+
+	/// Get the next random integer.
+	pub fn next_u32(&mut self) -> u32 {
+		self.state ^= self.state << 13;
+		self.state ^= self.state >> 17;
+		self.state ^= self.state << 5;
+		self.state
+	}
+
+	/// Get a random number between 0 and 1.
+	pub fn next_f32(&mut self) -> f32 {
+		(self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+	}
+
+	/// Get a random direction on the unit sphere.
+	pub fn next_unit_vector(&mut self) -> Vec3 {
+		loop {
+			let point = self.next_in_unit_sphere();
+			let length_squared = point.length_squared();
+			// points right at the center have no direction to speak of
+			if length_squared > 1e-6 {
+				return point / length_squared.sqrt();
+			}
+		}
+	}
+
+	/// Get a random point inside of the unit sphere, spread evenly through its volume.
+	///
+	/// Points are drawn from the cube around the sphere until one lands
+	/// inside. Unlike sin, cos or cbrt, which differ between the math
+	/// libraries of platforms, this only rounds in ways IEEE 754 pins down,
+	/// so stochastic brushes sculpt the same everywhere.
+	pub fn next_in_unit_sphere(&mut self) -> Vec3 {
+		loop {
+			let point = vec3(self.next_f32(), self.next_f32(), self.next_f32()) * 2.0 - 1.0;
+			if point.length_squared() <= 1.0 {
+				return point;
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    #[test]
+    fn same_seeds_give_the_same_numbers() {
+    	let mut a = Rng::new(42);
+    	let mut b = Rng::new(42);
+
+    	for _ in 0..16 {
+    		let point = a.next_in_unit_sphere();
+    		assert_eq!(point, b.next_in_unit_sphere());
+    		assert!(point.length() <= 1.0 + 1e-6);
+    	}
+    	assert_ne!(Rng::new(1).next_u32(), Rng::new(2).next_u32());
+    }
+}
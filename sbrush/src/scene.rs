@@ -0,0 +1,484 @@
+//! Several sculpt objects placed together in one scene.
+//!
+//! Each object is a document of its own, with its own resolution,
+//! placed in the world by a transform. Editing happens on the active
+//! object, in its own space, while rendering and ray casts take every
+//! object into account and keep the nearest hit.
+//!
+//! An object can also be instanced at more transforms, which show the
+//! same voxels again without copying them, and follow every edit.
+//!
+//! Objects can be hidden, or locked against strokes, and solo mode
+//! shows only the active object.
+//!
+//! Placing objects and instances can snap to a grid, to rotation
+//! increments, and onto the surface of the other objects.
+
+use crate::document::Document;
+use crate::sculpt::SculptHit;
+use crate::util::watch::FileWatch;
+
+use glam::{EulerRot, Mat4, Quat, Vec3};
+
+/// The placement of an object in the world.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+	pub translation: Vec3,
+	pub rotation: Quat,
+	/// A uniform scale, so voxels stay cubes.
+	pub scale: f32,
+}
+
+impl Default for Transform {
+	/// The identity, leaving the object where it was sculpted.
+	fn default() -> Self {
+		Self {
+			translation: Vec3::ZERO,
+			rotation: Quat::IDENTITY,
+			scale: 1.0,
+		}
+	}
+}
+
+impl Transform {
+	/// Get the matrix from object space to world space.
+	pub fn to_matrix(self) -> Mat4 {
+		Mat4::from_scale_rotation_translation(Vec3::splat(self.scale), self.rotation, self.translation)
+	}
+
+	/// Map a point in the world into object space.
+	pub fn to_local(self, point: Vec3) -> Vec3 {
+		self.rotation.inverse() * (point - self.translation) / self.scale
+	}
+
+	/// Map a direction in the world into object space, keeping its length relative to the scale.
+	pub fn to_local_vector(self, vector: Vec3) -> Vec3 {
+		self.rotation.inverse() * vector / self.scale
+	}
+
+	/// Map a point in object space into the world.
+	pub fn to_world(self, point: Vec3) -> Vec3 {
+		self.rotation * (point * self.scale) + self.translation
+	}
+
+	/// Map a direction in object space into the world.
+	pub fn to_world_vector(self, vector: Vec3) -> Vec3 {
+		self.rotation * (vector * self.scale)
+	}
+}
+
+/// How placements snap as objects are moved, turned and instanced.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Snapping {
+	/// The spacing of the grid positions snap to, if any.
+	pub grid: Option<f32>,
+	/// The increment angles around each axis snap to, in radians, if any.
+	pub angle: Option<f32>,
+	/// Whether positions drop onto the surface of another object below them.
+	pub surface: bool,
+}
+
+/// Which objects of the scene an export writes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum ExportFilter {
+	/// Every shown object.
+	#[default]
+	Scene,
+	/// The active object, even while it is hidden.
+	Active,
+	/// The objects with the given names, in the order they were named.
+	Named(Vec<String>),
+}
+
+/// Round a value to the closest multiple of a step.
+fn round_to(value: f32, step: f32) -> f32 {
+	(value / step).round() * step
+}
+
+/// A named sculpt placed in the scene.
+/// The file an object was opened from or saved to.
+#[derive(Clone, Debug)]
+pub struct ObjectFile {
+	pub watch: FileWatch,
+	/// The revision of the document which matches the file.
+	pub revision: u64,
+	/// The revision of the document last autosaved as a backup of the file.
+	pub autosaved: u64,
+}
+
+#[derive(Clone)]
+pub struct SceneObject {
+	pub name: String,
+	pub document: Document,
+	pub transform: Transform,
+	/// More places showing the same voxels.
+	pub instances: Vec<Transform>,
+	/// Whether the object is drawn and hit by rays.
+	pub visible: bool,
+	/// Whether strokes leave the object alone.
+	pub locked: bool,
+	/// The file of the object, watched for changes by other programs.
+	pub file: Option<ObjectFile>,
+}
+
+impl SceneObject {
+	/// Create an object at the origin.
+	pub fn new(name: impl Into<String>, document: Document) -> Self {
+		Self {
+			name: name.into(),
+			document,
+			transform: Transform::default(),
+			instances: Vec::new(),
+			visible: true,
+			locked: false,
+			file: None,
+		}
+	}
+
+	/// Iterate over every place the object is shown, starting with its own transform.
+	pub fn placements(&self) -> impl Iterator<Item = Transform> + '_ {
+		std::iter::once(self.transform).chain(self.instances.iter().copied())
+	}
+
+	/// Find the closest voxel along a ray in the world, as a hit in the world.
+	///
+	/// Instances are hit like the object itself.
+	pub fn raycast(&self, origin: Vec3, direction: Vec3) -> Option<SculptHit> {
+		self.placements()
+			.filter_map(|transform| {
+				let local_origin = transform.to_local(origin);
+				let local_direction = transform.to_local_vector(direction);
+				// the distance along the ray is the same in both spaces, as the direction is mapped along
+				let hit = self.document.raycast(local_origin, local_direction)?;
+
+				Some(SculptHit {
+					position: transform.to_world(hit.position),
+					normal: (transform.rotation * hit.normal).normalize_or_zero(),
+					..hit
+				})
+			})
+			.min_by(|a, b| a.distance.total_cmp(&b.distance))
+	}
+}
+
+/// The objects of a scene, one of which is edited.
+#[derive(Clone)]
+pub struct Scene {
+	objects: Vec<SceneObject>,
+	active: usize,
+	/// Whether only the active object is shown.
+	solo: bool,
+}
+
+impl Scene {
+	/// Create a scene holding a single, active object.
+	pub fn new(object: SceneObject) -> Self {
+		Self {
+			objects: vec![object],
+			active: 0,
+			solo: false,
+		}
+	}
+
+	/// Get every object, in the order they were added.
+	pub fn get_objects(&self) -> &[SceneObject] {
+		&self.objects
+	}
+
+	/// Get an object by its index.
+	pub fn get_object(&self, index: usize) -> Option<&SceneObject> {
+		self.objects.get(index)
+	}
+
+	/// Get an object by its index, to change it.
+	pub fn get_object_mut(&mut self, index: usize) -> Option<&mut SceneObject> {
+		self.objects.get_mut(index)
+	}
+
+	/// Find the index of an object by its name.
+	pub fn find(&self, name: &str) -> Option<usize> {
+		self.objects.iter().position(|object| object.name == name)
+	}
+
+	/// Get the index of the object being edited.
+	pub fn get_active_index(&self) -> usize {
+		self.active
+	}
+
+	/// Get the object being edited.
+	pub fn get_active(&self) -> &SceneObject {
+		&self.objects[self.active]
+	}
+
+	/// Get the object being edited, to change it.
+	pub fn get_active_mut(&mut self) -> &mut SceneObject {
+		&mut self.objects[self.active]
+	}
+
+	/// Edit another object, returning false if there is none at the index.
+	pub fn set_active(&mut self, index: usize) -> bool {
+		if index >= self.objects.len() {
+			return false;
+		}
+		self.active = index;
+
+		true
+	}
+
+	/// Show only the active object, or every visible one again.
+	pub fn set_solo(&mut self, solo: bool) {
+		self.solo = solo;
+	}
+
+	/// Check whether only the active object is shown.
+	pub fn is_solo(&self) -> bool {
+		self.solo
+	}
+
+	/// Check whether an object is drawn and hit by rays, by its own visibility and solo mode.
+	pub fn is_shown(&self, index: usize) -> bool {
+		match self.objects.get(index) {
+			Some(_) if self.solo => index == self.active,
+			Some(object) => object.visible,
+			None => false,
+		}
+	}
+
+	/// Make a name no other object has, by numbering it if needed.
+	pub fn unique_name(&self, name: &str) -> String {
+		if self.find(name).is_none() {
+			return name.to_owned();
+		}
+
+		(2..)
+			.map(|number| format!("{name} {number}"))
+			.find(|numbered| self.find(numbered).is_none())
+			.unwrap()
+	}
+
+	/// Add an object, renamed if its name is taken, returning its index.
+	pub fn add(&mut self, mut object: SceneObject) -> usize {
+		object.name = self.unique_name(&object.name);
+		self.objects.push(object);
+
+		self.objects.len() - 1
+	}
+
+	/// Copy an object, returning the index of the copy.
+	pub fn duplicate(&mut self, index: usize) -> Option<usize> {
+		let mut copy = self.objects.get(index)?.clone();
+		// the copy is new, so it has no file yet
+		copy.file = None;
+
+		Some(self.add(copy))
+	}
+
+	/// Remove an object, returning it.
+	///
+	/// The last object can't be removed, so there is always one to edit.
+	/// The active object stays active, unless it is the one removed.
+	pub fn remove(&mut self, index: usize) -> Option<SceneObject> {
+		if self.objects.len() <= 1 || index >= self.objects.len() {
+			return None;
+		}
+		let object = self.objects.remove(index);
+		if self.active > index || self.active == self.objects.len() {
+			self.active -= 1;
+		}
+
+		Some(object)
+	}
+
+	/// Find the closest voxel along a ray in the world across every shown object, along with the object's index.
+	pub fn raycast(&self, origin: Vec3, direction: Vec3) -> Option<(usize, SculptHit)> {
+		self.raycast_except(origin, direction, None)
+	}
+
+	/// Find the closest voxel along a ray in the world across every shown object but one.
+	fn raycast_except(&self, origin: Vec3, direction: Vec3, skipped: Option<usize>) -> Option<(usize, SculptHit)> {
+		self.objects.iter()
+			.enumerate()
+			.filter(|(index, _)| self.is_shown(*index) && Some(*index) != skipped)
+			.filter_map(|(index, object)| object.raycast(origin, direction).map(|hit| (index, hit)))
+			.min_by(|a, b| a.1.distance.total_cmp(&b.1.distance))
+	}
+
+	/// Get the indices of the objects an export writes, so every file format exports the same ones.
+	///
+	/// Fails with the first name no object has.
+	pub fn select(&self, filter: &ExportFilter) -> Result<Vec<usize>, String> {
+		match filter {
+			ExportFilter::Scene => Ok((0..self.objects.len()).filter(|index| self.is_shown(*index)).collect()),
+			ExportFilter::Active => Ok(vec![self.active]),
+			ExportFilter::Named(names) => names.iter()
+				.map(|name| self.find(name).ok_or_else(|| name.clone()))
+				.collect(),
+		}
+	}
+
+	/// Snap the placement of an object, or one of its instances.
+	///
+	/// Positions snap to the grid first, so dropping them onto the surface
+	/// of another object leaves them exactly on it. An object never lands on
+	/// itself or its own instances.
+	pub fn snap(&self, index: usize, transform: Transform, snapping: &Snapping) -> Transform {
+		let mut snapped = transform;
+		if let Some(grid) = snapping.grid.filter(|grid| *grid > 0.0) {
+			snapped.translation = transform.translation.map(|value| round_to(value, grid));
+		}
+		if let Some(angle) = snapping.angle.filter(|angle| *angle > 0.0) {
+			let (x, y, z) = transform.rotation.to_euler(EulerRot::XYZ);
+			snapped.rotation = Quat::from_euler(EulerRot::XYZ, round_to(x, angle), round_to(y, angle), round_to(z, angle));
+		}
+		if snapping.surface {
+			if let Some((_, hit)) = self.raycast_except(snapped.translation, Vec3::NEG_Z, Some(index)) {
+				snapped.translation.z = hit.position.z;
+			}
+		}
+
+		snapped
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    use crate::brush::{Brush, RoundBrushTip};
+
+    use glam::vec3;
+
+    /// A scene with a ball in one object, and a moved copy of it in another.
+    fn two_balls() -> Scene {
+    	let mut document = Document::new(32, 1.0);
+    	let mut brush = Brush::new("round".to_owned(), Box::new(RoundBrushTip::new()));
+    	brush.set_size(0.25);
+    	brush.add(&mut document, vec3(0.5, 0.5, 0.5));
+
+    	let mut scene = Scene::new(SceneObject::new("ball", document));
+    	let copy = scene.duplicate(0).unwrap();
+    	scene.get_object_mut(copy).unwrap().transform = Transform {
+    		translation: vec3(0.0, 0.0, -2.0),
+    		rotation: Quat::from_rotation_y(1.0),
+    		scale: 0.5,
+    	};
+
+    	scene
+    }
+
+    #[test]
+    fn transforms_map_both_ways() {
+    	let transform = Transform {
+    		translation: vec3(1.0, 2.0, 3.0),
+    		rotation: Quat::from_rotation_z(0.5),
+    		scale: 2.0,
+    	};
+    	let point = vec3(0.25, -0.5, 4.0);
+
+    	assert!(transform.to_world(transform.to_local(point)).abs_diff_eq(point, 1e-5));
+    	assert!(transform.to_matrix().transform_point3(point).abs_diff_eq(transform.to_world(point), 1e-5));
+    	assert!(transform.to_world_vector(transform.to_local_vector(Vec3::X)).abs_diff_eq(Vec3::X, 1e-5));
+    }
+
+    #[test]
+    fn rays_hit_the_nearest_object() {
+    	let scene = two_balls();
+    	assert_eq!(scene.get_objects()[1].name, "ball 2");
+
+    	// the copy is in front, scaled down around the origin of its space
+    	let center = scene.get_objects()[1].transform.to_world(vec3(0.5, 0.5, 0.5));
+    	let (index, hit) = scene.raycast(vec3(center.x, center.y, -5.0), Vec3::Z).unwrap();
+    	assert_eq!(index, 1);
+    	assert!((hit.position.z - (center.z - 0.125)).abs() < 0.05);
+    	// the faces of the rotated voxels are rotated too, but still face the ray
+    	assert!(hit.normal.dot(Vec3::Z) < 0.0 && hit.normal.z.abs() < 0.99);
+
+    	let (index, hit) = scene.raycast(vec3(0.5, 0.5, -1.0), Vec3::Z).unwrap();
+    	assert_eq!(index, 0);
+    	assert!((hit.position.z - 0.25).abs() < 0.05);
+    }
+
+    #[test]
+    fn instances_share_the_voxels() {
+    	let mut scene = two_balls();
+    	let instance = vec3(3.0, 0.0, 0.0);
+    	scene.get_object_mut(0).unwrap().instances.push(Transform {
+    		translation: instance,
+    		..Transform::default()
+    	});
+
+    	let (index, hit) = scene.raycast(instance + vec3(0.5, 0.5, -1.0), Vec3::Z).unwrap();
+    	assert_eq!(index, 0);
+    	assert!((hit.position.z - 0.25).abs() < 0.05);
+
+    	// carving the object carves its instance too
+    	let mut brush = Brush::new("round".to_owned(), Box::new(RoundBrushTip::new()));
+    	brush.set_size(0.6);
+    	brush.remove(&mut scene.get_object_mut(0).unwrap().document, vec3(0.5, 0.5, 0.5));
+    	assert!(scene.raycast(instance + vec3(0.5, 0.5, -1.0), Vec3::Z).is_none());
+    }
+
+    #[test]
+    fn hidden_objects_are_not_hit() {
+    	let mut scene = two_balls();
+    	let center = scene.get_objects()[1].transform.to_world(vec3(0.5, 0.5, 0.5));
+    	let origin = vec3(center.x, center.y, -5.0);
+
+    	scene.get_object_mut(1).unwrap().visible = false;
+    	assert!(scene.raycast(origin, Vec3::Z).is_none_or(|(index, _)| index != 1));
+
+    	// solo shows the active object, even while it is hidden
+    	scene.set_active(1);
+    	scene.set_solo(true);
+    	assert!(scene.is_shown(1) && !scene.is_shown(0));
+    	assert_eq!(scene.raycast(origin, Vec3::Z).unwrap().0, 1);
+    	assert!(scene.raycast(vec3(0.5, 0.5, -1.0), Vec3::Z).is_none());
+    }
+
+    #[test]
+    fn placements_snap_to_the_grid_and_surfaces() {
+    	let scene = two_balls();
+    	let transform = Transform {
+    		translation: vec3(0.52, 0.47, 2.0),
+    		rotation: Quat::from_rotation_z(0.3),
+    		scale: 1.0,
+    	};
+    	let snapping = Snapping {
+    		grid: Some(0.25),
+    		angle: Some(15f32.to_radians()),
+    		surface: true,
+    	};
+
+    	// the copy lands on the top of the ball in the other object
+    	let snapped = scene.snap(1, transform, &snapping);
+    	assert_eq!((snapped.translation.x, snapped.translation.y), (0.5, 0.5));
+    	assert!((snapped.translation.z - 0.75).abs() < 0.05);
+    	assert!(snapped.rotation.abs_diff_eq(Quat::from_rotation_z(15f32.to_radians()), 1e-5));
+
+    	// nothing is below the ball for it to land on
+    	assert_eq!(scene.snap(0, transform, &snapping).translation, vec3(0.5, 0.5, 2.0));
+    }
+
+    #[test]
+    fn exports_select_objects() {
+    	let mut scene = two_balls();
+    	scene.set_active(1);
+    	scene.get_object_mut(1).unwrap().visible = false;
+
+    	assert_eq!(scene.select(&ExportFilter::Scene), Ok(vec![0]));
+    	assert_eq!(scene.select(&ExportFilter::Active), Ok(vec![1]));
+    	let named = ExportFilter::Named(vec!["ball 2".to_owned(), "ball".to_owned()]);
+    	assert_eq!(scene.select(&named), Ok(vec![1, 0]));
+    	assert_eq!(scene.select(&ExportFilter::Named(vec!["cube".to_owned()])), Err("cube".to_owned()));
+    }
+
+    #[test]
+    fn removing_objects_keeps_the_active_one() {
+    	let mut scene = two_balls();
+    	scene.set_active(1);
+    	assert!(scene.remove(0).is_some());
+    	assert_eq!(scene.get_active().name, "ball 2");
+    	assert!(scene.remove(0).is_none());
+    	assert!(!scene.set_active(1));
+    }
+}
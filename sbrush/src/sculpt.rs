@@ -1,24 +1,60 @@
-use crate::material::Material;
+use crate::aabb::Aabb;
+use crate::material::{self, GpuMaterial, Material, MaterialMix};
+use crate::noise;
 
-use glam::{Vec3, vec3};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::rc::Rc;
+use std::thread;
+
+use glam::{IVec3, Vec3, vec3};
+
+mod blob;
+mod location;
+pub mod pool;
+mod stream;
+pub use location::{Frame, LocationalCode};
+
+/// The mask value above which voxels are kept by removal strokes.
+pub const MASK_PROTECTS: f32 = 0.5;
+
+/// The functions telling which nodes a shape fills, and which it contains whole.
+type ShapeFunctions = (Box<dyn Fn(f32, Vec3) -> bool>, Box<dyn Fn(f32, Vec3) -> bool>);
 
 /// The 3D sculpt.
 ///
 /// A sparse voxel octree with associated material
-/// information.
+/// information. The root node is a cube in world space,
+/// which is the unit cube by default.
+#[derive(Clone)]
 pub struct Sculpt {
 	root: SculptNode,
+	frame: Frame,
 	resolution: u32,
 	palette: SculptPalette,
+	/// The depth edits refine down to, if coarser than the finest voxels.
+	depth_limit: Option<u32>,
 }
 
 impl Sculpt {
 	/// Creates a new sculpt object.
 	pub fn new(resolution: u32) -> Self {
+		Self::new_with_bounds(resolution, Aabb::default())
+	}
+
+	/// Creates a new sculpt object covering a box in world space.
+	///
+	/// The octree is a cube, so it is fit around the box
+	/// using the longest edge.
+	pub fn new_with_bounds(resolution: u32, bounds: Aabb) -> Self {
+		let frame = Frame::new(bounds, resolution);
+
 		Self {
-			root: SculptNode::new(SculptNodeKind::None, 0, 1.0, vec3(0.5, 0.5, 0.5)),
+			root: SculptNode::new(SculptNodeKind::None, 0, LocationalCode::ROOT),
+			frame,
 			palette: SculptPalette::new(),
 			resolution,
+			depth_limit: None,
 		}
 	}
 
@@ -27,9 +63,75 @@ impl Sculpt {
 		self.resolution
 	}
 
+	/// Check whether the sculpt has no voxels.
+	pub fn is_empty(&self) -> bool {
+		self.root.kind != SculptNodeKind::Leaf && !self.root.children.iter().any(|child| child.is_some())
+	}
+
+	/// Get the cube covered by the root of the octree.
+	pub fn get_bounds(&self) -> Aabb {
+		self.root.bounds(&self.frame)
+	}
+
+	/// Get the edge length of the root of the octree.
+	pub fn get_size(&self) -> f32 {
+		self.root.size(&self.frame)
+	}
+
+	/// Map a point in the unit cube to the root of the octree.
+	pub fn unit_to_world(&self, point: Vec3) -> Vec3 {
+		self.get_bounds().min + point * self.get_size()
+	}
+
+	/// Get the edge length of the finest voxels.
+	pub fn get_voxel_size(&self) -> f32 {
+		self.min_leaf_size()
+	}
+
 	/// Get the minimum voxel leaf node size.
 	fn min_leaf_size(&self) -> f32 {
-		1.0 / (self.resolution as f32)
+		self.get_size() / (self.resolution as f32)
+	}
+
+	/// Limit the depth edits refine down to, so they make fewer but coarser leaves, or refine to single voxels again.
+	///
+	/// Depth 0 is the root.
+	pub fn set_depth_limit(&mut self, depth: Option<u32>) {
+		self.depth_limit = depth;
+	}
+
+	/// Get the depth edits refine down to, if it is limited.
+	pub fn get_depth_limit(&self) -> Option<u32> {
+		self.depth_limit
+	}
+
+	/// Get the size below which edits leave nodes whole, which is 0 unless the depth is limited.
+	fn coarsest_leaf_size(&self) -> f32 {
+		match self.depth_limit {
+			Some(depth) if depth < self.frame.get_depth() => self.get_size() / (1u64 << depth) as f32,
+			_ => 0.0,
+		}
+	}
+
+	/// Get how many nodes the octree has, counting interior nodes twice like the voxel buffer does.
+	pub fn get_node_count(&self) -> usize {
+		self.root.child_count as usize + 1
+	}
+
+	/// Count the nodes at each depth of the octree, with the root at 0.
+	pub fn get_depth_counts(&self) -> Vec<usize> {
+		let mut counts = Vec::new();
+		self.root.count_depths(&mut counts);
+
+		counts
+	}
+
+	/// Get the volume filled by each material, by its index in the palette.
+	pub fn get_material_volumes(&self) -> BTreeMap<u32, f32> {
+		let mut volumes = BTreeMap::new();
+		self.root.add_volumes(&mut volumes, &self.frame);
+
+		volumes
 	}
 
 	/// Gets the raw data for the voxel buffer.
@@ -37,26 +139,957 @@ impl Sculpt {
 		self.root.to_buffer()
 	}
 
+	/// Gets a per-voxel attribute channel laid out like the voxel buffer.
+	///
+	/// Each leaf entry holds the value of the leaf, so the shader
+	/// can read it with the same pointer as the leaf material.
+	/// Interior entries hold zeros.
+	pub fn get_channel_buffer(&self, channel: Channel) -> Vec<f32> {
+		self.root.to_channel_buffer(&|node| channel.value(node))
+	}
+
+	/// Record an edit of every leaf overlapping a box.
+	pub fn mark_edited(&mut self, bounds: Aabb, stroke: u32, decay: f32) {
+		self.root.mark_edited(&bounds, stroke, decay, &self.frame);
+	}
+
+	/// Get the centers and sizes of every leaf, in the order masks are set.
+	pub fn get_leaves(&self) -> Vec<(Vec3, f32)> {
+		let mut leaves = Vec::new();
+		self.root.visit_leaves(&mut |leaf| leaves.push((leaf.center(&self.frame), leaf.size(&self.frame))));
+
+		leaves
+	}
+
+	/// Set the mask of every leaf, in the order of `get_leaves`.
+	pub fn set_leaf_masks(&mut self, masks: &[f32]) {
+		let mut masks = masks.iter();
+		self.root.visit_leaves_mut(&mut |leaf| leaf.mask = masks.next().copied().unwrap_or(0.0));
+	}
+
+	/// Get the mask of the leaf containing a point, which is 0 outside of the sculpt.
+	pub fn get_mask(&self, point: Vec3) -> f32 {
+		self.leaf_at(point).map_or(0.0, |leaf| leaf.mask)
+	}
+
+	/// Unmask every voxel.
+	pub fn clear_mask(&mut self) {
+		self.root.visit_leaves_mut(&mut |leaf| leaf.mask = 0.0);
+	}
+
+	/// Paint the voxels selected by a classification with a packed color, leaving their shape alone.
+	///
+	/// Only solid and mixed coverage are painted, and mixed leaves are
+	/// split down to the finest voxels. Returns the paint of every leaf
+	/// that changed, from before it was painted.
+	pub fn paint(&mut self, classify: &dyn Fn(f32, Vec3) -> Coverage, paint: u32) -> Vec<PaintPatch> {
+		let mut changed = Vec::new();
+		self.root.paint(paint, classify, &self.frame, &mut changed);
+		self.root.set_child_count();
+
+		changed
+	}
+
+	/// Put back the paint recorded by `paint`, returning the paint it replaced.
+	///
+	/// The patches are put back in reverse, so overlapping ones end up as
+	/// they were first. Patches still apply after the shape changed.
+	pub fn restore_paint(&mut self, patches: &[PaintPatch]) -> Vec<PaintPatch> {
+		let voxel_size = self.min_leaf_size();
+		let mut changed = Vec::new();
+		for patch in patches.iter().rev() {
+			let (patch_low, patch_high) = cell_box(Vec3::ZERO, voxel_size, patch.size, patch.center);
+			self.root.paint(patch.paint, &|size, center| {
+				let (low, high) = cell_box(Vec3::ZERO, voxel_size, size, center);
+				if low.cmpge(patch_low).all() && high.cmple(patch_high).all() {
+					Coverage::Solid
+				} else if low.cmplt(patch_high).all() && high.cmpgt(patch_low).all() {
+					Coverage::Mixed
+				} else {
+					Coverage::Unchanged
+				}
+			}, &self.frame, &mut changed);
+		}
+		self.root.set_child_count();
+
+		changed
+	}
+
+	/// Set the material of every voxel from its center and current material, leaving the shape alone.
+	///
+	/// Leaves whose corner voxels get different materials are split,
+	/// down to single voxels, and leaves which end up alike are merged.
+	pub fn fill_materials(&mut self, material: &dyn Fn(Vec3, u32) -> u32) {
+		self.root.fill_materials(material, &self.frame);
+		self.root.set_child_count();
+	}
+
+	/// Get the box around every solid voxel, if there are any.
+	pub fn get_solid_bounds(&self) -> Option<Aabb> {
+		self.get_leaves()
+			.into_iter()
+			.map(|(center, size)| Aabb::cube(center, size))
+			.reduce(|bounds, leaf| Aabb::new(bounds.min.min(leaf.min), bounds.max.max(leaf.max)))
+	}
+
+	/// Find the leaf containing a point, following the code of the finest voxel there down from the root.
+	fn leaf_at(&self, point: Vec3) -> Option<&SculptNode> {
+		let code = self.frame.code_at(point)?;
+		let mut node = &self.root;
+		for depth in 0..code.get_depth() {
+			if node.is_leaf() {
+				break;
+			}
+			node = node.children[code.get_index_at(depth)].as_deref()?;
+		}
+
+		node.is_leaf().then_some(node)
+	}
+
+	/// Get the material of the leaf containing a point, if it is inside of the sculpt.
+	pub fn get_leaf_material(&self, point: Vec3) -> Option<u32> {
+		self.leaf_at(point).map(|leaf| leaf.material)
+	}
+
+	/// Get the packed paint of the leaf containing a point, which is 0 where nothing is painted.
+	pub fn get_paint(&self, point: Vec3) -> u32 {
+		self.leaf_at(point).map_or(0, |leaf| leaf.paint)
+	}
+
+	/// Gets the packed paint of every leaf, laid out like the voxel buffer.
+	pub fn get_paint_buffer(&self) -> Vec<u32> {
+		self.root.to_channel_buffer(&|node| node.paint)
+	}
+
 	/// Gets the raw data for the material palette buffer.
-	pub fn get_material_buffer(&self) -> Vec<f32> {
+	pub fn get_material_buffer(&self) -> Vec<GpuMaterial> {
 		self.palette.to_buffer()
 	}
 
 	/// Subdivides space to fill the sculpt.
 	pub fn subdivide(&mut self, is_filled: Box<dyn Fn(f32, Vec3) -> bool>, is_contained: Box<dyn Fn(f32, Vec3) -> bool>) {
-		self.root.subdivide(0, &is_filled, &is_contained, self.min_leaf_size(), false);
+		self.subdivide_with(&MaterialMix::single(0), is_filled, is_contained);
+	}
+
+	/// Subdivides space to fill the sculpt with a mix of materials.
+	///
+	/// A mix is dithered per leaf. The new leaves within a voxel of the
+	/// edge of the filled shape are split down to single voxels, so the
+	/// surface shows the mix finely grained, while larger leaves inside of
+	/// it take the material picked for their center.
+	pub fn subdivide_with(&mut self, material: &MaterialMix, is_filled: Box<dyn Fn(f32, Vec3) -> bool>, is_contained: Box<dyn Fn(f32, Vec3) -> bool>) {
+		let voxel_size = self.min_leaf_size();
+		let fill = |center: Vec3| material.pick((center / voxel_size).floor().as_ivec3());
+		let (is_filled, is_contained) = self.limit_depth(is_filled, is_contained);
+		if material.is_mixed() {
+			let is_contained = |size: f32, center: Vec3| is_contained(size, center) && is_contained(size + 2.0 * voxel_size, center);
+			self.root.subdivide(&fill, &*is_filled, &is_contained, &self.frame, false);
+		} else {
+			self.root.subdivide(&fill, &*is_filled, &*is_contained, &self.frame, false);
+		}
 		self.root.set_child_count();
 	}
 
 	/// Remove voxels from the sculpt.
+	///
+	/// The nodes the removal reached are compacted afterwards, so carving
+	/// doesn't leave structure behind which a fresh sculpt wouldn't have.
 	pub fn unsubdivide(&mut self, is_filled: Box<dyn Fn(f32, Vec3) -> bool>, is_contained: Box<dyn Fn(f32, Vec3) -> bool>) {
-		self.root.unsubdivide(0, &is_filled, &is_contained, self.min_leaf_size());
+		let (is_filled, is_contained) = self.limit_depth(is_filled, is_contained);
+		self.root.unsubdivide(&|_| 0, &*is_filled, &*is_contained, &self.frame);
+		self.root.compact(&*is_filled, &self.frame);
 		self.root.set_child_count();
 	}
+
+	/// Make the shape functions of an edit leave nodes at the depth limit whole, by whether their center is in the shape.
+	fn limit_depth(&self, is_filled: Box<dyn Fn(f32, Vec3) -> bool>, is_contained: Box<dyn Fn(f32, Vec3) -> bool>) -> ShapeFunctions {
+		let coarsest = self.coarsest_leaf_size();
+		if coarsest == 0.0 {
+			return (is_filled, is_contained);
+		}
+		let is_filled: Rc<dyn Fn(f32, Vec3) -> bool> = Rc::from(is_filled);
+		let covers = Rc::clone(&is_filled);
+
+		(
+			Box::new(move |size, center| is_filled(size, center) && (size > coarsest || is_filled(0.0, center))),
+			Box::new(move |size, center| is_contained(size, center) || (size <= coarsest && covers(0.0, center))),
+		)
+	}
+
+	/// Collapse every subtree below a depth into a single node, for a blocky look.
+	///
+	/// Nodes at the depth become solid (with the material filling most of them)
+	/// when their voxels fill at least half of them, and empty otherwise.
+	/// Depth 0 is the root.
+	pub fn quantize(&mut self, depth: u32) {
+		self.root.quantize(depth, &self.frame);
+		self.root.set_child_count();
+	}
+
+	/// Check the octree for inconsistencies, like ones left by a damaged file.
+	pub fn validate(&self) -> Vec<Issue> {
+		let mut issues = Vec::new();
+		self.root.validate(self.palette.len(), true, &mut issues, &self.frame);
+
+		issues
+	}
+
+	/// Fix the inconsistencies `validate` finds, returning them.
+	///
+	/// Kinds follow the children, empty nodes are dropped and
+	/// missing materials are replaced by the first one.
+	pub fn repair(&mut self) -> Vec<Issue> {
+		let issues = self.validate();
+		if !issues.is_empty() {
+			self.root.repair(self.palette.len());
+			self.root.set_child_count();
+		}
+
+		issues
+	}
+
+	/// Get the finest voxel cell containing a point.
+	pub fn cell_at(&self, point: Vec3) -> IVec3 {
+		((point - self.get_bounds().min) / self.min_leaf_size()).floor().as_ivec3()
+	}
+
+	/// Check whether a point is inside of a solid voxel.
+	pub fn is_solid(&self, point: Vec3) -> bool {
+		let cell = self.cell_at(point);
+
+		self.get_voxel_grid(cell, cell + 1).get(cell)
+	}
+
+	/// Estimate the mean curvature of the surface at a point on it, from the voxels within a radius.
+	///
+	/// A ball around a point on a flat surface is half solid, and the
+	/// share of it which is solid drops as the surface curves away from
+	/// it, by `3 / 16` of the radius times the curvature. So the curvature
+	/// is positive where the surface is convex, like `1 / r` on a sphere
+	/// of radius `r`, and negative where it is concave. Larger radii
+	/// smooth out the voxel steps, but blur the smaller features.
+	pub fn curvature_at(&self, point: Vec3, radius: f32) -> f32 {
+		let voxel_size = self.min_leaf_size();
+		let origin = self.get_bounds().min;
+		let (low, high) = (self.cell_at(point - radius), self.cell_at(point + radius) + 1);
+		let grid = self.get_voxel_grid(low, high);
+
+		let (mut inside, mut solid) = (0, 0);
+		for z in low.z..high.z {
+			for y in low.y..high.y {
+				for x in low.x..high.x {
+					let cell = IVec3::new(x, y, z);
+					if (origin + (cell.as_vec3() + 0.5) * voxel_size).distance_squared(point) < radius * radius {
+						inside += 1;
+						solid += grid.get(cell) as u32;
+					}
+				}
+			}
+		}
+		if inside == 0 {
+			return 0.0;
+		}
+
+		(0.5 - solid as f32 / inside as f32) * 16.0 / (3.0 * radius)
+	}
+
+	/// Sample the occupancy of the finest voxel cells in a box.
+	///
+	/// The low cell is inclusive, and the high cell is exclusive.
+	pub fn get_voxel_grid(&self, low: IVec3, high: IVec3) -> VoxelGrid {
+		let mut grid = VoxelGrid::new(low, high);
+		self.sample_voxels(&mut grid, self.get_bounds().min);
+
+		grid
+	}
+
+	/// Mark the solid cells of a grid whose cells are counted from another origin.
+	pub fn sample_voxels(&self, grid: &mut VoxelGrid, origin: Vec3) {
+		self.root.sample(grid, origin, self.min_leaf_size(), &self.frame);
+	}
+
+	/// Change the sculpt by classifying nodes from the top down.
+	pub fn apply(&mut self, classify: &dyn Fn(f32, Vec3) -> Coverage) {
+		self.apply_with(&|_| 0, classify);
+	}
+
+	/// Change the sculpt by classifying nodes from the top down, filling new solid nodes with a material by their center.
+	pub fn apply_with(&mut self, fill: &dyn Fn(Vec3) -> u32, classify: &dyn Fn(f32, Vec3) -> Coverage) {
+		self.root.apply(fill, classify, &self.frame);
+		self.root.set_child_count();
+	}
+
+	/// Warp the voxels by smooth noise, moving the surface by up to an amplitude.
+	///
+	/// Each point takes the voxel found at the point offset by vector
+	/// noise, with a frequency of features per unit, so the same seed
+	/// always warps the same way. Only nodes near the warped surface are
+	/// split, and paint and masks of the warped voxels are dropped.
+	pub fn displace(&mut self, amplitude: f32, frequency: f32, seed: u32) {
+		let source = self.clone();
+		let warp = |point: Vec3| point + noise::vector_noise(point * frequency, seed) * amplitude;
+		let voxel_size = self.min_leaf_size();
+		// points of a node are warped at most this much further apart than they are
+		let spread = 2.0 * 3f32.sqrt() * amplitude;
+
+		let classify = |size: f32, center: Vec3| {
+			let warped = warp(center);
+			if size > voxel_size * 1.5 {
+				let reach = size * 3f32.sqrt() / 2.0 + spread;
+				if source.closest_surface_point(warped).is_some_and(|(_, distance)| distance.abs() <= reach) {
+					return Coverage::Mixed;
+				}
+			}
+
+			if source.is_solid(warped) { Coverage::Solid } else { Coverage::Empty }
+		};
+		self.root.apply(&|center| source.get_leaf_material(warp(center)).unwrap_or(0), &classify, &self.frame);
+		self.root.set_child_count();
+	}
+
+	/// Smooth out stair-stepping of the voxels inside of a box.
+	///
+	/// Each iteration sets every voxel to the majority of its
+	/// 3x3x3 neighborhood.
+	pub fn relax(&mut self, low: Vec3, high: Vec3, iterations: u32) {
+		let low = self.cell_at(low).max(IVec3::ZERO);
+		let high = (self.cell_at(high) + 1).min(IVec3::splat(self.resolution as i32));
+		if iterations == 0 || high.cmple(low).any() {
+			return;
+		}
+
+		// sample a margin so the border voxels see their neighbors
+		let original = self.get_voxel_grid(low - 1, high + 1);
+		let mut current = original.clone();
+
+		for _ in 0..iterations {
+			let mut next = current.clone();
+			for z in low.z..high.z {
+				for y in low.y..high.y {
+					for x in low.x..high.x {
+						let cell = IVec3::new(x, y, z);
+						let count = current.solid_neighbors(cell) + current.get(cell) as u32;
+						next.set(cell, count >= 14);
+					}
+				}
+			}
+			current = next;
+		}
+
+		let changed = CellCounts::new(low, high, |cell| current.get(cell) != original.get(cell));
+		let solid = CellCounts::new(low, high, |cell| current.get(cell));
+		let origin = self.get_bounds().min;
+		let voxel_size = self.min_leaf_size();
+
+		self.apply(&|size, center| {
+			let (node_low, node_high) = cell_box(origin, voxel_size, size, center);
+
+			if changed.count(node_low, node_high) == 0 {
+				return Coverage::Unchanged;
+			}
+			if node_low.cmplt(low).any() || node_high.cmpgt(high).any() {
+				return Coverage::Mixed;
+			}
+
+			let volume = (node_high - node_low).element_product() as u32;
+			match solid.count(node_low, node_high) {
+				0 => Coverage::Empty,
+				count if count == volume => Coverage::Solid,
+				_ => Coverage::Mixed,
+			}
+		});
+	}
+
+	/// Wear down the convex edges of the sculpt.
+	///
+	/// The surroundings are the occupancy of the cells to wear, counted
+	/// from an origin, with a margin of the wear scale around them so the
+	/// cells at the edges see their neighbors. The share of solid cells
+	/// around each surface cell tells how convex it is, and the convex
+	/// cells are dithered into being worn by the intensity.
+	pub fn wear(&mut self, surroundings: &VoxelGrid, origin: Vec3, wear: &Wear) {
+		let voxel_size = self.min_leaf_size();
+		let radius = wear.scale.max(1) as i32;
+		let (low, high) = (surroundings.get_low() + radius, surroundings.get_high() - radius);
+		if high.cmple(low).any() {
+			return;
+		}
+
+		let solid = CellCounts::new(low - radius, high + radius, |cell| surroundings.get(cell));
+		// the share of solid cells around a cell on a flat surface, and how much less of it a straight edge has
+		let flat = (radius + 1) as f32 / (2 * radius + 1) as f32;
+		let edge = 1.0 - flat;
+		let neighborhood = ((2 * radius + 1) as u32).pow(3) as f32;
+		let worn = CellCounts::new(low, high, |cell| {
+			if !surroundings.is_surface(cell) {
+				return false;
+			}
+			let share = solid.count(cell - radius, cell + radius + 1) as f32 / neighborhood;
+			let convexity = (flat - share) / flat / edge;
+
+			convexity * wear.intensity > material::dither_threshold(cell)
+		});
+		if worn.count(low, high) == 0 {
+			return;
+		}
+
+		match wear.effect {
+			WearEffect::Material(material) => self.fill_materials(&|center, current| {
+				let cell = ((center - origin) / voxel_size).floor().as_ivec3();
+				if worn.count(cell, cell + 1) > 0 { material } else { current }
+			}),
+			WearEffect::Erode => self.apply(&|size, center| {
+				let (node_low, node_high) = cell_box(origin, voxel_size, size, center);
+				let volume = (node_high - node_low).element_product() as u32;
+				match worn.count(node_low, node_high) {
+					0 => Coverage::Unchanged,
+					count if count == volume => Coverage::Empty,
+					_ => Coverage::Mixed,
+				}
+			}),
+		}
+	}
+
+	/// Get a material from the palette.
+	pub fn get_material(&self, index: u32) -> Option<&Material> {
+		self.palette.get(index)
+	}
+
+	/// Replace the palette with a material of each color.
+	pub fn set_palette(&mut self, colors: &[[f32; 4]]) {
+		self.palette.set_colors(colors);
+	}
+
+	/// Set how much light scatters under the surface of each material, by its index.
+	///
+	/// Materials beyond the strengths scatter none.
+	pub fn set_subsurface(&mut self, strengths: &[f32]) {
+		self.palette.set_subsurface(strengths);
+	}
+
+	/// Find the closest leaf voxel along a ray.
+	///
+	/// The direction does not need to be normalized, but
+	/// the hit distance is measured in multiples of it.
+	pub fn raycast(&self, origin: Vec3, direction: Vec3) -> Option<SculptHit> {
+		self.root.raycast(origin, direction, direction.recip(), &self.frame)
+	}
+
+	/// Find the closest leaf voxel along each of many rays, spread across threads.
+	///
+	/// The hits are in the order of the rays. Small batches are cast
+	/// on the calling thread, where starting threads would cost more.
+	pub fn raycast_batch(&self, rays: &[Ray]) -> Vec<Option<SculptHit>> {
+		let threads = thread::available_parallelism().map_or(1, |count| count.get());
+		let rays_per_thread = rays.len().div_ceil(threads).max(RAYS_PER_THREAD);
+		let mut hits = vec![None; rays.len()];
+		let cast = |rays: &[Ray], hits: &mut [Option<SculptHit>]| {
+			for (ray, hit) in rays.iter().zip(hits) {
+				*hit = self.raycast(ray.origin, ray.direction);
+			}
+		};
+
+		let mut shares = rays.chunks(rays_per_thread).zip(hits.chunks_mut(rays_per_thread));
+		let first = shares.next();
+		thread::scope(|scope| {
+			for (rays, hits) in shares {
+				scope.spawn(move || cast(rays, hits));
+			}
+			// the first share is cast here, rather than waiting idle
+			if let Some((rays, hits)) = first {
+				cast(rays, hits);
+			}
+		});
+
+		hits
+	}
+
+	/// Find the closest point on the surface of the voxels to a point.
+	///
+	/// Returns the point on the surface and the distance to it, which is
+	/// negative from inside of the voxels, or None if the sculpt is empty.
+	/// Nodes are visited closest first, and skipped once they are further
+	/// away than the closest surface found so far.
+	pub fn closest_surface_point(&self, point: Vec3) -> Option<(Vec3, f32)> {
+		let inside = self.is_solid(point);
+		let mut closest = (point, f32::INFINITY);
+		let closer = |closest: (Vec3, f32), bounds: Aabb| {
+			let surface = point.clamp(bounds.min, bounds.max);
+			let distance = point.distance(surface);
+
+			if distance < closest.1 { (surface, distance) } else { closest }
+		};
+
+		// from the inside, the space around the sculpt is empty too
+		if inside {
+			let bounds = self.get_bounds();
+			let (axis, face) = (0..3)
+				.flat_map(|axis| [(axis, bounds.min[axis]), (axis, bounds.max[axis])])
+				.min_by(|(a, a_face), (b, b_face)| (point[*a] - a_face).abs().total_cmp(&(point[*b] - b_face).abs()))
+				.unwrap();
+			let mut surface = point;
+			surface[axis] = face;
+			closest = closer(closest, Aabb::new(surface, surface));
+		}
+
+		let mut queue = BinaryHeap::new();
+		queue.push(Nearest::new(point, &self.root, &self.frame));
+		while let Some(Nearest { distance, node }) = queue.pop() {
+			if distance >= closest.1 {
+				break;
+			}
+			// outside, the closest point of a leaf is on the surface, and inside, it is the closest point of an empty octant
+			if node.is_leaf() {
+				if !inside {
+					closest = closer(closest, node.bounds(&self.frame));
+				}
+				continue;
+			}
+			for (index, child) in node.children.iter().enumerate() {
+				match child {
+					Some(child) => queue.push(Nearest::new(point, child, &self.frame)),
+					None if inside && node.kind != SculptNodeKind::None => closest = closer(closest, Aabb::cube(self.frame.center(node.code.child(index)), node.size(&self.frame) / 2.0)),
+					None => (),
+				}
+			}
+		}
+
+		let (surface, distance) = closest;
+
+		distance.is_finite().then_some((surface, if inside { -distance } else { distance }))
+	}
+
+	/// Find every stretch of a ray inside of the voxels, in order.
+	pub fn raycast_spans(&self, origin: Vec3, direction: Vec3) -> Vec<SculptSpan> {
+		let mut spans = Vec::new();
+		self.root.raycast_spans(origin, direction, direction.recip(), &mut spans, &self.frame);
+
+		SculptSpan::merge(spans, self.min_leaf_size() * 1e-3 / direction.length())
+	}
+
+	/// Copy the nodes overlapping a box, so parts of them can be restored after an edit.
+	pub fn copy_within(&self, bounds: Aabb) -> SculptCopy {
+		SculptCopy {
+			root: self.root.copy_within(&bounds, &self.frame),
+		}
+	}
+
+	/// Put the cells marked in a grid back as they were in a copy.
+	///
+	/// The cells of the grid are counted from another origin. The
+	/// restored voxels keep their material, mask and edit heat.
+	pub fn restore(&mut self, copy: &SculptCopy, cells: &VoxelGrid, origin: Vec3) {
+		let marked = CellCounts::new(cells.get_low(), cells.get_high(), |cell| cells.get(cell));
+		let voxel_size = self.min_leaf_size();
+
+		self.root.restore(Some(&copy.root), &|size, center| {
+			let (low, high) = cell_box(origin, voxel_size, size, center);
+			match marked.count(low, high) {
+				0 => Coverage::Unchanged,
+				count if count == (high - low).element_product() as u32 => Coverage::Solid,
+				_ => Coverage::Mixed,
+			}
+		}, &self.frame);
+		self.root.set_child_count();
+	}
+}
+
+/// The nodes of part of a sculpt, as they were when copied.
+pub struct SculptCopy {
+	root: SculptNode,
+}
+
+/// The paint of a cube of the sculpt, as it was before it was painted over.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PaintPatch {
+	pub center: Vec3,
+	pub size: f32,
+	pub paint: u32,
+}
+
+/// Pack a color into 8 bits per channel, red first, like WGSL's `pack4x8unorm`.
+///
+/// The alpha is how much of the material the paint covers.
+pub fn pack_paint(color: [f32; 4]) -> u32 {
+	color.iter()
+		.enumerate()
+		.map(|(index, channel)| ((channel.clamp(0.0, 1.0) * 255.0).round() as u32) << (index * 8))
+		.fold(0, |packed, channel| packed | channel)
+}
+
+/// Unpack a color packed by `pack_paint`.
+pub fn unpack_paint(paint: u32) -> [f32; 4] {
+	std::array::from_fn(|index| ((paint >> (index * 8)) & 0xff) as f32 / 255.0)
+}
+
+/// Mix paint over a material color, by the alpha of the paint.
+pub fn mix_paint(color: [f32; 4], paint: u32) -> [f32; 4] {
+	let paint = unpack_paint(paint);
+
+	[
+		color[0] + (paint[0] - color[0]) * paint[3],
+		color[1] + (paint[1] - color[1]) * paint[3],
+		color[2] + (paint[2] - color[2]) * paint[3],
+		color[3],
+	]
+}
+
+/// The fewest rays worth starting a thread for in a batch.
+const RAYS_PER_THREAD: usize = 256;
+
+/// A ray to cast against a sculpt, as part of a batch.
+///
+/// The direction does not need to be normalized, but
+/// hit distances are measured in multiples of it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+	pub origin: Vec3,
+	pub direction: Vec3,
+}
+
+impl Ray {
+	pub fn new(origin: Vec3, direction: Vec3) -> Self {
+		Self { origin, direction }
+	}
+}
+
+/// The result of casting a ray against the sculpt.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SculptHit {
+	pub distance: f32,
+	pub position: Vec3,
+	pub normal: Vec3,
+	pub material: u32,
+	/// The packed paint over the material.
+	pub paint: u32,
+}
+
+/// A stretch of a ray inside of solid voxels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SculptSpan {
+	/// Where the ray enters the voxels.
+	pub entry: SculptHit,
+	/// Where the ray leaves the voxels, with the normal of the surface it leaves through.
+	pub exit: SculptHit,
+}
+
+impl SculptSpan {
+	/// Sort spans along the ray, joining the ones which touch or overlap.
+	pub fn merge(mut spans: Vec<SculptSpan>, epsilon: f32) -> Vec<SculptSpan> {
+		spans.sort_by(|a, b| a.entry.distance.total_cmp(&b.entry.distance));
+
+		let mut merged: Vec<SculptSpan> = Vec::with_capacity(spans.len());
+		for span in spans {
+			match merged.last_mut() {
+				Some(last) if span.entry.distance <= last.exit.distance + epsilon => {
+					if span.exit.distance > last.exit.distance {
+						last.exit = span.exit;
+					}
+				},
+				_ => merged.push(span),
+			}
+		}
+
+		merged
+	}
+}
+
+/// Get the cube of a number.
+///
+/// Unlike `powi`, whose precision is unspecified, this rounds the same on every platform.
+fn cube(value: f32) -> f32 {
+	value * value * value
+}
+
+/// Get the range of finest cells covered by a node.
+///
+/// The low cell is inclusive, and the high cell is exclusive.
+fn cell_box(origin: Vec3, voxel_size: f32, size: f32, center: Vec3) -> (IVec3, IVec3) {
+	let low = ((center - size / 2.0 - origin) / voxel_size).round().as_ivec3();
+	let high = ((center + size / 2.0 - origin) / voxel_size).round().as_ivec3();
+
+	(low, high)
+}
+
+/// How a node is affected when applying a change to the sculpt.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Coverage {
+	/// The node is left as it is.
+	Unchanged,
+	/// The node becomes empty space.
+	Empty,
+	/// The node becomes a solid leaf.
+	Solid,
+	/// The node is partially covered, so its children are classified.
+	Mixed,
+}
+
+/// How the convex edges of a sculpt are worn down.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Wear {
+	/// How much of the edges are worn, from 0 to 1, where sharp corners wear first.
+	pub intensity: f32,
+	/// How far around each voxel the edges are looked for, in voxels.
+	pub scale: u32,
+	pub effect: WearEffect,
+}
+
+/// What happens to the worn voxels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WearEffect {
+	/// The voxels get an edge material.
+	Material(u32),
+	/// The voxels are removed.
+	Erode,
+}
+
+/// A node waiting to be searched for the closest surface, by its distance from the point searched from.
+struct Nearest<'a> {
+	distance: f32,
+	node: &'a SculptNode,
+}
+
+impl<'a> Nearest<'a> {
+	fn new(point: Vec3, node: &'a SculptNode, frame: &Frame) -> Self {
+		let bounds = node.bounds(frame);
+
+		Self {
+			distance: point.distance(point.clamp(bounds.min, bounds.max)),
+			node,
+		}
+	}
+}
+
+impl PartialEq for Nearest<'_> {
+	fn eq(&self, other: &Self) -> bool {
+		self.distance == other.distance
+	}
+}
+
+impl Eq for Nearest<'_> {}
+
+impl PartialOrd for Nearest<'_> {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for Nearest<'_> {
+	/// The closest node is the greatest, to come first out of a max heap.
+	fn cmp(&self, other: &Self) -> Ordering {
+		other.distance.total_cmp(&self.distance)
+	}
+}
+
+/// A dense block of the finest voxel cells of a sculpt.
+///
+/// Used for neighborhood queries, which are awkward to do
+/// directly on the octree.
+#[derive(Clone)]
+pub struct VoxelGrid {
+	low: IVec3,
+	high: IVec3,
+	cells: Vec<bool>,
+}
+
+impl VoxelGrid {
+	/// Create an empty grid covering a box of cells.
+	pub fn new(low: IVec3, high: IVec3) -> Self {
+		let size = (high - low).max(IVec3::ZERO);
+
+		Self {
+			low,
+			high: low + size,
+			cells: vec![false; size.element_product() as usize],
+		}
+	}
+
+	/// Get the lowest cell of the grid (inclusive).
+	pub fn get_low(&self) -> IVec3 {
+		self.low
+	}
+
+	/// Get the highest cell of the grid (exclusive).
+	pub fn get_high(&self) -> IVec3 {
+		self.high
+	}
+
+	/// Check whether a cell is inside of the grid.
+	pub fn contains(&self, cell: IVec3) -> bool {
+		self.index(cell).is_some()
+	}
+
+	/// Find where a cell is stored.
+	fn index(&self, cell: IVec3) -> Option<usize> {
+		if cell.cmplt(self.low).any() || cell.cmpge(self.high).any() {
+			return None;
+		}
+
+		let size = self.high - self.low;
+		let offset = cell - self.low;
+
+		Some((offset.x + size.x * (offset.y + size.y * offset.z)) as usize)
+	}
+
+	/// Check whether a cell is solid (cells outside of the grid are empty).
+	pub fn get(&self, cell: IVec3) -> bool {
+		self.index(cell).is_some_and(|index| self.cells[index])
+	}
+
+	/// Set whether a cell is solid, ignoring cells outside of the grid.
+	pub fn set(&mut self, cell: IVec3, solid: bool) {
+		if let Some(index) = self.index(cell) {
+			self.cells[index] = solid;
+		}
+	}
+
+	/// Count the solid cells.
+	pub fn count_solid(&self) -> usize {
+		self.cells.iter().filter(|solid| **solid).count()
+	}
+
+	/// Count the solid cells among the 26 neighbors of a cell.
+	pub fn solid_neighbors(&self, cell: IVec3) -> u32 {
+		let mut count = 0;
+
+		for z in -1..=1 {
+			for y in -1..=1 {
+				for x in -1..=1 {
+					if (x, y, z) != (0, 0, 0) && self.get(cell + IVec3::new(x, y, z)) {
+						count += 1;
+					}
+				}
+			}
+		}
+
+		count
+	}
+
+	/// Check whether a cell is solid with an empty cell across one of its faces.
+	pub fn is_surface(&self, cell: IVec3) -> bool {
+		self.get(cell) && IVec3::AXES.iter().any(|axis| !self.get(cell + *axis) || !self.get(cell - *axis))
+	}
+}
+
+/// A summed volume table for counting cells in boxes quickly.
+struct CellCounts {
+	low: IVec3,
+	high: IVec3,
+	sums: Vec<u32>,
+}
+
+impl CellCounts {
+	/// Build the table from a predicate over a box of cells.
+	fn new(low: IVec3, high: IVec3, predicate: impl Fn(IVec3) -> bool) -> Self {
+		let size = high - low + 1;
+		let mut sums = vec![0u32; size.element_product() as usize];
+		let index = |x: i32, y: i32, z: i32| (x + size.x * (y + size.y * z)) as usize;
+
+		for z in 1..size.z {
+			for y in 1..size.y {
+				for x in 1..size.x {
+					let value = predicate(low + IVec3::new(x - 1, y - 1, z - 1)) as u32;
+					let added = value
+						+ sums[index(x - 1, y, z)] + sums[index(x, y - 1, z)] + sums[index(x, y, z - 1)]
+						+ sums[index(x - 1, y - 1, z - 1)];
+					let removed = sums[index(x - 1, y - 1, z)] + sums[index(x - 1, y, z - 1)] + sums[index(x, y - 1, z - 1)];
+					sums[index(x, y, z)] = added - removed;
+				}
+			}
+		}
+
+		Self {
+			low,
+			high,
+			sums,
+		}
+	}
+
+	/// Count the matching cells in a box, clipped to the table.
+	fn count(&self, low: IVec3, high: IVec3) -> u32 {
+		let low = (low.max(self.low) - self.low).as_uvec3();
+		let high = high.min(self.high) - self.low;
+		if high.cmple(low.as_ivec3()).any() {
+			return 0;
+		}
+		let high = high.as_uvec3();
+
+		let size = (self.high - self.low + 1).as_uvec3();
+		let sum = |x: u32, y: u32, z: u32| self.sums[(x + size.x * (y + size.y * z)) as usize];
+
+		let added = sum(high.x, high.y, high.z)
+			+ sum(low.x, low.y, high.z) + sum(low.x, high.y, low.z) + sum(high.x, low.y, low.z);
+		let removed = sum(low.x, high.y, high.z) + sum(high.x, low.y, high.z) + sum(high.x, high.y, low.z)
+			+ sum(low.x, low.y, low.z);
+
+		added - removed
+	}
+}
+
+/// An inconsistency in an octree, at the center of the node it was found in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Issue {
+	/// A leaf or empty node has children, or an empty node is kept as a child.
+	InconsistentKind(Vec3),
+	/// The stored child count, used to lay out the buffer, doesn't match the children.
+	StaleChildCount { center: Vec3, stored: u32, expected: u32 },
+	/// An interior node has no children.
+	EmptyInterior(Vec3),
+	/// A leaf uses a material missing from the palette.
+	MissingMaterial { center: Vec3, material: u32 },
+}
+
+/// A per-voxel attribute which can be sent to the GPU.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Channel {
+	/// How recently and often a voxel was edited, as of a stroke,
+	/// decaying by a factor with each stroke since.
+	Heat { stroke: u32, decay: f32 },
+	/// How strongly a voxel is protected from strokes, from 0 to 1.
+	Mask,
+}
+
+impl Channel {
+	/// Get the value of the channel for a node.
+	fn value(&self, node: &SculptNode) -> f32 {
+		match *self {
+			Channel::Heat { stroke, decay } => node.heat.at(stroke, decay),
+			Channel::Mask => node.mask,
+		}
+	}
+}
+
+/// A decaying count of edits.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct EditHeat {
+	count: f32,
+	stroke: u32,
+}
+
+impl EditHeat {
+	/// Get the decayed count as of a stroke.
+	fn at(&self, stroke: u32, decay: f32) -> f32 {
+		self.count * decay.powi(stroke.saturating_sub(self.stroke) as i32)
+	}
+
+	/// Count an edit made by a stroke.
+	fn touch(&mut self, stroke: u32, decay: f32) {
+		self.count = self.at(stroke, decay) + 1.0;
+		self.stroke = stroke;
+	}
+
+	/// Keep the more recent of two histories, or the larger if they are as recent.
+	fn latest(self, other: EditHeat) -> EditHeat {
+		if (other.stroke, other.count) > (self.stroke, self.count) {
+			other
+		} else {
+			self
+		}
+	}
 }
 
 /// The classification of a sculpt node.
-#[derive(PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum SculptNodeKind {
 	Leaf,
 	Interior,
@@ -64,138 +1097,729 @@ enum SculptNodeKind {
 }
 
 /// A node/voxel in the sparse voxel octree.
+///
+/// Children are allocated from the pool of the thread, and given back to it when dropped.
 struct SculptNode {
 	kind: SculptNodeKind,
 	children: [Option<Box<SculptNode>>; 8],
-	center: Vec3,
-	size: f32,
+	/// The address of the node, which its center and size are worked out from.
+	code: LocationalCode,
 	material: u32,
 	child_count: u32,
+	heat: EditHeat,
+	mask: f32,
+	/// The color painted over the material, packed by `pack_paint`.
+	paint: u32,
+}
+
+impl Clone for SculptNode {
+	fn clone(&self) -> Self {
+		Self {
+			kind: self.kind,
+			children: std::array::from_fn(|index| self.children[index].as_ref().map(|child| pool::allocate(child.as_ref().clone()))),
+			code: self.code,
+			material: self.material,
+			child_count: self.child_count,
+			heat: self.heat,
+			mask: self.mask,
+			paint: self.paint,
+		}
+	}
+}
+
+impl Drop for SculptNode {
+	fn drop(&mut self) {
+		self.clear_children();
+	}
 }
 
 impl SculptNode {
-	/// Make a sculpt node with the given parameters and no children.
-	fn new(kind: SculptNodeKind, material: u32, size: f32, center: Vec3) -> Self {
+	/// Make a sculpt node at an address, with no children.
+	fn new(kind: SculptNodeKind, material: u32, code: LocationalCode) -> Self {
 		Self {
 			kind,
 			children: [None, None, None, None, None, None, None, None],
-			center,
-			size,
+			code,
 			material,
 			child_count: 0,
+			heat: EditHeat::default(),
+			mask: 0.0,
+			paint: 0,
+		}
+	}
+
+	/// Drop a child, giving it back to the pool.
+	fn remove_child(&mut self, index: usize) {
+		if let Some(child) = self.children[index].take() {
+			pool::recycle(child);
+		}
+	}
+
+	/// Drop every child, giving them back to the pool.
+	fn clear_children(&mut self) {
+		for index in 0..self.children.len() {
+			self.remove_child(index);
+		}
+	}
+
+	/// Get the center of the node within the frame of the root.
+	fn center(&self, frame: &Frame) -> Vec3 {
+		frame.center(self.code)
+	}
+
+	/// Get the edge length of the node within the frame of the root.
+	fn size(&self, frame: &Frame) -> f32 {
+		frame.size(self.code)
+	}
+
+	/// Get the cube covered by the node within the frame of the root.
+	fn bounds(&self, frame: &Frame) -> Aabb {
+		Aabb::cube(self.center(frame), self.size(frame))
+	}
+
+	/// Check whether the node is a leaf without children.
+	fn is_leaf(&self) -> bool {
+		self.kind == SculptNodeKind::Leaf && !self.children.iter().any(|child| child.is_some())
+	}
+
+	/// Check whether the node is, or contains, a leaf protected by its mask.
+	fn is_masked(&self) -> bool {
+		if self.is_leaf() {
+			return self.mask > MASK_PROTECTS;
+		}
+
+		self.children.iter().flatten().any(|child| child.is_masked())
+	}
+
+	/// Check whether every leaf below the node has a material, without any protected by its mask.
+	fn is_uniform(&self, material: u32) -> bool {
+		if self.is_leaf() {
+			return self.material == material && self.mask <= MASK_PROTECTS;
+		}
+
+		self.children.iter().flatten().all(|child| child.is_uniform(material))
+	}
+
+	/// Call a function for every leaf, recursively.
+	fn visit_leaves(&self, visit: &mut dyn FnMut(&SculptNode)) {
+		if self.is_leaf() {
+			visit(self);
+		}
+		for child in self.children.iter().flatten() {
+			child.visit_leaves(visit);
+		}
+	}
+
+	/// Call a function for every leaf, recursively, in the order of `visit_leaves`.
+	fn visit_leaves_mut(&mut self, visit: &mut dyn FnMut(&mut SculptNode)) {
+		if self.is_leaf() {
+			visit(self);
+		}
+		for child in self.children.iter_mut().flatten() {
+			child.visit_leaves_mut(visit);
+		}
+	}
+
+	/// Handles the sparse voxel octree subdividing modifications, recursively.
+	fn subdivide(&mut self, fill: &dyn Fn(Vec3) -> u32, is_filled: &dyn Fn(f32, Vec3) -> bool, is_contained: &dyn Fn(f32, Vec3) -> bool, frame: &Frame, invert: bool) {
+		if !invert && self.kind == SculptNodeKind::Leaf {
+			return;
+		}
+		
+		if self.code.get_depth() >= frame.get_depth() {
+			self.clear_children();
+			self.kind = SculptNodeKind::Leaf;
+
+			return;
+		}
+		let center = self.center(frame);
+		if is_contained(self.size(frame), center) != invert && self.is_uniform(fill(center)) {
+			self.clear_children();
+			self.kind = SculptNodeKind::Leaf;
+			self.material = fill(center);
+
+			return;
+		}
+
+		self.subdivide_children(fill, is_filled, is_contained, frame, invert);
+	}
+
+	/// Handles the subdividing modifications below a node the shape doesn't cover whole, recursively.
+	///
+	/// Octants the shape covers whole become leaves right away, at the
+	/// highest level they fit, without being created empty and descended
+	/// into first, and existing subtrees it doesn't reach are skipped, so
+	/// fills only visit the nodes along their surface.
+	fn subdivide_children(&mut self, fill: &dyn Fn(Vec3) -> u32, is_filled: &dyn Fn(f32, Vec3) -> bool, is_contained: &dyn Fn(f32, Vec3) -> bool, frame: &Frame, invert: bool) {
+		let bottom = self.code.get_depth() + 1 >= frame.get_depth();
+		for index in 0..self.children.len() {
+			let code = self.code.child(index);
+			let (size, center) = (frame.size(code), frame.center(code));
+			let whole = || bottom || is_contained(size, center) != invert;
+			match self.children[index] {
+				Some(ref child) if !invert && child.kind == SculptNodeKind::Leaf => (),
+				// subtrees the shape doesn't reach are left as they are
+				Some(_) if !invert && !is_filled(size, center) => (),
+				// covered subtrees only collapse where they would look the same as one leaf
+				Some(ref mut child) if whole() && child.is_uniform(fill(center)) => {
+					child.clear_children();
+					child.kind = SculptNodeKind::Leaf;
+					child.material = fill(center);
+				}
+				Some(ref mut child) => child.subdivide_children(fill, is_filled, is_contained, frame, invert),
+				None if is_filled(size, center) != invert => {
+					let mut child = pool::allocate(SculptNode::new(SculptNodeKind::Leaf, fill(center), code));
+					if !whole() {
+						child.kind = SculptNodeKind::None;
+						child.subdivide_children(fill, is_filled, is_contained, frame, invert);
+					}
+					self.children[index] = Some(child);
+				}
+				None => (),
+			}
+		}
+
+		let mut all_leaves = true;
+		let mut materials = None;
+
+		for index in 0..self.children.len() {
+			if let Some(ref child) = self.children[index] {
+				all_leaves = all_leaves && child.kind == SculptNodeKind::Leaf && child.mask <= MASK_PROTECTS;
+				// leaves of a dithered fill only merge where they picked the same material
+				all_leaves = all_leaves && *materials.get_or_insert(child.material) == child.material;
+			} else {
+				all_leaves = false;
+			}
+		}
+
+		if all_leaves {
+			self.clear_children();
+			self.material = materials.unwrap_or(self.material);
+
+			self.kind = SculptNodeKind::Leaf;
+		} else if self.children.iter().any(|child| child.is_some()) {
+			self.kind = SculptNodeKind::Interior;
+		}
+	}
+
+	/// Handles the sparse voxel octree unsubdividing modifications, recursively.
+	fn unsubdivide(&mut self, fill: &dyn Fn(Vec3) -> u32, is_filled: &dyn Fn(f32, Vec3) -> bool, is_contained: &dyn Fn(f32, Vec3) -> bool, frame: &Frame) {
+		// masked leaves are kept whole
+		if !is_filled(self.size(frame), self.center(frame)) || (self.is_leaf() && self.mask > MASK_PROTECTS) {
+			return;
+		}
+
+		let mut removed_all = self.children.iter().any(|child| child.is_some());
+		for index in 0..self.children.len() {
+			let mut should_remove = false;
+			if let Some(ref mut child) = self.children[index] {
+				// children the shape erases whole are dropped without descending into them
+				if is_contained(child.size(frame), child.center(frame)) && !child.is_masked() {
+					should_remove = true;
+				} else {
+					child.unsubdivide(fill, is_filled, is_contained, frame);
+					should_remove = child.kind == SculptNodeKind::None;
+				}
+				removed_all = removed_all && should_remove;
+			}
+			if should_remove {
+				self.remove_child(index);
+			}
+		}
+
+		if removed_all {
+			self.kind = SculptNodeKind::None;
+
+			return;
+		}
+
+		// If it isn't a leaf, return
+		if self.children.iter().any(|child| child.is_some()) {
+			self.kind = SculptNodeKind::Interior;
+
+			return;
+		}
+
+		self.subdivide(fill, is_filled, is_contained, frame, true);
+
+		if !self.children.iter().any(|child| child.is_some()) {
+			self.kind = SculptNodeKind::None;
+		} else {
+			self.kind = SculptNodeKind::Interior;
+		}
+	}
+
+	/// Handle classified modifications, recursively.
+	fn apply(&mut self, fill: &dyn Fn(Vec3) -> u32, classify: &dyn Fn(f32, Vec3) -> Coverage, frame: &Frame) {
+		match classify(self.size(frame), self.center(frame)) {
+			Coverage::Unchanged => (),
+			Coverage::Empty => {
+				self.clear_children();
+				self.kind = SculptNodeKind::None;
+			},
+			Coverage::Solid => {
+				if self.kind != SculptNodeKind::Leaf || self.children.iter().any(|child| child.is_some()) {
+					self.material = fill(self.center(frame));
+				}
+				self.clear_children();
+				self.kind = SculptNodeKind::Leaf;
+			},
+			Coverage::Mixed => {
+				// a mixed voxel at the finest level is left alone
+				if self.code.get_depth() >= frame.get_depth() {
+					return;
+				}
+
+				let was_leaf = self.kind == SculptNodeKind::Leaf && !self.children.iter().any(|child| child.is_some());
+				let kind = if was_leaf { SculptNodeKind::Leaf } else { SculptNodeKind::None };
+
+				for index in 0..self.children.len() {
+					if self.children[index].is_none() {
+						self.children[index] = Some(pool::allocate(self.split_child(kind, index, frame)));
+					}
+					if let Some(ref mut child) = self.children[index] {
+						child.apply(fill, classify, frame);
+						if child.kind == SculptNodeKind::None {
+							self.remove_child(index);
+						}
+					}
+				}
+
+				self.simplify();
+			},
+		}
+	}
+
+	/// Paint the leaves selected by a classification, recording what they were painted before, recursively.
+	fn paint(&mut self, paint: u32, classify: &dyn Fn(f32, Vec3) -> Coverage, frame: &Frame, changed: &mut Vec<PaintPatch>) {
+		match classify(self.size(frame), self.center(frame)) {
+			Coverage::Unchanged | Coverage::Empty => (),
+			Coverage::Solid => self.visit_leaves_mut(&mut |leaf| {
+				if leaf.paint != paint {
+					changed.push(PaintPatch { center: leaf.center(frame), size: leaf.size(frame), paint: leaf.paint });
+					leaf.paint = paint;
+				}
+			}),
+			Coverage::Mixed => {
+				// a mixed voxel at the finest level is left alone
+				if self.code.get_depth() >= frame.get_depth() {
+					return;
+				}
+
+				if self.is_leaf() {
+					for index in 0..self.children.len() {
+						self.children[index] = Some(pool::allocate(self.split_child(SculptNodeKind::Leaf, index, frame)));
+					}
+				}
+				for child in self.children.iter_mut().flatten() {
+					child.paint(paint, classify, frame, changed);
+				}
+
+				self.simplify();
+			},
 		}
 	}
 
-	/// Handles the sparse voxel octree subdividing modifications, recursively.
-	fn subdivide(&mut self, fill: u32, is_filled: &Box<dyn Fn(f32, Vec3) -> bool>, is_contained: &Box<dyn Fn(f32, Vec3) -> bool>, min_leaf_size: f32, invert: bool) {
-		if !invert && self.kind == SculptNodeKind::Leaf {
-			return;
+	/// Set the material of the leaves from the centers of their voxels, recursively.
+	fn fill_materials(&mut self, material: &dyn Fn(Vec3, u32) -> u32, frame: &Frame) {
+		if self.is_leaf() {
+			let center = material(self.center(frame), self.material);
+			// the corner voxels are the furthest apart, so they tell whether the materials vary across the leaf
+			let inset = (self.size(frame) - frame.get_voxel_size()) / 2.0;
+			let varies = (0..8).any(|corner| {
+				let offset = vec3(
+					if corner & 1 == 0 { -inset } else { inset },
+					if corner & 2 == 0 { -inset } else { inset },
+					if corner & 4 == 0 { -inset } else { inset },
+				);
+				material(self.center(frame) + offset, self.material) != center
+			});
+			if !varies || self.code.get_depth() >= frame.get_depth() {
+				self.material = center;
+				return;
+			}
+
+			for index in 0..self.children.len() {
+				self.children[index] = Some(pool::allocate(self.split_child(SculptNodeKind::Leaf, index, frame)));
+			}
+		}
+		for child in self.children.iter_mut().flatten() {
+			child.fill_materials(material, frame);
 		}
-		
-		if self.size <= min_leaf_size || (is_contained(self.size, self.center) == !invert) {
-			self.children = [None, None, None, None, None, None, None, None];
-			self.kind = SculptNodeKind::Leaf;
 
-			return;
+		self.simplify();
+	}
+
+	/// Copy the node, leaving out the children outside of a box, recursively.
+	fn copy_within(&self, bounds: &Aabb, frame: &Frame) -> SculptNode {
+		let mut copy = SculptNode {
+			children: [None, None, None, None, None, None, None, None],
+			..*self
+		};
+		for (index, child) in self.children.iter().enumerate() {
+			if let Some(child) = child.as_ref().filter(|child| child.bounds(frame).intersects(bounds)) {
+				copy.children[index] = Some(pool::allocate(child.copy_within(bounds, frame)));
+			}
 		}
 
-		let half_size = self.size / 2.0;
-		let quarter_size = self.size / 4.0;
+		copy
+	}
 
-		let lfb = vec3(self.center.x - quarter_size, self.center.y - quarter_size, self.center.z - quarter_size);
-		let rfb = vec3(self.center.x + quarter_size, self.center.y - quarter_size, self.center.z - quarter_size);
-		let lbb = vec3(self.center.x - quarter_size, self.center.y + quarter_size, self.center.z - quarter_size);
-		let rbb = vec3(self.center.x + quarter_size, self.center.y + quarter_size, self.center.z - quarter_size);
-		let lft = vec3(self.center.x - quarter_size, self.center.y - quarter_size, self.center.z + quarter_size);
-		let rft = vec3(self.center.x + quarter_size, self.center.y - quarter_size, self.center.z + quarter_size);
-		let lbt = vec3(self.center.x - quarter_size, self.center.y + quarter_size, self.center.z + quarter_size);
-		let rbt = vec3(self.center.x + quarter_size, self.center.y + quarter_size, self.center.z + quarter_size);
+	/// Replace the parts of the node selected by a classification with an older node, recursively.
+	///
+	/// Solid coverage takes the older node, while mixed coverage splits both nodes.
+	fn restore(&mut self, old: Option<&SculptNode>, classify: &dyn Fn(f32, Vec3) -> Coverage, frame: &Frame) {
+		match classify(self.size(frame), self.center(frame)) {
+			Coverage::Unchanged | Coverage::Empty => (),
+			Coverage::Solid => match old {
+				Some(old) => *self = old.clone(),
+				None => {
+					self.clear_children();
+					self.kind = SculptNodeKind::None;
+				},
+			},
+			Coverage::Mixed => {
+				if self.code.get_depth() >= frame.get_depth() {
+					return;
+				}
 
-		if (is_filled(half_size, lfb) == !invert) && !self.children[0].is_some() {
-			self.children[0] = Some(Box::new(SculptNode::new(SculptNodeKind::None, fill, half_size, lfb)));
-		};
-		if (is_filled(half_size, rfb) == !invert) && !self.children[1].is_some() {
-			self.children[1] = Some(Box::new(SculptNode::new(SculptNodeKind::None, fill, half_size, rfb)));
-		};
-		if (is_filled(half_size, lbb) == !invert) && !self.children[2].is_some() {
-			self.children[2] = Some(Box::new(SculptNode::new(SculptNodeKind::None, fill, half_size, lbb)));
-		};
-		if (is_filled(half_size, rbb) == !invert) && !self.children[3].is_some() {
-			self.children[3] = Some(Box::new(SculptNode::new(SculptNodeKind::None, fill, half_size, rbb)));
-		};
-		if (is_filled(half_size, lft) == !invert) && !self.children[4].is_some() {
-			self.children[4] = Some(Box::new(SculptNode::new(SculptNodeKind::None, fill, half_size, lft)));
-		};
-		if (is_filled(half_size, rft) == !invert) && !self.children[5].is_some() {
-			self.children[5] = Some(Box::new(SculptNode::new(SculptNodeKind::None, fill, half_size, rft)));
-		};
-		if (is_filled(half_size, lbt) == !invert) && !self.children[6].is_some() {
-			self.children[6] = Some(Box::new(SculptNode::new(SculptNodeKind::None, fill, half_size, lbt)));
-		};
-		if (is_filled(half_size, rbt) == !invert) && !self.children[7].is_some() {
-			self.children[7] = Some(Box::new(SculptNode::new(SculptNodeKind::None, fill, half_size, rbt)));
-		};
+				let was_leaf = self.is_leaf();
+				for index in 0..self.children.len() {
+					if self.children[index].is_none() {
+						let kind = if was_leaf { SculptNodeKind::Leaf } else { SculptNodeKind::None };
+						self.children[index] = Some(pool::allocate(self.split_child(kind, index, frame)));
+					}
+
+					// an older leaf is split the same way
+					let split = old.filter(|old| old.is_leaf()).map(|old| old.split_child(SculptNodeKind::Leaf, index, frame));
+					let old_child = split.as_ref().or_else(|| old.and_then(|old| old.children[index].as_deref()));
+					if let Some(ref mut child) = self.children[index] {
+						child.restore(old_child, classify, frame);
+						if child.kind == SculptNodeKind::None {
+							self.remove_child(index);
+						}
+					}
+				}
 
-		let mut all_leaves = true;
+				self.simplify();
+			},
+		}
+	}
+
+	/// Make a child of the node with the node's attributes.
+	fn split_child(&self, kind: SculptNodeKind, index: usize, frame: &Frame) -> SculptNode {
+		let mut child = SculptNode::new(kind, self.material, self.code.child(index));
+		child.heat = self.heat;
+		child.mask = self.mask;
+		child.paint = self.paint;
+
+		child
+	}
+
+	/// Drop empty subtrees and merge full sets of matching leaves, recursively, where a shape reached.
+	fn compact(&mut self, reached: &dyn Fn(f32, Vec3) -> bool, frame: &Frame) {
+		if self.is_leaf() || !reached(self.size(frame), self.center(frame)) {
+			return;
+		}
 
 		for index in 0..self.children.len() {
 			if let Some(ref mut child) = self.children[index] {
-				child.subdivide(fill, &is_filled, &is_contained, min_leaf_size, invert);
-				all_leaves = all_leaves && (child.kind == SculptNodeKind::Leaf);
-			} else {
-				all_leaves = false;
+				child.compact(reached, frame);
+				if child.kind == SculptNodeKind::None {
+					self.remove_child(index);
+				}
 			}
 		}
+		self.simplify();
+	}
 
-		if all_leaves {
-			self.children = [None, None, None, None, None, None, None, None];
-
+	/// Update the kind of the node from its children, merging them if possible.
+	fn simplify(&mut self) {
+		let leaves: Vec<&SculptNode> = self.children.iter()
+			.flatten()
+			.filter(|child| child.kind == SculptNodeKind::Leaf && !child.children.iter().any(|child| child.is_some()))
+			.map(|child| child.as_ref())
+			.collect();
+
+		if leaves.len() == 8 && leaves.iter().all(|leaf| leaf.material == leaves[0].material && leaf.mask == leaves[0].mask && leaf.paint == leaves[0].paint) {
+			self.material = leaves[0].material;
+			self.mask = leaves[0].mask;
+			self.paint = leaves[0].paint;
+			self.heat = leaves.iter().fold(EditHeat::default(), |heat, leaf| heat.latest(leaf.heat));
+			self.clear_children();
 			self.kind = SculptNodeKind::Leaf;
 		} else if self.children.iter().any(|child| child.is_some()) {
 			self.kind = SculptNodeKind::Interior;
+		} else {
+			self.kind = SculptNodeKind::None;
 		}
 	}
 
-	/// Handles the sparse voxel octree unsubdividing modifications, recursively.
-	fn unsubdivide(&mut self, fill: u32, is_filled: &Box<dyn Fn(f32, Vec3) -> bool>, is_contained: &Box<dyn Fn(f32, Vec3) -> bool>, min_leaf_size: f32) {
-		if !is_filled(self.size, self.center) {
+	/// Count the node and its children at each of their depths, recursively.
+	fn count_depths(&self, counts: &mut Vec<usize>) {
+		let depth = self.code.get_depth() as usize;
+		if counts.len() <= depth {
+			counts.resize(depth + 1, 0);
+		}
+		counts[depth] += 1;
+		for child in self.children.iter().flatten() {
+			child.count_depths(counts);
+		}
+	}
+
+	/// Add up the volume filled by each material, recursively.
+	fn add_volumes(&self, volumes: &mut BTreeMap<u32, f32>, frame: &Frame) {
+		if self.is_leaf() {
+			*volumes.entry(self.material).or_default() += cube(self.size(frame));
+		}
+		for child in self.children.iter().flatten() {
+			child.add_volumes(volumes, frame);
+		}
+	}
+
+	/// Collapse the subtrees below a depth, recursively.
+	fn quantize(&mut self, depth: u32, frame: &Frame) {
+		if self.is_leaf() {
 			return;
 		}
 
-		let mut removed_all = self.children.iter().any(|child| child.is_some());
-		for index in 0..self.children.len() {
-			let mut should_remove = false;
-			if let Some(ref mut child) = self.children[index] {
-				child.unsubdivide(fill, &is_filled, &is_contained, min_leaf_size);
-				if (child.kind == SculptNodeKind::None) || is_contained(child.size, child.center) {
-					should_remove = true;
+		if depth > 0 {
+			for index in 0..self.children.len() {
+				if let Some(ref mut child) = self.children[index] {
+					child.quantize(depth - 1, frame);
+					if child.kind == SculptNodeKind::None {
+						self.remove_child(index);
+					}
 				}
-				removed_all = removed_all && should_remove;
-			}
-			if should_remove {
-				self.children[index] = None;
 			}
+			self.simplify();
+
+			return;
 		}
 
-		if removed_all {
+		// sorted maps add up the volumes in the same order every time, as rounding depends on it
+		let mut volumes = BTreeMap::new();
+		self.add_volumes(&mut volumes, frame);
+		let filled: f32 = volumes.values().sum();
+
+		if filled * 2.0 < cube(self.size(frame)) {
+			self.clear_children();
 			self.kind = SculptNodeKind::None;
 
 			return;
 		}
 
-		// If it isn't a leaf, return
-		if self.children.iter().any(|child| child.is_some()) {
-			self.kind = SculptNodeKind::Interior;
+		let mut heat = EditHeat::default();
+		let mut mask = 0.0f32;
+		let mut paints = BTreeMap::new();
+		self.visit_leaves(&mut |leaf| {
+			heat = heat.latest(leaf.heat);
+			mask = mask.max(leaf.mask);
+			*paints.entry(leaf.paint).or_insert(0.0) += cube(leaf.size(frame));
+		});
+		// ties go to the lowest material
+		self.material = volumes.into_iter()
+			.max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.cmp(&a.0)))
+			.map_or(self.material, |(material, _)| material);
+		self.paint = paints.into_iter()
+			.max_by(|a: &(u32, f32), b| a.1.total_cmp(&b.1).then(b.0.cmp(&a.0)))
+			.map_or(0, |(paint, _)| paint);
+		self.heat = heat;
+		self.mask = mask;
+		self.clear_children();
+		self.kind = SculptNodeKind::Leaf;
+	}
 
+	/// Mark the solid cells of a grid, recursively.
+	fn sample(&self, grid: &mut VoxelGrid, origin: Vec3, voxel_size: f32, frame: &Frame) {
+		let (low, high) = cell_box(origin, voxel_size, self.size(frame), self.center(frame));
+		let low = low.max(grid.low);
+		let high = high.min(grid.high);
+		if high.cmple(low).any() {
 			return;
 		}
 
-		self.subdivide(fill, &is_filled, &is_contained, min_leaf_size, true);
+		if self.children.iter().any(|child| child.is_some()) {
+			for child in self.children.iter().flatten() {
+				child.sample(grid, origin, voxel_size, frame);
+			}
+		} else if self.kind == SculptNodeKind::Leaf {
+			for z in low.z..high.z {
+				for y in low.y..high.y {
+					for x in low.x..high.x {
+						grid.set(IVec3::new(x, y, z), true);
+					}
+				}
+			}
+		}
+	}
+
+	/// Intersect a ray with the bounds of the node.
+	///
+	/// Returns the entry distance and the axis of the entered face.
+	fn intersect(&self, origin: Vec3, inverse_direction: Vec3, frame: &Frame) -> Option<(f32, usize)> {
+		self.intersect_span(origin, inverse_direction, frame).map(|(entry, _)| entry)
+	}
+
+	/// Intersect a ray with the bounds of the node.
+	///
+	/// Returns the distance and axis of both the entered and the exited face.
+	fn intersect_span(&self, origin: Vec3, inverse_direction: Vec3, frame: &Frame) -> Option<((f32, usize), (f32, usize))> {
+		let half_size = self.size(frame) / 2.0;
+
+		let mut entry = f32::NEG_INFINITY;
+		let mut exit = f32::INFINITY;
+		let mut axis = 0;
+		let mut exit_axis = 0;
+
+		for index in 0..3 {
+			let low = self.center(frame)[index] - half_size - origin[index];
+			let high = self.center(frame)[index] + half_size - origin[index];
+
+			let (near, far) = if inverse_direction[index].is_finite() {
+				let low = low * inverse_direction[index];
+				let high = high * inverse_direction[index];
+				(low.min(high), low.max(high))
+			} else if low <= 0.0 && high >= 0.0 {
+				// parallel to the slab and inside of it
+				(f32::NEG_INFINITY, f32::INFINITY)
+			} else {
+				return None;
+			};
+
+			if near > entry {
+				entry = near;
+				axis = index;
+			}
+			if far < exit {
+				exit = far;
+				exit_axis = index;
+			}
+		}
+
+		if exit < entry.max(0.0) {
+			return None;
+		}
+
+		Some(((entry, axis), (exit, exit_axis)))
+	}
+
+	/// Find the closest leaf along a ray, recursively.
+	fn raycast(&self, origin: Vec3, direction: Vec3, inverse_direction: Vec3, frame: &Frame) -> Option<SculptHit> {
+		let (entry, axis) = self.intersect(origin, inverse_direction, frame)?;
 
 		if !self.children.iter().any(|child| child.is_some()) {
+			if self.kind != SculptNodeKind::Leaf {
+				return None;
+			}
+
+			// the ray starts inside of the voxel
+			if entry < 0.0 {
+				return Some(SculptHit {
+					distance: 0.0,
+					position: origin,
+					normal: -direction.normalize_or_zero(),
+					material: self.material,
+					paint: self.paint,
+				});
+			}
+
+			let mut normal = Vec3::ZERO;
+			normal[axis] = -direction[axis].signum();
+
+			return Some(SculptHit {
+				distance: entry,
+				position: origin + direction * entry,
+				normal,
+				material: self.material,
+				paint: self.paint,
+			});
+		}
+
+		let mut children: Vec<(f32, &SculptNode)> = self.children.iter()
+			.flatten()
+			.filter_map(|child| child.intersect(origin, inverse_direction, frame).map(|(entry, _)| (entry, child.as_ref())))
+			.collect();
+		children.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+		children.into_iter().find_map(|(_, child)| child.raycast(origin, direction, inverse_direction, frame))
+	}
+
+	/// Collect every leaf along a ray, recursively.
+	fn raycast_spans(&self, origin: Vec3, direction: Vec3, inverse_direction: Vec3, spans: &mut Vec<SculptSpan>, frame: &Frame) {
+		let Some(((entry, axis), (exit, exit_axis))) = self.intersect_span(origin, inverse_direction, frame) else {
+			return;
+		};
+
+		if self.is_leaf() {
+			let hit = |distance: f32, normal: Vec3| SculptHit {
+				distance,
+				position: origin + direction * distance,
+				normal,
+				material: self.material,
+				paint: self.paint,
+			};
+			let mut normal = Vec3::ZERO;
+			normal[axis] = -direction[axis].signum();
+			let mut exit_normal = Vec3::ZERO;
+			exit_normal[exit_axis] = direction[exit_axis].signum();
+
+			// a ray starting inside of the voxel enters it right away
+			let entry = if entry < 0.0 { hit(0.0, -direction.normalize_or_zero()) } else { hit(entry, normal) };
+			spans.push(SculptSpan {
+				entry,
+				exit: hit(exit, exit_normal),
+			});
+		}
+		for child in self.children.iter().flatten() {
+			child.raycast_spans(origin, direction, inverse_direction, spans, frame);
+		}
+	}
+
+	/// Collect the inconsistencies of the node, recursively, returning the child count it should have.
+	fn validate(&self, materials: u32, is_root: bool, issues: &mut Vec<Issue>, frame: &Frame) -> u32 {
+		let has_children = self.children.iter().any(|child| child.is_some());
+		match self.kind {
+			SculptNodeKind::Leaf if has_children => issues.push(Issue::InconsistentKind(self.center(frame))),
+			SculptNodeKind::None if has_children || !is_root => issues.push(Issue::InconsistentKind(self.center(frame))),
+			SculptNodeKind::Interior if !has_children => issues.push(Issue::EmptyInterior(self.center(frame))),
+			_ => (),
+		}
+		if self.is_leaf() && self.material >= materials {
+			issues.push(Issue::MissingMaterial { center: self.center(frame), material: self.material });
+		}
+
+		let mut expected = 0;
+		for child in self.children.iter().flatten() {
+			expected += if child.kind == SculptNodeKind::Interior { 2 } else { 1 };
+			expected += child.validate(materials, false, issues, frame);
+		}
+		if expected != self.child_count {
+			issues.push(Issue::StaleChildCount { center: self.center(frame), stored: self.child_count, expected });
+		}
+
+		expected
+	}
+
+	/// Fix the inconsistencies of the node, recursively, apart from the child counts.
+	fn repair(&mut self, materials: u32) {
+		for index in 0..self.children.len() {
+			if let Some(ref mut child) = self.children[index] {
+				child.repair(materials);
+				if child.kind == SculptNodeKind::None {
+					self.remove_child(index);
+				}
+			}
+		}
+
+		if self.children.iter().any(|child| child.is_some()) {
+			self.simplify();
+		} else if self.kind == SculptNodeKind::Interior {
 			self.kind = SculptNodeKind::None;
-		} else {
-			self.kind = SculptNodeKind::Interior;
+		}
+
+		if self.is_leaf() && self.material >= materials {
+			self.material = 0;
 		}
 	}
 
@@ -234,6 +1858,45 @@ impl SculptNode {
 		buffer
 	}
 
+	/// Convert a per-node value to a buffer matching the voxel buffer.
+	fn to_channel_buffer<T: Copy + Default>(&self, value: &dyn Fn(&SculptNode) -> T) -> Vec<T> {
+		let mut buffer = vec![value(self), T::default()];
+
+		self.append_channel_to_buffer(&mut buffer, value);
+
+		buffer
+	}
+
+	/// Handle the recursive logic for channel buffers, in the order of `append_to_buffer`.
+	fn append_channel_to_buffer<T: Copy + Default>(&self, buffer: &mut Vec<T>, value: &dyn Fn(&SculptNode) -> T) {
+		for child in self.children.iter().flatten() {
+			if child.kind == SculptNodeKind::Interior {
+				buffer.extend([T::default(), T::default()]);
+			} else {
+				buffer.push(value(child));
+			}
+		}
+
+		for child in self.children.iter().flatten() {
+			child.append_channel_to_buffer(buffer, value);
+		}
+	}
+
+	/// Record an edit of the leaves overlapping a box, recursively.
+	fn mark_edited(&mut self, bounds: &Aabb, stroke: u32, decay: f32, frame: &Frame) {
+		if !self.bounds(frame).intersects(bounds) {
+			return;
+		}
+
+		if self.kind == SculptNodeKind::Leaf && !self.children.iter().any(|child| child.is_some()) {
+			self.heat.touch(stroke, decay);
+		}
+
+		for child in self.children.iter_mut().flatten() {
+			child.mark_edited(bounds, stroke, decay, frame);
+		}
+	}
+
 	/// Convert a node to an integer to send to the GPU.
 	fn to_u32(&self) -> u32 {
 		let mut value = 0u32;
@@ -300,6 +1963,7 @@ impl SculptNode {
 
 /// The `SculptPalette` stores the materials that are used in the current sculpt.
 /// They should be pruned if they are no longer in use.
+#[derive(Clone)]
 struct SculptPalette {
 	materials: Vec<Material>,
 }
@@ -312,26 +1976,44 @@ impl SculptPalette {
 		}
 	}
 
+	/// Get the number of materials.
+	fn len(&self) -> u32 {
+		self.materials.len() as u32
+	}
+
 	/// Get a material reference.
 	fn get(&self, index: u32) -> Option<&Material> {
 		self.materials.get(index as usize)
 	}
 
 	/// Converts the palette materials to a buffer for use on the GPU.
-	fn to_buffer(&self) -> Vec<f32> {
-		let mut buffer = Vec::new();
-
-		for material in &self.materials {
-			buffer.extend(material.to_buffer());
-		}
-
-		buffer
+	fn to_buffer(&self) -> Vec<GpuMaterial> {
+		self.materials.iter().map(|material| material.to_buffer()).collect()
 	}
 
 	/// Pushes a new material onto the palette.
 	fn push(&mut self, value: Material) {
 		self.materials.push(value)
 	}
+
+	/// Replace the materials with one of each color, keeping the default surface attributes.
+	fn set_colors(&mut self, colors: &[[f32; 4]]) {
+		self.materials = colors.iter()
+			.enumerate()
+			.map(|(index, color)| Material {
+				index: index as u32,
+				color: *color,
+				..Material::default()
+			})
+			.collect();
+	}
+
+	/// Set how much light scatters under the surface of each material, by its index.
+	fn set_subsurface(&mut self, strengths: &[f32]) {
+		for (index, material) in self.materials.iter_mut().enumerate() {
+			material.subsurface = strengths.get(index).copied().unwrap_or(0.0);
+		}
+	}
 }
 
 #[cfg(test)]
@@ -340,6 +2022,129 @@ mod tests {
 
     use crate::brush::RoundBrushTip;
 
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    /// Make a node of a sculpt in the unit cube from its size and center.
+    fn node(kind: SculptNodeKind, material: u32, size: f32, center: Vec3) -> SculptNode {
+    	let depth = (1.0 / size).log2().round() as u32;
+    	let cell = (center / size).floor().as_u64vec3();
+
+    	SculptNode::new(kind, material, LocationalCode::from_cell(depth, cell))
+    }
+
+    /// Wrap a shape test to count its calls.
+    fn counted(calls: &Rc<Cell<u32>>, shape: Box<dyn Fn(f32, Vec3) -> bool>) -> Box<dyn Fn(f32, Vec3) -> bool> {
+    	let calls = Rc::clone(calls);
+    	Box::new(move |size, center| {
+    		calls.set(calls.get() + 1);
+    		shape(size, center)
+    	})
+    }
+
+    #[test]
+    fn fills_only_visit_the_nodes_along_their_surface() {
+    	let calls = Rc::new(Cell::new(0));
+    	let counted = |shape| counted(&calls, shape);
+    	let mut sculpt = Sculpt::new(256);
+
+    	// a shape around the whole sculpt fills it with the root alone
+    	let center = vec3(0.5, 0.5, 0.5);
+    	sculpt.subdivide(counted(RoundBrushTip::filler(2.0, center)), counted(RoundBrushTip::container(2.0, center)));
+    	assert_eq!(calls.get(), 1);
+    	assert_eq!(sculpt.root.kind, SculptNodeKind::Leaf);
+
+    	// a stroke in a corner leaves the detail elsewhere alone
+    	let mut sculpt = Sculpt::new(256);
+    	sculpt.subdivide(RoundBrushTip::filler(0.3, center), RoundBrushTip::container(0.3, center));
+    	let before = sculpt.get_voxel_buffer().len();
+    	calls.set(0);
+    	let corner = vec3(0.02, 0.02, 0.02);
+    	sculpt.subdivide(counted(RoundBrushTip::filler(0.01, corner)), counted(RoundBrushTip::container(0.01, corner)));
+    	assert!(calls.get() < 500, "{} shape tests for a stroke of a few voxels", calls.get());
+    	assert!(sculpt.get_voxel_buffer().len() > before);
+    }
+
+    #[test]
+    fn fills_keep_the_leaves_they_cover() {
+    	let corner = vec3(0.25, 0.25, 0.25);
+    	let center = vec3(0.5, 0.5, 0.5);
+    	for (name, fill_center, radius) in [("an octant", corner, 0.45), ("the root", center, 0.9)] {
+    		let mut sculpt = Sculpt::new(32);
+    		sculpt.subdivide_with(&MaterialMix::single(1), RoundBrushTip::filler(0.1, corner), RoundBrushTip::container(0.1, corner));
+    		let volume = sculpt.get_material_volumes()[&1];
+
+    		// a fill covering the ball whole leaves it in its own material
+    		sculpt.subdivide_with(&MaterialMix::single(2), RoundBrushTip::filler(radius, fill_center), RoundBrushTip::container(radius, fill_center));
+    		assert_eq!(sculpt.get_material_volumes()[&1], volume, "covering {name}");
+    		assert!(sculpt.get_material_volumes()[&2] > 0.0, "covering {name}");
+
+    		// and keeps masked leaves apart, even in the same material
+    		let mut sculpt = Sculpt::new(32);
+    		sculpt.subdivide_with(&MaterialMix::single(1), RoundBrushTip::filler(0.1, corner), RoundBrushTip::container(0.1, corner));
+    		let leaves = sculpt.get_leaves().len();
+    		sculpt.set_leaf_masks(&vec![1.0; leaves]);
+    		sculpt.subdivide_with(&MaterialMix::single(1), RoundBrushTip::filler(radius, fill_center), RoundBrushTip::container(radius, fill_center));
+    		assert_eq!(sculpt.get_mask(corner), 1.0, "covering {name}");
+    	}
+    }
+
+    #[test]
+    fn removal_erases_whole_subtrees_and_compacts_what_it_reaches() {
+    	let calls = Rc::new(Cell::new(0));
+    	let counted = |shape| counted(&calls, shape);
+    	let empty = Sculpt::new(256).get_voxel_buffer().len();
+    	let center = vec3(0.5, 0.5, 0.5);
+
+    	// erasing everything drops the children of the root without descending into them
+    	let mut sculpt = Sculpt::new(256);
+    	sculpt.subdivide(RoundBrushTip::filler(0.3, center), RoundBrushTip::container(0.3, center));
+    	sculpt.unsubdivide(counted(RoundBrushTip::filler(2.0, center)), counted(RoundBrushTip::container(2.0, center)));
+    	assert!(calls.get() < 20, "{} shape tests to erase the sculpt", calls.get());
+    	assert_eq!(sculpt.get_voxel_buffer().len(), empty);
+
+    	// empty leftovers where a removal reaches are dropped, and the rest is left alone
+    	let mut sculpt = Sculpt::new(256);
+    	let mut split = sculpt.root.split_child(SculptNodeKind::Interior, 0, &sculpt.frame);
+    	for index in 0..8 {
+    		split.children[index] = Some(Box::new(split.split_child(SculptNodeKind::Leaf, index, &sculpt.frame)));
+    	}
+    	sculpt.root.children[0] = Some(Box::new(split));
+    	sculpt.root.children[7] = Some(Box::new(sculpt.root.split_child(SculptNodeKind::Interior, 7, &sculpt.frame)));
+    	sculpt.root.kind = SculptNodeKind::Interior;
+    	let corner = vec3(0.99, 0.99, 0.99);
+    	sculpt.unsubdivide(RoundBrushTip::filler(0.001, corner), RoundBrushTip::container(0.001, corner));
+    	assert!(sculpt.root.children[7].is_none());
+    	assert_eq!(sculpt.root.children[0].as_ref().unwrap().kind, SculptNodeKind::Interior);
+    	assert!(sculpt.validate().is_empty());
+    }
+
+    #[test]
+    fn removed_nodes_are_reused_by_later_strokes() {
+    	let center = vec3(0.5, 0.5, 0.5);
+    	let mut sculpt = Sculpt::new(64);
+    	sculpt.subdivide(RoundBrushTip::filler(0.3, center), RoundBrushTip::container(0.3, center));
+    	let added = sculpt.get_voxel_buffer();
+    	let free = pool::get_free_count();
+
+    	sculpt.unsubdivide(RoundBrushTip::filler(0.3, center), RoundBrushTip::container(0.3, center));
+    	let removed = pool::get_free_count();
+    	assert!(removed > free);
+    	assert!(removed <= pool::CAPACITY);
+
+    	// the same stroke again builds the same octree out of the freed nodes
+    	sculpt.subdivide(RoundBrushTip::filler(0.3, center), RoundBrushTip::container(0.3, center));
+    	assert!(pool::get_free_count() < removed);
+    	assert_eq!(sculpt.get_voxel_buffer(), added);
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn nodes_keep_no_center_or_size() {
+    	// 128 bytes when nodes stored an f32 center and edge length besides their code
+    	assert_eq!(std::mem::size_of::<SculptNode>(), 112);
+    }
+
     #[test]
     fn subdivide_creates_all_root_children_with_sphere_brush_at_center() {
     	let mut sculpt = Sculpt::new(32);
@@ -354,18 +2159,353 @@ mod tests {
     	assert_eq!(sculpt.root.children.iter().filter(|child| child.is_some()).count(), 8);
     }
 
+    #[test]
+    fn raycast_hits_front_face_of_sphere() {
+    	let mut sculpt = Sculpt::new(32);
+
+    	sculpt.subdivide(RoundBrushTip::filler(0.25, vec3(0.5, 0.5, 0.5)), RoundBrushTip::container(0.25, vec3(0.5, 0.5, 0.5)));
+
+    	let hit = sculpt.raycast(vec3(0.5, 0.5, 0.0), vec3(0.0, 0.0, 1.0)).unwrap();
+
+    	assert!((hit.distance - 0.25).abs() <= 1.0 / 32.0);
+    	assert_eq!(hit.normal, vec3(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn raycast_batches_keep_the_order_of_the_rays() {
+    	let mut sculpt = Sculpt::new(32);
+    	sculpt.subdivide(RoundBrushTip::filler(0.25, vec3(0.5, 0.5, 0.5)), RoundBrushTip::container(0.25, vec3(0.5, 0.5, 0.5)));
+
+    	// enough rays to be spread across threads, every other one missing
+    	let rays: Vec<Ray> = (0..2000)
+    		.map(|index| {
+    			let x = if index % 2 == 0 { 0.3 + index as f32 / 10000.0 } else { 0.95 };
+    			Ray::new(vec3(x, 0.5, 0.0), Vec3::Z)
+    		})
+    		.collect();
+    	let hits = sculpt.raycast_batch(&rays);
+    	assert_eq!(hits.len(), rays.len());
+    	for (ray, hit) in rays.iter().zip(&hits) {
+    		assert_eq!(*hit, sculpt.raycast(ray.origin, ray.direction));
+    	}
+    	assert!(hits[0].is_some());
+    	assert!(hits[1].is_none());
+    	assert!(sculpt.raycast_batch(&[]).is_empty());
+    }
+
+    #[test]
+    fn raycast_spans_cross_thin_shells() {
+    	let mut sculpt = Sculpt::new(32);
+    	let center = vec3(0.5, 0.5, 0.5);
+    	sculpt.subdivide(RoundBrushTip::filler(0.3, center), RoundBrushTip::container(0.3, center));
+    	sculpt.unsubdivide(RoundBrushTip::filler(0.2, center), RoundBrushTip::container(0.2, center));
+
+    	let spans = sculpt.raycast_spans(vec3(0.5, 0.5, 0.0), vec3(0.0, 0.0, 1.0));
+
+    	assert_eq!(spans.len(), 2);
+    	assert!((spans[0].entry.distance - 0.2).abs() <= 1.0 / 32.0);
+    	assert!((spans[1].exit.distance - 0.8).abs() <= 1.0 / 32.0);
+    	assert_eq!(spans[0].exit.normal, vec3(0.0, 0.0, 1.0));
+    	assert_eq!(spans[1].entry.normal, vec3(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn raycast_misses_empty_space() {
+    	let mut sculpt = Sculpt::new(32);
+
+    	sculpt.subdivide(RoundBrushTip::filler(0.25, vec3(0.5, 0.5, 0.5)), RoundBrushTip::container(0.25, vec3(0.5, 0.5, 0.5)));
+
+    	assert!(sculpt.raycast(vec3(0.05, 0.05, 0.0), vec3(0.0, 0.0, 1.0)).is_none());
+    	assert!(Sculpt::new(32).raycast(vec3(0.5, 0.5, 0.0), vec3(0.0, 0.0, 1.0)).is_none());
+    }
+
+    #[test]
+    fn quantize_collapses_subtrees_by_majority() {
+    	let mut sculpt = Sculpt::new(32);
+    	let center = vec3(0.5, 0.5, 0.5);
+    	sculpt.subdivide(RoundBrushTip::filler(0.3, center), RoundBrushTip::container(0.3, center));
+    	let detailed = sculpt.get_voxel_buffer().len();
+
+    	sculpt.quantize(2);
+
+    	assert!(sculpt.get_voxel_buffer().len() < detailed);
+    	assert!(sculpt.get_leaves().iter().all(|(_, size)| *size >= 0.25));
+    	// the middle blocks are mostly inside of the sphere, and the corners mostly outside
+    	assert!(sculpt.is_solid(vec3(0.4, 0.4, 0.4)));
+    	assert!(!sculpt.is_solid(vec3(0.1, 0.1, 0.1)));
+
+    	sculpt.quantize(0);
+    	assert!(sculpt.is_empty());
+    }
+
+    #[test]
+    fn heat_channel_matches_voxel_buffer() {
+    	let mut sculpt = Sculpt::new(16);
+    	sculpt.subdivide(RoundBrushTip::filler(0.25, vec3(0.5, 0.5, 0.5)), RoundBrushTip::container(0.25, vec3(0.5, 0.5, 0.5)));
+    	sculpt.mark_edited(Aabb::cube(vec3(0.5, 0.5, 0.5), 0.1), 1, 0.5);
+    	sculpt.mark_edited(Aabb::cube(vec3(0.5, 0.5, 0.5), 0.1), 2, 0.5);
+
+    	let voxels = sculpt.get_voxel_buffer();
+    	let heat = sculpt.get_channel_buffer(Channel::Heat { stroke: 3, decay: 0.5 });
+
+    	assert_eq!(heat.len(), voxels.len());
+    	// two edits, one and two strokes ago
+    	assert!(heat.contains(&0.75));
+    	assert!(heat.contains(&0.0));
+    }
+
+    #[test]
+    fn removal_keeps_masked_leaves() {
+    	let mut sculpt = Sculpt::new(16);
+    	let center = vec3(0.5, 0.5, 0.5);
+    	sculpt.subdivide(RoundBrushTip::filler(0.3, center), RoundBrushTip::container(0.3, center));
+    	let masks: Vec<f32> = sculpt.get_leaves().iter()
+    		.map(|(leaf, _)| if leaf.x < 0.5 { 1.0 } else { 0.0 })
+    		.collect();
+    	sculpt.set_leaf_masks(&masks);
+
+    	sculpt.unsubdivide(RoundBrushTip::filler(0.4, center), RoundBrushTip::container(0.4, center));
+
+    	assert!(sculpt.is_solid(vec3(0.4, 0.5, 0.5)));
+    	assert!(!sculpt.is_solid(vec3(0.6, 0.5, 0.5)));
+    	assert!(sculpt.get_channel_buffer(Channel::Mask).contains(&1.0));
+
+    	sculpt.clear_mask();
+    	sculpt.unsubdivide(RoundBrushTip::filler(0.4, center), RoundBrushTip::container(0.4, center));
+
+    	assert!(sculpt.is_empty());
+    }
+
+    #[test]
+    fn repair_fixes_what_validate_finds() {
+    	let mut sculpt = Sculpt::new(16);
+    	let center = vec3(0.5, 0.5, 0.5);
+    	sculpt.subdivide(RoundBrushTip::filler(0.3, center), RoundBrushTip::container(0.3, center));
+    	assert_eq!(sculpt.validate(), vec![]);
+
+    	sculpt.root.child_count += 1;
+    	sculpt.root.children[0].as_mut().unwrap().kind = SculptNodeKind::Leaf;
+    	sculpt.root.children[7] = Some(Box::new(node(SculptNodeKind::Interior, 0, 0.5, vec3(0.75, 0.75, 0.75))));
+    	let mut leaf = sculpt.root.children[1].as_mut().unwrap().as_mut();
+    	while !leaf.is_leaf() {
+    		leaf = leaf.children.iter_mut().flatten().next().unwrap();
+    	}
+    	leaf.material = 3;
+    	let leaf_center = leaf.center(&sculpt.frame);
+
+    	let issues = sculpt.repair();
+
+    	assert!(issues.contains(&Issue::InconsistentKind(vec3(0.25, 0.25, 0.25))));
+    	assert!(issues.contains(&Issue::EmptyInterior(vec3(0.75, 0.75, 0.75))));
+    	assert!(issues.contains(&Issue::MissingMaterial { center: leaf_center, material: 3 }));
+    	assert!(issues.iter().any(|issue| matches!(issue, Issue::StaleChildCount { .. })));
+    	assert_eq!(sculpt.validate(), vec![]);
+    	assert!(sculpt.root.children[7].is_none());
+    	assert_eq!(sculpt.repair(), vec![]);
+    }
+
+    #[test]
+    fn voxel_grid_counts_solid_neighbors() {
+    	let mut grid = VoxelGrid::new(IVec3::ZERO, IVec3::splat(3));
+    	grid.set(IVec3::new(0, 0, 0), true);
+    	grid.set(IVec3::new(1, 1, 1), true);
+    	grid.set(IVec3::new(2, 2, 2), true);
+    	grid.set(IVec3::new(5, 5, 5), true);
+
+    	assert_eq!(grid.solid_neighbors(IVec3::new(1, 1, 1)), 2);
+    	assert_eq!(grid.solid_neighbors(IVec3::new(0, 0, 0)), 1);
+    	assert!(!grid.get(IVec3::new(5, 5, 5)));
+    }
+
+    #[test]
+    fn sculpt_voxel_grid_matches_sphere() {
+    	let mut sculpt = Sculpt::new(16);
+    	sculpt.subdivide(RoundBrushTip::filler(0.25, vec3(0.5, 0.5, 0.5)), RoundBrushTip::container(0.25, vec3(0.5, 0.5, 0.5)));
+
+    	let grid = sculpt.get_voxel_grid(IVec3::ZERO, IVec3::splat(16));
+
+    	assert!(grid.get(IVec3::splat(8)));
+    	assert!(!grid.get(IVec3::splat(0)));
+    	assert!(sculpt.is_solid(vec3(0.5, 0.5, 0.5)));
+    	assert!(!sculpt.is_solid(vec3(0.05, 0.05, 0.05)));
+    }
+
+    #[test]
+    fn apply_solid_and_empty_regions() {
+    	let mut sculpt = Sculpt::new(16);
+
+    	sculpt.apply(&|size, center| {
+    		if center.x - size / 2.0 >= 0.5 {
+    			Coverage::Solid
+    		} else if center.x + size / 2.0 <= 0.5 {
+    			Coverage::Empty
+    		} else {
+    			Coverage::Mixed
+    		}
+    	});
+
+    	assert!(sculpt.is_solid(vec3(0.75, 0.25, 0.5)));
+    	assert!(!sculpt.is_solid(vec3(0.25, 0.25, 0.5)));
+    	assert_eq!(sculpt.root.kind, SculptNodeKind::Interior);
+    	assert_eq!(sculpt.root.children.iter().filter(|child| child.is_some()).count(), 4);
+    }
+
+    /// Fill the voxels whose centers are inside of a ball, or outside of it.
+    fn ball(sculpt: &mut Sculpt, center: Vec3, radius: f32, inside: bool) {
+    	let voxel_size = sculpt.get_voxel_size();
+    	sculpt.apply(&|size, node: Vec3| {
+    		// the distances to the nearest and the furthest voxel center of the node
+    		let reach = (size - voxel_size) / 2.0;
+    		let near = ((node - center).abs() - reach).max(Vec3::ZERO).length();
+    		let far = ((node - center).abs() + reach).length();
+    		if far < radius {
+    			if inside { Coverage::Solid } else { Coverage::Empty }
+    		} else if near >= radius {
+    			if inside { Coverage::Empty } else { Coverage::Solid }
+    		} else {
+    			Coverage::Mixed
+    		}
+    	});
+    }
+
+    #[test]
+    fn curvature_matches_spheres_and_boxes() {
+    	let (center, radius) = (vec3(0.5, 0.5, 0.5), 20.0 / 64.0);
+    	let surface = center + vec3(radius, 0.0, 0.0);
+    	let probe = 6.0 / 64.0;
+
+    	let mut sphere = Sculpt::new(64);
+    	ball(&mut sphere, center, radius, true);
+    	let convex = sphere.curvature_at(surface, probe);
+    	assert!((convex * radius - 1.0).abs() < 0.15, "{convex}");
+
+    	let mut hollow = Sculpt::new(64);
+    	ball(&mut hollow, center, radius, false);
+    	let concave = hollow.curvature_at(surface, probe);
+    	assert!((concave * radius + 1.0).abs() < 0.15, "{concave}");
+
+    	// a block covering the cells 16 to 47 along each axis
+    	let mut block = Sculpt::new(64);
+    	block.apply(&|size, node| {
+    		let low = node - size / 2.0;
+    		let high = node + size / 2.0;
+    		if low.cmpge(Vec3::splat(0.25)).all() && high.cmple(Vec3::splat(0.75)).all() {
+    			Coverage::Solid
+    		} else if low.cmpge(Vec3::splat(0.75)).any() || high.cmple(Vec3::splat(0.25)).any() {
+    			Coverage::Empty
+    		} else {
+    			Coverage::Mixed
+    		}
+    	});
+    	assert_eq!(block.curvature_at(vec3(0.5, 0.5, 0.75), probe), 0.0);
+    	let edge = block.curvature_at(vec3(0.5, 0.75, 0.75), probe);
+    	let corner = block.curvature_at(vec3(0.75, 0.75, 0.75), probe);
+    	assert!((edge * probe - 4.0 / 3.0).abs() < 0.1, "{edge}");
+    	assert!(corner > edge);
+    }
+
+    #[test]
+    fn closest_surface_point_from_either_side() {
+    	let (center, radius) = (vec3(0.5, 0.5, 0.5), 20.0 / 64.0);
+    	let voxel = 1.0 / 64.0;
+    	let mut sphere = Sculpt::new(64);
+    	assert_eq!(sphere.closest_surface_point(center), None);
+    	ball(&mut sphere, center, radius, true);
+
+    	for offset in [vec3(0.45, 0.0, 0.0), vec3(0.3, 0.3, -0.2), vec3(0.1, 0.05, 0.0), vec3(0.0, -0.2, 0.1)] {
+    		let point = center + offset;
+    		let (surface, distance) = sphere.closest_surface_point(point).unwrap();
+    		assert!((distance - (offset.length() - radius)).abs() < voxel, "{offset} {distance}");
+    		assert!((surface.distance(center) - radius).abs() < voxel, "{offset} {surface}");
+    		assert!((point.distance(surface) - distance.abs()).abs() < 1e-5);
+    	}
+
+    	// the edge of the sculpt is a surface from the inside
+    	let mut full = Sculpt::new(16);
+    	full.apply(&|_, _| Coverage::Solid);
+    	let (surface, distance) = full.closest_surface_point(vec3(0.5, 0.9, 0.5)).unwrap();
+    	assert_eq!(surface, vec3(0.5, 1.0, 0.5));
+    	assert!((distance + 0.1).abs() < 1e-5);
+    }
+
+    #[test]
+    fn relax_removes_isolated_voxel() {
+    	let mut sculpt = Sculpt::new(16);
+    	sculpt.subdivide(RoundBrushTip::filler(0.25, vec3(0.5, 0.5, 0.5)), RoundBrushTip::container(0.25, vec3(0.5, 0.5, 0.5)));
+
+    	let cell = IVec3::new(2, 2, 2);
+    	sculpt.apply(&|size, center| {
+    		let low = ((center - size / 2.0) * 16.0).round().as_ivec3();
+    		let high = ((center + size / 2.0) * 16.0).round().as_ivec3();
+    		if low == cell && high == cell + 1 {
+    			Coverage::Solid
+    		} else if cell.cmpge(low).all() && cell.cmplt(high).all() {
+    			Coverage::Mixed
+    		} else {
+    			Coverage::Unchanged
+    		}
+    	});
+    	assert!(sculpt.is_solid(vec3(2.5, 2.5, 2.5) / 16.0));
+
+    	sculpt.relax(Vec3::ZERO, Vec3::splat(0.3), 1);
+
+    	assert!(!sculpt.is_solid(vec3(2.5, 2.5, 2.5) / 16.0));
+    	assert!(sculpt.is_solid(vec3(0.5, 0.5, 0.5)));
+    }
+
+    #[test]
+    fn displace_warps_near_the_surface() {
+    	let mut sculpt = Sculpt::new(32);
+    	let center = vec3(0.5, 0.5, 0.5);
+    	sculpt.subdivide(RoundBrushTip::filler(0.3, center), RoundBrushTip::container(0.3, center));
+    	let samples: Vec<Vec3> = (0..512).map(|index| (vec3((index % 8) as f32, (index / 8 % 8) as f32, (index / 64) as f32) + 0.5) / 8.0).collect();
+
+    	// without an amplitude, nothing moves
+    	let mut still = sculpt.clone();
+    	still.displace(0.0, 4.0, 1);
+    	assert!(samples.iter().all(|point| still.is_solid(*point) == sculpt.is_solid(*point)));
+
+    	let mut warped = sculpt.clone();
+    	warped.displace(0.05, 4.0, 1);
+    	assert_eq!(warped.validate(), vec![]);
+    	assert!(samples.iter().any(|point| warped.is_solid(*point) != sculpt.is_solid(*point)));
+    	// the middle and the far corners are out of reach of the warp
+    	assert!(warped.is_solid(center) && !warped.is_solid(vec3(0.05, 0.05, 0.05)));
+
+    	let mut again = sculpt.clone();
+    	again.displace(0.05, 4.0, 1);
+    	assert_eq!(again.get_voxel_buffer(), warped.get_voxel_buffer());
+    	let mut reseeded = sculpt.clone();
+    	reseeded.displace(0.05, 4.0, 2);
+    	assert_ne!(reseeded.get_voxel_buffer(), warped.get_voxel_buffer());
+    }
+
+    #[test]
+    fn sculpt_with_bounds_fits_cube_around_box() {
+    	let mut sculpt = Sculpt::new_with_bounds(16, Aabb::new(vec3(10.0, 0.0, 0.0), vec3(14.0, 4.0, 2.0)));
+
+    	assert_eq!(sculpt.get_bounds(), Aabb::new(vec3(10.0, 0.0, -1.0), vec3(14.0, 4.0, 3.0)));
+    	assert_eq!(sculpt.get_voxel_size(), 0.25);
+
+    	sculpt.subdivide(RoundBrushTip::filler(1.0, vec3(12.0, 2.0, 1.0)), RoundBrushTip::container(1.0, vec3(12.0, 2.0, 1.0)));
+
+    	assert!(sculpt.is_solid(vec3(12.0, 2.0, 1.0)));
+    	assert!(!sculpt.is_solid(vec3(10.5, 0.5, 1.0)));
+    	assert!(sculpt.raycast(vec3(12.1, 2.1, -5.0), vec3(0.0, 0.0, 1.0)).is_some());
+    }
+
     #[test]
     fn simple_sculpt_node_generates_correct_buffer() {
-		let mut sculpt_node = SculptNode::new(SculptNodeKind::Interior, 1, 1.0, vec3(0.5, 0.5, 0.5));
+		let mut sculpt_node = node(SculptNodeKind::Interior, 1, 1.0, vec3(0.5, 0.5, 0.5));
 		sculpt_node.children = [
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.25, 0.25)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.25, 0.25)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.75, 0.25)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.75, 0.25)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.25, 0.75)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.25, 0.75)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.75, 0.75)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.75, 0.75)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.25, 0.25)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.25, 0.25)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.75, 0.25)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.75, 0.25)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.25, 0.75)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.25, 0.75)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.75, 0.75)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.75, 0.75)))),
 		];
 
 		let expected = vec![
@@ -387,16 +2527,16 @@ mod tests {
 
     #[test]
     fn sculpt_nodes_with_different_materials_generate_correct_buffer() {
-		let mut sculpt_node = SculptNode::new(SculptNodeKind::Interior, 1, 1.0, vec3(0.5, 0.5, 0.5));
+		let mut sculpt_node = node(SculptNodeKind::Interior, 1, 1.0, vec3(0.5, 0.5, 0.5));
 		sculpt_node.children = [
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 2, 0.5, vec3(0.25, 0.25, 0.25)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 3, 0.5, vec3(0.75, 0.25, 0.25)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 4, 0.5, vec3(0.25, 0.75, 0.25)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 5, 0.5, vec3(0.75, 0.75, 0.25)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 6, 0.5, vec3(0.25, 0.25, 0.75)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 7, 0.5, vec3(0.75, 0.25, 0.75)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 8, 0.5, vec3(0.25, 0.75, 0.75)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 9, 0.5, vec3(0.75, 0.75, 0.75)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 2, 0.5, vec3(0.25, 0.25, 0.25)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 3, 0.5, vec3(0.75, 0.25, 0.25)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 4, 0.5, vec3(0.25, 0.75, 0.25)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 5, 0.5, vec3(0.75, 0.75, 0.25)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 6, 0.5, vec3(0.25, 0.25, 0.75)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 7, 0.5, vec3(0.75, 0.25, 0.75)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 8, 0.5, vec3(0.25, 0.75, 0.75)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 9, 0.5, vec3(0.75, 0.75, 0.75)))),
 		];
 
 		let expected = vec![
@@ -418,16 +2558,16 @@ mod tests {
 
     #[test]
     fn simple_sculpt_node_missing_children_generates_correct_buffer() {
-		let mut sculpt_node = SculptNode::new(SculptNodeKind::Interior, 1, 1.0, vec3(0.5, 0.5, 0.5));
+		let mut sculpt_node = node(SculptNodeKind::Interior, 1, 1.0, vec3(0.5, 0.5, 0.5));
 		sculpt_node.children = [
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.25, 0.25)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.25, 0.25)))),
 			None,
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.75, 0.25)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.75, 0.25)))),
 			None,
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.25, 0.75)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.25, 0.75)))),
 			None,
 			None,
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.75, 0.75)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.75, 0.75)))),
 		];
 
 		let expected = vec![
@@ -445,29 +2585,29 @@ mod tests {
 
     #[test]
     fn simple_nested_sculpt_node_generates_correct_buffer() {
-		let mut sculpt_node = SculptNode::new(SculptNodeKind::Interior, 1, 1.0, vec3(0.5, 0.5, 0.5));
+		let mut sculpt_node = node(SculptNodeKind::Interior, 1, 1.0, vec3(0.5, 0.5, 0.5));
 
-		let mut sculpt_node_child_lfb = SculptNode::new(SculptNodeKind::Interior, 1, 0.5, vec3(0.25, 0.25, 0.25));
+		let mut sculpt_node_child_lfb = node(SculptNodeKind::Interior, 1, 0.5, vec3(0.25, 0.25, 0.25));
 		sculpt_node_child_lfb.children = [
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.125, 0.125, 0.125)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.375, 0.125, 0.125)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.125, 0.375, 0.125)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.375, 0.375, 0.125)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.125, 0.125, 0.375)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.375, 0.125, 0.375)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.125, 0.375, 0.375)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.375, 0.375, 0.375)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.125, 0.125, 0.125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.375, 0.125, 0.125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.125, 0.375, 0.125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.375, 0.375, 0.125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.125, 0.125, 0.375)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.375, 0.125, 0.375)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.125, 0.375, 0.375)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.375, 0.375, 0.375)))),
 		];
 
 		sculpt_node.children = [
 			Some(Box::new(sculpt_node_child_lfb)),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.25, 0.25)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.75, 0.25)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.75, 0.25)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.25, 0.75)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.25, 0.75)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.75, 0.75)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.75, 0.75)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.25, 0.25)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.75, 0.25)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.75, 0.25)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.25, 0.75)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.25, 0.75)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.75, 0.75)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.75, 0.75)))),
 		];
 
 		let expected = vec![
@@ -498,41 +2638,41 @@ mod tests {
     }
 
     fn multiple_nested_sculpt_node_generates_correct_buffer() {
-		let mut sculpt_node = SculptNode::new(SculptNodeKind::Interior, 1, 1.0, vec3(0.5, 0.5, 0.5));
+		let mut sculpt_node = node(SculptNodeKind::Interior, 1, 1.0, vec3(0.5, 0.5, 0.5));
 
-		let mut sculpt_node_child_lfb = SculptNode::new(SculptNodeKind::Interior, 1, 0.5, vec3(0.25, 0.25, 0.25));
+		let mut sculpt_node_child_lfb = node(SculptNodeKind::Interior, 1, 0.5, vec3(0.25, 0.25, 0.25));
 		sculpt_node_child_lfb.children = [
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.125, 0.125, 0.125)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.375, 0.125, 0.125)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.125, 0.375, 0.125)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.375, 0.375, 0.125)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.125, 0.125, 0.375)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.375, 0.125, 0.375)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.125, 0.375, 0.375)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.375, 0.375, 0.375)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.125, 0.125, 0.125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.375, 0.125, 0.125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.125, 0.375, 0.125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.375, 0.375, 0.125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.125, 0.125, 0.375)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.375, 0.125, 0.375)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.125, 0.375, 0.375)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.375, 0.375, 0.375)))),
 		];
 
-		let mut sculpt_node_child_rfb = SculptNode::new(SculptNodeKind::Interior, 1, 0.5, vec3(0.75, 0.25, 0.25));
+		let mut sculpt_node_child_rfb = node(SculptNodeKind::Interior, 1, 0.5, vec3(0.75, 0.25, 0.25));
 		sculpt_node_child_rfb.children = [
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.625, 0.125, 0.125)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.875, 0.125, 0.125)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.625, 0.375, 0.125)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.875, 0.375, 0.125)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.625, 0.125, 0.375)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.875, 0.125, 0.375)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.625, 0.375, 0.375)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.875, 0.375, 0.375)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.625, 0.125, 0.125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.875, 0.125, 0.125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.625, 0.375, 0.125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.875, 0.375, 0.125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.625, 0.125, 0.375)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.875, 0.125, 0.375)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.625, 0.375, 0.375)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.875, 0.375, 0.375)))),
 		];
 
 		sculpt_node.children = [
 			Some(Box::new(sculpt_node_child_lfb)),
 			Some(Box::new(sculpt_node_child_rfb)),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.75, 0.25)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.75, 0.25)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.25, 0.75)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.25, 0.75)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.75, 0.75)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.75, 0.75)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.75, 0.25)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.75, 0.25)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.25, 0.75)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.25, 0.75)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.75, 0.75)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.75, 0.75)))),
 		];
 
 		let expected = vec![
@@ -573,53 +2713,53 @@ mod tests {
     }
 
     fn deeply_nested_sculpt_node_generates_correct_buffer() {
-		let mut sculpt_node = SculptNode::new(SculptNodeKind::Interior, 1, 1.0, vec3(0.5, 0.5, 0.5));
+		let mut sculpt_node = node(SculptNodeKind::Interior, 1, 1.0, vec3(0.5, 0.5, 0.5));
 
-		let mut sculpt_node_child_lfb = SculptNode::new(SculptNodeKind::Interior, 1, 0.5, vec3(0.25, 0.25, 0.25));
+		let mut sculpt_node_child_lfb = node(SculptNodeKind::Interior, 1, 0.5, vec3(0.25, 0.25, 0.25));
 		sculpt_node_child_lfb.children = [
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.125, 0.125, 0.125)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.375, 0.125, 0.125)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.125, 0.375, 0.125)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.375, 0.375, 0.125)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.125, 0.125, 0.375)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.375, 0.125, 0.375)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.125, 0.375, 0.375)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.375, 0.375, 0.375)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.125, 0.125, 0.125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.375, 0.125, 0.125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.125, 0.375, 0.125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.375, 0.375, 0.125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.125, 0.125, 0.375)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.375, 0.125, 0.375)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.125, 0.375, 0.375)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.375, 0.375, 0.375)))),
 		];
 
-		let mut sculpt_node_nested_lfb = SculptNode::new(SculptNodeKind::Interior, 1, 0.25, vec3(0.625, 0.125, 0.125));
+		let mut sculpt_node_nested_lfb = node(SculptNodeKind::Interior, 1, 0.25, vec3(0.625, 0.125, 0.125));
 		sculpt_node_nested_lfb.children = [
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.125, vec3(0.5625, 0.0625, 0.0625)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.125, vec3(0.6875, 0.0625, 0.0625)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.125, vec3(0.5625, 0.1875, 0.0625)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.125, vec3(0.6875, 0.1875, 0.0625)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.125, vec3(0.5625, 0.0625, 0.1875)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.125, vec3(0.6875, 0.0625, 0.1875)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.125, vec3(0.5625, 0.1875, 0.1875)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.125, vec3(0.6875, 0.1875, 0.1875)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.125, vec3(0.5625, 0.0625, 0.0625)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.125, vec3(0.6875, 0.0625, 0.0625)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.125, vec3(0.5625, 0.1875, 0.0625)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.125, vec3(0.6875, 0.1875, 0.0625)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.125, vec3(0.5625, 0.0625, 0.1875)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.125, vec3(0.6875, 0.0625, 0.1875)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.125, vec3(0.5625, 0.1875, 0.1875)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.125, vec3(0.6875, 0.1875, 0.1875)))),
 		];
 
-		let mut sculpt_node_child_rfb = SculptNode::new(SculptNodeKind::Interior, 1, 0.5, vec3(0.75, 0.25, 0.25));
+		let mut sculpt_node_child_rfb = node(SculptNodeKind::Interior, 1, 0.5, vec3(0.75, 0.25, 0.25));
 		sculpt_node_child_rfb.children = [
 			Some(Box::new(sculpt_node_nested_lfb)),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.875, 0.125, 0.125)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.625, 0.375, 0.125)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.875, 0.375, 0.125)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.625, 0.125, 0.375)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.875, 0.125, 0.375)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.625, 0.375, 0.375)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.875, 0.375, 0.375)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.875, 0.125, 0.125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.625, 0.375, 0.125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.875, 0.375, 0.125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.625, 0.125, 0.375)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.875, 0.125, 0.375)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.625, 0.375, 0.375)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.875, 0.375, 0.375)))),
 		];
 
 		sculpt_node.children = [
 			Some(Box::new(sculpt_node_child_lfb)),
 			Some(Box::new(sculpt_node_child_rfb)),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.75, 0.25)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.75, 0.25)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.25, 0.75)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.25, 0.75)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.75, 0.75)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.75, 0.75)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.75, 0.25)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.75, 0.25)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.25, 0.75)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.25, 0.75)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.75, 0.75)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.75, 0.75)))),
 		];
 
 		let expected = vec![
@@ -670,65 +2810,65 @@ mod tests {
     }
 
     fn complex_sculpt_node_generates_correct_buffer() {
-		let mut sculpt_node = SculptNode::new(SculptNodeKind::Interior, 1, 1.0, vec3(0.5, 0.5, 0.5));
+		let mut sculpt_node = node(SculptNodeKind::Interior, 1, 1.0, vec3(0.5, 0.5, 0.5));
 
-		let mut sculpt_node_child_lfb = SculptNode::new(SculptNodeKind::Interior, 1, 0.5, vec3(0.25, 0.25, 0.25));
+		let mut sculpt_node_child_lfb = node(SculptNodeKind::Interior, 1, 0.5, vec3(0.25, 0.25, 0.25));
 		sculpt_node_child_lfb.children = [
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.125, 0.125, 0.125)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.375, 0.125, 0.125)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.125, 0.375, 0.125)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.375, 0.375, 0.125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.125, 0.125, 0.125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.375, 0.125, 0.125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.125, 0.375, 0.125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.375, 0.375, 0.125)))),
 			None,
 			None,
 			None,
 			None,
 		];
 
-		let mut sculpt_node_deeply_nested_lbb = SculptNode::new(SculptNodeKind::Interior, 1, 0.125, vec3(0.5625, 0.1875, 0.0625));
+		let mut sculpt_node_deeply_nested_lbb = node(SculptNodeKind::Interior, 1, 0.125, vec3(0.5625, 0.1875, 0.0625));
 		sculpt_node_deeply_nested_lbb.children = [
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.0625, vec3(0.53125, 0.15625, 0.03125)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.0625, vec3(0.59375, 0.15625, 0.03125)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.0625, vec3(0.53125, 0.21875, 0.03125)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.0625, vec3(0.59375, 0.21875, 0.03125)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.0625, vec3(0.53125, 0.15625, 0.09375)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.0625, vec3(0.59375, 0.15625, 0.09375)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.0625, vec3(0.53125, 0.21875, 0.09375)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.0625, vec3(0.59375, 0.21875, 0.09375)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.0625, vec3(0.53125, 0.15625, 0.03125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.0625, vec3(0.59375, 0.15625, 0.03125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.0625, vec3(0.53125, 0.21875, 0.03125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.0625, vec3(0.59375, 0.21875, 0.03125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.0625, vec3(0.53125, 0.15625, 0.09375)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.0625, vec3(0.59375, 0.15625, 0.09375)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.0625, vec3(0.53125, 0.21875, 0.09375)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.0625, vec3(0.59375, 0.21875, 0.09375)))),
 		];
 
-		let mut sculpt_node_nested_lfb = SculptNode::new(SculptNodeKind::Interior, 1, 0.25, vec3(0.625, 0.125, 0.125));
+		let mut sculpt_node_nested_lfb = node(SculptNodeKind::Interior, 1, 0.25, vec3(0.625, 0.125, 0.125));
 		sculpt_node_nested_lfb.children = [
 			None,
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.125, vec3(0.6875, 0.0625, 0.0625)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.125, vec3(0.6875, 0.0625, 0.0625)))),
 			Some(Box::new(sculpt_node_deeply_nested_lbb)),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.125, vec3(0.6875, 0.1875, 0.0625)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.125, vec3(0.5625, 0.0625, 0.1875)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.125, vec3(0.6875, 0.0625, 0.1875)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.125, vec3(0.6875, 0.1875, 0.0625)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.125, vec3(0.5625, 0.0625, 0.1875)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.125, vec3(0.6875, 0.0625, 0.1875)))),
 			None,
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.125, vec3(0.6875, 0.1875, 0.1875)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.125, vec3(0.6875, 0.1875, 0.1875)))),
 		];
 
-		let mut sculpt_node_child_rfb = SculptNode::new(SculptNodeKind::Interior, 1, 0.5, vec3(0.75, 0.25, 0.25));
+		let mut sculpt_node_child_rfb = node(SculptNodeKind::Interior, 1, 0.5, vec3(0.75, 0.25, 0.25));
 		sculpt_node_child_rfb.children = [
 			Some(Box::new(sculpt_node_nested_lfb)),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.875, 0.125, 0.125)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.625, 0.375, 0.125)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.875, 0.375, 0.125)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.625, 0.125, 0.375)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.875, 0.125, 0.375)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.875, 0.125, 0.125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.625, 0.375, 0.125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.875, 0.375, 0.125)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.625, 0.125, 0.375)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.875, 0.125, 0.375)))),
 			None,
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.25, vec3(0.875, 0.375, 0.375)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.25, vec3(0.875, 0.375, 0.375)))),
 		];
 
 		sculpt_node.children = [
 			Some(Box::new(sculpt_node_child_lfb)),
 			Some(Box::new(sculpt_node_child_rfb)),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.75, 0.25)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.75, 0.25)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.25, 0.75)))),
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.25, 0.75)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.75, 0.25)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.75, 0.25)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.25, 0.25, 0.75)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.25, 0.75)))),
 			None,
-			Some(Box::new(SculptNode::new(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.75, 0.75)))),
+			Some(Box::new(node(SculptNodeKind::Leaf, 1, 0.5, vec3(0.75, 0.75, 0.75)))),
 		];
 
 		let expected = vec![
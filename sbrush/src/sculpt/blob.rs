@@ -0,0 +1,189 @@
+//! The voxel buffer of a sculpt as a file, for game engines to load.
+//!
+//! A blob holds the exact u32 stream the renderer uploads, so an engine
+//! can traverse it with the same code as `ray_marching.wgsl`: the root
+//! takes two entries, its masks and a pointer to its children, and each
+//! child takes one entry if it is a leaf, its material, or two if it is
+//! interior, its masks and the pointer to its own children. The masks
+//! hold which children exist in bits 8 to 15 and which of them are
+//! leaves in bits 0 to 7.
+//!
+//! After the magic number and version, the header holds the resolution,
+//! the low corner and edge length of the root, and the palette colors,
+//! all little endian, followed by the number of entries and the entries.
+
+use super::{pool, LocationalCode, Sculpt, SculptNode, SculptNodeKind};
+use crate::aabb::Aabb;
+use crate::util::bytes::{invalid, take};
+use crate::util::migrations::Migrations;
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use glam::Vec3;
+
+/// The versions of the blob format.
+fn migrations() -> Migrations {
+	Migrations::new(*b"SWGB")
+}
+
+impl Sculpt {
+	/// Write the voxel buffer with its resolution, placement and palette to a file.
+	///
+	/// Paint, masks and edit heat aren't part of the voxel buffer, so they are left out.
+	pub fn export_gpu_blob(&self, path: impl AsRef<Path>) -> io::Result<()> {
+		fs::write(path, self.to_gpu_blob())
+	}
+
+	/// Read a sculpt from a file written by `export_gpu_blob`, or by an engine in the same format.
+	pub fn import_gpu_blob(path: impl AsRef<Path>) -> io::Result<Self> {
+		Self::from_gpu_blob(&fs::read(path)?)
+	}
+
+	/// Get the voxel buffer with its header, as written by `export_gpu_blob`.
+	pub fn to_gpu_blob(&self) -> Vec<u8> {
+		let bounds = self.get_bounds();
+		let colors: Vec<[f32; 4]> = self.palette.materials.iter().map(|material| material.color).collect();
+		let voxels = self.get_voxel_buffer();
+
+		let mut contents = Vec::new();
+		contents.extend_from_slice(&self.resolution.to_le_bytes());
+		for component in bounds.min.to_array() {
+			contents.extend_from_slice(&component.to_le_bytes());
+		}
+		contents.extend_from_slice(&self.get_size().to_le_bytes());
+		contents.extend_from_slice(&(colors.len() as u32).to_le_bytes());
+		for component in colors.iter().flatten() {
+			contents.extend_from_slice(&component.to_le_bytes());
+		}
+		contents.extend_from_slice(&(voxels.len() as u32).to_le_bytes());
+		for entry in voxels {
+			contents.extend_from_slice(&entry.to_le_bytes());
+		}
+
+		migrations().write(&contents)
+	}
+
+	/// Read a sculpt from a voxel buffer with its header.
+	///
+	/// Inconsistencies in the buffer are fixed, like leaves with materials missing from the palette.
+	pub fn from_gpu_blob(bytes: &[u8]) -> io::Result<Self> {
+		let contents = migrations().read(bytes)?;
+		let mut rest = contents.as_slice();
+
+		let resolution = u32::from_le_bytes(take(&mut rest)?);
+		let min = Vec3::from_array([take(&mut rest)?, take(&mut rest)?, take(&mut rest)?].map(f32::from_le_bytes));
+		let size = f32::from_le_bytes(take(&mut rest)?);
+		if resolution == 0 || !min.is_finite() || !size.is_finite() || size <= 0.0 {
+			return Err(invalid("the blob has no valid resolution or placement"));
+		}
+
+		let count = u32::from_le_bytes(take(&mut rest)?);
+		let mut colors = Vec::new();
+		for _ in 0..count {
+			let color: [[u8; 4]; 4] = [take(&mut rest)?, take(&mut rest)?, take(&mut rest)?, take(&mut rest)?];
+			colors.push(color.map(f32::from_le_bytes));
+		}
+
+		let length = u32::from_le_bytes(take(&mut rest)?) as usize;
+		if rest.len() != length * 4 || length < 2 {
+			return Err(invalid("the voxel buffer of the blob doesn't match its length"));
+		}
+		let voxels: Vec<u32> = rest.chunks_exact(4).map(|entry| u32::from_le_bytes(entry.try_into().unwrap())).collect();
+
+		let mut sculpt = Self::new_with_bounds(resolution, Aabb::new(min, min + size));
+		if !colors.is_empty() {
+			sculpt.set_palette(&colors);
+		}
+		if voxels[0] >> 8 & 255 != 0 {
+			sculpt.root = SculptNode::read_entry(&voxels, 0, LocationalCode::ROOT, sculpt.frame.get_depth())?;
+		}
+		sculpt.root.set_child_count();
+		sculpt.repair();
+
+		Ok(sculpt)
+	}
+}
+
+impl SculptNode {
+	/// Read an interior node and its children from the voxel buffer, where its masks are at an entry.
+	fn read_entry(voxels: &[u32], entry: usize, code: LocationalCode, depth: u32) -> io::Result<Self> {
+		if code.get_depth() >= depth {
+			return Err(invalid("the voxel buffer of the blob goes deeper than its resolution"));
+		}
+		let (Some(masks), Some(pointer)) = (voxels.get(entry), voxels.get(entry + 1)) else {
+			return Err(invalid("the voxel buffer of the blob points past its end"));
+		};
+		let children = masks >> 8 & 255;
+		let leaves = masks & 255;
+
+		let mut node = SculptNode::new(SculptNodeKind::Interior, 0, code);
+		let mut child_entry = *pointer as usize;
+		for index in (0..8).filter(|index| children >> index & 1 == 1) {
+			let child_code = code.child(index);
+			if leaves >> index & 1 == 1 {
+				let material = *voxels.get(child_entry).ok_or_else(|| invalid("the voxel buffer of the blob points past its end"))?;
+				node.children[index] = Some(pool::allocate(SculptNode::new(SculptNodeKind::Leaf, material, child_code)));
+				child_entry += 1;
+			} else {
+				let child = Self::read_entry(voxels, child_entry, child_code, depth)?;
+				node.children[index] = Some(pool::allocate(child));
+				child_entry += 2;
+			}
+		}
+
+		Ok(node)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    use crate::brush::RoundBrushTip;
+    use crate::material::MaterialMix;
+
+    use glam::vec3;
+
+    #[test]
+    fn blobs_round_trip() {
+    	let bounds = Aabb::cube(vec3(0.5, 1.5, 0.5), 1.0);
+    	let mut sculpt = Sculpt::new_with_bounds(32, bounds);
+    	sculpt.set_palette(&[[1.0, 0.0, 0.0, 1.0], [0.0, 0.0, 1.0, 1.0]]);
+    	let center = vec3(0.4, 1.5, 0.6);
+    	sculpt.subdivide(RoundBrushTip::filler(0.3, center), RoundBrushTip::container(0.3, center));
+
+    	let path = std::env::temp_dir().join(format!("swirlix-blob-{}.swgb", std::process::id()));
+    	sculpt.export_gpu_blob(&path).unwrap();
+    	let imported = Sculpt::import_gpu_blob(&path).unwrap();
+    	fs::remove_file(&path).unwrap();
+
+    	assert_eq!(imported.get_voxel_buffer(), sculpt.get_voxel_buffer());
+    	assert_eq!(imported.get_bounds(), sculpt.get_bounds());
+    	assert_eq!(imported.get_material(1).map(|material| material.color), Some([0.0, 0.0, 1.0, 1.0]));
+    	assert_eq!(imported.validate(), vec![]);
+
+    	// the voxel buffer follows the header unchanged, for engines to upload as is
+    	let blob = sculpt.to_gpu_blob();
+    	let voxels: Vec<u8> = sculpt.get_voxel_buffer().iter().flat_map(|entry| entry.to_le_bytes()).collect();
+    	assert!(blob.ends_with(&voxels));
+
+    	assert!(Sculpt::from_gpu_blob(&blob[..blob.len() - 4]).is_err());
+    	let empty = Sculpt::from_gpu_blob(&Sculpt::new(8).to_gpu_blob()).unwrap();
+    	assert_eq!(empty.get_voxel_buffer(), Sculpt::new(8).get_voxel_buffer());
+    }
+
+    #[test]
+    fn damaged_blobs_are_repaired() {
+    	let mut sculpt = Sculpt::new(16);
+    	let center = vec3(0.5, 0.5, 0.5);
+    	sculpt.subdivide_with(&MaterialMix::single(2), RoundBrushTip::filler(0.3, center), RoundBrushTip::container(0.3, center));
+    	assert!(!sculpt.validate().is_empty());
+
+    	let imported = Sculpt::from_gpu_blob(&sculpt.to_gpu_blob()).unwrap();
+
+    	assert_eq!(imported.validate(), vec![]);
+    	assert!(imported.is_solid(center));
+    }
+}
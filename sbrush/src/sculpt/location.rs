@@ -0,0 +1,189 @@
+//! Integer addresses of the nodes of an octree.
+//!
+//! A node is addressed by its locational code: a leading 1 bit, then the
+//! index of the child taken at each level down from the root, three bits
+//! each. The f32 centers of nodes stop telling children apart some 20
+//! levels down, while codes stay exact down to the deepest level they
+//! fit. Positions are worked out from a code in f64, within the frame of
+//! the root, and only handed out as f32.
+
+use crate::aabb::Aabb;
+
+use glam::{DVec3, U64Vec3, Vec3};
+
+/// The address of a node, as the path of child indices leading to it from the root.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LocationalCode(u128);
+
+impl LocationalCode {
+	/// The code of the root.
+	pub const ROOT: Self = Self(1);
+	/// The deepest level a code fits.
+	pub const MAX_DEPTH: u32 = 42;
+
+	/// Get the code of a child, where bit 0 of the index picks the high x half, bit 1 y, and bit 2 z.
+	pub fn child(self, index: usize) -> Self {
+		debug_assert!(self.get_depth() < Self::MAX_DEPTH && index < 8);
+
+		Self(self.0 << 3 | index as u128)
+	}
+
+	/// Get the code of the parent, unless this is the root.
+	pub fn parent(self) -> Option<Self> {
+		(self != Self::ROOT).then_some(Self(self.0 >> 3))
+	}
+
+	/// Get how many levels down from the root the node is.
+	pub fn get_depth(self) -> u32 {
+		(127 - self.0.leading_zeros()) / 3
+	}
+
+	/// Get the index of the child taken at a level, on the way down from the root.
+	pub fn get_index_at(self, depth: u32) -> usize {
+		debug_assert!(depth < self.get_depth());
+
+		(self.0 >> (3 * (self.get_depth() - 1 - depth)) & 7) as usize
+	}
+
+	/// Get the cell of the node, counted in nodes of its depth from the low corner of the root.
+	pub fn get_cell(self) -> U64Vec3 {
+		// nodes are placed by their codes during every traversal, so the bits of each axis are gathered all at once
+		let path = self.0 ^ 1 << (3 * self.get_depth());
+		let axis = |shift: u32| {
+			let bits = path >> shift;
+			compact(bits as u64) | compact((bits >> 63) as u64) << 21
+		};
+
+		U64Vec3::new(axis(0), axis(1), axis(2))
+	}
+
+	/// Get the code of a cell at a depth.
+	pub fn from_cell(depth: u32, cell: U64Vec3) -> Self {
+		debug_assert!(depth <= Self::MAX_DEPTH && cell.max_element() >> depth == 0);
+
+		let interleave = |cell: U64Vec3| (spread(cell.x) | spread(cell.y) << 1 | spread(cell.z) << 2) as u128;
+
+		Self(1 << (3 * depth) | interleave(cell >> 21) << 63 | interleave(cell & U64Vec3::splat(0x1f_ffff)))
+	}
+}
+
+/// Gather every third bit of a word, from bit 0 up, into its low 21 bits.
+fn compact(bits: u64) -> u64 {
+	let mut bits = bits & 0x1249_2492_4924_9249;
+	bits = (bits | bits >> 2) & 0x10c3_0c30_c30c_30c3;
+	bits = (bits | bits >> 4) & 0x100f_00f0_0f00_f00f;
+	bits = (bits | bits >> 8) & 0x001f_0000_ff00_00ff;
+	bits = (bits | bits >> 16) & 0x001f_0000_0000_ffff;
+	(bits | bits >> 32) & 0x1f_ffff
+}
+
+/// Spread the low 21 bits of a word out to every third bit, undoing `compact`.
+fn spread(bits: u64) -> u64 {
+	let mut bits = bits & 0x1f_ffff;
+	bits = (bits | bits << 32) & 0x001f_0000_0000_ffff;
+	bits = (bits | bits << 16) & 0x001f_0000_ff00_00ff;
+	bits = (bits | bits << 8) & 0x100f_00f0_0f00_f00f;
+	bits = (bits | bits << 4) & 0x10c3_0c30_c30c_30c3;
+	(bits | bits << 2) & 0x1249_2492_4924_9249
+}
+
+/// Where the root of an octree is, and how deep its finest voxels are.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frame {
+	/// The low corner of the root.
+	origin: DVec3,
+	/// The edge length of the root.
+	size: f64,
+	depth: u32,
+}
+
+impl Frame {
+	/// Fit a cube around a box, split down to a resolution along each edge.
+	pub fn new(bounds: Aabb, resolution: u32) -> Self {
+		let size = bounds.extent().max_element() as f64;
+
+		Self {
+			origin: bounds.center().as_dvec3() - size / 2.0,
+			size,
+			depth: resolution.max(1).next_power_of_two().trailing_zeros().min(LocationalCode::MAX_DEPTH),
+		}
+	}
+
+	/// Get the depth of the finest voxels.
+	pub fn get_depth(&self) -> u32 {
+		self.depth
+	}
+
+	/// Get the edge length of the finest voxels.
+	pub fn get_voxel_size(&self) -> f32 {
+		(self.size / (1u64 << self.depth) as f64) as f32
+	}
+
+	/// Get the edge length of a node.
+	pub fn size(&self, code: LocationalCode) -> f32 {
+		(self.size / (1u64 << code.get_depth()) as f64) as f32
+	}
+
+	/// Get the center of a node.
+	pub fn center(&self, code: LocationalCode) -> Vec3 {
+		let size = self.size / (1u64 << code.get_depth()) as f64;
+
+		(self.origin + (code.get_cell().as_dvec3() + 0.5) * size).as_vec3()
+	}
+
+	/// Get the code of the finest voxel containing a point, if it is inside of the root.
+	///
+	/// Points on the high faces of the root are in the voxels along them.
+	pub fn code_at(&self, point: Vec3) -> Option<LocationalCode> {
+		let cells = (1u64 << self.depth) as f64;
+		let cell = (point.as_dvec3() - self.origin) / self.size * cells;
+		if cell.cmplt(DVec3::ZERO).any() || cell.cmpgt(DVec3::splat(cells)).any() {
+			return None;
+		}
+
+		Some(LocationalCode::from_cell(self.depth, cell.floor().as_u64vec3().min(U64Vec3::splat((1u64 << self.depth) - 1))))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    use glam::vec3;
+
+    #[test]
+    fn codes_round_trip_through_cells() {
+    	let code = LocationalCode::ROOT.child(3).child(4).child(6);
+    	assert_eq!(code.get_depth(), 3);
+    	assert_eq!(code.get_cell(), U64Vec3::new(0b100, 0b101, 0b011));
+    	assert_eq!(LocationalCode::from_cell(3, code.get_cell()), code);
+    	assert_eq!(code.parent().and_then(LocationalCode::parent), Some(LocationalCode::ROOT.child(3)));
+    	assert_eq!(LocationalCode::ROOT.parent(), None);
+
+    	let deepest = LocationalCode::from_cell(LocationalCode::MAX_DEPTH, U64Vec3::splat((1 << LocationalCode::MAX_DEPTH) - 1));
+    	assert_eq!(deepest.get_depth(), LocationalCode::MAX_DEPTH);
+    	assert_eq!(deepest.get_index_at(0), 7);
+
+    	let cell = U64Vec3::new(0x2a5_5aa5_a55a, 0x155_a55a_5aa5, 0x3ff_0000_ffff);
+    	let code = LocationalCode::from_cell(LocationalCode::MAX_DEPTH, cell);
+    	assert_eq!(code.get_cell(), cell);
+    	assert_eq!((code.get_index_at(0), code.get_index_at(41)), (5, 6));
+    }
+
+    #[test]
+    fn deep_children_stay_apart() {
+    	let frame = Frame::new(Aabb::cube(vec3(1000.0, 0.0, 0.0), 2.0), 1 << 30);
+    	let point = vec3(1000.3, 0.2, -0.4);
+    	let code = frame.code_at(point).unwrap();
+    	assert_eq!(code.get_depth(), 30);
+    	assert!(frame.center(code).distance(point) < 1e-4);
+
+    	// the deepest siblings have distinct codes, even where f32 centers can't tell them apart
+    	let parent = code.parent().unwrap();
+    	assert_ne!(parent.child(0), parent.child(1));
+    	assert_eq!(frame.center(parent.child(0)).x, frame.center(parent.child(1)).x);
+
+    	assert_eq!(frame.code_at(vec3(1001.0, 1.0, 1.0)), Some(LocationalCode::from_cell(30, U64Vec3::splat((1 << 30) - 1))));
+    	assert_eq!(frame.code_at(vec3(998.9, 0.0, 0.0)), None);
+    }
+}
@@ -0,0 +1,51 @@
+//! Recycling of the allocations of octree nodes.
+//!
+//! Strokes split and drop nodes by the thousands, so dropped nodes are
+//! kept on a freelist of the thread and handed out again to the nodes
+//! created next, instead of going through the global allocator each
+//! time. The freelist is capped, so a huge removal doesn't hold on to
+//! its memory for good.
+
+use super::SculptNode;
+
+use std::cell::RefCell;
+
+/// How many dropped nodes a thread keeps for reuse.
+pub const CAPACITY: usize = 1 << 18;
+
+thread_local! {
+	// the boxes are the allocations being kept, so they aren't unboxed
+	#[allow(clippy::vec_box)]
+	static FREE: RefCell<Vec<Box<SculptNode>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Box a node, reusing the allocation of a dropped node if there is one.
+pub(super) fn allocate(node: SculptNode) -> Box<SculptNode> {
+	let free = FREE.try_with(|free| free.borrow_mut().pop()).ok().flatten();
+
+	match free {
+		Some(mut boxed) => {
+			*boxed = node;
+			boxed
+		},
+		None => Box::new(node),
+	}
+}
+
+/// Drop a boxed node and its children, keeping their allocations for reuse.
+pub(super) fn recycle(mut node: Box<SculptNode>) {
+	node.clear_children();
+
+	// the thread may be exiting, in which case the node is simply dropped
+	let _ = FREE.try_with(|free| {
+		let mut free = free.borrow_mut();
+		if free.len() < CAPACITY {
+			free.push(node);
+		}
+	});
+}
+
+/// Get how many dropped nodes the thread keeps for reuse.
+pub fn get_free_count() -> usize {
+	FREE.try_with(|free| free.borrow().len()).unwrap_or(0)
+}
@@ -0,0 +1,121 @@
+//! A compact byte stream of the nodes of a sculpt, for saving it.
+//!
+//! The nodes are written depth first, each as its kind, a mask of the
+//! children following it, its material, its paint and its mask, all
+//! little endian. Centers, sizes and child counts aren't stored, as they
+//! are worked out again from where each node is in the stream.
+
+use super::{pool, LocationalCode, Sculpt, SculptNode, SculptNodeKind};
+use super::location::Frame;
+use crate::aabb::Aabb;
+use crate::util::bytes::invalid;
+
+use std::io;
+
+/// The length of a node in the stream.
+const NODE_LENGTH: usize = 14;
+
+impl Sculpt {
+	/// Append the nodes of the sculpt to a byte stream.
+	pub fn write_nodes(&self, bytes: &mut Vec<u8>) {
+		self.root.write(bytes);
+	}
+
+	/// Read a sculpt covering a box from the nodes written by `write_nodes`.
+	///
+	/// The palette is the default one, for the caller to replace before
+	/// repairing the sculpt, as materials missing from it count as damage.
+	pub fn read_nodes(resolution: u32, bounds: Aabb, bytes: &[u8]) -> io::Result<Self> {
+		let mut sculpt = Self::new_with_bounds(resolution, bounds);
+		let mut rest = bytes;
+		sculpt.root = SculptNode::read(&mut rest, LocationalCode::ROOT, &sculpt.frame)?;
+		if !rest.is_empty() {
+			return Err(invalid("the nodes of a sculpt are followed by stray bytes"));
+		}
+		sculpt.root.set_child_count();
+
+		Ok(sculpt)
+	}
+}
+
+impl SculptNode {
+	/// Append the node and its children to a byte stream.
+	fn write(&self, bytes: &mut Vec<u8>) {
+		let kind = match self.kind {
+			SculptNodeKind::None => 0u8,
+			SculptNodeKind::Leaf => 1,
+			SculptNodeKind::Interior => 2,
+		};
+		let children = (0..8)
+			.filter(|index| self.children[*index].is_some())
+			.fold(0u8, |mask, index| mask | 1 << index);
+
+		bytes.extend_from_slice(&[kind, children]);
+		bytes.extend_from_slice(&self.material.to_le_bytes());
+		bytes.extend_from_slice(&self.paint.to_le_bytes());
+		bytes.extend_from_slice(&self.mask.to_le_bytes());
+		for child in self.children.iter().flatten() {
+			child.write(bytes);
+		}
+	}
+
+	/// Read a node at an address and its children from the front of a byte stream, advancing past them.
+	fn read(bytes: &mut &[u8], code: LocationalCode, frame: &Frame) -> io::Result<Self> {
+		if bytes.len() < NODE_LENGTH {
+			return Err(invalid("the nodes of a sculpt are cut off"));
+		}
+		let (fields, rest) = bytes.split_at(NODE_LENGTH);
+		*bytes = rest;
+
+		let kind = match fields[0] {
+			0 => SculptNodeKind::None,
+			1 => SculptNodeKind::Leaf,
+			2 => SculptNodeKind::Interior,
+			_ => return Err(invalid("a node of a sculpt has an unknown kind")),
+		};
+		let children = fields[1];
+		if children != 0 && code.get_depth() >= frame.get_depth() {
+			return Err(invalid("the nodes of a sculpt go deeper than its resolution"));
+		}
+
+		let mut node = SculptNode::new(kind, u32::from_le_bytes(fields[2..6].try_into().unwrap()), code);
+		node.paint = u32::from_le_bytes(fields[6..10].try_into().unwrap());
+		node.mask = f32::from_le_bytes(fields[10..14].try_into().unwrap());
+		for index in (0..8).filter(|index| children >> index & 1 == 1) {
+			node.children[index] = Some(pool::allocate(Self::read(bytes, code.child(index), frame)?));
+		}
+
+		Ok(node)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    use crate::brush::RoundBrushTip;
+    use crate::sculpt::Coverage;
+
+    use glam::vec3;
+
+    #[test]
+    fn nodes_round_trip() {
+    	let bounds = Aabb::cube(vec3(2.5, 0.5, 0.5), 1.0);
+    	let mut sculpt = Sculpt::new_with_bounds(32, bounds);
+    	let center = vec3(2.4, 0.5, 0.6);
+    	sculpt.subdivide(RoundBrushTip::filler(0.3, center), RoundBrushTip::container(0.3, center));
+    	sculpt.paint(&|_, center| if center.x < 2.3 { Coverage::Solid } else { Coverage::Unchanged }, 0xff0000ff);
+
+    	let mut bytes = Vec::new();
+    	sculpt.write_nodes(&mut bytes);
+    	let read = Sculpt::read_nodes(32, bounds, &bytes).unwrap();
+
+    	assert_eq!(read.get_voxel_buffer(), sculpt.get_voxel_buffer());
+    	assert_eq!(read.get_paint_buffer(), sculpt.get_paint_buffer());
+    	assert_eq!(read.validate(), vec![]);
+
+    	assert_eq!(Sculpt::read_nodes(32, bounds, &bytes[..bytes.len() - 1]).err().map(|error| error.kind()), Some(io::ErrorKind::InvalidData));
+    	// the stream is too deep for a coarser sculpt
+    	assert!(Sculpt::read_nodes(4, bounds, &bytes).is_err());
+    }
+}
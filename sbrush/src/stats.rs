@@ -0,0 +1,101 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Statistics about a sculpting session.
+///
+/// Only kept when the user opts in, and only ever
+/// written to a local file for them to review.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionStats {
+	pub strokes: u32,
+	pub undos: u32,
+	pub tools: BTreeMap<String, ToolStats>,
+}
+
+/// Statistics about a single tool.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToolStats {
+	pub strokes: u32,
+	pub seconds: f64,
+}
+
+impl SessionStats {
+	/// Count a stroke made with a tool.
+	pub fn record_stroke(&mut self, tool: &str) {
+		self.strokes += 1;
+		self.tool(tool).strokes += 1;
+	}
+
+	/// Count an undo.
+	pub fn record_undo(&mut self) {
+		self.undos += 1;
+	}
+
+	/// Add time spent with a tool selected.
+	pub fn record_time(&mut self, tool: &str, delta: Duration) {
+		self.tool(tool).seconds += delta.as_secs_f64();
+	}
+
+	/// Get the undos per stroke, or 0 before the first stroke.
+	pub fn undo_frequency(&self) -> f64 {
+		if self.strokes == 0 {
+			return 0.0;
+		}
+
+		self.undos as f64 / self.strokes as f64
+	}
+
+	/// Write the statistics as JSON.
+	pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+		fs::write(path, serde_json::to_string_pretty(self)?)
+	}
+
+	/// Read statistics written by save.
+	pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+		Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+	}
+
+	/// Get the statistics of a tool, starting them if needed.
+	fn tool(&mut self, tool: &str) -> &mut ToolStats {
+		self.tools.entry(tool.to_owned()).or_default()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    #[test]
+    fn records_strokes_and_time_per_tool() {
+    	let mut stats = SessionStats::default();
+
+    	stats.record_stroke("Round Brush");
+    	stats.record_stroke("Round Brush");
+    	stats.record_stroke("Square Brush");
+    	stats.record_undo();
+    	stats.record_time("Round Brush", Duration::from_millis(1500));
+
+    	assert_eq!(stats.strokes, 3);
+    	assert_eq!(stats.tools["Round Brush"].strokes, 2);
+    	assert_eq!(stats.tools["Round Brush"].seconds, 1.5);
+    	assert!((stats.undo_frequency() - 1.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+    	let mut stats = SessionStats::default();
+    	stats.record_stroke("Round Brush");
+    	let path = std::env::temp_dir().join(format!("swirlix-stats-{}.json", std::process::id()));
+
+    	stats.save(&path).unwrap();
+    	let loaded = SessionStats::load(&path).unwrap();
+    	fs::remove_file(&path).unwrap();
+
+    	assert_eq!(loaded, stats);
+    }
+}
@@ -0,0 +1,9 @@
+//! Small pieces shared by several features.
+
+pub mod backups;
+pub(crate) mod bytes;
+pub mod jobs;
+pub mod migrations;
+pub mod profile;
+pub mod progress;
+pub mod watch;
@@ -0,0 +1,224 @@
+//! Backups and versions kept next to a saved file.
+//!
+//! Every autosave rotates the backups, keeping the latest few as
+//! `<file>.1.bak` (the newest) up to `<file>.<count>.bak`. Versions are
+//! saved on demand instead, into a `<file>.versions` folder, each named
+//! by when it was saved and with an optional thumbnail beside it, so a
+//! picker can list them to restore one.
+
+use std::cmp::Reverse;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A version of a file, saved on demand.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Version {
+	/// When the version was saved.
+	pub time: SystemTime,
+	pub path: PathBuf,
+	/// A PNG showing the version, if one was saved with it.
+	pub thumbnail: Option<PathBuf>,
+}
+
+impl Version {
+	/// Describe when the version was saved, as a date and time in UTC.
+	pub fn describe_time(&self) -> String {
+		let seconds = self.time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+		let (days, time) = (seconds / 86400, seconds % 86400);
+
+		// the civil date of a day since 1970, in eras of 400 years starting in March
+		let days = days + 719468;
+		let era = days / 146097;
+		let day_of_era = days % 146097;
+		let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+		let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+		let month_index = (5 * day_of_year + 2) / 153;
+		let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+		let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+		let year = era * 400 + year_of_era + u64::from(month <= 2);
+
+		format!("{year}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC", time / 3600, time / 60 % 60, time % 60)
+	}
+}
+
+/// How many autosaves of a file are kept as backups.
+pub const BACKUP_COUNT: usize = 5;
+
+/// The backups and versions of a file.
+pub struct Backups {
+	path: PathBuf,
+	/// How many backups are kept.
+	count: usize,
+}
+
+impl Backups {
+	/// Keep a number of backups of a file.
+	pub fn new(path: impl Into<PathBuf>, count: usize) -> Self {
+		Self {
+			path: path.into(),
+			count,
+		}
+	}
+
+	/// Get the path of the file with a suffix added to its name.
+	fn with_suffix(&self, suffix: &str) -> PathBuf {
+		let mut name: OsString = self.path.file_name().unwrap_or_default().to_owned();
+		name.push(suffix);
+
+		self.path.with_file_name(name)
+	}
+
+	/// Get the path of a backup, where 1 is the newest.
+	fn backup_path(&self, number: usize) -> PathBuf {
+		self.with_suffix(&format!(".{number}.bak"))
+	}
+
+	/// Get the folder holding the versions.
+	fn versions_folder(&self) -> PathBuf {
+		self.with_suffix(".versions")
+	}
+
+	/// Make room for a new backup, dropping the oldest one past the count, and get the path to write it to.
+	///
+	/// Returns None if no backups are kept.
+	pub fn next_backup(&self) -> io::Result<Option<PathBuf>> {
+		if self.count == 0 {
+			return Ok(None);
+		}
+
+		// the oldest backup is overwritten by the one after it
+		for number in (1..self.count).rev() {
+			let older = self.backup_path(number);
+			if older.exists() {
+				fs::rename(&older, self.backup_path(number + 1))?;
+			}
+		}
+
+		Ok(Some(self.backup_path(1)))
+	}
+
+	/// Get the backups which exist, newest first.
+	pub fn get_backups(&self) -> Vec<PathBuf> {
+		(1..=self.count)
+			.map(|number| self.backup_path(number))
+			.filter(|path| path.exists())
+			.collect()
+	}
+
+	/// Pick the path of a new version, named by the current time, creating the folder of versions.
+	///
+	/// Versions picked within the same millisecond are spread apart, so none is overwritten.
+	pub fn next_version(&self) -> io::Result<Version> {
+		let folder = self.versions_folder();
+		fs::create_dir_all(&folder)?;
+
+		let extension = self.path.extension().map(|extension| format!(".{}", extension.to_string_lossy())).unwrap_or_default();
+		let mut millis = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+		while folder.join(format!("{millis}{extension}")).exists() {
+			millis += 1;
+		}
+
+		Ok(Version {
+			time: UNIX_EPOCH + Duration::from_millis(millis),
+			path: folder.join(format!("{millis}{extension}")),
+			thumbnail: None,
+		})
+	}
+
+	/// Get the saved versions, newest first.
+	pub fn get_versions(&self) -> io::Result<Vec<Version>> {
+		let folder = self.versions_folder();
+		if !folder.exists() {
+			return Ok(Vec::new());
+		}
+
+		let mut versions: Vec<Version> = fs::read_dir(&folder)?
+			.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+			.filter(|path| path.extension().is_none_or(|extension| extension != "png"))
+			.filter_map(|path| {
+				let millis: u64 = path.file_stem()?.to_str()?.parse().ok()?;
+				let thumbnail = thumbnail_path(&path);
+
+				Some(Version {
+					time: UNIX_EPOCH + Duration::from_millis(millis),
+					thumbnail: thumbnail.exists().then_some(thumbnail),
+					path,
+				})
+			})
+			.collect();
+		versions.sort_by_key(|version| Reverse(version.time));
+
+		Ok(versions)
+	}
+}
+
+/// Get the path of the thumbnail beside a version.
+pub fn thumbnail_path(version: &Path) -> PathBuf {
+	version.with_extension("png")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    /// A folder of its own for a test, emptied first.
+    fn folder(name: &str) -> PathBuf {
+    	let folder = std::env::temp_dir().join(format!("swirlix-{name}-{}", std::process::id()));
+    	let _ = fs::remove_dir_all(&folder);
+    	fs::create_dir_all(&folder).unwrap();
+
+    	folder
+    }
+
+    #[test]
+    fn backups_rotate() {
+    	let folder = folder("backups");
+    	let backups = Backups::new(folder.join("model.swx"), 2);
+
+    	for contents in [b"first", b"secnd", b"third"] {
+    		fs::write(backups.next_backup().unwrap().unwrap(), contents).unwrap();
+    	}
+    	let kept = backups.get_backups();
+    	assert_eq!(kept, vec![folder.join("model.swx.1.bak"), folder.join("model.swx.2.bak")]);
+    	assert_eq!(fs::read(&kept[0]).unwrap(), b"third");
+    	assert_eq!(fs::read(&kept[1]).unwrap(), b"secnd");
+    	assert!(!folder.join("model.swx.3.bak").exists());
+
+    	fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn versions_describe_when_they_were_saved() {
+    	let at = |seconds| Version { time: UNIX_EPOCH + Duration::from_secs(seconds), path: PathBuf::new(), thumbnail: None }.describe_time();
+
+    	assert_eq!(at(0), "1970-01-01 00:00:00 UTC");
+    	assert_eq!(at(951_782_400), "2000-02-29 00:00:00 UTC");
+    	assert_eq!(at(1_000_000_000), "2001-09-09 01:46:40 UTC");
+    }
+
+    #[test]
+    fn versions_are_listed_newest_first() {
+    	let folder = folder("versions");
+    	let backups = Backups::new(folder.join("model.swx"), 2);
+    	assert!(backups.get_versions().unwrap().is_empty());
+
+    	let old = backups.next_version().unwrap();
+    	fs::write(&old.path, b"old").unwrap();
+    	let mut new = backups.next_version().unwrap();
+    	fs::write(&new.path, b"new").unwrap();
+    	assert!(new.time > old.time);
+    	let thumbnail = thumbnail_path(&new.path);
+    	fs::write(&thumbnail, b"png").unwrap();
+    	new.thumbnail = Some(thumbnail);
+
+    	let versions = backups.get_versions().unwrap();
+    	assert_eq!(versions, vec![new, old]);
+    	assert_eq!(fs::read(&versions[0].path).unwrap(), b"new");
+    	assert_eq!(versions[0].thumbnail.as_ref().map(|thumbnail| fs::read(thumbnail).unwrap()), Some(b"png".to_vec()));
+
+    	fs::remove_dir_all(&folder).unwrap();
+    }
+}
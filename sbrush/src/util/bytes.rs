@@ -0,0 +1,19 @@
+//! Reading the little endian fields of the files the app saves.
+
+use std::io;
+
+/// Take a field from the front of some bytes.
+pub(crate) fn take<const N: usize>(bytes: &mut &[u8]) -> io::Result<[u8; N]> {
+	if bytes.len() < N {
+		return Err(invalid("the file is cut off"));
+	}
+	let (field, rest) = bytes.split_at(N);
+	*bytes = rest;
+
+	Ok(field.try_into().unwrap())
+}
+
+/// Make an error for bytes which aren't valid for what they are read as.
+pub(crate) fn invalid(message: &str) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -0,0 +1,337 @@
+//! A shared pool of background threads for long tasks.
+//!
+//! Saving, baking and similar work is queued as jobs, instead of each
+//! feature spawning its own threads. Jobs run by priority, and report
+//! to a `Progress` which can also cancel them.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+
+use super::progress::Progress;
+
+/// How urgently a job should run, compared to the other queued jobs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+	/// Work nobody is waiting for, like autosaves.
+	Low,
+	#[default]
+	Normal,
+	/// Work the user is waiting for, like a save they asked for.
+	High,
+}
+
+/// A handle to a queued job.
+///
+/// Dropping the handle leaves the job running.
+pub struct Job<T> {
+	progress: Progress,
+	receiver: Receiver<T>,
+	result: Option<T>,
+	done: bool,
+}
+
+impl<T> Job<T> {
+	/// Get the progress of the job, which is finished once it has stopped.
+	pub fn get_progress(&self) -> &Progress {
+		&self.progress
+	}
+
+	/// Ask the job to stop, or skip it if it hasn't started yet.
+	pub fn cancel(&self) {
+		self.progress.cancel();
+	}
+
+	/// Pick up the result, if the job has stopped.
+	fn poll(&mut self) {
+		if self.done {
+			return;
+		}
+		match self.receiver.try_recv() {
+			Ok(result) => {
+				self.result = Some(result);
+				self.done = true;
+			},
+			Err(TryRecvError::Disconnected) => self.done = true,
+			Err(TryRecvError::Empty) => (),
+		}
+	}
+
+	/// Check whether the job has stopped.
+	pub fn is_done(&mut self) -> bool {
+		self.poll();
+
+		self.done
+	}
+
+	/// Take the result once the job is finished.
+	///
+	/// Jobs which were skipped or panicked never have a result.
+	pub fn try_take(&mut self) -> Option<T> {
+		self.poll();
+
+		self.result.take()
+	}
+
+	/// Block until the job has stopped, returning its result.
+	pub fn wait(mut self) -> Option<T> {
+		self.poll();
+		if self.done {
+			return self.result;
+		}
+
+		self.receiver.recv().ok()
+	}
+}
+
+/// A queued job, ordered by priority and then by when it was queued.
+struct Queued {
+	priority: Priority,
+	sequence: u64,
+	run: Box<dyn FnOnce() + Send>,
+}
+
+impl PartialEq for Queued {
+	fn eq(&self, other: &Self) -> bool {
+		self.cmp(other) == Ordering::Equal
+	}
+}
+
+impl Eq for Queued {}
+
+impl PartialOrd for Queued {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for Queued {
+	fn cmp(&self, other: &Self) -> Ordering {
+		self.priority.cmp(&other.priority).then(other.sequence.cmp(&self.sequence))
+	}
+}
+
+/// The queued jobs.
+#[derive(Default)]
+struct Queue {
+	jobs: BinaryHeap<Queued>,
+	/// Whether the pool was dropped, so the threads stop once the jobs are done.
+	closed: bool,
+}
+
+/// The queue shared between the pool and its threads.
+#[derive(Default)]
+struct Shared {
+	queue: Mutex<Queue>,
+	available: Condvar,
+	sequence: AtomicU64,
+}
+
+impl Shared {
+	/// Run queued jobs until the pool shuts down and the queue is empty.
+	fn work(&self) {
+		loop {
+			let job = {
+				let mut queue = self.queue.lock().unwrap();
+				loop {
+					if let Some(job) = queue.jobs.pop() {
+						break job;
+					}
+					if queue.closed {
+						return;
+					}
+					queue = self.available.wait(queue).unwrap();
+				}
+			};
+			// a panicking job drops its result, but leaves the thread to run the next one
+			let _ = panic::catch_unwind(AssertUnwindSafe(job.run));
+		}
+	}
+}
+
+/// Finishes a progress when dropped.
+struct Finish(Progress);
+
+impl Drop for Finish {
+	fn drop(&mut self) {
+		self.0.finish();
+	}
+}
+
+/// The threads, which are joined once every handle to the pool is dropped.
+struct Workers {
+	shared: Arc<Shared>,
+	threads: Vec<JoinHandle<()>>,
+}
+
+impl Drop for Workers {
+	fn drop(&mut self) {
+		self.shared.queue.lock().unwrap().closed = true;
+		self.shared.available.notify_all();
+
+		// queued jobs are finished first, so nothing being saved is lost
+		let current = thread::current().id();
+		for thread in self.threads.drain(..).filter(|thread| thread.thread().id() != current) {
+			let _ = thread.join();
+		}
+	}
+}
+
+/// A pool of threads running queued jobs.
+///
+/// Clones share the same threads.
+#[derive(Clone)]
+pub struct JobPool {
+	shared: Arc<Shared>,
+	workers: Arc<Workers>,
+}
+
+impl JobPool {
+	/// Create a pool with a number of threads.
+	pub fn new(threads: usize) -> Self {
+		let shared = Arc::new(Shared::default());
+		let threads = (0..threads.max(1))
+			.map(|index| {
+				let shared = Arc::clone(&shared);
+				thread::Builder::new()
+					.name(format!("swirlix job {index}"))
+					.spawn(move || shared.work())
+					.expect("failed to spawn a job thread")
+			})
+			.collect();
+
+		Self {
+			shared: Arc::clone(&shared),
+			workers: Arc::new(Workers {
+				shared,
+				threads,
+			}),
+		}
+	}
+
+	/// Queue a job.
+	///
+	/// The job reports to its progress, described by a message, and should stop early once it is cancelled.
+	pub fn spawn<T: Send + 'static>(
+		&self,
+		priority: Priority,
+		message: impl Into<String>,
+		job: impl FnOnce(&Progress) -> T + Send + 'static,
+	) -> Job<T> {
+		let progress = Progress::new(message);
+		let (sender, receiver) = mpsc::channel();
+		let reported = progress.clone();
+		let run = Box::new(move || {
+			// the progress finishes even if the job panics, so nobody waits on it forever
+			let _finish = Finish(reported.clone());
+			if !reported.is_cancelled() {
+				let value = job(&reported);
+				reported.set_fraction(1.0);
+				let _ = sender.send(value);
+			}
+		});
+
+		let sequence = self.shared.sequence.fetch_add(1, AtomicOrdering::Relaxed);
+		self.shared.queue.lock().unwrap().jobs.push(Queued {
+			priority,
+			sequence,
+			run,
+		});
+		self.shared.available.notify_one();
+
+		Job {
+			progress,
+			receiver,
+			result: None,
+			done: false,
+		}
+	}
+}
+
+impl Default for JobPool {
+	/// A pool leaving one core for the interface.
+	fn default() -> Self {
+		Self::new(thread::available_parallelism().map_or(1, |count| count.get()).saturating_sub(1))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    #[test]
+    fn jobs_run_by_priority() {
+    	let pool = JobPool::new(1);
+    	let (release, blocked) = mpsc::channel::<()>();
+    	let order = Arc::new(Mutex::new(Vec::new()));
+
+    	let blocker = pool.spawn(Priority::Normal, "block", move |_| blocked.recv().unwrap());
+    	let jobs: Vec<Job<()>> = [(Priority::Low, "low"), (Priority::High, "high"), (Priority::Normal, "normal"), (Priority::High, "later")]
+    		.into_iter()
+    		.map(|(priority, name)| {
+    			let order = Arc::clone(&order);
+    			pool.spawn(priority, name, move |_| order.lock().unwrap().push(name))
+    		})
+    		.collect();
+    	release.send(()).unwrap();
+
+    	blocker.wait();
+    	for job in jobs {
+    		job.wait();
+    	}
+    	assert_eq!(*order.lock().unwrap(), vec!["high", "later", "normal", "low"]);
+    }
+
+    #[test]
+    fn jobs_report_progress_and_cancel() {
+    	let pool = JobPool::new(1);
+    	let (release, blocked) = mpsc::channel::<()>();
+
+    	let mut blocker = pool.spawn(Priority::Normal, "block", move |progress| {
+    		progress.set_fraction(0.5);
+    		blocked.recv().unwrap();
+    		progress.is_cancelled()
+    	});
+    	let skipped = pool.spawn(Priority::Normal, "skip", |_| 1);
+    	skipped.cancel();
+    	while blocker.get_progress().get_fraction() < 0.5 {
+    		thread::yield_now();
+    	}
+    	assert!(!blocker.is_done());
+    	blocker.cancel();
+    	release.send(()).unwrap();
+
+    	assert_eq!(skipped.wait(), None);
+    	assert!(blocker.is_done());
+    	assert!(blocker.get_progress().is_finished());
+    	assert_eq!(blocker.get_progress().get_fraction(), 1.0);
+    	assert_eq!(blocker.try_take(), Some(true));
+    }
+
+    #[test]
+    fn panicking_jobs_finish_and_leave_the_thread_running() {
+    	let pool = JobPool::new(1);
+
+    	let panicked = pool.spawn(Priority::Normal, "panic", |_| -> u32 { panic!("the job failed") });
+    	let next = pool.spawn(Priority::Normal, "next", |_| 2);
+
+    	assert_eq!(next.wait(), Some(2));
+    	assert!(panicked.get_progress().is_finished());
+    	assert_eq!(panicked.wait(), None);
+    }
+
+    #[test]
+    fn dropping_the_pool_finishes_queued_jobs() {
+    	let pool = JobPool::new(2);
+    	let jobs: Vec<Job<u32>> = (0..8).map(|index| pool.spawn(Priority::Low, "double", move |_| index * 2)).collect();
+
+    	drop(pool);
+
+    	let results: Vec<Option<u32>> = jobs.into_iter().map(Job::wait).collect();
+    	assert_eq!(results, (0..8).map(|index| Some(index * 2)).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,144 @@
+//! Upgrades files saved by older versions of a format.
+//!
+//! A file starts with a magic number naming its format, followed by
+//! the version it was saved in. Each version bump registers a function
+//! upgrading the contents of the version before it, so loading runs
+//! every upgrade from the version of the file to the current one.
+//! Files from newer versions are refused rather than misread.
+
+use std::io;
+
+/// Upgrades the contents of a file by one version.
+pub type Upgrade = fn(Vec<u8>) -> io::Result<Vec<u8>>;
+
+/// The length of the magic number and the version in front of the contents.
+pub const HEADER_LENGTH: usize = 8;
+
+/// The versions of a file format, and how to upgrade from each to the next.
+pub struct Migrations {
+	magic: [u8; 4],
+	/// The upgrade from each version to the one after it, starting from version 0.
+	upgrades: Vec<Upgrade>,
+}
+
+impl Migrations {
+	/// Start a format at version 0, named by a magic number.
+	pub fn new(magic: [u8; 4]) -> Self {
+		Self {
+			magic,
+			upgrades: Vec::new(),
+		}
+	}
+
+	/// Bump the version, registering how to upgrade the contents of the version before.
+	pub fn register(&mut self, upgrade: Upgrade) -> &mut Self {
+		self.upgrades.push(upgrade);
+
+		self
+	}
+
+	/// Get the version files are saved in.
+	pub fn get_version(&self) -> u32 {
+		self.upgrades.len() as u32
+	}
+
+	/// Get the header of the current version, for contents written after it.
+	pub fn get_header(&self) -> [u8; HEADER_LENGTH] {
+		let mut header = [0; HEADER_LENGTH];
+		header[..4].copy_from_slice(&self.magic);
+		header[4..].copy_from_slice(&self.get_version().to_le_bytes());
+
+		header
+	}
+
+	/// Put the header of the current version in front of some contents.
+	pub fn write(&self, contents: &[u8]) -> Vec<u8> {
+		let mut bytes = Vec::with_capacity(HEADER_LENGTH + contents.len());
+		bytes.extend_from_slice(&self.get_header());
+		bytes.extend_from_slice(contents);
+
+		bytes
+	}
+
+	/// Read the version of a file, checking that it is in this format.
+	pub fn read_version(&self, bytes: &[u8]) -> io::Result<u32> {
+		if bytes.len() < HEADER_LENGTH || bytes[..4] != self.magic {
+			return Err(io::Error::new(io::ErrorKind::InvalidData, "the file isn't in this format"));
+		}
+
+		Ok(u32::from_le_bytes(bytes[4..HEADER_LENGTH].try_into().unwrap()))
+	}
+
+	/// Get the contents of a file, upgraded to the current version.
+	pub fn read(&self, bytes: &[u8]) -> io::Result<Vec<u8>> {
+		let version = self.read_version(bytes)?;
+		if version > self.get_version() {
+			return Err(io::Error::new(
+				io::ErrorKind::Unsupported,
+				format!("the file was saved by a newer version (format {version}, this one reads up to {})", self.get_version()),
+			));
+		}
+
+		self.upgrades[version as usize..].iter()
+			.try_fold(bytes[HEADER_LENGTH..].to_vec(), |contents, upgrade| upgrade(contents))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    /// An example format of comma separated numbers, which gained a count
+    /// in front in version 1, and switched to bytes in version 2.
+    fn numbers() -> Migrations {
+    	let mut migrations = Migrations::new(*b"NUMS");
+    	migrations
+    		.register(|contents| {
+    			let text = String::from_utf8(contents).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    			let count = text.split(',').filter(|number| !number.is_empty()).count();
+    			Ok(format!("{count};{text}").into_bytes())
+    		})
+    		.register(|contents| {
+    			let text = String::from_utf8(contents).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    			let (_, numbers) = text.split_once(';').ok_or(io::ErrorKind::InvalidData)?;
+    			numbers.split(',')
+    				.filter(|number| !number.is_empty())
+    				.map(|number| number.parse::<u8>().map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error)))
+    				.collect()
+    		});
+
+    	migrations
+    }
+
+    /// The same numbers, as saved by every version of the example format.
+    const FIXTURES: [&[u8]; 3] = [
+    	b"NUMS\0\0\0\x001,2,3",
+    	b"NUMS\x01\0\0\x003;1,2,3",
+    	b"NUMS\x02\0\0\0\x01\x02\x03",
+    ];
+
+    #[test]
+    fn every_version_is_upgraded() {
+    	let migrations = numbers();
+    	assert_eq!(migrations.get_version(), 2);
+
+    	for (version, fixture) in FIXTURES.iter().enumerate() {
+    		assert_eq!(migrations.read_version(fixture).unwrap(), version as u32);
+    		let contents = migrations.read(fixture).unwrap();
+    		assert_eq!(contents, vec![1, 2, 3]);
+
+    		// saving again writes the current version, which reads back the same
+    		let saved = migrations.write(&contents);
+    		assert_eq!(saved, FIXTURES[2]);
+    		assert_eq!(migrations.read(&saved).unwrap(), contents);
+    	}
+    }
+
+    #[test]
+    fn other_formats_and_newer_versions_are_refused() {
+    	let migrations = numbers();
+
+    	assert_eq!(migrations.read(b"PNG").unwrap_err().kind(), io::ErrorKind::InvalidData);
+    	assert_eq!(migrations.read(b"NUMS\x03\0\0\0").unwrap_err().kind(), io::ErrorKind::Unsupported);
+    }
+}
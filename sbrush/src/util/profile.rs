@@ -0,0 +1,171 @@
+//! CPU timings of the work done for each frame.
+//!
+//! The time the main thread spends between two frames is split by what it
+//! was spent on, so a hitch can be told apart from a slow GPU frame and
+//! put down to handling events, sculpting, reading and writing files, or
+//! uploading the document. The last frames are kept for averages and
+//! peaks, as a single frame is too noisy to read.
+
+use crate::i18n::{tr, tr_args};
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// How many of the last frames are kept.
+pub const WINDOW: usize = 120;
+
+/// What the main thread spends its time on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+	/// Handling window events and commands.
+	Events,
+	/// Applying the queued inputs to the document.
+	Sculpt,
+	/// Reading chunks of opened files, and reloading files changed by other programs.
+	Serialization,
+	/// Latching the document and sending it to the renderer.
+	Upload,
+}
+
+impl Phase {
+	/// Every phase, in the order they are listed.
+	pub const ALL: [Phase; 4] = [Phase::Events, Phase::Sculpt, Phase::Serialization, Phase::Upload];
+
+	/// Get the message key of the phase's name.
+	fn message(&self) -> &'static str {
+		match self {
+			Phase::Events => "profile-events",
+			Phase::Sculpt => "profile-sculpt",
+			Phase::Serialization => "profile-serialization",
+			Phase::Upload => "profile-upload",
+		}
+	}
+
+	/// Get the translated name of the phase.
+	pub fn describe(&self) -> String {
+		tr(self.message())
+	}
+}
+
+/// The CPU time spent in each phase for one frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameTimes {
+	times: [Duration; Phase::ALL.len()],
+}
+
+impl FrameTimes {
+	/// Get the time spent in a phase.
+	pub fn get(&self, phase: Phase) -> Duration {
+		self.times[phase as usize]
+	}
+
+	/// Get the time spent in every phase together.
+	pub fn get_total(&self) -> Duration {
+		self.times.iter().sum()
+	}
+
+	/// Describe the times in a line, for logging slow frames.
+	pub fn summary(&self) -> String {
+		let phases: Vec<String> = Phase::ALL.iter()
+			.map(|phase| format!("{} {:.1} ms", phase.describe(), milliseconds(self.get(*phase))))
+			.collect();
+
+		tr_args("profile-slow-frame", &[("total", &format!("{:.1}", milliseconds(self.get_total()))), ("phases", &phases.join(", "))])
+	}
+}
+
+/// Gathers the CPU times of the last frames.
+#[derive(Clone, Debug, Default)]
+pub struct FrameProfiler {
+	/// The times of the frame being prepared.
+	current: FrameTimes,
+	/// The times of the last frames, oldest first.
+	frames: VecDeque<FrameTimes>,
+}
+
+impl FrameProfiler {
+	/// Add time spent in a phase to the frame being prepared.
+	pub fn record(&mut self, phase: Phase, duration: Duration) {
+		self.current.times[phase as usize] += duration;
+	}
+
+	/// Finish the frame being prepared, once it is drawn, returning its times.
+	pub fn finish_frame(&mut self) -> FrameTimes {
+		let times = std::mem::take(&mut self.current);
+		if self.frames.len() == WINDOW {
+			self.frames.pop_front();
+		}
+		self.frames.push_back(times);
+
+		times
+	}
+
+	/// Get the times of the last finished frame, if any.
+	pub fn get_last(&self) -> Option<FrameTimes> {
+		self.frames.back().copied()
+	}
+
+	/// Get the average times of the last frames.
+	pub fn get_average(&self) -> FrameTimes {
+		let mut average = FrameTimes::default();
+		if self.frames.is_empty() {
+			return average;
+		}
+		for (index, time) in average.times.iter_mut().enumerate() {
+			*time = self.frames.iter().map(|frame| frame.times[index]).sum::<Duration>() / self.frames.len() as u32;
+		}
+
+		average
+	}
+
+	/// Get the longest times of the last frames, of each phase on its own.
+	pub fn get_peak(&self) -> FrameTimes {
+		let mut peak = FrameTimes::default();
+		for frame in &self.frames {
+			for (time, frame_time) in peak.times.iter_mut().zip(frame.times) {
+				*time = (*time).max(frame_time);
+			}
+		}
+
+		peak
+	}
+}
+
+/// Get a duration in fractional milliseconds.
+pub fn milliseconds(duration: Duration) -> f64 {
+	duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    #[test]
+    fn frames_keep_their_times_apart() {
+    	let mut profiler = FrameProfiler::default();
+    	assert_eq!(profiler.get_last(), None);
+    	assert_eq!(profiler.get_average(), FrameTimes::default());
+
+    	profiler.record(Phase::Sculpt, Duration::from_millis(3));
+    	profiler.record(Phase::Sculpt, Duration::from_millis(1));
+    	profiler.record(Phase::Upload, Duration::from_millis(2));
+    	let first = profiler.finish_frame();
+    	assert_eq!(first.get(Phase::Sculpt), Duration::from_millis(4));
+    	assert_eq!(first.get(Phase::Events), Duration::ZERO);
+    	assert_eq!(first.get_total(), Duration::from_millis(6));
+
+    	profiler.record(Phase::Upload, Duration::from_millis(6));
+    	profiler.finish_frame();
+    	assert_eq!(profiler.get_last().unwrap().get(Phase::Sculpt), Duration::ZERO);
+    	assert_eq!(profiler.get_average().get(Phase::Sculpt), Duration::from_millis(2));
+    	assert_eq!(profiler.get_average().get(Phase::Upload), Duration::from_millis(4));
+    	assert_eq!(profiler.get_peak().get(Phase::Sculpt), Duration::from_millis(4));
+    	assert_eq!(profiler.get_peak().get(Phase::Upload), Duration::from_millis(6));
+
+    	// old frames drop out of the window
+    	for _ in 0..WINDOW {
+    		profiler.finish_frame();
+    	}
+    	assert_eq!(profiler.get_peak(), FrameTimes::default());
+    }
+}
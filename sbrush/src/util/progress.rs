@@ -0,0 +1,102 @@
+//! Progress of long operations, shared with whoever is waiting on them.
+
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// The state behind every clone of a progress handle.
+#[derive(Default)]
+struct ProgressState {
+	/// The bits of the finished fraction, as there are no atomic floats.
+	fraction: AtomicU32,
+	message: Mutex<String>,
+	cancelled: AtomicBool,
+	finished: AtomicBool,
+}
+
+/// A handle to how far along a long operation is.
+///
+/// The operation reports through one clone, while the app polls another
+/// to draw a progress bar and to ask for the operation to stop. An
+/// operation which is cancelled stops between steps, so whatever it
+/// works on is left whole.
+#[derive(Clone, Default)]
+pub struct Progress {
+	state: Arc<ProgressState>,
+}
+
+impl Progress {
+	/// Create the progress of an operation described by a message.
+	pub fn new(message: impl Into<String>) -> Self {
+		let progress = Self::default();
+		progress.set_message(message);
+
+		progress
+	}
+
+	/// Report how much of the operation is done, from 0 to 1.
+	pub fn set_fraction(&self, fraction: f32) {
+		self.state.fraction.store(fraction.clamp(0.0, 1.0).to_bits(), Ordering::Relaxed);
+	}
+
+	/// Get how much of the operation is done, from 0 to 1.
+	pub fn get_fraction(&self) -> f32 {
+		f32::from_bits(self.state.fraction.load(Ordering::Relaxed))
+	}
+
+	/// Get how much of the operation is done, in whole percent.
+	pub fn get_percent(&self) -> u32 {
+		(self.get_fraction() * 100.0).floor() as u32
+	}
+
+	/// Describe what the operation is doing.
+	pub fn set_message(&self, message: impl Into<String>) {
+		*self.state.message.lock().unwrap() = message.into();
+	}
+
+	/// Get the description of what the operation is doing.
+	pub fn get_message(&self) -> String {
+		self.state.message.lock().unwrap().clone()
+	}
+
+	/// Ask the operation to stop.
+	pub fn cancel(&self) {
+		self.state.cancelled.store(true, Ordering::Relaxed);
+	}
+
+	/// Check whether the operation should stop.
+	pub fn is_cancelled(&self) -> bool {
+		self.state.cancelled.load(Ordering::Relaxed)
+	}
+
+	/// Mark the operation as stopped, whether it was done or cancelled.
+	pub fn finish(&self) {
+		self.state.finished.store(true, Ordering::Relaxed);
+	}
+
+	/// Check whether the operation has stopped.
+	pub fn is_finished(&self) -> bool {
+		self.state.finished.load(Ordering::Relaxed)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    #[test]
+    fn clones_share_progress() {
+    	let progress = Progress::new("Rendering");
+    	let reported = progress.clone();
+
+    	reported.set_fraction(0.256);
+    	reported.set_message("Saving");
+    	progress.cancel();
+
+    	assert_eq!(progress.get_percent(), 25);
+    	assert_eq!(progress.get_message(), "Saving");
+    	assert!(reported.is_cancelled());
+    	assert!(!progress.is_finished());
+    	reported.set_fraction(2.0);
+    	assert_eq!(progress.get_fraction(), 1.0);
+    }
+}
@@ -0,0 +1,86 @@
+//! Noticing when another program changes a file.
+//!
+//! A watch polls the modification time and length of its file, which
+//! needs no threads, works the same everywhere, and catches files
+//! replaced by a rename as well as files rewritten in place.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// A file, and how it was when it was last looked at.
+#[derive(Clone, Debug)]
+pub struct FileWatch {
+	path: PathBuf,
+	/// The modification time and length, or nothing while the file is missing.
+	stamp: Option<(SystemTime, u64)>,
+}
+
+impl FileWatch {
+	/// Watch a file for changes from how it is now.
+	pub fn new(path: impl Into<PathBuf>) -> Self {
+		let path = path.into();
+
+		Self {
+			stamp: stamp(&path),
+			path,
+		}
+	}
+
+	/// Get the path of the file.
+	pub fn get_path(&self) -> &Path {
+		&self.path
+	}
+
+	/// Check whether the file changed since it was last checked or acknowledged.
+	///
+	/// A file which was removed isn't a change, but it is once it is back.
+	pub fn check(&mut self) -> bool {
+		let stamp = stamp(&self.path);
+		let changed = stamp.is_some() && stamp != self.stamp;
+		self.stamp = stamp;
+
+		changed
+	}
+
+	/// Take the file as it is now as unchanged, like after saving it.
+	pub fn acknowledge(&mut self) {
+		self.stamp = stamp(&self.path);
+	}
+}
+
+/// Get the modification time and length of a file, if it exists.
+fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+	let metadata = fs::metadata(path).ok()?;
+
+	Some((metadata.modified().ok()?, metadata.len()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+    #[test]
+    fn changes_are_noticed_once() {
+    	let path = std::env::temp_dir().join(format!("swirlix-watch-{}.swx", std::process::id()));
+    	let _ = fs::remove_file(&path);
+
+    	let mut watch = FileWatch::new(&path);
+    	assert!(!watch.check());
+
+    	fs::write(&path, b"first").unwrap();
+    	assert!(watch.check());
+    	assert!(!watch.check());
+
+    	// a rewrite of another length is a change even within the resolution of the clock
+    	fs::write(&path, b"second").unwrap();
+    	watch.acknowledge();
+    	assert!(!watch.check());
+    	fs::write(&path, b"third!!").unwrap();
+    	assert!(watch.check());
+
+    	fs::remove_file(&path).unwrap();
+    	assert!(!watch.check());
+    	assert_eq!(watch.get_path(), path);
+    }
+}
@@ -0,0 +1,203 @@
+//! Scripted sessions driving the editor through its public API.
+//!
+//! Each test plays a sequence of strokes and commands, like a user would,
+//! and checks the resulting octrees rather than any one function.
+
+use sbrush::{Editor, Error};
+
+use glam::{IVec3, vec3};
+
+/// A step of a scripted session.
+#[derive(Clone, Copy)]
+enum Step {
+	/// A stroke adding voxels through points of the screen (0 to 1).
+	Add(&'static [(f32, f32)]),
+	/// A stroke removing voxels through points of the screen.
+	Remove(&'static [(f32, f32)]),
+	/// A line typed into the command palette.
+	Command(&'static str),
+}
+
+/// A summary of the octrees of an object.
+#[derive(Debug, PartialEq)]
+struct Stats {
+	chunks: usize,
+	nodes: usize,
+}
+
+/// Play steps on an editor, the way mouse strokes and the command palette do.
+fn play(editor: &mut Editor, steps: &[Step]) {
+	for step in steps {
+		match *step {
+			Step::Add(points) | Step::Remove(points) => {
+				editor.begin_stroke();
+				for &(x, y) in points {
+					match step {
+						Step::Add(_) => editor.add(x, y),
+						_ => editor.remove(x, y),
+					}
+				}
+				editor.end_stroke();
+			}
+			Step::Command(line) => {
+				editor.run_command(line).unwrap_or_else(|error| panic!("\"{line}\" should run: {error}"));
+			}
+		}
+	}
+}
+
+/// Play steps on a new editor, returning the voxel buffers of the active object.
+fn session(steps: &[Step]) -> (Editor, Vec<(IVec3, Vec<u32>)>) {
+	let mut editor = Editor::default();
+	play(&mut editor, steps);
+	let buffers = editor.get_voxel_buffers(editor.get_scene().get_active_index());
+
+	(editor, buffers)
+}
+
+/// Summarize voxel buffers.
+fn stats(buffers: &[(IVec3, Vec<u32>)]) -> Stats {
+	Stats {
+		chunks: buffers.len(),
+		nodes: buffers.iter().map(|(_, voxels)| voxels.len()).sum(),
+	}
+}
+
+/// Hash voxel buffers with FNV-1a, which unlike the hasher of std is the same everywhere.
+fn fingerprint(buffers: &[(IVec3, Vec<u32>)]) -> u64 {
+	let words = buffers.iter().flat_map(|(coordinate, voxels)| coordinate.to_array().map(|value| value as u32).into_iter().chain(voxels.iter().copied()));
+
+	words.flat_map(u32::to_le_bytes).fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+const ACROSS: &[(f32, f32)] = &[(0.4, 0.5), (0.45, 0.5), (0.5, 0.5), (0.55, 0.5), (0.6, 0.5)];
+
+#[test]
+fn strokes_grow_and_carve_the_octree() {
+	let (_, empty) = session(&[]);
+	let (editor, added) = session(&[Step::Add(ACROSS)]);
+	assert!(stats(&added).nodes > stats(&empty).nodes);
+	assert_eq!(editor.validate(), 0);
+
+	let (editor, carved) = session(&[Step::Add(ACROSS), Step::Remove(&[(0.5, 0.5)])]);
+	assert_ne!(carved, added);
+	assert_eq!(editor.validate(), 0);
+}
+
+#[test]
+fn sessions_replay_identically() {
+	let steps = [
+		Step::Command("brush size 0.2"),
+		Step::Add(ACROSS),
+		Step::Command("brush square"),
+		Step::Remove(&[(0.5, 0.45), (0.5, 0.55)]),
+		Step::Command("orbit 0.1 0"),
+		Step::Add(&[(0.5, 0.5)]),
+	];
+
+	assert_eq!(session(&steps).1, session(&steps).1);
+}
+
+#[test]
+fn commands_stroke_like_the_mouse() {
+	let (_, typed) = session(&[Step::Command("add 0.5 0.5"), Step::Command("remove 0.45 0.5")]);
+	let (_, drawn) = session(&[Step::Add(&[(0.5, 0.5)]), Step::Remove(&[(0.45, 0.5)])]);
+
+	assert_eq!(typed, drawn);
+}
+
+#[test]
+fn dragging_leaves_no_gaps_between_samples() {
+	let (editor, _) = session(&[Step::Command("brush size 0.05"), Step::Add(&[(0.3, 0.5), (0.7, 0.5)])]);
+	let between = editor.get_stamp(0.5, 0.5).center;
+	assert!(editor.get_document().is_solid(between));
+
+	// samples typed as separate commands aren't a drag, so nothing is filled in between them
+	let (editor, _) = session(&[Step::Command("brush size 0.05"), Step::Command("add 0.3 0.5"), Step::Command("add 0.7 0.5")]);
+	assert!(!editor.get_document().is_solid(between));
+}
+
+#[test]
+fn brush_settings_change_the_result() {
+	let (_, small) = session(&[Step::Command("brush size 0.05"), Step::Add(&[(0.5, 0.5)])]);
+	let (_, large) = session(&[Step::Command("brush size 0.3"), Step::Add(&[(0.5, 0.5)])]);
+	let (_, square) = session(&[Step::Command("brush size 0.3"), Step::Command("brush square"), Step::Add(&[(0.5, 0.5)])]);
+
+	assert_ne!(small, large);
+	assert_ne!(large, square);
+	assert!(stats(&large).chunks >= stats(&small).chunks);
+}
+
+#[test]
+fn sessions_sculpt_the_same_on_every_platform() {
+	// the front view maps the screen straight onto the sculpt, so no trigonometry
+	// from the math library of the platform is involved in placing the strokes
+	let steps = [
+		Step::Command("brush size 0.15"),
+		Step::Add(ACROSS),
+		Step::Command("brush spray"),
+		Step::Add(&[(0.3, 0.3), (0.35, 0.35), (0.4, 0.4)]),
+		Step::Command("brush square"),
+		Step::Command("brush hardness 0.5"),
+		Step::Remove(&[(0.5, 0.55)]),
+		Step::Command("brush round"),
+		Step::Command("brush through on"),
+		Step::Remove(&[(0.6, 0.5)]),
+		Step::Command("quantize 5"),
+	];
+	let (editor, buffers) = session(&steps);
+	assert_eq!(editor.validate(), 0);
+
+	// a recorded fingerprint, which changes only when sculpting itself is changed on purpose
+	assert_eq!(fingerprint(&buffers), 0xd620_e170_9dbd_536a);
+}
+
+#[test]
+fn failures_are_errors_to_handle() {
+	let mut editor = Editor::default();
+
+	// the new object wasn't opened from a file, and the other never existed
+	assert!(matches!(editor.reload_document(), Err(Error::NoFile)));
+	let missing = std::env::temp_dir().join("swirlix-missing.swirlix");
+	match editor.open_document(&missing) {
+		Err(Error::Io(error)) => assert_eq!(error.kind(), std::io::ErrorKind::NotFound),
+		other => panic!("expected a missing file, got {other:?}"),
+	}
+	assert_eq!(editor.get_scene().get_objects().len(), 1);
+	assert!(editor.run_command("brush material 9").is_err());
+}
+
+#[test]
+fn undo_steps_back_through_strokes_and_commands() {
+	let (_, before) = session(&[Step::Add(&[(0.5, 0.5)])]);
+	let (mut editor, after) = session(&[
+		Step::Add(&[(0.5, 0.5)]),
+		Step::Add(&[(0.3, 0.3), (0.35, 0.35), (0.4, 0.4)]),
+		Step::Command("quantize 5"),
+	]);
+	let active = editor.get_scene().get_active_index();
+
+	play(&mut editor, &[Step::Command("undo"), Step::Command("undo")]);
+	assert_eq!(editor.get_voxel_buffers(active), before);
+
+	play(&mut editor, &[Step::Command("redo"), Step::Command("redo")]);
+	assert_eq!(editor.get_voxel_buffers(active), after);
+	assert!(editor.run_command("redo").is_err());
+}
+
+#[test]
+fn strokes_land_level_with_the_picked_surface() {
+	let mut editor = Editor::default();
+	let middle = editor.get_stamp(0.3, 0.6).center;
+
+	editor.set_picked_surface(Some(vec3(0.5, 0.5, 0.1)));
+	let picked = editor.get_stamp(0.3, 0.6).center;
+	assert!(picked.abs_diff_eq(vec3(middle.x, middle.y, 0.1), 1e-5));
+
+	// a stroke there builds on the picked surface, not in the middle of the sculpt
+	play(&mut editor, &[Step::Add(&[(0.3, 0.6)])]);
+	assert!(editor.get_document().is_solid(picked) && !editor.get_document().is_solid(middle));
+
+	editor.set_picked_surface(None);
+	assert_eq!(editor.get_stamp(0.3, 0.6).center, middle);
+}